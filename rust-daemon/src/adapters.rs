@@ -2,20 +2,23 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
-use notify::{Config as NotifyConfig, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use chrono::{DateTime, Utc};
+use notify::{Config as NotifyConfig, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use sysinfo::System;
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::{interval, Duration};
 use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
-use crate::events::EventBus;
-use crate::models::{AgentType, EventType, Session, SessionEvent, SessionStatus};
+use crate::events::{EventBus, EventFilter};
+use crate::models::{describe_compaction, AgentType, EventType, Session, SessionEvent, SessionSource, SessionStatus};
+use crate::procwatch::{ProcessEvent, ProcessMatcher, ProcessWatcher};
 use crate::storage::Storage;
+use crate::watchmode::{WatchConfig, WatchMethod};
 
 /// Trait for agent adapters.
 #[async_trait]
@@ -37,6 +40,603 @@ pub trait Adapter: Send + Sync {
 
     /// Get adapter capabilities.
     fn capabilities(&self) -> HashMap<String, bool>;
+
+    /// Get a snapshot of the adapter's health.
+    fn health(&self) -> AdapterHealth;
+}
+
+// ============================================================================
+// Adapter Health
+// ============================================================================
+
+/// Status of a path an adapter watches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedPath {
+    pub path: String,
+    pub exists: bool,
+    pub watching: bool,
+    pub error: Option<String>,
+    /// Failed attempts in a row at watching the path
+    #[serde(default)]
+    pub failures: u32,
+    /// When watching it is tried again
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Why it is polled rather than given native events, if it is
+    #[serde(default)]
+    pub polled: Option<String>,
+}
+
+/// Point-in-time health of an adapter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdapterHealth {
+    pub name: String,
+    /// One of: ok, degraded, stale, stopped
+    pub state: String,
+    pub running: bool,
+    pub watched_paths: Vec<WatchedPath>,
+    pub last_scan_at: Option<DateTime<Utc>>,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Seconds since the last successful scan
+    pub lag_seconds: Option<i64>,
+    pub scan_interval_secs: u64,
+    pub events_processed: u64,
+    pub error_count: u64,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Sessions the adapter holds in memory
+    #[serde(default)]
+    pub sessions_in_memory: usize,
+    /// Ended sessions dropped from memory since the adapter was created
+    #[serde(default)]
+    pub sessions_evicted: u64,
+    /// Directories no longer watched for being the least recently active
+    #[serde(default)]
+    pub watches_evicted: u64,
+}
+
+/// Health recorder shared between an adapter and its background tasks.
+#[derive(Clone)]
+pub struct HealthTracker {
+    inner: Arc<std::sync::RwLock<AdapterHealth>>,
+}
+
+impl HealthTracker {
+    /// Create a tracker for an adapter that scans every `scan_interval_secs`.
+    pub fn new(name: &str, scan_interval_secs: u64) -> Self {
+        Self {
+            inner: Arc::new(std::sync::RwLock::new(AdapterHealth {
+                name: name.to_string(),
+                scan_interval_secs,
+                ..AdapterHealth::default()
+            })),
+        }
+    }
+
+    pub fn set_running(&self, running: bool) {
+        self.inner.write().unwrap().running = running;
+    }
+
+    /// Record a completed discovery or process scan.
+    pub fn record_scan(&self) {
+        self.inner.write().unwrap().last_scan_at = Some(Utc::now());
+    }
+
+    /// Record a successfully parsed file change.
+    pub fn record_event(&self) {
+        let mut health = self.inner.write().unwrap();
+        health.last_event_at = Some(Utc::now());
+        health.events_processed += 1;
+    }
+
+    pub fn record_error(&self, error: impl std::fmt::Display) {
+        let mut health = self.inner.write().unwrap();
+        health.error_count += 1;
+        health.last_error = Some(error.to_string());
+        health.last_error_at = Some(Utc::now());
+    }
+
+    /// Record whether watching `path` succeeded.
+    pub fn set_watch(&self, path: &Path, result: std::result::Result<(), String>) {
+        if let Err(ref e) = result {
+            self.record_error(format!("watch {}: {}", path.display(), e));
+        }
+
+        self.put_watch(WatchedPath {
+            path: path.to_string_lossy().to_string(),
+            exists: path.exists(),
+            watching: result.is_ok(),
+            error: result.err(),
+            failures: 0,
+            retry_at: None,
+            polled: None,
+        });
+    }
+
+    /// Record that `path` is polled, and why.
+    pub fn set_watch_polled(&self, path: &Path, reason: &str) {
+        let mut health = self.inner.write().unwrap();
+        let path = path.to_string_lossy();
+        if let Some(watched) = health.watched_paths.iter_mut().find(|w| w.path == path) {
+            watched.polled = Some(reason.to_string());
+        }
+    }
+
+    /// Record that watching `path` failed `failures` times in a row and is
+    /// tried again at `retry_at`. A path that doesn't exist yet is waited
+    /// for rather than counted as an error.
+    pub fn set_watch_retry(&self, path: &Path, error: String, failures: u32, retry_at: DateTime<Utc>) {
+        let exists = path.exists();
+        if exists {
+            self.record_error(format!("watch {}: {}", path.display(), error));
+        }
+        self.put_watch(WatchedPath {
+            path: path.to_string_lossy().to_string(),
+            exists,
+            watching: false,
+            error: Some(error),
+            failures,
+            retry_at: Some(retry_at),
+            polled: None,
+        });
+    }
+
+    /// Forget `path`, no longer watched on purpose.
+    pub fn remove_watch(&self, path: &Path) {
+        let path = path.to_string_lossy();
+        self.inner.write().unwrap().watched_paths.retain(|w| w.path != path);
+    }
+
+    pub fn record_watch_eviction(&self) {
+        self.inner.write().unwrap().watches_evicted += 1;
+    }
+
+    fn put_watch(&self, watched: WatchedPath) {
+        let mut health = self.inner.write().unwrap();
+        health.watched_paths.retain(|w| w.path != watched.path);
+        health.watched_paths.push(watched);
+    }
+
+    /// Record a sweep of the in-memory sessions that dropped `evicted`
+    /// and left `held`.
+    pub fn record_sweep(&self, held: usize, evicted: usize) {
+        let mut health = self.inner.write().unwrap();
+        health.sessions_in_memory = held;
+        health.sessions_evicted += evicted as u64;
+    }
+
+    /// Current health, with the number of `sessions` held right now when
+    /// they aren't being written to.
+    pub fn snapshot_holding(&self, sessions: &RwLock<HashMap<String, Session>>) -> AdapterHealth {
+        let mut health = self.snapshot();
+        if let Ok(sessions) = sessions.try_read() {
+            health.sessions_in_memory = sessions.len();
+        }
+        health
+    }
+
+    /// Current health with lag and overall state filled in.
+    pub fn snapshot(&self) -> AdapterHealth {
+        let mut health = self.inner.read().unwrap().clone();
+        let now = Utc::now();
+        let interval = health.scan_interval_secs.max(1) as i64;
+
+        health.lag_seconds = health.last_scan_at.map(|t| (now - t).num_seconds());
+        let recent_error = health
+            .last_error_at
+            .map(|t| (now - t).num_seconds() < interval)
+            .unwrap_or(false);
+        let lost_watch = health.watched_paths.iter().any(|w| w.exists && !w.watching);
+
+        health.state = if !health.running {
+            "stopped"
+        } else if lost_watch || recent_error {
+            "degraded"
+        } else if health.lag_seconds.map(|lag| lag > interval * 3).unwrap_or(true) {
+            "stale"
+        } else {
+            "ok"
+        }
+        .to_string();
+
+        health
+    }
+}
+
+// ============================================================================
+// Watch Registration
+// ============================================================================
+
+/// Seconds before a failed watch is first tried again; the wait doubles with
+/// each failure up to `WATCH_RETRY_MAX_SECS`.
+const WATCH_RETRY_MIN_SECS: f64 = 1.0;
+
+/// Longest wait between attempts at a failed watch.
+const WATCH_RETRY_MAX_SECS: f64 = 300.0;
+
+/// Wait after `failures` failed attempts at a watch. `jitter` (0 to 1)
+/// stretches it by up to a fifth, so watches that failed together don't
+/// retry together.
+fn watch_retry_delay(failures: u32, jitter: f64) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16) as i32;
+    let secs = (WATCH_RETRY_MIN_SECS * 2f64.powi(doublings)).min(WATCH_RETRY_MAX_SECS);
+    Duration::from_secs_f64(secs * (1.0 + 0.2 * jitter.clamp(0.0, 1.0)))
+}
+
+/// A number from 0 to 1 that differs between calls.
+fn jitter() -> f64 {
+    use std::hash::BuildHasher;
+    let random = std::collections::hash_map::RandomState::new().hash_one(Utc::now().timestamp_nanos_opt());
+    (random % 1_000) as f64 / 1_000.0
+}
+
+/// A directory a file watcher follows.
+struct WatchTarget {
+    path: PathBuf,
+    /// Whether what's below it is watched too
+    mode: RecursiveMode,
+    /// For a directory watched while in use, when it last was, in uses of
+    /// the set; None for one always watched
+    used: Option<u64>,
+    /// How it is watched, once it is
+    watched: Option<WatchMethod>,
+    /// Failed attempts in a row
+    failures: u32,
+    /// When to try again after a failure
+    retry_at: Option<tokio::time::Instant>,
+}
+
+impl WatchTarget {
+    fn watching(&self) -> bool {
+        self.watched.is_some()
+    }
+}
+
+/// Changes and errors of the watchers a `WatchSet` owns.
+type WatchEvents = mpsc::Sender<Result<Event, notify::Error>>;
+
+/// The directories a file watcher follows, each with native events or
+/// polled as `WatchConfig` decides. Some are always watched: one that can't
+/// be (most often because it doesn't exist yet, as on a machine where the
+/// agent never ran) is tried again with backoff, and right away once it
+/// appears, its nearest existing parent being watched meanwhile; one that
+/// is removed is waited for the same way. Others are watched while in use,
+/// at most `max_project_dirs` of them, dropping the least recently used.
+struct WatchSet {
+    targets: Vec<WatchTarget>,
+    /// Parents watched for missing targets to appear, and how
+    parents: HashMap<PathBuf, WatchMethod>,
+    /// Uses of in-use directories so far
+    uses: u64,
+    health: HealthTracker,
+    config: WatchConfig,
+    native: RecommendedWatcher,
+    /// Polling watchers by interval in milliseconds, made when first needed
+    polling: HashMap<u64, PollWatcher>,
+    events: WatchEvents,
+}
+
+impl WatchSet {
+    fn new(
+        paths: impl IntoIterator<Item = (PathBuf, RecursiveMode)>,
+        config: WatchConfig,
+        health: HealthTracker,
+        events: WatchEvents,
+    ) -> notify::Result<Self> {
+        let sender = events.clone();
+        let native = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                let _ = sender.blocking_send(res);
+            },
+            NotifyConfig::default(),
+        )?;
+        let targets = paths
+            .into_iter()
+            .map(|(path, mode)| WatchTarget { path, mode, used: None, watched: None, failures: 0, retry_at: None })
+            .collect();
+        Ok(Self {
+            targets,
+            parents: HashMap::new(),
+            uses: 0,
+            health,
+            config,
+            native,
+            polling: HashMap::new(),
+            events,
+        })
+    }
+
+    /// The watcher for `method`.
+    fn watcher(&mut self, method: &WatchMethod) -> notify::Result<&mut dyn Watcher> {
+        let secs = match method {
+            WatchMethod::Native => return Ok(&mut self.native),
+            WatchMethod::Poll { secs, .. } => *secs,
+        };
+        let key = (secs * 1000.0) as u64;
+        if !self.polling.contains_key(&key) {
+            let sender = self.events.clone();
+            let watcher = PollWatcher::new(
+                move |res: Result<Event, notify::Error>| {
+                    let _ = sender.blocking_send(res);
+                },
+                NotifyConfig::default().with_poll_interval(Duration::from_secs_f64(secs)),
+            )?;
+            self.polling.insert(key, watcher);
+        }
+        Ok(self.polling.get_mut(&key).expect("inserted above"))
+    }
+
+    /// Try to watch each target that isn't watched and is due, or all of
+    /// them with `now`.
+    fn register(&mut self, now: bool) {
+        let at = tokio::time::Instant::now();
+        for i in 0..self.targets.len() {
+            let target = &self.targets[i];
+            if target.watching() || (!now && target.retry_at.is_some_and(|retry_at| retry_at > at)) {
+                continue;
+            }
+            self.watch_target(i, at);
+        }
+        self.watch_parents();
+    }
+
+    fn watch_target(&mut self, i: usize, at: tokio::time::Instant) {
+        let (path, mode) = (self.targets[i].path.clone(), self.targets[i].mode);
+        let method = self.config.method(&path);
+        let result = self.watcher(&method).and_then(|w| w.watch(&path, mode));
+        let target = &mut self.targets[i];
+        match result {
+            Ok(()) => {
+                self.health.set_watch(&path, Ok(()));
+                match &method {
+                    WatchMethod::Native => info!("📁 Watching: {:?}", path),
+                    WatchMethod::Poll { secs, reason } => {
+                        info!("📁 Polling {:?} every {}s ({})", path, secs, reason);
+                        self.health.set_watch_polled(&path, reason);
+                    }
+                }
+                target.watched = Some(method);
+                target.failures = 0;
+                target.retry_at = None;
+            }
+            Err(e) => {
+                target.failures += 1;
+                let delay = watch_retry_delay(target.failures, jitter());
+                target.retry_at = Some(at + delay);
+                if path.exists() {
+                    warn!("Failed to watch {:?} (attempt {}): {}", path, target.failures, e);
+                } else {
+                    debug!("Waiting for {:?} to appear", path);
+                }
+                let retry_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+                self.health.set_watch_retry(&path, e.to_string(), target.failures, retry_at);
+            }
+        }
+    }
+
+    /// Watch `dir` and what's below it while it is in use. True when it
+    /// wasn't watched already, so what was written in it meanwhile can be
+    /// caught up on. Only with `selective`: otherwise everything is.
+    fn activate(&mut self, dir: &Path) -> bool {
+        if !self.config.selective || !dir.is_dir() {
+            return false;
+        }
+        self.uses += 1;
+        if let Some(target) = self.targets.iter_mut().find(|t| t.path == dir) {
+            if target.used.is_some() {
+                target.used = Some(self.uses);
+            }
+            return false;
+        }
+        self.targets.push(WatchTarget {
+            path: dir.to_path_buf(),
+            mode: RecursiveMode::Recursive,
+            used: Some(self.uses),
+            watched: None,
+            failures: 0,
+            retry_at: None,
+        });
+        self.evict();
+        self.watch_target(self.targets.len() - 1, tokio::time::Instant::now());
+        true
+    }
+
+    /// Note activity at `path`, keeping the in-use directory it is in.
+    fn touch(&mut self, path: &Path) {
+        if let Some(target) = self.targets.iter_mut().find(|t| t.used.is_some() && path.starts_with(&t.path)) {
+            self.uses += 1;
+            target.used = Some(self.uses);
+        }
+    }
+
+    /// Stop watching the least recently used in-use directories beyond
+    /// `max_project_dirs`.
+    fn evict(&mut self) {
+        let limit = self.config.max_project_dirs.max(1);
+        while self.targets.iter().filter(|t| t.used.is_some()).count() > limit {
+            let Some((_, i)) = self.targets.iter().enumerate().filter_map(|(i, t)| Some((t.used?, i))).min() else {
+                break;
+            };
+            let target = self.targets.remove(i);
+            debug!("Stopped watching {:?}, the least recently active", target.path);
+            self.forget(target);
+            self.health.record_watch_eviction();
+        }
+    }
+
+    /// Stop watching `target` for good.
+    fn forget(&mut self, target: WatchTarget) {
+        if let Some(ref method) = target.watched {
+            if let Ok(watcher) = self.watcher(method) {
+                let _ = watcher.unwatch(&target.path);
+            }
+        }
+        self.health.remove_watch(&target.path);
+    }
+
+    /// Watch the nearest existing parent of each target that isn't watched,
+    /// unless a watched target covers it, and stop watching the parents no
+    /// longer needed.
+    fn watch_parents(&mut self) {
+        let covering: Vec<(&Path, RecursiveMode)> =
+            self.targets.iter().filter(|t| t.watching()).map(|t| (t.path.as_path(), t.mode)).collect();
+        let needed: std::collections::HashSet<PathBuf> = self
+            .targets
+            .iter()
+            .filter(|t| !t.watching())
+            .filter_map(|t| t.path.ancestors().skip(1).find(|a| a.is_dir()))
+            .filter(|parent| {
+                !covering
+                    .iter()
+                    .any(|(w, mode)| parent == w || (*mode == RecursiveMode::Recursive && parent.starts_with(w)))
+            })
+            .map(Path::to_path_buf)
+            .collect();
+        let gone: Vec<(PathBuf, WatchMethod)> =
+            self.parents.iter().filter(|(p, _)| !needed.contains(*p)).map(|(p, m)| (p.clone(), m.clone())).collect();
+        for (parent, method) in gone {
+            if let Ok(watcher) = self.watcher(&method) {
+                let _ = watcher.unwatch(&parent);
+            }
+            self.parents.remove(&parent);
+        }
+        for parent in needed {
+            if self.parents.contains_key(&parent) {
+                continue;
+            }
+            let method = self.config.method(&parent);
+            if let Err(e) = self.watcher(&method).and_then(|w| w.watch(&parent, RecursiveMode::NonRecursive)) {
+                debug!("Failed to watch {:?} for missing directories: {}", parent, e);
+            }
+            self.parents.insert(parent, method);
+        }
+    }
+
+    /// When the next target is due to be tried again.
+    fn next_retry(&self) -> Option<tokio::time::Instant> {
+        self.targets.iter().filter(|t| !t.watching()).filter_map(|t| t.retry_at).min()
+    }
+
+    /// Whether `event` means a target can be tried right away: something
+    /// appeared at or above one that isn't watched, or a watched one was
+    /// removed (and is then waited for).
+    fn changed_by(&mut self, event: &Event) -> bool {
+        use notify::EventKind;
+
+        match event.kind {
+            EventKind::Create(_) => self
+                .targets
+                .iter()
+                .any(|t| !t.watching() && event.paths.iter().any(|p| t.path.starts_with(p))),
+            EventKind::Remove(_) => self.lost(&event.paths),
+            _ => false,
+        }
+    }
+
+    /// Mark the watched targets at or below `paths` as no longer watched.
+    /// In-use directories are dropped instead.
+    fn lost(&mut self, paths: &[PathBuf]) -> bool {
+        let (lost, kept): (Vec<WatchTarget>, Vec<WatchTarget>) = std::mem::take(&mut self.targets)
+            .into_iter()
+            .partition(|t| t.watching() && paths.iter().any(|p| t.path.starts_with(p)));
+        self.targets = kept;
+        let any = !lost.is_empty();
+        for mut target in lost {
+            warn!("Lost watch on {:?}", target.path);
+            if target.used.is_some() {
+                self.forget(target);
+                continue;
+            }
+            // A polling watcher keeps polling a removed path until told
+            if let Some(method @ WatchMethod::Poll { .. }) = target.watched.take() {
+                if let Ok(watcher) = self.watcher(&method) {
+                    let _ = watcher.unwatch(&target.path);
+                }
+            }
+            target.retry_at = None;
+            self.targets.push(target);
+        }
+        any
+    }
+}
+
+// ============================================================================
+// In-memory Session Cleanup
+// ============================================================================
+
+/// Time between sweeps of an adapter's in-memory sessions.
+const SESSION_SWEEP_SECS: u64 = 300;
+
+/// Hours without activity after which a session is dropped from memory even
+/// while storage still has it open.
+const SESSION_IDLE_HOURS: i64 = 24;
+
+/// The keys of the in-memory `sessions` that have ended: closed in storage
+/// (the exit monitor closes them when their process goes), no longer in it,
+/// or idle for `SESSION_IDLE_HOURS`.
+async fn ended_sessions(
+    sessions: &RwLock<HashMap<String, Session>>,
+    storage: &Storage,
+    now: DateTime<Utc>,
+) -> Result<Vec<(String, String)>> {
+    let held: Vec<(String, String, DateTime<Utc>)> = sessions
+        .read()
+        .await
+        .iter()
+        .map(|(key, s)| (key.clone(), s.id.clone(), s.last_activity_at))
+        .collect();
+    let mut ended = Vec::new();
+    for (key, id, last_activity) in held {
+        let idle_since = match storage.get_session(&id).await? {
+            Some(stored) if !matches!(stored.status, SessionStatus::Completed | SessionStatus::Crashed) => {
+                stored.last_activity_at.max(last_activity)
+            }
+            _ => {
+                ended.push((key, id));
+                continue;
+            }
+        };
+        if now - idle_since > chrono::Duration::hours(SESSION_IDLE_HOURS) {
+            ended.push((key, id));
+        }
+    }
+    Ok(ended)
+}
+
+/// Drop the ended sessions (see `ended_sessions`) an adapter holds in
+/// memory, so its map follows the sessions' status in storage instead of
+/// growing with every project ever seen.
+async fn sweep_sessions(sessions: &RwLock<HashMap<String, Session>>, storage: &Storage, health: &HealthTracker) {
+    let ended = match ended_sessions(sessions, storage, Utc::now()).await {
+        Ok(ended) => ended,
+        Err(e) => {
+            health.record_error(format!("session sweep: {}", e));
+            return;
+        }
+    };
+    let mut sessions = sessions.write().await;
+    let before = sessions.len();
+    for (key, id) in ended {
+        // A new session may have taken the key since
+        if sessions.get(&key).is_some_and(|s| s.id == id) {
+            sessions.remove(&key);
+        }
+    }
+    let evicted = before - sessions.len();
+    if evicted > 0 {
+        debug!("Dropped {} ended sessions from memory, {} left", evicted, sessions.len());
+    }
+    health.record_sweep(sessions.len(), evicted);
+}
+
+/// Processes of an agent type, for the agents that run as local processes.
+pub fn process_matcher(agent_type: AgentType) -> Option<ProcessMatcher> {
+    match agent_type {
+        AgentType::ClaudeCode => Some(ClaudeCodeAdapter::process_matcher()),
+        AgentType::Cursor => Some(CursorAdapter::process_matcher()),
+        AgentType::Aider => Some(AiderAdapter::process_matcher()),
+        _ => None,
+    }
 }
 
 /// Registry of all adapters.
@@ -45,16 +645,18 @@ pub struct AdapterRegistry {
     config: Config,
     event_bus: EventBus,
     storage: Storage,
+    processes: ProcessWatcher,
 }
 
 impl AdapterRegistry {
     /// Create a new adapter registry.
-    pub fn new(config: &Config, event_bus: EventBus, storage: Storage) -> Self {
+    pub fn new(config: &Config, event_bus: EventBus, storage: Storage, processes: ProcessWatcher) -> Self {
         Self {
             adapters: Vec::new(),
             config: config.clone(),
             event_bus,
             storage,
+            processes,
         }
     }
 
@@ -64,6 +666,7 @@ impl AdapterRegistry {
             &self.config,
             self.event_bus.clone(),
             self.storage.clone(),
+            self.processes.clone(),
         );
         self.adapters.push(Box::new(adapter));
         Ok(())
@@ -75,6 +678,7 @@ impl AdapterRegistry {
             &self.config,
             self.event_bus.clone(),
             self.storage.clone(),
+            self.processes.clone(),
         );
         self.adapters.push(Box::new(adapter));
         Ok(())
@@ -86,6 +690,7 @@ impl AdapterRegistry {
             &self.config,
             self.event_bus.clone(),
             self.storage.clone(),
+            self.processes.clone(),
         );
         self.adapters.push(Box::new(adapter));
         Ok(())
@@ -118,8 +723,67 @@ impl AdapterRegistry {
         }
         Ok(())
     }
+
+    /// Health of every registered adapter.
+    pub fn health(&self) -> Vec<AdapterHealth> {
+        self.adapters.iter().map(|a| a.health()).collect()
+    }
+
+    /// Start a single adapter, registering it first if it is known but not
+    /// enabled (e.g. the Cursor adapter).
+    pub async fn start_adapter(&mut self, name: &str) -> Result<()> {
+        if self.find(name).is_none() {
+            match name {
+                "claude_code" => self.register_claude_code().await?,
+                "cursor" => self.register_cursor().await?,
+                "aider" => self.register_aider().await?,
+                _ => anyhow::bail!("Unknown adapter: {}", name),
+            }
+        }
+
+        let adapter = self.find(name).expect("adapter registered above");
+        if adapter.health().running {
+            anyhow::bail!("Adapter {} is already running", name);
+        }
+        info!("Starting adapter: {}", name);
+        adapter.start().await
+    }
+
+    /// Stop a single adapter; it stays registered and can be started again.
+    pub async fn stop_adapter(&mut self, name: &str) -> Result<()> {
+        let adapter = self
+            .find(name)
+            .ok_or_else(|| anyhow::anyhow!("Adapter {} is not registered", name))?;
+        info!("Stopping adapter: {}", name);
+        adapter.stop().await
+    }
+
+    /// Stop (if running) and start a single adapter.
+    pub async fn restart_adapter(&mut self, name: &str) -> Result<()> {
+        if let Some(adapter) = self.find(name) {
+            if adapter.health().running {
+                info!("Stopping adapter: {}", name);
+                adapter.stop().await?;
+            }
+        }
+        self.start_adapter(name).await
+    }
+
+    fn find(&mut self, name: &str) -> Option<&mut Box<dyn Adapter>> {
+        self.adapters.iter_mut().find(|a| a.name() == name)
+    }
 }
 
+/// Most active sessions reconciled after a restart.
+const MAX_RECOVERED: usize = 500;
+
+/// Recent events read to judge how a session ended while the daemon was down.
+const RECOVERY_EVENTS: usize = 20;
+
+/// Seconds back new transcripts are read from when their project directory
+/// starts being watched.
+const CATCH_UP_SECS: u64 = 600;
+
 /// Claude Code adapter with file watching and process detection.
 pub struct ClaudeCodeAdapter {
     claude_home: PathBuf,
@@ -129,15 +793,35 @@ pub struct ClaudeCodeAdapter {
     storage: Storage,
     sessions: Arc<RwLock<HashMap<String, Session>>>,
     running: Arc<RwLock<bool>>,
-    /// Track the last read position in history file
-    last_history_pos: Arc<RwLock<u64>>,
+    /// How far each watched file has been read
+    read_positions: Arc<RwLock<HashMap<PathBuf, u64>>>,
     /// Sender to stop file watcher
     watcher_stop_tx: Option<mpsc::Sender<()>>,
+    /// Reacts to agent processes starting, aborted on stop
+    scanner_task: Option<tokio::task::JoinHandle<()>>,
+    processes: ProcessWatcher,
+    health: HealthTracker,
+    /// Native events or polling, per directory
+    watch: WatchConfig,
+}
+
+/// What the Claude Code file watcher task shares with its adapter.
+struct FileWatcher {
+    claude_home: PathBuf,
+    history_file: PathBuf,
+    projects_dir: PathBuf,
+    storage: Storage,
+    event_bus: EventBus,
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    read_positions: Arc<RwLock<HashMap<PathBuf, u64>>>,
+    processes: ProcessWatcher,
+    health: HealthTracker,
+    watch: WatchConfig,
 }
 
 impl ClaudeCodeAdapter {
     /// Create a new Claude Code adapter.
-    pub fn new(config: &Config, event_bus: EventBus, storage: Storage) -> Self {
+    pub fn new(config: &Config, event_bus: EventBus, storage: Storage, processes: ProcessWatcher) -> Self {
         Self {
             claude_home: config.claude_home.clone(),
             history_file: config.claude_home.join("history.jsonl"),
@@ -146,70 +830,76 @@ impl ClaudeCodeAdapter {
             storage,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             running: Arc::new(RwLock::new(false)),
-            last_history_pos: Arc::new(RwLock::new(0)),
+            read_positions: Arc::new(RwLock::new(HashMap::new())),
             watcher_stop_tx: None,
+            scanner_task: None,
+            processes,
+            health: HealthTracker::new("claude_code", 60),
+            watch: config.watch.clone(),
         }
     }
 
     /// Start the real-time file watcher for Claude Code directories.
-    fn start_file_watcher(
-        claude_home: PathBuf,
-        history_file: PathBuf,
-        projects_dir: PathBuf,
-        storage: Storage,
-        event_bus: EventBus,
-        sessions: Arc<RwLock<HashMap<String, Session>>>,
-        last_history_pos: Arc<RwLock<u64>>,
-        mut stop_rx: mpsc::Receiver<()>,
-    ) {
+    fn start_file_watcher(watcher: FileWatcher, mut stop_rx: mpsc::Receiver<()>) {
+        let FileWatcher {
+            claude_home,
+            history_file,
+            projects_dir,
+            storage,
+            event_bus,
+            sessions,
+            read_positions,
+            processes,
+            health,
+            watch,
+        } = watcher;
         tokio::spawn(async move {
-            // Channel for file events
-            let (tx, mut rx) = mpsc::channel::<Event>(100);
-
-            // Create the watcher
-            let watcher_result: Result<RecommendedWatcher, notify::Error> = {
-                let tx = tx.clone();
-                Watcher::new(
-                    move |res: Result<Event, notify::Error>| {
-                        if let Ok(event) = res {
-                            let _ = tx.blocking_send(event);
-                        }
-                    },
-                    NotifyConfig::default(),
-                )
-            };
-
-            let mut watcher = match watcher_result {
-                Ok(w) => w,
+            // Channel for file events and watcher errors
+            let (tx, mut rx) = mpsc::channel::<Result<Event, notify::Error>>(100);
+
+            // Watch the Claude home and projects directories, or wait for
+            // them; with `selective`, the project directories in use on
+            // their own
+            let mode = if watch.selective { RecursiveMode::NonRecursive } else { RecursiveMode::Recursive };
+            let active_days = watch.active_days;
+            let targets = [(claude_home.clone(), mode), (projects_dir.clone(), mode)];
+            let mut watches = match WatchSet::new(targets, watch, health.clone(), tx) {
+                Ok(watches) => watches,
                 Err(e) => {
                     error!("Failed to create file watcher: {}", e);
+                    health.set_watch(&claude_home, Err(e.to_string()));
                     return;
                 }
             };
-
-            // Watch the Claude home directory
-            if let Err(e) = watcher.watch(&claude_home, RecursiveMode::Recursive) {
-                warn!("Failed to watch Claude home directory: {}", e);
-            } else {
-                info!("📁 Watching: {:?}", claude_home);
-            }
-
-            // Also watch projects directory if it exists
-            if projects_dir.exists() {
-                if let Err(e) = watcher.watch(&projects_dir, RecursiveMode::Recursive) {
-                    warn!("Failed to watch projects directory: {}", e);
-                } else {
-                    info!("📁 Watching: {:?}", projects_dir);
+            watches.register(true);
+            match Self::active_project_dirs(&storage, &processes, &projects_dir, active_days).await {
+                Ok(dirs) => {
+                    for dir in dirs {
+                        watches.activate(&dir);
+                    }
                 }
+                Err(e) => warn!("Failed to find the projects in use: {}", e),
             }
+            let mut started = processes.subscribe(Self::process_matcher());
+            let mut prompts = event_bus.subscribe_filtered(
+                "Claude Code file watcher",
+                EventFilter::all()
+                    .agent(AgentType::ClaudeCode)
+                    .event_types([EventType::SessionStart, EventType::PromptReceived]),
+            );
 
             // Initialize history position to end of file
             if history_file.exists() {
                 if let Ok(metadata) = std::fs::metadata(&history_file) {
-                    *last_history_pos.write().await = metadata.len();
+                    read_positions.write().await.insert(history_file.clone(), metadata.len());
                 }
             }
 
+            // Catch up on transcripts written while the daemon was down
+            if let Err(e) = Self::recover(&storage, &event_bus, &sessions, &read_positions, &processes).await {
+                warn!("Crash recovery failed: {}", e);
+            }
+
             info!("✦ File watcher started");
 
             loop {
@@ -219,15 +909,70 @@ impl ClaudeCodeAdapter {
                         info!("File watcher stopping...");
                         break;
                     }
+                    // Try failed watches again when due
+                    _ = tokio::time::sleep_until(watches.next_retry().unwrap_or_else(tokio::time::Instant::now)),
+                        if watches.next_retry().is_some() =>
+                    {
+                        watches.register(false);
+                    }
+                    // Watch the project a prompt arrives or Claude Code starts in
+                    Some(event) = prompts.recv() => {
+                        let Some(cwd) = event.working_directory else {
+                            continue;
+                        };
+                        let dir = projects_dir.join(crate::transcripts::project_dir_name(&cwd));
+                        if watches.activate(&dir) {
+                            Self::catch_up(&dir, &storage, &event_bus, &sessions, &read_positions, &health).await;
+                        }
+                    }
+                    Some(event) = started.recv() => {
+                        let ProcessEvent::Started(process) = event else {
+                            continue;
+                        };
+                        let Some(cwd) = process.cwd else {
+                            continue;
+                        };
+                        let dir = projects_dir.join(crate::transcripts::project_dir_name(&cwd));
+                        if watches.activate(&dir) {
+                            Self::catch_up(&dir, &storage, &event_bus, &sessions, &read_positions, &health).await;
+                        }
+                    }
                     // Handle file events
-                    Some(event) = rx.recv() => {
+                    Some(received) = rx.recv() => {
+                        let event = match received {
+                            Ok(event) => event,
+                            Err(e) => {
+                                warn!("File watcher error: {}", e);
+                                health.record_error(format!("watcher: {}", e));
+                                if watches.lost(&e.paths) {
+                                    watches.register(true);
+                                }
+                                continue;
+                            }
+                        };
+                        if watches.changed_by(&event) {
+                            watches.register(true);
+                        }
+                        // A new project, whose first lines may have been
+                        // written before it was watched
+                        if matches!(event.kind, notify::EventKind::Create(_)) {
+                            for dir in event.paths.iter().filter(|p| p.parent() == Some(projects_dir.as_path())) {
+                                if watches.activate(dir) {
+                                    Self::catch_up(dir, &storage, &event_bus, &sessions, &read_positions, &health).await;
+                                }
+                            }
+                        }
+                        for path in &event.paths {
+                            watches.touch(path);
+                        }
                         Self::handle_file_event(
                             event,
                             &history_file,
                             &storage,
                             &event_bus,
                             &sessions,
-                            &last_history_pos,
+                            &read_positions,
+                            &health,
                         ).await;
                     }
                 }
@@ -242,7 +987,8 @@ impl ClaudeCodeAdapter {
         storage: &Storage,
         event_bus: &EventBus,
         sessions: &Arc<RwLock<HashMap<String, Session>>>,
-        last_history_pos: &Arc<RwLock<u64>>,
+        read_positions: &Arc<RwLock<HashMap<PathBuf, u64>>>,
+        health: &HealthTracker,
     ) {
         use notify::EventKind;
 
@@ -255,14 +1001,19 @@ impl ClaudeCodeAdapter {
             // Process history.jsonl
             if path == history_file {
                 debug!("History file changed, reading new entries...");
-                if let Err(e) = Self::process_file_changes(
+                match Self::process_file_changes(
                     path,
+                    false,
                     storage,
                     event_bus,
                     sessions,
-                    last_history_pos,
+                    read_positions,
                 ).await {
-                    warn!("Error processing history changes: {}", e);
+                    Ok(()) => health.record_event(),
+                    Err(e) => {
+                        warn!("Error processing history changes: {}", e);
+                        health.record_error(e);
+                    }
                 }
             }
             // Process project session JSONL files
@@ -270,61 +1021,264 @@ impl ClaudeCodeAdapter {
                 // Only process if it's in a projects directory
                 if path.to_string_lossy().contains("/projects/") {
                     debug!("Project session file changed: {:?}", path);
-                    if let Err(e) = Self::process_file_changes(
+                    match Self::process_file_changes(
                         path,
+                        true,
                         storage,
                         event_bus,
                         sessions,
-                        last_history_pos,
+                        read_positions,
                     ).await {
-                        warn!("Error processing project session: {}", e);
+                        Ok(()) => health.record_event(),
+                        Err(e) => {
+                            warn!("Error processing project session: {}", e);
+                            health.record_error(e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Project directories in use: those with transcripts read or started
+    /// in the last `days`, least recently first, then those Claude Code is
+    /// running in.
+    async fn active_project_dirs(
+        storage: &Storage,
+        processes: &ProcessWatcher,
+        projects_dir: &Path,
+        days: i64,
+    ) -> Result<Vec<PathBuf>> {
+        let since = Utc::now() - chrono::Duration::days(days);
+        let mut recent: Vec<(DateTime<Utc>, PathBuf)> = storage
+            .get_session_sources(None)
+            .await?
+            .into_iter()
+            .filter(|s| s.last_read_at >= since)
+            .filter_map(|s| {
+                let project = Path::new(&s.path).strip_prefix(projects_dir).ok()?.components().next()?;
+                Some((s.last_read_at, projects_dir.join(project)))
+            })
+            .collect();
+        // A new transcript changes its directory's modification time
+        for entry in std::fs::read_dir(projects_dir).into_iter().flatten().flatten() {
+            let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            let modified = DateTime::<Utc>::from(modified);
+            if modified >= since && entry.path().is_dir() {
+                recent.push((modified, entry.path()));
+            }
+        }
+        recent.sort();
+        let mut dirs: Vec<PathBuf> = recent.into_iter().map(|(_, dir)| dir).collect();
+        dirs.extend(
+            processes
+                .matching(&Self::process_matcher())
+                .into_iter()
+                .filter_map(|p| p.cwd)
+                .map(|cwd| projects_dir.join(crate::transcripts::project_dir_name(&cwd))),
+        );
+        Ok(dirs)
+    }
+
+    /// Read the transcripts in `dir` written while it wasn't watched: those
+    /// read before that have grown since, and new ones written in the last
+    /// `CATCH_UP_SECS`.
+    async fn catch_up(
+        dir: &Path,
+        storage: &Storage,
+        event_bus: &EventBus,
+        sessions: &Arc<RwLock<HashMap<String, Session>>>,
+        read_positions: &Arc<RwLock<HashMap<PathBuf, u64>>>,
+        health: &HealthTracker,
+    ) {
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().is_none_or(|e| e != "jsonl") {
+                    continue;
+                }
+                let due = match read_positions.read().await.get(&path) {
+                    Some(&offset) => metadata.len() != offset,
+                    None => metadata
+                        .modified()
+                        .ok()
+                        .and_then(|at| at.elapsed().ok())
+                        .is_some_and(|age| age < Duration::from_secs(CATCH_UP_SECS)),
+                };
+                if !due {
+                    continue;
+                }
+                match Self::process_file_changes(&path, true, storage, event_bus, sessions, read_positions).await {
+                    Ok(()) => health.record_event(),
+                    Err(e) => {
+                        warn!("Error catching up on {:?}: {}", path, e);
+                        health.record_error(e);
                     }
                 }
             }
         }
     }
 
-    /// Process changes from any JSONL file (history or project session).
+    /// Resume every transcript from where the last run stopped reading it.
+    /// Lines written since are read into the session they were going to,
+    /// then the sessions read from transcripts that are still marked active
+    /// are reconciled: their activity is that of their last event, and those
+    /// with no Claude Code process left in their project are closed as
+    /// completed or crashed, judged by their last events.
+    async fn recover(
+        storage: &Storage,
+        event_bus: &EventBus,
+        sessions: &Arc<RwLock<HashMap<String, Session>>>,
+        read_positions: &Arc<RwLock<HashMap<PathBuf, u64>>>,
+        processes: &ProcessWatcher,
+    ) -> Result<()> {
+        let sources = storage.get_session_sources(None).await?;
+        // A file goes on into the session that read it last
+        let mut latest: HashMap<PathBuf, &SessionSource> = HashMap::new();
+        for source in &sources {
+            let path = PathBuf::from(&source.path);
+            if latest.get(&path).is_none_or(|s| s.last_read_at < source.last_read_at) {
+                latest.insert(path, source);
+            }
+        }
+
+        let mut caught_up = 0;
+        for (path, source) in latest {
+            read_positions.write().await.insert(path.clone(), source.offset as u64);
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.len() == source.offset as u64 {
+                continue;
+            }
+            if let Some(session) = storage.get_session(&source.session_id).await? {
+                sessions.write().await.entry(session.project_path.clone()).or_insert(session);
+            }
+            Self::process_file_changes(&path, true, storage, event_bus, sessions, read_positions).await?;
+            caught_up += 1;
+        }
+        if caught_up > 0 {
+            info!("Caught up on {} transcripts written while the daemon was down", caught_up);
+        }
+
+        let running: Vec<String> = processes
+            .matching(&Self::process_matcher())
+            .into_iter()
+            .filter_map(|p| p.cwd)
+            .collect();
+        let from_transcripts: std::collections::HashSet<&str> =
+            sources.iter().map(|s| s.session_id.as_str()).collect();
+        for mut session in storage.get_active_sessions(MAX_RECOVERED).await? {
+            if session.agent_type != AgentType::ClaudeCode || !from_transcripts.contains(session.id.as_str()) {
+                continue;
+            }
+            let events = storage.get_session_events(&session.id, RECOVERY_EVENTS).await?;
+            if let Some(last) = events.first() {
+                session.last_activity_at = last.timestamp;
+                session.duration_seconds = (last.timestamp - session.started_at).num_seconds() as f64;
+            }
+            if !running.contains(&session.project_path) {
+                session.status = crate::exits::exit_status(&events).0;
+                session.ended_at = Some(session.last_activity_at);
+                info!("Session {} ended while the daemon was down: {}", session.id, session.status);
+            }
+            storage.upsert_session(&session).await?;
+            if let Some(open) = sessions.write().await.get_mut(&session.project_path) {
+                if open.id == session.id {
+                    *open = session;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process new lines of any JSONL file (history or project session).
+    /// A file seen for the first time has its last 50 lines read; after that
+    /// only the complete lines added since the last read, so the several
+    /// watcher events of one write don't count its entries more than once.
+    /// How far each `transcript` file was read is stored as a source of the
+    /// sessions its entries went to, so a restart resumes where it stopped.
     async fn process_file_changes(
         file_path: &PathBuf,
+        transcript: bool,
         storage: &Storage,
         event_bus: &EventBus,
         sessions: &Arc<RwLock<HashMap<String, Session>>>,
-        _last_history_pos: &Arc<RwLock<u64>>,
+        read_positions: &Arc<RwLock<HashMap<PathBuf, u64>>>,
     ) -> Result<()> {
-        use std::io::{BufRead, BufReader};
+        use std::io::{Read, Seek, SeekFrom};
 
         if !file_path.exists() {
             return Ok(());
         }
 
-        let file = std::fs::File::open(file_path)?;
-        let reader = BufReader::new(file);
-
-        // Read last 50 lines for incremental updates
-        let lines: Vec<String> = reader.lines().filter_map(|l| l.ok()).collect();
-        let start = lines.len().saturating_sub(50);
+        let mut file = std::fs::File::open(file_path)?;
+        let len = file.metadata()?.len();
+        let mut positions = read_positions.write().await;
+        // A file that shrank was rewritten, so it's read as new
+        let known = positions.get(file_path).copied().filter(|&pos| pos <= len);
+        let from = known.unwrap_or(0);
+
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(from))?;
+        file.take(len - from).read_to_end(&mut bytes)?;
+        // A line still being written is left for the next event
+        let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        positions.insert(file_path.clone(), from + complete as u64);
+        drop(positions);
+
+        let (entries, skipped) = parse_jsonl(&bytes[..complete]);
+        if skipped > 0 {
+            warn!("Skipped {} malformed lines in {:?}", skipped, file_path);
+        }
+        let start = if known.is_some() { 0 } else { entries.len().saturating_sub(50) };
 
-        for line in &lines[start..] {
-            if line.trim().is_empty() {
-                continue;
+        let mut counted: HashMap<String, i64> = HashMap::new();
+        for entry in &entries[start..] {
+            if let Some(session_id) = Self::process_entry(entry, storage, event_bus, sessions).await {
+                *counted.entry(session_id).or_default() += 1;
             }
+        }
 
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                Self::process_entry(&entry, storage, event_bus, sessions).await;
+        if transcript {
+            let now = chrono::Utc::now();
+            for (session_id, entries) in counted {
+                let source = SessionSource {
+                    session_id,
+                    path: file_path.to_string_lossy().into_owned(),
+                    offset: (from + complete as u64) as i64,
+                    entries,
+                    first_read_at: now,
+                    last_read_at: now,
+                };
+                if let Err(e) = storage.record_session_source(&source).await {
+                    warn!("Failed to record session source: {}", e);
+                }
             }
         }
 
         Ok(())
     }
 
-    /// Process a single JSON entry from any source.
+    /// Process a single JSON entry from any source, returning the ID of the
+    /// session it was counted in.
     async fn process_entry(
         entry: &Value,
         storage: &Storage,
         event_bus: &EventBus,
         sessions: &Arc<RwLock<HashMap<String, Session>>>,
-    ) {
+    ) -> Option<String> {
         // Support both history.jsonl format (project) and session file format (cwd)
         let project = entry.get("cwd")
             .or_else(|| entry.get("project"))
@@ -335,11 +1289,11 @@ impl ClaudeCodeAdapter {
 
         // Skip file-history-snapshot entries
         if msg_type == "file-history-snapshot" {
-            return;
+            return None;
         }
 
         if project.is_empty() {
-            return;
+            return None;
         }
 
         let mut sessions_guard = sessions.write().await;
@@ -358,15 +1312,21 @@ impl ClaudeCodeAdapter {
         session.message_count += 1;
         session.update_activity();
         session.status = SessionStatus::Active;
+        if let Some(branch) = entry.get("gitBranch").and_then(|v| v.as_str()).filter(|b| !b.is_empty()) {
+            session.git_branch = Some(branch.to_string());
+        }
 
         // Extract token information from message.usage (new format)
         if let Some(message) = entry.get("message") {
             if let Some(usage) = message.get("usage") {
                 if let Some(input) = usage.get("input_tokens").and_then(|v| v.as_i64()) {
-                    session.tokens_input += input;
+                    session.tokens_input = session.tokens_input.saturating_add(input.max(0));
                 }
                 if let Some(output) = usage.get("output_tokens").and_then(|v| v.as_i64()) {
-                    session.tokens_output += output;
+                    session.tokens_output = session.tokens_output.saturating_add(output.max(0));
+                }
+                if let Some(context) = context_tokens(usage) {
+                    session.context_tokens = Some(context);
                 }
             }
             // Extract model ID
@@ -377,6 +1337,12 @@ impl ClaudeCodeAdapter {
             }
         }
 
+        // A compaction replaces the conversation with a summary, so the
+        // previous context size no longer applies
+        if is_compaction_entry(entry) {
+            session.context_tokens = None;
+        }
+
         // Calculate cost
         let input_cost = session.tokens_input as f64 * 3.0 / 1_000_000.0;
         let output_cost = session.tokens_output as f64 * 15.0 / 1_000_000.0;
@@ -406,7 +1372,13 @@ impl ClaudeCodeAdapter {
             .and_then(|r| r.as_str())
             .unwrap_or(msg_type);
 
+        let is_boundary = entry.get("subtype").and_then(|v| v.as_str()) == Some("compact_boundary");
+        // A failed response, or a failed request Claude Code is about to retry
+        let is_api_error = entry.get("isApiErrorMessage").and_then(|v| v.as_bool()) == Some(true)
+            || (msg_type == "system" && entry.get("subtype").and_then(|v| v.as_str()) == Some("api_error"));
         let event_type = match role {
+            _ if is_boundary => EventType::Compaction,
+            _ if is_api_error => EventType::ApiError,
             "user" => EventType::PromptReceived,
             "assistant" => EventType::ResponseGenerated,
             _ => EventType::Custom,
@@ -422,6 +1394,11 @@ impl ClaudeCodeAdapter {
         // Build full content FIRST so we can use it for stable ID
         let mut full_content: Option<String> = None;
         let mut tool_name: Option<String> = None;
+        let mut tool_input: Option<serde_json::Value> = None;
+        let mut tool_output: Option<String> = None;
+        // Call IDs, so results and latencies can be matched to their calls
+        let mut tool_uses: Vec<serde_json::Value> = Vec::new();
+        let mut tool_results: Vec<serde_json::Value> = Vec::new();
 
         if let Some(message) = entry.get("message") {
             // First check if content is a plain string (user messages often)
@@ -450,12 +1427,24 @@ impl ClaudeCodeAdapter {
                                 .map(|i| serde_json::to_string_pretty(i).unwrap_or_default())
                                 .unwrap_or_default();
                             text_parts.push(format!("[TOOL: {}]\n{}", name, input));
-                            tool_name = Some(name.to_string());
+                            tool_uses.push(serde_json::json!({ "id": block.get("id"), "name": name }));
+                            // The event's tool fields describe its first call
+                            if tool_name.is_none() {
+                                tool_name = Some(name.to_string());
+                                tool_input = block.get("input").cloned();
+                            }
                         }
                         "tool_result" => {
                             if let Some(content) = block.get("content").and_then(|c| c.as_str()) {
                                 text_parts.push(format!("[RESULT]\n{}", content));
                             }
+                            if tool_output.is_none() {
+                                tool_output = tool_result_text(block);
+                            }
+                            tool_results.push(serde_json::json!({
+                                "tool_use_id": block.get("tool_use_id"),
+                                "is_error": block.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false),
+                            }));
                         }
                         _ => {}
                     }
@@ -466,6 +1455,14 @@ impl ClaudeCodeAdapter {
             }
         }
 
+        if is_boundary {
+            let metadata = entry.get("compactMetadata");
+            full_content = Some(describe_compaction(
+                metadata.and_then(|m| m.get("trigger")).and_then(|v| v.as_str()),
+                metadata.and_then(|m| m.get("preTokens")).and_then(|v| v.as_i64()),
+            ));
+        }
+
         // Fallback for history.jsonl format
         if full_content.is_none() {
             if let Some(display) = entry.get("display").and_then(|v| v.as_str()) {
@@ -473,6 +1470,20 @@ impl ClaudeCodeAdapter {
             }
         }
 
+        let api_error = is_api_error.then(|| api_error_details(entry, full_content.as_deref()));
+        if let Some((ref message, _)) = api_error {
+            full_content.get_or_insert_with(|| message.clone());
+        }
+
+        let interrupted = interruption(entry, full_content.as_deref());
+        if interrupted == Some("stop_hook") && full_content.is_none() {
+            let reason = entry.get("stopReason").and_then(|v| v.as_str()).filter(|r| !r.is_empty());
+            full_content = Some(match reason {
+                Some(reason) => format!("Stopped by a Stop hook: {}", reason),
+                None => "Stopped by a Stop hook".to_string(),
+            });
+        }
+
         // Create event with stable ID based on session + timestamp + FULL content
         let mut event = SessionEvent::new_with_stable_id(
             &session.id,
@@ -483,6 +1494,36 @@ impl ClaudeCodeAdapter {
         );
         event.working_directory = Some(project.to_string());
         event.tool_name = tool_name;
+        event.tool_input = tool_input;
+        if let Some(output) = tool_output {
+            event.set_tool_output(&output);
+        }
+        let mut raw = serde_json::Map::new();
+        if let Some((message, details)) = api_error {
+            event.error_message = message.lines().next().map(str::to_string);
+            raw.extend(details);
+        }
+        if !tool_uses.is_empty() {
+            raw.insert("tool_uses".to_string(), tool_uses.into());
+        }
+        if !tool_results.is_empty() {
+            raw.insert("tool_results".to_string(), tool_results.into());
+        }
+        if let Some(reason) = interrupted {
+            raw.insert("interrupted".to_string(), reason.into());
+        }
+        // The model of each response, as a session can switch models
+        if let Some(model) = entry.pointer("/message/model").and_then(|v| v.as_str()) {
+            raw.insert("model".to_string(), model.into());
+        }
+        if !raw.is_empty() {
+            event.raw_data = Some(raw.into());
+        }
+        // Claude Code's own record of the call it just ran
+        if let Some(result) = entry.get("toolUseResult") {
+            event.exit_code = result.get("exitCode").and_then(|v| v.as_i64()).map(|c| c as i32);
+            event.duration_ms = result.get("durationMs").and_then(|v| v.as_i64());
+        }
 
         // Extract token info
         if let Some(message) = entry.get("message") {
@@ -492,73 +1533,71 @@ impl ClaudeCodeAdapter {
             }
         }
 
+        if is_boundary && compaction_already_recorded(storage, &event).await {
+            return Some(event.session_id);
+        }
+
         // Store and publish event
-        if let Err(e) = storage.insert_event(&event).await {
+        let counted_in = event.session_id.clone();
+        if let Err(e) = storage.record_event(event, event_bus).await {
             warn!("Failed to insert event: {}", e);
         }
-        event_bus.publish(event);
+        Some(counted_in)
     }
 
     /// Parse history.jsonl file.
     async fn parse_history(&self) -> Result<Vec<Session>> {
-        use std::io::{BufRead, BufReader};
-
         let mut sessions: HashMap<String, Session> = HashMap::new();
 
         if !self.history_file.exists() {
             return Ok(Vec::new());
         }
 
-        let file = std::fs::File::open(&self.history_file)?;
-        let reader = BufReader::new(file);
+        let (entries, skipped) = parse_jsonl(&std::fs::read(&self.history_file)?);
+        if skipped > 0 {
+            warn!("Skipped {} malformed lines in {:?}", skipped, self.history_file);
+        }
 
-        // Read last 1000 lines
-        let lines: Vec<String> = reader.lines().filter_map(|l| l.ok()).collect();
-        let start = if lines.len() > 1000 {
-            lines.len() - 1000
-        } else {
-            0
-        };
+        // Only the last 1000 entries
+        let start = entries.len().saturating_sub(1000);
+
+        for entry in &entries[start..] {
+            let project = entry.get("project").and_then(|v| v.as_str()).unwrap_or("");
+            let session_id = entry
+                .get("sessionId")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+
+            // Parse timestamp - support both RFC3339 string and Unix milliseconds
+            let timestamp_ms: i64 = if let Some(ts_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
+                // RFC3339 format: "2026-01-05T18:56:29.954Z"
+                chrono::DateTime::parse_from_rfc3339(ts_str)
+                    .map(|dt| dt.timestamp_millis())
+                    .unwrap_or(0)
+            } else {
+                // Fallback to i64 (Unix milliseconds)
+                entry.get("timestamp").and_then(|v| v.as_i64()).unwrap_or(0)
+            };
 
-        for line in &lines[start..] {
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                let project = entry.get("project").and_then(|v| v.as_str()).unwrap_or("");
-                let session_id = entry
-                    .get("sessionId")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-
-                // Parse timestamp - support both RFC3339 string and Unix milliseconds
-                let timestamp_ms: i64 = if let Some(ts_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
-                    // RFC3339 format: "2026-01-05T18:56:29.954Z"
-                    chrono::DateTime::parse_from_rfc3339(ts_str)
-                        .map(|dt| dt.timestamp_millis())
-                        .unwrap_or(0)
-                } else {
-                    // Fallback to i64 (Unix milliseconds)
-                    entry.get("timestamp").and_then(|v| v.as_i64()).unwrap_or(0)
-                };
+            if !project.is_empty() {
+                let session = sessions.entry(project.to_string()).or_insert_with(|| {
+                    let mut s = Session::new(AgentType::ClaudeCode, project, session_id);
+                    s.metadata.insert(
+                        "source".to_string(),
+                        serde_json::Value::String("history".to_string()),
+                    );
+                    s
+                });
 
-                if !project.is_empty() {
-                    let session = sessions.entry(project.to_string()).or_insert_with(|| {
-                        let mut s = Session::new(AgentType::ClaudeCode, project, session_id);
-                        s.metadata.insert(
-                            "source".to_string(),
-                            serde_json::Value::String("history".to_string()),
-                        );
-                        s
-                    });
-
-                    session.message_count += 1;
-                    session.update_activity();
-
-                    // Check if still active (activity in last 30 minutes)
-                    let now_ms = chrono::Utc::now().timestamp_millis();
-                    if timestamp_ms > 0 && (now_ms - timestamp_ms) < 30 * 60 * 1000 {
-                        session.status = SessionStatus::Active;
-                    } else {
-                        session.status = SessionStatus::Completed;
-                    }
+                session.message_count += 1;
+                session.update_activity();
+
+                // Check if still active (activity in last 30 minutes)
+                let now_ms = chrono::Utc::now().timestamp_millis();
+                if timestamp_ms > 0 && now_ms.saturating_sub(timestamp_ms) < 30 * 60 * 1000 {
+                    session.status = SessionStatus::Active;
+                } else {
+                    session.status = SessionStatus::Completed;
                 }
             }
         }
@@ -566,36 +1605,25 @@ impl ClaudeCodeAdapter {
         Ok(sessions.into_values().collect())
     }
 
+    /// Processes that are Claude Code.
+    pub fn process_matcher() -> ProcessMatcher {
+        ProcessMatcher::new(&["claude"], &["@anthropic-ai/claude-code"], &[])
+    }
+
     /// Find running Claude Code processes.
     async fn find_processes(&self) -> Result<Vec<Session>> {
         let mut sessions = Vec::new();
-        let system = System::new_all();
 
-        for (pid, process) in system.processes() {
-            // sysinfo 0.30+ returns OsStr for name and OsString for cmd
-            let name = format!("{:?}", process.name()).to_lowercase();
-            let cmd: String = process.cmd()
-                .iter()
-                .map(|s| format!("{:?}", s))
-                .collect::<Vec<_>>()
-                .join(" ")
-                .to_lowercase();
-
-            if name.contains("claude") || cmd.contains("@anthropic-ai/claude-code") {
-                let cwd = process.cwd()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                if !cwd.is_empty() {
-                    let mut session =
-                        Session::new(AgentType::ClaudeCode, &cwd, &format!("proc_{}", pid));
-                    session.pid = Some(pid.as_u32() as i32);
-                    session.metadata.insert(
-                        "source".to_string(),
-                        serde_json::Value::String("process".to_string()),
-                    );
-                    sessions.push(session);
-                }
+        for process in self.processes.matching(&Self::process_matcher()) {
+            if let Some(ref cwd) = process.cwd {
+                let mut session =
+                    Session::new(AgentType::ClaudeCode, cwd, &format!("proc_{}", process.pid));
+                session.pid = Some(process.pid as i32);
+                session.metadata.insert(
+                    "source".to_string(),
+                    serde_json::Value::String("process".to_string()),
+                );
+                sessions.push(session);
             }
         }
 
@@ -603,6 +1631,130 @@ impl ClaudeCodeAdapter {
     }
 }
 
+/// Context size reported by one Claude response: every input token the
+/// request carried, cached or not.
+fn context_tokens(usage: &Value) -> Option<i64> {
+    let tokens: i64 = ["input_tokens", "cache_read_input_tokens", "cache_creation_input_tokens"]
+        .iter()
+        .filter_map(|key| usage.get(key).and_then(|v| v.as_i64()))
+        .fold(0, |sum, n| sum.saturating_add(n.max(0)));
+    (tokens > 0).then_some(tokens)
+}
+
+/// Why a transcript entry marks a response as cut off: the user pressed Esc
+/// (Claude Code then logs a "[Request interrupted by user]" message), a Stop
+/// hook blocked it from continuing, the API failed mid-stream, or it ran into
+/// the output token limit.
+fn interruption(entry: &Value, content: Option<&str>) -> Option<&'static str> {
+    let message = entry.get("message");
+    if content.is_some_and(|c| c.trim_start().starts_with("[Request interrupted by user")) {
+        Some("user")
+    } else if entry.get("preventedContinuation").and_then(|v| v.as_bool()) == Some(true) {
+        Some("stop_hook")
+    } else if entry.get("isApiErrorMessage").and_then(|v| v.as_bool()) == Some(true) {
+        Some("api_error")
+    } else if message.and_then(|m| m.get("stop_reason")).and_then(|v| v.as_str()) == Some("max_tokens") {
+        Some("max_tokens")
+    } else {
+        None
+    }
+}
+
+/// Message and details (status, kind, retry) of an API error entry: the
+/// failed response Claude Code shows, or the system entry it logs before
+/// retrying a request.
+fn api_error_details(entry: &Value, content: Option<&str>) -> (String, serde_json::Map<String, Value>) {
+    let error = entry.get("error");
+    let reported = error.and_then(|e| e.get("status")).and_then(|v| v.as_u64()).map(|s| s as u16);
+    let error_type = error.and_then(|e| e.pointer("/error/error/type")).and_then(|v| v.as_str());
+    let (status, kind) = crate::apierrors::classify(
+        reported,
+        &format!("{} {}", content.unwrap_or_default(), error_type.unwrap_or_default()),
+    );
+    let attempt = entry.get("retryAttempt").and_then(|v| v.as_i64());
+    let max_retries = entry.get("maxRetries").and_then(|v| v.as_i64());
+    let retry_in_ms = entry.get("retryInMs").and_then(|v| v.as_f64());
+
+    let message = match (content, attempt) {
+        (Some(content), _) => content.to_string(),
+        (None, Some(attempt)) => format!(
+            "API error {}({}), retry {}/{} in {:.1}s",
+            status.map(|s| format!("{} ", s)).unwrap_or_default(),
+            kind,
+            attempt,
+            max_retries.map(|m| m.to_string()).unwrap_or_else(|| "?".to_string()),
+            retry_in_ms.unwrap_or(0.0) / 1000.0
+        ),
+        (None, None) => format!("API error {}({})", status.map(|s| format!("{} ", s)).unwrap_or_default(), kind),
+    };
+    let mut details = serde_json::Map::new();
+    details.insert("status".to_string(), status.into());
+    details.insert("kind".to_string(), kind.into());
+    if let Some(attempt) = attempt {
+        details.insert("retry_attempt".to_string(), attempt.into());
+        details.insert("max_retries".to_string(), max_retries.into());
+        details.insert("retry_in_ms".to_string(), retry_in_ms.into());
+    }
+    (message, details)
+}
+
+/// Text of a `tool_result` block: a string, or text blocks joined.
+fn tool_result_text(block: &Value) -> Option<String> {
+    match block.get("content")? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => {
+            let texts: Vec<&str> = parts.iter().filter_map(|p| p.get("text").and_then(|t| t.as_str())).collect();
+            (!texts.is_empty()).then(|| texts.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+/// Longest JSONL line parsed. Lines carrying large tool results run to a few
+/// MB; anything past this is a corrupt file rather than an entry.
+const MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+/// The JSON objects in JSONL `bytes`, and how many non-blank lines were
+/// skipped for being invalid UTF-8 or JSON, not an object, or too long.
+fn parse_jsonl(bytes: &[u8]) -> (Vec<Value>, usize) {
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for line in bytes.split(|&b| b == b'\n') {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        if line.len() > MAX_LINE_BYTES {
+            skipped += 1;
+            continue;
+        }
+        match serde_json::from_slice::<Value>(line) {
+            Ok(entry) if entry.is_object() => entries.push(entry),
+            _ => skipped += 1,
+        }
+    }
+    (entries, skipped)
+}
+
+/// Whether a transcript entry marks a compaction: the `compact_boundary`
+/// system entry, or the summary message that replaces the conversation.
+fn is_compaction_entry(entry: &Value) -> bool {
+    entry.get("subtype").and_then(|v| v.as_str()) == Some("compact_boundary")
+        || entry.get("isCompactSummary").and_then(|v| v.as_bool()) == Some(true)
+}
+
+/// Whether the PreCompact hook already recorded the compaction that a
+/// transcript boundary marks (the hook fires just before it).
+async fn compaction_already_recorded(storage: &Storage, boundary: &SessionEvent) -> bool {
+    let Ok(events) = storage.get_session_events(&boundary.session_id, 50).await else {
+        return false;
+    };
+    events.iter().any(|e| {
+        e.event_type == EventType::Compaction
+            && e.id != boundary.id
+            && (boundary.timestamp - e.timestamp).num_minutes().abs() <= 10
+    })
+}
+
 #[async_trait]
 impl Adapter for ClaudeCodeAdapter {
     fn name(&self) -> &str {
@@ -615,6 +1767,7 @@ impl Adapter for ClaudeCodeAdapter {
 
     async fn start(&mut self) -> Result<()> {
         *self.running.write().await = true;
+        self.health.set_running(true);
 
         // Initial discovery
         let sessions = self.discover_sessions().await?;
@@ -625,77 +1778,74 @@ impl Adapter for ClaudeCodeAdapter {
                 .await
                 .insert(session.id.clone(), session);
         }
+        self.health.record_scan();
 
         // Create stop channel for file watcher
         let (stop_tx, stop_rx) = mpsc::channel::<()>(1);
         self.watcher_stop_tx = Some(stop_tx);
 
         // Start the real file watcher
-        Self::start_file_watcher(
-            self.claude_home.clone(),
-            self.history_file.clone(),
-            self.projects_dir.clone(),
-            self.storage.clone(),
-            self.event_bus.clone(),
-            self.sessions.clone(),
-            self.last_history_pos.clone(),
-            stop_rx,
-        );
+        let watcher = FileWatcher {
+            claude_home: self.claude_home.clone(),
+            history_file: self.history_file.clone(),
+            projects_dir: self.projects_dir.clone(),
+            storage: self.storage.clone(),
+            event_bus: self.event_bus.clone(),
+            sessions: self.sessions.clone(),
+            read_positions: self.read_positions.clone(),
+            processes: self.processes.clone(),
+            health: self.health.clone(),
+            watch: self.watch.clone(),
+        };
+        Self::start_file_watcher(watcher, stop_rx);
 
-        // Also start a periodic process scanner (every 60 seconds)
+        // Pick up Claude Code processes as the shared process watcher sees them start
         let storage = self.storage.clone();
         let sessions = self.sessions.clone();
-        let running = self.running.clone();
+        let health = self.health.clone();
+        let mut process_events = self.processes.subscribe(Self::process_matcher());
 
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(60));
-
-            while *running.read().await {
-                interval.tick().await;
-
-                // Scan for new processes
-                let system = System::new_all();
-                for (pid, process) in system.processes() {
-                    let name = format!("{:?}", process.name()).to_lowercase();
-                    let cmd: String = process.cmd()
-                        .iter()
-                        .map(|s| format!("{:?}", s))
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                        .to_lowercase();
-
-                    if name.contains("claude") || cmd.contains("@anthropic-ai/claude-code") {
-                        let cwd = process.cwd()
-                            .map(|p| p.to_string_lossy().to_string())
-                            .unwrap_or_default();
-
-                        if !cwd.is_empty() {
-                            let mut sessions_guard = sessions.write().await;
-                            if !sessions_guard.contains_key(&cwd) {
-                                let mut session = Session::new(
-                                    AgentType::ClaudeCode,
-                                    &cwd,
-                                    &format!("proc_{}", pid),
-                                );
-                                session.pid = Some(pid.as_u32() as i32);
-                                session.metadata.insert(
-                                    "source".to_string(),
-                                    serde_json::Value::String("process_scan".to_string()),
-                                );
-
-                                if let Err(e) = storage.upsert_session(&session).await {
-                                    warn!("Failed to save process-detected session: {}", e);
-                                }
+        let scanner = tokio::spawn(async move {
+            let mut heartbeat = interval(Duration::from_secs(60));
+            let mut sweep = interval(Duration::from_secs(SESSION_SWEEP_SECS));
 
-                                sessions_guard.insert(cwd, session);
+            loop {
+                tokio::select! {
+                    Some(event) = process_events.recv() => {
+                        let ProcessEvent::Started(process) = event else {
+                            continue;
+                        };
+                        let Some(cwd) = process.cwd else {
+                            continue;
+                        };
+
+                        let mut sessions_guard = sessions.write().await;
+                        if !sessions_guard.contains_key(&cwd) {
+                            let mut session = Session::new(
+                                AgentType::ClaudeCode,
+                                &cwd,
+                                &format!("proc_{}", process.pid),
+                            );
+                            session.pid = Some(process.pid as i32);
+                            session.metadata.insert(
+                                "source".to_string(),
+                                serde_json::Value::String("process_scan".to_string()),
+                            );
+
+                            if let Err(e) = storage.upsert_session(&session).await {
+                                warn!("Failed to save process-detected session: {}", e);
+                                health.record_error(e);
                             }
+
+                            sessions_guard.insert(cwd, session);
                         }
                     }
+                    _ = heartbeat.tick() => health.record_scan(),
+                    _ = sweep.tick() => sweep_sessions(&sessions, &storage, &health).await,
                 }
-
-                debug!("Process scan complete");
             }
         });
+        self.scanner_task = Some(scanner);
 
         info!("Claude Code adapter started with file watching");
         Ok(())
@@ -708,6 +1858,10 @@ impl Adapter for ClaudeCodeAdapter {
         if let Some(tx) = self.watcher_stop_tx.take() {
             let _ = tx.send(()).await;
         }
+        if let Some(scanner) = self.scanner_task.take() {
+            scanner.abort();
+        }
+        self.health.set_running(false);
 
         info!("Claude Code adapter stopped");
         Ok(())
@@ -741,6 +1895,10 @@ impl Adapter for ClaudeCodeAdapter {
         caps.insert("transcript_access".to_string(), true);
         caps
     }
+
+    fn health(&self) -> AdapterHealth {
+        self.health.snapshot_holding(&self.sessions)
+    }
 }
 
 // ============================================================================
@@ -756,11 +1914,15 @@ pub struct CursorAdapter {
     sessions: Arc<RwLock<HashMap<String, Session>>>,
     running: Arc<RwLock<bool>>,
     watcher_stop_tx: Option<mpsc::Sender<()>>,
+    /// Reacts to agent processes starting, aborted on stop
+    scanner_task: Option<tokio::task::JoinHandle<()>>,
+    processes: ProcessWatcher,
+    health: HealthTracker,
 }
 
 impl CursorAdapter {
     /// Create a new Cursor adapter.
-    pub fn new(_config: &Config, event_bus: EventBus, storage: Storage) -> Self {
+    pub fn new(_config: &Config, event_bus: EventBus, storage: Storage, processes: ProcessWatcher) -> Self {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
 
         // Cursor stores data in different locations per platform
@@ -781,35 +1943,37 @@ impl CursorAdapter {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             running: Arc::new(RwLock::new(false)),
             watcher_stop_tx: None,
+            scanner_task: None,
+            processes,
+            health: HealthTracker::new("cursor", 30),
         }
     }
 
+    /// Processes that are Cursor.
+    pub fn process_matcher() -> ProcessMatcher {
+        ProcessMatcher::new(&["cursor"], &[], &["cursorless"])
+    }
+
     /// Find running Cursor processes.
     async fn find_processes(&self) -> Result<Vec<Session>> {
         let mut sessions = Vec::new();
-        let system = System::new_all();
 
-        for (pid, process) in system.processes() {
-            let name = format!("{:?}", process.name()).to_lowercase();
-
-            if name.contains("cursor") && !name.contains("cursorless") {
-                let cwd = process.cwd()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                if !cwd.is_empty() && !cwd.contains("Application Support") {
+        for process in self.processes.matching(&Self::process_matcher()) {
+            match process.cwd {
+                Some(ref cwd) if !cwd.contains("Application Support") => {
                     let mut session = Session::new(
                         AgentType::Cursor,
-                        &cwd,
-                        &format!("cursor_{}", pid),
+                        cwd,
+                        &format!("cursor_{}", process.pid),
                     );
-                    session.pid = Some(pid.as_u32() as i32);
+                    session.pid = Some(process.pid as i32);
                     session.metadata.insert(
                         "source".to_string(),
                         serde_json::Value::String("process".to_string()),
                     );
                     sessions.push(session);
                 }
+                _ => {}
             }
         }
 
@@ -880,6 +2044,7 @@ impl Adapter for CursorAdapter {
 
     async fn start(&mut self) -> Result<()> {
         *self.running.write().await = true;
+        self.health.set_running(true);
 
         // Initial discovery
         let sessions = self.discover_sessions().await?;
@@ -887,48 +2052,51 @@ impl Adapter for CursorAdapter {
             self.storage.upsert_session(&session).await?;
             self.sessions.write().await.insert(session.id.clone(), session);
         }
+        self.health.record_scan();
 
-        // Start periodic process scanner
+        // Pick up Cursor processes as the shared process watcher sees them start
         let storage = self.storage.clone();
         let sessions = self.sessions.clone();
-        let running = self.running.clone();
+        let health = self.health.clone();
+        let mut process_events = self.processes.subscribe(Self::process_matcher());
 
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(30));
-
-            while *running.read().await {
-                interval.tick().await;
-
-                let system = System::new_all();
-                for (pid, process) in system.processes() {
-                    let name = format!("{:?}", process.name()).to_lowercase();
-
-                    if name.contains("cursor") && !name.contains("cursorless") {
-                        let cwd = process.cwd()
-                            .map(|p| p.to_string_lossy().to_string())
-                            .unwrap_or_default();
-
-                        if !cwd.is_empty() && !cwd.contains("Application Support") {
-                            let mut sessions_guard = sessions.write().await;
-                            if !sessions_guard.contains_key(&cwd) {
-                                let mut session = Session::new(
-                                    AgentType::Cursor,
-                                    &cwd,
-                                    &format!("cursor_{}", pid),
-                                );
-                                session.pid = Some(pid.as_u32() as i32);
-
-                                if let Err(e) = storage.upsert_session(&session).await {
-                                    warn!("Failed to save Cursor session: {}", e);
-                                }
+        let scanner = tokio::spawn(async move {
+            let mut heartbeat = interval(Duration::from_secs(30));
+            let mut sweep = interval(Duration::from_secs(SESSION_SWEEP_SECS));
 
-                                sessions_guard.insert(cwd, session);
+            loop {
+                tokio::select! {
+                    Some(event) = process_events.recv() => {
+                        let ProcessEvent::Started(process) = event else {
+                            continue;
+                        };
+                        let Some(cwd) = process.cwd.filter(|c| !c.contains("Application Support")) else {
+                            continue;
+                        };
+
+                        let mut sessions_guard = sessions.write().await;
+                        if !sessions_guard.contains_key(&cwd) {
+                            let mut session = Session::new(
+                                AgentType::Cursor,
+                                &cwd,
+                                &format!("cursor_{}", process.pid),
+                            );
+                            session.pid = Some(process.pid as i32);
+
+                            if let Err(e) = storage.upsert_session(&session).await {
+                                warn!("Failed to save Cursor session: {}", e);
+                                health.record_error(e);
                             }
+
+                            sessions_guard.insert(cwd, session);
                         }
                     }
+                    _ = heartbeat.tick() => health.record_scan(),
+                    _ = sweep.tick() => sweep_sessions(&sessions, &storage, &health).await,
                 }
             }
         });
+        self.scanner_task = Some(scanner);
 
         info!("Cursor adapter started");
         Ok(())
@@ -939,6 +2107,10 @@ impl Adapter for CursorAdapter {
         if let Some(tx) = self.watcher_stop_tx.take() {
             let _ = tx.send(()).await;
         }
+        if let Some(scanner) = self.scanner_task.take() {
+            scanner.abort();
+        }
+        self.health.set_running(false);
         info!("Cursor adapter stopped");
         Ok(())
     }
@@ -971,6 +2143,10 @@ impl Adapter for CursorAdapter {
         caps.insert("transcript_access".to_string(), false);
         caps
     }
+
+    fn health(&self) -> AdapterHealth {
+        self.health.snapshot_holding(&self.sessions)
+    }
 }
 
 // ============================================================================
@@ -987,11 +2163,15 @@ pub struct AiderAdapter {
     running: Arc<RwLock<bool>>,
     last_history_pos: Arc<RwLock<u64>>,
     watcher_stop_tx: Option<mpsc::Sender<()>>,
+    /// Reacts to agent processes starting, aborted on stop
+    scanner_task: Option<tokio::task::JoinHandle<()>>,
+    processes: ProcessWatcher,
+    health: HealthTracker,
 }
 
 impl AiderAdapter {
     /// Create a new Aider adapter.
-    pub fn new(_config: &Config, event_bus: EventBus, storage: Storage) -> Self {
+    pub fn new(_config: &Config, event_bus: EventBus, storage: Storage, processes: ProcessWatcher) -> Self {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
         let aider_home = home.join(".aider");
 
@@ -1004,52 +2184,48 @@ impl AiderAdapter {
             running: Arc::new(RwLock::new(false)),
             last_history_pos: Arc::new(RwLock::new(0)),
             watcher_stop_tx: None,
+            scanner_task: None,
+            processes,
+            health: HealthTracker::new("aider", 30),
         }
     }
 
+    /// Processes that are Aider.
+    pub fn process_matcher() -> ProcessMatcher {
+        ProcessMatcher::new(&[], &["aider"], &["aider-"])
+    }
+
     /// Find running Aider processes.
     async fn find_processes(&self) -> Result<Vec<Session>> {
         let mut sessions = Vec::new();
-        let system = System::new_all();
-
-        for (pid, process) in system.processes() {
-            let cmd: String = process.cmd()
-                .iter()
-                .map(|s| format!("{:?}", s))
-                .collect::<Vec<_>>()
-                .join(" ")
-                .to_lowercase();
 
-            if cmd.contains("aider") && !cmd.contains("aider-") {
-                let cwd = process.cwd()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default();
+        for process in self.processes.matching(&Self::process_matcher()) {
+            let cmd = process.cmd.to_lowercase();
 
-                if !cwd.is_empty() {
-                    let mut session = Session::new(
-                        AgentType::Aider,
-                        &cwd,
-                        &format!("aider_{}", pid),
-                    );
-                    session.pid = Some(pid.as_u32() as i32);
-                    session.metadata.insert(
-                        "source".to_string(),
-                        serde_json::Value::String("process".to_string()),
-                    );
+            if let Some(ref cwd) = process.cwd {
+                let mut session = Session::new(
+                    AgentType::Aider,
+                    cwd,
+                    &format!("aider_{}", process.pid),
+                );
+                session.pid = Some(process.pid as i32);
+                session.metadata.insert(
+                    "source".to_string(),
+                    serde_json::Value::String("process".to_string()),
+                );
 
-                    // Try to detect model from command line
-                    if cmd.contains("--model") {
-                        if let Some(model_pos) = cmd.find("--model") {
-                            let after = &cmd[model_pos + 7..];
-                            let model: String = after.split_whitespace().next().unwrap_or("").to_string();
-                            if !model.is_empty() {
-                                session.model_id = Some(model);
-                            }
+                // Try to detect model from command line
+                if cmd.contains("--model") {
+                    if let Some(model_pos) = cmd.find("--model") {
+                        let after = &cmd[model_pos + 7..];
+                        let model: String = after.split_whitespace().next().unwrap_or("").to_string();
+                        if !model.is_empty() {
+                            session.model_id = Some(model);
                         }
                     }
-
-                    sessions.push(session);
                 }
+
+                sessions.push(session);
             }
         }
 
@@ -1123,6 +2299,7 @@ impl Adapter for AiderAdapter {
 
     async fn start(&mut self) -> Result<()> {
         *self.running.write().await = true;
+        self.health.set_running(true);
 
         // Initial discovery
         let sessions = self.discover_sessions().await?;
@@ -1130,53 +2307,51 @@ impl Adapter for AiderAdapter {
             self.storage.upsert_session(&session).await?;
             self.sessions.write().await.insert(session.id.clone(), session);
         }
+        self.health.record_scan();
 
-        // Start periodic process scanner
+        // Pick up Aider processes as the shared process watcher sees them start
         let storage = self.storage.clone();
         let sessions = self.sessions.clone();
-        let running = self.running.clone();
+        let health = self.health.clone();
+        let mut process_events = self.processes.subscribe(Self::process_matcher());
 
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(30));
-
-            while *running.read().await {
-                interval.tick().await;
-
-                let system = System::new_all();
-                for (pid, process) in system.processes() {
-                    let cmd: String = process.cmd()
-                        .iter()
-                        .map(|s| format!("{:?}", s))
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                        .to_lowercase();
-
-                    if cmd.contains("aider") && !cmd.contains("aider-") {
-                        let cwd = process.cwd()
-                            .map(|p| p.to_string_lossy().to_string())
-                            .unwrap_or_default();
-
-                        if !cwd.is_empty() {
-                            let mut sessions_guard = sessions.write().await;
-                            if !sessions_guard.contains_key(&cwd) {
-                                let mut session = Session::new(
-                                    AgentType::Aider,
-                                    &cwd,
-                                    &format!("aider_{}", pid),
-                                );
-                                session.pid = Some(pid.as_u32() as i32);
-
-                                if let Err(e) = storage.upsert_session(&session).await {
-                                    warn!("Failed to save Aider session: {}", e);
-                                }
+        let scanner = tokio::spawn(async move {
+            let mut heartbeat = interval(Duration::from_secs(30));
+            let mut sweep = interval(Duration::from_secs(SESSION_SWEEP_SECS));
 
-                                sessions_guard.insert(cwd, session);
+            loop {
+                tokio::select! {
+                    Some(event) = process_events.recv() => {
+                        let ProcessEvent::Started(process) = event else {
+                            continue;
+                        };
+                        let Some(cwd) = process.cwd else {
+                            continue;
+                        };
+
+                        let mut sessions_guard = sessions.write().await;
+                        if !sessions_guard.contains_key(&cwd) {
+                            let mut session = Session::new(
+                                AgentType::Aider,
+                                &cwd,
+                                &format!("aider_{}", process.pid),
+                            );
+                            session.pid = Some(process.pid as i32);
+
+                            if let Err(e) = storage.upsert_session(&session).await {
+                                warn!("Failed to save Aider session: {}", e);
+                                health.record_error(e);
                             }
+
+                            sessions_guard.insert(cwd, session);
                         }
                     }
+                    _ = heartbeat.tick() => health.record_scan(),
+                    _ = sweep.tick() => sweep_sessions(&sessions, &storage, &health).await,
                 }
             }
         });
+        self.scanner_task = Some(scanner);
 
         info!("Aider adapter started");
         Ok(())
@@ -1187,6 +2362,10 @@ impl Adapter for AiderAdapter {
         if let Some(tx) = self.watcher_stop_tx.take() {
             let _ = tx.send(()).await;
         }
+        if let Some(scanner) = self.scanner_task.take() {
+            scanner.abort();
+        }
+        self.health.set_running(false);
         info!("Aider adapter stopped");
         Ok(())
     }
@@ -1219,4 +2398,375 @@ impl Adapter for AiderAdapter {
         caps.insert("transcript_access".to_string(), true);
         caps
     }
+
+    fn health(&self) -> AdapterHealth {
+        self.health.snapshot_holding(&self.sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::json_entry;
+    use crate::watchmode::WatchMode;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_health_state_transitions() {
+        let health = HealthTracker::new("test", 30);
+        assert_eq!(health.snapshot().state, "stopped");
+
+        health.set_running(true);
+        assert_eq!(health.snapshot().state, "stale");
+
+        health.record_scan();
+        assert_eq!(health.snapshot().state, "ok");
+
+        health.record_error("permission denied");
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.state, "degraded");
+        assert_eq!(snapshot.error_count, 1);
+    }
+
+    #[test]
+    fn test_lost_watch_is_degraded() {
+        let health = HealthTracker::new("test", 30);
+        health.set_running(true);
+        health.record_scan();
+
+        // A missing directory is not a failure; an existing one we can't watch is
+        health.set_watch(Path::new("/nonexistent/agent-monitor"), Ok(()));
+        assert_eq!(health.snapshot().state, "ok");
+
+        let dir = std::env::temp_dir();
+        health.inner.write().unwrap().watched_paths.push(WatchedPath {
+            path: dir.to_string_lossy().to_string(),
+            exists: true,
+            watching: false,
+            error: None,
+            failures: 0,
+            retry_at: None,
+            polled: None,
+        });
+        assert_eq!(health.snapshot().state, "degraded");
+    }
+
+    #[tokio::test]
+    async fn test_missing_directories_are_waited_for() {
+        assert_eq!(watch_retry_delay(1, 0.0), Duration::from_secs(1));
+        assert_eq!(watch_retry_delay(4, 0.0), Duration::from_secs(8));
+        assert_eq!(watch_retry_delay(40, 0.0), Duration::from_secs(300));
+        assert_eq!(watch_retry_delay(40, 1.0), Duration::from_secs(360));
+
+        let home = tempfile::tempdir().unwrap();
+        let claude_home = home.path().join(".claude");
+        let projects = claude_home.join("projects");
+        let health = HealthTracker::new("test", 30);
+        let (tx, _rx) = mpsc::channel(100);
+        let config = WatchConfig { mode: WatchMode::Native, ..WatchConfig::default() };
+        let targets = [(claude_home.clone(), RecursiveMode::Recursive), (projects.clone(), RecursiveMode::Recursive)];
+        let mut watches = WatchSet::new(targets, config, health.clone(), tx.clone()).unwrap();
+
+        // A fresh machine: nothing to watch yet, so the home directory is
+        // watched for it to appear, without counting errors
+        watches.register(true);
+        assert!(watches.next_retry().is_some());
+        assert_eq!(watches.parents.keys().collect::<Vec<_>>(), [home.path()]);
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.error_count, 0);
+        assert!(snapshot.watched_paths.iter().all(|w| !w.exists && w.failures == 1 && w.retry_at.is_some()));
+
+        std::fs::create_dir(&claude_home).unwrap();
+        let created = Event::new(notify::EventKind::Create(notify::event::CreateKind::Folder)).add_path(claude_home.clone());
+        assert!(watches.changed_by(&created));
+        watches.register(true);
+        assert!(watches.targets[0].watching() && !watches.targets[1].watching());
+        // The Claude home's own watch covers the projects directory appearing
+        assert!(watches.parents.is_empty());
+
+        std::fs::create_dir(&projects).unwrap();
+        let created = Event::new(notify::EventKind::Create(notify::event::CreateKind::Folder)).add_path(projects.clone());
+        assert!(watches.changed_by(&created));
+        watches.register(true);
+        assert!(watches.next_retry().is_none());
+        assert!(health.snapshot().watched_paths.iter().all(|w| w.watching));
+
+        // Removed again: waited for the same way
+        let removed = Event::new(notify::EventKind::Remove(notify::event::RemoveKind::Folder)).add_path(claude_home.clone());
+        assert!(watches.changed_by(&removed));
+        assert!(watches.targets.iter().all(|t| !t.watching()));
+
+        // Polled directories say why
+        std::fs::create_dir_all(&projects).unwrap();
+        let config = WatchConfig { mode: WatchMode::Poll, ..WatchConfig::default() };
+        let health = HealthTracker::new("test", 30);
+        let mut watches = WatchSet::new([(projects.clone(), RecursiveMode::Recursive)], config, health.clone(), tx).unwrap();
+        watches.register(true);
+        assert!(matches!(watches.targets[0].watched, Some(WatchMethod::Poll { .. })));
+        assert_eq!(health.snapshot().watched_paths[0].polled.as_deref(), Some("configured"));
+    }
+
+    #[tokio::test]
+    async fn test_only_projects_in_use_are_watched() {
+        let home = tempfile::tempdir().unwrap();
+        let projects = home.path().join("projects");
+        let dirs: Vec<PathBuf> = ["-src-api", "-src-web", "-src-cli"].iter().map(|d| projects.join(d)).collect();
+        for dir in &dirs {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let health = HealthTracker::new("test", 30);
+        let (tx, _rx) = mpsc::channel(100);
+        let config = WatchConfig { mode: WatchMode::Native, max_project_dirs: 2, ..WatchConfig::default() };
+        let targets = [
+            (home.path().to_path_buf(), RecursiveMode::NonRecursive),
+            (projects.clone(), RecursiveMode::NonRecursive),
+        ];
+        let mut watches = WatchSet::new(targets, config.clone(), health.clone(), tx.clone()).unwrap();
+        watches.register(true);
+
+        assert!(watches.activate(&dirs[0]));
+        assert!(watches.activate(&dirs[1]));
+        assert!(!watches.activate(&dirs[0]));
+        assert!(!watches.activate(&projects.join("-src-gone")));
+        // Activity in the API project keeps it; the web project goes
+        watches.touch(&dirs[0].join("session.jsonl"));
+        assert!(watches.activate(&dirs[2]));
+        let watched: Vec<&Path> = watches.targets.iter().map(|t| t.path.as_path()).collect();
+        assert_eq!(watched, [home.path(), &projects, &dirs[0], &dirs[2]]);
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.watches_evicted, 1);
+        assert!(!snapshot.watched_paths.iter().any(|w| Path::new(&w.path) == dirs[1]));
+
+        // A removed project is dropped rather than waited for
+        assert!(watches.lost(std::slice::from_ref(&dirs[2])));
+        assert_eq!(watches.targets.len(), 3);
+        assert!(watches.next_retry().is_none());
+
+        // Without `selective` everything is watched already
+        let config = WatchConfig { selective: false, ..config };
+        let mut watches = WatchSet::new([(projects.clone(), RecursiveMode::Recursive)], config, health, tx).unwrap();
+        assert!(!watches.activate(&dirs[1]));
+    }
+
+    #[test]
+    fn test_context_signals() {
+        let usage = serde_json::json!({
+            "input_tokens": 12,
+            "cache_creation_input_tokens": 3000,
+            "cache_read_input_tokens": 150000,
+            "output_tokens": 400
+        });
+        assert_eq!(context_tokens(&usage), Some(153_012));
+        assert_eq!(context_tokens(&serde_json::json!({})), None);
+
+        let boundary = serde_json::json!({"type": "system", "subtype": "compact_boundary"});
+        let summary = serde_json::json!({"type": "user", "isCompactSummary": true});
+        assert!(is_compaction_entry(&boundary));
+        assert!(is_compaction_entry(&summary));
+        assert!(!is_compaction_entry(&serde_json::json!({"type": "user"})));
+    }
+
+    #[test]
+    fn test_giant_jsonl_line_is_skipped() {
+        let mut bytes = vec![b'"'; MAX_LINE_BYTES + 1];
+        bytes.extend_from_slice(b"\n{\"type\": \"user\"}\n");
+        let (entries, skipped) = parse_jsonl(&bytes);
+        assert_eq!(entries, [serde_json::json!({"type": "user"})]);
+        assert_eq!(skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_recover_reads_what_was_written_while_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let transcript = dir.path().join("abc.jsonl");
+        let line = |kind: &str, text: &str, minutes_ago: i64| {
+            let timestamp = (Utc::now() - chrono::Duration::minutes(minutes_ago)).to_rfc3339();
+            format!(
+                "{}\n",
+                serde_json::json!({
+                    "type": kind,
+                    "cwd": "/work/api",
+                    "sessionId": "abc",
+                    "timestamp": timestamp,
+                    "message": { "role": kind, "content": text },
+                })
+            )
+        };
+        let read = line("user", "fix the build", 90);
+        std::fs::write(&transcript, format!("{}{}", read, line("assistant", "Fixed.", 80))).unwrap();
+
+        // The last run read the prompt, then stopped
+        let storage = Storage::in_memory();
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+        let source = SessionSource {
+            session_id: session.id.clone(),
+            path: transcript.to_string_lossy().into_owned(),
+            offset: read.len() as i64,
+            entries: 1,
+            first_read_at: Utc::now(),
+            last_read_at: Utc::now(),
+        };
+        storage.record_session_source(&source).await.unwrap();
+
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let positions = Arc::new(RwLock::new(HashMap::new()));
+        ClaudeCodeAdapter::recover(&storage, &EventBus::new(), &sessions, &positions, &ProcessWatcher::new(60))
+            .await
+            .unwrap();
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].content.as_deref(), Some("Fixed."));
+        let sources = storage.get_session_sources(Some(&session.id)).await.unwrap();
+        assert_eq!(sources[0].entries, 2);
+        assert_eq!(sources[0].offset as u64, std::fs::metadata(&transcript).unwrap().len());
+
+        // No agent is running there, so the session ended at its last event
+        let recovered = storage.get_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(recovered.status, SessionStatus::Completed);
+        assert_eq!(recovered.ended_at, Some(events[0].timestamp));
+        assert_eq!(recovered.last_activity_at, events[0].timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_responses_are_counted() {
+        let storage = Storage::in_memory();
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let entries = [
+            serde_json::json!({ "type": "user", "message": { "role": "user", "content": "refactor the parser" } }),
+            serde_json::json!({ "type": "system", "subtype": "api_error", "error": { "status": 429 }, "retryAttempt": 2, "maxRetries": 10, "retryInMs": 1500.0 }),
+            serde_json::json!({ "type": "assistant", "message": { "role": "assistant", "content": "Starting with", "stop_reason": "max_tokens" } }),
+            serde_json::json!({ "type": "user", "message": { "role": "user", "content": [{ "type": "text", "text": "[Request interrupted by user]" }] } }),
+            serde_json::json!({ "type": "assistant", "isApiErrorMessage": true, "message": { "role": "assistant", "content": "API Error: 529 overloaded" } }),
+            serde_json::json!({ "type": "system", "subtype": "stop_hook_summary", "preventedContinuation": true, "stopReason": "tests failing" }),
+            serde_json::json!({ "type": "assistant", "message": { "role": "assistant", "content": "Done.", "stop_reason": "end_turn" } }),
+        ];
+        for (i, mut entry) in entries.into_iter().enumerate() {
+            entry["cwd"] = "/work/api".into();
+            entry["sessionId"] = "abc".into();
+            entry["timestamp"] = (Utc::now() - chrono::Duration::seconds(10 - i as i64)).to_rfc3339().into();
+            ClaudeCodeAdapter::process_entry(&entry, &storage, &EventBus::new(), &sessions).await;
+        }
+
+        let session_id = sessions.read().await["/work/api"].id.clone();
+        let session = storage.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(session.interruptions, 4);
+        let events = storage.get_session_events(&session_id, 10).await.unwrap();
+        let reasons: Vec<_> = events.iter().rev().filter_map(|e| e.interruption()).collect();
+        assert_eq!(reasons, vec!["max_tokens", "user", "api_error", "stop_hook"]);
+        assert!(events.iter().any(|e| e.content.as_deref() == Some("Stopped by a Stop hook: tests failing")));
+
+        // Failed responses and retried requests are API errors
+        let api_errors: Vec<_> = events.iter().filter(|e| e.event_type == EventType::ApiError).collect();
+        assert_eq!(api_errors.len(), 2);
+        assert_eq!(api_errors[0].raw_data.as_ref().unwrap()["kind"], "overloaded");
+        assert_eq!(api_errors[0].raw_data.as_ref().unwrap()["interrupted"], "api_error");
+        assert_eq!(api_errors[1].error_message.as_deref(), Some("API error 429 (rate_limited), retry 2/10 in 1.5s"));
+
+        // Quitting right after an interrupt is not a crash
+        let interrupted = &events[3];
+        assert_eq!(interrupted.interruption(), Some("user"));
+        assert_eq!(crate::exits::exit_status(&events[3..]).0, SessionStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_drops_ended_sessions() {
+        let storage = Storage::in_memory();
+        let health = HealthTracker::new("test", 30);
+        let sessions = Arc::new(RwLock::new(HashMap::new()));
+        let session = |project: &str, status: SessionStatus, idle_hours: i64| {
+            let mut s = Session::new(AgentType::ClaudeCode, project, project);
+            s.status = status;
+            s.last_activity_at = Utc::now() - chrono::Duration::hours(idle_hours);
+            s
+        };
+        let open = session("/work/api", SessionStatus::Active, 0);
+        let idle = session("/work/web", SessionStatus::Idle, 2);
+        let closed = session("/work/cli", SessionStatus::Completed, 0);
+        let abandoned = session("/work/old", SessionStatus::Active, 48);
+        let unsaved = session("/work/tmp", SessionStatus::Active, 0);
+        for s in [&open, &idle, &closed, &abandoned] {
+            storage.upsert_session(s).await.unwrap();
+        }
+        for s in [open, idle, closed, abandoned, unsaved] {
+            sessions.write().await.insert(s.project_path.clone(), s);
+        }
+
+        sweep_sessions(&sessions, &storage, &health).await;
+        let mut left: Vec<String> = sessions.read().await.keys().cloned().collect();
+        left.sort();
+        assert_eq!(left, ["/work/api", "/work/web"]);
+        let snapshot = health.snapshot_holding(&sessions);
+        assert_eq!((snapshot.sessions_in_memory, snapshot.sessions_evicted), (2, 3));
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_process_entry_survives_any_entry(entries in prop::collection::vec(json_entry(), 1..8)) {
+            let sessions = Arc::new(RwLock::new(HashMap::new()));
+            block_on(async {
+                let storage = Storage::in_memory();
+                for entry in &entries {
+                    ClaudeCodeAdapter::process_entry(entry, &storage, &EventBus::new(), &sessions).await;
+                }
+            });
+            for session in block_on(sessions.read()).values() {
+                prop_assert!(session.tokens_input >= 0 && session.tokens_output >= 0);
+                prop_assert!(session.estimated_cost.is_finite() && session.estimated_cost >= 0.0);
+            }
+        }
+
+        #[test]
+        fn test_jsonl_survives_corrupt_lines(
+            entries in prop::collection::vec(json_entry(), 0..8),
+            cut in any::<prop::sample::Index>(),
+            garbage in prop::collection::vec(any::<u8>(), 0..128),
+        ) {
+            // Good lines, then one cut off mid-write, then random bytes
+            let mut bytes = Vec::new();
+            for entry in &entries {
+                bytes.extend(serde_json::to_vec(entry).unwrap());
+                bytes.push(b'\n');
+            }
+            let partial = serde_json::to_vec(&serde_json::json!({"type": "user", "cwd": "/work"})).unwrap();
+            bytes.extend(&partial[..cut.index(partial.len())]);
+            bytes.push(b'\n');
+            bytes.extend(&garbage);
+
+            let (parsed, skipped) = parse_jsonl(&bytes);
+            prop_assert_eq!(&parsed[..entries.len()], &entries[..]);
+            prop_assert!(skipped >= 1);
+        }
+
+        #[test]
+        fn test_history_survives_corrupt_lines(
+            entries in prop::collection::vec(json_entry(), 0..16),
+            garbage in prop::collection::vec(any::<u8>(), 0..256),
+        ) {
+            let dir = tempfile::tempdir().unwrap();
+            let mut bytes = garbage;
+            for entry in &entries {
+                bytes.push(b'\n');
+                bytes.extend(serde_json::to_vec(entry).unwrap());
+            }
+            std::fs::write(dir.path().join("history.jsonl"), &bytes).unwrap();
+
+            let config = Config { claude_home: dir.path().to_path_buf(), ..Config::default() };
+            let adapter = ClaudeCodeAdapter::new(&config, EventBus::new(), Storage::in_memory(), ProcessWatcher::new(1));
+            let sessions = block_on(adapter.parse_history()).unwrap();
+            let projects: std::collections::HashSet<&str> = entries
+                .iter()
+                .filter_map(|e| e.get("project").and_then(|v| v.as_str()))
+                .filter(|p| !p.is_empty())
+                .collect();
+            prop_assert!(sessions.len() <= projects.len());
+        }
+    }
 }
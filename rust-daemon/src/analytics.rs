@@ -9,7 +9,8 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
-use crate::models::{SessionEvent, EventType};
+use crate::models::{MemoryEntry, SessionEvent, EventType};
+use crate::storage::Storage;
 
 // ============================================================================
 // Exit Detection System (Ralph-inspired)
@@ -814,101 +815,54 @@ pub struct AnalyticsStatus {
 // Memory Persistence (Auto-Claude inspired)
 // ============================================================================
 
-/// Memory entry for cross-session persistence.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryEntry {
-    pub key: String,
-    pub value: serde_json::Value,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    pub session_id: Option<String>,
-    pub tags: Vec<String>,
-}
-
-/// Memory store for persistent insights across sessions.
-#[derive(Debug)]
+/// Memory store for persistent insights across sessions, kept in the
+/// session database so agents and scripts can share it.
+#[derive(Clone)]
 pub struct MemoryStore {
-    entries: Arc<RwLock<HashMap<String, MemoryEntry>>>,
-    storage_path: Option<PathBuf>,
+    storage: Storage,
 }
 
 impl MemoryStore {
-    pub fn new() -> Self {
-        Self {
-            entries: Arc::new(RwLock::new(HashMap::new())),
-            storage_path: None,
-        }
+    pub fn new(storage: Storage) -> Self {
+        Self { storage }
     }
 
-    /// Set persistent storage path.
-    pub fn set_storage_path(&mut self, path: PathBuf) {
-        self.storage_path = Some(path);
-    }
-
-    /// Write a memory entry.
-    pub async fn write(&self, key: &str, value: serde_json::Value, session_id: Option<&str>, tags: Vec<String>) {
-        let mut entries = self.entries.write().await;
+    /// Write a memory entry. The creation time and originating session of
+    /// an existing key are kept; value and tags are replaced.
+    pub async fn write(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+        session_id: Option<&str>,
+        tags: Vec<String>,
+    ) -> anyhow::Result<MemoryEntry> {
         let now = Utc::now();
-
-        let entry = entries.entry(key.to_string()).or_insert_with(|| MemoryEntry {
+        let entry = MemoryEntry {
             key: key.to_string(),
-            value: serde_json::Value::Null,
+            value,
             created_at: now,
             updated_at: now,
             session_id: session_id.map(|s| s.to_string()),
-            tags: vec![],
-        });
+            tags,
+        };
+        self.storage.upsert_memory(&entry).await?;
 
-        entry.value = value;
-        entry.updated_at = now;
-        entry.tags = tags;
+        Ok(self.storage.get_memory(key).await?.unwrap_or(entry))
     }
 
     /// Read a memory entry.
-    pub async fn read(&self, key: &str) -> Option<MemoryEntry> {
-        let entries = self.entries.read().await;
-        entries.get(key).cloned()
+    pub async fn read(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        self.storage.get_memory(key).await
     }
 
-    /// List all memory entries.
-    pub async fn list(&self) -> Vec<MemoryEntry> {
-        let entries = self.entries.read().await;
-        entries.values().cloned().collect()
+    /// List memory entries, optionally only those carrying `tag`.
+    pub async fn list(&self, tag: Option<&str>) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.storage.list_memory(tag).await
     }
 
     /// Delete a memory entry.
-    pub async fn delete(&self, key: &str) -> bool {
-        let mut entries = self.entries.write().await;
-        entries.remove(key).is_some()
-    }
-
-    /// Save to persistent storage.
-    pub async fn persist(&self) -> anyhow::Result<()> {
-        if let Some(ref path) = self.storage_path {
-            let entries = self.entries.read().await;
-            let json = serde_json::to_string_pretty(&*entries)?;
-            tokio::fs::write(path, json).await?;
-        }
-        Ok(())
-    }
-
-    /// Load from persistent storage.
-    pub async fn load(&self) -> anyhow::Result<()> {
-        if let Some(ref path) = self.storage_path {
-            if path.exists() {
-                let json = tokio::fs::read_to_string(path).await?;
-                let loaded: HashMap<String, MemoryEntry> = serde_json::from_str(&json)?;
-                let mut entries = self.entries.write().await;
-                *entries = loaded;
-            }
-        }
-        Ok(())
-    }
-}
-
-impl Default for MemoryStore {
-    fn default() -> Self {
-        Self::new()
+    pub async fn delete(&self, key: &str) -> anyhow::Result<bool> {
+        self.storage.delete_memory(key).await
     }
 }
 
@@ -1068,20 +1022,34 @@ mod tests {
 
     #[tokio::test]
     async fn test_memory_store_basic() {
-        let store = MemoryStore::new();
+        let store = MemoryStore::new(Storage::in_memory());
 
-        store.write("key1", serde_json::json!("value1"), None, vec!["tag1".to_string()]).await;
-        store.write("key2", serde_json::json!("value2"), Some("session1"), vec![]).await;
+        store.write("key1", serde_json::json!("value1"), None, vec!["tag1".to_string()]).await.unwrap();
+        store.write("key2", serde_json::json!("value2"), Some("session1"), vec![]).await.unwrap();
 
-        let entry = store.read("key1").await;
+        let entry = store.read("key1").await.unwrap();
         assert!(entry.is_some());
         assert_eq!(entry.unwrap().value, serde_json::json!("value1"));
 
-        let list = store.list().await;
+        let list = store.list(None).await.unwrap();
         assert_eq!(list.len(), 2);
+        assert_eq!(store.list(Some("tag1")).await.unwrap().len(), 1);
+
+        assert!(store.delete("key1").await.unwrap());
+        assert!(store.read("key1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_overwrite_keeps_origin() {
+        let store = MemoryStore::new(Storage::in_memory());
+
+        let first = store.write("k", serde_json::json!(1), Some("s1"), vec!["a".to_string()]).await.unwrap();
+        let second = store.write("k", serde_json::json!(2), Some("s2"), vec!["b".to_string()]).await.unwrap();
 
-        assert!(store.delete("key1").await);
-        assert!(store.read("key1").await.is_none());
+        assert_eq!(second.value, serde_json::json!(2));
+        assert_eq!(second.session_id.as_deref(), Some("s1"));
+        assert_eq!(second.created_at, first.created_at);
+        assert_eq!(second.tags, vec!["b".to_string()]);
     }
 
     #[test]
@@ -0,0 +1,359 @@
+//! Bursts of errors, tool calls or token spend within a session.
+//!
+//! Each session's events are counted in windows of `window_secs`. A window
+//! whose errors, tool calls or tokens are more than `threshold` standard
+//! deviations above the mean of the session's previous `history` windows
+//! (quiet windows count as zero) is a burst: the session gets an Anomaly
+//! event, which rules can act on (`event_types: ["anomaly"]`) and the TUI
+//! shows, and with `notify` a notification. Small numbers are never a
+//! burst, however unusual: a window needs at least the metric's minimum.
+//! Each metric is flagged at most once per `cooldown_secs` per session.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use tracing::{info, warn};
+
+use crate::events::{EventBus, EventFilter};
+use crate::humanize;
+use crate::models::{EventType, SessionEvent};
+use crate::notifications::Notifier;
+use crate::storage::Storage;
+
+/// Anomaly detection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// Whether sessions are watched for bursts
+    pub enabled: bool,
+
+    /// Seconds of each counting window
+    pub window_secs: u64,
+
+    /// Previous windows the mean and standard deviation are taken over
+    pub history: usize,
+
+    /// Windows a session needs before it can have a burst
+    pub min_history: usize,
+
+    /// Standard deviations above the mean that make a burst
+    pub threshold: f64,
+
+    /// Least errors in a window to be a burst
+    pub min_errors: f64,
+
+    /// Least tool calls in a window to be a burst
+    pub min_tool_calls: f64,
+
+    /// Least tokens in a window to be a burst
+    pub min_tokens: f64,
+
+    /// Seconds before the same metric of a session is flagged again
+    pub cooldown_secs: u64,
+
+    /// Also send a notification for each burst
+    pub notify: bool,
+
+    /// Notification channel; the desktop when unset
+    pub channel: Option<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 60,
+            history: 30,
+            min_history: 5,
+            threshold: 3.0,
+            min_errors: 5.0,
+            min_tool_calls: 30.0,
+            min_tokens: 200_000.0,
+            cooldown_secs: 600,
+            notify: false,
+            channel: None,
+        }
+    }
+}
+
+/// What a burst is of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Errors,
+    ToolCalls,
+    Tokens,
+}
+
+impl Metric {
+    const ALL: [Metric; 3] = [Metric::Errors, Metric::ToolCalls, Metric::Tokens];
+
+    /// How much of this metric `event` adds.
+    fn of(self, event: &SessionEvent) -> f64 {
+        match self {
+            Metric::Errors => matches!(event.event_type, EventType::Error | EventType::ApiError) as u8 as f64,
+            Metric::ToolCalls => matches!(event.event_type, EventType::ToolStart | EventType::ToolExecuted) as u8 as f64,
+            Metric::Tokens => (event.tokens_input.unwrap_or(0) + event.tokens_output.unwrap_or(0)) as f64,
+        }
+    }
+
+    fn minimum(self, config: &AnomalyConfig) -> f64 {
+        match self {
+            Metric::Errors => config.min_errors,
+            Metric::ToolCalls => config.min_tool_calls,
+            Metric::Tokens => config.min_tokens,
+        }
+    }
+
+    fn number(self, value: f64) -> String {
+        match self {
+            Metric::Tokens => humanize::tokens(value as i64),
+            Metric::Errors | Metric::ToolCalls => format!("{:.0}", value),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Errors => "errors",
+            Metric::ToolCalls => "tool calls",
+            Metric::Tokens => "tokens",
+        }
+    }
+}
+
+/// A window well above the session's usual.
+#[derive(Debug, Clone, Serialize)]
+pub struct Anomaly {
+    pub session_id: String,
+    pub metric: Metric,
+    /// The metric in the window
+    pub value: f64,
+    /// Mean and standard deviation of the previous windows
+    pub mean: f64,
+    pub stddev: f64,
+    pub window_secs: u64,
+}
+
+impl Anomaly {
+    pub fn describe(&self) -> String {
+        format!(
+            "Burst of {} {} within {}s (usually {})",
+            self.metric.number(self.value),
+            self.metric.name(),
+            self.window_secs,
+            self.metric.number(self.mean)
+        )
+    }
+}
+
+/// One session's windows.
+#[derive(Default)]
+struct SessionWindows {
+    /// Index of the current window (seconds since the epoch / window)
+    current: i64,
+    /// Metrics of the current window
+    counts: HashMap<Metric, f64>,
+    /// Metrics of earlier windows, oldest first
+    past: VecDeque<HashMap<Metric, f64>>,
+    /// When each metric was last flagged
+    flagged: HashMap<Metric, DateTime<Utc>>,
+}
+
+/// Rolling per-session statistics, fed one event at a time.
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    sessions: HashMap<String, SessionWindows>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self { config, sessions: HashMap::new() }
+    }
+
+    /// Count `event`, returning the bursts it completes.
+    pub fn observe(&mut self, event: &SessionEvent) -> Vec<Anomaly> {
+        if event.event_type == EventType::Anomaly {
+            return Vec::new();
+        }
+        if event.event_type == EventType::SessionEnd {
+            self.sessions.remove(&event.session_id);
+            return Vec::new();
+        }
+        let window_secs = self.config.window_secs.max(1);
+        let window = event.timestamp.timestamp().div_euclid(window_secs as i64);
+        let history = self.config.history.max(1);
+        let session = self.sessions.entry(event.session_id.clone()).or_insert_with(|| SessionWindows {
+            current: window,
+            ..SessionWindows::default()
+        });
+        // Late events count towards the current window
+        if window > session.current {
+            let closed = std::mem::take(&mut session.counts);
+            session.past.push_back(closed);
+            // Windows without events
+            let quiet = (window - session.current - 1).min(history as i64);
+            session.past.extend((0..quiet).map(|_| HashMap::new()));
+            while session.past.len() > history {
+                session.past.pop_front();
+            }
+            session.current = window;
+        }
+
+        let mut anomalies = Vec::new();
+        for metric in Metric::ALL {
+            let added = metric.of(event);
+            if added == 0.0 {
+                continue;
+            }
+            let value = session.counts.entry(metric).or_default();
+            *value += added;
+            let value = *value;
+            if session.past.len() < self.config.min_history || value < metric.minimum(&self.config) {
+                continue;
+            }
+            let cooldown = Duration::seconds(self.config.cooldown_secs as i64);
+            if session.flagged.get(&metric).is_some_and(|at| event.timestamp - *at < cooldown) {
+                continue;
+            }
+            let (mean, stddev) = mean_stddev(session.past.iter().map(|w| w.get(&metric).copied().unwrap_or(0.0)));
+            if value > mean + self.config.threshold * stddev {
+                session.flagged.insert(metric, event.timestamp);
+                anomalies.push(Anomaly {
+                    session_id: event.session_id.clone(),
+                    metric,
+                    value,
+                    mean,
+                    stddev,
+                    window_secs,
+                });
+            }
+        }
+        anomalies
+    }
+
+    /// Forget sessions without events since `before`.
+    pub fn prune(&mut self, before: DateTime<Utc>) {
+        let window = before.timestamp().div_euclid(self.config.window_secs.max(1) as i64);
+        self.sessions.retain(|_, s| s.current >= window);
+    }
+}
+
+fn mean_stddev(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    (mean, variance.sqrt())
+}
+
+/// Watches the event bus for bursts.
+pub struct AnomalyWatcher {
+    detector: AnomalyDetector,
+    storage: Storage,
+    event_bus: EventBus,
+    notifier: Notifier,
+}
+
+impl AnomalyWatcher {
+    pub fn new(config: AnomalyConfig, storage: Storage, event_bus: EventBus, notifier: Notifier) -> Self {
+        Self {
+            detector: AnomalyDetector::new(config),
+            storage,
+            event_bus,
+            notifier,
+        }
+    }
+
+    /// Follow events until the daemon stops.
+    pub async fn run(mut self) {
+        let mut events = self.event_bus.subscribe_filtered("Anomaly detection", EventFilter::all());
+        let window = Duration::seconds(self.detector.config.window_secs.max(1) as i64);
+        let mut pruned_at = Utc::now();
+        while let Some(event) = events.recv().await {
+            // Transcripts read again from the start replay old events;
+            // only what is happening now is judged
+            let now = Utc::now();
+            if event.timestamp < now - window {
+                continue;
+            }
+            for anomaly in self.detector.observe(&event) {
+                if let Err(e) = self.record(&anomaly, &event).await {
+                    warn!("Failed to record anomaly in session {}: {}", anomaly.session_id, e);
+                }
+            }
+            if now - pruned_at > window * self.detector.config.history as i32 {
+                self.detector.prune(now - window * self.detector.config.history as i32);
+                pruned_at = now;
+            }
+        }
+    }
+
+    async fn record(&self, anomaly: &Anomaly, cause: &SessionEvent) -> Result<()> {
+        let description = anomaly.describe();
+        info!("Session {}: {}", anomaly.session_id, description);
+        let mut event = SessionEvent::new(&anomaly.session_id, EventType::Anomaly, cause.agent_type);
+        event.content = Some(description.clone());
+        event.working_directory = cause.working_directory.clone();
+        event.raw_data = Some(json!({ "source": "anomaly", "anomaly": anomaly }));
+        self.storage.record_event(event, &self.event_bus).await?;
+
+        if self.detector.config.notify {
+            let project = cause.working_directory.as_deref().and_then(|d| d.rsplit('/').next()).unwrap_or("a session");
+            let title = format!("Unusual activity in {}", project);
+            self.notifier.send(self.detector.config.channel.as_deref(), &title, &description).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_flags_bursts_above_the_session_baseline() {
+        let start = DateTime::parse_from_rfc3339("2026-03-14T12:00:00Z").unwrap().with_timezone(&Utc);
+        let event = |event_type: EventType, secs: i64| {
+            let mut e = SessionEvent::new("s1", event_type, AgentType::ClaudeCode);
+            e.timestamp = start + Duration::seconds(secs);
+            e
+        };
+        let mut detector = AnomalyDetector::new(AnomalyConfig::default());
+
+        // Ten minutes of a steady two or three tool calls a minute, no errors
+        let mut anomalies = Vec::new();
+        for minute in 0..10 {
+            for call in 0..(2 + minute % 2) {
+                anomalies.extend(detector.observe(&event(EventType::ToolStart, minute * 60 + call)));
+            }
+        }
+        assert!(anomalies.is_empty());
+
+        // Then forty tool calls and six API errors in one minute
+        for i in 0..40 {
+            anomalies.extend(detector.observe(&event(EventType::ToolStart, 600 + i)));
+        }
+        for i in 0..6 {
+            anomalies.extend(detector.observe(&event(EventType::ApiError, 640 + i)));
+        }
+        let metrics: Vec<Metric> = anomalies.iter().map(|a| a.metric).collect();
+        assert_eq!(metrics, [Metric::ToolCalls, Metric::Errors]);
+        assert_eq!(anomalies[0].value, 30.0);
+        assert_eq!(anomalies[1].describe(), "Burst of 5 errors within 60s (usually 0)");
+
+        // Flagged once per cooldown, and a new session has no baseline yet
+        for i in 0..20 {
+            assert!(detector.observe(&event(EventType::ToolStart, 720 + i)).is_empty());
+        }
+        let mut other = event(EventType::Error, 650);
+        other.session_id = "s2".to_string();
+        for _ in 0..10 {
+            assert!(detector.observe(&other).is_empty());
+        }
+    }
+}
@@ -6,6 +6,7 @@ use axum::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         Path, Query, State,
     },
+    http::{header, Method},
     response::{Html, IntoResponse},
     routing::get,
     Json, Router,
@@ -13,47 +14,120 @@ use axum::{
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
 use std::net::SocketAddr;
-use std::path::PathBuf;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::broadcast;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::{broadcast, RwLock};
 use tower_http::cors::CorsLayer;
-use tracing::{error, info, debug};
+use tracing::{error, info, debug, warn};
 
+use crate::adapters::AdapterRegistry;
+use crate::capabilities::{self, ApiKeys};
+use crate::commands::CommandTracker;
+use crate::config::Config;
+use crate::context::{self, ContextConfig};
+use crate::events::{EventBus, EventFilter};
+use crate::highlight;
+use crate::hooks;
+use crate::markdown;
+use crate::models::{describe_compaction, AgentType, EventType, Session, SessionEvent, SessionGroup};
+use crate::policy::{self, HookDecision, PolicyEngine};
+use crate::preview;
+use crate::refresh::RefreshConfig;
+use crate::rules::{AutomationRule, RulesEngine};
+use crate::sockets::{self, PeerPolicy, SocketConfig};
+use crate::spool::MAX_PAYLOAD_BYTES;
+use crate::sso::{self, Sso};
 use crate::storage::Storage;
-use crate::integrations::{IntegrationState, create_integration_router, openapi_handler};
+use crate::subscribe;
+use crate::trash::Trash;
+use crate::integrations::{self, IntegrationState, create_integration_router, openapi_handler};
+use crate::timefmt;
+
+/// Longest IPC request line: a hook payload of up to [`MAX_PAYLOAD_BYTES`]
+/// plus the message around it.
+const MAX_REQUEST_BYTES: u64 = 2 * MAX_PAYLOAD_BYTES;
+
+/// Wait after an event before updating the dashboard, so a burst of events
+/// makes one update.
+const PUSH_DELAY: Duration = Duration::from_millis(500);
 
 /// IPC Server using Unix sockets.
+#[derive(Clone)]
 pub struct IpcServer {
     socket_path: PathBuf,
     storage: Storage,
+    policy: PolicyEngine,
+    adapters: Arc<RwLock<AdapterRegistry>>,
+    rules: RulesEngine,
+    context: ContextConfig,
+    commands: CommandTracker,
+    events: EventBus,
+    socket: SocketConfig,
+    /// Unlocks the write actions; None when they are turned off
+    write_token: Option<String>,
+    /// Where cleared and pruned sessions go
+    trash: Trash,
+}
+
+/// The daemon's parts the IPC server answers from.
+pub struct IpcServices {
+    pub storage: Storage,
+    pub policy: PolicyEngine,
+    pub adapters: Arc<RwLock<AdapterRegistry>>,
+    pub rules: RulesEngine,
+    pub commands: CommandTracker,
+    pub events: EventBus,
+    pub trash: Trash,
 }
 
 impl IpcServer {
-    /// Create a new IPC server.
-    pub fn new(socket_path: &PathBuf, storage: Storage) -> Self {
-        Self {
-            socket_path: socket_path.clone(),
+    /// Create a new IPC server, creating the write token if the config
+    /// turns write actions on.
+    pub fn new(config: &Config, services: IpcServices) -> Result<Self> {
+        let IpcServices { storage, policy, adapters, rules, commands, events, trash } = services;
+        let write_token = config
+            .socket
+            .write_actions
+            .then(|| capabilities::load_or_create_token(&config.data_dir))
+            .transpose()?;
+        Ok(Self {
+            socket_path: config.socket_path.clone(),
             storage,
-        }
+            policy,
+            adapters,
+            rules,
+            context: config.context.clone(),
+            commands,
+            events,
+            socket: config.socket.clone(),
+            write_token,
+            trash,
+        })
     }
 
     /// Run the IPC server.
     pub async fn run(&self) -> Result<()> {
-        // Remove existing socket
-        if self.socket_path.exists() {
-            std::fs::remove_file(&self.socket_path)?;
-        }
-
-        let listener = UnixListener::bind(&self.socket_path)?;
+        let listener = sockets::bind(&self.socket_path)?;
+        let peers = PeerPolicy::new(&self.socket_path, &self.socket.allowed_uids)?;
         info!("IPC server listening at {:?}", self.socket_path);
 
         loop {
             match listener.accept().await {
                 Ok((stream, _)) => {
-                    let storage = self.storage.clone();
+                    let uid = match peers.check(&stream) {
+                        Ok(uid) => uid,
+                        Err(e) => {
+                            warn!("Rejected IPC connection: {}", e);
+                            continue;
+                        }
+                    };
+                    let server = self.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, storage).await {
+                        if let Err(e) = server.handle_client(stream, uid).await {
                             error!("Client error: {}", e);
                         }
                     });
@@ -64,41 +138,489 @@ impl IpcServer {
             }
         }
     }
-}
 
-async fn handle_client(stream: UnixStream, storage: Storage) -> Result<()> {
-    let (reader, mut writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
+    /// Answer one client's requests until it disconnects. `uid` is the
+    /// user the client runs as.
+    async fn handle_client(self, stream: UnixStream, uid: u32) -> Result<()> {
+        let IpcServer { storage, policy, adapters, rules, context, commands, events, write_token, trash, .. } = self;
+        // Granted by a hello carrying the write token
+        let mut can_write = false;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
 
-    while reader.read_line(&mut line).await? > 0 {
-        let request: serde_json::Value = serde_json::from_str(&line)?;
-        let action = request.get("action").and_then(|v| v.as_str()).unwrap_or("");
+        while (&mut reader).take(MAX_REQUEST_BYTES).read_line(&mut line).await? > 0 {
+            let request: serde_json::Value = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    let response = serde_json::json!({ "error": format!("Invalid request: {}", e) });
+                    writer.write_all((serde_json::to_string(&response)? + "\n").as_bytes()).await?;
+                    // The rest of a line cut off at the limit can't be told apart from the next request
+                    if !line.ends_with('\n') {
+                        break;
+                    }
+                    line.clear();
+                    continue;
+                }
+            };
+            let action = request.get("action").and_then(|v| v.as_str()).unwrap_or("");
 
-        let response = match action {
-            "get_sessions" => {
-                let sessions = storage.get_active_sessions(100).await?;
-                serde_json::json!({ "sessions": sessions })
+            // The connection becomes a stream of events until the client leaves
+            if action == "subscribe" {
+                let filter: EventFilter = match request.get("filter").cloned().map(serde_json::from_value).transpose() {
+                    Ok(filter) => filter.unwrap_or_default(),
+                    Err(e) => {
+                        let response = serde_json::json!({ "error": format!("Invalid filter: {}", e) });
+                        writer.write_all((serde_json::to_string(&response)? + "\n").as_bytes()).await?;
+                        return Ok(());
+                    }
+                };
+                let full = request.get("full").and_then(|v| v.as_bool()).unwrap_or(false);
+                return subscribe::serve(&mut writer, events.subscribe_filtered("IPC subscriber", filter), full).await;
             }
-            "get_metrics" => {
-                let metrics = storage.get_summary_metrics(24).await?;
-                serde_json::json!({ "metrics": metrics })
+
+            let response = match action {
+                "hello" => {
+                    let token = request.get("token").and_then(|v| v.as_str());
+                    match (token, write_token.as_deref()) {
+                        (None, _) => serde_json::json!({ "capabilities": ["read"] }),
+                        (Some(token), Some(expected)) if token == expected => {
+                            can_write = true;
+                            serde_json::json!({ "capabilities": ["read", "write"] })
+                        }
+                        (Some(_), Some(_)) => serde_json::json!({ "error": "Invalid write token" }),
+                        (Some(_), None) => serde_json::json!({ "error": "Write actions are turned off (socket.write_actions)" }),
+                    }
+                }
+                _ if capabilities::WRITE_ACTIONS.contains(&action) => {
+                    match capabilities::perform(&storage, &trash, uid, action, &request, can_write).await {
+                        Ok(result) => result,
+                        Err(e) => serde_json::json!({ "error": format!("{:#}", e) }),
+                    }
+                }
+                "get_sessions" => {
+                    let sessions = storage.get_active_sessions(100).await?;
+                    serde_json::json!({ "sessions": sessions })
+                }
+                "get_metrics" => {
+                    let metrics = storage.get_summary_metrics(24).await?;
+                    serde_json::json!({ "metrics": metrics })
+                }
+                "get_events" => {
+                    let events = storage.get_recent_events(50).await?;
+                    serde_json::json!({ "events": events })
+                }
+                "hook_event" if request.pointer(&format!("/data/{}", hooks::PROBE_FIELD)).is_some() => {
+                    serde_json::json!({ "probe": { "version": env!("CARGO_PKG_VERSION") } })
+                }
+                "hook_event" => {
+                    let decision = evaluate_hook(&request, &storage, &policy, &events).await;
+                    let event_type = request.get("event_type").and_then(|v| v.as_str()).unwrap_or("");
+                    if let Some(error) = request.pointer("/data/payload_error").and_then(|v| v.as_str()) {
+                        warn!("Malformed {} hook payload: {}", event_type, error);
+                    }
+                    if event_type == "PreCompact" {
+                        record_compaction(&request, &storage, &events).await;
+                    }
+                    if event_type == "PostToolUse" {
+                        record_tool_use(&request, &storage, &events).await;
+                    }
+                    if event_type == "SessionStart" && context.enabled {
+                        let context = session_start_context(&request, &storage, &context).await;
+                        serde_json::json!({ "decision": decision, "context": context })
+                    } else {
+                        serde_json::json!({ "decision": decision })
+                    }
+                }
+                "get_adapters" => {
+                    let health = adapters.read().await.health();
+                    serde_json::json!({ "adapters": health })
+                }
+                "adapter_control" if !can_write => {
+                    serde_json::json!({
+                        "error": "'adapter_control' needs the write capability: send a hello with the daemon's write token first"
+                    })
+                }
+                "adapter_control" => {
+                    let name = request.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    let command = request.get("command").and_then(|v| v.as_str()).unwrap_or("");
+                    let mut registry = adapters.write().await;
+                    let result = match command {
+                        "start" => registry.start_adapter(name).await,
+                        "stop" => registry.stop_adapter(name).await,
+                        "restart" => registry.restart_adapter(name).await,
+                        _ => Err(anyhow::anyhow!("Unknown adapter command: {}", command)),
+                    };
+                    match result {
+                        Ok(()) => serde_json::json!({ "adapters": registry.health() }),
+                        Err(e) => serde_json::json!({ "error": e.to_string() }),
+                    }
+                }
+                "get_rules" => {
+                    let limit = request.get("log_limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+                    serde_json::json!({ "rules": rules.list().await, "log": rules.log(limit).await })
+                }
+                "rule_upsert" | "rule_delete" if !can_write => {
+                    serde_json::json!({
+                        "error": format!("'{}' needs the write capability: send a hello with the daemon's write token first", action)
+                    })
+                }
+                "rule_upsert" => {
+                    let rule = request.get("rule").cloned().unwrap_or_default();
+                    let result = match serde_json::from_value::<AutomationRule>(rule) {
+                        Ok(rule) => rules.upsert(rule).await,
+                        Err(e) => Err(anyhow::anyhow!("Invalid rule: {}", e)),
+                    };
+                    match result {
+                        Ok(rule) => serde_json::json!({ "rule": rule }),
+                        Err(e) => serde_json::json!({ "error": format!("{:#}", e) }),
+                    }
+                }
+                "rule_delete" => {
+                    let id = request.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                    match rules.delete(id).await {
+                        Ok(deleted) => serde_json::json!({ "deleted": deleted }),
+                        Err(e) => serde_json::json!({ "error": e.to_string() }),
+                    }
+                }
+                "get_commands" => {
+                    let session_id = request.get("session_id").and_then(|v| v.as_str()).unwrap_or("");
+                    serde_json::json!({ "commands": commands.running(session_id) })
+                }
+                _ => {
+                    serde_json::json!({ "error": format!("Unknown action: {}", action) })
+                }
+            };
+
+            let response_str = serde_json::to_string(&response)? + "\n";
+            writer.write_all(response_str.as_bytes()).await?;
+            line.clear();
+        }
+
+        Ok(())
+    }
+}
+
+/// Send one request to the daemon's IPC socket and return its response.
+pub async fn ipc_request(socket_path: &FsPath, request: &serde_json::Value) -> Result<serde_json::Value> {
+    ipc_exchange(socket_path, None, request).await
+}
+
+/// Send a write action, on a connection that first says hello with the
+/// write `token`.
+pub async fn ipc_write_request(socket_path: &FsPath, token: &str, request: &serde_json::Value) -> Result<serde_json::Value> {
+    ipc_exchange(socket_path, Some(token), request).await
+}
+
+async fn ipc_exchange(socket_path: &FsPath, token: Option<&str>, request: &serde_json::Value) -> Result<serde_json::Value> {
+    let hello = token.map(|token| serde_json::json!({ "action": "hello", "token": token }));
+    let exchange = async {
+        let stream = UnixStream::connect(socket_path).await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut response = serde_json::Value::Null;
+        for request in hello.iter().chain([request]) {
+            writer.write_all((serde_json::to_string(request)? + "\n").as_bytes()).await?;
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+            response = serde_json::from_str(&line)?;
+            if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+                anyhow::bail!("{}", error);
             }
-            "get_events" => {
-                let events = storage.get_recent_events(50).await?;
-                serde_json::json!({ "events": events })
+        }
+        Ok(response)
+    };
+
+    tokio::time::timeout(std::time::Duration::from_secs(2), exchange)
+        .await
+        .map_err(|_| anyhow::anyhow!("Daemon did not respond"))?
+}
+
+/// The `data` of a hook message, from the JSON Claude Code writes to the
+/// hook's stdin. A payload that isn't a JSON object, such as one cut off when
+/// the hook was killed, becomes an object holding the parse error, so the
+/// daemon logs it instead of acting on an empty payload.
+pub fn parse_hook_payload(payload: &[u8]) -> serde_json::Value {
+    if payload.iter().all(u8::is_ascii_whitespace) {
+        return serde_json::json!({});
+    }
+    match serde_json::from_slice::<serde_json::Value>(payload) {
+        Ok(data) if data.is_object() => data,
+        Ok(_) => serde_json::json!({ "payload_error": "payload is not a JSON object" }),
+        Err(e) => serde_json::json!({ "payload_error": e.to_string() }),
+    }
+}
+
+/// Longest string kept when a payload is shortened to fit the socket.
+const SHORTENED_STRING_CHARS: usize = 4096;
+
+/// What policy matches on, never shortened.
+const POLICY_FIELDS: &[&str] = &[
+    "/cwd",
+    "/tool_name",
+    "/tool_input/file_path",
+    "/tool_input/notebook_path",
+    "/tool_input/path",
+    "/tool_input/command",
+];
+
+/// A payload too large to send over the socket, cut down so the daemon can
+/// still decide on it: long strings other than those policy matches on are
+/// shortened. If it still doesn't fit it reads as a payload error, which
+/// blocks the hook rather than letting it through unchecked.
+pub fn shorten_hook_payload(payload: &[u8]) -> serde_json::Value {
+    let mut data = parse_hook_payload(payload);
+    if data.get("payload_error").is_some() {
+        return data;
+    }
+    shorten_strings(&mut data, "");
+    let size = serde_json::to_vec(&data).map(|bytes| bytes.len() as u64).unwrap_or(u64::MAX);
+    if size > MAX_PAYLOAD_BYTES {
+        return serde_json::json!({ "payload_error": "payload too large to send to the daemon" });
+    }
+    data
+}
+
+fn shorten_strings(value: &mut serde_json::Value, pointer: &str) {
+    match value {
+        serde_json::Value::String(text) if text.len() > SHORTENED_STRING_CHARS && !POLICY_FIELDS.contains(&pointer) => {
+            *text = text.chars().take(SHORTENED_STRING_CHARS).collect();
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                shorten_strings(item, &format!("{}/{}", pointer, i));
             }
-            _ => {
-                serde_json::json!({ "error": format!("Unknown action: {}", action) })
+        }
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                shorten_strings(field, &format!("{}/{}", pointer, key));
             }
+        }
+        _ => {}
+    }
+}
+
+/// Build the context returned to a SessionStart hook, if any.
+async fn session_start_context(
+    request: &serde_json::Value,
+    storage: &Storage,
+    config: &ContextConfig,
+) -> Option<String> {
+    let data = request.get("data")?;
+    let cwd = data.get("cwd").and_then(|v| v.as_str())?;
+    let session_id = data.get("session_id").and_then(|v| v.as_str());
+
+    match context::build_session_context(storage, config, cwd, session_id).await {
+        Ok(context) => context,
+        Err(e) => {
+            error!("Failed to build session context: {}", e);
+            None
+        }
+    }
+}
+
+/// Record a PreCompact hook as a compaction event on the project's active
+/// session, at the time the hook fired.
+pub async fn record_compaction(request: &serde_json::Value, storage: &Storage, events: &EventBus) {
+    let Some(data) = request.get("data") else {
+        return;
+    };
+    let Some(cwd) = data.get("cwd").and_then(|v| v.as_str()) else {
+        return;
+    };
+    let Ok(Some(session)) = storage.get_active_session_for_project(cwd).await else {
+        return;
+    };
+
+    let trigger = data.get("trigger").and_then(|v| v.as_str());
+    let mut event = SessionEvent::new(&session.id, EventType::Compaction, AgentType::ClaudeCode);
+    event.content = Some(describe_compaction(trigger, session.context_tokens));
+    event.working_directory = Some(cwd.to_string());
+    if let Some(timestamp) = hook_timestamp(request) {
+        event.timestamp = timestamp;
+    }
+    event.raw_data = Some(serde_json::json!({
+        "source": "hook",
+        "trigger": trigger,
+        "custom_instructions": data.get("custom_instructions"),
+    }));
+    if let Err(e) = storage.record_event(event, events).await {
+        error!("Failed to record compaction: {}", e);
+    }
+}
+
+/// When the hook fired, which for a spooled hook is well before it's replayed.
+fn hook_timestamp(request: &serde_json::Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    request
+        .get("timestamp")
+        .and_then(|v| v.as_str())
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+/// Record a PostToolUse hook as a tool execution on the project's active
+/// session at the time the hook fired, with the call's input, output, exit
+/// code and duration.
+pub async fn record_tool_use(request: &serde_json::Value, storage: &Storage, events: &EventBus) {
+    let Some(data) = request.get("data") else {
+        return;
+    };
+    let Some(cwd) = data.get("cwd").and_then(|v| v.as_str()) else {
+        return;
+    };
+    let Ok(Some(session)) = storage.get_active_session_for_project(cwd).await else {
+        return;
+    };
+
+    let tool_name = data.get("tool_name").and_then(|v| v.as_str()).unwrap_or("unknown");
+    let input = data.get("tool_input").filter(|v| !v.is_null());
+    let response = data.get("tool_response");
+    let number = |keys: &[&str]| {
+        keys.iter().find_map(|key| {
+            data.get(*key)
+                .or_else(|| response.and_then(|r| r.get(*key)))
+                .and_then(|v| v.as_i64())
+        })
+    };
+
+    let mut event = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+    event.content = Some(match input.and_then(preview::tool_argument) {
+        Some(argument) => format!("{}: {}", tool_name, argument),
+        None => tool_name.to_string(),
+    });
+    event.tool_name = Some(tool_name.to_string());
+    event.working_directory = Some(cwd.to_string());
+    event.file_path = input
+        .and_then(|i| i.get("file_path").or_else(|| i.get("notebook_path")))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    event.tool_input = input.cloned();
+    // Bash answers with stdout and stderr; other tools with text or JSON
+    let output = match response {
+        Some(serde_json::Value::String(text)) => Some(text.clone()),
+        Some(r) if r.get("stdout").is_some() || r.get("stderr").is_some() => Some(
+            [r.get("stdout"), r.get("stderr")]
+                .into_iter()
+                .filter_map(|v| v.and_then(|v| v.as_str()).filter(|s| !s.is_empty()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Some(serde_json::Value::Null) | None => None,
+        Some(other) => Some(other.to_string()),
+    };
+    if let Some(output) = output {
+        event.set_tool_output(&output);
+    }
+    event.exit_code = number(&["exit_code", "exitCode"]).map(|c| c as i32);
+    event.duration_ms = number(&["duration_ms", "durationMs"]);
+    if let Some(timestamp) = hook_timestamp(request) {
+        event.timestamp = timestamp;
+    }
+    if let Err(e) = storage.record_event(event, events).await {
+        error!("Failed to record tool use: {}", e);
+    }
+}
+
+/// Evaluate a hook event against the daemon's policy.
+async fn evaluate_hook(
+    request: &serde_json::Value,
+    storage: &Storage,
+    policy: &PolicyEngine,
+    events: &EventBus,
+) -> HookDecision {
+    if !policy.is_enabled() {
+        return HookDecision::allow();
+    }
+
+    let event_type = request.get("event_type").and_then(|v| v.as_str()).unwrap_or("");
+    let data = request.get("data").cloned().unwrap_or_else(|| serde_json::json!({}));
+
+    // Budget checks need the session this hook belongs to: the active one
+    // in its directory, else the one carrying Claude's own session ID
+    let session = match data.get("cwd").and_then(|v| v.as_str()) {
+        Some(cwd) => storage.get_active_session_for_project(cwd).await.unwrap_or(None),
+        None => None,
+    };
+    let session = match (session, data.get("session_id").and_then(|v| v.as_str())) {
+        (None, Some(external_id)) => storage.get_session_by_external_id(external_id).await.unwrap_or(None),
+        (session, _) => session,
+    };
+
+    let decision = match data.get("payload_error").and_then(|v| v.as_str()) {
+        // Policy can't vouch for a tool call it can't read
+        Some(error) if policy::is_decision_event(event_type) => {
+            HookDecision::block(format!("Hook payload could not be read: {}", error))
+        }
+        _ => policy.evaluate(event_type, &data, session.as_ref()),
+    };
+    if let Some(ref reason) = decision.reason {
+        info!("Policy blocked {}: {}", event_type, reason);
+    }
+
+    if policy::is_decision_event(event_type) {
+        // Events must belong to a stored session, so a hook that beats the
+        // transcript watcher gets a placeholder session to hold its decision
+        let session = match session {
+            Some(session) => Ok(session),
+            None => hook_session(&data, storage).await,
+        };
+        let recorded = match session {
+            Ok(session) => storage
+                .record_event(policy_decision_event(&session.id, event_type, &data, &decision), events)
+                .await,
+            Err(e) => Err(e),
         };
+        if let Err(e) = recorded {
+            error!("Failed to record policy decision: {}", e);
+        }
+    }
+
+    decision
+}
+
+/// Store a session for a hook that arrived before anything else saw it,
+/// keyed by Claude's session ID so later lookups find it.
+async fn hook_session(data: &serde_json::Value, storage: &Storage) -> Result<Session> {
+    let cwd = data.get("cwd").and_then(|v| v.as_str()).unwrap_or_default();
+    let external_id = data.get("session_id").and_then(|v| v.as_str()).unwrap_or_default();
+    let mut session = Session::new(AgentType::ClaudeCode, cwd, external_id);
+    session
+        .metadata
+        .insert("source".to_string(), serde_json::Value::String("hook".to_string()));
+    storage.upsert_session(&session).await?;
+    Ok(session)
+}
+
+/// Build the audit event recorded for every policy decision.
+fn policy_decision_event(
+    session_id: &str,
+    event_type: &str,
+    data: &serde_json::Value,
+    decision: &HookDecision,
+) -> SessionEvent {
+    let tool_name = data.get("tool_name").and_then(|v| v.as_str()).map(String::from);
+    let subject = tool_name.clone().unwrap_or_else(|| event_type.to_string());
 
-        let response_str = serde_json::to_string(&response)? + "\n";
-        writer.write_all(response_str.as_bytes()).await?;
-        line.clear();
+    let mut content = format!("{} {}", decision.decision.to_string().to_uppercase(), subject);
+    if let Some(command) = data.pointer("/tool_input/command").and_then(|v| v.as_str()) {
+        content.push_str(&format!(": {}", command));
+    } else if let Some(path) = data.pointer("/tool_input/file_path").and_then(|v| v.as_str()) {
+        content.push_str(&format!(": {}", path));
+    }
+    if let Some(ref reason) = decision.reason {
+        content.push_str(&format!("\n[REASON]\n{}", reason));
     }
 
-    Ok(())
+    let mut event = SessionEvent::new(session_id, EventType::PolicyDecision, AgentType::ClaudeCode);
+    event.content = Some(content);
+    event.tool_name = tool_name;
+    event.working_directory = data.get("cwd").and_then(|v| v.as_str()).map(String::from);
+    event.tool_input = data.get("tool_input").filter(|v| !v.is_null()).cloned();
+    event.raw_data = Some(serde_json::json!({
+        "hook_event": event_type,
+        "decision": decision,
+    }));
+    event
 }
 
 /// Application state for web server.
@@ -116,6 +638,12 @@ pub struct SessionsQuery {
     limit: usize,
     #[serde(default)]
     active_only: bool,
+    tag: Option<String>,
+    /// Add `content_html` to agent responses (rendered Markdown, with
+    /// `content_format: "markdown"`) and to other events with code
+    /// (highlighted)
+    #[serde(default)]
+    highlight: bool,
 }
 
 fn default_limit() -> usize {
@@ -133,8 +661,35 @@ fn default_hours() -> i64 {
     24
 }
 
-/// Run the web server.
-pub async fn run_web_server(host: &str, port: u16, storage: Storage) -> Result<()> {
+/// Put `app` behind a login when `sso` is given, and otherwise make changes
+/// need the daemon's `write_token`. Once there are `api_keys`, API reads
+/// need a login or one of them.
+fn protect(app: Router, sso: Option<Sso>, write_token: Option<String>, api_keys: ApiKeys) -> Router {
+    let with_sso = sso.is_some();
+    let app = match sso {
+        Some(sso) => app
+            .merge(sso::router(sso.clone()))
+            .layer(axum::middleware::from_fn_with_state(sso, sso::require_login)),
+        None => app.layer(axum::middleware::from_fn_with_state(write_token, capabilities::require_write_token)),
+    };
+    let app = app.layer(axum::middleware::from_fn_with_state((api_keys, with_sso), capabilities::require_api_key));
+    // Other origins may read, but a browser won't send them changes
+    app.layer(CorsLayer::permissive().allow_methods([Method::GET, Method::HEAD]))
+}
+
+/// Run the web server, behind a login when `sso` is given. Dashboard
+/// clients get an update shortly after events arrive on `events`; while
+/// `relayed` says nothing feeds the bus, storage is polled as often as
+/// `refresh` says instead.
+pub async fn run_web_server(
+    host: &str,
+    port: u16,
+    storage: Storage,
+    events: EventBus,
+    relayed: Arc<AtomicBool>,
+    sso: Option<Sso>,
+    refresh: RefreshConfig,
+) -> Result<()> {
     // Create broadcast channel for real-time updates
     let (update_tx, _) = broadcast::channel::<String>(100);
 
@@ -144,14 +699,23 @@ pub async fn run_web_server(host: &str, port: u16, storage: Storage) -> Result<(
     };
 
     // Create integration state for the new v1 API
-    let integration_state = IntegrationState::new(storage.clone());
+    let integration_state = IntegrationState::new(storage.clone(), events.clone());
+    let write_token = integration_state.write_token.clone();
+    let api_keys = integration_state.api_keys.clone();
+    tokio::spawn(integrations::trigger_webhooks(
+        events.subscribe_filtered("Webhooks", EventFilter::all()),
+        integration_state.webhook_manager.clone(),
+    ));
     let integration_router = create_integration_router(integration_state);
 
     // Build main app router with state
     let main_router = Router::new()
         .route("/", get(index_handler))
+        .route("/projects", get(projects_page_handler))
+        .route("/timefmt.js", get(time_script_handler))
         .route("/api/sessions", get(sessions_handler))
         .route("/api/sessions/:id", get(session_handler))
+        .route("/api/sessions/:id/events", get(session_events_handler))
         .route("/api/metrics/summary", get(metrics_handler))
         .route("/api/events", get(events_handler))
         .route("/api/ws", get(websocket_handler))
@@ -159,28 +723,53 @@ pub async fn run_web_server(host: &str, port: u16, storage: Storage) -> Result<(
         .with_state(state);
 
     // Merge integration router (has its own state already applied)
-    let app = Router::new()
-        .merge(main_router)
-        .merge(integration_router)
-        .layer(CorsLayer::permissive());
+    let app = protect(Router::new().merge(main_router).merge(integration_router), sso, write_token, api_keys);
 
-    // Start periodic broadcast of updates
+    // Start broadcasting updates as events arrive, polling (backing off
+    // while nothing changes) only while no events are relayed
     let broadcast_storage = storage.clone();
     let broadcast_tx = update_tx.clone();
+    let mut dashboard_events = events.subscribe_filtered("Dashboard", EventFilter::all());
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+        let mut poller = refresh.poller(refresh.dashboard_secs);
+        let mut last: Option<serde_json::Value> = None;
+        let mut pushed_at: Option<tokio::time::Instant> = None;
         loop {
-            interval.tick().await;
-            if let Ok(sessions) = broadcast_storage.get_active_sessions(50).await {
-                if let Ok(metrics) = broadcast_storage.get_summary_metrics(24).await {
-                    let update = serde_json::json!({
-                        "type": "update",
-                        "sessions": sessions,
-                        "metrics": metrics,
-                        "timestamp": chrono::Utc::now().to_rfc3339(),
-                    });
-                    let _ = broadcast_tx.send(serde_json::to_string(&update).unwrap_or_default());
+            let polled_at = tokio::time::Instant::now()
+                + if relayed.load(Ordering::Relaxed) {
+                    Duration::from_secs_f64(refresh.max_secs)
+                } else {
+                    poller.interval()
+                };
+            tokio::select! {
+                // A burst of events makes one update
+                Some(_) = dashboard_events.recv_or_lag() => {
+                    pushed_at.get_or_insert(tokio::time::Instant::now() + PUSH_DELAY);
+                    continue;
                 }
+                _ = tokio::time::sleep_until(pushed_at.unwrap_or(polled_at)) => {}
+            }
+            pushed_at = None;
+            // Nobody is watching: leave the database alone
+            if broadcast_tx.receiver_count() == 0 {
+                last = None;
+                continue;
+            }
+            let (Ok(sessions), Ok(metrics)) = (
+                broadcast_storage.get_active_sessions(50).await,
+                broadcast_storage.get_summary_metrics(24).await,
+            ) else {
+                continue;
+            };
+            let current = serde_json::json!({ "sessions": sessions, "metrics": metrics });
+            let changed = last.as_ref() != Some(&current);
+            poller.polled(std::time::Instant::now(), changed);
+            if changed {
+                let mut update = current.clone();
+                update["type"] = serde_json::json!("update");
+                update["timestamp"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+                let _ = broadcast_tx.send(serde_json::to_string(&update).unwrap_or_default());
+                last = Some(current);
             }
         }
     });
@@ -287,15 +876,44 @@ async fn index_handler() -> Html<&'static str> {
     Html(DASHBOARD_HTML)
 }
 
+/// Project leaderboard page.
+async fn projects_page_handler() -> Html<&'static str> {
+    Html(PROJECTS_HTML)
+}
+
+/// Timestamp formatting shared by the dashboard pages, with the daemon's
+/// `timestamps` settings.
+async fn time_script_handler() -> impl IntoResponse {
+    let settings = serde_json::to_string(timefmt::config()).unwrap_or_else(|_| "{}".to_string());
+    (
+        [(header::CONTENT_TYPE, "application/javascript")],
+        format!("const TIME_SETTINGS = {};\n{}", settings, TIME_JS),
+    )
+}
+
 /// Sessions handler.
 async fn sessions_handler(
     State(state): State<AppState>,
     Query(query): Query<SessionsQuery>,
 ) -> impl IntoResponse {
-    let sessions = if query.active_only {
-        state.storage.get_active_sessions(query.limit).await
-    } else {
-        state.storage.get_recent_sessions(168, query.limit).await
+    let sessions = match query.tag {
+        // Filter before the limit so it counts tagged sessions
+        Some(ref tag) => async {
+            let tagged = state.storage.sessions_tagged(tag).await?;
+            let sessions = if query.active_only {
+                state.storage.get_active_sessions(100_000).await?
+            } else {
+                state.storage.get_recent_sessions(168, 100_000).await?
+            };
+            Ok(sessions
+                .into_iter()
+                .filter(|s| tagged.contains(&s.id))
+                .take(query.limit)
+                .collect::<Vec<_>>())
+        }
+        .await,
+        None if query.active_only => state.storage.get_active_sessions(query.limit).await,
+        None => state.storage.get_recent_sessions(168, query.limit).await,
     };
 
     match sessions {
@@ -312,8 +930,42 @@ async fn session_handler(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    // TODO: Implement get_session by ID
-    Json(serde_json::json!({ "error": "Not implemented" }))
+    match state.storage.get_session(&id).await {
+        Ok(Some(session)) => Json(serde_json::json!({ "session": session })),
+        Ok(None) => Json(serde_json::json!({ "error": "Session not found" })),
+        Err(e) => Json(serde_json::json!({ "error": e.to_string() })),
+    }
+}
+
+/// Full events for a single session (newest first).
+async fn session_events_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SessionsQuery>,
+) -> impl IntoResponse {
+    match state.storage.get_session_events(&id, query.limit).await {
+        Ok(events) => {
+            let mut rendered = serde_json::to_value(&events).unwrap_or_default();
+            if let (true, Some(values)) = (query.highlight, rendered.as_array_mut()) {
+                for (value, event) in values.iter_mut().zip(&events) {
+                    let Some(content) = event.content.as_deref() else {
+                        continue;
+                    };
+                    if event.event_type == EventType::ResponseGenerated {
+                        value["content_html"] = serde_json::json!(markdown::to_html(content));
+                        value["content_format"] = serde_json::json!("markdown");
+                    } else if highlight::has_code(content) {
+                        value["content_html"] = serde_json::json!(highlight::to_html(content));
+                    }
+                }
+            }
+            Json(serde_json::json!({
+                "events": rendered,
+                "total": events.len(),
+            }))
+        }
+        Err(e) => Json(serde_json::json!({ "error": e.to_string() })),
+    }
 }
 
 /// Metrics handler.
@@ -321,8 +973,16 @@ async fn metrics_handler(
     State(state): State<AppState>,
     Query(query): Query<MetricsQuery>,
 ) -> impl IntoResponse {
-    match state.storage.get_summary_metrics(query.hours).await {
-        Ok(metrics) => Json(serde_json::json!({ "metrics": metrics })),
+    let metrics = async {
+        let hours = Some(query.hours);
+        Ok::<_, anyhow::Error>(serde_json::json!({
+            "metrics": state.storage.get_summary_metrics(query.hours).await?,
+            "by_agent_type": state.storage.count_sessions_grouped_by(SessionGroup::AgentType, hours, false).await?,
+            "by_status": state.storage.count_sessions_grouped_by(SessionGroup::Status, hours, false).await?,
+        }))
+    };
+    match metrics.await {
+        Ok(metrics) => Json(metrics),
         Err(e) => Json(serde_json::json!({ "error": e.to_string() })),
     }
 }
@@ -381,6 +1041,10 @@ const DASHBOARD_HTML: &str = r#"
             gap: 10px;
         }
         .starfield { color: #444; font-size: 12px; }
+        .forecast { font-size: 12px; color: #666; text-align: right; }
+        .forecast b { color: var(--aurora-blue); }
+        .forecast .over-budget { color: #f74c00; }
+        .nav-link { font-size: 12px; color: var(--aurora-blue); text-decoration: none; }
         .connection-status {
             display: flex;
             align-items: center;
@@ -471,6 +1135,82 @@ const DASHBOARD_HTML: &str = r#"
             font-size: 11px;
             font-weight: 600;
         }
+        .session-item { cursor: pointer; }
+        .session-item.selected { border-color: var(--cosmic-violet); }
+        .compactions { color: #ff6ec8; }
+        .interruptions { color: #ffb000; }
+        .event-row {
+            display: flex;
+            gap: 12px;
+            padding: 6px 0;
+            font-size: 12px;
+            border-bottom: 1px solid var(--galaxy-border);
+        }
+        .event-time { color: #666; white-space: nowrap; }
+        .event-type { color: var(--cosmic-violet); width: 150px; flex-shrink: 0; }
+        .event-content { color: #bbb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+        .event-row.expandable { cursor: pointer; }
+        .event-full {
+            margin: 0 0 8px;
+            padding: 10px 12px;
+            background: rgba(0, 0, 0, 0.35);
+            border-radius: 6px;
+            color: #ccc;
+            font-size: 12px;
+            white-space: pre-wrap;
+            word-break: break-word;
+        }
+        .event-full.markdown { white-space: normal; line-height: 1.5; }
+        .event-full.markdown h1, .event-full.markdown h2, .event-full.markdown h3 {
+            margin: 10px 0 6px;
+            color: var(--cosmic-violet);
+        }
+        .event-full.markdown h1 { font-size: 16px; }
+        .event-full.markdown h2 { font-size: 14px; }
+        .event-full.markdown h3 { font-size: 13px; }
+        .event-full.markdown p { margin: 6px 0; }
+        .event-full.markdown ul, .event-full.markdown ol { margin: 6px 0; padding-left: 22px; }
+        .event-full.markdown code { color: #ffb000; }
+        .event-full.markdown pre {
+            padding: 8px 10px;
+            background: rgba(0, 0, 0, 0.4);
+            border-radius: 4px;
+            white-space: pre-wrap;
+        }
+        .event-full.markdown pre code { color: inherit; }
+        .event-full.markdown blockquote { margin: 6px 0; padding-left: 10px; border-left: 2px solid #555; color: #999; }
+        .event-full.markdown table { border-collapse: collapse; }
+        .event-full.markdown th, .event-full.markdown td { padding: 2px 8px; border: 1px solid var(--galaxy-border); }
+        .event-full .raw { margin: 0; white-space: pre-wrap; }
+        .raw-toggle { float: right; color: #666; font-size: 11px; cursor: pointer; }
+        .raw-toggle:hover { color: var(--cosmic-violet); }
+        .memory-loss {
+            margin: 10px 0;
+            padding: 8px 12px;
+            border-top: 1px dashed #ff6ec8;
+            border-bottom: 1px dashed #ff6ec8;
+            color: #ff6ec8;
+            font-size: 12px;
+        }
+        .compare-header { display: flex; justify-content: space-between; align-items: baseline; }
+        .compare-header select {
+            background: var(--nebula-dark);
+            color: var(--stellar-white);
+            border: 1px solid var(--galaxy-border);
+            border-radius: 4px;
+            font-size: 11px;
+        }
+        .compare-grid { display: grid; grid-template-columns: repeat(4, 1fr); gap: 20px; }
+        @media (max-width: 900px) { .compare-grid { grid-template-columns: repeat(2, 1fr); } }
+        .compare-metric h3 { font-size: 11px; color: #666; font-weight: 400; margin-bottom: 8px; }
+        .bar-row { font-size: 12px; margin-bottom: 6px; }
+        .bar-label { display: flex; justify-content: space-between; color: #bbb; }
+        .bar-track { height: 6px; background: rgba(122, 201, 255, 0.05); border-radius: 3px; margin-top: 2px; }
+        .bar-fill {
+            height: 100%;
+            border-radius: 3px;
+            background: linear-gradient(90deg, var(--aurora-blue), var(--cosmic-violet));
+        }
         .update-flash {
             animation: flash 0.5s ease-out;
         }
@@ -488,6 +1228,12 @@ const DASHBOARD_HTML: &str = r#"
                 Agent Monitor
                 <span class="rust-badge">🦀 Rust</span>
             </h1>
+            <div class="forecast">
+                <div id="forecast"></div>
+                <div id="trend"></div>
+            </div>
+            <a class="nav-link" href="/projects">★ Projects</a>
+            <a class="nav-link" href="javascript:toggleRelativeTime()">◷ Times</a>
             <div class="connection-status">
                 <div class="status-dot disconnected" id="ws-status"></div>
                 <span id="ws-label">Connecting...</span>
@@ -522,8 +1268,24 @@ const DASHBOARD_HTML: &str = r#"
             <h2>✧ Active Sessions</h2>
             <div id="sessions-list">Connecting to daemon...</div>
         </div>
+
+        <div class="card">
+            <div class="compare-header">
+                <h2>⚖ Agent Comparison · 30 days</h2>
+                <select id="compare-project" onchange="updateComparison()">
+                    <option value="">All projects</option>
+                </select>
+            </div>
+            <div id="compare-chart">No sessions yet</div>
+        </div>
+
+        <div class="card" id="detail-card" style="display: none">
+            <h2 id="detail-title">✦ Session</h2>
+            <div id="detail-events"></div>
+        </div>
     </div>
 
+    <script src="/timefmt.js"></script>
     <script>
         let ws;
         let reconnectAttempts = 0;
@@ -568,12 +1330,20 @@ const DASHBOARD_HTML: &str = r#"
             if (data.sessions) {
                 const list = document.getElementById('sessions-list');
                 if (data.sessions.length > 0) {
+                    data.sessions.forEach(s => { sessionsById[s.id] = s; });
                     list.innerHTML = data.sessions.map(s => {
                         const project = s.project_path.split('/').pop() || 'Unknown';
                         const statusClass = 'status-' + (s.status || 'completed');
                         const tokens = formatTokens(s.tokens_input + s.tokens_output);
+                        const selected = s.id === selectedSession ? ' selected' : '';
+                        const compactions = s.compactions
+                            ? `<span class="compactions">⟲ ${s.compactions} compaction${s.compactions === 1 ? '' : 's'}</span>`
+                            : '';
+                        const interruptions = s.interruptions
+                            ? `<span class="interruptions">⏹ ${s.interruptions} interrupted</span>`
+                            : '';
                         return `
-                            <div class="session-item">
+                            <div class="session-item${selected}" onclick="showSession('${s.id}', this)">
                                 <div class="session-header">
                                     <span class="session-name">${project}</span>
                                     <span class="session-type">${s.agent_type}</span>
@@ -583,6 +1353,8 @@ const DASHBOARD_HTML: &str = r#"
                                     <span>${tokens} tokens</span>
                                     <span>$${(s.estimated_cost || 0).toFixed(2)}</span>
                                     <span class="${statusClass}">● ${s.status}</span>
+                                    ${compactions}
+                                    ${interruptions}
                                 </div>
                             </div>
                         `;
@@ -602,6 +1374,170 @@ const DASHBOARD_HTML: &str = r#"
             }
         }
 
+        let selectedSession = null;
+        document.addEventListener('timeformatchange', () => {
+            const item = document.querySelector('.session-item.selected');
+            if (selectedSession && item) showSession(selectedSession, item);
+        });
+        const sessionsById = {};
+
+        async function showSession(id, item) {
+            selectedSession = id;
+            const session = sessionsById[id] || { project_path: id, compactions: 0 };
+            const project = session.project_path.split('/').pop() || 'Unknown';
+            document.querySelectorAll('.session-item').forEach(el => el.classList.remove('selected'));
+            item.classList.add('selected');
+            try {
+                const response = await fetch(`/api/sessions/${id}/events?limit=200&highlight=true`);
+                const body = await response.json();
+                const events = body.events || [];
+                document.getElementById('detail-card').style.display = '';
+                const compactions = session.compactions ? ` · ⟲ ${session.compactions} compactions` : '';
+                document.getElementById('detail-title').textContent = `✦ ${project} · ${events.length} events${compactions}`;
+                document.getElementById('detail-events').innerHTML = events.map(e => {
+                    const time = formatTimestamp(e.timestamp, false);
+                    const content = escapeHtml((e.content || e.tool_name || e.file_path || '').split('\n')[0]);
+                    // Newest first: everything below a compaction was summarized away
+                    if (e.event_type === 'compaction') {
+                        return `<div class="memory-loss">⟲ ${time} · ${content} — memory loss: earlier detail below was summarized away</div>`;
+                    }
+                    // Click to show the full content; the server renders
+                    // responses' Markdown and highlights code
+                    const raw = escapeHtml(e.content || '');
+                    const full = e.content_format === 'markdown'
+                        ? `<div class="event-full markdown" style="display: none"><a class="raw-toggle" onclick="toggleRaw(this)">raw</a><div class="rendered">${e.content_html}</div><pre class="raw" style="display: none">${raw}</pre></div>`
+                        : `<pre class="event-full" style="display: none">${e.content_html || raw}</pre>`;
+                    const expandable = (e.content || '').includes('\n') || e.content_html;
+                    return `
+                        <div class="event-row${expandable ? ' expandable' : ''}"${expandable ? ' onclick="toggleEvent(this)"' : ''}>
+                            <span class="event-time">${time}</span>
+                            <span class="event-type">${e.event_type}</span>
+                            <span class="event-content">${content}</span>
+                        </div>
+                        ${expandable ? full : ''}
+                    `;
+                }).join('') || '<div class="event-row">No events recorded</div>';
+            } catch (e) {
+                console.error('Session detail error:', e);
+            }
+        }
+
+        function toggleEvent(row) {
+            const full = row.nextElementSibling;
+            full.style.display = full.style.display === 'none' ? '' : 'none';
+        }
+
+        function toggleRaw(link) {
+            const rendered = link.nextElementSibling;
+            const raw = rendered.nextElementSibling;
+            const showRaw = raw.style.display === 'none';
+            raw.style.display = showRaw ? '' : 'none';
+            rendered.style.display = showRaw ? 'none' : '';
+            link.textContent = showRaw ? 'rendered' : 'raw';
+        }
+
+        function escapeHtml(text) {
+            return String(text).replace(/[&<>"']/g, c => ({
+                '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;'
+            })[c]);
+        }
+
+        async function updateForecast() {
+            try {
+                const response = await fetch('/api/v1/analytics/forecast');
+                const body = await response.json();
+                if (!body.success) return;
+                const f = body.data;
+                let text = `<b>$${f.daily_burn.toFixed(2)}</b>/day · projected <b>$${f.projected_month.toFixed(2)}</b> this month`;
+                if (f.budget != null) {
+                    const over = f.projected_month > f.budget ? ' class="over-budget"' : '';
+                    const left = f.days_until_exhausted == null ? ''
+                        : f.days_until_exhausted <= 0 ? ', exhausted'
+                        : `, ${Math.floor(f.days_until_exhausted)} days left`;
+                    text += ` · <span${over}>budget $${f.budget.toFixed(2)}${left}</span>`;
+                }
+                document.getElementById('forecast').innerHTML = text;
+            } catch (e) {
+                console.error('Forecast error:', e);
+            }
+        }
+
+        const TREND_ARROWS = { up: '↑', down: '↓', flat: '→' };
+
+        // Same wording as `agent-monitor report --compare`
+        function deltaLabel(d) {
+            const change = d.current - d.previous;
+            const sign = d.trend === 'flat' ? '±' : change > 0 ? '+' : '−';
+            if (d.unit === 'points') return `${sign}${Math.abs(change * 100).toFixed(0)}pp`;
+            if (d.unit === 'count') return `${sign}${Math.abs(change).toFixed(0)}`;
+            if (d.previous === 0) return d.current === 0 ? '±0%' : 'new';
+            if (d.trend === 'flat') return '±0%';
+            return `${sign}${Math.abs(change / d.previous * 100).toFixed(0)}%`;
+        }
+
+        async function updateTrend() {
+            try {
+                const response = await fetch('/api/v1/analytics/report?days=7&compare=previous');
+                const body = await response.json();
+                if (!body.success || !body.data.comparison) return;
+                const c = body.data.comparison;
+                const metrics = [['cost', c.cost], ['sessions', c.sessions], ['error rate', c.error_rate]]
+                    .filter(([, d]) => d);
+                document.getElementById('trend').innerHTML = '7 days vs the 7 before: ' + metrics
+                    .map(([name, d]) => `${name} <b>${TREND_ARROWS[d.trend]} ${deltaLabel(d)}</b>`)
+                    .join(' · ');
+            } catch (e) {
+                console.error('Trend error:', e);
+            }
+        }
+
+        const COMPARE_METRICS = [
+            ['Cost / session', a => a.cost_per_session, v => '$' + v.toFixed(2)],
+            ['Messages / completed', a => a.messages_per_completed, v => v.toFixed(1)],
+            ['Error rate', a => a.error_rate, v => (v * 100).toFixed(0) + '%'],
+            ['Files changed / session', a => a.files_per_session, v => v.toFixed(1)],
+        ];
+
+        async function updateComparison() {
+            const select = document.getElementById('compare-project');
+            const projects = [...new Set(Object.values(sessionsById).map(s => s.project_path))].sort();
+            projects.filter(p => ![...select.options].some(o => o.value === p)).forEach(p => {
+                select.add(new Option(p.split('/').pop() || p, p));
+            });
+
+            try {
+                const query = select.value ? `&project=${encodeURIComponent(select.value)}` : '';
+                const response = await fetch(`/api/v1/analytics/compare?days=30${query}`);
+                const body = await response.json();
+                if (!body.success) return;
+                const agents = body.data.agents;
+                const chart = document.getElementById('compare-chart');
+                if (agents.length === 0) {
+                    chart.textContent = 'No sessions yet';
+                    return;
+                }
+                chart.innerHTML = '<div class="compare-grid">' + COMPARE_METRICS.map(([title, value, format]) => {
+                    const max = Math.max(...agents.map(a => value(a) || 0)) || 1;
+                    const rows = agents.map(a => {
+                        const v = value(a);
+                        const width = v == null ? 0 : (v / max) * 100;
+                        return `
+                            <div class="bar-row">
+                                <div class="bar-label">
+                                    <span>${escapeHtml(a.agent_type)} (${a.sessions})</span>
+                                    <span>${v == null ? '–' : format(v)}</span>
+                                </div>
+                                <div class="bar-track"><div class="bar-fill" style="width: ${width}%"></div></div>
+                            </div>
+                        `;
+                    }).join('');
+                    return `<div class="compare-metric"><h3>${title}</h3>${rows}</div>`;
+                }).join('') + '</div>';
+            } catch (e) {
+                console.error('Comparison error:', e);
+            }
+        }
+
         function formatTokens(count) {
             if (count >= 1000000) return (count / 1000000).toFixed(1) + 'M';
             if (count >= 1000) return (count / 1000).toFixed(1) + 'K';
@@ -609,7 +1545,614 @@ const DASHBOARD_HTML: &str = r#"
         }
 
         connectWebSocket();
+        updateForecast();
+        setInterval(updateForecast, 60000);
+        updateTrend();
+        setInterval(updateTrend, 300000);
+        updateComparison();
+        setInterval(updateComparison, 60000);
     </script>
 </body>
 </html>
 "#;
+
+/// Project leaderboard and per-project page (`/projects?path=...`).
+const PROJECTS_HTML: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Agent Monitor ✦ Projects</title>
+    <style>
+        :root {
+            --aurora-blue: #7AC9FF;
+            --cosmic-violet: #BFA6FF;
+            --stellar-white: #FFFFFF;
+            --pulse-cyan: #00D4FF;
+            --deep-space: #0a0e14;
+            --nebula-dark: #121820;
+            --galaxy-border: #1e2832;
+        }
+        * { margin: 0; padding: 0; box-sizing: border-box; }
+        body {
+            font-family: 'SF Mono', 'Menlo', 'Monaco', 'Courier New', monospace;
+            background: var(--deep-space);
+            color: var(--stellar-white);
+            min-height: 100vh;
+            padding: 20px;
+        }
+        a { color: var(--aurora-blue); text-decoration: none; }
+        a:hover { text-decoration: underline; }
+        .container { max-width: 1400px; margin: 0 auto; }
+        .header {
+            display: flex;
+            align-items: center;
+            justify-content: space-between;
+            margin-bottom: 30px;
+            padding-bottom: 20px;
+            border-bottom: 1px solid var(--galaxy-border);
+        }
+        h1 { color: var(--aurora-blue); font-size: 24px; }
+        .nav { font-size: 12px; display: flex; gap: 15px; }
+        .card {
+            background: var(--nebula-dark);
+            border: 1px solid var(--galaxy-border);
+            border-radius: 12px;
+            padding: 20px;
+            margin-bottom: 20px;
+            position: relative;
+            overflow: hidden;
+        }
+        .card::before {
+            content: '';
+            position: absolute;
+            top: 0;
+            left: 0;
+            right: 0;
+            height: 2px;
+            background: linear-gradient(90deg, var(--aurora-blue), var(--cosmic-violet));
+        }
+        .card h2 {
+            font-size: 12px;
+            text-transform: uppercase;
+            letter-spacing: 1px;
+            margin-bottom: 15px;
+            color: var(--cosmic-violet);
+        }
+        .metric { font-size: 36px; font-weight: 700; color: var(--aurora-blue); }
+        .grid { display: grid; grid-template-columns: repeat(4, 1fr); gap: 20px; }
+        @media (max-width: 900px) { .grid { grid-template-columns: repeat(2, 1fr); } }
+        table { width: 100%; border-collapse: collapse; font-size: 12px; }
+        th {
+            text-align: left;
+            color: #666;
+            font-weight: 400;
+            padding: 6px 8px;
+            border-bottom: 1px solid var(--galaxy-border);
+        }
+        td { padding: 8px; border-bottom: 1px solid var(--galaxy-border); color: #bbb; }
+        td.num, th.num { text-align: right; }
+        .path { color: #666; font-size: 11px; }
+        .status-active { color: var(--pulse-cyan); }
+        .status-completed { color: #666; }
+        .status-crashed { color: #f74c00; }
+        .event-row {
+            display: flex;
+            gap: 12px;
+            padding: 6px 0;
+            font-size: 12px;
+            border-bottom: 1px solid var(--galaxy-border);
+        }
+        .event-time { color: #666; white-space: nowrap; }
+        .event-type { color: var(--cosmic-violet); width: 150px; flex-shrink: 0; }
+        .event-content { color: #bbb; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1 id="title">✦ Projects</h1>
+            <div class="nav">
+                <a href="/">Dashboard</a>
+                <a href="/projects">Projects</a>
+                <a href="javascript:toggleRelativeTime()">Times</a>
+            </div>
+        </div>
+        <div id="content">Loading...</div>
+    </div>
+
+    <script src="/timefmt.js"></script>
+    <script>
+        const path = new URLSearchParams(window.location.search).get('path');
+
+        function escapeHtml(text) {
+            return String(text).replace(/[&<>"']/g, c => ({
+                '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;'
+            })[c]);
+        }
+
+        function formatRate(rate) {
+            return rate == null ? '–' : (rate * 100).toFixed(0) + '%';
+        }
+
+        async function fetchData(url) {
+            const response = await fetch(url);
+            const body = await response.json();
+            if (!body.success) throw new Error(body.error || 'request failed');
+            return body.data;
+        }
+
+        async function showLeaderboard() {
+            const projects = await fetchData('/api/v1/projects?days=30');
+            if (projects.length === 0) {
+                document.getElementById('content').innerHTML = '<div class="card">No projects in the last 30 days</div>';
+                return;
+            }
+            const rows = projects.map((p, i) => `
+                <tr>
+                    <td>${i + 1}</td>
+                    <td>
+                        <a href="/projects?path=${encodeURIComponent(p.project_path)}">${escapeHtml(p.project_name)}</a>
+                        <div class="path">${escapeHtml(p.project_path)}</div>
+                    </td>
+                    <td class="num ${p.active_sessions > 0 ? 'status-active' : ''}">${p.active_sessions}</td>
+                    <td class="num">${p.sessions}</td>
+                    <td class="num">$${p.cost_7d.toFixed(2)}</td>
+                    <td class="num">${formatRate(p.success_rate)}</td>
+                    <td class="num">${timeAgo(p.last_activity_at)}</td>
+                </tr>
+            `).join('');
+            document.getElementById('content').innerHTML = `
+                <div class="card">
+                    <h2>★ Leaderboard · 30 days</h2>
+                    <table>
+                        <tr>
+                            <th>#</th><th>Project</th><th class="num">Active</th><th class="num">Sessions</th>
+                            <th class="num">7-day cost</th><th class="num">Success</th><th class="num">Last activity</th>
+                        </tr>
+                        ${rows}
+                    </table>
+                </div>
+            `;
+        }
+
+        async function showProject() {
+            const detail = await fetchData(`/api/v1/projects/detail?path=${encodeURIComponent(path)}&days=30&events=100`);
+            const p = detail.project;
+            document.getElementById('title').textContent = '✦ ' + p.project_name;
+            document.title = 'Agent Monitor ✦ ' + p.project_name;
+
+            const sessions = detail.sessions.map(s => `
+                <tr>
+                    <td>${escapeHtml(s.agent_type)}</td>
+                    <td class="status-${escapeHtml(s.status)}">${escapeHtml(s.status)}</td>
+                    <td>${escapeHtml(s.summary ? s.summary.split('\n')[0] : '')}</td>
+                    <td class="num">${s.message_count}</td>
+                    <td class="num">$${s.estimated_cost.toFixed(2)}</td>
+                    <td class="num">${timeAgo(s.last_activity_at)}</td>
+                </tr>
+            `).join('');
+            const events = detail.events.map(e => `
+                <div class="event-row">
+                    <span class="event-time">${formatTimestamp(e.timestamp, true)}</span>
+                    <span class="event-type">${escapeHtml(e.event_type)}</span>
+                    <span class="event-content">${escapeHtml(e.preview || e.tool_name || '')}</span>
+                </div>
+            `).join('');
+
+            document.getElementById('content').innerHTML = `
+                <div class="grid">
+                    <div class="card"><h2>● Active Sessions</h2><div class="metric">${p.active_sessions}</div></div>
+                    <div class="card"><h2>◎ 7-Day Cost</h2><div class="metric">$${p.cost_7d.toFixed(2)}</div></div>
+                    <div class="card"><h2>✓ Success Rate</h2><div class="metric">${formatRate(p.success_rate)}</div></div>
+                    <div class="card"><h2>◷ Last Activity</h2><div class="metric">${timeAgo(p.last_activity_at)}</div></div>
+                </div>
+                <div class="card">
+                    <h2>✧ Sessions · ${escapeHtml(p.project_path)}</h2>
+                    <table>
+                        <tr>
+                            <th>Agent</th><th>Status</th><th>Summary</th><th class="num">Messages</th>
+                            <th class="num">Cost</th><th class="num">Last activity</th>
+                        </tr>
+                        ${sessions}
+                    </table>
+                </div>
+                <div class="card">
+                    <h2>◆ Latest Events</h2>
+                    ${events || 'No events'}
+                </div>
+            `;
+        }
+
+        async function refresh() {
+            try {
+                await (path ? showProject() : showLeaderboard());
+            } catch (e) {
+                document.getElementById('content').innerHTML = `<div class="card">${escapeHtml(e.message)}</div>`;
+            }
+        }
+
+        refresh();
+        setInterval(refresh, 30000);
+        document.addEventListener('timeformatchange', refresh);
+    </script>
+</body>
+</html>
+"#;
+
+/// Served as `/timefmt.js` after `TIME_SETTINGS`, the `timestamps` config.
+/// Formats like the CLI and TUI; the relative toggle is kept per browser.
+const TIME_JS: &str = r#"
+function isRelativeTime() {
+    const saved = localStorage.getItem('relativeTimes');
+    return saved === null ? TIME_SETTINGS.relative : saved === 'true';
+}
+
+function toggleRelativeTime() {
+    localStorage.setItem('relativeTimes', String(!isRelativeTime()));
+    document.dispatchEvent(new Event('timeformatchange'));
+}
+
+function timeAgo(timestamp) {
+    const delta = Date.now() - new Date(timestamp);
+    const seconds = Math.floor(Math.abs(delta) / 1000);
+    if (seconds < 10) return 'just now';
+    let amount;
+    if (seconds < 60) amount = seconds + 's';
+    else if (seconds < 3600) amount = Math.floor(seconds / 60) + 'm';
+    else if (seconds < 86400) amount = Math.floor(seconds / 3600) + 'h';
+    else amount = Math.floor(seconds / 86400) + 'd';
+    return delta < 0 ? 'in ' + amount : amount + ' ago';
+}
+
+// The common strftime specifiers, with `-` to drop padding
+function strftime(date, format) {
+    const utc = TIME_SETTINGS.timezone === 'utc';
+    const part = name => date[(utc ? 'getUTC' : 'get') + name]();
+    const months = ['Jan', 'Feb', 'Mar', 'Apr', 'May', 'Jun', 'Jul', 'Aug', 'Sep', 'Oct', 'Nov', 'Dec'];
+    const days = ['Sun', 'Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat'];
+    const hours = part('Hours');
+    const values = {
+        Y: [part('FullYear'), 4], y: [part('FullYear') % 100, 2], m: [part('Month') + 1, 2], d: [part('Date'), 2],
+        e: [part('Date'), 0], H: [hours, 2], I: [hours % 12 || 12, 2], M: [part('Minutes'), 2], S: [part('Seconds'), 2],
+        p: [hours < 12 ? 'AM' : 'PM', 0], b: [months[part('Month')], 0], a: [days[part('Day')], 0],
+        Z: [utc ? 'UTC' : '', 0], '%': ['%', 0],
+    };
+    const expanded = format.replace(/%F/g, '%Y-%m-%d').replace(/%T/g, '%H:%M:%S').replace(/%R/g, '%H:%M');
+    return expanded.replace(/%(-?)([a-zA-Z%])/g, (match, flag, spec) => {
+        if (!(spec in values)) return match;
+        const [value, width] = values[spec];
+        return flag ? String(value) : String(value).padStart(width, '0');
+    });
+}
+
+// A date with a time, or with `withDate` false a time of day
+function formatTimestamp(timestamp, withDate) {
+    const date = new Date(timestamp);
+    if (isRelativeTime() && Math.abs(Date.now() - date) < 30 * 86400000) return timeAgo(date);
+    return strftime(date, withDate ? TIME_SETTINGS.format : TIME_SETTINGS.time_format);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::json_entry;
+    use proptest::prelude::*;
+    use crate::policy::{Decision, PolicyConfig};
+    use crate::models::SessionStatus;
+
+    #[tokio::test]
+    async fn test_adapter_control_needs_the_write_capability() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_profile(None);
+        config.data_dir = dir.path().join("data");
+        config.config_dir = dir.path().join("config");
+        config.claude_home = dir.path().join("claude");
+        config.socket_path = dir.path().join("daemon.sock");
+        let storage = Storage::in_memory();
+        let events = EventBus::new();
+        let processes = crate::procwatch::ProcessWatcher::new(30);
+        let services = IpcServices {
+            policy: PolicyEngine::new(PolicyConfig::default()).unwrap(),
+            adapters: Arc::new(RwLock::new(AdapterRegistry::new(&config, events.clone(), storage.clone(), processes.clone()))),
+            rules: RulesEngine::new(&config, storage.clone()).await.unwrap(),
+            commands: CommandTracker::new(Default::default(), storage.clone(), events.clone(), processes),
+            trash: Trash::new(dir.path().join("trash"), 7, None),
+            storage,
+            events,
+        };
+        let server = IpcServer::new(&config, services).unwrap();
+        tokio::spawn(async move { server.run().await });
+        while !config.socket_path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let stop = serde_json::json!({ "action": "adapter_control", "command": "stop", "name": "claude_code" });
+        let denied = ipc_request(&config.socket_path, &stop).await.unwrap_err();
+        assert!(denied.to_string().contains("write capability"));
+
+        let token = capabilities::load_or_create_token(&config.data_dir).unwrap();
+        // Let through to the registry, which has no Claude Code here to stop
+        let allowed = ipc_write_request(&config.socket_path, &token, &stop).await.unwrap_err();
+        assert_eq!(allowed.to_string(), "Adapter claude_code is not registered");
+    }
+
+    #[tokio::test]
+    async fn test_changes_need_the_write_token() {
+        let state = IntegrationState::new(Storage::in_memory(), EventBus::new());
+        let app = protect(create_integration_router(state), None, Some("secret".to_string()), Default::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let rule = serde_json::json!({
+            "name": "pwn",
+            "trigger": { "type": "event" },
+            "actions": [{ "type": "command", "command": "touch /tmp/pwned" }]
+        });
+        let create = |token: Option<&str>| {
+            let request = client.post(format!("{}/api/v1/rules", base)).json(&rule);
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        };
+
+        assert_eq!(create(None).send().await.unwrap().status(), 403);
+        assert_eq!(create(Some("guess")).send().await.unwrap().status(), 403);
+        // With the token the request goes on to the daemon (not running here)
+        assert_eq!(create(Some("secret")).send().await.unwrap().status(), 400);
+        assert_eq!(client.get(format!("{}/health", base)).send().await.unwrap().status(), 200);
+
+        // Clearing sessions and controlling adapters likewise
+        let clear = client.delete(format!("{}/api/v1/sessions?project=*", base)).send().await.unwrap();
+        assert_eq!(clear.status(), 403);
+        let stop = client.post(format!("{}/api/v1/adapters/claude_code/stop", base)).send().await.unwrap();
+        assert_eq!(stop.status(), 403);
+        // and other origins aren't invited to try
+        let preflight = client
+            .request(reqwest::Method::OPTIONS, format!("{}/api/v1/sessions", base))
+            .header("origin", "https://example.com")
+            .header("access-control-request-method", "DELETE")
+            .send()
+            .await
+            .unwrap();
+        let allowed = preflight.headers().get("access-control-allow-methods").unwrap().to_str().unwrap();
+        assert!(!allowed.contains("DELETE"));
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_let_machine_clients_read() {
+        let state = IntegrationState::new(Storage::in_memory(), EventBus::new());
+        let keys = capabilities::api_keys(&[capabilities::ApiKey { name: "laptop".to_string(), key: "k1".to_string() }]);
+        let app = protect(create_integration_router(state), None, Some("secret".to_string()), keys);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let hourly = format!("{}/api/v1/analytics/hourly", base);
+        assert_eq!(client.get(&hourly).send().await.unwrap().status(), 401);
+        let guessed = client.get(&hourly).header("X-API-Key", "guess").send().await.unwrap();
+        assert_eq!(guessed.status(), 401);
+        assert_eq!(client.get(format!("{}/health", base)).send().await.unwrap().status(), 200);
+
+        // `watch --remote --api-key` reads with it
+        let remote = crate::remote::RemoteClient::new(&base, Some("k1".to_string())).unwrap();
+        assert!(remote.get_hourly_usage(24).await.is_ok());
+        assert!(crate::remote::RemoteClient::new(&base, None).unwrap().get_hourly_usage(24).await.is_err());
+
+        // but a key alone changes nothing
+        let clear = client
+            .delete(format!("{}/api/v1/sessions?project=*", base))
+            .header("X-API-Key", "k1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(clear.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_hooks_are_still_decided() {
+        let storage = Storage::in_memory();
+        let bus = EventBus::new();
+        let policy = PolicyEngine::new(PolicyConfig {
+            enabled: true,
+            restrict_writes_to_project: true,
+            deny_commands: vec!["rm -rf /".to_string()],
+            ..PolicyConfig::default()
+        })
+        .unwrap();
+        let big = "x".repeat(2 * MAX_PAYLOAD_BYTES as usize);
+        let decide = |data: serde_json::Value| {
+            let payload = serde_json::to_vec(&data).unwrap();
+            assert!(payload.len() as u64 > MAX_PAYLOAD_BYTES);
+            serde_json::json!({ "event_type": "PreToolUse", "data": shorten_hook_payload(&payload) })
+        };
+
+        // A large write outside the project is still refused
+        let write = decide(serde_json::json!({
+            "cwd": "/work/app",
+            "tool_name": "Write",
+            "tool_input": { "file_path": "/etc/profile", "content": big },
+        }));
+        assert!(serde_json::to_vec(&write).unwrap().len() as u64 <= MAX_REQUEST_BYTES);
+        assert_eq!(evaluate_hook(&write, &storage, &policy, &bus).await.decision, Decision::Block);
+
+        // The command is never shortened, so its end is still checked
+        let command = decide(serde_json::json!({
+            "cwd": "/work/app",
+            "tool_name": "Bash",
+            "tool_input": { "command": format!("echo {} && rm -rf /", "y".repeat(8192)), "description": big },
+        }));
+        assert_eq!(evaluate_hook(&command, &storage, &policy, &bus).await.decision, Decision::Block);
+
+        // One that can't be cut down to size is blocked rather than allowed
+        let unsendable = decide(serde_json::json!({
+            "cwd": "/work/app",
+            "tool_name": "Bash",
+            "tool_input": { "command": big },
+        }));
+        assert_eq!(evaluate_hook(&unsendable, &storage, &policy, &bus).await.decision, Decision::Block);
+
+        // A write inside the project is allowed as before
+        let allowed = decide(serde_json::json!({
+            "cwd": "/work/app",
+            "tool_name": "Write",
+            "tool_input": { "file_path": "/work/app/big.txt", "content": big },
+        }));
+        assert_eq!(evaluate_hook(&allowed, &storage, &policy, &bus).await.decision, Decision::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hook_is_recorded_against_session() {
+        let storage = Storage::in_memory();
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+        let session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+
+        let policy = PolicyEngine::new(PolicyConfig {
+            enabled: true,
+            ..PolicyConfig::default()
+        })
+        .unwrap();
+        let request = serde_json::json!({
+            "event_type": "PreToolUse",
+            "data": {
+                "cwd": "/work/app",
+                "tool_name": "Bash",
+                "tool_input": {"command": "rm -rf /"}
+            }
+        });
+
+        let decision = evaluate_hook(&request, &storage, &policy, &bus).await;
+        assert_eq!(decision.decision, Decision::Block);
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::PolicyDecision);
+        assert_eq!(subscriber.try_recv().unwrap().id, events[0].id);
+    }
+
+    #[tokio::test]
+    async fn test_hook_decisions_survive_foreign_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::new(&dir.path().join("sessions.db")).await.unwrap();
+        storage.initialize().await.unwrap();
+        let bus = EventBus::new();
+        let policy = PolicyEngine::new(PolicyConfig {
+            enabled: true,
+            ..PolicyConfig::default()
+        })
+        .unwrap();
+        let hook = |cwd: &str, session_id: &str| {
+            serde_json::json!({
+                "event_type": "PreToolUse",
+                "data": {
+                    "cwd": cwd,
+                    "session_id": session_id,
+                    "tool_name": "Bash",
+                    "tool_input": {"command": "rm -rf /"}
+                }
+            })
+        };
+
+        // A finished session is found by Claude's session ID
+        let mut known = Session::new(AgentType::ClaudeCode, "/work/app", "claude-known");
+        known.status = SessionStatus::Completed;
+        storage.upsert_session(&known).await.unwrap();
+        evaluate_hook(&hook("/work/app/sub", "claude-known"), &storage, &policy, &bus).await;
+        let events = storage.get_session_events(&known.id, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::PolicyDecision);
+
+        // A session nothing has seen yet gets a placeholder
+        evaluate_hook(&hook("/work/new", "claude-new"), &storage, &policy, &bus).await;
+        let placeholder = storage.get_session_by_external_id("claude-new").await.unwrap().unwrap();
+        assert_eq!(placeholder.project_path, "/work/new");
+        let events = storage.get_session_events(&placeholder.id, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pre_compact_hook_counts_compaction() {
+        let storage = Storage::in_memory();
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        session.context_tokens = Some(165_000);
+        storage.upsert_session(&session).await.unwrap();
+
+        let request = serde_json::json!({
+            "event_type": "PreCompact",
+            "data": { "cwd": "/work/app", "trigger": "auto" }
+        });
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+        record_compaction(&request, &storage, &bus).await;
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events[0].event_type, EventType::Compaction);
+        assert_eq!(subscriber.try_recv().unwrap().id, events[0].id);
+        assert_eq!(events[0].content.as_deref(), Some("Context compacted (auto, 165000 tokens before)"));
+        assert_eq!(storage.get_session(&session.id).await.unwrap().unwrap().compactions, 1);
+
+        // Re-inserting the same event doesn't count twice
+        storage.insert_event(&events[0]).await.unwrap();
+        assert_eq!(storage.get_session(&session.id).await.unwrap().unwrap().compactions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_tool_use_hook_records_tool_call() {
+        let storage = Storage::in_memory();
+        let session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+
+        let request = serde_json::json!({
+            "event_type": "PostToolUse",
+            "data": {
+                "cwd": "/work/app",
+                "tool_name": "Bash",
+                "tool_input": { "command": "cargo test", "description": "Run the tests" },
+                "tool_response": { "stdout": "test result: FAILED", "stderr": "error: 1 test failed", "exit_code": 101 },
+                "duration_ms": 5400
+            }
+        });
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+        record_tool_use(&request, &storage, &bus).await;
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        let event = &events[0];
+        assert_eq!(subscriber.try_recv().unwrap().id, event.id);
+        assert_eq!(event.event_type, EventType::ToolExecuted);
+        assert_eq!(event.content.as_deref(), Some("Bash: cargo test"));
+        assert_eq!(event.tool_input.as_ref().unwrap()["command"], "cargo test");
+        assert_eq!(event.tool_output.as_deref(), Some("test result: FAILED\nerror: 1 test failed"));
+        assert_eq!(event.exit_code, Some(101));
+        assert_eq!(event.duration_ms, Some(5400));
+    }
+
+    proptest! {
+        #[test]
+        fn test_hook_payload_is_always_an_object(payload in prop::collection::vec(any::<u8>(), 0..512)) {
+            prop_assert!(parse_hook_payload(&payload).is_object());
+        }
+
+        #[test]
+        fn test_truncated_hook_payload_is_reported(data in json_entry(), cut in any::<prop::sample::Index>()) {
+            let payload = serde_json::to_vec_pretty(&data).unwrap();
+            prop_assert_eq!(parse_hook_payload(&payload), data);
+
+            let truncated = &payload[..cut.index(payload.len())];
+            let parsed = parse_hook_payload(truncated);
+            if truncated.iter().all(u8::is_ascii_whitespace) {
+                prop_assert_eq!(parsed, serde_json::json!({}));
+            } else {
+                prop_assert!(parsed.get("payload_error").is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Provider API errors and status page incidents.
+//!
+//! Agents log failed API calls (rate limits, overloads, server errors) and
+//! their retries; these are stored as ApiError events with the HTTP status
+//! and a kind. Optionally the provider's status page is polled and its
+//! incidents kept in the data directory, so hours with API errors can be
+//! annotated with the incident that was running: a spike during a provider
+//! incident is not the prompt's fault. The default URL is Anthropic's
+//! Statuspage incidents feed; any Statuspage `incidents.json` works.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::time::{interval, Duration};
+use tracing::{debug, info, warn};
+
+use crate::models::EventType;
+use crate::storage::Storage;
+
+/// Most API error events charted.
+const MAX_ERRORS: usize = 20_000;
+
+/// Status codes reported as API errors.
+const ERROR_STATUSES: [u16; 6] = [429, 500, 502, 503, 504, 529];
+
+/// Provider status page polling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderStatusConfig {
+    /// Whether the status page is polled for incidents
+    pub enabled: bool,
+
+    /// Statuspage incidents feed
+    pub url: String,
+
+    /// Seconds between polls
+    pub interval_secs: u64,
+
+    /// Days incidents are kept
+    pub keep_days: i64,
+}
+
+impl Default for ProviderStatusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://status.anthropic.com/api/v2/incidents.json".to_string(),
+            interval_secs: 300,
+            keep_days: 30,
+        }
+    }
+}
+
+/// An incident on the provider's status page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: String,
+    pub name: String,
+    /// none, minor, major or critical
+    pub impact: String,
+    pub started_at: DateTime<Utc>,
+    /// None while it is ongoing
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub url: Option<String>,
+}
+
+impl Incident {
+    fn overlaps(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+        self.started_at < to && self.resolved_at.is_none_or(|end| end >= from)
+    }
+}
+
+/// HTTP status and kind (`rate_limited`, `overloaded`, `server_error` or
+/// `other`) of an API error, from its reported status or its message.
+pub fn classify(status: Option<u16>, message: &str) -> (Option<u16>, &'static str) {
+    let lower = message.to_lowercase();
+    let status = status.or_else(|| {
+        lower
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|n| n.parse::<u16>().ok())
+            .find(|n| ERROR_STATUSES.contains(n))
+    });
+    let kind = match status {
+        Some(429) => "rate_limited",
+        Some(529) => "overloaded",
+        Some(500..=599) => "server_error",
+        _ if lower.contains("overloaded") => "overloaded",
+        _ if lower.contains("rate limit") || lower.contains("rate_limit") => "rate_limited",
+        _ => "other",
+    };
+    (status, kind)
+}
+
+/// API errors in one hour, and the incidents running during it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiErrorHour {
+    /// Start of the hour (UTC)
+    pub hour: DateTime<Utc>,
+    pub errors: i64,
+    /// Errors per kind
+    pub kinds: BTreeMap<String, i64>,
+    /// Names of provider incidents open during the hour
+    pub incidents: Vec<String>,
+}
+
+/// One point per hour for the last `hours`, oldest first.
+pub async fn hourly_api_errors(storage: &Storage, hours: i64, incidents: &[Incident]) -> Result<Vec<ApiErrorHour>> {
+    let hours = hours.max(1);
+    let now = Utc::now();
+    let current = now.duration_trunc(ChronoDuration::hours(1)).unwrap_or(now);
+    let first = current - ChronoDuration::hours(hours - 1);
+    let mut points: Vec<ApiErrorHour> = (0..hours)
+        .map(|i| {
+            let hour = first + ChronoDuration::hours(i);
+            ApiErrorHour {
+                hour,
+                incidents: incidents
+                    .iter()
+                    .filter(|incident| incident.overlaps(hour, hour + ChronoDuration::hours(1)))
+                    .map(|incident| incident.name.clone())
+                    .collect(),
+                ..ApiErrorHour::default()
+            }
+        })
+        .collect();
+
+    for event in storage.get_recent_events_of_type(EventType::ApiError, hours, MAX_ERRORS).await? {
+        let index = (event.timestamp - first).num_hours();
+        let Some(point) = usize::try_from(index).ok().and_then(|i| points.get_mut(i)) else {
+            continue;
+        };
+        let kind = event
+            .raw_data
+            .as_ref()
+            .and_then(|r| r.get("kind"))
+            .and_then(|k| k.as_str())
+            .unwrap_or("other");
+        point.errors += 1;
+        *point.kinds.entry(kind.to_string()).or_insert(0) += 1;
+    }
+    Ok(points)
+}
+
+/// Where polled incidents are kept.
+pub fn incidents_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("provider_incidents.json")
+}
+
+/// Incidents seen so far; none if the status page was never polled.
+pub fn load_incidents(path: &Path) -> Vec<Incident> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Incidents in a Statuspage `incidents.json` response.
+fn parse_incidents(feed: &serde_json::Value) -> Vec<Incident> {
+    let time = |incident: &serde_json::Value, key: &str| {
+        incident
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let text = |incident: &serde_json::Value, key: &str| {
+        incident.get(key).and_then(|v| v.as_str()).map(str::to_string)
+    };
+    feed.get("incidents")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|incident| {
+            Some(Incident {
+                id: text(incident, "id")?,
+                name: text(incident, "name").unwrap_or_default(),
+                impact: text(incident, "impact").unwrap_or_else(|| "none".to_string()),
+                started_at: time(incident, "started_at").or_else(|| time(incident, "created_at"))?,
+                resolved_at: time(incident, "resolved_at"),
+                url: text(incident, "shortlink"),
+            })
+        })
+        .collect()
+}
+
+/// Periodically copies the status page's incidents to the data directory.
+pub struct StatusPoller {
+    config: ProviderStatusConfig,
+    path: PathBuf,
+    client: reqwest::Client,
+}
+
+impl StatusPoller {
+    pub fn new(config: ProviderStatusConfig, data_dir: &Path) -> Self {
+        Self {
+            config,
+            path: incidents_path(data_dir),
+            client: reqwest::Client::builder().timeout(Duration::from_secs(30)).build().unwrap_or_default(),
+        }
+    }
+
+    /// Poll until the daemon stops.
+    pub async fn run(self) {
+        let mut ticker = interval(Duration::from_secs(self.config.interval_secs.max(60)));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.poll().await {
+                warn!("Polling the provider status page failed: {}", e);
+            }
+        }
+    }
+
+    async fn poll(&self) -> Result<()> {
+        let feed: serde_json::Value = self.client.get(&self.config.url).send().await?.error_for_status()?.json().await?;
+        let polled = parse_incidents(&feed);
+        let mut incidents = load_incidents(&self.path);
+        for incident in polled {
+            match incidents.iter_mut().find(|i| i.id == incident.id) {
+                Some(known) => *known = incident,
+                None => {
+                    info!("Provider incident: {} ({})", incident.name, incident.impact);
+                    incidents.push(incident);
+                }
+            }
+        }
+        let cutoff = Utc::now() - ChronoDuration::days(self.config.keep_days.max(1));
+        incidents.retain(|i| i.resolved_at.is_none_or(|end| end >= cutoff));
+        incidents.sort_by_key(|i| i.started_at);
+        std::fs::write(&self.path, serde_json::to_string_pretty(&incidents)?)?;
+        debug!("Provider status page lists {} recent incidents", incidents.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, Session, SessionEvent};
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(Some(529), ""), (Some(529), "overloaded"));
+        assert_eq!(classify(None, "API Error: 429 {\"type\":\"rate_limit_error\"}"), (Some(429), "rate_limited"));
+        assert_eq!(classify(None, "API Error: 500 Internal server error"), (Some(500), "server_error"));
+        assert_eq!(classify(None, "Overloaded"), (None, "overloaded"));
+        assert_eq!(classify(None, "API Error: Request timed out."), (None, "other"));
+        // Numbers that are not error statuses are ignored
+        assert_eq!(classify(None, "retry 2 of 10 after 4000ms"), (None, "other"));
+    }
+
+    #[tokio::test]
+    async fn test_hours_are_annotated_with_incidents() {
+        let feed = serde_json::json!({ "incidents": [
+            {
+                "id": "inc1",
+                "name": "Elevated errors on Claude Opus",
+                "impact": "major",
+                "created_at": (Utc::now() - ChronoDuration::minutes(200)).to_rfc3339(),
+                "started_at": (Utc::now() - ChronoDuration::minutes(150)).to_rfc3339(),
+                "resolved_at": (Utc::now() - ChronoDuration::minutes(100)).to_rfc3339(),
+                "shortlink": "https://stspg.io/abc",
+            },
+            { "name": "No ID" },
+        ]});
+        let incidents = parse_incidents(&feed);
+        assert_eq!(incidents.len(), 1);
+        assert!(incidents[0].started_at > Utc::now() - ChronoDuration::minutes(151));
+
+        let storage = Storage::in_memory();
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+        for (minutes_ago, kind) in [(120, "overloaded"), (120, "overloaded"), (5, "rate_limited")] {
+            let mut event = SessionEvent::new(&session.id, EventType::ApiError, AgentType::ClaudeCode);
+            event.timestamp = Utc::now() - ChronoDuration::minutes(minutes_ago);
+            event.raw_data = Some(serde_json::json!({ "kind": kind }));
+            storage.insert_event(&event).await.unwrap();
+        }
+
+        let points = hourly_api_errors(&storage, 6, &incidents).await.unwrap();
+        assert_eq!(points.len(), 6);
+        assert_eq!(points.iter().map(|p| p.errors).sum::<i64>(), 3);
+        let spike = points.iter().find(|p| p.kinds.get("overloaded") == Some(&2)).unwrap();
+        assert_eq!(spike.incidents, vec!["Elevated errors on Claude Opus".to_string()]);
+        assert!(points.last().unwrap().incidents.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! Moving old sessions to cold storage.
+//!
+//! `agent-monitor archive --older-than 60d` writes each session idle that
+//! long, with its events and tags, to a gzipped JSON bundle in a directory or
+//! bucket (see `objstore`), then deletes it from the database. A row in
+//! `archived_sessions` keeps the session itself, its opening prompt and where
+//! the bundle went, so it still turns up in search and `archive list`, and
+//! `archive restore` loads it back. With encryption on, bundles are encrypted
+//! with the database key. Resource and network samples are not archived.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Duration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+use crate::models::{ArchivedSession, EventType, Session, SessionEvent, SessionStatus, SessionTag};
+use crate::objstore::{self, Destination};
+use crate::storage::{ContentCipher, Storage};
+
+/// Bundle format written by this version.
+const BUNDLE_VERSION: u32 = 1;
+
+/// Appended to the names of encrypted bundles.
+const ENCRYPTED_SUFFIX: &str = ".enc";
+
+/// Longest opening prompt kept in the index.
+const MAX_PROMPT_CHARS: usize = 500;
+
+/// More rows than any one query returns in practice.
+const ALL: usize = i32::MAX as usize;
+
+/// One archived session, as written to cold storage.
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    session: Session,
+    /// Oldest first
+    events: Vec<SessionEvent>,
+    tags: Vec<SessionTag>,
+}
+
+/// `60d`, `12h`, `2w` or `30m`.
+pub fn parse_age(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.len().saturating_sub(1);
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| anyhow!("Invalid age '{}' (expected e.g. 60d, 12h, 2w)", text))?;
+    match unit {
+        "m" => Ok(Duration::minutes(count)),
+        "h" => Ok(Duration::hours(count)),
+        "d" => Ok(Duration::days(count)),
+        "w" => Ok(Duration::weeks(count)),
+        _ => bail!("Invalid age '{}' (expected e.g. 60d, 12h, 2w)", text),
+    }
+}
+
+/// Sessions that would be archived: not active, and idle for `older_than`.
+pub async fn candidates(storage: &Storage, older_than: Duration) -> Result<Vec<Session>> {
+    let cutoff = Utc::now() - older_than;
+    Ok(storage
+        .get_all_sessions(ALL)
+        .await?
+        .into_iter()
+        .filter(|s| s.status != SessionStatus::Active && s.last_activity_at < cutoff)
+        .collect())
+}
+
+/// Write `sessions` to `destination` and delete them from `storage`. A
+/// failed write stops the run; sessions archived before it stay archived.
+pub async fn archive(
+    storage: &Storage,
+    sessions: &[Session],
+    destination: &Destination,
+    cipher: Option<&ContentCipher>,
+) -> Result<Vec<ArchivedSession>> {
+    let mut archived = Vec::new();
+    for session in sessions {
+        let (location, first_prompt) = write_bundle(storage, session, destination, cipher)
+            .await
+            .with_context(|| format!("Could not archive session {}", session.id))?;
+        let entry = ArchivedSession { session: session.clone(), first_prompt, location, archived_at: Utc::now() };
+        storage.insert_archived_session(&entry).await?;
+        storage.delete_session(&session.id).await?;
+        archived.push(entry);
+    }
+    Ok(archived)
+}
+
+/// Write `session` with its events and tags to `destination`, returning
+/// where the bundle went and the session's opening prompt.
+pub async fn write_bundle(
+    storage: &Storage,
+    session: &Session,
+    destination: &Destination,
+    cipher: Option<&ContentCipher>,
+) -> Result<(String, Option<String>)> {
+    let mut events = storage.get_session_events(&session.id, ALL).await?;
+    events.reverse();
+    let first_prompt = events
+        .iter()
+        .find(|e| e.event_type == EventType::PromptReceived)
+        .and_then(|e| e.content.as_deref())
+        .map(|p| p.chars().take(MAX_PROMPT_CHARS).collect());
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        session: session.clone(),
+        events,
+        tags: storage.list_session_tags(Some(&session.id), None).await?,
+    };
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(&bundle)?)?;
+    let mut data = encoder.finish()?;
+    let mut name = format!("{}.json.gz", session.id);
+    if let Some(cipher) = cipher {
+        data = cipher.encrypt_bytes(&data)?;
+        name.push_str(ENCRYPTED_SUFFIX);
+    }
+    let location = destination.put(&name, data).await?;
+    Ok((location, first_prompt))
+}
+
+/// Load an archived session back into `storage`. The bundle is left where it is.
+pub async fn restore(storage: &Storage, session_id: &str, cipher: Option<&ContentCipher>) -> Result<Session> {
+    let entry = storage
+        .get_archived_session(session_id)
+        .await?
+        .ok_or_else(|| anyhow!("Session {} is not archived", session_id))?;
+    let session = load_bundle(storage, &entry.location, cipher).await?;
+    storage.delete_archived_session(session_id).await?;
+    Ok(session)
+}
+
+/// Whether the bundle at `location` was encrypted.
+pub fn is_encrypted(location: &str) -> bool {
+    location.ends_with(ENCRYPTED_SUFFIX)
+}
+
+/// Load the bundle at `location` back into `storage`.
+pub async fn load_bundle(storage: &Storage, location: &str, cipher: Option<&ContentCipher>) -> Result<Session> {
+    let mut data = objstore::fetch(location).await?;
+    if is_encrypted(location) {
+        let cipher = cipher.ok_or_else(|| anyhow!("{} is encrypted; turn on encryption to restore it", location))?;
+        data = cipher
+            .decrypt_bytes(&data)
+            .ok_or_else(|| anyhow!("{} is encrypted with another key", location))?;
+    }
+    let mut json = Vec::new();
+    GzDecoder::new(&data[..])
+        .read_to_end(&mut json)
+        .with_context(|| format!("{} is not a gzipped bundle", location))?;
+    let bundle: Bundle = serde_json::from_slice(&json)?;
+    if bundle.version > BUNDLE_VERSION {
+        bail!("{} was written by a newer agent-monitor (bundle version {})", location, bundle.version);
+    }
+
+    // Storage counts compactions and interruptions as their events arrive
+    let mut session = bundle.session;
+    session.compactions = 0;
+    session.interruptions = 0;
+    storage.upsert_session(&session).await?;
+    for event in &bundle.events {
+        storage.insert_event(event).await?;
+    }
+    for tag in &bundle.tags {
+        storage.add_session_tag(tag).await?;
+    }
+    Ok(session)
+}
+
+/// Whether every word of `query` appears in the archived session's project,
+/// summary or opening prompt, ignoring case.
+pub fn matches(archived: &ArchivedSession, query: &str) -> bool {
+    let text = [
+        Some(archived.session.project_path.as_str()),
+        archived.session.summary.as_deref(),
+        archived.first_prompt.as_deref(),
+    ]
+    .iter()
+    .flatten()
+    .map(|t| t.to_lowercase())
+    .collect::<Vec<_>>()
+    .join("\n");
+    query.split_whitespace().all(|word| text.contains(&word.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[tokio::test]
+    async fn test_archive_and_restore() {
+        let storage = Storage::in_memory();
+        let mut old = Session::new(AgentType::ClaudeCode, "/work/billing", "old");
+        old.status = SessionStatus::Completed;
+        old.last_activity_at = Utc::now() - Duration::days(90);
+        let recent = Session::new(AgentType::ClaudeCode, "/work/api", "recent");
+        for session in [&old, &recent] {
+            storage.upsert_session(session).await.unwrap();
+            let mut prompt = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+            prompt.content = Some("Migrate invoices to the new ledger".to_string());
+            storage.insert_event(&prompt).await.unwrap();
+        }
+        storage.add_session_tag(&SessionTag::new(&old.id, "finance", "manual")).await.unwrap();
+
+        let sessions = candidates(&storage, parse_age("60d").unwrap()).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        let dir = tempfile::tempdir().unwrap();
+        let cipher = ContentCipher::new(&[7; 32]);
+        let destination = Destination::Local(dir.path().to_path_buf());
+        let archived = archive(&storage, &sessions, &destination, Some(&cipher)).await.unwrap();
+
+        // Gone from the hot tables, still findable through the index
+        assert!(storage.get_session(&old.id).await.unwrap().is_none());
+        assert!(storage.get_session_events(&old.id, 10).await.unwrap().is_empty());
+        assert!(archived[0].location.ends_with(".json.gz.enc"));
+        let index = storage.list_archived_sessions().await.unwrap();
+        assert!(matches(&index[0], "ledger BILLING"));
+        assert!(!matches(&index[0], "ledger api"));
+
+        assert!(restore(&storage, &old.id, None).await.is_err());
+        let restored = restore(&storage, &old.id, Some(&cipher)).await.unwrap();
+        assert_eq!(restored.id, old.id);
+        assert_eq!(storage.get_session_events(&old.id, 10).await.unwrap().len(), 1);
+        assert_eq!(storage.list_session_tags(Some(&old.id), None).await.unwrap()[0].tag, "finance");
+        assert!(storage.list_archived_sessions().await.unwrap().is_empty());
+
+        assert_eq!(parse_age("2w").unwrap(), Duration::days(14));
+        assert!(parse_age("60").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Load-testing harness for the event pipeline.
+//!
+//! Appends synthetic Claude Code transcript lines to a scratch `~/.claude`
+//! tree and measures how long each takes to travel through the file watcher,
+//! adapter parsing, storage, and out of the EventBus to a subscriber (the
+//! same hop the SSE stream and websocket consume).
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+
+use crate::adapters::{Adapter, ClaudeCodeAdapter};
+use crate::config::Config;
+use crate::events::EventBus;
+use crate::procwatch::ProcessWatcher;
+use crate::storage::Storage;
+
+/// Marker embedded in each synthetic prompt so received events can be matched.
+const SEQ_MARKER: &str = "bench-seq-";
+
+/// Benchmark settings.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Transcript lines written per second
+    pub rate: u32,
+    /// How long to keep writing
+    pub duration: Duration,
+    /// Use the in-memory backend instead of a scratch SQLite file
+    pub in_memory: bool,
+}
+
+/// Results of a benchmark run.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub backend: String,
+    pub target_rate: u32,
+    pub sent: usize,
+    pub received: usize,
+    /// Events published more than once for the same transcript line
+    pub duplicate_publishes: usize,
+    /// Events the subscriber missed because the bus overflowed
+    pub lagged: u64,
+    pub stored: usize,
+    pub throughput_per_sec: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub latency_p99_ms: f64,
+    pub latency_max_ms: f64,
+}
+
+/// Run the pipeline benchmark in a scratch directory that is removed afterwards.
+pub async fn run(bench: BenchConfig) -> Result<BenchReport> {
+    let root = std::env::temp_dir().join(format!("agent-monitor-bench-{}", std::process::id()));
+    let result = run_in(&root, bench).await;
+    let _ = std::fs::remove_dir_all(&root);
+    result
+}
+
+async fn run_in(root: &Path, bench: BenchConfig) -> Result<BenchReport> {
+    let claude_home = root.join("claude");
+    let project_dir = claude_home.join("projects").join("-bench-project");
+    std::fs::create_dir_all(&project_dir)?;
+    let transcript = project_dir.join("bench-session.jsonl");
+    std::fs::File::create(&transcript)?;
+
+    let config = Config {
+        claude_home: claude_home.clone(),
+        db_path: root.join("bench.db"),
+        ..Config::default()
+    };
+
+    let storage = if bench.in_memory {
+        Storage::in_memory()
+    } else {
+        Storage::new(&config.db_path).await?
+    };
+    storage.initialize().await?;
+
+    let event_bus = EventBus::new();
+    let mut receiver = event_bus.subscribe();
+
+    let mut adapter = ClaudeCodeAdapter::new(
+        &config,
+        event_bus.clone(),
+        storage.clone(),
+        ProcessWatcher::new(config.poll_interval),
+    );
+    adapter.start().await?;
+    // Give the watcher a moment to register before writing
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let sent_at: Arc<Mutex<HashMap<u64, Instant>>> = Arc::new(Mutex::new(HashMap::new()));
+    let latencies: Arc<Mutex<HashMap<u64, Duration>>> = Arc::new(Mutex::new(HashMap::new()));
+    let duplicates = Arc::new(Mutex::new(0usize));
+    let lagged = Arc::new(Mutex::new(0u64));
+    let last_received = Arc::new(Mutex::new(None::<Instant>));
+
+    // Subscriber: match each published event back to the line that produced it
+    let collector = {
+        let sent_at = sent_at.clone();
+        let latencies = latencies.clone();
+        let duplicates = duplicates.clone();
+        let lagged = lagged.clone();
+        let last_received = last_received.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        *lagged.lock().await += missed;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let received = Instant::now();
+                let Some(seq) = event.content.as_deref().and_then(parse_seq) else {
+                    continue;
+                };
+                let Some(start) = sent_at.lock().await.get(&seq).copied() else {
+                    continue;
+                };
+                match latencies.lock().await.entry(seq) {
+                    Entry::Occupied(_) => *duplicates.lock().await += 1,
+                    Entry::Vacant(slot) => {
+                        slot.insert(received - start);
+                        *last_received.lock().await = Some(received);
+                    }
+                }
+            }
+        })
+    };
+
+    // Writer: append transcript lines at the target rate
+    let period = Duration::from_secs_f64(1.0 / bench.rate.max(1) as f64);
+    let mut interval = tokio::time::interval(period);
+    let mut file = std::fs::OpenOptions::new().append(true).open(&transcript)?;
+    let started = Instant::now();
+    let mut sent = 0u64;
+    while started.elapsed() < bench.duration {
+        interval.tick().await;
+        let line = serde_json::json!({
+            "type": "user",
+            "cwd": "/bench/project",
+            "sessionId": "bench-session",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "message": {
+                "role": "user",
+                "content": format!("{}{}", SEQ_MARKER, sent),
+            },
+        });
+        sent_at.lock().await.insert(sent, Instant::now());
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        sent += 1;
+    }
+
+    // Let in-flight events drain
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let elapsed = last_received
+        .lock()
+        .await
+        .map(|t| t - started)
+        .unwrap_or_else(|| started.elapsed());
+    adapter.stop().await?;
+    collector.abort();
+
+    let mut samples: Vec<Duration> = latencies.lock().await.values().copied().collect();
+    samples.sort();
+
+    let stored = match storage.get_active_session_for_project("/bench/project").await? {
+        Some(session) => storage.get_session_events(&session.id, usize::MAX >> 1).await?.len(),
+        None => 0,
+    };
+    let duplicate_publishes = *duplicates.lock().await;
+    let lagged = *lagged.lock().await;
+
+    Ok(BenchReport {
+        backend: if bench.in_memory { "memory" } else { "sqlite" }.to_string(),
+        target_rate: bench.rate,
+        sent: sent as usize,
+        received: samples.len(),
+        duplicate_publishes,
+        lagged,
+        stored,
+        throughput_per_sec: samples.len() as f64 / elapsed.as_secs_f64(),
+        latency_p50_ms: percentile_ms(&samples, 50.0),
+        latency_p95_ms: percentile_ms(&samples, 95.0),
+        latency_p99_ms: percentile_ms(&samples, 99.0),
+        latency_max_ms: samples.last().map(|d| d.as_secs_f64() * 1000.0).unwrap_or(0.0),
+    })
+}
+
+fn parse_seq(content: &str) -> Option<u64> {
+    content.strip_prefix(SEQ_MARKER)?.trim().parse().ok()
+}
+
+/// Nearest-rank percentile of sorted samples, in milliseconds.
+fn percentile_ms(sorted: &[Duration], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    let index = rank.clamp(1, sorted.len()) - 1;
+    sorted[index].as_secs_f64() * 1000.0
+}
@@ -0,0 +1,141 @@
+//! iCalendar feed of agent work sessions.
+//!
+//! `/api/v1/calendar.ics` turns each session into an event from its start to
+//! its end (or last activity, while it runs), titled with the agent and
+//! project, so a calendar or time tracker subscribed to the feed shows when
+//! agents were working on what. Event UIDs are session IDs, so refreshing
+//! the feed updates running sessions in place instead of duplicating them.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::humanize;
+use crate::models::Session;
+
+/// Shortest event shown, so brief sessions stay visible.
+const MIN_EVENT_MINUTES: i64 = 1;
+
+/// Longest content line before folding, in octets (RFC 5545 §3.1).
+const MAX_LINE_OCTETS: usize = 75;
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append `line` with CRLF, folding it without splitting a character.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts toward the next line
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn duration_text(seconds: i64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes.max(1))
+    }
+}
+
+/// A VCALENDAR with one VEVENT per session.
+pub fn to_ics(sessions: &[Session], now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//agent-monitor//sessions//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "X-WR-CALNAME:Agent sessions");
+
+    for session in sessions {
+        let start = session.started_at;
+        let end = session
+            .ended_at
+            .unwrap_or(session.last_activity_at)
+            .max(start + Duration::minutes(MIN_EVENT_MINUTES));
+        let project = session
+            .project_path
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .unwrap_or(&session.project_path);
+
+        let mut description = format!(
+            "Project: {}\nAgent: {}\nDuration: {}\nStatus: {:?}\nTokens: {} in / {} out\nCost: {}",
+            session.project_path,
+            session.agent_type,
+            duration_text((end - start).num_seconds()),
+            session.status,
+            session.tokens_input,
+            session.tokens_output,
+            humanize::cost(session.estimated_cost),
+        );
+        if let Some(ref summary) = session.summary {
+            description.push_str("\n\n");
+            description.push_str(summary);
+        }
+
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}@agent-monitor", session.id));
+        push_line(&mut out, &format!("DTSTAMP:{}", ics_time(now)));
+        push_line(&mut out, &format!("DTSTART:{}", ics_time(start)));
+        push_line(&mut out, &format!("DTEND:{}", ics_time(end)));
+        push_line(&mut out, &format!("SUMMARY:{}", escape(&format!("{} · {}", session.agent_type, project))));
+        push_line(&mut out, &format!("DESCRIPTION:{}", escape(&description)));
+        push_line(&mut out, &format!("CATEGORIES:{}", escape(project)));
+        push_line(&mut out, "TRANSP:TRANSPARENT");
+        push_line(&mut out, "END:VEVENT");
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_sessions_become_events() {
+        let now = Utc::now();
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/billing, api", "one");
+        session.started_at = now - Duration::hours(2);
+        session.ended_at = Some(now - Duration::minutes(30));
+        session.summary = Some("Moved invoices; fixed rounding. ".repeat(5));
+
+        let ics = to_ics(&[session.clone()], now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains(&format!("UID:{}@agent-monitor\r\n", session.id)));
+        assert!(ics.contains(&format!("DTEND:{}\r\n", ics_time(now - Duration::minutes(30)))));
+        assert!(ics.contains("CATEGORIES:billing\\, api\r\n"));
+        assert!(ics.lines().all(|line| line.trim_end_matches('\r').len() <= MAX_LINE_OCTETS));
+        // Unfolding restores the escaped text
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains("Duration: 1h 30m\\nStatus"));
+        assert!(unfolded.contains("Moved invoices\\; fixed rounding."));
+    }
+}
@@ -0,0 +1,285 @@
+//! Mutating IPC actions and the capability that unlocks them.
+//!
+//! Every client allowed on the socket can read. To clear, prune, annotate or
+//! mark sessions a connection first sends `{"action": "hello", "token": ...}`
+//! with the write token the daemon keeps in its data directory (owner-only,
+//! created on first start), so a tool must be able to read the daemon's
+//! files as well as reach its socket. Each attempt at a mutating action,
+//! allowed or not, is recorded in the audit log. Cleared and pruned sessions
+//! go to the trash like those cleared from the CLI. Installing or deleting
+//! automation rules needs the capability too, since a rule can run commands,
+//! and so does starting or stopping adapters.
+//!
+//! The web API holds to the same: without SSO, a request that changes
+//! anything needs the write token as `Authorization: Bearer <token>`, so a
+//! page open in the user's browser can't reach through the dashboard port.
+//! Machine clients such as `watch --remote` read with a key from `api_keys`
+//! in the config, sent as `X-API-Key`; once any key is configured, API
+//! reads without SSO need one, and with SSO a key stands in for a viewer's
+//! login.
+
+use anyhow::{anyhow, bail, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::archive;
+use crate::clear::{self, ClearFilter};
+use crate::models::{normalize_tag, AuditEntry, SessionStatus, SessionTag};
+use crate::storage::Storage;
+use crate::trash::Trash;
+
+/// Actions that change data, and so need the write capability.
+pub const WRITE_ACTIONS: [&str; 4] = ["clear", "prune", "annotate", "mark_session"];
+
+/// Where the write token is kept.
+pub fn token_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("ipc_token")
+}
+
+/// The write token, created with owner-only permissions if there is none.
+pub fn load_or_create_token(data_dir: &Path) -> Result<String> {
+    let path = token_path(data_dir);
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    std::fs::create_dir_all(data_dir)?;
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)?;
+    file.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Middleware for the web API without SSO: reads are open, anything else
+/// needs the write `token` as a bearer token.
+pub async fn require_write_token(State(token): State<Option<String>>, request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let error = match (presented, token.as_deref()) {
+        (_, None) => "Write actions are turned off (socket.write_actions)",
+        (Some(presented), Some(token)) if presented == token => return next.run(request).await,
+        _ => "Changes need the daemon's write token as an 'Authorization: Bearer' header, or an SSO admin login",
+    };
+    (StatusCode::FORBIDDEN, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+/// A key a machine client presents in the `X-API-Key` header to read the
+/// web API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Who holds the key
+    pub name: String,
+    pub key: String,
+}
+
+/// Configured API keys, naming the holder of each.
+pub type ApiKeys = Arc<HashMap<String, String>>;
+
+/// Index `keys` by their value.
+pub fn api_keys(keys: &[ApiKey]) -> ApiKeys {
+    Arc::new(keys.iter().map(|k| (k.key.clone(), k.name.clone())).collect())
+}
+
+/// Request extension naming the holder of a valid API key.
+#[derive(Debug, Clone)]
+pub struct ApiKeyHolder(pub String);
+
+/// Middleware checking `X-API-Key` once any keys are configured. A valid
+/// key is passed on as an [`ApiKeyHolder`]; an unknown one is turned away.
+/// Without a key, API calls get a 401 unless `sso` will ask for a login.
+pub async fn require_api_key(
+    State((keys, sso)): State<(ApiKeys, bool)>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if keys.is_empty() {
+        return next.run(request).await;
+    }
+    let presented = request.headers().get("x-api-key").and_then(|v| v.to_str().ok());
+    let error = match presented.map(|key| keys.get(key)) {
+        Some(Some(name)) => {
+            let holder = ApiKeyHolder(name.clone());
+            request.extensions_mut().insert(holder);
+            return next.run(request).await;
+        }
+        Some(None) => "Unknown API key",
+        None => {
+            let path = request.uri().path();
+            if sso || !(path.starts_with("/api") || path == "/openapi.yaml") {
+                return next.run(request).await;
+            }
+            "API requests need a key in the X-API-Key header"
+        }
+    };
+    (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+/// Run a mutating `action` and record it in the audit log; `allowed` is
+/// whether the connection holds the write capability.
+pub async fn perform(
+    storage: &Storage,
+    trash: &Trash,
+    uid: u32,
+    action: &str,
+    request: &Value,
+    allowed: bool,
+) -> Result<Value> {
+    let mut params = request.clone();
+    if let Some(params) = params.as_object_mut() {
+        params.remove("action");
+    }
+    let result = if allowed {
+        run(storage, trash, action, request).await
+    } else {
+        Err(anyhow!("'{}' needs the write capability: send a hello with the daemon's write token first", action))
+    };
+    let outcome = match (&result, allowed) {
+        (Ok(_), _) => "ok".to_string(),
+        (Err(_), false) => "denied".to_string(),
+        (Err(e), true) => format!("{:#}", e),
+    };
+    storage.insert_audit_entry(&AuditEntry::new(uid, action, params, &outcome)).await?;
+    result
+}
+
+async fn run(storage: &Storage, trash: &Trash, action: &str, request: &Value) -> Result<Value> {
+    let text = |key: &str| request.get(key).and_then(|v| v.as_str());
+    let session_id = || text("session_id").ok_or_else(|| anyhow!("'{}' needs a session_id", action));
+    match action {
+        "clear" => {
+            let dry_run = request.get("dry_run").and_then(|v| v.as_bool()) == Some(true);
+            if request.get("all").and_then(|v| v.as_bool()) == Some(true) {
+                let cleared = if dry_run {
+                    storage.get_all_sessions(i32::MAX as usize).await?.len()
+                } else {
+                    clear::clear_all(storage, trash).await?
+                };
+                return Ok(serde_json::json!({ "cleared": cleared, "all": true, "dry_run": dry_run }));
+            }
+            let filter = ClearFilter {
+                agent_type: text("agent_type").map(str::to_string),
+                project: text("project").map(str::to_string),
+                before: text("before").map(clear::parse_time).transpose()?,
+                after: text("after").map(clear::parse_time).transpose()?,
+                session_id: text("session_id").map(str::to_string),
+            };
+            let sessions = clear::clear(storage, trash, &filter, dry_run).await?;
+            let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+            Ok(serde_json::json!({ "cleared": ids.len(), "sessions": ids, "dry_run": dry_run }))
+        }
+        "prune" => {
+            let age = archive::parse_age(text("older_than").ok_or_else(|| anyhow!("'prune' needs older_than, e.g. 90d"))?)?;
+            let sessions = archive::candidates(storage, age).await?;
+            trash.discard(storage, &sessions, "prune").await?;
+            Ok(serde_json::json!({ "pruned": sessions.len() }))
+        }
+        "annotate" => {
+            let session_id = session_id()?;
+            if storage.get_session(session_id).await?.is_none() {
+                bail!("No session {}", session_id);
+            }
+            let tags = |key: &str| -> Result<Vec<String>> {
+                let values = request.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                values
+                    .iter()
+                    .map(|v| {
+                        let tag = v.as_str().unwrap_or_default();
+                        normalize_tag(tag).ok_or_else(|| anyhow!("Invalid tag '{}'", tag))
+                    })
+                    .collect()
+            };
+            let (add, remove) = (tags("add")?, tags("remove")?);
+            for tag in &add {
+                storage.add_session_tag(&SessionTag::new(session_id, tag, "ipc")).await?;
+            }
+            for tag in &remove {
+                storage.remove_session_tag(session_id, tag).await?;
+            }
+            if let Some(summary) = text("summary") {
+                storage.set_session_summary(session_id, summary).await?;
+            }
+            let tags: Vec<String> =
+                storage.list_session_tags(Some(session_id), None).await?.into_iter().map(|t| t.tag).collect();
+            Ok(serde_json::json!({ "tags": tags }))
+        }
+        "mark_session" => {
+            let session_id = session_id()?;
+            let status: SessionStatus = serde_json::from_value(request.get("status").cloned().unwrap_or_default())
+                .map_err(|_| anyhow!("'mark_session' needs a status: active, idle, completed or crashed"))?;
+            let mut session = storage.get_session(session_id).await?.ok_or_else(|| anyhow!("No session {}", session_id))?;
+            session.status = status;
+            session.ended_at = match status {
+                SessionStatus::Completed | SessionStatus::Crashed => session.ended_at.or(Some(chrono::Utc::now())),
+                _ => None,
+            };
+            storage.upsert_session(&session).await?;
+            Ok(serde_json::json!({ "session": session }))
+        }
+        _ => bail!("Unknown action: {}", action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, Session};
+
+    #[tokio::test]
+    async fn test_writes_need_the_capability_and_are_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let token = load_or_create_token(dir.path()).unwrap();
+        assert_eq!(load_or_create_token(dir.path()).unwrap(), token);
+        let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(token_path(dir.path())).unwrap().permissions());
+        assert_eq!(mode & 0o777, 0o600);
+
+        let storage = Storage::in_memory();
+        let trash = Trash::new(dir.path().join("trash"), 30, None);
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+
+        let annotate = serde_json::json!({ "action": "annotate", "session_id": session.id, "add": ["Reviewed"] });
+        assert!(perform(&storage, &trash, 1000, "annotate", &annotate, false).await.is_err());
+        assert!(storage.list_session_tags(Some(&session.id), None).await.unwrap().is_empty());
+
+        let tagged = perform(&storage, &trash, 1000, "annotate", &annotate, true).await.unwrap();
+        assert_eq!(tagged["tags"], serde_json::json!(["reviewed"]));
+        let mark = serde_json::json!({ "action": "mark_session", "session_id": session.id, "status": "crashed" });
+        perform(&storage, &trash, 1000, "mark_session", &mark, true).await.unwrap();
+        let marked = storage.get_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(marked.status, SessionStatus::Crashed);
+        assert!(marked.ended_at.is_some());
+        let clear = serde_json::json!({ "action": "clear" });
+        assert!(perform(&storage, &trash, 1000, "clear", &clear, true).await.is_err());
+
+        let log = storage.get_audit_log(10).await.unwrap();
+        let outcomes: Vec<_> = log.iter().map(|e| (e.action.as_str(), e.outcome.as_str())).collect();
+        assert_eq!(outcomes[1..], [("mark_session", "ok"), ("annotate", "ok"), ("annotate", "denied")]);
+        assert!(outcomes[0].1.contains("at least one"));
+        assert_eq!(log[2].params["add"], serde_json::json!(["Reviewed"]));
+        assert!(log[2].params.get("action").is_none());
+    }
+}
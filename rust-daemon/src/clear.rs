@@ -0,0 +1,161 @@
+//! Selective deletion of sessions.
+//!
+//! A clear removes the sessions matching every given criterion (agent type,
+//! project path glob, last activity before or after a time, session ID)
+//! together with their events, tags and samples, moving them to the trash
+//! when it is on (see `trash`). A dry run lists the same sessions without
+//! deleting them. Clearing everything stays a separate, explicit operation.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::import::start_of_day;
+use crate::models::Session;
+use crate::policy::glob_to_regex;
+use crate::storage::Storage;
+use crate::trash::Trash;
+
+/// More rows than any one query returns in practice.
+const ALL: usize = i32::MAX as usize;
+
+/// Which sessions a clear removes; unset criteria match every session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClearFilter {
+    pub agent_type: Option<String>,
+    /// Glob over the project path; `**` crosses directories
+    pub project: Option<String>,
+    /// Only sessions last active before this
+    pub before: Option<DateTime<Utc>>,
+    /// Only sessions last active after this
+    pub after: Option<DateTime<Utc>>,
+    pub session_id: Option<String>,
+}
+
+impl ClearFilter {
+    /// Whether no criterion is set, so the filter would match everything.
+    pub fn is_empty(&self) -> bool {
+        self.agent_type.is_none()
+            && self.project.is_none()
+            && self.before.is_none()
+            && self.after.is_none()
+            && self.session_id.is_none()
+    }
+}
+
+/// A day (`2026-01-31`, from local midnight) or an RFC 3339 time.
+pub fn parse_time(text: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(start_of_day)
+        .ok_or_else(|| anyhow!("Invalid date '{}' (expected YYYY-MM-DD or an RFC 3339 time)", text))
+}
+
+/// The sessions `filter` matches, most recently active first.
+pub async fn matching(storage: &Storage, filter: &ClearFilter) -> Result<Vec<Session>> {
+    if filter.is_empty() {
+        bail!("Give at least one of agent type, project, before, after or session");
+    }
+    let project: Option<Regex> = filter.project.as_deref().map(glob_to_regex).transpose()?;
+    let sessions = match filter.session_id.as_deref() {
+        Some(id) => storage.get_session(id).await?.into_iter().collect(),
+        None => storage.get_all_sessions(ALL).await?,
+    };
+    Ok(sessions
+        .into_iter()
+        .filter(|s| filter.agent_type.as_deref().is_none_or(|a| s.agent_type.to_string() == a))
+        .filter(|s| project.as_ref().is_none_or(|p| p.is_match(&s.project_path)))
+        .filter(|s| filter.before.is_none_or(|t| s.last_activity_at < t))
+        .filter(|s| filter.after.is_none_or(|t| s.last_activity_at >= t))
+        .collect())
+}
+
+/// Delete the sessions `filter` matches, or with `dry_run` only list them.
+pub async fn clear(storage: &Storage, trash: &Trash, filter: &ClearFilter, dry_run: bool) -> Result<Vec<Session>> {
+    let sessions = matching(storage, filter).await?;
+    if dry_run {
+        return Ok(sessions);
+    }
+    let by_type_only = filter.project.is_none()
+        && filter.before.is_none()
+        && filter.after.is_none()
+        && filter.session_id.is_none();
+    match filter.agent_type.as_deref() {
+        // One statement per table instead of one round per session
+        Some(agent_type) if by_type_only && !trash.is_enabled() => {
+            storage.delete_sessions_by_type(agent_type).await?;
+        }
+        _ => trash.discard(storage, &sessions, "clear").await?,
+    }
+    Ok(sessions)
+}
+
+/// Delete every session, returning how many there were. With the trash on
+/// they go there; otherwise the whole database is emptied.
+pub async fn clear_all(storage: &Storage, trash: &Trash) -> Result<usize> {
+    let sessions = storage.get_all_sessions(ALL).await?;
+    if trash.is_enabled() {
+        trash.discard(storage, &sessions, "clear").await?;
+    } else {
+        storage.clear_all().await?;
+    }
+    Ok(sessions.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[tokio::test]
+    async fn test_clear_by_project_time_and_session() {
+        let storage = Storage::in_memory();
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().to_path_buf(), 30, None);
+        let mut sessions = Vec::new();
+        for (project, agent, days_ago) in [
+            ("/work/api", AgentType::ClaudeCode, 40),
+            ("/work/api", AgentType::Aider, 40),
+            ("/work/web/app", AgentType::ClaudeCode, 40),
+            ("/work/api", AgentType::ClaudeCode, 1),
+        ] {
+            let mut session = Session::new(agent, project, &format!("{}{}", project, days_ago));
+            session.last_activity_at = Utc::now() - chrono::Duration::days(days_ago);
+            storage.upsert_session(&session).await.unwrap();
+            sessions.push(session);
+        }
+
+        assert!(matching(&storage, &ClearFilter::default()).await.is_err());
+        assert_eq!(parse_time("2026-01-31T10:00:00Z").unwrap().to_rfc3339(), "2026-01-31T10:00:00+00:00");
+        assert!(parse_time("last week").is_err());
+
+        let old_api = ClearFilter {
+            project: Some("/work/api".to_string()),
+            before: Some(Utc::now() - chrono::Duration::days(30)),
+            agent_type: Some("claude_code".to_string()),
+            ..ClearFilter::default()
+        };
+        let listed = clear(&storage, &trash, &old_api, true).await.unwrap();
+        assert_eq!(listed.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec![sessions[0].id.as_str()]);
+        assert_eq!(storage.get_all_sessions(10).await.unwrap().len(), 4);
+
+        clear(&storage, &trash, &old_api, false).await.unwrap();
+        assert!(storage.get_session(&sessions[0].id).await.unwrap().is_none());
+
+        let nested = ClearFilter { project: Some("/work/**".to_string()), ..ClearFilter::default() };
+        assert_eq!(matching(&storage, &nested).await.unwrap().len(), 3);
+        let by_id = ClearFilter { session_id: Some(sessions[2].id.clone()), ..ClearFilter::default() };
+        assert_eq!(clear(&storage, &trash, &by_id, false).await.unwrap().len(), 1);
+        assert_eq!(storage.get_all_sessions(10).await.unwrap().len(), 2);
+        assert_eq!(storage.list_trashed_sessions().await.unwrap().len(), 2);
+
+        assert_eq!(clear_all(&storage, &trash).await.unwrap(), 2);
+        assert!(storage.get_all_sessions(10).await.unwrap().is_empty());
+        assert_eq!(storage.list_trashed_sessions().await.unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,328 @@
+//! Commands run by agents.
+//!
+//! Tool calls like `cargo test` or `npm install` run as child processes of
+//! the agent. The tracker follows each active session's process tree, and
+//! once a command started by the agent has run for `min_secs` it adds a
+//! "running" event to the session's timeline, then a "finished" event when it
+//! exits. Shorter commands are left to the agent's own tool events. Everything
+//! in the tree right now is kept in memory and served over IPC, so what an
+//! agent is executing can be answered even when it logs nothing.
+
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::time::{interval, Duration};
+use tracing::warn;
+
+use crate::events::EventBus;
+use crate::models::{EventType, Session, SessionEvent};
+use crate::procwatch::{descendants, ProcessInfo, ProcessWatcher};
+use crate::resources::session_roots;
+use crate::storage::Storage;
+
+/// Most active sessions followed.
+const MAX_ACTIVE: usize = 500;
+
+/// Longest command line kept on an event.
+const MAX_COMMAND_CHARS: usize = 200;
+
+/// Endings Claude Code appends after the quoted command of a Bash tool call.
+const EVAL_SUFFIXES: [&str; 3] = ["' && pwd -P", "' \\< /dev/null", "' < /dev/null"];
+
+/// Command tracking settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandsConfig {
+    /// Whether agents' child processes are followed
+    pub enabled: bool,
+
+    /// Seconds a command must run before it is added to the timeline
+    pub min_secs: u64,
+
+    /// Seconds between checks of the process trees
+    pub interval_secs: u64,
+
+    /// Commands never added to the timeline, matched case-insensitively
+    /// against the name and command line (MCP servers run for the whole session)
+    pub exclude: Vec<String>,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_secs: 10,
+            interval_secs: 5,
+            exclude: vec!["mcp".to_string(), "modelcontextprotocol".to_string()],
+        }
+    }
+}
+
+/// A process in a session's tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunningCommand {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    /// Started by the agent itself rather than by another command
+    pub top_level: bool,
+}
+
+impl RunningCommand {
+    fn from_process(process: &ProcessInfo, top_level: bool) -> Self {
+        Self {
+            pid: process.pid,
+            parent: process.parent,
+            name: process.name.clone(),
+            command: describe_command(&process.cmd, &process.name),
+            started_at: Utc
+                .timestamp_opt(process.start_time as i64, 0)
+                .single()
+                .unwrap_or_else(Utc::now),
+            top_level,
+        }
+    }
+}
+
+/// A command crossing into or out of the timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandChange {
+    Running(RunningCommand),
+    Finished(RunningCommand),
+}
+
+/// Timeline commands already reported, by session, PID and start time.
+type Reported = HashMap<(String, u32, DateTime<Utc>), RunningCommand>;
+
+/// Follows agents' child processes.
+#[derive(Clone)]
+pub struct CommandTracker {
+    config: CommandsConfig,
+    storage: Storage,
+    event_bus: EventBus,
+    processes: ProcessWatcher,
+    /// Each active session's process tree as of the last check
+    running: Arc<Mutex<HashMap<String, Vec<RunningCommand>>>>,
+}
+
+impl CommandTracker {
+    pub fn new(config: CommandsConfig, storage: Storage, event_bus: EventBus, processes: ProcessWatcher) -> Self {
+        Self {
+            config,
+            storage,
+            event_bus,
+            processes,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Processes in a session's tree as of the last check, oldest first.
+    pub fn running(&self, session_id: &str) -> Vec<RunningCommand> {
+        self.running
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Follow process trees until the daemon stops.
+    pub async fn run(self) {
+        let mut ticker = interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        let mut reported = Reported::new();
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.check(&mut reported).await {
+                warn!("Command tracking failed: {}", e);
+            }
+        }
+    }
+
+    async fn check(&self, reported: &mut Reported) -> Result<()> {
+        let sessions = self.storage.get_active_sessions(MAX_ACTIVE).await?;
+        let processes = self.processes.snapshot();
+        let current: HashMap<String, Vec<RunningCommand>> = sessions
+            .iter()
+            .map(|s| (s.id.clone(), session_commands(s, &processes)))
+            .filter(|(_, commands)| !commands.is_empty())
+            .collect();
+
+        let changes = changes(reported, &current, Utc::now(), &self.config);
+        *self.running.lock().unwrap() = current;
+
+        for (session_id, change) in changes {
+            let Some(session) = sessions.iter().find(|s| s.id == session_id) else {
+                continue;
+            };
+            let event = command_event(session, &change);
+            self.storage.record_event(event, &self.event_bus).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Every process below a session's agent processes, oldest first.
+fn session_commands(session: &Session, processes: &[ProcessInfo]) -> Vec<RunningCommand> {
+    let roots: HashSet<u32> = session_roots(session, processes).into_iter().collect();
+    let tree: HashSet<u32> = descendants(processes, &roots.iter().copied().collect::<Vec<_>>())
+        .into_iter()
+        .collect();
+    let mut commands: Vec<RunningCommand> = processes
+        .iter()
+        .filter(|p| tree.contains(&p.pid) && !roots.contains(&p.pid))
+        .map(|p| RunningCommand::from_process(p, p.parent.is_some_and(|parent| roots.contains(&parent))))
+        .collect();
+    commands.sort_by_key(|c| (c.started_at, c.pid));
+    commands
+}
+
+/// Top-level commands that have now run for `min_secs`, and reported ones
+/// that are gone. `reported` is updated to match.
+fn changes(
+    reported: &mut Reported,
+    current: &HashMap<String, Vec<RunningCommand>>,
+    now: DateTime<Utc>,
+    config: &CommandsConfig,
+) -> Vec<(String, CommandChange)> {
+    let mut changes = Vec::new();
+
+    reported.retain(|(session_id, _, _), command| {
+        let running = current
+            .get(session_id)
+            .is_some_and(|commands| commands.iter().any(|c| c.pid == command.pid && c.started_at == command.started_at));
+        if !running {
+            changes.push((session_id.clone(), CommandChange::Finished(command.clone())));
+        }
+        running
+    });
+
+    for (session_id, commands) in current {
+        for command in commands {
+            let key = (session_id.clone(), command.pid, command.started_at);
+            let long_running = (now - command.started_at).num_seconds() >= config.min_secs as i64;
+            if command.top_level && long_running && !is_excluded(command, config) && !reported.contains_key(&key) {
+                reported.insert(key, command.clone());
+                changes.push((session_id.clone(), CommandChange::Running(command.clone())));
+            }
+        }
+    }
+    changes
+}
+
+fn is_excluded(command: &RunningCommand, config: &CommandsConfig) -> bool {
+    let name = command.name.to_lowercase();
+    let cmd = command.command.to_lowercase();
+    config.exclude.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        name.contains(&pattern) || cmd.contains(&pattern)
+    })
+}
+
+fn command_event(session: &Session, change: &CommandChange) -> SessionEvent {
+    let (command, content, state) = match change {
+        CommandChange::Running(command) => (command, format!("Running `{}` (pid {})", command.command, command.pid), "running"),
+        CommandChange::Finished(command) => {
+            let seconds = (Utc::now() - command.started_at).num_seconds().max(0);
+            (command, format!("Finished `{}` after ~{}s", command.command, seconds), "finished")
+        }
+    };
+    let mut event = SessionEvent::new(&session.id, EventType::Custom, session.agent_type);
+    event.content = Some(content);
+    event.working_directory = Some(session.project_path.clone());
+    event.raw_data = Some(json!({
+        "source": "process_tree",
+        "state": state,
+        "pid": command.pid,
+        "command": command.command,
+        "started_at": command.started_at,
+    }));
+    event
+}
+
+/// A readable command line. Claude Code runs Bash tool calls as
+/// `bash -c "source <snapshot> && eval '<command>' < /dev/null && pwd -P ..."`,
+/// so the quoted command is pulled out of that.
+fn describe_command(cmd: &str, name: &str) -> String {
+    let command = match cmd.find("eval '") {
+        Some(start) => {
+            let rest = &cmd[start + "eval '".len()..];
+            let end = EVAL_SUFFIXES
+                .iter()
+                .find_map(|suffix| rest.rfind(suffix))
+                .or_else(|| rest.rfind('\''))
+                .unwrap_or(rest.len());
+            rest[..end].replace("'\\''", "'").replace("'\"'\"'", "'")
+        }
+        None if cmd.is_empty() => name.to_string(),
+        None => cmd.to_string(),
+    };
+    if command.chars().count() > MAX_COMMAND_CHARS {
+        let truncated: String = command.chars().take(MAX_COMMAND_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_commands_and_changes() {
+        let now = Utc::now();
+        let started = (now.timestamp() - 30) as u64;
+        let process = |pid: u32, parent: u32, name: &str, cmd: &str| ProcessInfo {
+            pid,
+            parent: Some(parent),
+            name: name.to_string(),
+            cmd: cmd.to_string(),
+            cwd: Some("/work/api".to_string()),
+            start_time: started,
+        };
+        let processes = vec![
+            process(10, 1, "claude", "claude"),
+            process(
+                11,
+                10,
+                "bash",
+                "/bin/bash -c -l source /tmp/snapshot.sh && eval 'cargo test' \\< /dev/null && pwd -P",
+            ),
+            process(12, 11, "cargo", "cargo test"),
+            process(13, 10, "node", "npx @modelcontextprotocol/server-filesystem /work"),
+        ];
+
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        session.pid = Some(10);
+        let commands = session_commands(&session, &processes);
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].command, "cargo test");
+        assert!(commands[0].top_level && !commands[1].top_level);
+
+        let config = CommandsConfig::default();
+        let mut reported = Reported::new();
+        let mut current = HashMap::from([(session.id.clone(), commands)]);
+
+        // Only the top-level shell is reported; the MCP server is excluded
+        let first = changes(&mut reported, &current, now, &config);
+        assert_eq!(first.len(), 1);
+        assert!(matches!(&first[0].1, CommandChange::Running(c) if c.pid == 11));
+        assert!(changes(&mut reported, &current, now, &config).is_empty());
+
+        current.clear();
+        let last = changes(&mut reported, &current, now, &config);
+        assert!(matches!(&last[..], [(_, CommandChange::Finished(c))] if c.pid == 11));
+        assert!(reported.is_empty());
+
+        // Not yet long enough
+        let quick = CommandsConfig { min_secs: 60, ..CommandsConfig::default() };
+        current.insert(session.id.clone(), session_commands(&session, &processes));
+        assert!(changes(&mut reported, &current, now, &quick).is_empty());
+    }
+}
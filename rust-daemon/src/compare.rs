@@ -0,0 +1,194 @@
+//! Side-by-side comparison of agents over a period.
+//!
+//! Sessions from the last `days` (optionally limited to one project) are
+//! grouped by agent type and compared on cost per session, messages per
+//! completed session, error rate and files changed. Error rate is the share
+//! of sessions that crashed or logged at least one error event. Files changed
+//! counts distinct paths written per session, from `FileModified` events and
+//! Write/Edit tool calls; a write without a recorded path counts once.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::{EventType, Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+/// Most sessions or events of one type considered for a comparison.
+const MAX_ROWS: usize = 20_000;
+
+const WRITE_TOOLS: &[&str] = &["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
+/// Metrics for one agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentComparison {
+    pub agent_type: String,
+    pub sessions: usize,
+    pub completed: usize,
+    pub total_cost: f64,
+    pub cost_per_session: f64,
+    /// None until a session has completed
+    pub messages_per_completed: Option<f64>,
+    /// Share of sessions (0–1) that crashed or logged an error
+    pub error_rate: f64,
+    pub files_changed: usize,
+    pub files_per_session: f64,
+}
+
+/// Agents compared over one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub generated_at: DateTime<Utc>,
+    pub days: i64,
+    pub project: Option<String>,
+    /// Most sessions first
+    pub agents: Vec<AgentComparison>,
+}
+
+/// Compare agents over the last `days`, optionally within one project.
+pub async fn build_comparison(storage: &Storage, project: Option<&str>, days: i64) -> Result<Comparison> {
+    let days = days.max(1);
+    let hours = days * 24;
+    let sessions: Vec<Session> = storage
+        .get_recent_sessions(hours, MAX_ROWS)
+        .await?
+        .into_iter()
+        .filter(|s| project.is_none_or(|p| s.project_path == p))
+        .collect();
+
+    let mut events = Vec::new();
+    for event_type in [
+        EventType::Error,
+        EventType::FileModified,
+        EventType::ToolStart,
+        EventType::ResponseGenerated,
+    ] {
+        events.extend(storage.get_recent_events_of_type(event_type, hours, MAX_ROWS).await?);
+    }
+
+    Ok(Comparison {
+        generated_at: Utc::now(),
+        days,
+        project: project.map(str::to_string),
+        agents: compare(&sessions, &events),
+    })
+}
+
+fn compare(sessions: &[Session], events: &[SessionEvent]) -> Vec<AgentComparison> {
+    let mut errored: HashSet<&str> = HashSet::new();
+    let mut written: HashMap<&str, HashSet<&str>> = HashMap::new();
+    let mut unnamed_writes: HashMap<&str, usize> = HashMap::new();
+    for event in events {
+        let session_id = event.session_id.as_str();
+        if event.event_type == EventType::Error {
+            errored.insert(session_id);
+            continue;
+        }
+        let writes = event.event_type == EventType::FileModified
+            || event.tool_name.as_deref().is_some_and(|t| WRITE_TOOLS.contains(&t));
+        if !writes {
+            continue;
+        }
+        match event.file_path.as_deref() {
+            Some(path) => {
+                written.entry(session_id).or_default().insert(path);
+            }
+            None => *unnamed_writes.entry(session_id).or_default() += 1,
+        }
+    }
+
+    let mut by_agent: BTreeMap<String, Vec<&Session>> = BTreeMap::new();
+    for session in sessions {
+        by_agent.entry(session.agent_type.to_string()).or_default().push(session);
+    }
+
+    let mut agents: Vec<AgentComparison> = by_agent
+        .into_iter()
+        .map(|(agent_type, sessions)| {
+            let count = sessions.len();
+            let completed: Vec<&&Session> = sessions
+                .iter()
+                .filter(|s| s.status == SessionStatus::Completed)
+                .collect();
+            let failed = sessions
+                .iter()
+                .filter(|s| s.status == SessionStatus::Crashed || errored.contains(s.id.as_str()))
+                .count();
+            let files_changed: usize = sessions
+                .iter()
+                .map(|s| {
+                    written.get(s.id.as_str()).map_or(0, |paths| paths.len())
+                        + unnamed_writes.get(s.id.as_str()).copied().unwrap_or(0)
+                })
+                .sum();
+            let total_cost: f64 = sessions.iter().map(|s| s.estimated_cost).sum();
+            let completed_messages: i64 = completed.iter().map(|s| s.message_count).sum();
+
+            AgentComparison {
+                agent_type,
+                sessions: count,
+                completed: completed.len(),
+                total_cost,
+                cost_per_session: total_cost / count as f64,
+                messages_per_completed: (!completed.is_empty())
+                    .then(|| completed_messages as f64 / completed.len() as f64),
+                error_rate: failed as f64 / count as f64,
+                files_changed,
+                files_per_session: files_changed as f64 / count as f64,
+            }
+        })
+        .collect();
+    agents.sort_by(|a, b| b.sessions.cmp(&a.sessions).then(a.agent_type.cmp(&b.agent_type)));
+    agents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_compare_agents() {
+        let mut claude_done = Session::new(AgentType::ClaudeCode, "/work/api", "x");
+        claude_done.status = SessionStatus::Completed;
+        claude_done.message_count = 30;
+        claude_done.estimated_cost = 3.0;
+        let mut claude_live = Session::new(AgentType::ClaudeCode, "/work/api", "x");
+        claude_live.estimated_cost = 1.0;
+        let mut aider = Session::new(AgentType::Aider, "/work/api", "x");
+        aider.status = SessionStatus::Crashed;
+        aider.estimated_cost = 0.5;
+
+        let edit = |session: &Session, path: Option<&str>| {
+            let mut event = SessionEvent::new(&session.id, EventType::ResponseGenerated, session.agent_type);
+            event.tool_name = Some("Edit".to_string());
+            event.file_path = path.map(str::to_string);
+            event
+        };
+        let events = vec![
+            edit(&claude_done, Some("src/lib.rs")),
+            edit(&claude_done, Some("src/lib.rs")),
+            edit(&claude_done, Some("src/main.rs")),
+            edit(&claude_live, None),
+            SessionEvent::new(&claude_live.id, EventType::Error, AgentType::ClaudeCode),
+            SessionEvent::new(&aider.id, EventType::FileModified, AgentType::Aider),
+        ];
+
+        let agents = compare(&[claude_done, claude_live, aider], &events);
+        assert_eq!(agents.len(), 2);
+
+        let claude = &agents[0];
+        assert_eq!(claude.agent_type, "claude_code");
+        assert_eq!(claude.sessions, 2);
+        assert!((claude.cost_per_session - 2.0).abs() < 1e-9);
+        assert_eq!(claude.messages_per_completed, Some(30.0));
+        assert!((claude.error_rate - 0.5).abs() < 1e-9);
+        assert_eq!(claude.files_changed, 3);
+
+        let aider = &agents[1];
+        assert_eq!(aider.messages_per_completed, None);
+        assert!((aider.error_rate - 1.0).abs() < 1e-9);
+        assert_eq!(aider.files_changed, 1);
+    }
+}
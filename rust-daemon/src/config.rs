@@ -1,8 +1,44 @@
 //! Configuration management for the daemon.
+//!
+//! A named profile (`--profile work`) has its own config file, data
+//! directory (and so database) and socket, so separate daemons can monitor
+//! separate activity on one machine. Without one, the standard paths apply.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::anomaly::AnomalyConfig;
+use crate::apierrors::ProviderStatusConfig;
+use crate::capabilities::ApiKey;
+use crate::commands::CommandsConfig;
+use crate::context::ContextConfig;
+use crate::digest::EmailDigestConfig;
+use crate::drift::DriftConfig;
+use crate::duplicates::DuplicatesConfig;
+use crate::export::ExportConfig;
+use crate::forecast::ForecastConfig;
+use crate::humanize::HumanizeConfig;
+use crate::issues::IssuesConfig;
+use crate::loops::LoopConfig;
+use crate::network::NetworkConfig;
+use crate::notifications::NotificationChannel;
+use crate::otlp::OtlpConfig;
+use crate::plugins::PluginConfig;
+use crate::policy::PolicyConfig;
+use crate::refresh::RefreshConfig;
+use crate::resources::ResourcesConfig;
+use crate::storage::{CacheConfig, ContentConfig, EncryptionConfig};
+use crate::rules::AutomationRule;
+use crate::search::EmbeddingsConfig;
+use crate::sockets::SocketConfig;
+use crate::sso::SsoConfig;
+use crate::summarize::SummarizerConfig;
+use crate::timefmt::TimestampConfig;
+use crate::timetrack::TimeTrackingConfig;
+use crate::trash::TrashConfig;
+use crate::watchmode::WatchConfig;
 
 /// Main configuration for the daemon.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +49,15 @@ pub struct Config {
     /// Path to SQLite database
     pub db_path: PathBuf,
 
+    /// Shared database URL (postgres://...); overrides `db_path` when set
+    #[serde(default)]
+    pub database_url: Option<String>,
+
+    /// Who sessions recorded here are attributed to in a shared database;
+    /// defaults to the login name
+    #[serde(default)]
+    pub user: Option<String>,
+
     /// Path to Unix socket
     pub socket_path: PathBuf,
 
@@ -30,32 +75,250 @@ pub struct Config {
 
     /// HTTP port for web server
     pub http_port: u16,
+
+    /// Hook policy settings
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
+    /// WASM event plugins, run in order before events are stored
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+
+    /// Context injected into new Claude Code sessions
+    #[serde(default)]
+    pub context: ContextConfig,
+
+    /// Push channels (ntfy, Pushover, Gotify) that rule notify actions can target
+    #[serde(default)]
+    pub notification_channels: Vec<NotificationChannel>,
+
+    /// Automation rules (more can be added at runtime via the API)
+    #[serde(default)]
+    pub rules: Vec<AutomationRule>,
+
+    /// Summaries of completed sessions
+    #[serde(default)]
+    pub summarizer: SummarizerConfig,
+
+    /// Embeddings index for semantic transcript search
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+
+    /// Detection of the same prompt sent to several sessions
+    #[serde(default)]
+    pub duplicates: DuplicatesConfig,
+
+    /// Weekly HTML report by email
+    #[serde(default)]
+    pub email_digest: EmailDigestConfig,
+
+    /// Burn-rate window and monthly budgets for spend forecasts
+    #[serde(default)]
+    pub forecast: ForecastConfig,
+
+    /// OTLP/HTTP receiver for agents that export OpenTelemetry
+    #[serde(default)]
+    pub otlp: OtlpConfig,
+
+    /// CPU and memory sampling of active sessions
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+
+    /// Long-running commands started by agents
+    #[serde(default)]
+    pub commands: CommandsConfig,
+
+    /// Network traffic sampling of active sessions (Linux, opt-in)
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    /// At-rest encryption of transcript content
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    /// Where large event content is stored
+    #[serde(default)]
+    pub content: ContentConfig,
+
+    /// In-memory caching of hot reads
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    /// Scheduled JSONL exports to a directory or bucket
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// Time entries in Toggl or Clockify for finished sessions
+    #[serde(default)]
+    pub time_tracking: TimeTrackingConfig,
+
+    /// Issue keys found in prompts and branches, and tracker updates
+    #[serde(default)]
+    pub issues: IssuesConfig,
+
+    /// OpenID Connect login for the web dashboard and API
+    #[serde(default)]
+    pub sso: SsoConfig,
+
+    /// How often the TUI and web dashboard re-read the database
+    #[serde(default)]
+    pub refresh: RefreshConfig,
+
+    /// Watching Claude Code settings for changes
+    #[serde(default)]
+    pub drift: DriftConfig,
+
+    /// Flagging bursts of errors, tool calls or token spend within a session
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+
+    /// Flagging agents that repeat the same calls or undo their own edits
+    #[serde(default)]
+    pub loops: LoopConfig,
+
+    /// Polling the provider's status page to annotate API errors
+    #[serde(default)]
+    pub provider_status: ProviderStatusConfig,
+
+    /// Users allowed to connect to the daemon's socket
+    #[serde(default)]
+    pub socket: SocketConfig,
+
+    /// How long cleared sessions can be restored
+    #[serde(default)]
+    pub trash: TrashConfig,
+
+    /// Timezone and format of timestamps in the CLI, TUI and dashboard
+    #[serde(default)]
+    pub timestamps: TimestampConfig,
+
+    /// Precision, unit thresholds and duration styles of numbers shown
+    #[serde(default)]
+    pub humanize: HumanizeConfig,
+
+    /// Native file events or polling for the directories adapters watch
+    #[serde(default)]
+    pub watch: WatchConfig,
+
+    /// Keys machine clients such as `watch --remote` read the web API with
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+}
+
+/// The profile of this run, set once at startup.
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Use the named profile for the rest of this run; `None` or `default` keeps
+/// the standard paths. Later calls have no effect.
+pub fn set_profile(name: Option<&str>) -> Result<()> {
+    let name = name.filter(|n| *n != "default");
+    if let Some(name) = name {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("Invalid profile name {:?}: use letters, digits, '-' and '_'", name);
+        }
+    }
+    let _ = PROFILE.set(name.map(String::from));
+    Ok(())
+}
+
+/// The active named profile, if any.
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|p| p.as_deref())
 }
 
 impl Default for Config {
     fn default() -> Self {
+        Self::for_profile(profile())
+    }
+}
+
+/// The config directory of a profile, under the platform's config directory
+/// (`~/.config` on Linux, `~/Library/Application Support` on macOS).
+fn config_dir(profile: Option<&str>) -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let dir = dirs::config_dir().unwrap_or_else(|| home.join(".config")).join("agent-monitor");
+    match profile {
+        Some(profile) => dir.join("profiles").join(profile),
+        None => dir,
+    }
+}
+
+/// Copy a config file written where older versions looked for it to `path`,
+/// unless there is one at `path` already. The original stays for the Python
+/// daemon, which still reads it there.
+fn migrate_config(legacy: &Path, path: &Path) -> Result<()> {
+    if legacy == path || path.exists() || !legacy.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::copy(legacy, path)?;
+    tracing::info!("Copied {} to {}", legacy.display(), path.display());
+    Ok(())
+}
+
+impl Config {
+    /// Defaults for a profile: named profiles live under `profiles/<name>` in
+    /// the data and config directories, with a socket of their own.
+    pub fn for_profile(profile: Option<&str>) -> Self {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let data_dir = dirs::data_local_dir()
+        let mut data_dir = dirs::data_local_dir()
             .unwrap_or_else(|| home.join(".local/share"))
             .join("agent-monitor");
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| home.join(".config"))
-            .join("agent-monitor");
+        let config_dir = config_dir(profile);
+        let mut socket_path = PathBuf::from("/tmp/agent-monitor.sock");
+        if let Some(profile) = profile {
+            data_dir = data_dir.join("profiles").join(profile);
+            socket_path = PathBuf::from(format!("/tmp/agent-monitor-{}.sock", profile));
+        }
 
         Self {
             db_path: data_dir.join("sessions.db"),
-            socket_path: PathBuf::from("/tmp/agent-monitor.sock"),
+            database_url: None,
+            user: None,
+            socket_path,
             config_dir,
             data_dir,
             claude_home: home.join(".claude"),
             log_level: "info".to_string(),
             poll_interval: 30,
             http_port: 8765,
+            policy: PolicyConfig::default(),
+            plugins: Vec::new(),
+            context: ContextConfig::default(),
+            notification_channels: Vec::new(),
+            rules: Vec::new(),
+            summarizer: SummarizerConfig::default(),
+            embeddings: EmbeddingsConfig::default(),
+            duplicates: DuplicatesConfig::default(),
+            email_digest: EmailDigestConfig::default(),
+            forecast: ForecastConfig::default(),
+            otlp: OtlpConfig::default(),
+            resources: ResourcesConfig::default(),
+            commands: CommandsConfig::default(),
+            network: NetworkConfig::default(),
+            encryption: EncryptionConfig::default(),
+            content: ContentConfig::default(),
+            cache: CacheConfig::default(),
+            export: ExportConfig::default(),
+            time_tracking: TimeTrackingConfig::default(),
+            issues: IssuesConfig::default(),
+            sso: SsoConfig::default(),
+            refresh: RefreshConfig::default(),
+            drift: DriftConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            loops: LoopConfig::default(),
+            provider_status: ProviderStatusConfig::default(),
+            socket: SocketConfig::default(),
+            trash: TrashConfig::default(),
+            timestamps: TimestampConfig::default(),
+            humanize: HumanizeConfig::default(),
+            watch: WatchConfig::default(),
+            api_keys: Vec::new(),
         }
     }
-}
 
-impl Config {
     /// Load configuration from a file.
     pub fn load(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
@@ -63,6 +326,49 @@ impl Config {
         Ok(config)
     }
 
+    /// The user config file of the active profile, as written by `config --init`.
+    pub fn user_config_path() -> PathBuf {
+        config_dir(profile()).join("config.json")
+    }
+
+    /// Load the user config file if present, otherwise use defaults. A file
+    /// only in `~/.config/agent-monitor` is copied to the config directory first.
+    pub fn load_or_default() -> Result<Self> {
+        let path = Self::user_config_path();
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        let mut legacy = home.join(".config/agent-monitor");
+        if let Some(profile) = profile() {
+            legacy = legacy.join("profiles").join(profile);
+        }
+        migrate_config(&legacy.join("config.json"), &path)?;
+        if path.exists() {
+            Self::load(&path.to_string_lossy())
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Whether sessions live in the local SQLite file rather than a shared database.
+    pub fn uses_local_db(&self) -> bool {
+        self.database_url.is_none()
+    }
+
+    /// `database_url` with any password replaced, for display.
+    pub fn redacted_database_url(&self) -> Option<String> {
+        let url = self.database_url.as_ref()?;
+        let redacted = match (url.find("://"), url.rfind('@')) {
+            (Some(scheme_end), Some(at)) if at > scheme_end => {
+                let credentials = &url[scheme_end + 3..at];
+                match credentials.split_once(':') {
+                    Some((user, _)) => format!("{}{}:***{}", &url[..scheme_end + 3], user, &url[at..]),
+                    None => url.clone(),
+                }
+            }
+            _ => url.clone(),
+        };
+        Some(redacted)
+    }
+
     /// Save configuration to a file.
     pub fn save(&self, path: &str) -> Result<()> {
         let content = serde_json::to_string_pretty(self)?;
@@ -77,3 +383,40 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_are_separate() {
+        let standard = Config::for_profile(None);
+        let work = Config::for_profile(Some("work"));
+        assert!(standard.db_path.ends_with("agent-monitor/sessions.db"));
+        assert!(work.db_path.ends_with("agent-monitor/profiles/work/sessions.db"));
+        assert!(work.config_dir.ends_with("profiles/work"));
+        assert_eq!(work.socket_path, PathBuf::from("/tmp/agent-monitor-work.sock"));
+        assert_ne!(standard.socket_path, work.socket_path);
+
+        assert!(set_profile(Some("../work")).is_err());
+        assert!(set_profile(Some("")).is_err());
+    }
+
+    #[test]
+    fn test_config_file_lives_in_config_dir() {
+        assert_eq!(Config::user_config_path().parent(), Some(Config::default().config_dir.as_path()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = dir.path().join(".config/agent-monitor/config.json");
+        let path = dir.path().join("Library/Application Support/agent-monitor/config.json");
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, "{\"http_port\": 9000}").unwrap();
+        migrate_config(&legacy, &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"http_port\": 9000}");
+
+        // A file already in place wins
+        std::fs::write(&legacy, "{}").unwrap();
+        migrate_config(&legacy, &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"http_port\": 9000}");
+    }
+}
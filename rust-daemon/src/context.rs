@@ -0,0 +1,206 @@
+//! Context injected into new Claude Code sessions.
+//!
+//! When a SessionStart hook fires, the daemon answers with the project's
+//! memory entries (those tagged `project:<path>`) and a short digest of the
+//! latest sessions in the same directory. Claude Code adds the text to the
+//! new session as `additionalContext`, so each run starts knowing what the
+//! previous ones did.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::humanize;
+use crate::models::Session;
+use crate::storage::Storage;
+
+/// SessionStart context settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Whether SessionStart hooks receive context
+    pub enabled: bool,
+
+    /// Prior sessions to summarize
+    pub max_sessions: usize,
+
+    /// Memory entries to include
+    pub max_memory_entries: usize,
+
+    /// Only consider sessions active within this many days
+    pub lookback_days: i64,
+
+    /// Upper bound on the injected text
+    pub max_chars: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_sessions: 3,
+            max_memory_entries: 10,
+            lookback_days: 14,
+            max_chars: 4000,
+        }
+    }
+}
+
+/// Longest single memory value or task line shown.
+const MAX_LINE_CHARS: usize = 300;
+
+/// Memory tag that scopes an entry to a project directory.
+pub fn project_tag(project: &str) -> String {
+    format!("project:{}", project)
+}
+
+/// Build the context for a new session in `project`. Sessions whose external
+/// ID is `current_session` (the one starting) are skipped. Returns None when
+/// there is nothing worth injecting.
+pub async fn build_session_context(
+    storage: &Storage,
+    config: &ContextConfig,
+    project: &str,
+    current_session: Option<&str>,
+) -> Result<Option<String>> {
+    let memory = storage.list_memory(Some(&project_tag(project))).await?;
+
+    let sessions: Vec<Session> = storage
+        .get_recent_sessions(config.lookback_days * 24, 500)
+        .await?
+        .into_iter()
+        .filter(|s| s.project_path == project)
+        .filter(|s| Some(s.external_id.as_str()) != current_session)
+        .filter(|s| s.message_count > 0)
+        .take(config.max_sessions)
+        .collect();
+
+    if memory.is_empty() && sessions.is_empty() {
+        return Ok(None);
+    }
+
+    let mut text = String::from("# Agent Monitor: prior work in this project\n");
+
+    if !memory.is_empty() {
+        text.push_str("\n## Memory\n");
+        for entry in memory.iter().take(config.max_memory_entries) {
+            let value = match &entry.value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            text.push_str(&format!("- {}: {}\n", entry.key, truncate(&value, MAX_LINE_CHARS)));
+        }
+    }
+
+    if !sessions.is_empty() {
+        text.push_str("\n## Recent sessions\n");
+        for session in &sessions {
+            text.push_str(&format!("- {}\n", describe_session(session)));
+        }
+    }
+
+    Ok(Some(truncate(&text, config.max_chars)))
+}
+
+/// Digest of a prior session: its stats, then its summary (or current task).
+fn describe_session(session: &Session) -> String {
+    let ago = format_age(Utc::now() - session.last_activity_at);
+    let mut line = format!(
+        "{} ago, {} ({} messages, {} tool calls, {})",
+        ago, session.status, session.message_count, session.tool_call_count, humanize::cost(session.estimated_cost)
+    );
+    if let Some(ref summary) = session.summary {
+        for summary_line in summary.lines() {
+            line.push_str(&format!("\n  {}", truncate(summary_line, MAX_LINE_CHARS)));
+        }
+    } else if let Some(ref task) = session.current_task {
+        let task = task.lines().next().unwrap_or_default();
+        line.push_str(&format!(": {}", truncate(task, MAX_LINE_CHARS)));
+    }
+    line
+}
+
+fn format_age(age: Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(1))
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Render context as the JSON Claude Code expects on SessionStart hook stdout.
+pub fn to_hook_output(context: &str) -> Value {
+    serde_json::json!({
+        "hookSpecificOutput": {
+            "hookEventName": "SessionStart",
+            "additionalContext": context,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, MemoryEntry};
+
+    async fn remember(storage: &Storage, key: &str, value: Value, tags: Vec<String>) {
+        let now = Utc::now();
+        let entry = MemoryEntry {
+            key: key.to_string(),
+            value,
+            created_at: now,
+            updated_at: now,
+            session_id: None,
+            tags,
+        };
+        storage.upsert_memory(&entry).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_context_scoped_to_project() {
+        let storage = Storage::in_memory();
+        remember(&storage, "test.cmd", Value::from("cargo test"), vec![project_tag("/work/app")]).await;
+        remember(&storage, "unrelated", Value::from("x"), vec![project_tag("/work/other")]).await;
+
+        let mut previous = Session::new(AgentType::ClaudeCode, "/work/app", "old-run");
+        previous.message_count = 12;
+        previous.current_task = Some("Add pagination to /users".to_string());
+        storage.upsert_session(&previous).await.unwrap();
+
+        let mut starting = Session::new(AgentType::ClaudeCode, "/work/app", "new-run");
+        starting.message_count = 1;
+        starting.current_task = Some("Should not appear".to_string());
+        storage.upsert_session(&starting).await.unwrap();
+
+        let context = build_session_context(&storage, &ContextConfig::default(), "/work/app", Some("new-run"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(context.contains("test.cmd: cargo test"));
+        assert!(context.contains("Add pagination to /users"));
+        assert!(!context.contains("unrelated"));
+        assert!(!context.contains("Should not appear"));
+    }
+
+    #[tokio::test]
+    async fn test_no_context_for_new_project() {
+        let storage = Storage::in_memory();
+        let context = build_session_context(&storage, &ContextConfig::default(), "/fresh", None)
+            .await
+            .unwrap();
+        assert!(context.is_none());
+    }
+}
@@ -0,0 +1,252 @@
+//! Synthetic session generator for demo mode.
+//!
+//! Produces sessions and events shaped like the ones the Claude Code adapter
+//! records, so the TUI and dashboard can be exercised without real agents.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use tracing::warn;
+
+use crate::events::EventBus;
+use crate::models::{AgentType, EventType, Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+const PROJECTS: &[&str] = &[
+    "/home/demo/code/api-server",
+    "/home/demo/code/web-dashboard",
+    "/home/demo/code/ml-pipeline",
+    "/home/demo/code/mobile-app",
+    "/home/demo/code/infra",
+    "/home/demo/code/docs-site",
+];
+
+const PROMPTS: &[&str] = &[
+    "Fix the failing integration tests in the auth module",
+    "Add pagination to the /users endpoint",
+    "Refactor the config loader to support environment overrides",
+    "Why is the build slow? Profile it and suggest improvements",
+    "Write unit tests for the rate limiter",
+    "Update the README with the new deployment steps",
+    "Migrate the date handling to chrono",
+];
+
+const FILES: &[&str] = &[
+    "src/main.rs",
+    "src/config.rs",
+    "src/handlers/users.rs",
+    "src/auth/session.rs",
+    "tests/integration.rs",
+    "README.md",
+    "Cargo.toml",
+];
+
+const COMMANDS: &[&str] = &[
+    "cargo test",
+    "cargo build --release",
+    "git status",
+    "npm run lint",
+    "rg TODO src/",
+];
+
+const MODELS: &[&str] = &["claude-sonnet-4-20250514", "claude-opus-4-20250514"];
+
+/// Settings for the synthetic generator.
+#[derive(Debug, Clone)]
+pub struct DemoConfig {
+    /// Number of concurrently active sessions
+    pub sessions: usize,
+    /// Events generated per second across all sessions
+    pub rate: f64,
+}
+
+/// Small xorshift generator; demo data does not need a real RNG.
+struct Rng(u64);
+
+impl Rng {
+    fn seeded() -> Self {
+        let seed = Utc::now().timestamp_nanos_opt().unwrap_or(1) as u64;
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next() % (high - low).max(1) as u64) as i64
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// Where a synthetic session is in its prompt → tools → answer cycle.
+struct DemoSession {
+    session: Session,
+    remaining_tools: usize,
+}
+
+/// Feeds synthetic sessions and events into storage.
+pub struct DemoGenerator {
+    storage: Storage,
+    event_bus: EventBus,
+    config: DemoConfig,
+    rng: Rng,
+    sessions: Vec<DemoSession>,
+}
+
+impl DemoGenerator {
+    /// Create a generator writing to `storage`.
+    pub fn new(storage: Storage, event_bus: EventBus, config: DemoConfig) -> Self {
+        Self {
+            storage,
+            event_bus,
+            config,
+            rng: Rng::seeded(),
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Create the initial sessions, then emit events at the configured rate forever.
+    pub async fn run(mut self) -> Result<()> {
+        for _ in 0..self.config.sessions {
+            let session = self.start_session();
+            self.sessions.push(session);
+        }
+        for demo in &self.sessions {
+            self.storage.upsert_session(&demo.session).await?;
+        }
+
+        if self.sessions.is_empty() {
+            return Ok(());
+        }
+
+        let period = std::time::Duration::from_secs_f64(1.0 / self.config.rate.max(0.1));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.step().await {
+                warn!("Demo generator error: {}", e);
+            }
+        }
+    }
+
+    fn start_session(&mut self) -> DemoSession {
+        let agent_type = match self.rng.below(10) {
+            0 => AgentType::Aider,
+            1 => AgentType::Cursor,
+            _ => AgentType::ClaudeCode,
+        };
+        let project = self.rng.pick(PROJECTS);
+        let external_id = uuid::Uuid::new_v4().to_string();
+
+        let mut session = Session::new(agent_type, project, &external_id);
+        session.started_at = Utc::now() - Duration::minutes(self.rng.range(1, 90));
+        session.model_id = Some(self.rng.pick(MODELS).to_string());
+        session.pid = Some(self.rng.range(10_000, 99_999) as i32);
+        session.metadata.insert("source".to_string(), json!("demo"));
+        session.update_activity();
+
+        DemoSession {
+            session,
+            remaining_tools: 0,
+        }
+    }
+
+    /// Advance one random session by a single event.
+    async fn step(&mut self) -> Result<()> {
+        let index = self.rng.below(self.sessions.len());
+        let tool_count = self.rng.range(1, 6) as usize;
+        let prompt = self.rng.pick(PROMPTS);
+        let tool = self.rng.pick(&["Read", "Edit", "Bash", "Grep", "Write"]);
+        let file = self.rng.pick(FILES);
+        let command = self.rng.pick(COMMANDS);
+        let tokens_in = self.rng.range(500, 12_000);
+        let tokens_out = self.rng.range(50, 2_000);
+        let finished = self.rng.chance(15);
+
+        let demo = &mut self.sessions[index];
+        let project = demo.session.project_path.clone();
+
+        let (event_type, content, tool_name) = if demo.remaining_tools == 0 && demo.session.current_task.is_none() {
+            // Start of a new turn
+            demo.remaining_tools = tool_count;
+            demo.session.current_task = Some(prompt.to_string());
+            (EventType::PromptReceived, prompt.to_string(), None)
+        } else if demo.remaining_tools > 0 {
+            demo.remaining_tools -= 1;
+            demo.session.tool_call_count += 1;
+            let input = match tool {
+                "Bash" => json!({ "command": command }),
+                "Grep" => json!({ "pattern": "fn main", "path": project }),
+                "Edit" => json!({
+                    "file_path": format!("{}/{}", project, file),
+                    "old_string": "let value = 1;",
+                    "new_string": "let value = compute();",
+                }),
+                _ => json!({ "file_path": format!("{}/{}", project, file) }),
+            };
+            if matches!(tool, "Edit" | "Write") {
+                demo.session.file_operations += 1;
+            }
+            let content = format!(
+                "[THINKING]\nLooking at {} next.\n\n[TOOL: {}]\n{}",
+                file,
+                tool,
+                serde_json::to_string_pretty(&input).unwrap_or_default()
+            );
+            (EventType::ResponseGenerated, content, Some(tool.to_string()))
+        } else {
+            // Final answer closes the turn
+            let task = demo.session.current_task.take().unwrap_or_default();
+            let content = format!("Done: {}. All checks pass.", task.to_lowercase());
+            (EventType::ResponseGenerated, content, None)
+        };
+
+        demo.session.message_count += 1;
+        demo.session.tokens_input += tokens_in;
+        demo.session.tokens_output += tokens_out;
+        demo.session.estimated_cost = demo.session.tokens_input as f64 * 3.0 / 1_000_000.0
+            + demo.session.tokens_output as f64 * 15.0 / 1_000_000.0;
+        demo.session.update_activity();
+
+        let mut event = SessionEvent::new(&demo.session.id, event_type, demo.session.agent_type);
+        event.content = Some(content);
+        event.tool_name = tool_name;
+        event.working_directory = Some(project);
+        if event_type == EventType::ResponseGenerated {
+            event.tokens_input = Some(tokens_in);
+            event.tokens_output = Some(tokens_out);
+        }
+
+        self.storage.upsert_session(&demo.session).await?;
+        self.storage.record_event(event, &self.event_bus).await?;
+
+        // Occasionally retire a session between turns and start a fresh one
+        let idle = demo.remaining_tools == 0 && demo.session.current_task.is_none();
+        if idle && finished {
+            demo.session.status = SessionStatus::Completed;
+            demo.session.end();
+            self.storage.upsert_session(&demo.session).await?;
+
+            let replacement = self.start_session();
+            self.storage.upsert_session(&replacement.session).await?;
+            self.sessions[index] = replacement;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! Weekly email digest.
+//!
+//! Once a week (at `weekday`/`hour`, local time) the daemon builds a report
+//! of the last `days` days and mails it as HTML to `recipients` through the
+//! configured SMTP server. The time of the last digest is kept in the data
+//! directory so a restart neither skips nor repeats one; when the digest is
+//! first enabled, the first one goes out at the next scheduled time.
+//! `agent-monitor report --email` sends one immediately.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc, Weekday};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::humanize;
+use crate::report::{self, Report};
+use crate::storage::Storage;
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (port 587)
+    Starttls,
+    /// TLS from the start (port 465)
+    Tls,
+    /// Unencrypted, for local relays only (port 25)
+    None,
+}
+
+/// Email digest settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailDigestConfig {
+    /// Whether the weekly digest is sent
+    pub enabled: bool,
+
+    /// SMTP server host name
+    pub smtp_server: String,
+
+    /// SMTP port (defaults to the standard port for `security`)
+    pub smtp_port: Option<u16>,
+
+    /// `starttls`, `tls` or `none`
+    pub security: SmtpSecurity,
+
+    /// SMTP user name
+    pub username: Option<String>,
+
+    /// Environment variable holding the SMTP password
+    pub password_env: Option<String>,
+
+    /// Sender address, e.g. `Agent Monitor <monitor@example.com>`
+    pub from: String,
+
+    /// Recipient addresses
+    pub recipients: Vec<String>,
+
+    /// Day the digest goes out
+    pub weekday: Weekday,
+
+    /// Local hour (0–23) the digest goes out
+    pub hour: u32,
+
+    /// Days covered by each digest
+    pub days: i64,
+}
+
+impl Default for EmailDigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            smtp_server: String::new(),
+            smtp_port: None,
+            security: SmtpSecurity::Starttls,
+            username: None,
+            password_env: Some("AGENT_MONITOR_SMTP_PASSWORD".to_string()),
+            from: String::new(),
+            recipients: Vec::new(),
+            weekday: Weekday::Mon,
+            hour: 9,
+            days: 7,
+        }
+    }
+}
+
+/// How often the schedule is checked.
+const CHECK_INTERVAL_SECS: u64 = 600;
+
+/// Build the report and mail it to every recipient.
+pub async fn send_digest(config: &EmailDigestConfig, storage: &Storage) -> Result<Report> {
+    if config.smtp_server.is_empty() || config.from.is_empty() || config.recipients.is_empty() {
+        bail!("email_digest needs smtp_server, from and at least one recipient");
+    }
+
+    let mut report = report::build_report(storage, config.days, None, None).await?;
+    let offset = chrono::Duration::days(config.days.max(1));
+    report.comparison = Some(report::compare(storage, &report, offset, None, None).await?);
+
+    let mut message = Message::builder()
+        .from(config.from.parse::<Mailbox>().with_context(|| format!("Invalid from address {}", config.from))?)
+        .subject(format!(
+            "Agent Monitor digest: {} across {} sessions",
+            humanize::cost(report.cost), report.sessions
+        ))
+        .header(ContentType::TEXT_HTML);
+    for recipient in &config.recipients {
+        message = message.to(recipient.parse().with_context(|| format!("Invalid recipient {}", recipient))?);
+    }
+    let message = message.body(report.to_html())?;
+
+    let mut transport = match config.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_server)?,
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_server)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_server),
+    };
+    if let Some(port) = config.smtp_port {
+        transport = transport.port(port);
+    }
+    if let Some(ref username) = config.username {
+        let var = config
+            .password_env
+            .as_deref()
+            .ok_or_else(|| anyhow!("email_digest.username is set but password_env is not"))?;
+        let password = std::env::var(var).map_err(|_| anyhow!("{} is not set", var))?;
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("Failed to send digest through {}", config.smtp_server))?;
+
+    Ok(report)
+}
+
+/// The most recent scheduled send time at or before `now`.
+fn last_scheduled(now: DateTime<Local>, weekday: Weekday, hour: u32) -> Option<DateTime<Local>> {
+    let time = NaiveTime::from_hms_opt(hour.min(23), 0, 0)?;
+    (0..=7).find_map(|back| {
+        let date = now.date_naive() - Duration::days(back);
+        if date.weekday() != weekday {
+            return None;
+        }
+        let scheduled = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+        (scheduled <= now).then_some(scheduled)
+    })
+}
+
+/// Whether a digest is due, given when the last one went out.
+fn is_due(now: DateTime<Local>, last_sent: DateTime<Utc>, weekday: Weekday, hour: u32) -> bool {
+    last_scheduled(now, weekday, hour).is_some_and(|scheduled| last_sent < scheduled)
+}
+
+/// Sends the digest on schedule.
+pub struct DigestScheduler {
+    config: EmailDigestConfig,
+    storage: Storage,
+    state_path: PathBuf,
+}
+
+impl DigestScheduler {
+    pub fn new(config: EmailDigestConfig, storage: Storage, data_dir: PathBuf) -> Self {
+        Self {
+            config,
+            storage,
+            state_path: data_dir.join("digest_last_sent"),
+        }
+    }
+
+    fn last_sent(&self) -> Option<DateTime<Utc>> {
+        let content = std::fs::read_to_string(&self.state_path).ok()?;
+        DateTime::parse_from_rfc3339(content.trim()).ok().map(|t| t.with_timezone(&Utc))
+    }
+
+    fn record_sent(&self, at: DateTime<Utc>) {
+        if let Err(e) = std::fs::write(&self.state_path, at.to_rfc3339()) {
+            warn!("Failed to record digest time in {}: {}", self.state_path.display(), e);
+        }
+    }
+
+    /// Check the schedule every few minutes and send when due.
+    pub async fn run(self) {
+        if self.last_sent().is_none() {
+            self.record_sent(Utc::now());
+        }
+
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            let last_sent = self.last_sent().unwrap_or_else(Utc::now);
+            if !is_due(Local::now(), last_sent, self.config.weekday, self.config.hour) {
+                continue;
+            }
+
+            match send_digest(&self.config, &self.storage).await {
+                Ok(report) => {
+                    info!(
+                        "Sent email digest to {} recipients ({} sessions)",
+                        self.config.recipients.len(),
+                        report.sessions
+                    );
+                    self.record_sent(Utc::now());
+                }
+                // Retried at the next check
+                Err(e) => warn!("Email digest failed: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_digest_schedule() {
+        // 2026-10-12 is a Monday
+        let monday_morning = local(2026, 10, 12, 9);
+        let sunday = local(2026, 10, 11, 12);
+        let last_week = local(2026, 10, 5, 9).with_timezone(&Utc);
+
+        assert!(is_due(monday_morning, last_week, Weekday::Mon, 9));
+        assert!(!is_due(sunday, last_week, Weekday::Mon, 9));
+        // Already sent after this week's slot
+        assert!(!is_due(monday_morning, monday_morning.with_timezone(&Utc), Weekday::Mon, 9));
+        // Before the hour on the day itself, the slot is last week's
+        assert!(!is_due(local(2026, 10, 12, 8), last_week, Weekday::Mon, 9));
+    }
+
+    #[test]
+    fn test_config_parses_weekday() {
+        let config: EmailDigestConfig =
+            serde_json::from_str(r#"{"enabled": true, "weekday": "Fri", "security": "tls"}"#).unwrap();
+        assert_eq!(config.weekday, Weekday::Fri);
+        assert_eq!(config.security, SmtpSecurity::Tls);
+        assert_eq!(config.hour, 9);
+    }
+}
@@ -0,0 +1,254 @@
+//! Health checks behind `agent-monitor doctor`.
+//!
+//! Each check looks at one thing a working monitor needs (config, database,
+//! daemon, hooks) and says how to fix it when it is wrong. A failing check
+//! doesn't stop the others, so one run shows everything that needs fixing.
+//! The report also says how much space shared content blobs save.
+//!
+//! On Linux, inotify watches are counted against the per-user limits: every
+//! watched directory takes one, shared with editors and file syncers, and
+//! running out silently stops file events.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::api;
+use crate::config::Config;
+use crate::models::BlobStats;
+use crate::storage::Storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+    /// None when the database can't be read
+    pub content: Option<BlobStats>,
+}
+
+impl DoctorReport {
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+}
+
+/// Run every check against the current profile's setup.
+pub async fn run() -> DoctorReport {
+    let mut checks = Vec::new();
+    let config_path = Config::user_config_path();
+    let config = match Config::load_or_default() {
+        Ok(config) => {
+            let detail = if config_path.exists() {
+                config_path.display().to_string()
+            } else {
+                "defaults (no config file)".to_string()
+            };
+            checks.push(Check::new("config", CheckStatus::Ok, detail));
+            config
+        }
+        Err(e) => {
+            checks.push(Check::new("config", CheckStatus::Fail, format!("{}: {:#}", config_path.display(), e)));
+            Config::default()
+        }
+    };
+
+    let (check, storage) = check_database(&config).await;
+    checks.push(check);
+    let content = match storage {
+        Some(ref storage) => {
+            let (check, stats) = check_content(storage).await;
+            checks.push(check);
+            stats
+        }
+        None => None,
+    };
+
+    checks.push(check_daemon(&config).await);
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    checks.push(check_hooks(&Path::new(&home).join(".claude/hooks")));
+    if let Some(check) = check_inotify() {
+        checks.push(check);
+    }
+
+    DoctorReport { checks, content }
+}
+
+async fn check_database(config: &Config) -> (Check, Option<Storage>) {
+    if config.uses_local_db() && !config.db_path.exists() {
+        let detail = format!("{} not found; start the daemon to create it", config.db_path.display());
+        return (Check::new("database", CheckStatus::Fail, detail), None);
+    }
+    let storage = match Storage::connect(config).await {
+        Ok(storage) => storage,
+        Err(e) => return (Check::new("database", CheckStatus::Fail, format!("{:#}", e)), None),
+    };
+    match storage.get_summary_metrics(24).await {
+        Ok(metrics) => {
+            let location = match config.redacted_database_url() {
+                Some(url) => url,
+                None => config.db_path.display().to_string(),
+            };
+            let detail = format!("{} ({} sessions in 24h)", location, metrics.total_sessions);
+            (Check::new("database", CheckStatus::Ok, detail), Some(storage))
+        }
+        Err(e) => (Check::new("database", CheckStatus::Fail, format!("{:#}", e)), None),
+    }
+}
+
+async fn check_content(storage: &Storage) -> (Check, Option<BlobStats>) {
+    match storage.get_blob_stats().await {
+        Ok(stats) => {
+            let detail = format!("{} blobs shared by {} events", stats.blobs, stats.references);
+            (Check::new("content", CheckStatus::Ok, detail), Some(stats))
+        }
+        // Databases from before the blob table get it when the daemon next starts
+        Err(e) => (Check::new("content", CheckStatus::Warn, format!("{:#}; restart the daemon", e)), None),
+    }
+}
+
+async fn check_daemon(config: &Config) -> Check {
+    let request = serde_json::json!({ "action": "get_adapters" });
+    match api::ipc_request(&config.socket_path, &request).await {
+        Ok(_) => Check::new("daemon", CheckStatus::Ok, format!("running at {}", config.socket_path.display())),
+        Err(_) => Check::new(
+            "daemon",
+            CheckStatus::Warn,
+            format!("not running at {}; start it with 'agent-monitor daemon'", config.socket_path.display()),
+        ),
+    }
+}
+
+fn check_hooks(hooks_dir: &Path) -> Check {
+    let missing: Vec<&str> = crate::HOOK_EVENTS
+        .iter()
+        .copied()
+        .filter(|event| !hooks_dir.join(format!("{}.sh", event)).exists())
+        .collect();
+    if missing.is_empty() {
+        Check::new("hooks", CheckStatus::Ok, format!("{} hooks in {}", crate::HOOK_EVENTS.len(), hooks_dir.display()))
+    } else {
+        Check::new(
+            "hooks",
+            CheckStatus::Warn,
+            format!("missing {}; run 'agent-monitor install-hooks'", missing.join(", ")),
+        )
+    }
+}
+
+/// Share of an inotify limit in use that is worth a warning.
+const INOTIFY_WARN_SHARE: f64 = 0.8;
+
+/// Watch limits this low are likely run out of (the old kernel default).
+const INOTIFY_LOW_WATCHES: u64 = 8_192;
+
+/// inotify limits and use; None where there is no inotify.
+fn check_inotify() -> Option<Check> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let limit = |name: &str| -> Option<u64> {
+        std::fs::read_to_string(format!("/proc/sys/fs/inotify/{}", name)).ok()?.trim().parse().ok()
+    };
+    Some(inotify_check(limit("max_user_watches")?, limit("max_user_instances")?, inotify_usage()))
+}
+
+fn inotify_check(max_watches: u64, max_instances: u64, usage: Option<(u64, u64)>) -> Check {
+    const HINT: &str = "; raise it with 'sudo sysctl fs.inotify.max_user_watches=524288' \
+                        (and in /etc/sysctl.d to keep it), or set watch.mode to poll";
+    let Some((watches, instances)) = usage else {
+        let detail = format!("limit of {} watches and {} instances", max_watches, max_instances);
+        return if max_watches <= INOTIFY_LOW_WATCHES {
+            Check::new("inotify", CheckStatus::Warn, format!("{}{}", detail, HINT))
+        } else {
+            Check::new("inotify", CheckStatus::Ok, detail)
+        };
+    };
+    let detail = format!("{} of {} watches and {} of {} instances in use", watches, max_watches, instances, max_instances);
+    let near = |used: u64, max: u64| used as f64 >= max as f64 * INOTIFY_WARN_SHARE;
+    if near(instances, max_instances) {
+        let hint = "; raise fs.inotify.max_user_instances with sysctl";
+        Check::new("inotify", CheckStatus::Warn, format!("{}{}", detail, hint))
+    } else if near(watches, max_watches) {
+        Check::new("inotify", CheckStatus::Warn, format!("{}{}", detail, HINT))
+    } else {
+        Check::new("inotify", CheckStatus::Ok, detail)
+    }
+}
+
+/// inotify watches and instances of the processes this user can see, from
+/// their `fdinfo`. None when `/proc` can't be read.
+fn inotify_usage() -> Option<(u64, u64)> {
+    let (mut watches, mut instances) = (0, 0);
+    for process in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(fds) = std::fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            if std::fs::read_link(fd.path()).is_ok_and(|target| target == Path::new("anon_inode:inotify")) {
+                instances += 1;
+                let info = process.path().join("fdinfo").join(fd.file_name());
+                let info = std::fs::read_to_string(info).unwrap_or_default();
+                watches += info.lines().filter(|line| line.starts_with("inotify wd:")).count() as u64;
+            }
+        }
+    }
+    Some((watches, instances))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_hooks_are_named() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = check_hooks(dir.path());
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.starts_with("missing SessionStart, UserPromptSubmit"));
+
+        for event in crate::HOOK_EVENTS {
+            std::fs::write(dir.path().join(format!("{}.sh", event)), "").unwrap();
+        }
+        assert_eq!(check_hooks(dir.path()).status, CheckStatus::Ok);
+
+        let report = DoctorReport {
+            checks: vec![check, Check::new("database", CheckStatus::Fail, "not found")],
+            content: None,
+        };
+        assert!(!report.healthy());
+    }
+
+    #[test]
+    fn test_inotify_limits() {
+        let check = inotify_check(65_536, 128, Some((1_200, 9)));
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(check.detail, "1200 of 65536 watches and 9 of 128 instances in use");
+
+        let full = inotify_check(8_192, 128, Some((8_000, 9)));
+        assert_eq!(full.status, CheckStatus::Warn);
+        assert!(full.detail.contains("max_user_watches=524288"));
+        assert!(inotify_check(65_536, 128, Some((10, 120))).detail.contains("max_user_instances"));
+        assert_eq!(inotify_check(8_192, 128, None).status, CheckStatus::Warn);
+        assert_eq!(inotify_check(524_288, 128, None).status, CheckStatus::Ok);
+    }
+}
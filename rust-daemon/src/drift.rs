@@ -0,0 +1,467 @@
+//! Claude Code settings drift.
+//!
+//! Every `interval_secs` the watcher reads Claude Code's user settings, the
+//! project settings of active sessions and the newest shell snapshot, and
+//! compares them with what it saw last. A change becomes a ConfigChanged
+//! event on every active session it applies to, and on sessions that start
+//! within `carry_hours` afterwards, so "it started behaving differently" can
+//! be traced to the edit. What was last seen is kept in the data directory,
+//! so edits made while the daemon was down are caught too. Environment
+//! variables and shell snapshot entries are stored as hashes: only their
+//! names show up in events.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::time::{interval, Duration};
+use tracing::{debug, info, warn};
+
+use crate::events::EventBus;
+use crate::models::{AgentType, EventType, Session, SessionEvent};
+use crate::storage::Storage;
+
+/// Most active sessions checked per round.
+const MAX_ACTIVE: usize = 500;
+
+/// Settings files of a scope, under `~/.claude` or `<project>/.claude`.
+const SETTINGS_FILES: [&str; 2] = ["settings.json", "settings.local.json"];
+
+/// Marks a value replaced by its hash.
+const REDACTED: &str = "redacted:";
+
+/// Changes listed in an event's content; the rest are only in its raw data.
+const MAX_DESCRIBED: usize = 8;
+
+/// Settings drift watching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriftConfig {
+    /// Whether Claude Code settings are watched for changes
+    pub enabled: bool,
+
+    /// Seconds between checks
+    pub interval_secs: u64,
+
+    /// Hours a change is still reported to sessions that start after it
+    pub carry_hours: u64,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 30,
+            carry_hours: 24,
+        }
+    }
+}
+
+/// One changed setting, by dotted key. `before` is None when it was added,
+/// `after` when it was removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingChange {
+    pub key: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// A change to one file, waiting to reach the sessions it applies to.
+struct Drift {
+    path: PathBuf,
+    /// Project whose sessions it applies to; None for user-wide files
+    project: Option<String>,
+    changes: Vec<SettingChange>,
+    detected_at: DateTime<Utc>,
+    /// Sessions that already have the event
+    told: HashSet<String>,
+}
+
+/// Periodically compares Claude Code's settings with what was last seen.
+pub struct SettingsWatcher {
+    config: DriftConfig,
+    storage: Storage,
+    event_bus: EventBus,
+    claude_home: PathBuf,
+    state_path: PathBuf,
+    /// Last seen, redacted content of each watched file; Null when missing
+    seen: BTreeMap<PathBuf, Value>,
+    pending: Vec<Drift>,
+}
+
+impl SettingsWatcher {
+    pub fn new(config: DriftConfig, storage: Storage, event_bus: EventBus, claude_home: PathBuf, data_dir: &Path) -> Self {
+        let state_path = data_dir.join("claude_settings_seen.json");
+        let seen = std::fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Self {
+            config,
+            storage,
+            event_bus,
+            claude_home,
+            state_path,
+            seen,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Check until the daemon stops.
+    pub async fn run(mut self) {
+        let mut ticker = interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.check().await {
+                warn!("Settings drift check failed: {}", e);
+            }
+        }
+    }
+
+    async fn check(&mut self) -> Result<()> {
+        let sessions: Vec<Session> = self
+            .storage
+            .get_active_sessions(MAX_ACTIVE)
+            .await?
+            .into_iter()
+            .filter(|s| s.agent_type == AgentType::ClaudeCode)
+            .collect();
+
+        let mut watched: Vec<(PathBuf, Option<String>)> =
+            SETTINGS_FILES.iter().map(|f| (self.claude_home.join(f), None)).collect();
+        let projects: BTreeSet<&str> = sessions.iter().map(|s| s.project_path.as_str()).collect();
+        for project in projects.into_iter().filter(|p| !p.is_empty()) {
+            let dir = Path::new(project).join(".claude");
+            watched.extend(SETTINGS_FILES.iter().map(|f| (dir.join(f), Some(project.to_string()))));
+        }
+
+        let now = Utc::now();
+        let mut dirty = false;
+        for (path, project) in watched {
+            let current = read_settings(&path);
+            dirty |= self.observe(path, project, current, now);
+        }
+        let snapshots = self.claude_home.join("shell-snapshots");
+        if let Some(current) = newest_file(&snapshots).map(|file| read_snapshot(&file)) {
+            dirty |= self.observe(snapshots, None, current, now);
+        }
+        if dirty {
+            std::fs::write(&self.state_path, serde_json::to_string_pretty(&self.seen)?)?;
+        }
+
+        let cutoff = now - ChronoDuration::hours(self.config.carry_hours as i64);
+        self.pending.retain(|d| d.detected_at >= cutoff);
+        for drift in &mut self.pending {
+            for session in &sessions {
+                if drift.told.contains(&session.id)
+                    || drift.project.as_deref().is_some_and(|p| p != session.project_path)
+                {
+                    continue;
+                }
+                let event = drift_event(drift, session);
+                self.storage.record_event(event, &self.event_bus).await?;
+                drift.told.insert(session.id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare a file's content with what was last seen, queueing the
+    /// difference. A file never seen before only sets the baseline. Returns
+    /// whether anything changed.
+    fn observe(&mut self, path: PathBuf, project: Option<String>, current: Value, now: DateTime<Utc>) -> bool {
+        let Some(previous) = self.seen.get(&path) else {
+            self.seen.insert(path, current);
+            return true;
+        };
+        let changes = diff(previous, &current);
+        if changes.is_empty() {
+            return false;
+        }
+        info!("Claude Code settings changed in {}: {}", path.display(), describe(&changes));
+        self.seen.insert(path.clone(), current);
+        self.pending.push(Drift {
+            path,
+            project,
+            changes,
+            detected_at: now,
+            told: HashSet::new(),
+        });
+        true
+    }
+}
+
+/// A settings file with its `env` values hashed; Null when missing or unreadable.
+fn read_settings(path: &Path) -> Value {
+    let Some(mut settings) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+    else {
+        return Value::Null;
+    };
+    if let Some(env) = settings.get_mut("env").and_then(Value::as_object_mut) {
+        for value in env.values_mut() {
+            *value = redact(&value.to_string());
+        }
+    }
+    settings
+}
+
+/// The most recently modified file in a directory.
+fn newest_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .filter(|(_, path)| path.is_file())
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// A shell snapshot as hashed exports and aliases; everything else
+/// (functions, shell options) is hashed together as `other`.
+fn read_snapshot(path: &Path) -> Value {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse_snapshot(&text),
+        Err(e) => {
+            debug!("Could not read shell snapshot {}: {}", path.display(), e);
+            Value::Null
+        }
+    }
+}
+
+fn parse_snapshot(text: &str) -> Value {
+    let mut exports = Map::new();
+    let mut aliases = Map::new();
+    let mut other = String::new();
+    for line in text.lines().map(str::trim) {
+        let entry = line
+            .strip_prefix("export ")
+            .map(|rest| (&mut exports, rest))
+            .or_else(|| line.strip_prefix("alias ").map(|rest| (&mut aliases, rest)));
+        match entry.and_then(|(map, rest)| Some((map, rest.trim_start_matches("-- ").split_once('=')?))) {
+            Some((map, (name, value))) => {
+                map.insert(name.to_string(), redact(value));
+            }
+            None if line.is_empty() || line.starts_with('#') => {}
+            None => {
+                other.push_str(line);
+                other.push('\n');
+            }
+        }
+    }
+    json!({ "export": exports, "alias": aliases, "other": redact(&other) })
+}
+
+fn redact(value: &str) -> Value {
+    Value::String(format!("{}{:x}", REDACTED, Sha256::digest(value.as_bytes())))
+}
+
+/// Differences between two settings documents, by dotted key. Objects are
+/// compared key by key; anything else, arrays included, as a whole.
+pub fn diff(before: &Value, after: &Value) -> Vec<SettingChange> {
+    let mut changes = Vec::new();
+    diff_into("", before, after, &mut changes);
+    changes
+}
+
+fn diff_into(prefix: &str, before: &Value, after: &Value, changes: &mut Vec<SettingChange>) {
+    // A missing file compares as an empty one
+    let empty = Map::new();
+    let (Some(old), Some(new)) = (as_object(before, prefix, &empty), as_object(after, prefix, &empty)) else {
+        if before != after {
+            changes.push(SettingChange {
+                key: prefix.to_string(),
+                before: Some(before.clone()),
+                after: Some(after.clone()),
+            });
+        }
+        return;
+    };
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match (old.get(key), new.get(key)) {
+            (Some(a), Some(b)) => diff_into(&path, a, b, changes),
+            (a, b) => changes.push(SettingChange {
+                key: path,
+                before: a.cloned(),
+                after: b.cloned(),
+            }),
+        }
+    }
+}
+
+fn as_object<'a>(value: &'a Value, prefix: &str, empty: &'a Map<String, Value>) -> Option<&'a Map<String, Value>> {
+    match value {
+        Value::Object(map) => Some(map),
+        Value::Null if prefix.is_empty() => Some(empty),
+        _ => None,
+    }
+}
+
+/// Changes in a line, e.g. "model: sonnet → opus; permissions.allow: +Bash(npm test)".
+pub fn describe(changes: &[SettingChange]) -> String {
+    let mut parts: Vec<String> = changes.iter().take(MAX_DESCRIBED).map(describe_change).collect();
+    if changes.len() > MAX_DESCRIBED {
+        parts.push(format!("{} more", changes.len() - MAX_DESCRIBED));
+    }
+    parts.join("; ")
+}
+
+fn describe_change(change: &SettingChange) -> String {
+    let redacted = |v: &Option<Value>| v.as_ref().and_then(Value::as_str).is_some_and(|s| s.starts_with(REDACTED));
+    let scalar = |v: &Value| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(v.to_string()),
+        _ => None,
+    };
+    let key = &change.key;
+    match (&change.before, &change.after) {
+        (None, Some(v)) => match scalar(v).filter(|_| !redacted(&change.after)) {
+            Some(v) => format!("{} = {} added", key, v),
+            None => format!("{} added", key),
+        },
+        (Some(_), None) => format!("{} removed", key),
+        _ if redacted(&change.before) || redacted(&change.after) => format!("{} changed", key),
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            let items = |list: &[Value]| list.iter().map(|v| scalar(v).unwrap_or_else(|| v.to_string())).collect::<Vec<_>>();
+            let (old, new) = (items(old), items(new));
+            let added = new.iter().filter(|v| !old.contains(v)).map(|v| format!("+{}", v));
+            let removed = old.iter().filter(|v| !new.contains(v)).map(|v| format!("-{}", v));
+            let list: Vec<String> = added.chain(removed).collect();
+            if list.is_empty() {
+                format!("{} reordered", key)
+            } else {
+                format!("{}: {}", key, list.join(", "))
+            }
+        }
+        (Some(a), Some(b)) => match (scalar(a), scalar(b)) {
+            (Some(a), Some(b)) => format!("{}: {} → {}", key, a, b),
+            _ => format!("{} changed", key),
+        },
+        (None, None) => key.clone(),
+    }
+}
+
+fn drift_event(drift: &Drift, session: &Session) -> SessionEvent {
+    let shell = drift.path.ends_with("shell-snapshots");
+    let content = if shell {
+        format!("Shell environment changed: {}", describe(&drift.changes))
+    } else {
+        format!("Claude Code settings changed ({}): {}", drift.path.display(), describe(&drift.changes))
+    };
+    let timestamp = drift.detected_at.max(session.started_at);
+    let mut event = SessionEvent::new_with_stable_id(
+        &session.id,
+        EventType::ConfigChanged,
+        AgentType::ClaudeCode,
+        timestamp,
+        Some(&content),
+    );
+    event.file_path = Some(drift.path.to_string_lossy().to_string());
+    event.working_directory = Some(session.project_path.clone());
+    event.raw_data = Some(json!({
+        "path": drift.path,
+        "scope": if shell { "shell" } else if drift.project.is_some() { "project" } else { "user" },
+        "detected_at": drift.detected_at,
+        "changes": drift.changes,
+    }));
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_describe() {
+        let before = json!({
+            "model": "sonnet",
+            "permissions": {"allow": ["Bash(ls)", "Read"]},
+            "env": {"API_TOKEN": redact("\"old\"")},
+        });
+        let after = json!({
+            "model": "opus",
+            "permissions": {"allow": ["Read", "Bash(npm test)"]},
+            "env": {"API_TOKEN": redact("\"new\"")},
+            "hooks": {"PreToolUse": [{"matcher": "Bash"}]},
+        });
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(),
+            vec!["env.API_TOKEN", "hooks", "model", "permissions.allow"]
+        );
+        assert_eq!(
+            describe(&changes),
+            "env.API_TOKEN changed; hooks added; model: sonnet → opus; permissions.allow: +Bash(npm test), -Bash(ls)"
+        );
+
+        // A file appearing or disappearing lists its top-level keys
+        assert_eq!(describe(&diff(&after, &Value::Null)).matches("removed").count(), 4);
+        assert!(diff(&after, &after).is_empty());
+
+        let snapshot = parse_snapshot("# Snapshot\nexport PATH=/usr/bin\nalias ll='ls -l'\nshopt -s extglob\n");
+        assert!(snapshot["export"]["PATH"].as_str().unwrap().starts_with(REDACTED));
+        assert!(snapshot["alias"].get("ll").is_some());
+        assert!(!snapshot.to_string().contains("/usr/bin"));
+    }
+
+    #[tokio::test]
+    async fn test_changes_reach_active_and_later_sessions() {
+        let home = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let storage = Storage::in_memory();
+        let settings = home.path().join("settings.json");
+        std::fs::write(&settings, r#"{"model": "sonnet", "env": {"TOKEN": "secret-1"}}"#).unwrap();
+
+        let running = Session::new(AgentType::ClaudeCode, &project.path().to_string_lossy(), "a");
+        storage.upsert_session(&running).await.unwrap();
+        let mut watcher = SettingsWatcher::new(
+            DriftConfig::default(),
+            storage.clone(),
+            EventBus::new(),
+            home.path().to_path_buf(),
+            home.path(),
+        );
+
+        // The first check only records what is there
+        watcher.check().await.unwrap();
+        assert!(storage.get_session_events(&running.id, 10).await.unwrap().is_empty());
+
+        std::fs::write(&settings, r#"{"model": "opus", "env": {"TOKEN": "secret-2"}}"#).unwrap();
+        std::fs::create_dir_all(project.path().join(".claude")).unwrap();
+        std::fs::write(project.path().join(".claude/settings.json"), r#"{"permissions": {"allow": []}}"#).unwrap();
+        watcher.check().await.unwrap();
+        watcher.check().await.unwrap();
+
+        let mut events = storage.get_session_events(&running.id, 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        events.retain(|e| e.file_path.as_deref() == Some(settings.to_str().unwrap()));
+        assert_eq!(events[0].event_type, EventType::ConfigChanged);
+        let content = events[0].content.as_deref().unwrap();
+        assert!(content.contains("model: sonnet → opus") && content.contains("env.TOKEN changed"));
+        assert!(!events[0].raw_data.as_ref().unwrap().to_string().contains("secret"));
+
+        // A session starting later still hears about the user-wide change but
+        // not the other project's, and the state survives a restart
+        let later = Session::new(AgentType::ClaudeCode, "/elsewhere", "b");
+        storage.upsert_session(&later).await.unwrap();
+        watcher.check().await.unwrap();
+        assert_eq!(storage.get_session_events(&later.id, 10).await.unwrap().len(), 1);
+
+        std::fs::write(&settings, r#"{"model": "haiku", "env": {"TOKEN": "secret-2"}}"#).unwrap();
+        let mut restarted = SettingsWatcher::new(
+            DriftConfig::default(),
+            storage.clone(),
+            EventBus::new(),
+            home.path().to_path_buf(),
+            home.path(),
+        );
+        restarted.check().await.unwrap();
+        assert_eq!(storage.get_session_events(&later.id, 10).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,264 @@
+//! Duplicate prompt detection across sessions.
+//!
+//! Prompts are normalized (case, punctuation and whitespace folded) and
+//! grouped when they are identical after normalization or their word sets
+//! overlap by at least `min_similarity` (Jaccard). Only groups that span
+//! more than one session are reported: the same request sent to several
+//! sessions or projects usually means paying for the same work twice.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Duplicate prompt detection settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicatesConfig {
+    /// Word-set overlap (0–1) at which two prompts count as the same request
+    pub min_similarity: f64,
+
+    /// Prompts shorter than this after normalization ("yes", "continue") are ignored
+    pub min_chars: usize,
+
+    /// How far back to look, in hours
+    pub lookback_hours: i64,
+}
+
+impl Default for DuplicatesConfig {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.85,
+            min_chars: 24,
+            lookback_hours: 30 * 24,
+        }
+    }
+}
+
+/// Most prompts compared per scan.
+const MAX_PROMPTS: usize = 2000;
+
+/// Length of the prompt preview in results.
+const PREVIEW_CHARS: usize = 200;
+
+/// One place a prompt was sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptOccurrence {
+    pub event_id: String,
+    pub session_id: String,
+    pub project_path: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A prompt sent to more than one session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicatePrompt {
+    /// Preview of the first occurrence
+    pub prompt: String,
+    /// Lowest similarity of any occurrence to the first one
+    pub similarity: f64,
+    /// Oldest first
+    pub occurrences: Vec<PromptOccurrence>,
+}
+
+impl DuplicatePrompt {
+    /// The earliest occurrence in a session other than `session_id`, if
+    /// that session also sent this prompt ("you've asked this before").
+    pub fn earlier_than(&self, session_id: &str) -> Option<&PromptOccurrence> {
+        let first_here = self.occurrences.iter().find(|o| o.session_id == session_id)?;
+        self.occurrences
+            .iter()
+            .find(|o| o.session_id != session_id && o.timestamp <= first_here.timestamp)
+    }
+}
+
+struct Prompt {
+    occurrence: PromptOccurrence,
+    preview: String,
+    normalized: String,
+    words: HashSet<String>,
+}
+
+/// Lowercase, drop punctuation and collapse whitespace.
+pub fn normalize_prompt(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn similarity(a: &Prompt, b: &Prompt) -> f64 {
+    if a.normalized == b.normalized {
+        return 1.0;
+    }
+    let shared = a.words.intersection(&b.words).count();
+    let total = a.words.len() + b.words.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Find prompts sent to more than one session, most recently repeated first.
+/// With `session_id`, only groups that include that session are returned.
+pub async fn find_duplicates(
+    storage: &Storage,
+    config: &DuplicatesConfig,
+    session_id: Option<&str>,
+) -> Result<Vec<DuplicatePrompt>> {
+    let mut events = storage
+        .get_recent_events_of_type(EventType::PromptReceived, config.lookback_hours, MAX_PROMPTS)
+        .await?;
+    events.reverse();
+
+    let mut projects: HashMap<String, String> = HashMap::new();
+    let mut prompts = Vec::new();
+    for event in events {
+        let Some(prompt) = to_prompt(storage, &mut projects, event, config).await? else {
+            continue;
+        };
+        prompts.push(prompt);
+    }
+
+    let mut groups = group_prompts(prompts, config.min_similarity);
+    if let Some(id) = session_id {
+        groups.retain(|g| g.occurrences.iter().any(|o| o.session_id == id));
+    }
+    groups.sort_by_key(|g| std::cmp::Reverse(g.occurrences.last().map(|o| o.timestamp)));
+    Ok(groups)
+}
+
+async fn to_prompt(
+    storage: &Storage,
+    projects: &mut HashMap<String, String>,
+    event: SessionEvent,
+    config: &DuplicatesConfig,
+) -> Result<Option<Prompt>> {
+    let Some(content) = event.content else {
+        return Ok(None);
+    };
+    let normalized = normalize_prompt(&content);
+    if normalized.len() < config.min_chars {
+        return Ok(None);
+    }
+
+    if !projects.contains_key(&event.session_id) {
+        let project = match storage.get_session(&event.session_id).await? {
+            Some(session) => session.project_path,
+            None => event.working_directory.clone().unwrap_or_default(),
+        };
+        projects.insert(event.session_id.clone(), project);
+    }
+
+    Ok(Some(Prompt {
+        occurrence: PromptOccurrence {
+            event_id: event.id,
+            project_path: projects[&event.session_id].clone(),
+            session_id: event.session_id,
+            timestamp: event.timestamp,
+        },
+        preview: content.trim().chars().take(PREVIEW_CHARS).collect(),
+        words: normalized.split(' ').map(str::to_string).collect(),
+        normalized,
+    }))
+}
+
+/// Cluster prompts (oldest first) against each group's first prompt and keep
+/// the groups that span several sessions.
+fn group_prompts(prompts: Vec<Prompt>, min_similarity: f64) -> Vec<DuplicatePrompt> {
+    let mut groups: Vec<(Prompt, DuplicatePrompt)> = Vec::new();
+    let mut exact: HashMap<String, usize> = HashMap::new();
+
+    'prompts: for prompt in prompts {
+        if let Some(&index) = exact.get(&prompt.normalized) {
+            groups[index].1.occurrences.push(prompt.occurrence);
+            continue;
+        }
+
+        for (first, group) in groups.iter_mut() {
+            // Jaccard can't reach the threshold if the sizes differ too much
+            let (small, large) = if first.words.len() < prompt.words.len() {
+                (first.words.len(), prompt.words.len())
+            } else {
+                (prompt.words.len(), first.words.len())
+            };
+            if (small as f64) < large as f64 * min_similarity {
+                continue;
+            }
+
+            let score = similarity(first, &prompt);
+            if score >= min_similarity {
+                group.similarity = group.similarity.min(score);
+                group.occurrences.push(prompt.occurrence);
+                continue 'prompts;
+            }
+        }
+
+        exact.insert(prompt.normalized.clone(), groups.len());
+        let group = DuplicatePrompt {
+            prompt: prompt.preview.clone(),
+            similarity: 1.0,
+            occurrences: vec![prompt.occurrence.clone()],
+        };
+        groups.push((prompt, group));
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|g| {
+            let sessions: HashSet<&str> = g.occurrences.iter().map(|o| o.session_id.as_str()).collect();
+            sessions.len() > 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, Session};
+
+    async fn prompt(storage: &Storage, project: &str, text: &str) -> Session {
+        let session = Session::new(AgentType::ClaudeCode, project, &uuid::Uuid::new_v4().to_string());
+        storage.upsert_session(&session).await.unwrap();
+        let mut event = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        event.content = Some(text.to_string());
+        storage.insert_event(&event).await.unwrap();
+        session
+    }
+
+    #[test]
+    fn test_normalize_prompt() {
+        assert_eq!(normalize_prompt("  Fix the LOGIN bug!!\n\nThanks. "), "fix the login bug thanks");
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_across_sessions() {
+        let storage = Storage::in_memory();
+        let first = prompt(&storage, "/work/api", "Add retry with exponential backoff to the payment client").await;
+        let second = prompt(&storage, "/work/web", "add retry with exponential backoff to the payment client.").await;
+        let third = prompt(&storage, "/work/api", "Please add retry with exponential backoff to the payment client").await;
+        prompt(&storage, "/work/api", "Write a README section about deployment steps").await;
+        prompt(&storage, "/work/api", "continue").await;
+
+        let groups = find_duplicates(&storage, &DuplicatesConfig::default(), None).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].occurrences.len(), 3);
+        assert!(groups[0].similarity < 1.0);
+
+        let earlier = groups[0].earlier_than(&third.id).unwrap();
+        assert_eq!(earlier.session_id, first.id);
+        assert!(groups[0].earlier_than(&first.id).is_none());
+
+        let for_second = find_duplicates(&storage, &DuplicatesConfig::default(), Some(&second.id))
+            .await
+            .unwrap();
+        assert_eq!(for_second.len(), 1);
+    }
+}
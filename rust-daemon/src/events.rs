@@ -1,9 +1,114 @@
 //! Event bus for distributing events to subscribers.
+//!
+//! Adapters, hooks and watchers publish each event they record. Everything
+//! that reacts to events (rules, watchers, the IPC and terminit streams,
+//! webhooks, the dashboard) follows a `Subscription` instead of polling
+//! storage. A subscription can be narrowed to a session, an agent or some
+//! event types, and deals with falling behind itself.
 
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, RwLock};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt as _};
+use tracing::warn;
 
-use crate::models::SessionEvent;
+use crate::models::{AgentType, EventType, SessionEvent};
+
+/// Which events a subscription receives. The default receives all of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    /// Only events of this session
+    pub session_id: Option<String>,
+    /// Only events of this agent
+    pub agent_type: Option<AgentType>,
+    /// Only events of these types; all types when empty
+    pub event_types: Vec<EventType>,
+}
+
+impl EventFilter {
+    /// Every event.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn agent(mut self, agent_type: AgentType) -> Self {
+        self.agent_type = Some(agent_type);
+        self
+    }
+
+    pub fn event_types(mut self, event_types: impl IntoIterator<Item = EventType>) -> Self {
+        self.event_types.extend(event_types);
+        self
+    }
+
+    pub fn matches(&self, event: &SessionEvent) -> bool {
+        self.session_id.as_ref().is_none_or(|id| *id == event.session_id)
+            && self.agent_type.is_none_or(|agent| agent == event.agent_type)
+            && (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+    }
+}
+
+/// What a subscription hands out next.
+#[derive(Debug, Clone)]
+pub enum Received {
+    Event(Box<SessionEvent>),
+    /// The subscriber fell behind and this many events (matching or not)
+    /// were dropped
+    Lagged(u64),
+}
+
+/// The events of a bus that match a filter.
+pub struct Subscription {
+    /// Who is subscribed, for logging
+    name: &'static str,
+    filter: EventFilter,
+    receiver: broadcast::Receiver<SessionEvent>,
+}
+
+impl Subscription {
+    /// The next matching event, or None once the bus is gone. Dropped
+    /// events are logged and otherwise ignored.
+    pub async fn recv(&mut self) -> Option<SessionEvent> {
+        loop {
+            match self.recv_or_lag().await? {
+                Received::Event(event) => return Some(*event),
+                Received::Lagged(missed) => warn!("{} missed {} events", self.name, missed),
+            }
+        }
+    }
+
+    /// Like `recv`, but tells the subscriber when it fell behind, for those
+    /// that have to catch up from storage.
+    pub async fn recv_or_lag(&mut self) -> Option<Received> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Some(Received::Event(Box::new(event))),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => return Some(Received::Lagged(missed)),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The subscription as a stream, for server-sent events.
+    pub fn into_stream(self) -> impl Stream<Item = Received> {
+        let filter = self.filter;
+        BroadcastStream::new(self.receiver).filter_map(move |received| match received {
+            Ok(event) if filter.matches(&event) => Some(Received::Event(Box::new(event))),
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Received::Lagged(missed)),
+        })
+    }
+}
 
 /// Event bus for distributing session events.
 #[derive(Clone)]
@@ -27,10 +132,20 @@ impl EventBus {
         let _ = self.sender.send(event);
     }
 
-    /// Subscribe to events.
+    /// Subscribe to every event, unfiltered.
     pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
         self.sender.subscribe()
     }
+
+    /// Subscribe to the events matching `filter`. `name` says who is
+    /// subscribed when events are dropped.
+    pub fn subscribe_filtered(&self, name: &'static str, filter: EventFilter) -> Subscription {
+        Subscription {
+            name,
+            filter,
+            receiver: self.sender.subscribe(),
+        }
+    }
 }
 
 impl Default for EventBus {
@@ -38,3 +153,39 @@ impl Default for EventBus {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_filtered_subscriptions() {
+        let bus = EventBus::new();
+        let mut tools = bus.subscribe_filtered("tools", EventFilter::all().event_types([EventType::ToolStart]));
+        let mut cursor = bus.subscribe_filtered("cursor", EventFilter::all().session("b").agent(AgentType::Cursor));
+
+        bus.publish(SessionEvent::new("a", EventType::PromptReceived, AgentType::ClaudeCode));
+        bus.publish(SessionEvent::new("a", EventType::ToolStart, AgentType::ClaudeCode));
+        bus.publish(SessionEvent::new("b", EventType::ToolStart, AgentType::ClaudeCode));
+        bus.publish(SessionEvent::new("b", EventType::PromptReceived, AgentType::Cursor));
+        drop(bus);
+
+        let mut sessions = Vec::new();
+        while let Some(event) = tools.recv().await {
+            sessions.push(event.session_id);
+        }
+        assert_eq!(sessions, ["a", "b"]);
+
+        let event = cursor.recv().await.unwrap();
+        assert_eq!((event.session_id.as_str(), event.event_type), ("b", EventType::PromptReceived));
+        assert!(cursor.recv().await.is_none());
+
+        // Falling behind is reported to those that ask
+        let bus = EventBus::new();
+        let mut slow = bus.subscribe_filtered("slow", EventFilter::all());
+        for _ in 0..1100 {
+            bus.publish(SessionEvent::new("a", EventType::ToolStart, AgentType::ClaudeCode));
+        }
+        assert!(matches!(slow.recv_or_lag().await, Some(Received::Lagged(_))));
+    }
+}
@@ -0,0 +1,231 @@
+//! Closing sessions when their agent process exits.
+//!
+//! The monitor follows each agent's processes through the shared process
+//! watcher. When one exits, the agent's active sessions with that PID are
+//! closed, as are those in the same project directory once no other process
+//! of that agent is running there (transcript sessions carry no PID). A
+//! session whose last event was an error, an unanswered prompt or a tool that
+//! never finished is marked crashed; otherwise completed. Each closed session
+//! gets a `session_end` event, so rules can notify on it.
+
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::adapters::process_matcher;
+use crate::events::EventBus;
+use crate::models::{AgentType, EventType, Session, SessionEvent, SessionStatus};
+use crate::procwatch::{ProcessEvent, ProcessInfo, ProcessMatcher, ProcessWatcher};
+use crate::storage::Storage;
+
+/// Recent events read to judge how a session ended.
+const EXIT_EVENTS: usize = 20;
+
+/// Most active sessions considered when a process exits.
+const MAX_ACTIVE: usize = 500;
+
+/// Closes sessions whose agent process has exited.
+#[derive(Clone)]
+pub struct ExitMonitor {
+    storage: Storage,
+    event_bus: EventBus,
+    processes: ProcessWatcher,
+}
+
+impl ExitMonitor {
+    pub fn new(storage: Storage, event_bus: EventBus, processes: ProcessWatcher) -> Self {
+        Self {
+            storage,
+            event_bus,
+            processes,
+        }
+    }
+
+    /// Follow every agent's processes until the daemon stops.
+    pub async fn run(self) {
+        let agents = [AgentType::ClaudeCode, AgentType::Cursor, AgentType::Aider];
+        let watches: Vec<_> = agents
+            .into_iter()
+            .filter_map(|agent_type| {
+                let matcher = process_matcher(agent_type)?;
+                Some(tokio::spawn(self.clone().watch(agent_type, matcher)))
+            })
+            .collect();
+        futures_util::future::join_all(watches).await;
+    }
+
+    async fn watch(self, agent_type: AgentType, matcher: ProcessMatcher) {
+        let mut events = self.processes.subscribe(matcher.clone());
+        let mut running: HashMap<u32, ProcessInfo> = self
+            .processes
+            .matching(&matcher)
+            .into_iter()
+            .map(|p| (p.pid, p))
+            .collect();
+
+        // Sessions left open by processes that exited while the daemon was down
+        if let Err(e) = self.close_orphans(agent_type, &running).await {
+            warn!("Failed to close orphaned {} sessions: {}", agent_type, e);
+        }
+
+        self.follow(agent_type, &mut events, &mut running).await;
+    }
+
+    async fn follow(
+        &self,
+        agent_type: AgentType,
+        events: &mut mpsc::UnboundedReceiver<ProcessEvent>,
+        running: &mut HashMap<u32, ProcessInfo>,
+    ) {
+        while let Some(event) = events.recv().await {
+            match event {
+                ProcessEvent::Started(process) => {
+                    running.insert(process.pid, process);
+                }
+                ProcessEvent::Exited(process) => {
+                    running.remove(&process.pid);
+                    if let Err(e) = self.on_exit(agent_type, &process, running).await {
+                        warn!("Failed to close sessions for pid {}: {}", process.pid, e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn on_exit(
+        &self,
+        agent_type: AgentType,
+        process: &ProcessInfo,
+        running: &HashMap<u32, ProcessInfo>,
+    ) -> Result<()> {
+        let project_still_running = process
+            .cwd
+            .as_ref()
+            .is_some_and(|cwd| running.values().any(|p| p.cwd.as_ref() == Some(cwd)));
+
+        for session in self.storage.get_active_sessions(MAX_ACTIVE).await? {
+            let same_pid = session.pid == Some(process.pid as i32);
+            let same_project = !project_still_running
+                && session.pid.is_none_or(|pid| !running.contains_key(&(pid as u32)))
+                && process.cwd.as_deref() == Some(session.project_path.as_str());
+            if session.agent_type == agent_type && (same_pid || same_project) {
+                self.close(session, process.pid).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn close_orphans(&self, agent_type: AgentType, running: &HashMap<u32, ProcessInfo>) -> Result<()> {
+        for session in self.storage.get_active_sessions(MAX_ACTIVE).await? {
+            let Some(pid) = session.pid else {
+                continue;
+            };
+            if session.agent_type == agent_type && !running.contains_key(&(pid as u32)) {
+                self.close(session, pid as u32).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&self, mut session: Session, pid: u32) -> Result<()> {
+        let events = self.storage.get_session_events(&session.id, EXIT_EVENTS).await?;
+        let (status, reason) = exit_status(&events);
+
+        session.status = status;
+        session.end();
+        self.storage.upsert_session(&session).await?;
+
+        let mut content = format!("{} exited (pid {}): {}", session.agent_type, pid, status);
+        if let Some(ref reason) = reason {
+            content.push_str(&format!(" - {}", reason));
+        }
+        info!("Session {} {}", session.id, content);
+
+        let mut event = SessionEvent::new(&session.id, EventType::SessionEnd, session.agent_type);
+        event.content = Some(content);
+        event.working_directory = Some(session.project_path.clone());
+        event.error_message = reason.filter(|_| status == SessionStatus::Crashed);
+        event.raw_data = Some(json!({ "source": "process_exit", "pid": pid, "status": status }));
+        self.storage.record_event(event, &self.event_bus).await?;
+        Ok(())
+    }
+}
+
+/// How a session ended, judged from its latest events (newest first), with
+/// the reason when it crashed.
+pub fn exit_status(events: &[SessionEvent]) -> (SessionStatus, Option<String>) {
+    let last = events.iter().find(|e| {
+        let ignored = matches!(
+            e.event_type,
+            EventType::Custom
+                | EventType::PolicyDecision
+                | EventType::Compaction
+                | EventType::ConfigChanged
+                | EventType::Anomaly
+                | EventType::Loop
+                | EventType::SessionEnd
+        );
+        // A user interrupt is logged as a prompt but asks nothing
+        !ignored && e.interruption() != Some("user")
+    });
+    let reason = match last {
+        Some(e) if e.event_type == EventType::Error => Some(format!(
+            "last event was an error: {}",
+            e.error_message.as_deref().or(e.content.as_deref()).unwrap_or("unknown")
+        )),
+        Some(e) if e.event_type == EventType::ApiError => Some(format!(
+            "last event was an API error: {}",
+            e.error_message.as_deref().or(e.content.as_deref()).unwrap_or("unknown")
+        )),
+        Some(e) if e.event_type == EventType::PromptReceived => {
+            Some("exited before answering the last prompt".to_string())
+        }
+        Some(e) if e.event_type == EventType::ToolStart => Some(format!(
+            "exited while running {}",
+            e.tool_name.as_deref().unwrap_or("a tool")
+        )),
+        _ => None,
+    };
+    match reason {
+        Some(reason) => (SessionStatus::Crashed, Some(reason)),
+        None => (SessionStatus::Completed, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_status() {
+        let event = |event_type: EventType| SessionEvent::new("s", event_type, AgentType::ClaudeCode);
+
+        assert_eq!(exit_status(&[]), (SessionStatus::Completed, None));
+        assert_eq!(
+            exit_status(&[event(EventType::ResponseGenerated), event(EventType::PromptReceived)]).0,
+            SessionStatus::Completed
+        );
+
+        // Newest first: the prompt after the last response was never answered
+        let (status, reason) = exit_status(&[
+            event(EventType::Compaction),
+            event(EventType::PromptReceived),
+            event(EventType::ResponseGenerated),
+        ]);
+        assert_eq!(status, SessionStatus::Crashed);
+        assert_eq!(reason.as_deref(), Some("exited before answering the last prompt"));
+
+        let mut tool = event(EventType::ToolStart);
+        tool.tool_name = Some("Bash".to_string());
+        assert_eq!(exit_status(&[tool]).1.as_deref(), Some("exited while running Bash"));
+
+        let mut error = event(EventType::Error);
+        error.error_message = Some("overloaded".to_string());
+        assert_eq!(
+            exit_status(&[error]).1.as_deref(),
+            Some("last event was an error: overloaded")
+        );
+    }
+}
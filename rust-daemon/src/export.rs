@@ -0,0 +1,415 @@
+//! Scheduled exports for a data warehouse.
+//!
+//! Every `interval_hours` the daemon writes the sessions and events that
+//! changed since the last export to `destination` (a directory,
+//! `s3://bucket/prefix` or `gs://bucket/prefix`; see `objstore`) as
+//! `sessions-<time>.jsonl` and `events-<time>.jsonl`, one JSON object per
+//! line, or as Parquet files with typed columns that DuckDB and warehouses
+//! read directly; `agent-monitor export` writes the same files once. A
+//! session that stays active appears in each export it changed in, so
+//! loaders should keep the latest row per `id`. The first export covers
+//! everything; the time of the last one is kept in the data directory so a
+//! restart neither skips nor repeats rows. Content is exported decrypted.
+
+use anyhow::Result;
+use arrow_array::builder::{Float64Builder, Int32Builder, Int64Builder, StringBuilder, TimestampMicrosecondBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::models::{Session, SessionEvent};
+use crate::objstore::Destination;
+use crate::storage::Storage;
+
+/// File format of exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Columnar, with typed columns
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn sessions(self, sessions: &[Session]) -> Result<Vec<u8>> {
+        match self {
+            ExportFormat::Jsonl => to_jsonl(sessions),
+            ExportFormat::Parquet => sessions_parquet(sessions),
+        }
+    }
+
+    pub fn events(self, events: &[SessionEvent]) -> Result<Vec<u8>> {
+        match self {
+            ExportFormat::Jsonl => to_jsonl(events),
+            ExportFormat::Parquet => events_parquet(events),
+        }
+    }
+}
+
+/// Scheduled export settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Whether the daemon exports on a schedule
+    pub enabled: bool,
+
+    /// Directory, `s3://bucket/prefix` or `gs://bucket/prefix`
+    pub destination: String,
+
+    /// `jsonl` or `parquet`
+    pub format: ExportFormat,
+
+    /// Hours between exports
+    pub interval_hours: u64,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            destination: String::new(),
+            format: ExportFormat::Jsonl,
+            interval_hours: 24,
+        }
+    }
+}
+
+/// More rows than any one query returns in practice.
+const ALL: usize = i32::MAX as usize;
+
+/// Rows written by one export.
+#[derive(Debug, Default)]
+pub struct ExportCounts {
+    pub sessions: usize,
+    pub events: usize,
+}
+
+/// One JSON object per line.
+fn to_jsonl<T: Serialize>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut out, row)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+/// How an enum is spelled in JSON, e.g. `claude_code`.
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(other) => other.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), nullable)
+}
+
+fn timestamps(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    let mut builder = TimestampMicrosecondBuilder::new().with_timezone("UTC");
+    for value in values {
+        builder.append_option(value.map(|t| t.timestamp_micros()));
+    }
+    Arc::new(builder.finish())
+}
+
+fn strings<S: AsRef<str>>(values: impl Iterator<Item = Option<S>>) -> ArrayRef {
+    let mut builder = StringBuilder::new();
+    for value in values {
+        builder.append_option(value);
+    }
+    Arc::new(builder.finish())
+}
+
+fn integers(values: impl Iterator<Item = Option<i64>>) -> ArrayRef {
+    let mut builder = Int64Builder::new();
+    for value in values {
+        builder.append_option(value);
+    }
+    Arc::new(builder.finish())
+}
+
+fn floats(values: impl Iterator<Item = f64>) -> ArrayRef {
+    let mut builder = Float64Builder::new();
+    for value in values {
+        builder.append_value(value);
+    }
+    Arc::new(builder.finish())
+}
+
+fn write_parquet(fields: Vec<Field>, columns: Vec<ArrayRef>) -> Result<Vec<u8>> {
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut out = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut out, batch.schema(), Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(out)
+}
+
+/// Sessions as Parquet; `metadata` is a JSON string.
+fn sessions_parquet(sessions: &[Session]) -> Result<Vec<u8>> {
+    let fields = vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("agent_type", DataType::Utf8, false),
+        Field::new("external_id", DataType::Utf8, false),
+        Field::new("project_path", DataType::Utf8, false),
+        Field::new("status", DataType::Utf8, false),
+        timestamp_field("started_at", false),
+        timestamp_field("last_activity_at", false),
+        timestamp_field("ended_at", true),
+        Field::new("duration_seconds", DataType::Float64, false),
+        Field::new("message_count", DataType::Int64, false),
+        Field::new("tool_call_count", DataType::Int64, false),
+        Field::new("file_operations", DataType::Int64, false),
+        Field::new("tokens_input", DataType::Int64, false),
+        Field::new("tokens_output", DataType::Int64, false),
+        Field::new("estimated_cost", DataType::Float64, false),
+        Field::new("model_id", DataType::Utf8, true),
+        Field::new("pid", DataType::Int32, true),
+        Field::new("current_task", DataType::Utf8, true),
+        Field::new("progress", DataType::Float64, false),
+        Field::new("metadata", DataType::Utf8, false),
+        Field::new("summary", DataType::Utf8, true),
+        Field::new("context_tokens", DataType::Int64, true),
+        Field::new("compactions", DataType::Int64, false),
+        Field::new("interruptions", DataType::Int64, false),
+        Field::new("git_branch", DataType::Utf8, true),
+        Field::new("user", DataType::Utf8, true),
+    ];
+    let mut pids = Int32Builder::new();
+    for session in sessions {
+        pids.append_option(session.pid);
+    }
+    let s = sessions;
+    let columns: Vec<ArrayRef> = vec![
+        strings(s.iter().map(|s| Some(&s.id))),
+        strings(s.iter().map(|s| Some(label(&s.agent_type)))),
+        strings(s.iter().map(|s| Some(&s.external_id))),
+        strings(s.iter().map(|s| Some(&s.project_path))),
+        strings(s.iter().map(|s| Some(label(&s.status)))),
+        timestamps(s.iter().map(|s| Some(s.started_at))),
+        timestamps(s.iter().map(|s| Some(s.last_activity_at))),
+        timestamps(s.iter().map(|s| s.ended_at)),
+        floats(s.iter().map(|s| s.duration_seconds)),
+        integers(s.iter().map(|s| Some(s.message_count))),
+        integers(s.iter().map(|s| Some(s.tool_call_count))),
+        integers(s.iter().map(|s| Some(s.file_operations))),
+        integers(s.iter().map(|s| Some(s.tokens_input))),
+        integers(s.iter().map(|s| Some(s.tokens_output))),
+        floats(s.iter().map(|s| s.estimated_cost)),
+        strings(s.iter().map(|s| s.model_id.as_ref())),
+        Arc::new(pids.finish()),
+        strings(s.iter().map(|s| s.current_task.as_ref())),
+        floats(s.iter().map(|s| s.progress)),
+        strings(s.iter().map(|s| Some(serde_json::to_string(&s.metadata).unwrap_or_default()))),
+        strings(s.iter().map(|s| s.summary.as_ref())),
+        integers(s.iter().map(|s| s.context_tokens)),
+        integers(s.iter().map(|s| Some(s.compactions))),
+        integers(s.iter().map(|s| Some(s.interruptions))),
+        strings(s.iter().map(|s| s.git_branch.as_ref())),
+        strings(s.iter().map(|s| s.user.as_ref())),
+    ];
+    write_parquet(fields, columns)
+}
+
+/// Events as Parquet; `raw_data` is a JSON string.
+fn events_parquet(events: &[SessionEvent]) -> Result<Vec<u8>> {
+    let fields = vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("session_id", DataType::Utf8, false),
+        Field::new("event_type", DataType::Utf8, false),
+        timestamp_field("timestamp", false),
+        Field::new("agent_type", DataType::Utf8, false),
+        Field::new("content", DataType::Utf8, true),
+        Field::new("working_directory", DataType::Utf8, true),
+        Field::new("tool_name", DataType::Utf8, true),
+        Field::new("file_path", DataType::Utf8, true),
+        Field::new("tokens_input", DataType::Int64, true),
+        Field::new("tokens_output", DataType::Int64, true),
+        Field::new("error_message", DataType::Utf8, true),
+        Field::new("raw_data", DataType::Utf8, true),
+    ];
+    let e = events;
+    let columns: Vec<ArrayRef> = vec![
+        strings(e.iter().map(|e| Some(&e.id))),
+        strings(e.iter().map(|e| Some(&e.session_id))),
+        strings(e.iter().map(|e| Some(label(&e.event_type)))),
+        timestamps(e.iter().map(|e| Some(e.timestamp))),
+        strings(e.iter().map(|e| Some(label(&e.agent_type)))),
+        strings(e.iter().map(|e| e.content.as_ref())),
+        strings(e.iter().map(|e| e.working_directory.as_ref())),
+        strings(e.iter().map(|e| e.tool_name.as_ref())),
+        strings(e.iter().map(|e| e.file_path.as_ref())),
+        integers(e.iter().map(|e| e.tokens_input)),
+        integers(e.iter().map(|e| e.tokens_output)),
+        strings(e.iter().map(|e| e.error_message.as_ref())),
+        strings(e.iter().map(|e| e.raw_data.as_ref().map(|v| v.to_string()))),
+    ];
+    write_parquet(fields, columns)
+}
+
+/// Write the sessions and events that changed after `since` (everything
+/// when None) and up to `until`. Nothing is written when nothing changed.
+pub async fn export(
+    storage: &Storage,
+    destination: &Destination,
+    format: ExportFormat,
+    since: Option<DateTime<Utc>>,
+    until: DateTime<Utc>,
+) -> Result<ExportCounts> {
+    let in_window = |t: DateTime<Utc>| since.is_none_or(|s| t > s) && t <= until;
+    let sessions: Vec<Session> = storage
+        .get_all_sessions(ALL)
+        .await?
+        .into_iter()
+        .filter(|s| in_window(s.last_activity_at))
+        .collect();
+    let mut events: Vec<SessionEvent> = Vec::new();
+    for session in &sessions {
+        let mut session_events = storage.get_session_events(&session.id, ALL).await?;
+        session_events.retain(|e| in_window(e.timestamp));
+        session_events.reverse();
+        events.extend(session_events);
+    }
+
+    let counts = ExportCounts { sessions: sessions.len(), events: events.len() };
+    if sessions.is_empty() {
+        return Ok(counts);
+    }
+    let stamp = until.format("%Y%m%dT%H%M%SZ");
+    let extension = format.extension();
+    destination.put(&format!("sessions-{}.{}", stamp, extension), format.sessions(&sessions)?).await?;
+    destination.put(&format!("events-{}.{}", stamp, extension), format.events(&events)?).await?;
+    Ok(counts)
+}
+
+/// Exports on schedule.
+pub struct Exporter {
+    config: ExportConfig,
+    storage: Storage,
+    destination: Destination,
+    state_path: PathBuf,
+}
+
+impl Exporter {
+    pub fn new(config: ExportConfig, storage: Storage, data_dir: PathBuf) -> Result<Self> {
+        let destination = Destination::parse(&config.destination)?;
+        Ok(Self {
+            config,
+            storage,
+            destination,
+            state_path: data_dir.join("export_last_run"),
+        })
+    }
+
+    fn last_run(&self) -> Option<DateTime<Utc>> {
+        let content = std::fs::read_to_string(&self.state_path).ok()?;
+        DateTime::parse_from_rfc3339(content.trim()).ok().map(|t| t.with_timezone(&Utc))
+    }
+
+    fn record_run(&self, at: DateTime<Utc>) {
+        if let Err(e) = std::fs::write(&self.state_path, at.to_rfc3339()) {
+            warn!("Failed to record export time in {}: {}", self.state_path.display(), e);
+        }
+    }
+
+    /// Export whenever `interval_hours` have passed since the last export.
+    pub async fn run(self) {
+        let interval = chrono::Duration::hours(self.config.interval_hours.max(1) as i64);
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let since = self.last_run();
+            let now = Utc::now();
+            if since.is_some_and(|s| now - s < interval) {
+                continue;
+            }
+
+            match export(&self.storage, &self.destination, self.config.format, since, now).await {
+                Ok(counts) => {
+                    if counts.sessions > 0 {
+                        info!(
+                            "Exported {} sessions and {} events to {}",
+                            counts.sessions, counts.events, self.config.destination
+                        );
+                    }
+                    self.record_run(now);
+                }
+                // Retried at the next check, covering the same window
+                Err(e) => warn!("Scheduled export failed: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType};
+
+    #[tokio::test]
+    async fn test_export_writes_changes_since_last_run() {
+        let storage = Storage::in_memory();
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "one");
+        storage.upsert_session(&session).await.unwrap();
+        for _ in 0..2 {
+            let event = SessionEvent::new(&session.id, EventType::ToolStart, AgentType::ClaudeCode);
+            storage.insert_event(&event).await.unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let destination = Destination::Local(dir.path().to_path_buf());
+        let first = Utc::now();
+        let counts = export(&storage, &destination, ExportFormat::Jsonl, None, first).await.unwrap();
+        assert_eq!((counts.sessions, counts.events), (1, 2));
+        let name = format!("events-{}.jsonl", first.format("%Y%m%dT%H%M%SZ"));
+        let lines = std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        let row: SessionEvent = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(row.session_id, session.id);
+
+        // Nothing changed since, so nothing more is written
+        let counts = export(&storage, &destination, ExportFormat::Jsonl, Some(first), Utc::now()).await.unwrap();
+        assert_eq!(counts.sessions, 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_parquet_columns_are_typed() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "one");
+        session.tokens_input = 1200;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), ExportFormat::Parquet.sessions(&[session]).unwrap()).unwrap();
+        let reader = SerializedFileReader::new(file.reopen().unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 1);
+        let schema = metadata.file_metadata().schema_descr();
+        let column = |name: &str| (0..schema.num_columns()).map(|i| schema.column(i)).find(|c| c.name() == name).unwrap();
+        assert_eq!(column("tokens_input").physical_type(), parquet::basic::Type::INT64);
+        assert_eq!(column("started_at").physical_type(), parquet::basic::Type::INT64);
+        assert_eq!(column("agent_type").physical_type(), parquet::basic::Type::BYTE_ARRAY);
+
+        let event = SessionEvent::new("s", EventType::ToolStart, AgentType::ClaudeCode);
+        assert!(ExportFormat::Parquet.events(&[event]).unwrap().starts_with(b"PAR1"));
+    }
+}
@@ -0,0 +1,268 @@
+//! Spend forecasts and burn rates.
+//!
+//! Session costs are rolled up per day (by the day of last activity, local
+//! time). The average over the trailing `window_days` is the daily burn rate;
+//! month-to-date spend plus that rate for the rest of the calendar month is
+//! the projected monthly spend. Where a monthly budget is configured, the
+//! remaining budget divided by the burn rate gives the days until it runs
+//! out. Budgets can be set overall and per project.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::Session;
+use crate::storage::Storage;
+
+/// Forecast settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForecastConfig {
+    /// Trailing days averaged for the burn rate
+    pub window_days: i64,
+
+    /// Monthly budget across all projects, in dollars
+    pub monthly_budget: Option<f64>,
+
+    /// Monthly budgets for individual projects, keyed by project path
+    pub project_budgets: HashMap<String, f64>,
+}
+
+impl Default for ForecastConfig {
+    fn default() -> Self {
+        Self {
+            window_days: 14,
+            monthly_budget: None,
+            project_budgets: HashMap::new(),
+        }
+    }
+}
+
+/// Most sessions considered for one forecast.
+const MAX_SESSIONS: usize = 20_000;
+
+/// Spend on one day.
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyCost {
+    pub date: NaiveDate,
+    pub cost: f64,
+}
+
+/// Burn rate and month-end projection for all projects or a single one.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Projection {
+    /// Average spend per day over the window
+    pub daily_burn: f64,
+    pub month_to_date: f64,
+    /// Month-to-date spend plus the burn rate for the remaining days
+    pub projected_month: f64,
+    pub budget: Option<f64>,
+    /// Days until the budget is spent at the current rate; 0 once exceeded,
+    /// None without a budget or without spend
+    pub days_until_exhausted: Option<f64>,
+}
+
+impl Projection {
+    fn new(daily_burn: f64, month_to_date: f64, remaining_days: i64, budget: Option<f64>) -> Self {
+        let days_until_exhausted = budget.and_then(|budget| {
+            let left = budget - month_to_date;
+            if left <= 0.0 {
+                Some(0.0)
+            } else if daily_burn > 0.0 {
+                Some(left / daily_burn)
+            } else {
+                None
+            }
+        });
+        Self {
+            daily_burn,
+            month_to_date,
+            projected_month: month_to_date + daily_burn * remaining_days as f64,
+            budget,
+            days_until_exhausted,
+        }
+    }
+
+    /// Whether the projection runs past the budget before the month ends.
+    pub fn over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.projected_month > budget)
+    }
+}
+
+/// Projection for one project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectForecast {
+    pub project_path: String,
+    #[serde(flatten)]
+    pub projection: Projection,
+}
+
+/// Spend forecast for the current month.
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+    pub generated_at: DateTime<Utc>,
+    pub window_days: i64,
+    /// Days left in the month after today
+    pub remaining_days: i64,
+    #[serde(flatten)]
+    pub total: Projection,
+    /// Most expensive projected spend first
+    pub projects: Vec<ProjectForecast>,
+    /// Spend per day over the window, oldest first
+    pub daily: Vec<DailyCost>,
+}
+
+/// Forecast the current month from the stored sessions.
+pub async fn build_forecast(storage: &Storage, config: &ForecastConfig) -> Result<Forecast> {
+    let today = Local::now().date_naive();
+    let window_days = config.window_days.max(1);
+    let lookback_days = window_days.max(today.day() as i64) + 1;
+    let sessions = storage.get_recent_sessions(lookback_days * 24, MAX_SESSIONS).await?;
+    Ok(forecast(&sessions, config, today))
+}
+
+fn forecast(sessions: &[Session], config: &ForecastConfig, today: NaiveDate) -> Forecast {
+    let window_days = config.window_days.max(1);
+    let window_start = today - Duration::days(window_days - 1);
+    let month_start = today.with_day(1).unwrap_or(today);
+    let remaining_days = days_in_month(today) - today.day() as i64;
+
+    // Daily rollups per project
+    let mut rollups: HashMap<&str, BTreeMap<NaiveDate, f64>> = HashMap::new();
+    for session in sessions {
+        let date = session.last_activity_at.with_timezone(&Local).date_naive();
+        if date > today || (date < window_start && date < month_start) {
+            continue;
+        }
+        *rollups
+            .entry(session.project_path.as_str())
+            .or_default()
+            .entry(date)
+            .or_insert(0.0) += session.estimated_cost;
+    }
+
+    let project = |days: Option<&BTreeMap<NaiveDate, f64>>, budget: Option<f64>| {
+        let window: f64 = days.map(|d| d.range(window_start..).map(|(_, c)| c).sum()).unwrap_or(0.0);
+        let month: f64 = days.map(|d| d.range(month_start..).map(|(_, c)| c).sum()).unwrap_or(0.0);
+        Projection::new(window / window_days as f64, month, remaining_days, budget)
+    };
+
+    let mut projects: Vec<ProjectForecast> = rollups
+        .iter()
+        .map(|(path, days)| ProjectForecast {
+            project_path: path.to_string(),
+            projection: project(Some(days), config.project_budgets.get(*path).copied()),
+        })
+        .collect();
+    // Budgeted projects are listed even before they spend anything
+    for (path, budget) in &config.project_budgets {
+        if !rollups.contains_key(path.as_str()) {
+            projects.push(ProjectForecast {
+                project_path: path.clone(),
+                projection: project(None, Some(*budget)),
+            });
+        }
+    }
+    projects.sort_by(|a, b| {
+        b.projection
+            .projected_month
+            .total_cmp(&a.projection.projected_month)
+            .then(a.project_path.cmp(&b.project_path))
+    });
+
+    let daily: Vec<DailyCost> = (0..window_days)
+        .map(|offset| {
+            let date = window_start + Duration::days(offset);
+            DailyCost {
+                date,
+                // fold rather than sum: an empty f64 sum is -0.0
+                cost: rollups.values().filter_map(|d| d.get(&date)).fold(0.0, |a, c| a + c),
+            }
+        })
+        .collect();
+
+    let window: f64 = daily.iter().map(|d| d.cost).sum();
+    let month: f64 = rollups
+        .values()
+        .flat_map(|d| d.range(month_start..))
+        .map(|(_, c)| c)
+        .sum();
+
+    Forecast {
+        generated_at: Utc::now(),
+        window_days,
+        remaining_days,
+        total: Projection::new(window / window_days as f64, month, remaining_days, config.monthly_budget),
+        projects,
+        daily,
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> i64 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| date.with_day(1).map(|first| (next - first).num_days()))
+        .unwrap_or(30)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::TimeZone;
+
+    fn session(project: &str, date: NaiveDate, cost: f64) -> Session {
+        let mut session = Session::new(AgentType::ClaudeCode, project, "x");
+        session.last_activity_at = Local
+            .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        session.estimated_cost = cost;
+        session
+    }
+
+    #[test]
+    fn test_forecast_burn_and_budget() {
+        // 2026-04-10, 20 days left in April
+        let today = NaiveDate::from_ymd_opt(2026, 4, 10).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 4, d).unwrap();
+        let sessions = vec![
+            session("/work/api", day(9), 6.0),
+            session("/work/api", day(10), 4.0),
+            session("/work/web", day(2), 2.0),
+            // Inside the window but last month
+            session("/work/web", NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(), 8.0),
+        ];
+        let config = ForecastConfig {
+            window_days: 20,
+            monthly_budget: Some(100.0),
+            project_budgets: HashMap::from([
+                ("/work/api".to_string(), 15.0),
+                ("/work/docs".to_string(), 5.0),
+            ]),
+        };
+
+        let forecast = forecast(&sessions, &config, today);
+        assert_eq!(forecast.remaining_days, 20);
+        assert_eq!(forecast.daily.len(), 20);
+        assert!((forecast.total.daily_burn - 1.0).abs() < 1e-9);
+        assert!((forecast.total.month_to_date - 12.0).abs() < 1e-9);
+        assert!((forecast.total.projected_month - 32.0).abs() < 1e-9);
+        assert!((forecast.total.days_until_exhausted.unwrap() - 88.0).abs() < 1e-9);
+        assert!(!forecast.total.over_budget());
+
+        let api = &forecast.projects[0];
+        assert_eq!(api.project_path, "/work/api");
+        assert!((api.projection.days_until_exhausted.unwrap() - 10.0).abs() < 1e-9);
+        assert!(api.projection.over_budget());
+
+        let docs = forecast.projects.iter().find(|p| p.project_path == "/work/docs").unwrap();
+        assert_eq!(docs.projection.days_until_exhausted, None);
+        assert_eq!(days_in_month(NaiveDate::from_ymd_opt(2028, 2, 3).unwrap()), 29);
+    }
+}
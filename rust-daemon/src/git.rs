@@ -0,0 +1,114 @@
+//! The git branch each session works on.
+//!
+//! Claude Code transcripts name the branch on every entry. For other agents
+//! the daemon reads `HEAD` of the session's project (worktrees included,
+//! without running git) when the session starts and on each prompt, and
+//! stores the branch on the session. Cost per branch is rolled up in
+//! `report::branch_usage`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::events::{EventBus, EventFilter};
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Sessions whose last seen branch is remembered before the map is reset.
+const MAX_TRACKED: usize = 10_000;
+
+/// The git directory of the repository containing `dir`.
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        // Worktrees and submodules have a `.git` file pointing elsewhere
+        if dot_git.is_file() {
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let target = content.strip_prefix("gitdir:")?.trim();
+            return Some(ancestor.join(target));
+        }
+    }
+    None
+}
+
+/// Branch checked out in the repository containing `dir`; the short commit
+/// hash when HEAD is detached, and None outside a repository.
+pub fn current_branch(dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir(dir)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string()),
+        None => head.get(..7).map(str::to_string),
+    }
+}
+
+/// Keeps each session's branch up to date from its project's `HEAD`.
+pub struct BranchTracker {
+    storage: Storage,
+    /// Last branch stored per session
+    known: HashMap<String, String>,
+}
+
+impl BranchTracker {
+    pub fn new(storage: Storage) -> Self {
+        Self { storage, known: HashMap::new() }
+    }
+
+    async fn on_event(&mut self, event: &SessionEvent) -> anyhow::Result<()> {
+        if !matches!(event.event_type, EventType::SessionStart | EventType::PromptReceived) {
+            return Ok(());
+        }
+        let Some(session) = self.storage.get_session(&event.session_id).await? else {
+            return Ok(());
+        };
+        let Some(branch) = current_branch(Path::new(&session.project_path)) else {
+            return Ok(());
+        };
+        let stored = self.known.get(&session.id).or(session.git_branch.as_ref());
+        if stored != Some(&branch) {
+            self.storage.set_session_branch(&session.id, &branch).await?;
+        }
+        if self.known.len() >= MAX_TRACKED {
+            self.known.clear();
+        }
+        self.known.insert(session.id, branch);
+        Ok(())
+    }
+
+    pub async fn run(mut self, event_bus: EventBus) {
+        let mut events = event_bus.subscribe_filtered("Branch tracker", EventFilter::all());
+        while let Some(event) = events.recv().await {
+            if let Err(e) = self.on_event(&event).await {
+                warn!("Branch tracking failed for session {}: {:#}", event.session_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        let nested = repo.path().join("src/app");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(current_branch(&nested), None);
+
+        std::fs::create_dir(repo.path().join(".git")).unwrap();
+        std::fs::write(repo.path().join(".git/HEAD"), "ref: refs/heads/feature/login\n").unwrap();
+        assert_eq!(current_branch(&nested).as_deref(), Some("feature/login"));
+
+        // A worktree's HEAD lives in the main repository
+        let worktree = tempfile::tempdir().unwrap();
+        let worktree_git = repo.path().join(".git/worktrees/hotfix");
+        std::fs::create_dir_all(&worktree_git).unwrap();
+        std::fs::write(worktree_git.join("HEAD"), "3f9c2a7d1e0b4c5a6978a1b2c3d4e5f607182930\n").unwrap();
+        std::fs::write(worktree.path().join(".git"), format!("gitdir: {}\n", worktree_git.display())).unwrap();
+        assert_eq!(current_branch(worktree.path()).as_deref(), Some("3f9c2a7"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Sessions grouped by day or by project.
+//!
+//! A flat list of a week's sessions is hard to scan, so `sessions
+//! --group-by` and the TUI's History tab show them in groups: by the day
+//! they were last active (in the timezone timestamps are shown in, see
+//! `timefmt`) or by project. Groups and the sessions in them are newest
+//! first, and each group carries its totals for the separator line.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::models::Session;
+use crate::timefmt::{self, TimestampConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// The day the session was last active
+    Day,
+    /// The project directory
+    Project,
+}
+
+/// Sessions sharing a day or a project.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionGroup {
+    /// `2026-03-14` for days, the project path for projects
+    pub key: String,
+    /// `Today`, `Yesterday`, `Thu Mar 12`, or the project's name
+    pub label: String,
+    pub tokens: i64,
+    pub cost: f64,
+    pub sessions: Vec<Session>,
+}
+
+impl SessionGroup {
+    /// The separator line, like `Today · 4 sessions · 1.2M tokens · $3.10`.
+    pub fn heading(&self) -> String {
+        let count = self.sessions.len();
+        format!(
+            "{} · {} session{} · {} tokens · {}",
+            self.label,
+            count,
+            if count == 1 { "" } else { "s" },
+            crate::humanize::tokens(self.tokens),
+            crate::humanize::cost(self.cost)
+        )
+    }
+}
+
+/// Group `sessions` the way timestamps are currently shown.
+pub fn group(sessions: Vec<Session>, by: GroupBy) -> Vec<SessionGroup> {
+    group_with(sessions, by, timefmt::config(), Utc::now())
+}
+
+fn group_with(mut sessions: Vec<Session>, by: GroupBy, config: &TimestampConfig, now: DateTime<Utc>) -> Vec<SessionGroup> {
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_activity_at));
+    let day = |time: DateTime<Utc>| config.absolute(time, "%Y-%m-%d");
+    let today = day(now);
+    let yesterday = day(now - Duration::days(1));
+
+    let mut groups: Vec<SessionGroup> = Vec::new();
+    for session in sessions {
+        let key = match by {
+            GroupBy::Day => day(session.last_activity_at),
+            GroupBy::Project => session.project_path.clone(),
+        };
+        // Sessions are newest first, so a group's first session decides
+        // where it goes
+        let index = match groups.iter().position(|g| g.key == key) {
+            Some(index) => index,
+            None => {
+                let label = match by {
+                    GroupBy::Day if key == today => "Today".to_string(),
+                    GroupBy::Day if key == yesterday => "Yesterday".to_string(),
+                    GroupBy::Day => config.absolute(session.last_activity_at, "%a %b %-d"),
+                    GroupBy::Project => key.rsplit('/').find(|p| !p.is_empty()).unwrap_or("—").to_string(),
+                };
+                groups.push(SessionGroup { key, label, tokens: 0, cost: 0.0, sessions: Vec::new() });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.tokens += session.tokens_input + session.tokens_output;
+        group.cost += session.estimated_cost;
+        group.sessions.push(session);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use crate::timefmt::TimeZoneSetting;
+
+    #[test]
+    fn test_groups_by_day_and_project() {
+        let now = DateTime::parse_from_rfc3339("2026-03-14T12:00:00Z").unwrap().with_timezone(&Utc);
+        let session = |project: &str, hours_ago: i64, cost: f64| {
+            let mut s = Session::new(AgentType::ClaudeCode, project, &format!("{}-{}", project, hours_ago));
+            s.last_activity_at = now - Duration::hours(hours_ago);
+            s.tokens_input = 1_000;
+            s.estimated_cost = cost;
+            s
+        };
+        let sessions = vec![
+            session("/src/api", 30, 1.0),
+            session("/src/web", 2, 0.5),
+            session("/src/api", 1, 0.25),
+            session("/src/web", 100, 2.0),
+        ];
+        let utc = TimestampConfig { timezone: TimeZoneSetting::Utc, ..TimestampConfig::default() };
+
+        let days = group_with(sessions.clone(), GroupBy::Day, &utc, now);
+        let labels: Vec<&str> = days.iter().map(|g| g.label.as_str()).collect();
+        assert_eq!(labels, ["Today", "Yesterday", "Tue Mar 10"]);
+        assert_eq!(days[0].key, "2026-03-14");
+        assert_eq!(days[0].sessions.len(), 2);
+        assert_eq!(days[0].sessions[0].project_path, "/src/api");
+        assert_eq!(days[0].tokens, 2_000);
+        assert!((days[0].cost - 0.75).abs() < 1e-9);
+        assert_eq!(days[0].heading(), "Today · 2 sessions · 2.0K tokens · $0.75");
+
+        let projects = group_with(sessions, GroupBy::Project, &utc, now);
+        let labels: Vec<&str> = projects.iter().map(|g| g.label.as_str()).collect();
+        assert_eq!(labels, ["api", "web"]);
+        assert_eq!(projects[1].sessions.len(), 2);
+        assert_eq!(projects[1].heading(), "web · 2 sessions · 2.0K tokens · $2.50");
+    }
+}
@@ -0,0 +1,203 @@
+//! Syntax highlighting of code in event content.
+//!
+//! Fenced code blocks (```` ```rust ````) are highlighted with syntect using
+//! the fence's language, and content that is a unified diff as a whole is
+//! highlighted as one. Everything else is left as is. Output keeps one entry
+//! per content line, so line counts and scroll positions don't change; the
+//! TUI maps the colors onto terminal RGB and the web dashboard onto inline
+//! styles.
+
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Theme the colors come from; readable on the dark TUI and dashboard.
+const THEME: &str = "base16-eighties.dark";
+
+/// Color of fence lines.
+const FENCE: Rgb = (110, 110, 110);
+
+/// A foreground color.
+pub type Rgb = (u8, u8, u8);
+
+/// A piece of a line and its color; None keeps the surrounding text color.
+pub type Run<'a> = (Option<Rgb>, &'a str);
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+/// Whether `content` has anything to highlight.
+pub fn has_code(content: &str) -> bool {
+    is_diff(content) || content.lines().any(|line| fence(line).is_some())
+}
+
+/// The opening or closing marker of a code fence, with the info string
+/// (language) after it.
+fn fence(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("```").or_else(|| line.strip_prefix("~~~")).map(str::trim)
+}
+
+/// A unified diff: file headers or hunk headers, with added or removed lines.
+fn is_diff(content: &str) -> bool {
+    let mut headers = false;
+    let mut changes = false;
+    for line in content.lines() {
+        if line.starts_with("@@ ") || line.starts_with("diff --git ") || line.starts_with("+++ ") {
+            headers = true;
+        } else if line.starts_with('+') || line.starts_with('-') {
+            changes = true;
+        }
+    }
+    headers && changes
+}
+
+/// Syntax for a fence's info string, e.g. `rust`, `py`, `ts` or `sh`.
+fn syntax_for(info: &str) -> Option<&'static SyntaxReference> {
+    let token = info.split(|c: char| c.is_whitespace() || c == ',' || c == '{').next()?;
+    if token.is_empty() {
+        return None;
+    }
+    let token = match token.to_ascii_lowercase().as_str() {
+        "ts" | "typescript" | "tsx" | "jsx" => "js".to_string(),
+        "shell" | "console" | "zsh" => "sh".to_string(),
+        "yml" => "yaml".to_string(),
+        other => other.to_string(),
+    };
+    syntaxes().find_syntax_by_token(&token)
+}
+
+/// Highlight every line of `code` with `syntax`.
+fn highlight_block<'a>(code: &[&'a str], syntax: &SyntaxReference, out: &mut Vec<Vec<Run<'a>>>) {
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    for line in code {
+        // syntect tracks state across lines through their newlines
+        let with_newline = format!("{}\n", line);
+        let runs = match highlighter.highlight_line(&with_newline, syntaxes()) {
+            Ok(styled) => {
+                let mut at = 0;
+                styled
+                    .into_iter()
+                    .filter_map(|(style, text)| {
+                        let end = (at + text.len()).min(line.len());
+                        let run = line.get(at..end).filter(|t| !t.is_empty());
+                        at = end;
+                        run.map(|t| (Some((style.foreground.r, style.foreground.g, style.foreground.b)), t))
+                    })
+                    .collect()
+            }
+            Err(_) => vec![(None, *line)],
+        };
+        out.push(runs);
+    }
+}
+
+/// Runs for each line of `code` in the language `info` names; uncolored when
+/// the language is unknown.
+pub fn highlight_code<'a>(code: &'a str, info: &str) -> Vec<Vec<Run<'a>>> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    match syntax_for(info) {
+        Some(syntax) => highlight_block(&lines, syntax, &mut out),
+        None => out.extend(lines.iter().map(|line| vec![(None, *line)])),
+    }
+    out
+}
+
+/// Runs for each line of `content`.
+pub fn highlight(content: &str) -> Vec<Vec<Run<'_>>> {
+    let lines: Vec<&str> = LinesWithEndings::from(content)
+        .map(|l| l.trim_end_matches(['\n', '\r']))
+        .collect();
+    let mut out = Vec::with_capacity(lines.len());
+
+    if is_diff(content) {
+        if let Some(diff) = syntaxes().find_syntax_by_token("diff") {
+            highlight_block(&lines, diff, &mut out);
+            return out;
+        }
+    }
+
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(info) = fence(lines[i]) else {
+            out.push(vec![(None, lines[i])]);
+            i += 1;
+            continue;
+        };
+        out.push(vec![(Some(FENCE), lines[i])]);
+        let start = i + 1;
+        let end = (start..lines.len()).find(|&j| fence(lines[j]) == Some("")).unwrap_or(lines.len());
+        let code = &lines[start..end];
+        match syntax_for(info) {
+            Some(syntax) => highlight_block(code, syntax, &mut out),
+            None => out.extend(code.iter().map(|line| vec![(None, *line)])),
+        }
+        if end < lines.len() {
+            out.push(vec![(Some(FENCE), lines[end])]);
+        }
+        i = end + 1;
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `content` as HTML for a `<pre>`, with code in colored spans.
+pub fn to_html(content: &str) -> String {
+    runs_to_html(highlight(content))
+}
+
+/// Highlighted lines as HTML for a `<pre>`.
+pub fn runs_to_html(lines: Vec<Vec<Run<'_>>>) -> String {
+    lines
+        .into_iter()
+        .map(|runs| {
+            runs.into_iter()
+                .map(|(color, text)| match color {
+                    Some((r, g, b)) => format!("<span style=\"color:#{:02x}{:02x}{:02x}\">{}</span>", r, g, b, escape_html(text)),
+                    None => escape_html(text),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_keeps_lines_and_colors_code() {
+        let content = "Here is the fix:\n```rust\nfn main() {\n    let x = 1;\n}\n```\nDone <ok>";
+        let lines = highlight(content);
+        assert_eq!(lines.len(), content.lines().count());
+        assert_eq!(lines[0], vec![(None, "Here is the fix:")]);
+        assert_eq!(lines[1], vec![(Some(FENCE), "```rust")]);
+        // Highlighted runs still spell out the line
+        assert_eq!(lines[3].iter().map(|(_, t)| *t).collect::<String>(), "    let x = 1;");
+        assert!(lines[3].iter().any(|(color, _)| color.is_some()));
+        assert_eq!(lines[6], vec![(None, "Done <ok>")]);
+        assert!(to_html(content).ends_with("Done &lt;ok&gt;"));
+
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n same";
+        assert!(has_code(diff));
+        let lines = highlight(diff);
+        assert_eq!(lines.len(), 6);
+        assert_ne!(lines[3][0].0, lines[4][0].0);
+
+        assert!(!has_code("plain text\n- a list item"));
+    }
+}
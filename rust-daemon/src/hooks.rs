@@ -0,0 +1,412 @@
+//! Health of the Claude Code hooks behind `agent-monitor hooks status`.
+//!
+//! A hook is a script in `~/.claude/hooks` that runs this binary, and an
+//! entry under `hooks` in `~/.claude/settings.json` that makes Claude Code run
+//! the script. Claude Code ignores a hook that fails, so a binary that moved
+//! after an upgrade, a script that lost its executable bit or an entry that
+//! was never added all lose events without a word. Each hook is checked for
+//! those, and for running another version or profile than this binary; a
+//! probe event sent through one script shows the daemon answers. `--repair`
+//! rewrites the scripts and settings entries that are wrong.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+use crate::HOOK_EVENTS;
+
+/// Marks a hook payload as a probe, which the daemon answers without acting on.
+pub const PROBE_FIELD: &str = "agent_monitor_probe";
+
+/// Event whose script the probe goes through; it is never blocked or delayed.
+const PROBE_EVENT: &str = "SubagentStop";
+
+/// Seconds a probe may take, including starting the binary.
+const PROBE_TIMEOUT_SECS: u64 = 5;
+
+/// The script `install-hooks` writes for `event`.
+pub fn script_content(event: &str, exe: &Path, profile_arg: &str) -> String {
+    format!(
+        "#!/bin/bash\n# Agent Monitor hook for {}\n\"{}\"{} hook {} < /dev/stdin\n",
+        event,
+        exe.to_string_lossy(),
+        profile_arg,
+        event
+    )
+}
+
+/// Write the script for `event` to `hooks_dir`, executable.
+pub fn write_script(hooks_dir: &Path, event: &str, exe: &Path, profile_arg: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(hooks_dir)?;
+    let path = hooks_dir.join(format!("{}.sh", event));
+    std::fs::write(&path, script_content(event, exe, profile_arg))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(path)
+}
+
+/// The binary a hook script runs and the arguments before `hook`, like
+/// ` --profile work`.
+fn script_target(content: &str) -> Option<(PathBuf, String)> {
+    content.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix('"')?;
+        let (exe, args) = rest.split_once('"')?;
+        let (profile, _) = args.split_once(" hook ")?;
+        Some((PathBuf::from(exe), profile.to_string()))
+    })
+}
+
+/// The program a settings command runs: its first word, or the quoted path.
+fn command_program(command: &str) -> &str {
+    let command = command.trim();
+    match command.strip_prefix('"') {
+        Some(rest) => rest.split('"').next().unwrap_or(rest),
+        None => command.split_whitespace().next().unwrap_or(command),
+    }
+}
+
+/// Commands in `settings` run for `event`.
+fn registered_commands(settings: &Value, event: &str) -> Vec<String> {
+    settings
+        .pointer(&format!("/hooks/{}", event))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|group| group.get("hooks").and_then(Value::as_array))
+        .flatten()
+        .filter_map(|hook| hook.get("command").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether a settings command for `event` is one of ours: the hook script, or
+/// a direct call to `agent-monitor hook`.
+fn is_ours(command: &str, event: &str) -> bool {
+    let program = command_program(command);
+    program.ends_with(&format!("/hooks/{}.sh", event))
+        || (program.contains("agent-monitor") && command.contains(&format!(" hook {}", event)))
+}
+
+/// One hook's state.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookCheck {
+    pub event: String,
+    pub script: PathBuf,
+    /// The binary the script runs
+    pub target: Option<PathBuf>,
+    /// Version of that binary, when it is not this one
+    pub version: Option<String>,
+    /// Whether the script runs this binary and profile
+    pub script_ok: bool,
+    pub registered: bool,
+    /// What is wrong, empty if nothing
+    pub problems: Vec<String>,
+    /// Settings commands of ours that run something missing
+    #[serde(skip)]
+    stale_commands: Vec<String>,
+}
+
+impl HookCheck {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Where hooks live and what they should run.
+pub struct HookSetup {
+    pub hooks_dir: PathBuf,
+    pub settings_path: PathBuf,
+    /// This binary
+    pub exe: PathBuf,
+    pub profile_arg: String,
+}
+
+impl HookSetup {
+    /// The hooks of the current user, running this binary.
+    pub fn current(profile_arg: String) -> Result<Self> {
+        let home = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+        Ok(Self {
+            hooks_dir: home.join(".claude").join("hooks"),
+            settings_path: home.join(".claude").join("settings.json"),
+            exe: std::env::current_exe()?,
+            profile_arg,
+        })
+    }
+
+    fn read_settings(&self) -> Result<Value> {
+        if !self.settings_path.exists() {
+            return Ok(serde_json::json!({}));
+        }
+        let text = std::fs::read_to_string(&self.settings_path)?;
+        serde_json::from_str(&text).map_err(|e| anyhow!("{} is not valid JSON: {}", self.settings_path.display(), e))
+    }
+
+    /// Check every hook.
+    pub fn inspect(&self) -> Result<Vec<HookCheck>> {
+        let settings = self.read_settings()?;
+        Ok(HOOK_EVENTS.iter().map(|event| self.inspect_one(&settings, event)).collect())
+    }
+
+    fn inspect_one(&self, settings: &Value, event: &str) -> HookCheck {
+        let script = self.hooks_dir.join(format!("{}.sh", event));
+        let mut problems = Vec::new();
+        let mut target = None;
+        let mut version = None;
+        match std::fs::read_to_string(&script) {
+            Err(_) => problems.push("script is missing".to_string()),
+            Ok(content) => {
+                let executable = std::fs::metadata(&script).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false);
+                if !executable {
+                    problems.push("script is not executable".to_string());
+                }
+                match script_target(&content) {
+                    None => problems.push("script does not run agent-monitor".to_string()),
+                    Some((exe, profile)) => {
+                        if !is_executable(&exe) {
+                            problems.push(format!("runs {}, which does not exist", exe.display()));
+                        } else if !same_file(&exe, &self.exe) {
+                            version = version_of(&exe);
+                            if version.as_deref() != Some(env!("CARGO_PKG_VERSION")) {
+                                problems.push(format!(
+                                    "runs {} (version {}, this is {})",
+                                    exe.display(),
+                                    version.as_deref().unwrap_or("unknown"),
+                                    env!("CARGO_PKG_VERSION")
+                                ));
+                            }
+                        }
+                        if profile != self.profile_arg {
+                            problems.push(format!("runs a different profile ('{}')", profile.trim()));
+                        }
+                        target = Some(exe);
+                    }
+                }
+            }
+        }
+
+        let script_ok = problems.is_empty();
+
+        let ours: Vec<String> = registered_commands(settings, event).into_iter().filter(|c| is_ours(c, event)).collect();
+        let stale_commands: Vec<String> =
+            ours.iter().filter(|c| !is_executable(Path::new(command_program(c)))).cloned().collect();
+        for command in &stale_commands {
+            problems.push(format!("settings.json runs {}, which does not exist", command_program(command)));
+        }
+        let registered = ours.len() > stale_commands.len();
+        if !registered && stale_commands.is_empty() {
+            problems.push("not registered in settings.json".to_string());
+        }
+
+        HookCheck { event: event.to_string(), script, target, version, script_ok, registered, problems, stale_commands }
+    }
+
+    /// Rewrite the broken scripts and settings entries in `checks`, returning
+    /// the events repaired.
+    pub fn repair(&self, checks: &[HookCheck]) -> Result<Vec<String>> {
+        let mut settings = self.read_settings()?;
+        let mut settings_changed = false;
+        let mut repaired = Vec::new();
+        for check in checks.iter().filter(|c| !c.is_ok()) {
+            if !check.script_ok {
+                write_script(&self.hooks_dir, &check.event, &self.exe, &self.profile_arg)?;
+            }
+            if !check.registered {
+                register(&mut settings, &check.event, &check.script.to_string_lossy(), &check.stale_commands)?;
+                settings_changed = true;
+            }
+            repaired.push(check.event.clone());
+        }
+        if settings_changed {
+            if let Some(parent) = self.settings_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&self.settings_path, serde_json::to_string_pretty(&settings)? + "\n")?;
+        }
+        Ok(repaired)
+    }
+}
+
+/// Make `settings` run `command` for `event`, replacing the `stale` commands
+/// if there are any and adding an entry otherwise.
+fn register(settings: &mut Value, event: &str, command: &str, stale: &[String]) -> Result<()> {
+    let object = settings
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("settings.json is not a JSON object"))?;
+    let hooks = object.entry("hooks").or_insert_with(|| serde_json::json!({}));
+    let groups = hooks
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("'hooks' in settings.json is not a JSON object"))?
+        .entry(event)
+        .or_insert_with(|| serde_json::json!([]));
+    let groups = groups
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("'hooks.{}' in settings.json is not a list", event))?;
+
+    let mut replaced = false;
+    for hook in groups.iter_mut().filter_map(|g| g.get_mut("hooks")).filter_map(Value::as_array_mut).flatten() {
+        let is_stale = hook.get("command").and_then(Value::as_str).is_some_and(|c| stale.iter().any(|s| s == c));
+        if is_stale {
+            hook["command"] = Value::String(command.to_string());
+            replaced = true;
+        }
+    }
+    if !replaced {
+        let mut group = serde_json::json!({ "hooks": [{ "type": "command", "command": command }] });
+        if event.ends_with("ToolUse") {
+            group["matcher"] = Value::String("*".to_string());
+        }
+        groups.push(group);
+    }
+    Ok(())
+}
+
+/// Remove every hook of ours from `settings`, with the groups and events
+/// left empty, returning how many there were.
+pub fn unregister(settings: &mut Value) -> usize {
+    let Some(events) = settings.get_mut("hooks").and_then(Value::as_object_mut) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for (event, groups) in events.iter_mut() {
+        let Some(groups) = groups.as_array_mut() else { continue };
+        for group in groups.iter_mut() {
+            if let Some(hooks) = group.get_mut("hooks").and_then(Value::as_array_mut) {
+                let before = hooks.len();
+                hooks.retain(|h| !h.get("command").and_then(Value::as_str).is_some_and(|c| is_ours(c, event)));
+                removed += before - hooks.len();
+            }
+        }
+        groups.retain(|g| g.get("hooks").and_then(Value::as_array).is_none_or(|h| !h.is_empty()));
+    }
+    events.retain(|_, groups| groups.as_array().is_none_or(|g| !g.is_empty()));
+    if events.is_empty() {
+        if let Some(settings) = settings.as_object_mut() {
+            settings.remove("hooks");
+        }
+    }
+    removed
+}
+
+fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path).map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// The version `exe --version` reports, like `0.4.0`.
+fn version_of(exe: &Path) -> Option<String> {
+    let output = std::process::Command::new(exe).arg("--version").stderr(Stdio::null()).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).split_whitespace().last().map(str::to_string)
+}
+
+/// What came back from a probe event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub detail: String,
+    pub millis: u64,
+}
+
+/// Send a probe event through the hook script the way Claude Code would, and
+/// check the daemon answered it.
+pub async fn probe(hooks_dir: &Path) -> ProbeResult {
+    let started = std::time::Instant::now();
+    let result = tokio::time::timeout(std::time::Duration::from_secs(PROBE_TIMEOUT_SECS), run_probe(hooks_dir)).await;
+    let millis = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(Ok(version)) => ProbeResult { ok: true, detail: format!("daemon {} answered", version), millis },
+        Ok(Err(e)) => ProbeResult { ok: false, detail: format!("{:#}", e), millis },
+        Err(_) => ProbeResult { ok: false, detail: format!("no answer within {}s", PROBE_TIMEOUT_SECS), millis },
+    }
+}
+
+async fn run_probe(hooks_dir: &Path) -> Result<String> {
+    let script = hooks_dir.join(format!("{}.sh", PROBE_EVENT));
+    let mut child = tokio::process::Command::new(&script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("could not run {}: {}", script.display(), e))?;
+    let payload = serde_json::json!({ "hook_event_name": PROBE_EVENT, PROBE_FIELD: true });
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.to_string().as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let response: Value = serde_json::from_str(stdout.trim())
+        .map_err(|_| anyhow!("the daemon did not answer (is it running? 'agent-monitor daemon')"))?;
+    response
+        .pointer("/probe/version")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("unexpected answer: {}", stdout.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_hooks_are_found_and_repaired() {
+        let dir = tempfile::tempdir().unwrap();
+        let setup = HookSetup {
+            hooks_dir: dir.path().join("hooks"),
+            settings_path: dir.path().join("settings.json"),
+            exe: std::env::current_exe().unwrap(),
+            profile_arg: String::new(),
+        };
+        assert_eq!(
+            script_target(&script_content("PreToolUse", Path::new("/opt/agent monitor"), " --profile work")),
+            Some((PathBuf::from("/opt/agent monitor"), " --profile work".to_string()))
+        );
+
+        // Nothing installed
+        let checks = setup.inspect().unwrap();
+        assert_eq!(checks.len(), HOOK_EVENTS.len());
+        assert_eq!(checks[0].problems, vec!["script is missing", "not registered in settings.json"]);
+
+        // Scripts left behind by a binary that moved, one not executable, and
+        // a settings entry pointing at a removed hooks directory
+        for event in HOOK_EVENTS {
+            write_script(&setup.hooks_dir, event, Path::new("/gone/agent-monitor"), "").unwrap();
+        }
+        let pre_tool = setup.hooks_dir.join("PreToolUse.sh");
+        std::fs::set_permissions(&pre_tool, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let settings = serde_json::json!({
+            "model": "opus",
+            "hooks": {
+                "PreToolUse": [{ "matcher": "*", "hooks": [
+                    { "type": "command", "command": "/old/home/.claude/hooks/PreToolUse.sh" },
+                    { "type": "command", "command": "lint-check" },
+                ]}],
+            },
+        });
+        std::fs::write(&setup.settings_path, settings.to_string()).unwrap();
+        let checks = setup.inspect().unwrap();
+        let pre = checks.iter().find(|c| c.event == "PreToolUse").unwrap();
+        assert_eq!(pre.problems.len(), 3);
+        assert!(pre.problems[0].contains("not executable"));
+        assert!(pre.problems[1].contains("/gone/agent-monitor"));
+        assert!(pre.problems[2].contains("/old/home"));
+
+        let repaired = setup.repair(&checks).unwrap();
+        assert_eq!(repaired.len(), HOOK_EVENTS.len());
+        assert!(setup.inspect().unwrap().iter().all(HookCheck::is_ok));
+        let settings: Value = serde_json::from_str(&std::fs::read_to_string(&setup.settings_path).unwrap()).unwrap();
+        assert_eq!(settings["model"], "opus");
+        assert_eq!(
+            registered_commands(&settings, "PreToolUse"),
+            vec![pre_tool.to_string_lossy().to_string(), "lint-check".to_string()]
+        );
+        assert_eq!(settings["hooks"]["SessionStart"][0].get("matcher"), None);
+    }
+}
@@ -0,0 +1,214 @@
+//! Durations, token counts, sizes and costs for people to read.
+//!
+//! The CLI, the TUI, reports and notifications all format numbers through
+//! here, so the `humanize` settings change them everywhere at once: how many
+//! decimals compact units and costs get, from which value the next unit is
+//! used, and how durations are written. Tables and the TUI use
+//! `durations`; reports and session summaries use `report_durations`, which
+//! can spell them out ("2 hours 13 minutes"). The settings are picked once at
+//! startup like the timestamp settings (see `timefmt`).
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// How a duration is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationStyle {
+    /// The largest unit with decimals, like `2.2h`
+    #[default]
+    Compact,
+    /// Hours and minutes, like `2h 13m`
+    HoursMinutes,
+    /// Spelled out, like `2 hours 13 minutes`
+    Long,
+}
+
+/// How numbers are shown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HumanizeConfig {
+    /// Decimals of compact units (`1.5h`, `12.3K`, `4.2 MB`)
+    pub precision: usize,
+
+    /// Decimals of costs
+    pub cost_precision: usize,
+
+    /// Durations in tables and the TUI
+    pub durations: DurationStyle,
+
+    /// Durations in reports and session summaries
+    pub report_durations: DurationStyle,
+
+    /// Seconds from which durations are shown in minutes
+    pub minutes_from: f64,
+
+    /// Seconds from which durations are shown in hours
+    pub hours_from: f64,
+
+    /// Tokens from which counts are shown in thousands
+    pub thousands_from: i64,
+
+    /// Tokens from which counts are shown in millions
+    pub millions_from: i64,
+}
+
+impl Default for HumanizeConfig {
+    fn default() -> Self {
+        Self {
+            precision: 1,
+            cost_precision: 2,
+            durations: DurationStyle::Compact,
+            report_durations: DurationStyle::HoursMinutes,
+            minutes_from: 60.0,
+            hours_from: 3600.0,
+            thousands_from: 1_000,
+            millions_from: 1_000_000,
+        }
+    }
+}
+
+impl HumanizeConfig {
+    pub fn duration(&self, seconds: f64, style: DurationStyle) -> String {
+        let seconds = seconds.max(0.0);
+        match style {
+            DurationStyle::Compact => {
+                if seconds >= self.hours_from {
+                    format!("{:.*}h", self.precision, seconds / 3600.0)
+                } else if seconds >= self.minutes_from {
+                    format!("{:.*}m", self.precision, seconds / 60.0)
+                } else {
+                    format!("{:.0}s", seconds)
+                }
+            }
+            DurationStyle::HoursMinutes => {
+                let minutes = (seconds / 60.0).round() as i64;
+                if seconds < self.minutes_from {
+                    format!("{:.0}s", seconds)
+                } else if seconds >= self.hours_from {
+                    format!("{}h {}m", minutes / 60, minutes % 60)
+                } else {
+                    format!("{}m", minutes)
+                }
+            }
+            DurationStyle::Long => {
+                let plural = |n: i64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+                let minutes = (seconds / 60.0).round() as i64;
+                if seconds < self.minutes_from {
+                    plural(seconds.round() as i64, "second")
+                } else if seconds < self.hours_from {
+                    plural(minutes, "minute")
+                } else if minutes % 60 == 0 {
+                    plural(minutes / 60, "hour")
+                } else {
+                    format!("{} {}", plural(minutes / 60, "hour"), plural(minutes % 60, "minute"))
+                }
+            }
+        }
+    }
+
+    pub fn tokens(&self, count: i64) -> String {
+        if count >= self.millions_from {
+            format!("{:.*}M", self.precision, count as f64 / 1_000_000.0)
+        } else if count >= self.thousands_from {
+            format!("{:.*}K", self.precision, count as f64 / 1_000.0)
+        } else {
+            format!("{}", count)
+        }
+    }
+
+    pub fn bytes(&self, bytes: i64) -> String {
+        let bytes = bytes as f64;
+        let units = [(1024.0 * 1024.0 * 1024.0, "GB"), (1024.0 * 1024.0, "MB"), (1024.0, "KB")];
+        match units.iter().find(|(size, _)| bytes >= *size) {
+            Some((size, unit)) => format!("{:.*} {}", self.precision, bytes / size, unit),
+            None => format!("{} B", bytes),
+        }
+    }
+
+    pub fn cost(&self, dollars: f64) -> String {
+        format!("${:.*}", self.cost_precision, dollars)
+    }
+}
+
+static CONFIG: OnceLock<HumanizeConfig> = OnceLock::new();
+
+/// Use `config` for this run. Later calls have no effect.
+pub fn init(config: &HumanizeConfig) {
+    CONFIG.get_or_init(|| config.clone());
+}
+
+/// The active settings; the defaults until [`init`] runs.
+pub fn config() -> &'static HumanizeConfig {
+    CONFIG.get_or_init(HumanizeConfig::default)
+}
+
+/// A duration in tables and the TUI, like `2.2h`.
+pub fn duration(seconds: f64) -> String {
+    config().duration(seconds, config().durations)
+}
+
+/// A duration in a report or summary, like `2h 13m`.
+pub fn report_duration(seconds: f64) -> String {
+    config().duration(seconds, config().report_durations)
+}
+
+/// A duration given in milliseconds, which short ones stay in.
+pub fn duration_ms(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{:.0}ms", ms)
+    } else if ms < 60_000.0 {
+        format!("{:.*}s", config().precision, ms / 1000.0)
+    } else {
+        duration(ms / 1000.0)
+    }
+}
+
+/// A token count, like `312.4K`.
+pub fn tokens(count: i64) -> String {
+    config().tokens(count)
+}
+
+/// A size, like `4.2 MB`.
+pub fn bytes(bytes: i64) -> String {
+    config().bytes(bytes)
+}
+
+/// A cost in dollars, like `$1.50`.
+pub fn cost(dollars: f64) -> String {
+    config().cost(dollars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_precision_and_thresholds() {
+        let config = HumanizeConfig::default();
+        let long = 2.0 * 3600.0 + 13.0 * 60.0;
+        assert_eq!(config.duration(long, DurationStyle::Compact), "2.2h");
+        assert_eq!(config.duration(long, DurationStyle::HoursMinutes), "2h 13m");
+        assert_eq!(config.duration(long, DurationStyle::Long), "2 hours 13 minutes");
+        assert_eq!(config.duration(3600.0, DurationStyle::Long), "1 hour");
+        assert_eq!(config.duration(61.0, DurationStyle::Long), "1 minute");
+        assert_eq!(config.duration(42.0, DurationStyle::HoursMinutes), "42s");
+        assert_eq!(config.tokens(312_400), "312.4K");
+        assert_eq!(config.tokens(999), "999");
+        assert_eq!(config.bytes(4 * 1024 * 1024 + 200_000), "4.2 MB");
+        assert_eq!(config.cost(1.5), "$1.50");
+
+        let precise = HumanizeConfig {
+            precision: 2,
+            cost_precision: 4,
+            minutes_from: 120.0,
+            thousands_from: 10_000,
+            ..HumanizeConfig::default()
+        };
+        assert_eq!(precise.duration(90.0, DurationStyle::Compact), "90s");
+        assert_eq!(precise.duration(150.0, DurationStyle::Compact), "2.50m");
+        assert_eq!(precise.tokens(9_500), "9500");
+        assert_eq!(precise.tokens(12_345), "12.35K");
+        assert_eq!(precise.cost(0.01234), "$0.0123");
+    }
+}
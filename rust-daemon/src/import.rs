@@ -0,0 +1,598 @@
+//! Importing history recorded by other tools.
+//!
+//! `agent-monitor import --from python-daemon <path>` reads what the earlier
+//! Python implementation of the monitor recorded: its SQLite database, or the
+//! JSON its `status --json` and `sessions --json` commands printed. The Python
+//! daemon stored naive local times, which are read as local time here.
+//!
+//! `--from ccusage` reads a ccusage JSON report (`daily`, `weekly`,
+//! `monthly`, `session` or `blocks`) and `--from claude-desktop` the
+//! `conversations.json` of a Claude app data export. Neither knows about
+//! individual sessions, so each period, project, block or conversation
+//! becomes one coarse session carrying its tokens and cost; the reports
+//! overlap, so import only one of them. `--before` keeps only what started
+//! before a day, such as the day this monitor was installed, so nothing is
+//! counted twice.
+//!
+//! Imported records keep stable IDs, so importing the same data twice
+//! updates it instead of duplicating it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteRow};
+use sqlx::{Column, Connection, Row};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::models::{AgentType, EventType, Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+/// Where imported history comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportSource {
+    /// The Python agent monitor: its sessions.db, or JSON its CLI printed
+    PythonDaemon,
+    /// A ccusage JSON report of Claude Code usage
+    Ccusage,
+    /// conversations.json from a Claude app data export
+    ClaudeDesktop,
+}
+
+impl ImportSource {
+    /// Name stored in the metadata of imported sessions.
+    fn key(self) -> &'static str {
+        match self {
+            ImportSource::PythonDaemon => "python_daemon",
+            ImportSource::Ccusage => "ccusage",
+            ImportSource::ClaudeDesktop => "claude_desktop",
+        }
+    }
+}
+
+/// What an import stored.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ImportCounts {
+    pub sessions: usize,
+    pub events: usize,
+    /// Records that could not be read or started too late, and events of
+    /// sessions not imported
+    pub skipped: usize,
+}
+
+/// Import what `source` recorded at `path` into `storage`, only sessions
+/// that started `before` a time if given.
+pub async fn import(
+    storage: &Storage,
+    source: ImportSource,
+    path: &Path,
+    before: Option<DateTime<Utc>>,
+) -> Result<ImportCounts> {
+    let mut sink = Sink { storage, before, counts: ImportCounts::default(), imported: HashSet::new() };
+    match source {
+        ImportSource::PythonDaemon => python_daemon(&mut sink, path).await?,
+        ImportSource::Ccusage => ccusage(&mut sink, &read_json(path)?).await?,
+        ImportSource::ClaudeDesktop => claude_desktop(&mut sink, &read_json(path)?).await?,
+    }
+    Ok(sink.counts)
+}
+
+/// Stores imported sessions, counting them.
+struct Sink<'a> {
+    storage: &'a Storage,
+    before: Option<DateTime<Utc>>,
+    counts: ImportCounts,
+    /// IDs of the sessions stored
+    imported: HashSet<String>,
+}
+
+impl Sink<'_> {
+    async fn session(&mut self, session: Option<Session>) -> Result<()> {
+        match session.filter(|s| self.before.is_none_or(|before| s.started_at < before)) {
+            Some(session) => {
+                self.storage.upsert_session(&session).await?;
+                self.imported.insert(session.id);
+                self.counts.sessions += 1;
+            }
+            None => self.counts.skipped += 1,
+        }
+        Ok(())
+    }
+
+    async fn event(&mut self, event: Option<SessionEvent>) -> Result<()> {
+        match event.filter(|e| self.imported.contains(&e.session_id)) {
+            Some(event) => {
+                self.storage.insert_event(&event).await?;
+                self.counts.events += 1;
+            }
+            None => self.counts.skipped += 1,
+        }
+        Ok(())
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("{} is not JSON", path.display()))
+}
+
+/// Magic bytes at the start of every SQLite database.
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+async fn python_daemon(sink: &mut Sink<'_>, path: &Path) -> Result<()> {
+    // A data directory holds the database under its default name
+    let path: PathBuf = if path.is_dir() { path.join("sessions.db") } else { path.to_path_buf() };
+    let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if bytes.starts_with(SQLITE_HEADER) {
+        drop(bytes);
+        return python_database(sink, &path).await;
+    }
+
+    let data: Value = serde_json::from_slice(&bytes)
+        .with_context(|| format!("{} is neither a SQLite database nor JSON", path.display()))?;
+    // `sessions --json` prints a list, `status --json` an object holding one
+    let sessions = match &data {
+        Value::Array(sessions) => sessions,
+        Value::Object(status) => match status.get("sessions") {
+            Some(Value::Array(sessions)) => sessions,
+            _ => anyhow::bail!("{} has no sessions", path.display()),
+        },
+        _ => anyhow::bail!("{} has no sessions", path.display()),
+    };
+
+    for record in sessions {
+        sink.session(python_session(record)).await?;
+    }
+    Ok(())
+}
+
+async fn python_database(sink: &mut Sink<'_>, path: &Path) -> Result<()> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+    let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+        .fetch_all(&mut conn)
+        .await?;
+    // The Python schema tracks its version; this monitor's does not
+    if !tables.iter().any(|t| t == "schema_version") || !tables.iter().any(|t| t == "sessions") {
+        anyhow::bail!("{} is not a database of the Python agent monitor", path.display());
+    }
+
+    let mut rows = sqlx::query("SELECT * FROM sessions").fetch(&mut conn);
+    while let Some(row) = rows.try_next().await? {
+        sink.session(python_session(&row_to_json(&row))).await?;
+    }
+    drop(rows);
+
+    if tables.iter().any(|t| t == "session_events") {
+        let mut rows = sqlx::query("SELECT * FROM session_events ORDER BY timestamp").fetch(&mut conn);
+        while let Some(row) = rows.try_next().await? {
+            sink.event(python_event(&row_to_json(&row))).await?;
+        }
+    }
+
+    conn.close().await?;
+    Ok(())
+}
+
+/// A row as the JSON the Python models serialize to: `*_json` columns are
+/// parsed and stored without the suffix.
+fn row_to_json(row: &SqliteRow) -> Value {
+    let mut fields = Map::new();
+    for column in row.columns() {
+        let name = column.name();
+        let value = if let Ok(Some(n)) = row.try_get::<Option<i64>, _>(name) {
+            json!(n)
+        } else if let Ok(Some(x)) = row.try_get::<Option<f64>, _>(name) {
+            json!(x)
+        } else if let Ok(Some(text)) = row.try_get::<Option<String>, _>(name) {
+            match name.strip_suffix("_json") {
+                Some(field) => {
+                    fields.insert(field.to_string(), serde_json::from_str(&text).unwrap_or(Value::Null));
+                    continue;
+                }
+                None => Value::String(text),
+            }
+        } else {
+            Value::Null
+        };
+        fields.insert(name.to_string(), value);
+    }
+    Value::Object(fields)
+}
+
+/// A time the Python daemon stored: ISO 8601, usually without an offset.
+fn python_time(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.as_str()?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())?;
+    Some(Local.from_local_datetime(&naive).earliest()?.with_timezone(&Utc))
+}
+
+fn text(record: &Value, key: &str) -> Option<String> {
+    record.get(key)?.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+fn int(record: &Value, key: &str) -> Option<i64> {
+    record.get(key)?.as_i64()
+}
+
+fn float(record: &Value, key: &str) -> Option<f64> {
+    record.get(key)?.as_f64()
+}
+
+/// One of this monitor's enums from its snake_case name.
+fn variant<T: serde::de::DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(Value::String(name.to_string())).ok()
+}
+
+/// A session from a Python session record.
+fn python_session(record: &Value) -> Option<Session> {
+    let id = text(record, "id")?;
+    let agent_type = text(record, "agent_type").and_then(|t| variant(&t)).unwrap_or(AgentType::Custom);
+    let project_path = text(record, "project_path").unwrap_or_default();
+    let external_id = text(record, "external_id").unwrap_or_else(|| id.clone());
+
+    let mut session = Session::new(agent_type, &project_path, &external_id);
+    session.id = id;
+    session.started_at = python_time(record.get("started_at")?)?;
+    session.last_activity_at = record
+        .get("last_activity_at")
+        .and_then(python_time)
+        .unwrap_or(session.started_at);
+    session.ended_at = record.get("ended_at").and_then(python_time);
+    // Nothing the Python daemon saw running still is
+    session.status = match text(record, "status").and_then(|s| variant(&s)) {
+        Some(SessionStatus::Crashed) => SessionStatus::Crashed,
+        _ => SessionStatus::Completed,
+    };
+    if session.ended_at.is_none() {
+        session.ended_at = Some(session.last_activity_at);
+    }
+    session.duration_seconds = float(record, "duration_seconds")
+        .unwrap_or_else(|| (session.last_activity_at - session.started_at).num_seconds() as f64);
+    session.message_count = int(record, "message_count").unwrap_or(0);
+    session.tool_call_count = int(record, "tool_call_count").unwrap_or(0);
+    session.file_operations = int(record, "file_operations").unwrap_or(0);
+    session.tokens_input = int(record, "tokens_input").unwrap_or(0).max(0);
+    session.tokens_output = int(record, "tokens_output").unwrap_or(0).max(0);
+    session.estimated_cost = float(record, "estimated_cost").unwrap_or(0.0);
+    session.model_id = text(record, "model_id");
+    session.current_task = text(record, "current_task");
+    session.progress = float(record, "progress").unwrap_or(0.0);
+
+    if let Some(Value::Object(metadata)) = record.get("metadata") {
+        session.metadata.extend(metadata.clone());
+    }
+    session.metadata.insert("imported_from".to_string(), json!(ImportSource::PythonDaemon.key()));
+    Some(session)
+}
+
+/// This monitor's event type for a Python one; None when there is none.
+fn python_event_type(name: &str) -> Option<EventType> {
+    match name {
+        "response_started" | "response_completed" => Some(EventType::ResponseGenerated),
+        "tool_error" => Some(EventType::Error),
+        "file_write" | "file_edit" | "file_delete" => Some(EventType::FileModified),
+        "custom" => Some(EventType::Custom),
+        other => variant(other).filter(|t| *t != EventType::Custom),
+    }
+}
+
+/// An event from a Python event record.
+fn python_event(record: &Value) -> Option<SessionEvent> {
+    let name = text(record, "event_type")?;
+    let agent_type = text(record, "agent_type").and_then(|t| variant(&t)).unwrap_or(AgentType::Custom);
+
+    let mut event = SessionEvent::new(&text(record, "session_id")?, EventType::Custom, agent_type);
+    event.id = text(record, "id")?;
+    event.timestamp = python_time(record.get("timestamp")?)?;
+    event.content = text(record, "content");
+    event.working_directory = text(record, "working_directory");
+    event.tool_name = text(record, "tool_name");
+    event.file_path = text(record, "file_path");
+    event.tokens_input = int(record, "tokens_input");
+    event.tokens_output = int(record, "tokens_output");
+    event.error_message = text(record, "error_message");
+    event.tool_input = record.get("tool_input").filter(|v| !v.is_null()).cloned();
+    if let Some(output) = record.get("tool_output").filter(|v| !v.is_null()) {
+        match output {
+            Value::String(output) => event.set_tool_output(output),
+            other => event.set_tool_output(&other.to_string()),
+        }
+    }
+    event.duration_ms = int(record, "tool_duration_ms");
+    event.raw_data = record.get("raw_data").filter(|v| !v.is_null()).cloned();
+
+    match python_event_type(&name) {
+        Some(event_type) => event.event_type = event_type,
+        // Kept as custom, with the type it had
+        None => {
+            event.raw_data = Some(json!({ "python_event_type": name, "raw_data": event.raw_data }));
+        }
+    }
+    Some(event)
+}
+
+/// Reports ccusage prints as JSON, by their top-level key.
+const CCUSAGE_REPORTS: &[&str] = &["daily", "weekly", "monthly", "sessions", "blocks"];
+
+async fn ccusage(sink: &mut Sink<'_>, data: &Value) -> Result<()> {
+    let Some((report, records)) = CCUSAGE_REPORTS
+        .iter()
+        .find_map(|report| Some((*report, data.get(*report)?.as_array()?)))
+    else {
+        anyhow::bail!("Not a ccusage JSON report; expected one of: {}", CCUSAGE_REPORTS.join(", "));
+    };
+    for record in records {
+        // Blocks cover every five hours, used or not
+        if record.get("isGap").and_then(Value::as_bool) == Some(true) {
+            continue;
+        }
+        sink.session(ccusage_session(report, record)).await?;
+    }
+    Ok(())
+}
+
+/// Local midnight of `day`.
+pub fn start_of_day(day: NaiveDate) -> Option<DateTime<Utc>> {
+    Some(Local.from_local_datetime(&day.and_hms_opt(0, 0, 0)?).earliest()?.with_timezone(&Utc))
+}
+
+/// Start of a ccusage period: a day, the first day of a week or a month,
+/// or a timestamp.
+fn ccusage_time(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.as_str()?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", text), "%Y-%m-%d"))
+        .ok()?;
+    start_of_day(day)
+}
+
+/// A session standing for one record of a ccusage report.
+fn ccusage_session(report: &str, record: &Value) -> Option<Session> {
+    let (key, started_at, ended_at) = match report {
+        "daily" => (text(record, "date")?, record.get("date")?, None),
+        "weekly" => (text(record, "week")?, record.get("week")?, None),
+        "monthly" => (text(record, "month")?, record.get("month")?, None),
+        "sessions" => (text(record, "sessionId")?, record.get("lastActivity")?, None),
+        _ => (
+            text(record, "id").or_else(|| text(record, "startTime"))?,
+            record.get("startTime")?,
+            record.get("actualEndTime").or_else(|| record.get("endTime")),
+        ),
+    };
+    let started_at = ccusage_time(started_at)?;
+    let ended_at = ended_at.and_then(ccusage_time).unwrap_or(started_at);
+
+    // Blocks count tokens apart from their other fields
+    let counts = record.get("tokenCounts").unwrap_or(record);
+    let tokens = |keys: &[&str]| keys.iter().find_map(|key| int(counts, key)).unwrap_or(0).max(0);
+    let models: Vec<Value> = ["modelsUsed", "models"]
+        .iter()
+        .find_map(|key| record.get(*key)?.as_array().cloned())
+        .unwrap_or_default();
+
+    let project = if report == "sessions" { text(record, "projectPath") } else { None };
+    let mut session = Session::new(AgentType::ClaudeCode, project.as_deref().unwrap_or(""), &key);
+    session.id = format!("ccusage-{}-{}", report, key);
+    session.status = SessionStatus::Completed;
+    session.started_at = started_at;
+    session.last_activity_at = ended_at;
+    session.ended_at = Some(ended_at);
+    // How long Claude Code was in use is not known
+    session.duration_seconds = 0.0;
+    session.message_count = int(record, "entries").unwrap_or(0);
+    session.tokens_input = tokens(&["inputTokens"]);
+    session.tokens_output = tokens(&["outputTokens"]);
+    session.estimated_cost = ["totalCost", "costUSD"].iter().find_map(|key| float(record, key)).unwrap_or(0.0);
+    session.model_id = models.first().and_then(Value::as_str).map(str::to_string);
+
+    session.metadata.insert("imported_from".to_string(), json!(ImportSource::Ccusage.key()));
+    session.metadata.insert("ccusage_report".to_string(), json!(report));
+    session.metadata.insert("models".to_string(), Value::Array(models));
+    session.metadata.insert(
+        "cache_creation_tokens".to_string(),
+        json!(tokens(&["cacheCreationTokens", "cacheCreationInputTokens"])),
+    );
+    session.metadata.insert(
+        "cache_read_tokens".to_string(),
+        json!(tokens(&["cacheReadTokens", "cacheReadInputTokens"])),
+    );
+    Some(session)
+}
+
+/// Project the conversations of the Claude apps are grouped under.
+const CLAUDE_DESKTOP_PROJECT: &str = "Claude desktop";
+
+async fn claude_desktop(sink: &mut Sink<'_>, data: &Value) -> Result<()> {
+    let Some(conversations) = data.as_array() else {
+        anyhow::bail!("Not a Claude conversations export; expected a list of conversations");
+    };
+    for conversation in conversations {
+        sink.session(claude_desktop_session(conversation)).await?;
+    }
+    Ok(())
+}
+
+/// A session standing for one conversation in the Claude apps.
+fn claude_desktop_session(conversation: &Value) -> Option<Session> {
+    let uuid = text(conversation, "uuid")?;
+    let messages = conversation.get("chat_messages").and_then(Value::as_array);
+    let started_at = python_time(conversation.get("created_at")?)?;
+    let last_activity_at = messages
+        .and_then(|m| m.last())
+        .and_then(|m| m.get("created_at"))
+        .or_else(|| conversation.get("updated_at"))
+        .and_then(python_time)
+        .unwrap_or(started_at);
+
+    let mut session = Session::new(AgentType::Custom, CLAUDE_DESKTOP_PROJECT, &uuid);
+    session.id = format!("claude-desktop-{}", uuid);
+    session.status = SessionStatus::Completed;
+    session.started_at = started_at;
+    session.last_activity_at = last_activity_at;
+    session.ended_at = Some(last_activity_at);
+    session.duration_seconds = (last_activity_at - started_at).num_seconds().max(0) as f64;
+    session.message_count = messages.map_or(0, |m| m.len() as i64);
+    session.current_task = text(conversation, "name");
+    session.metadata.insert("imported_from".to_string(), json!(ImportSource::ClaudeDesktop.key()));
+    Some(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_import_python_daemon_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        let options = SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        // The tables of the Python schema.sql that are imported
+        for statement in [
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY)",
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, agent_type TEXT NOT NULL, external_id TEXT NOT NULL,
+                project_path TEXT NOT NULL, status TEXT NOT NULL, started_at TIMESTAMP NOT NULL,
+                last_activity_at TIMESTAMP NOT NULL, ended_at TIMESTAMP, duration_seconds REAL DEFAULT 0,
+                message_count INTEGER DEFAULT 0, tokens_input INTEGER DEFAULT 0, estimated_cost REAL DEFAULT 0,
+                model_id TEXT, metadata_json TEXT DEFAULT '{}')",
+            "CREATE TABLE session_events (id TEXT PRIMARY KEY, session_id TEXT NOT NULL, event_type TEXT NOT NULL,
+                timestamp TIMESTAMP NOT NULL, agent_type TEXT NOT NULL, content TEXT, tool_name TEXT,
+                tool_input_json TEXT, tool_output_json TEXT, tool_duration_ms INTEGER, raw_data_json TEXT)",
+            "INSERT INTO schema_version VALUES (1)",
+            "INSERT INTO sessions VALUES ('s1', 'claude_code', 'abc', '/work/api', 'active',
+                '2026-03-01T09:00:00.250000', '2026-03-01T10:30:00', NULL, 5400, 12, 3000, 0.42,
+                'claude-sonnet-4', '{\"source\": \"hooks\"}')",
+            "INSERT INTO session_events VALUES ('e1', 's1', 'tool_start', '2026-03-01T09:05:00', 'claude_code',
+                'Running tests', 'Bash', '{\"command\": \"pytest\"}', '{\"stdout\": \"ok\"}', 1200, NULL)",
+            "INSERT INTO session_events VALUES ('e2', 's1', 'token_usage', '2026-03-01T09:06:00', 'claude_code',
+                NULL, NULL, NULL, NULL, NULL, '{\"tokens\": 10}')",
+            "INSERT INTO session_events VALUES ('e3', 'gone', 'error', '2026-03-01T09:07:00', 'claude_code',
+                'boom', NULL, NULL, NULL, NULL, NULL)",
+        ] {
+            sqlx::query(statement).execute(&mut conn).await.unwrap();
+        }
+        conn.close().await.unwrap();
+
+        let storage = Storage::in_memory();
+        let counts = import(&storage, ImportSource::PythonDaemon, dir.path(), None).await.unwrap();
+        assert_eq!(counts, ImportCounts { sessions: 1, events: 2, skipped: 1 });
+        // Importing again changes nothing
+        import(&storage, ImportSource::PythonDaemon, &path, None).await.unwrap();
+
+        let session = storage.get_session("s1").await.unwrap().unwrap();
+        let local = |text: &str| python_time(&json!(text)).unwrap();
+        assert_eq!(session.started_at, local("2026-03-01 09:00:00.25"));
+        assert_eq!(session.status, SessionStatus::Completed);
+        assert_eq!(session.ended_at, Some(local("2026-03-01T10:30:00")));
+        assert_eq!((session.message_count, session.tokens_input), (12, 3000));
+        assert_eq!(session.metadata["source"], "hooks");
+        assert_eq!(session.metadata["imported_from"], "python_daemon");
+
+        let events = storage.get_session_events("s1", 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        let custom = &events[0];
+        assert_eq!(custom.event_type, EventType::Custom);
+        assert_eq!(custom.raw_data.as_ref().unwrap()["python_event_type"], "token_usage");
+        let tool = &events[1];
+        assert_eq!(tool.event_type, EventType::ToolStart);
+        assert_eq!(tool.tool_input, Some(json!({ "command": "pytest" })));
+        assert_eq!(tool.tool_output.as_deref(), Some("{\"stdout\":\"ok\"}"));
+        assert_eq!(tool.duration_ms, Some(1200));
+    }
+
+    #[tokio::test]
+    async fn test_import_python_daemon_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let status = json!({
+            "active_sessions": 1,
+            "sessions": [
+                { "id": "s2", "agent_type": "aider", "external_id": "x", "project_path": "/work/web",
+                  "status": "crashed", "started_at": "2026-03-02T08:00:00+00:00",
+                  "last_activity_at": "2026-03-02T08:10:00+00:00", "message_count": 4, "metadata": {} },
+                { "agent_type": "aider" },
+            ],
+        });
+        std::fs::write(&path, status.to_string()).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+
+        let storage = Storage::in_memory();
+        let counts = import(&storage, ImportSource::PythonDaemon, &path, None).await.unwrap();
+        assert_eq!(counts, ImportCounts { sessions: 1, events: 0, skipped: 1 });
+        let session = storage.get_session("s2").await.unwrap().unwrap();
+        assert_eq!(session.agent_type, AgentType::Aider);
+        assert_eq!(session.status, SessionStatus::Crashed);
+        assert_eq!(session.duration_seconds, 600.0);
+
+        assert!(import(&storage, ImportSource::PythonDaemon, &dir.path().join("notes.txt"), None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_ccusage_and_claude_desktop() {
+        let dir = tempfile::tempdir().unwrap();
+        let daily = dir.path().join("daily.json");
+        let report = json!({
+            "daily": [
+                { "date": "2025-05-30", "inputTokens": 1200, "outputTokens": 800, "cacheCreationTokens": 50,
+                  "cacheReadTokens": 9000, "totalCost": 1.25, "modelsUsed": ["claude-sonnet-4-20250514"] },
+                { "date": "2025-06-02", "inputTokens": 10, "outputTokens": 10, "totalCost": 0.5 },
+            ],
+            "totals": { "totalCost": 1.75 },
+        });
+        std::fs::write(&daily, report.to_string()).unwrap();
+
+        let storage = Storage::in_memory();
+        let before = start_of_day(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap());
+        let counts = import(&storage, ImportSource::Ccusage, &daily, before).await.unwrap();
+        assert_eq!(counts, ImportCounts { sessions: 1, events: 0, skipped: 1 });
+        let day = storage.get_session("ccusage-daily-2025-05-30").await.unwrap().unwrap();
+        assert_eq!((day.tokens_input, day.tokens_output, day.estimated_cost), (1200, 800, 1.25));
+        assert_eq!(day.model_id.as_deref(), Some("claude-sonnet-4-20250514"));
+        assert_eq!(day.metadata["cache_read_tokens"], 9000);
+        assert_eq!(day.started_at, start_of_day(NaiveDate::from_ymd_opt(2025, 5, 30).unwrap()).unwrap());
+
+        let blocks = dir.path().join("blocks.json");
+        let report = json!({ "blocks": [
+            { "id": "2025-05-30T10:00:00.000Z", "startTime": "2025-05-30T10:00:00.000Z",
+              "endTime": "2025-05-30T15:00:00.000Z", "actualEndTime": "2025-05-30T11:20:00.000Z",
+              "isGap": false, "entries": 42, "costUSD": 3.5, "models": ["claude-opus-4"],
+              "tokenCounts": { "inputTokens": 100, "outputTokens": 200, "cacheReadInputTokens": 7 } },
+            { "id": "gap", "startTime": "2025-05-30T15:00:00.000Z", "isGap": true },
+        ]});
+        std::fs::write(&blocks, report.to_string()).unwrap();
+        let counts = import(&storage, ImportSource::Ccusage, &blocks, None).await.unwrap();
+        assert_eq!(counts.sessions, 1);
+        let block = storage.get_session("ccusage-blocks-2025-05-30T10:00:00.000Z").await.unwrap().unwrap();
+        assert_eq!((block.message_count, block.tokens_output, block.estimated_cost), (42, 200, 3.5));
+        assert_eq!((block.ended_at.unwrap() - block.started_at).num_minutes(), 80);
+        assert_eq!(block.metadata["cache_read_tokens"], 7);
+
+        let conversations = dir.path().join("conversations.json");
+        let export = json!([{
+            "uuid": "c1", "name": "Refactor the parser",
+            "created_at": "2025-04-01T09:00:00.000000Z", "updated_at": "2025-04-03T09:00:00.000000Z",
+            "chat_messages": [
+                { "sender": "human", "text": "hi", "created_at": "2025-04-01T09:00:00.000000Z" },
+                { "sender": "assistant", "text": "hello", "created_at": "2025-04-01T09:02:00.000000Z" },
+            ],
+        }]);
+        std::fs::write(&conversations, export.to_string()).unwrap();
+        import(&storage, ImportSource::ClaudeDesktop, &conversations, None).await.unwrap();
+        let chat = storage.get_session("claude-desktop-c1").await.unwrap().unwrap();
+        assert_eq!((chat.message_count, chat.duration_seconds), (2, 120.0));
+        assert_eq!(chat.current_task.as_deref(), Some("Refactor the parser"));
+
+        assert!(import(&storage, ImportSource::Ccusage, &conversations, None).await.is_err());
+    }
+}
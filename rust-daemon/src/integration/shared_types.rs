@@ -213,6 +213,42 @@ impl From<&SessionEvent> for UnifiedAgentEvent {
                 message: event.error_message.clone().unwrap_or_default(),
                 timestamp,
             },
+            EventType::ApiError => UnifiedAgentEvent::Error {
+                session_id,
+                error_type: "api_error".to_string(),
+                message: event.error_message.clone().unwrap_or_default(),
+                timestamp,
+            },
+            EventType::PolicyDecision => UnifiedAgentEvent::Custom {
+                session_id,
+                event_type: "policy_decision".to_string(),
+                data: event.raw_data.clone().unwrap_or(serde_json::json!({})),
+                timestamp,
+            },
+            EventType::Compaction => UnifiedAgentEvent::Custom {
+                session_id,
+                event_type: "compaction".to_string(),
+                data: event.raw_data.clone().unwrap_or(serde_json::json!({})),
+                timestamp,
+            },
+            EventType::ConfigChanged => UnifiedAgentEvent::Custom {
+                session_id,
+                event_type: "config_changed".to_string(),
+                data: event.raw_data.clone().unwrap_or(serde_json::json!({})),
+                timestamp,
+            },
+            EventType::Anomaly => UnifiedAgentEvent::Custom {
+                session_id,
+                event_type: "anomaly".to_string(),
+                data: event.raw_data.clone().unwrap_or(serde_json::json!({})),
+                timestamp,
+            },
+            EventType::Loop => UnifiedAgentEvent::Custom {
+                session_id,
+                event_type: "loop".to_string(),
+                data: event.raw_data.clone().unwrap_or(serde_json::json!({})),
+                timestamp,
+            },
             EventType::Custom => UnifiedAgentEvent::Custom {
                 session_id,
                 event_type: "custom".to_string(),
@@ -270,6 +306,9 @@ pub struct BridgeConfig {
 
     /// Event buffer size
     pub event_buffer_size: usize,
+
+    /// Users besides the daemon's own allowed to use the socket
+    pub allowed_uids: Vec<u32>,
 }
 
 impl Default for BridgeConfig {
@@ -280,6 +319,7 @@ impl Default for BridgeConfig {
             auto_connect: true,
             reconnect_interval: 5,
             event_buffer_size: 1000,
+            allowed_uids: Vec::new(),
         }
     }
 }
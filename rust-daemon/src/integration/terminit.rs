@@ -7,12 +7,13 @@ use anyhow::Result;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
+use tokio::net::UnixStream;
 use tokio::sync::{broadcast, mpsc, RwLock};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::events::EventBus;
+use crate::events::{EventBus, EventFilter};
 use crate::models::Session;
+use crate::sockets::{self, PeerPolicy};
 use crate::storage::Storage;
 
 use super::shared_types::{BridgeConfig, BridgeMessage, UnifiedAgentEvent, UnifiedSessionState};
@@ -60,12 +61,8 @@ impl TerminitBridge {
 
     /// Start the Unix socket server.
     async fn start_socket_server(&self, socket_path: PathBuf) -> Result<()> {
-        // Remove existing socket
-        if socket_path.exists() {
-            std::fs::remove_file(&socket_path)?;
-        }
-
-        let listener = UnixListener::bind(&socket_path)?;
+        let listener = sockets::bind(&socket_path)?;
+        let peers = PeerPolicy::new(&socket_path, &self.config.allowed_uids)?;
         info!("Terminit bridge listening at {:?}", socket_path);
 
         let storage = self.storage.clone();
@@ -82,14 +79,19 @@ impl TerminitBridge {
 
                 match listener.accept().await {
                     Ok((stream, _)) => {
+                        if let Err(e) = peers.check(&stream) {
+                            warn!("Rejected terminit connection: {}", e);
+                            continue;
+                        }
                         info!("Terminit client connected");
 
                         let storage = storage.clone();
+                        let event_bus = event_bus.clone();
                         let outgoing_rx = outgoing_tx.subscribe();
                         let clients = connected_clients.clone();
 
                         tokio::spawn(async move {
-                            if let Err(e) = handle_terminit_client(stream, storage, outgoing_rx, clients).await
+                            if let Err(e) = handle_terminit_client(stream, storage, event_bus, outgoing_rx, clients).await
                             {
                                 error!("Terminit client error: {}", e);
                             }
@@ -130,16 +132,20 @@ impl TerminitBridge {
     }
 }
 
-/// Handle a connected terminit client.
+/// Handle a connected terminit client. It gets every recorded event until
+/// it subscribes to a single session, and every event again once it
+/// unsubscribes.
 async fn handle_terminit_client(
     stream: UnixStream,
     storage: Storage,
+    event_bus: EventBus,
     mut outgoing_rx: broadcast::Receiver<BridgeMessage>,
     _clients: Arc<RwLock<Vec<mpsc::Sender<BridgeMessage>>>>,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
+    let mut events = event_bus.subscribe_filtered("Terminit client", EventFilter::all());
 
     // Send initial session list
     if let Ok(sessions) = storage.get_active_sessions(100).await {
@@ -157,6 +163,19 @@ async fn handle_terminit_client(
                     Ok(0) => break, // Connection closed
                     Ok(_) => {
                         if let Ok(message) = serde_json::from_str::<BridgeMessage>(&line) {
+                            match &message {
+                                BridgeMessage::Subscribe { session_id } => {
+                                    let filter = match session_id {
+                                        Some(id) => EventFilter::all().session(id.clone()),
+                                        None => EventFilter::all(),
+                                    };
+                                    events = event_bus.subscribe_filtered("Terminit client", filter);
+                                }
+                                BridgeMessage::Unsubscribe { .. } => {
+                                    events = event_bus.subscribe_filtered("Terminit client", EventFilter::all());
+                                }
+                                _ => {}
+                            }
                             let response = handle_message(message, &storage).await;
                             if let Some(resp) = response {
                                 let json = serde_json::to_string(&resp)? + "\n";
@@ -172,6 +191,16 @@ async fn handle_terminit_client(
                 }
             }
 
+            // Forward recorded events to terminit
+            received = events.recv() => {
+                let Some(event) = received else { break };
+                let message = BridgeMessage::EventNotification { event: UnifiedAgentEvent::from(&event) };
+                let json = serde_json::to_string(&message)? + "\n";
+                if writer.write_all(json.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+
             // Forward outgoing messages to terminit
             result = outgoing_rx.recv() => {
                 match result {
@@ -211,7 +240,7 @@ async fn handle_message(message: BridgeMessage, storage: &Storage) -> Option<Bri
 
         BridgeMessage::Subscribe { session_id } => {
             debug!("Client subscribed to session: {:?}", session_id);
-            None // Subscription is handled implicitly via broadcast
+            None // The client's event subscription was narrowed already
         }
 
         BridgeMessage::Unsubscribe { session_id } => {
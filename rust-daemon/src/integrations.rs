@@ -7,25 +7,48 @@ use axum::{
     extract::{Path, Query, State},
     http::{header, StatusCode},
     response::{sse::Event, IntoResponse, Response, Sse},
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use chrono::{DateTime, Utc};
-use futures_util::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{broadcast, RwLock};
-use tokio_stream::wrappers::BroadcastStream;
+use tokio::sync::RwLock;
 use tokio_stream::StreamExt as _;
 use tracing::{error, warn};
 
-use crate::models::{Session, SessionEvent};
+use crate::adapters::AdapterHealth;
+use crate::apierrors;
+use crate::calendar;
+use crate::capabilities::{self, ApiKeys};
+use crate::clear::{self, ClearFilter};
+use crate::commands::RunningCommand;
+use crate::config::Config;
+use crate::duplicates::{self, DuplicatesConfig};
+use crate::events::{EventBus, EventFilter, Received, Subscription};
+use crate::export::ExportFormat;
+use crate::compare;
+use crate::mcp;
+use crate::projects::{self, ProjectStats};
+use crate::forecast::{self, ForecastConfig};
+use crate::models::{normalize_tag, EventType, Session, SessionEvent, SessionGroup, SessionSource, SessionTag};
+use crate::preview;
+use crate::report;
+use crate::rules::{AutomationRule, RuleExecution, RuleInfo};
+use crate::search::SemanticIndex;
+use crate::slash;
 use crate::storage::Storage;
-use crate::analytics::RateLimiterState;
+use crate::throughput;
+use crate::timeseries;
+use crate::transcripts;
+use crate::trash::Trash;
+use crate::turns;
+use crate::uptime;
+use crate::analytics::{MemoryStore, RateLimiterState};
 
 // =============================================================================
 // API Types and Responses
@@ -98,6 +121,18 @@ pub struct SessionSummary {
     pub started_at: DateTime<Utc>,
     pub last_activity_at: DateTime<Utc>,
     pub duration_seconds: f64,
+    pub summary: Option<String>,
+    /// Share of the model's context window in use (0–1), if reported
+    pub context_utilization: Option<f64>,
+    /// Context is close enough to the limit that compaction is near
+    pub context_warning: bool,
+    /// Times the conversation has been compacted
+    pub compactions: i64,
+    /// Responses cut off before they finished
+    pub interruptions: i64,
+    pub user: Option<String>,
+    /// Filled in by handlers that look tags up
+    pub tags: Vec<String>,
 }
 
 impl From<&Session> for SessionSummary {
@@ -113,6 +148,13 @@ impl From<&Session> for SessionSummary {
             started_at: s.started_at,
             last_activity_at: s.last_activity_at,
             duration_seconds: s.duration_seconds,
+            summary: s.summary.clone(),
+            context_utilization: s.context_utilization(),
+            context_warning: s.context_near_limit(),
+            compactions: s.compactions,
+            interruptions: s.interruptions,
+            user: s.user.clone(),
+            tags: Vec::new(),
         }
     }
 }
@@ -127,29 +169,26 @@ pub struct EventSummary {
     pub preview: String,
     pub has_content: bool,
     pub tool_name: Option<String>,
+    /// The call's command, file or other telling input, for tool calls
+    pub tool_argument: Option<String>,
+    /// First line of the agent's thinking, kept out of `preview`
+    pub thinking: Option<String>,
 }
 
 impl From<&SessionEvent> for EventSummary {
     fn from(e: &SessionEvent) -> Self {
-        let preview = e.content.as_ref()
-            .map(|c| {
-                let first_line = c.lines().next().unwrap_or("");
-                if first_line.len() > 100 {
-                    format!("{}...", &first_line[..100])
-                } else {
-                    first_line.to_string()
-                }
-            })
-            .unwrap_or_default();
+        let preview = preview::build(e);
 
         Self {
             id: e.id.clone(),
             session_id: e.session_id.clone(),
             event_type: format!("{:?}", e.event_type),
             timestamp: e.timestamp,
-            preview,
+            preview: preview.text,
             has_content: e.content.is_some(),
-            tool_name: e.tool_name.clone(),
+            tool_name: preview.tool,
+            tool_argument: preview.tool_argument,
+            thinking: preview.thinking,
         }
     }
 }
@@ -223,6 +262,8 @@ pub struct SessionsQueryParams {
     pub agent_type: Option<String>,
     pub status: Option<String>,
     pub project: Option<String>,
+    pub tag: Option<String>,
+    pub user: Option<String>,
     #[serde(default)]
     pub active_only: bool,
 }
@@ -240,14 +281,19 @@ pub struct EventsQueryParams {
     pub event_type: Option<String>,
     pub since: Option<DateTime<Utc>>,
     pub until: Option<DateTime<Utc>>,
+    /// Only events of sessions with this tag
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ExportQueryParams {
-    pub format: Option<String>,  // json, csv, jsonl
+    pub format: Option<String>,  // json, csv, jsonl, parquet
+    /// Which table a Parquet export holds: events (default) or sessions
+    pub table: Option<String>,
     pub since: Option<DateTime<Utc>>,
     pub until: Option<DateTime<Utc>>,
     pub session_id: Option<String>,
+    pub tag: Option<String>,
 }
 
 // =============================================================================
@@ -386,13 +432,7 @@ impl StatusFileWriter {
 
     pub async fn write_status(&self) -> Result<()> {
         let metrics = self.storage.get_summary_metrics(24).await?;
-        let sessions = self.storage.get_active_sessions(100).await?;
-
-        // Count by agent type
-        let mut by_agent_type: HashMap<String, i64> = HashMap::new();
-        for session in &sessions {
-            *by_agent_type.entry(session.agent_type.to_string()).or_insert(0) += 1;
-        }
+        let by_agent_type = self.storage.count_sessions_grouped_by(SessionGroup::AgentType, None, true).await?;
 
         let status = StatusFile {
             daemon_status: "running".to_string(),
@@ -436,39 +476,67 @@ impl StatusFileWriter {
 #[derive(Clone)]
 pub struct IntegrationState {
     pub storage: Storage,
-    pub event_tx: broadcast::Sender<SessionEvent>,
+    /// Events as the daemon records them
+    pub events: EventBus,
     pub webhook_manager: Arc<WebhookManager>,
     pub started_at: DateTime<Utc>,
-    pub api_keys: Arc<RwLock<HashMap<String, ApiKeyInfo>>>,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct ApiKeyInfo {
-    pub name: String,
-    pub created_at: DateTime<Utc>,
-    pub last_used: Option<DateTime<Utc>>,
-    pub permissions: Vec<String>,
+    /// Keys machine clients read the API with
+    pub api_keys: ApiKeys,
+    /// Daemon IPC socket, for data only the daemon process holds
+    pub socket_path: PathBuf,
+    /// Semantic search, when an embeddings endpoint is configured
+    pub semantic: Option<SemanticIndex>,
+    pub duplicates: DuplicatesConfig,
+    pub forecast: ForecastConfig,
+    /// Claude Code's transcripts, served raw
+    pub projects_dir: PathBuf,
+    /// Provider incidents polled by the daemon
+    pub incidents_path: PathBuf,
+    /// Where cleared sessions go; None if its encryption key could not be loaded
+    pub trash: Option<Trash>,
+    /// The daemon's write token, for changes made through it; None when
+    /// write actions are turned off
+    pub write_token: Option<String>,
 }
 
 impl IntegrationState {
-    pub fn new(storage: Storage) -> Self {
-        let (event_tx, _) = broadcast::channel(1000);
+    pub fn new(storage: Storage, events: EventBus) -> Self {
+        let config = Config::load_or_default().unwrap_or_default();
+        let semantic = config
+            .embeddings
+            .enabled
+            .then(|| SemanticIndex::new(config.embeddings.clone(), storage.clone()));
+        let trash = Trash::open(&config).ok();
+        let write_token = config
+            .socket
+            .write_actions
+            .then(|| capabilities::load_or_create_token(&config.data_dir).ok())
+            .flatten();
 
         Self {
             storage,
-            event_tx,
+            events,
             webhook_manager: Arc::new(WebhookManager::new()),
             started_at: Utc::now(),
-            api_keys: Arc::new(RwLock::new(HashMap::new())),
+            api_keys: capabilities::api_keys(&config.api_keys),
+            socket_path: config.socket_path,
+            semantic,
+            duplicates: config.duplicates,
+            forecast: config.forecast,
+            projects_dir: config.claude_home.join("projects"),
+            incidents_path: apierrors::incidents_path(&config.data_dir),
+            trash,
+            write_token,
         }
     }
 
-    pub async fn add_api_key(&self, key: String, info: ApiKeyInfo) {
-        self.api_keys.write().await.insert(key, info);
-    }
-
-    pub async fn validate_api_key(&self, key: &str) -> bool {
-        self.api_keys.read().await.contains_key(key)
+    /// Send a write action to the daemon.
+    async fn ipc_write(&self, request: &serde_json::Value) -> Result<serde_json::Value> {
+        let token = self
+            .write_token
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Write actions are turned off (socket.write_actions)"))?;
+        crate::api::ipc_write_request(&self.socket_path, token, request).await
     }
 
     pub async fn uptime_seconds(&self) -> u64 {
@@ -497,6 +565,7 @@ pub async fn health_handler(State(state): State<IntegrationState>) -> Json<ApiRe
 
 /// System info endpoint
 pub async fn info_handler(State(state): State<IntegrationState>) -> Json<ApiResponse<SystemInfo>> {
+    let config = Config::load_or_default().unwrap_or_default();
     Json(ApiResponse::success(SystemInfo {
         version: env!("CARGO_PKG_VERSION").to_string(),
         rust_version: env!("CARGO_PKG_RUST_VERSION").to_string(),
@@ -505,11 +574,9 @@ pub async fn info_handler(State(state): State<IntegrationState>) -> Json<ApiResp
         pid: std::process::id(),
         started_at: state.started_at,
         config: ConfigInfo {
-            data_dir: dirs::data_dir()
-                .map(|p| p.join("agent-monitor").to_string_lossy().to_string())
-                .unwrap_or_default(),
-            socket_path: "/tmp/agent-monitor.sock".to_string(),
-            http_port: 8765,
+            data_dir: config.data_dir.to_string_lossy().to_string(),
+            socket_path: config.socket_path.to_string_lossy().to_string(),
+            http_port: config.http_port,
             rate_limit_enabled: false,
         },
     }))
@@ -526,9 +593,13 @@ pub async fn list_sessions_handler(
         state.storage.get_recent_sessions(168, 1000).await
     };
 
+    // Tags are looked up once; a database from before tags has none
+    let tags = state.storage.tags_by_session().await.unwrap_or_default();
+
     match all_sessions {
         Ok(sessions) => {
             // Apply filters
+            let tagged = params.tag.as_ref().map(|t| normalize_tag(t).unwrap_or_else(|| t.clone()));
             let filtered: Vec<_> = sessions.iter()
                 .filter(|s| {
                     params.agent_type.as_ref().map(|t| s.agent_type.to_string() == *t).unwrap_or(true)
@@ -539,6 +610,12 @@ pub async fn list_sessions_handler(
                 .filter(|s| {
                     params.project.as_ref().map(|p| s.project_path.contains(p)).unwrap_or(true)
                 })
+                .filter(|s| {
+                    tagged.as_ref().map(|t| tags.get(&s.id).is_some_and(|ts| ts.contains(t))).unwrap_or(true)
+                })
+                .filter(|s| {
+                    params.user.as_ref().map(|u| s.user.as_ref() == Some(u)).unwrap_or(true)
+                })
                 .collect();
 
             let total = filtered.len();
@@ -548,7 +625,10 @@ pub async fn list_sessions_handler(
                 .into_iter()
                 .skip(start)
                 .take(params.per_page)
-                .map(|s| s.into())
+                .map(|s| SessionSummary {
+                    tags: tags.get(&s.id).cloned().unwrap_or_default(),
+                    ..s.into()
+                })
                 .collect();
 
             Json(ApiResponse::success(PaginatedResponse {
@@ -587,6 +667,257 @@ pub async fn get_session_handler(
     }
 }
 
+/// Health of the daemon's adapters (requires a running daemon)
+pub async fn adapters_handler(State(state): State<IntegrationState>) -> impl IntoResponse {
+    let request = serde_json::json!({ "action": "get_adapters" });
+    let adapters = state
+        .ipc_write(&request)
+        .await
+        .and_then(|response| {
+            let adapters = response.get("adapters").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<Vec<AdapterHealth>>(adapters)?)
+        });
+
+    match adapters {
+        Ok(adapters) => Json(ApiResponse::success(adapters)).into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error(&format!("Daemon unavailable: {}", e))),
+        ).into_response(),
+    }
+}
+
+/// Start, stop, or restart one adapter in the daemon
+pub async fn adapter_control_handler(
+    State(state): State<IntegrationState>,
+    Path((name, command)): Path<(String, String)>,
+) -> impl IntoResponse {
+    if !matches!(command.as_str(), "start" | "stop" | "restart") {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Command must be start, stop, or restart")),
+        ).into_response();
+    }
+
+    let request = serde_json::json!({
+        "action": "adapter_control",
+        "name": name,
+        "command": command,
+    });
+    let adapters = crate::api::ipc_request(&state.socket_path, &request)
+        .await
+        .and_then(|response| {
+            let adapters = response.get("adapters").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<Vec<AdapterHealth>>(adapters)?)
+        });
+
+    match adapters {
+        Ok(adapters) => Json(ApiResponse::success(adapters)).into_response(),
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for the rule execution log
+#[derive(Debug, Deserialize)]
+pub struct RulesLogQuery {
+    #[serde(default = "default_rules_log_limit")]
+    pub limit: usize,
+}
+
+fn default_rules_log_limit() -> usize {
+    50
+}
+
+/// List automation rules in the daemon
+pub async fn list_rules_handler(State(state): State<IntegrationState>) -> impl IntoResponse {
+    let request = serde_json::json!({ "action": "get_rules", "log_limit": 0 });
+    let rules = crate::api::ipc_request(&state.socket_path, &request)
+        .await
+        .and_then(|response| {
+            let rules = response.get("rules").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<Vec<RuleInfo>>(rules)?)
+        });
+
+    match rules {
+        Ok(rules) => Json(ApiResponse::success(rules)).into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error(&format!("Daemon unavailable: {}", e))),
+        ).into_response(),
+    }
+}
+
+/// Recent rule executions, newest first
+pub async fn rules_log_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<RulesLogQuery>,
+) -> impl IntoResponse {
+    let request = serde_json::json!({ "action": "get_rules", "log_limit": params.limit });
+    let log = crate::api::ipc_request(&state.socket_path, &request)
+        .await
+        .and_then(|response| {
+            let log = response.get("log").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<Vec<RuleExecution>>(log)?)
+        });
+
+    match log {
+        Ok(log) => Json(ApiResponse::success(log)).into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error(&format!("Daemon unavailable: {}", e))),
+        ).into_response(),
+    }
+}
+
+/// Create an automation rule
+pub async fn create_rule_handler(
+    State(state): State<IntegrationState>,
+    Json(rule): Json<AutomationRule>,
+) -> impl IntoResponse {
+    upsert_rule(&state, rule).await
+}
+
+/// Replace an automation rule
+pub async fn update_rule_handler(
+    State(state): State<IntegrationState>,
+    Path(rule_id): Path<String>,
+    Json(mut rule): Json<AutomationRule>,
+) -> impl IntoResponse {
+    rule.id = rule_id;
+    upsert_rule(&state, rule).await
+}
+
+async fn upsert_rule(state: &IntegrationState, rule: AutomationRule) -> Response {
+    let request = serde_json::json!({ "action": "rule_upsert", "rule": rule });
+    let saved = state
+        .ipc_write(&request)
+        .await
+        .and_then(|response| {
+            let rule = response.get("rule").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<AutomationRule>(rule)?)
+        });
+
+    match saved {
+        Ok(rule) => Json(ApiResponse::success(rule)).into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Delete an automation rule
+pub async fn delete_rule_handler(
+    State(state): State<IntegrationState>,
+    Path(rule_id): Path<String>,
+) -> impl IntoResponse {
+    let request = serde_json::json!({ "action": "rule_delete", "id": rule_id });
+    let deleted = state
+        .ipc_write(&request)
+        .await
+        .map(|response| response.get("deleted").and_then(|v| v.as_bool()).unwrap_or(false));
+
+    match deleted {
+        Ok(true) => Json(ApiResponse::success(serde_json::json!({"deleted": true}))).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Rule not found")),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for listing memory entries
+#[derive(Debug, Deserialize)]
+pub struct MemoryQueryParams {
+    pub tag: Option<String>,
+}
+
+/// Body for writing a memory entry
+#[derive(Debug, Deserialize)]
+pub struct MemoryWriteRequest {
+    pub value: serde_json::Value,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// List memory entries, optionally filtered by tag
+pub async fn list_memory_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<MemoryQueryParams>,
+) -> impl IntoResponse {
+    match MemoryStore::new(state.storage.clone()).list(params.tag.as_deref()).await {
+        Ok(entries) => Json(ApiResponse::success(entries)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Get one memory entry
+pub async fn get_memory_handler(
+    State(state): State<IntegrationState>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    match MemoryStore::new(state.storage.clone()).read(&key).await {
+        Ok(Some(entry)) => Json(ApiResponse::success(entry)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Memory entry not found")),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Create or update a memory entry
+pub async fn put_memory_handler(
+    State(state): State<IntegrationState>,
+    Path(key): Path<String>,
+    Json(request): Json<MemoryWriteRequest>,
+) -> impl IntoResponse {
+    let memory = MemoryStore::new(state.storage.clone());
+    match memory
+        .write(&key, request.value, request.session_id.as_deref(), request.tags)
+        .await
+    {
+        Ok(entry) => Json(ApiResponse::success(entry)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Delete a memory entry
+pub async fn delete_memory_handler(
+    State(state): State<IntegrationState>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    match MemoryStore::new(state.storage.clone()).delete(&key).await {
+        Ok(true) => Json(ApiResponse::success(serde_json::json!({"deleted": true}))).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Memory entry not found")),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
 /// Get events for a session
 pub async fn get_session_events_handler(
     State(state): State<IntegrationState>,
@@ -654,6 +985,11 @@ pub async fn list_events_handler(
 ) -> Json<ApiResponse<PaginatedResponse<EventSummary>>> {
     let limit = params.per_page * 10; // Get more for filtering
 
+    let tagged = match params.tag {
+        Some(ref tag) => Some(state.storage.sessions_tagged(tag).await.unwrap_or_default()),
+        None => None,
+    };
+
     match state.storage.get_recent_events(limit).await {
         Ok(events) => {
             // Apply filters
@@ -672,6 +1008,9 @@ pub async fn list_events_handler(
                 .filter(|e| {
                     params.until.map(|u| e.timestamp <= u).unwrap_or(true)
                 })
+                .filter(|e| {
+                    tagged.as_ref().map(|ids| ids.contains(&e.session_id)).unwrap_or(true)
+                })
                 .collect();
 
             let total = filtered.len();
@@ -702,356 +1041,2281 @@ pub async fn list_events_handler(
     }
 }
 
-/// Export data in various formats
-pub async fn export_handler(
+/// Query parameters for duplicate prompts
+#[derive(Debug, Deserialize)]
+pub struct DuplicatePromptParams {
+    pub session_id: Option<String>,
+    pub hours: Option<i64>,
+}
+
+/// Prompts sent to more than one session
+pub async fn duplicate_prompts_handler(
     State(state): State<IntegrationState>,
-    Query(params): Query<ExportQueryParams>,
+    Query(params): Query<DuplicatePromptParams>,
 ) -> impl IntoResponse {
-    let format = params.format.as_deref().unwrap_or("json");
-
-    let sessions = state.storage.get_recent_sessions(168, 1000).await.unwrap_or_default();
-    let events = if let Some(ref sid) = params.session_id {
-        state.storage.get_session_events(sid, 10000).await.unwrap_or_default()
-    } else {
-        state.storage.get_recent_events(10000).await.unwrap_or_default()
-    };
+    let mut config = state.duplicates.clone();
+    if let Some(hours) = params.hours {
+        config.lookback_hours = hours;
+    }
 
-    match format {
-        "csv" => {
-            let mut csv = String::from("timestamp,session_id,event_type,content_preview\n");
-            for event in &events {
-                let preview = event.content.as_ref()
-                    .map(|c| c.lines().next().unwrap_or("").replace(",", ";").replace("\n", " "))
-                    .unwrap_or_default();
-                csv.push_str(&format!(
-                    "{},{},{:?},{}\n",
-                    event.timestamp.to_rfc3339(),
-                    event.session_id,
-                    event.event_type,
-                    preview.chars().take(100).collect::<String>()
-                ));
-            }
+    match duplicates::find_duplicates(&state.storage, &config, params.session_id.as_deref()).await {
+        Ok(groups) => Json(ApiResponse::success(groups)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
 
-            Response::builder()
-                .header(header::CONTENT_TYPE, "text/csv")
-                .header(header::CONTENT_DISPOSITION, "attachment; filename=\"events.csv\"")
-                .body(Body::from(csv))
-                .unwrap()
-                .into_response()
-        }
-        "jsonl" => {
-            let lines: Vec<String> = events.iter()
-                .map(|e| serde_json::to_string(e).unwrap_or_default())
-                .collect();
+/// Query parameters for the spend forecast
+#[derive(Debug, Deserialize)]
+pub struct ForecastParams {
+    pub window_days: Option<i64>,
+}
 
-            Response::builder()
-                .header(header::CONTENT_TYPE, "application/jsonl")
-                .header(header::CONTENT_DISPOSITION, "attachment; filename=\"events.jsonl\"")
-                .body(Body::from(lines.join("\n")))
-                .unwrap()
-                .into_response()
-        }
-        _ => {
-            // JSON (default)
-            let export = serde_json::json!({
-                "exported_at": Utc::now().to_rfc3339(),
-                "sessions": sessions,
-                "events": events,
-            });
+/// Burn rate and projected monthly spend, overall and per project
+pub async fn forecast_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<ForecastParams>,
+) -> impl IntoResponse {
+    let mut config = state.forecast.clone();
+    if let Some(days) = params.window_days {
+        config.window_days = days;
+    }
 
-            Response::builder()
-                .header(header::CONTENT_TYPE, "application/json")
-                .body(Body::from(serde_json::to_string_pretty(&export).unwrap_or_default()))
-                .unwrap()
-                .into_response()
-        }
+    match forecast::build_forecast(&state.storage, &config).await {
+        Ok(forecast) => Json(ApiResponse::success(forecast)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
     }
 }
 
-/// Server-Sent Events stream for real-time updates
-pub async fn sse_handler(
-    State(state): State<IntegrationState>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let rx = state.event_tx.subscribe();
-
-    let stream = BroadcastStream::new(rx)
-        .filter_map(|result| {
-            match result {
-                Ok(event) => {
-                    let data = serde_json::to_string(&EventSummary::from(&event)).ok()?;
-                    Some(Ok(Event::default()
-                        .event("event")
-                        .data(data)))
-                }
-                Err(_) => None,
-            }
-        });
+/// Query parameters for the agent comparison
+#[derive(Debug, Deserialize)]
+pub struct CompareParams {
+    pub project: Option<String>,
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
 
-    Sse::new(stream).keep_alive(
-        axum::response::sse::KeepAlive::new()
-            .interval(Duration::from_secs(30))
-            .text("keep-alive"),
-    )
+fn default_analytics_days() -> i64 {
+    30
 }
 
-/// Register a webhook
-pub async fn register_webhook_handler(
+/// Agents compared on cost, messages, errors and files changed
+pub async fn compare_handler(
     State(state): State<IntegrationState>,
-    Json(config): Json<WebhookConfig>,
+    Query(params): Query<CompareParams>,
 ) -> impl IntoResponse {
-    state.webhook_manager.register(config.clone()).await;
-    Json(ApiResponse::success(config))
+    match compare::build_comparison(&state.storage, params.project.as_deref(), params.days).await {
+        Ok(comparison) => Json(ApiResponse::success(comparison)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
 }
 
-/// List webhooks
-pub async fn list_webhooks_handler(
-    State(state): State<IntegrationState>,
-) -> Json<ApiResponse<Vec<WebhookConfig>>> {
-    let webhooks = state.webhook_manager.list().await;
-    Json(ApiResponse::success(webhooks))
+/// Query parameters for the usage report
+#[derive(Debug, Deserialize)]
+pub struct ReportParams {
+    #[serde(default = "default_report_days")]
+    pub days: i64,
+    pub tag: Option<String>,
+    pub user: Option<String>,
+    /// previous, yesterday, last-week, last-month or an age like 14d
+    pub compare: Option<String>,
 }
 
-/// Delete a webhook
-pub async fn delete_webhook_handler(
+fn default_report_days() -> i64 {
+    7
+}
+
+/// Usage report over the last `days`, optionally compared with an earlier window
+pub async fn report_handler(
     State(state): State<IntegrationState>,
-    Path(webhook_id): Path<String>,
+    Query(params): Query<ReportParams>,
 ) -> impl IntoResponse {
-    if state.webhook_manager.unregister(&webhook_id).await {
-        (StatusCode::OK, Json(ApiResponse::success(serde_json::json!({"deleted": true}))))
-    } else {
-        (StatusCode::NOT_FOUND, Json(ApiResponse::success(serde_json::json!({"error": "Webhook not found", "deleted": false}))))
+    let offset = match params.compare.as_deref().map(|c| report::parse_offset(c, params.days)).transpose() {
+        Ok(offset) => offset,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(&e.to_string()))).into_response(),
+    };
+    let (tag, user) = (params.tag.as_deref(), params.user.as_deref());
+    let result = async {
+        let mut report = report::build_report(&state.storage, params.days, tag, user).await?;
+        if let Some(offset) = offset {
+            report.comparison = Some(report::compare(&state.storage, &report, offset, tag, user).await?);
+        }
+        Ok::<_, anyhow::Error>(report)
+    }
+    .await;
+    match result {
+        Ok(report) => Json(ApiResponse::success(report)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
     }
 }
 
-/// Get current status (Ralph-compatible)
-pub async fn status_handler(
-    State(state): State<IntegrationState>,
-) -> Json<StatusFile> {
-    let metrics = state.storage.get_summary_metrics(24).await.ok();
-    let sessions = state.storage.get_active_sessions(100).await.unwrap_or_default();
+/// Query parameters for MCP server and slash command usage
+#[derive(Debug, Deserialize)]
+pub struct McpParams {
+    pub project: Option<String>,
+    pub session: Option<String>,
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
 
-    let mut by_agent_type: HashMap<String, i64> = HashMap::new();
-    for session in &sessions {
-        *by_agent_type.entry(session.agent_type.to_string()).or_insert(0) += 1;
+/// Calls, errors and latency per MCP server and tool
+pub async fn mcp_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<McpParams>,
+) -> impl IntoResponse {
+    match mcp::build_report(&state.storage, params.project.as_deref(), params.session.as_deref(), params.days).await {
+        Ok(report) => Json(ApiResponse::success(report)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
     }
+}
 
-    Json(StatusFile {
-        daemon_status: "running".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        timestamp: Utc::now(),
-        uptime_seconds: state.uptime_seconds().await,
-        sessions: SessionsStatus {
-            active_count: metrics.as_ref().map(|m| m.active_sessions).unwrap_or(0),
-            total_24h: metrics.as_ref().map(|m| m.total_sessions).unwrap_or(0),
-            by_agent_type,
-        },
-        analytics: AnalyticsStatus {
-            total_messages: metrics.as_ref().map(|m| m.total_messages).unwrap_or(0),
-            total_cost: metrics.as_ref().map(|m| m.total_cost).unwrap_or(0.0),
-            rate_limit: None,
-        },
-    })
+/// Output tokens per second of responses, p50 and p95 per model and day
+pub async fn throughput_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<McpParams>,
+) -> impl IntoResponse {
+    match throughput::build_report(&state.storage, params.project.as_deref(), params.session.as_deref(), params.days).await {
+        Ok(report) => Json(ApiResponse::success(report)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
 }
 
-// =============================================================================
-// Router Builder
-// =============================================================================
+/// Slash commands run, most used first
+pub async fn slash_commands_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<McpParams>,
+) -> impl IntoResponse {
+    match slash::build_report(&state.storage, params.project.as_deref(), params.session.as_deref(), params.days).await {
+        Ok(report) => Json(ApiResponse::success(report)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
 
-/// Create the full integration API router
-pub fn create_integration_router(state: IntegrationState) -> Router {
-    Router::new()
-        // Health and info
-        .route("/health", get(health_handler))
-        .route("/info", get(info_handler))
-        .route("/status", get(status_handler))
+/// Query parameters for spend per branch
+#[derive(Debug, Deserialize)]
+pub struct BranchesParams {
+    pub project: Option<String>,
+    pub user: Option<String>,
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
 
-        // Sessions
-        .route("/api/v1/sessions", get(list_sessions_handler))
-        .route("/api/v1/sessions/:id", get(get_session_handler))
-        .route("/api/v1/sessions/:id/events", get(get_session_events_handler))
+/// Most sessions rolled up per branch or user
+const MAX_ROLLUP_SESSIONS: usize = 10_000;
 
-        // Events
-        .route("/api/v1/events", get(list_events_handler))
-        .route("/api/v1/events/:id", get(get_event_handler))
+/// Cost and tokens per project branch, most expensive first
+pub async fn branches_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<BranchesParams>,
+) -> impl IntoResponse {
+    match state.storage.get_recent_sessions(params.days.max(1) * 24, MAX_ROLLUP_SESSIONS).await {
+        Ok(mut sessions) => {
+            if let Some(ref project) = params.project {
+                sessions.retain(|s| &s.project_path == project);
+            }
+            if let Some(ref user) = params.user {
+                sessions.retain(|s| s.user.as_ref() == Some(user));
+            }
+            Json(ApiResponse::success(report::branch_usage(&sessions))).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
 
-        // Export
-        .route("/api/v1/export", get(export_handler))
+/// Query parameters for per-user usage
+#[derive(Debug, Deserialize)]
+pub struct UsersParams {
+    pub project: Option<String>,
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
 
-        // Real-time
-        .route("/api/v1/stream", get(sse_handler))
+/// Cost and activity per user, most expensive first
+pub async fn users_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<UsersParams>,
+) -> impl IntoResponse {
+    match state.storage.get_recent_sessions(params.days.max(1) * 24, MAX_ROLLUP_SESSIONS).await {
+        Ok(mut sessions) => {
+            if let Some(ref project) = params.project {
+                sessions.retain(|s| &s.project_path == project);
+            }
+            Json(ApiResponse::success(report::user_usage(&sessions))).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
 
-        // Webhooks
-        .route("/api/v1/webhooks", get(list_webhooks_handler).post(register_webhook_handler))
-        .route("/api/v1/webhooks/:id", delete(delete_webhook_handler))
+/// Query parameters for hourly usage
+#[derive(Debug, Deserialize)]
+pub struct HourlyParams {
+    #[serde(default = "default_hourly_hours")]
+    pub hours: i64,
+}
 
-        .with_state(state)
+fn default_hourly_hours() -> i64 {
+    24
+}
+
+/// Cost, tokens and events per agent for each recent hour, oldest first
+pub async fn hourly_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<HourlyParams>,
+) -> impl IntoResponse {
+    match timeseries::hourly_usage(&state.storage, params.hours.clamp(1, 24 * 31)).await {
+        Ok(points) => Json(ApiResponse::success(points)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// API errors per recent hour, oldest first, with the provider incidents
+/// open during each
+pub async fn api_errors_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<HourlyParams>,
+) -> impl IntoResponse {
+    let incidents = apierrors::load_incidents(&state.incidents_path);
+    match apierrors::hourly_api_errors(&state.storage, params.hours.clamp(1, 24 * 31), &incidents).await {
+        Ok(points) => Json(ApiResponse::success(points)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for session counts
+#[derive(Debug, Deserialize)]
+pub struct SessionCountParams {
+    #[serde(default = "default_session_group")]
+    pub by: SessionGroup,
+    /// Only sessions active within this many hours; all of them if absent
+    pub hours: Option<i64>,
+    /// Only sessions still running
+    #[serde(default)]
+    pub active: bool,
+}
+
+fn default_session_group() -> SessionGroup {
+    SessionGroup::AgentType
+}
+
+/// Session counts per agent type, status or project, largest first
+pub async fn session_counts_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<SessionCountParams>,
+) -> impl IntoResponse {
+    match state.storage.count_sessions_grouped_by(params.by, params.hours, params.active).await {
+        Ok(counts) => {
+            let mut counts: Vec<(String, i64)> = counts.into_iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let counts: Vec<_> = counts
+                .into_iter()
+                .map(|(key, sessions)| serde_json::json!({ "key": key, "sessions": sessions }))
+                .collect();
+            Json(ApiResponse::success(counts)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for the project leaderboard
+#[derive(Debug, Deserialize)]
+pub struct ProjectsParams {
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
+
+/// Projects ranked by 7-day spend
+pub async fn list_projects_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<ProjectsParams>,
+) -> impl IntoResponse {
+    match projects::build_leaderboard(&state.storage, params.days).await {
+        Ok(projects) => Json(ApiResponse::success(projects)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for one project
+#[derive(Debug, Deserialize)]
+pub struct ProjectParams {
+    pub path: String,
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+    #[serde(default = "default_per_page")]
+    pub events: usize,
+}
+
+/// One project's rollup with its sessions and latest events
+#[derive(Debug, Serialize)]
+pub struct ProjectDetail {
+    pub project: ProjectStats,
+    pub sessions: Vec<SessionSummary>,
+    pub events: Vec<EventSummary>,
+}
+
+/// Sessions and events for a single project
+pub async fn get_project_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<ProjectParams>,
+) -> impl IntoResponse {
+    let detail = async {
+        let sessions = projects::project_sessions(&state.storage, &params.path, params.days).await?;
+        let events = projects::project_events(&state.storage, &sessions, params.events).await?;
+        let tags = state.storage.tags_by_session().await.unwrap_or_default();
+        Ok::<_, anyhow::Error>(projects::leaderboard(&sessions, Utc::now()).into_iter().next().map(|project| {
+            ProjectDetail {
+                project,
+                sessions: sessions
+                    .iter()
+                    .map(|s| SessionSummary {
+                        tags: tags.get(&s.id).cloned().unwrap_or_default(),
+                        ..s.into()
+                    })
+                    .collect(),
+                events: events.iter().map(|e| e.into()).collect(),
+            }
+        }))
+    };
+
+    match detail.await {
+        Ok(Some(detail)) => Json(ApiResponse::success(detail)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("No sessions for this project")),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for the tag list
+#[derive(Debug, Deserialize)]
+pub struct TagsParams {
+    #[serde(default = "default_analytics_days")]
+    pub days: i64,
+}
+
+/// Every tag with its session count and spend
+pub async fn list_tags_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<TagsParams>,
+) -> impl IntoResponse {
+    let usage = async {
+        let sessions = state.storage.get_recent_sessions(params.days.max(1) * 24, 100_000).await?;
+        let tags = state.storage.tags_by_session().await?;
+        Ok::<_, anyhow::Error>(report::tag_usage(&sessions, &tags))
+    };
+
+    match usage.await {
+        Ok(usage) => Json(ApiResponse::success(usage)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Network samples of one session
+pub async fn get_session_network_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+    Query(params): Query<ResourcesQuery>,
+) -> impl IntoResponse {
+    match state.storage.get_network_samples(&session_id, params.limit.min(10_000)).await {
+        Ok(samples) => Json(ApiResponse::success(samples)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// What each prompt of a session added to its context, oldest first
+pub async fn get_session_turns_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    match turns::session_turns(&state.storage, &session_id).await {
+        Ok(turns) => Json(ApiResponse::success(turns)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// The upstream transcript lines of a session, unmodified
+pub async fn get_session_raw_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let session = match state.storage.get_session(&session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Session not found")),
+        ).into_response(),
+        Err(e) => return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    };
+    let sources = match state.storage.get_session_sources(Some(&session.id)).await {
+        Ok(sources) => sources,
+        Err(e) => return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    };
+    let files = transcripts::files(&session, &sources, &state.projects_dir);
+    if files.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("No transcript found for this session")),
+        ).into_response();
+    }
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/jsonl")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.jsonl\"", session.external_id.replace('"', "")),
+        )
+        .body(Body::from_stream(transcripts::stream(files)))
+        .unwrap()
+        .into_response()
+}
+
+/// A file a session was read from, next to its current size.
+#[derive(Debug, Serialize)]
+pub struct SessionSourceStatus {
+    #[serde(flatten)]
+    pub source: SessionSource,
+    /// None when the file is gone
+    pub size_bytes: Option<u64>,
+    /// Bytes written after the last read, such as lines not yet processed
+    pub unread_bytes: Option<i64>,
+}
+
+/// The files a session was read from and how far, to tell why events are missing
+pub async fn get_session_sources_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    match state.storage.get_session_sources(Some(&session_id)).await {
+        Ok(sources) => {
+            let sources: Vec<SessionSourceStatus> = sources
+                .into_iter()
+                .map(|source| {
+                    let size_bytes = std::fs::metadata(&source.path).ok().map(|m| m.len());
+                    let unread_bytes = size_bytes.map(|size| size as i64 - source.offset);
+                    SessionSourceStatus { source, size_bytes, unread_bytes }
+                })
+                .collect();
+            Json(ApiResponse::success(sources)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Processes a session's agent is running right now (requires a running daemon)
+pub async fn get_session_commands_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let request = serde_json::json!({ "action": "get_commands", "session_id": session_id });
+    let commands = crate::api::ipc_request(&state.socket_path, &request)
+        .await
+        .and_then(|response| {
+            let commands = response.get("commands").cloned().unwrap_or_default();
+            Ok(serde_json::from_value::<Vec<RunningCommand>>(commands)?)
+        });
+
+    match commands {
+        Ok(commands) => Json(ApiResponse::success(commands)).into_response(),
+        Err(e) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error(&format!("Daemon unavailable: {}", e))),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for a session's resource and network samples
+#[derive(Debug, Deserialize)]
+pub struct ResourcesQuery {
+    #[serde(default = "default_resources_limit")]
+    pub limit: usize,
+}
+
+fn default_resources_limit() -> usize {
+    120
 }
 
-// =============================================================================
-// OpenAPI Documentation
-// =============================================================================
+/// CPU and memory samples of one session
+pub async fn get_session_resources_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+    Query(params): Query<ResourcesQuery>,
+) -> impl IntoResponse {
+    match state.storage.get_resource_samples(&session_id, params.limit.min(10_000)).await {
+        Ok(samples) => Json(ApiResponse::success(samples)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Tags of one session
+pub async fn get_session_tags_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    match state.storage.list_session_tags(Some(&session_id), None).await {
+        Ok(tags) => Json(ApiResponse::success(tags)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Request body for tagging a session
+#[derive(Debug, Deserialize)]
+pub struct AddTagRequest {
+    pub tag: String,
+}
+
+/// Tag a session by hand
+pub async fn add_session_tag_handler(
+    State(state): State<IntegrationState>,
+    Path(session_id): Path<String>,
+    Json(request): Json<AddTagRequest>,
+) -> impl IntoResponse {
+    let Some(tag) = normalize_tag(&request.tag) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Tags may only contain letters, digits and -_.:/ (up to 64 characters)")),
+        ).into_response();
+    };
+    match state.storage.get_session(&session_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::<()>::error("Session not found")),
+            ).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error(&e.to_string())),
+            ).into_response()
+        }
+    }
+
+    let session_tag = SessionTag::new(&session_id, &tag, "manual");
+    match state.storage.add_session_tag(&session_tag).await {
+        Ok(added) => Json(ApiResponse::success(serde_json::json!({ "tag": tag, "added": added }))).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Remove a tag from a session
+pub async fn remove_session_tag_handler(
+    State(state): State<IntegrationState>,
+    Path((session_id, tag)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let tag = normalize_tag(&tag).unwrap_or(tag);
+    match state.storage.remove_session_tag(&session_id, &tag).await {
+        Ok(true) => Json(ApiResponse::success(serde_json::json!({"deleted": true}))).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Session does not have this tag")),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for clearing sessions
+#[derive(Debug, Deserialize)]
+pub struct ClearParams {
+    pub agent_type: Option<String>,
+    pub project: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub session: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Delete the sessions matching every given criterion, or with `dry_run`
+/// list them
+pub async fn clear_sessions_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<ClearParams>,
+) -> impl IntoResponse {
+    let times = params.before.as_deref().map(clear::parse_time).transpose().and_then(|before| {
+        Ok((before, params.after.as_deref().map(clear::parse_time).transpose()?))
+    });
+    let (before, after) = match times {
+        Ok(times) => times,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(&e.to_string()))).into_response(),
+    };
+    let filter = ClearFilter { agent_type: params.agent_type, project: params.project, before, after, session_id: params.session };
+    if filter.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Give at least one of agent_type, project, before, after or session")),
+        ).into_response();
+    }
+    let Some(trash) = state.trash.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("The trash is unavailable: the encryption key could not be loaded")),
+        ).into_response();
+    };
+    match clear::clear(&state.storage, trash, &filter, params.dry_run).await {
+        Ok(sessions) => Json(ApiResponse::success(serde_json::json!({
+            "dry_run": params.dry_run,
+            "cleared": sessions.len(),
+            "sessions": sessions,
+        }))).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Query parameters for semantic search
+#[derive(Debug, Deserialize)]
+pub struct SemanticSearchParams {
+    pub q: String,
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+    pub project: Option<String>,
+}
+
+fn default_search_limit() -> usize {
+    10
+}
+
+/// A session matching a semantic query
+#[derive(Debug, Serialize)]
+pub struct SemanticSearchResult {
+    pub session: SessionSummary,
+    pub score: f32,
+    pub event_id: String,
+    pub snippet: String,
+    /// Restore with `agent-monitor archive restore`
+    pub archived: bool,
+}
+
+/// Find sessions by meaning rather than keywords
+pub async fn semantic_search_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<SemanticSearchParams>,
+) -> impl IntoResponse {
+    let Some(ref index) = state.semantic else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()>::error("Semantic search is disabled; set embeddings.enabled in the config")),
+        ).into_response();
+    };
+    if params.q.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Query parameter q is required")),
+        ).into_response();
+    }
+
+    match index.search(&params.q, params.limit.clamp(1, 100), params.project.as_deref()).await {
+        Ok(matches) => {
+            let results: Vec<SemanticSearchResult> = matches
+                .into_iter()
+                .map(|m| SemanticSearchResult {
+                    session: SessionSummary::from(&m.session),
+                    score: m.score,
+                    event_id: m.event_id,
+                    snippet: m.snippet,
+                    archived: m.archived,
+                })
+                .collect();
+            Json(ApiResponse::success(results)).into_response()
+        }
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(ApiResponse::<()>::error(&format!("Semantic search failed: {}", e))),
+        ).into_response(),
+    }
+}
+
+/// Export data in various formats
+pub async fn export_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<ExportQueryParams>,
+) -> impl IntoResponse {
+    let format = params.format.as_deref().unwrap_or("json");
+
+    let mut sessions = state.storage.get_recent_sessions(168, 1000).await.unwrap_or_default();
+    let mut events = if let Some(ref sid) = params.session_id {
+        state.storage.get_session_events(sid, 10000).await.unwrap_or_default()
+    } else {
+        state.storage.get_recent_events(10000).await.unwrap_or_default()
+    };
+    if let Some(ref tag) = params.tag {
+        let tagged = state.storage.sessions_tagged(tag).await.unwrap_or_default();
+        sessions.retain(|s| tagged.contains(&s.id));
+        events.retain(|e| tagged.contains(&e.session_id));
+    }
+
+    match format {
+        "csv" => {
+            let mut csv = String::from("timestamp,session_id,event_type,content_preview\n");
+            for event in &events {
+                let preview = event.content.as_ref()
+                    .map(|c| c.lines().next().unwrap_or("").replace(",", ";").replace("\n", " "))
+                    .unwrap_or_default();
+                csv.push_str(&format!(
+                    "{},{},{:?},{}\n",
+                    event.timestamp.to_rfc3339(),
+                    event.session_id,
+                    event.event_type,
+                    preview.chars().take(100).collect::<String>()
+                ));
+            }
+
+            Response::builder()
+                .header(header::CONTENT_TYPE, "text/csv")
+                .header(header::CONTENT_DISPOSITION, "attachment; filename=\"events.csv\"")
+                .body(Body::from(csv))
+                .unwrap()
+                .into_response()
+        }
+        "parquet" => {
+            let (name, data) = match params.table.as_deref() {
+                Some("sessions") => ("sessions", ExportFormat::Parquet.sessions(&sessions)),
+                _ => ("events", ExportFormat::Parquet.events(&events)),
+            };
+            match data {
+                Ok(data) => Response::builder()
+                    .header(header::CONTENT_TYPE, "application/vnd.apache.parquet")
+                    .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.parquet\"", name))
+                    .body(Body::from(data))
+                    .unwrap()
+                    .into_response(),
+                Err(e) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::<()>::error(&format!("Parquet export failed: {}", e))),
+                ).into_response(),
+            }
+        }
+        "jsonl" => {
+            let lines: Vec<String> = events.iter()
+                .map(|e| serde_json::to_string(e).unwrap_or_default())
+                .collect();
+
+            Response::builder()
+                .header(header::CONTENT_TYPE, "application/jsonl")
+                .header(header::CONTENT_DISPOSITION, "attachment; filename=\"events.jsonl\"")
+                .body(Body::from(lines.join("\n")))
+                .unwrap()
+                .into_response()
+        }
+        _ => {
+            // JSON (default)
+            let export = serde_json::json!({
+                "exported_at": Utc::now().to_rfc3339(),
+                "sessions": sessions,
+                "events": events,
+            });
+
+            Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_string_pretty(&export).unwrap_or_default()))
+                .unwrap()
+                .into_response()
+        }
+    }
+}
+
+/// A read-only SQLite copy of the database for analytical tools
+pub async fn snapshot_handler(State(state): State<IntegrationState>) -> impl IntoResponse {
+    let path = std::env::temp_dir().join(format!("agent-monitor-snapshot-{}.db", uuid::Uuid::new_v4()));
+    let result = match state.storage.write_snapshot(&path).await {
+        Ok(()) => tokio::fs::read(&path).await.map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+    let _ = tokio::fs::remove_file(&path).await;
+
+    match result {
+        Ok(data) => Response::builder()
+            .header(header::CONTENT_TYPE, "application/vnd.sqlite3")
+            .header(header::CONTENT_DISPOSITION, "attachment; filename=\"agent-monitor-snapshot.db\"")
+            .body(Body::from(data))
+            .unwrap()
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&format!("Snapshot failed: {}", e))),
+        ).into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarParams {
+    pub days: Option<i64>,
+    pub project: Option<String>,
+    pub tag: Option<String>,
+}
+
+/// Sessions as an iCalendar feed for calendars and time trackers
+pub async fn calendar_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<CalendarParams>,
+) -> impl IntoResponse {
+    let hours = params.days.unwrap_or(30).clamp(1, 366) * 24;
+    let mut sessions = match state.storage.get_recent_sessions(hours, 10000).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error(&format!("Failed to load sessions: {}", e))),
+            ).into_response()
+        }
+    };
+    if let Some(ref project) = params.project {
+        sessions.retain(|s| &s.project_path == project);
+    }
+    if let Some(ref tag) = params.tag {
+        let tagged = state.storage.sessions_tagged(tag).await.unwrap_or_default();
+        sessions.retain(|s| tagged.contains(&s.id));
+    }
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, "inline; filename=\"agent-sessions.ics\"")
+        .body(Body::from(calendar::to_ics(&sessions, Utc::now())))
+        .unwrap()
+        .into_response()
+}
+
+/// Query parameters narrowing the event stream
+#[derive(Debug, Deserialize)]
+pub struct StreamQueryParams {
+    pub session_id: Option<String>,
+    pub agent_type: Option<String>,
+    /// Comma-separated event types, like `tool_start,error`
+    pub event_type: Option<String>,
+}
+
+impl StreamQueryParams {
+    fn filter(self) -> Result<EventFilter, String> {
+        fn parse<T: serde::de::DeserializeOwned>(kind: &str, value: &str) -> Result<T, String> {
+            serde_json::from_value(serde_json::Value::String(value.trim().to_string()))
+                .map_err(|_| format!("Unknown {}: {}", kind, value.trim()))
+        }
+        let mut filter = EventFilter::all();
+        filter.session_id = self.session_id;
+        if let Some(agent) = self.agent_type.as_deref() {
+            filter = filter.agent(parse("agent type", agent)?);
+        }
+        if let Some(types) = self.event_type.as_deref() {
+            let types = types.split(',').map(|t| parse("event type", t)).collect::<Result<Vec<EventType>, _>>()?;
+            filter = filter.event_types(types);
+        }
+        Ok(filter)
+    }
+}
+
+/// Server-Sent Events stream of the events matching the query
+pub async fn sse_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<StreamQueryParams>,
+) -> Response {
+    let filter = match params.filter() {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(&e))).into_response(),
+    };
+
+    let stream = state
+        .events
+        .subscribe_filtered("SSE client", filter)
+        .into_stream()
+        .filter_map(|received| {
+            let event = match received {
+                Received::Event(event) => {
+                    Event::default().event("event").data(serde_json::to_string(&EventSummary::from(event.as_ref())).ok()?)
+                }
+                // The client missed some and should re-read what it shows
+                Received::Lagged(missed) => Event::default().event("lagged").data(missed.to_string()),
+            };
+            Some(Ok::<_, Infallible>(event))
+        });
+
+    Sse::new(stream)
+        .keep_alive(
+            axum::response::sse::KeepAlive::new()
+                .interval(Duration::from_secs(30))
+                .text("keep-alive"),
+        )
+        .into_response()
+}
+
+/// Send each event to the webhooks registered for it: `event` for every
+/// event and the event's own type (`session_start`, `error`, ...) for those
+/// of that type.
+pub async fn trigger_webhooks(mut events: Subscription, webhooks: Arc<WebhookManager>) {
+    while let Some(event) = events.recv().await {
+        if webhooks.webhooks.read().await.is_empty() {
+            continue;
+        }
+        let data = serde_json::to_value(EventSummary::from(&event)).unwrap_or_default();
+        let event_type = serde_json::to_value(event.event_type)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        webhooks.trigger("event", data.clone()).await;
+        webhooks.trigger(&event_type, data).await;
+    }
+}
+
+/// Register a webhook
+pub async fn register_webhook_handler(
+    State(state): State<IntegrationState>,
+    Json(config): Json<WebhookConfig>,
+) -> impl IntoResponse {
+    state.webhook_manager.register(config.clone()).await;
+    Json(ApiResponse::success(config))
+}
+
+/// List webhooks
+pub async fn list_webhooks_handler(
+    State(state): State<IntegrationState>,
+) -> Json<ApiResponse<Vec<WebhookConfig>>> {
+    let webhooks = state.webhook_manager.list().await;
+    Json(ApiResponse::success(webhooks))
+}
+
+/// Delete a webhook
+pub async fn delete_webhook_handler(
+    State(state): State<IntegrationState>,
+    Path(webhook_id): Path<String>,
+) -> impl IntoResponse {
+    if state.webhook_manager.unregister(&webhook_id).await {
+        (StatusCode::OK, Json(ApiResponse::success(serde_json::json!({"deleted": true}))))
+    } else {
+        (StatusCode::NOT_FOUND, Json(ApiResponse::success(serde_json::json!({"error": "Webhook not found", "deleted": false}))))
+    }
+}
+
+/// Query parameters for monitoring coverage
+#[derive(Debug, Deserialize)]
+pub struct UptimeParams {
+    #[serde(default = "default_report_days")]
+    pub days: i64,
+}
+
+/// Share of the last `days` the daemon was running, with the gaps
+pub async fn uptime_handler(
+    State(state): State<IntegrationState>,
+    Query(params): Query<UptimeParams>,
+) -> impl IntoResponse {
+    match uptime::build(&state.storage, params.days).await {
+        Ok(coverage) => Json(ApiResponse::success(coverage)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&e.to_string())),
+        ).into_response(),
+    }
+}
+
+/// Get current status (Ralph-compatible)
+pub async fn status_handler(
+    State(state): State<IntegrationState>,
+) -> Json<StatusFile> {
+    let metrics = state.storage.get_summary_metrics(24).await.ok();
+    let by_agent_type = state
+        .storage
+        .count_sessions_grouped_by(SessionGroup::AgentType, None, true)
+        .await
+        .unwrap_or_default();
+
+    Json(StatusFile {
+        daemon_status: "running".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: Utc::now(),
+        uptime_seconds: state.uptime_seconds().await,
+        sessions: SessionsStatus {
+            active_count: metrics.as_ref().map(|m| m.active_sessions).unwrap_or(0),
+            total_24h: metrics.as_ref().map(|m| m.total_sessions).unwrap_or(0),
+            by_agent_type,
+        },
+        analytics: AnalyticsStatus {
+            total_messages: metrics.as_ref().map(|m| m.total_messages).unwrap_or(0),
+            total_cost: metrics.as_ref().map(|m| m.total_cost).unwrap_or(0.0),
+            rate_limit: None,
+        },
+    })
+}
+
+// =============================================================================
+// Router Builder
+// =============================================================================
+
+/// Create the full integration API router
+pub fn create_integration_router(state: IntegrationState) -> Router {
+    Router::new()
+        // Health and info
+        .route("/health", get(health_handler))
+        .route("/info", get(info_handler))
+        .route("/status", get(status_handler))
+        .route("/api/v1/uptime", get(uptime_handler))
+
+        // Sessions
+        .route("/api/v1/sessions", get(list_sessions_handler).delete(clear_sessions_handler))
+        .route("/api/v1/sessions/:id", get(get_session_handler))
+        .route("/api/v1/sessions/:id/events", get(get_session_events_handler))
+        .route("/api/v1/sessions/:id/resources", get(get_session_resources_handler))
+        .route("/api/v1/sessions/:id/commands", get(get_session_commands_handler))
+        .route("/api/v1/sessions/:id/turns", get(get_session_turns_handler))
+        .route("/api/v1/sessions/:id/raw", get(get_session_raw_handler))
+        .route("/api/v1/sessions/:id/sources", get(get_session_sources_handler))
+        .route("/api/v1/sessions/:id/network", get(get_session_network_handler))
+        .route(
+            "/api/v1/sessions/:id/tags",
+            get(get_session_tags_handler).post(add_session_tag_handler),
+        )
+        .route("/api/v1/sessions/:id/tags/:tag", delete(remove_session_tag_handler))
+        .route("/api/v1/tags", get(list_tags_handler))
+
+        // Events
+        .route("/api/v1/events", get(list_events_handler))
+        .route("/api/v1/events/:id", get(get_event_handler))
+
+        // Search
+        .route("/api/v1/search/semantic", get(semantic_search_handler))
+        .route("/api/v1/prompts/duplicates", get(duplicate_prompts_handler))
+
+        // Projects
+        .route("/api/v1/projects", get(list_projects_handler))
+        .route("/api/v1/projects/detail", get(get_project_handler))
+
+        // Analytics
+        .route("/api/v1/analytics/forecast", get(forecast_handler))
+        .route("/api/v1/analytics/compare", get(compare_handler))
+        .route("/api/v1/analytics/report", get(report_handler))
+        .route("/api/v1/analytics/mcp", get(mcp_handler))
+        .route("/api/v1/analytics/slash-commands", get(slash_commands_handler))
+        .route("/api/v1/analytics/throughput", get(throughput_handler))
+        .route("/api/v1/analytics/branches", get(branches_handler))
+        .route("/api/v1/analytics/users", get(users_handler))
+        .route("/api/v1/analytics/hourly", get(hourly_handler))
+        .route("/api/v1/analytics/api-errors", get(api_errors_handler))
+        .route("/api/v1/analytics/sessions", get(session_counts_handler))
+
+        // Export
+        .route("/api/v1/export", get(export_handler))
+        .route("/api/v1/snapshot", get(snapshot_handler))
+        .route("/api/v1/calendar.ics", get(calendar_handler))
+
+        // Adapters
+        .route("/api/v1/adapters", get(adapters_handler))
+        .route("/api/v1/adapters/:name/:command", post(adapter_control_handler))
+
+        // Automation rules
+        .route("/api/v1/rules", get(list_rules_handler).post(create_rule_handler))
+        .route("/api/v1/rules/log", get(rules_log_handler))
+        .route("/api/v1/rules/:id", put(update_rule_handler).delete(delete_rule_handler))
+
+        // Cross-session memory
+        .route("/api/v1/memory", get(list_memory_handler))
+        .route(
+            "/api/v1/memory/:key",
+            get(get_memory_handler).put(put_memory_handler).delete(delete_memory_handler),
+        )
+
+        // Real-time
+        .route("/api/v1/stream", get(sse_handler))
+
+        // Webhooks
+        .route("/api/v1/webhooks", get(list_webhooks_handler).post(register_webhook_handler))
+        .route("/api/v1/webhooks/:id", delete(delete_webhook_handler))
+
+        .with_state(state)
+}
+
+// =============================================================================
+// OpenAPI Documentation
+// =============================================================================
+
+pub const OPENAPI_SPEC: &str = r#"
+openapi: 3.0.3
+info:
+  title: Agent Monitor API
+  description: |
+    REST API for monitoring AI agent sessions (Claude Code, Cursor, Aider, etc.)
+
+    ## Authentication
+    When `sso` is enabled, requests need the login cookie set by signing in
+    at `/auth/login`; without it API calls get 401. `/auth/me` names the
+    signed-in user and role. Viewers may only read; POST, PUT and DELETE
+    need an admin. Without `sso`, POST, PUT and DELETE need the daemon's
+    write token (`ipc_token` in the data directory) as
+    `Authorization: Bearer <token>`.
+
+    Machine clients read with a key from `api_keys` in the config, sent in
+    the `X-API-Key` header; with `sso` it stands in for a login. Once any
+    key is configured, API calls without `sso` need one. An unknown key
+    gets 401. A key never allows changes.
+
+    ## Real-time Updates
+    - WebSocket: Connect to `/api/ws` for bidirectional communication
+    - SSE: Connect to `/api/v1/stream` for server-sent events
+
+    ## Webhooks
+    Register webhooks to receive push notifications for events.
+  version: 0.1.0
+  contact:
+    name: Agent Monitor
+servers:
+  - url: http://localhost:8765
+    description: Local daemon
+
+paths:
+  /health:
+    get:
+      summary: Health check
+      tags: [System]
+      responses:
+        '200':
+          description: Service health status
+
+  /info:
+    get:
+      summary: System information
+      tags: [System]
+      responses:
+        '200':
+          description: System info including version and config
+
+  /status:
+    get:
+      summary: Status file (Ralph-compatible)
+      tags: [System]
+      responses:
+        '200':
+          description: Current daemon status
+
+  /api/v1/uptime:
+    get:
+      summary: Monitoring coverage
+      description: |
+        Share of the last `days` the daemon was running, measured from its
+        first recorded run, with the gaps between runs and whether each
+        followed a crash. Sessions during a gap may be missing or partial.
+      tags: [System]
+      parameters:
+        - name: days
+          in: query
+          description: Days to cover (default 7)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Coverage, runs, crashes and gaps (oldest first)
+
+  /api/v1/sessions:
+    get:
+      summary: List sessions
+      tags: [Sessions]
+      parameters:
+        - name: page
+          in: query
+          schema:
+            type: integer
+            default: 1
+        - name: per_page
+          in: query
+          schema:
+            type: integer
+            default: 50
+        - name: agent_type
+          in: query
+          schema:
+            type: string
+        - name: status
+          in: query
+          schema:
+            type: string
+        - name: active_only
+          in: query
+          schema:
+            type: boolean
+        - name: tag
+          in: query
+          description: Only sessions with this tag
+          schema:
+            type: string
+        - name: user
+          in: query
+          description: Only sessions attributed to this user
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Paginated list of sessions
+    delete:
+      summary: Clear sessions
+      description: |
+        Delete the sessions matching every given criterion, with their
+        events, tags and samples. They stay in the trash for `trash.grace_days`
+        and can be restored with `agent-monitor trash restore`. At least one
+        criterion is required; with `dry_run` the matching sessions are listed
+        and nothing is deleted.
+      tags: [Sessions]
+      parameters:
+        - name: agent_type
+          in: query
+          schema:
+            type: string
+        - name: project
+          in: query
+          description: Glob over the project path (`**` crosses directories)
+          schema:
+            type: string
+        - name: before
+          in: query
+          description: Only sessions last active before this day (YYYY-MM-DD) or RFC 3339 time
+          schema:
+            type: string
+        - name: after
+          in: query
+          description: Only sessions last active after this day (YYYY-MM-DD) or RFC 3339 time
+          schema:
+            type: string
+        - name: session
+          in: query
+          description: Only this session
+          schema:
+            type: string
+        - name: dry_run
+          in: query
+          schema:
+            type: boolean
+            default: false
+      responses:
+        '200':
+          description: The sessions cleared, or that would be
+        '400':
+          description: No criterion, or an invalid date
+        '503':
+          description: The trash is unavailable
+
+  /api/v1/sessions/{id}:
+    get:
+      summary: Get session details
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Session details
+        '404':
+          description: Session not found
+
+  /api/v1/sessions/{id}/events:
+    get:
+      summary: Get session events
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Paginated list of events
+
+  /api/v1/sessions/{id}/resources:
+    get:
+      summary: Get session resource usage
+      description: |
+        CPU and resident memory of the session's agent processes and their
+        children, sampled every `resources.interval_secs` while the session is
+        active. `cpu_percent` is summed over the processes, so 100 is one full
+        core.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+        - name: limit
+          in: query
+          description: Samples to return (default 120)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Samples, newest first
+
+  /api/v1/sessions/{id}/network:
+    get:
+      summary: Get session network usage
+      description: |
+        TCP traffic of the session's agent processes and their children per
+        sampling interval, with the part exchanged with `network.api_hosts`
+        and the number of new connections. Only recorded when
+        `network.enabled` is set, on Linux; intervals without traffic are
+        skipped.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+        - name: limit
+          in: query
+          description: Samples to return (default 120)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Samples, newest first
+
+  /api/v1/sessions/{id}/commands:
+    get:
+      summary: Get commands a session is running
+      description: |
+        Every process below the session's agent processes, as of the daemon's
+        last check, oldest first. `top_level` marks commands the agent started
+        itself; `parent` links the rest into a tree. Commands that run for
+        `commands.min_secs` are also added to the session's events. Requires a
+        running daemon.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Running processes
+        '503':
+          description: Daemon unavailable
+
+  /api/v1/sessions/{id}/turns:
+    get:
+      summary: Get what each prompt added to the context
+      description: |
+        One entry per user prompt with everything up to the next prompt:
+        tokens exchanged, files read for the first time (`new_files`) and
+        again (`reread_files`), files modified, runs per tool, errors and
+        whether the context was compacted. Activity before the first prompt
+        is turn 0. Use it to find the turn that made a session's context
+        explode.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Turns, oldest first
+
+  /api/v1/sessions/{id}/raw:
+    get:
+      summary: Get a session's original transcript
+      description: |
+        Streams the JSONL lines Claude Code wrote for the session, exactly as
+        they are in its transcript files under `~/.claude/projects`, oldest
+        file first. A session spanning several conversations in one project
+        includes each of their files. The files are re-read on every request,
+        so lines the agent wrote since are included.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Transcript lines
+          content:
+            application/jsonl: {}
+        '404':
+          description: Unknown session, or no transcript file found for it
+
+  /api/v1/sessions/{id}/sources:
+    get:
+      summary: Get the files a session was read from
+      description: |
+        Each log file the session's events came from, with the byte offset
+        reading stopped at, how many entries were read and when. The file's
+        current `size_bytes` and the `unread_bytes` past the offset tell
+        whether events are missing because lines were never read; a negative
+        `unread_bytes` means the file was rewritten. The daemon resumes each
+        file from its offset after a restart.
+      tags: [Sessions]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Sources, first read first
+
+  /api/v1/sessions/{id}/tags:
+    get:
+      summary: Get session tags
+      description: Tags on the session, with where each came from (`manual` or `rule:<name>`).
+      tags: [Tags]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Tags, oldest first
+    post:
+      summary: Tag a session
+      description: |
+        Tags are lowercased; letters, digits and `-_.:/` only, up to 64
+        characters. Adding a tag the session already has is not an error.
+      tags: [Tags]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [tag]
+              properties:
+                tag:
+                  type: string
+      responses:
+        '200':
+          description: Tag and whether it was newly added
+        '400':
+          description: Invalid tag
+        '404':
+          description: Session not found
+
+  /api/v1/sessions/{id}/tags/{tag}:
+    delete:
+      summary: Remove a tag from a session
+      tags: [Tags]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+        - name: tag
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Tag removed
+        '404':
+          description: Session does not have this tag
+
+  /api/v1/tags:
+    get:
+      summary: List tags
+      description: Every tag on sessions active in the last `days`, with session count, tokens and spend.
+      tags: [Tags]
+      parameters:
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Tags, most expensive first
+
+  /api/v1/events:
+    get:
+      summary: List all events
+      tags: [Events]
+      parameters:
+        - name: session_id
+          in: query
+          schema:
+            type: string
+        - name: event_type
+          in: query
+          schema:
+            type: string
+        - name: since
+          in: query
+          schema:
+            type: string
+            format: date-time
+        - name: tag
+          in: query
+          description: Only events of sessions with this tag
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Paginated list of events
+
+  /api/v1/events/{id}:
+    get:
+      summary: Get event details
+      tags: [Events]
+      responses:
+        '200':
+          description: Event with full content
+
+  /api/v1/export:
+    get:
+      summary: Export data
+      tags: [Export]
+      parameters:
+        - name: format
+          in: query
+          schema:
+            type: string
+            enum: [json, csv, jsonl, parquet]
+            default: json
+        - name: table
+          in: query
+          description: With format=parquet, which table to export
+          schema:
+            type: string
+            enum: [events, sessions]
+            default: events
+        - name: tag
+          in: query
+          description: Only sessions (and their events) with this tag
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Exported data
+
+  /api/v1/snapshot:
+    get:
+      summary: Read-only database snapshot
+      description: >
+        A checkpointed SQLite copy of the database with daily_usage, tool_usage
+        and session_tag_costs views, for BI tools and DuckDB. SQLite backend only.
+      tags: [Export]
+      responses:
+        '200':
+          description: SQLite database file
+        '500':
+          description: Snapshot failed (e.g. PostgreSQL backend)
+
+  /api/v1/calendar.ics:
+    get:
+      summary: Sessions as a calendar feed
+      description: One iCalendar event per session, from start to end, titled with agent and project
+      tags: [Export]
+      parameters:
+        - name: days
+          in: query
+          schema:
+            type: integer
+            default: 30
+        - name: project
+          in: query
+          schema:
+            type: string
+        - name: tag
+          in: query
+          schema:
+            type: string
+      responses:
+        '200':
+          description: text/calendar feed
+
+  /api/v1/adapters:
+    get:
+      summary: Adapter health
+      description: Watched paths, last scan/parse times, lag, error counts, and sessions held in memory (and evicted once ended) per adapter
+      tags: [System]
+      responses:
+        '200':
+          description: Health of each adapter
+        '503':
+          description: Daemon not running
+
+  /api/v1/adapters/{name}/{command}:
+    post:
+      summary: Control an adapter
+      description: Start, stop, or restart a single adapter without restarting the daemon
+      tags: [System]
+      parameters:
+        - name: name
+          in: path
+          required: true
+          schema:
+            type: string
+            enum: [claude_code, cursor, aider]
+        - name: command
+          in: path
+          required: true
+          schema:
+            type: string
+            enum: [start, stop, restart]
+      responses:
+        '200':
+          description: Health of each adapter after the command
+        '502':
+          description: Daemon rejected the command or is not running
+
+  /api/v1/rules:
+    get:
+      summary: List automation rules
+      description: Rules from the config file (read-only) and rules added via the API
+      tags: [Rules]
+      responses:
+        '200':
+          description: Rules with their source
+        '503':
+          description: Daemon not running
+    post:
+      summary: Create an automation rule
+      tags: [Rules]
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [name, trigger, actions]
+              properties:
+                name:
+                  type: string
+                enabled:
+                  type: boolean
+                trigger:
+                  type: object
+                  description: |
+                    One of `{"type": "event", "event_types": [...], "tool": "...", "content": "regex"}`,
+                    `{"type": "cost", "above": 5.0}`, `{"type": "idle", "minutes": 15}`,
+                    `{"type": "context", "above": 80}` (percent of the context window),
+                    `{"type": "circuit_open"}`
+                actions:
+                  type: array
+                  description: |
+                    Each one of `{"type": "webhook", "url"}`, `{"type": "command", "command"}`,
+                    `{"type": "notify", "title", "message", "channel"}` (channel names an
+                    entry in `notification_channels`; omit for a desktop notification),
+                    `{"type": "mark", "tag"}`,
+                    `{"type": "memory", "key", "value", "tags"}`
+                  items:
+                    type: object
+      responses:
+        '200':
+          description: Rule saved
+        '400':
+          description: Invalid rule or daemon not running
+
+  /api/v1/rules/{id}:
+    put:
+      summary: Replace an automation rule
+      tags: [Rules]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Rule saved
+        '400':
+          description: Invalid rule, config-defined rule, or daemon not running
+    delete:
+      summary: Delete an automation rule
+      tags: [Rules]
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Rule deleted
+        '404':
+          description: Rule not found
+
+  /api/v1/rules/log:
+    get:
+      summary: Rule execution log
+      tags: [Rules]
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            default: 50
+      responses:
+        '200':
+          description: Recent rule executions with per-action results, newest first
 
-pub const OPENAPI_SPEC: &str = r#"
-openapi: 3.0.3
-info:
-  title: Agent Monitor API
-  description: |
-    REST API for monitoring AI agent sessions (Claude Code, Cursor, Aider, etc.)
+  /api/v1/memory:
+    get:
+      summary: List memory entries
+      description: Cross-session memory shared by agents, scripts, and automation rules
+      tags: [Memory]
+      parameters:
+        - name: tag
+          in: query
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Entries, most recently updated first
 
-    ## Authentication
-    Use API key in the `X-API-Key` header for authenticated endpoints.
+  /api/v1/memory/{key}:
+    get:
+      summary: Get a memory entry
+      tags: [Memory]
+      parameters:
+        - name: key
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: The entry
+        '404':
+          description: Entry not found
+    put:
+      summary: Create or update a memory entry
+      description: An existing entry keeps its creation time and originating session
+      tags: [Memory]
+      parameters:
+        - name: key
+          in: path
+          required: true
+          schema:
+            type: string
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [value]
+              properties:
+                value: {}
+                session_id:
+                  type: string
+                tags:
+                  type: array
+                  items:
+                    type: string
+      responses:
+        '200':
+          description: The saved entry
+    delete:
+      summary: Delete a memory entry
+      tags: [Memory]
+      parameters:
+        - name: key
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Entry deleted
+        '404':
+          description: Entry not found
 
-    ## Real-time Updates
-    - WebSocket: Connect to `/api/ws` for bidirectional communication
-    - SSE: Connect to `/api/v1/stream` for server-sent events
+  /api/v1/search/semantic:
+    get:
+      summary: Semantic search over transcripts
+      description: |
+        Ranks sessions by how closely their prompts and responses match the
+        query in meaning. Requires `embeddings.enabled` and an embeddings
+        endpoint in the daemon config.
+      tags: [Search]
+      parameters:
+        - name: q
+          in: query
+          required: true
+          schema:
+            type: string
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            default: 10
+        - name: project
+          in: query
+          description: Only sessions in this project directory
+          schema:
+            type: string
+      responses:
+        '200':
+          description: Sessions with their best-matching message and cosine score
+        '502':
+          description: The embeddings endpoint failed
+        '503':
+          description: Semantic search is disabled
 
-    ## Webhooks
-    Register webhooks to receive push notifications for events.
-  version: 0.1.0
-  contact:
-    name: Agent Monitor
-servers:
-  - url: http://localhost:8765
-    description: Local daemon
+  /api/v1/prompts/duplicates:
+    get:
+      summary: Prompts sent to more than one session
+      description: |
+        Groups prompts that are identical after normalization or nearly so
+        (word overlap of at least `duplicates.min_similarity`), keeping only
+        groups that span several sessions. Most recently repeated first.
+      tags: [Search]
+      parameters:
+        - name: session_id
+          in: query
+          description: Only groups that include this session
+          schema:
+            type: string
+        - name: hours
+          in: query
+          description: Lookback window (default from config, 30 days)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Duplicate prompt groups with every occurrence, oldest first
 
-paths:
-  /health:
+  /api/v1/projects:
     get:
-      summary: Health check
-      tags: [System]
+      summary: Project leaderboard
+      description: |
+        Projects with sessions in the last `days`, ranked by spend over the
+        last seven days. Each lists active sessions, last activity and the
+        success rate (completed share of completed and crashed sessions).
+      tags: [Projects]
+      parameters:
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Service health status
+          description: Projects, most 7-day spend first
 
-  /info:
+  /api/v1/projects/detail:
     get:
-      summary: System information
-      tags: [System]
+      summary: Get one project
+      tags: [Projects]
+      parameters:
+        - name: path
+          in: query
+          required: true
+          description: Project path
+          schema:
+            type: string
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
+        - name: events
+          in: query
+          description: Latest events to include (default 50)
+          schema:
+            type: integer
       responses:
         '200':
-          description: System info including version and config
+          description: Project rollup with its sessions and latest events
+        '404':
+          description: No sessions for this project
 
-  /status:
+  /api/v1/analytics/forecast:
     get:
-      summary: Status file (Ralph-compatible)
-      tags: [System]
+      summary: Spend forecast
+      description: |
+        Daily burn rate averaged over the last `window_days`, month-to-date
+        spend, projected spend by month end and, where `forecast.monthly_budget`
+        or `forecast.project_budgets` are configured, the days until each
+        budget is exhausted at the current rate. Includes per-project
+        projections and the daily rollups behind them.
+      tags: [Analytics]
+      parameters:
+        - name: window_days
+          in: query
+          description: Days averaged for the burn rate (default from config, 14)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Current daemon status
+          description: Overall and per-project projections with daily spend
 
-  /api/v1/sessions:
+  /api/v1/analytics/compare:
     get:
-      summary: List sessions
-      tags: [Sessions]
+      summary: Agent comparison
+      description: |
+        Sessions from the last `days` grouped by agent type, with cost per
+        session, messages per completed session, error rate (share of
+        sessions that crashed or logged an error) and files changed.
+      tags: [Analytics]
       parameters:
-        - name: page
+        - name: project
+          in: query
+          description: Only sessions in this project path
+          schema:
+            type: string
+        - name: days
           in: query
+          description: Days to look back (default 30)
           schema:
             type: integer
-            default: 1
-        - name: per_page
+      responses:
+        '200':
+          description: Per-agent metrics, most sessions first
+
+  /api/v1/analytics/report:
+    get:
+      summary: Usage report
+      description: |
+        Spend, sessions, success and error rates, top projects, users, tags,
+        branches, slash commands, notable errors and the longest sessions of
+        the last `days`. With `compare`, each headline metric also gets its
+        change since the same window shifted back: relative for amounts,
+        absolute for the session count and in percentage points for rates,
+        with a trend (up, down or flat).
+      tags: [Analytics]
+      parameters:
+        - name: days
           in: query
+          description: Days to cover (default 7)
           schema:
             type: integer
-            default: 50
-        - name: agent_type
+        - name: tag
           in: query
+          description: Only sessions with this tag
           schema:
             type: string
-        - name: status
+        - name: user
           in: query
+          description: Only sessions attributed to this user
           schema:
             type: string
-        - name: active_only
+        - name: compare
           in: query
+          description: previous, yesterday, last-week, last-month or an age like 14d
           schema:
-            type: boolean
+            type: string
       responses:
         '200':
-          description: Paginated list of sessions
+          description: The report, with a `comparison` when asked for
+        '400':
+          description: Invalid comparison
 
-  /api/v1/sessions/{id}:
+  /api/v1/analytics/mcp:
     get:
-      summary: Get session details
-      tags: [Sessions]
+      summary: MCP server usage
+      description: |
+        Calls to MCP server tools (`mcp__<server>__<tool>`) from the last
+        `days`, or from one session, grouped by server and tool with error
+        counts and latency. Latency runs from the response that made a call
+        to the message carrying its result, or is the duration reported by
+        the PostToolUse hook; calls recorded without either have none.
+      tags: [Analytics]
       parameters:
-        - name: id
-          in: path
-          required: true
+        - name: project
+          in: query
+          description: Only calls made in this project path
+          schema:
+            type: string
+        - name: session
+          in: query
+          description: Only calls of this session; `days` and `project` are ignored
           schema:
             type: string
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Session details
-        '404':
-          description: Session not found
+          description: Per-server usage, most calls first
 
-  /api/v1/sessions/{id}/events:
+  /api/v1/analytics/slash-commands:
     get:
-      summary: Get session events
-      tags: [Sessions]
+      summary: Slash command usage
+      description: |
+        Slash commands (`/compact`, `/review`, project and plugin commands)
+        run in prompts from the last `days`, or in one session, with uses,
+        sessions and last use. `builtin` is false for commands Claude Code
+        does not ship.
+      tags: [Analytics]
       parameters:
-        - name: id
-          in: path
-          required: true
+        - name: project
+          in: query
+          description: Only prompts in this project path
+          schema:
+            type: string
+        - name: session
+          in: query
+          description: Only prompts of this session; `days` and `project` are ignored
           schema:
             type: string
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Paginated list of events
+          description: Commands, most used first
 
-  /api/v1/events:
+  /api/v1/analytics/throughput:
     get:
-      summary: List all events
-      tags: [Events]
+      summary: Response throughput
+      description: |
+        Output tokens per second of responses from the last `days`, or of
+        one session: p50 and p95 per model over the period and per model and
+        day (UTC). A response lasts from the prompt or tool results that
+        started it to its last entry; responses under 20 output tokens are
+        not measured. Falling throughput is an early sign of API degradation.
+      tags: [Analytics]
       parameters:
-        - name: session_id
+        - name: project
           in: query
+          description: Only responses in this project path
           schema:
             type: string
-        - name: event_type
+        - name: session
           in: query
+          description: Only responses of this session; `days` and `project` are ignored
           schema:
             type: string
-        - name: since
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Throughput per model, and per model and day
+
+  /api/v1/analytics/branches:
+    get:
+      summary: Spend per git branch
+      description: |
+        Sessions from the last `days` grouped by project and the git branch
+        checked out while they ran, with session count, tokens and cost.
+        Sessions whose branch is unknown are grouped under an empty branch.
+      tags: [Analytics]
+      parameters:
+        - name: project
           in: query
+          description: Only sessions in this project path
           schema:
             type: string
-            format: date-time
+        - name: user
+          in: query
+          description: Only sessions attributed to this user
+          schema:
+            type: string
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Paginated list of events
+          description: Per-branch usage, most expensive first
 
-  /api/v1/events/{id}:
+  /api/v1/analytics/users:
     get:
-      summary: Get event details
-      tags: [Events]
+      summary: Usage per user
+      description: |
+        Sessions from the last `days` grouped by the user they are attributed
+        to (the `user` config setting or login name of the machine that
+        recorded them, or the user an OTLP exporter reported), with session
+        counts, messages, tool calls, tokens, cost and last activity.
+        Sessions recorded before attribution are grouped under an empty user.
+      tags: [Analytics]
+      parameters:
+        - name: project
+          in: query
+          description: Only sessions in this project path
+          schema:
+            type: string
+        - name: days
+          in: query
+          description: Days to look back (default 30)
+          schema:
+            type: integer
       responses:
         '200':
-          description: Event with full content
+          description: Per-user usage, most expensive first
 
-  /api/v1/export:
+  /api/v1/analytics/hourly:
     get:
-      summary: Export data
-      tags: [Export]
+      summary: Usage per hour
+      description: |
+        One point per hour, oldest first, with tokens, events per agent type
+        and cost. A session's cost is spread over the hours in proportion to
+        the tokens it logged in each (its events, when it reports no tokens).
+      tags: [Analytics]
       parameters:
-        - name: format
+        - name: hours
+          in: query
+          description: Hours to cover, up to 744 (default 24)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Hourly usage points
+
+  /api/v1/analytics/api-errors:
+    get:
+      summary: API errors per hour
+      description: |
+        One point per hour, oldest first, with the rate limit, overload and
+        server errors agents logged (retried requests included), by kind.
+        When provider status polling is enabled, each point also names the
+        provider incidents open during that hour.
+      tags: [Analytics]
+      parameters:
+        - name: hours
+          in: query
+          description: Hours to cover, up to 744 (default 24)
+          schema:
+            type: integer
+      responses:
+        '200':
+          description: Hourly API error points
+
+  /api/v1/analytics/sessions:
+    get:
+      summary: Session counts
+      description: |
+        Number of sessions per agent type, status or project, counted by the
+        database rather than by loading the sessions. Largest groups first.
+      tags: [Analytics]
+      parameters:
+        - name: by
           in: query
+          description: agent_type, status or project (default agent_type)
           schema:
             type: string
-            enum: [json, csv, jsonl]
-            default: json
+            enum: [agent_type, status, project]
+        - name: hours
+          in: query
+          description: Only sessions active within this many hours (default all)
+          schema:
+            type: integer
+        - name: active
+          in: query
+          description: Only sessions still running (default false)
+          schema:
+            type: boolean
       responses:
         '200':
-          description: Exported data
+          description: List of {key, sessions}
 
   /api/v1/stream:
     get:
       summary: Server-Sent Events stream
       tags: [Real-time]
+      parameters:
+        - name: session_id
+          in: query
+          schema:
+            type: string
+        - name: agent_type
+          in: query
+          schema:
+            type: string
+        - name: event_type
+          in: query
+          description: Comma-separated event types
+          schema:
+            type: string
       responses:
         '200':
-          description: SSE stream of events
+          description: SSE stream of events ("event"), and "lagged" with the number missed
+        '400':
+          description: Unknown agent or event type
 
   /api/v1/webhooks:
     get:
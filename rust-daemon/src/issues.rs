@@ -0,0 +1,303 @@
+//! Linking sessions to Jira, Linear or GitHub issues.
+//!
+//! Issue keys (`ABC-123`) and numbers (`#456`) found in prompts or in the
+//! session's git branch are stored as `issue:abc-123` / `issue:456` session
+//! tags, so `agent-monitor tag list` shows spend per issue and the API can
+//! filter by it. With a `tracker` configured, each linked issue gets a comment
+//! (or the webhook a POST) when the session ends, with its cost and a link to
+//! the transcript export.
+
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::events::{EventBus, EventFilter};
+use crate::humanize;
+use crate::models::{EventType, Session, SessionEvent, SessionTag};
+use crate::storage::Storage;
+
+/// Tag prefix and source of issue tags.
+const TAG_PREFIX: &str = "issue:";
+const TAG_SOURCE: &str = "issue";
+
+/// Prefixes of things that look like issue keys but aren't.
+const NOT_ISSUES: [&str; 10] = ["UTF", "SHA", "ISO", "GPT", "HTTP", "TLS", "RFC", "CVE", "MD", "X"];
+
+/// Where completion notes go.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IssueTracker {
+    /// Comment on Jira issues through the REST API
+    Jira {
+        /// e.g. `https://acme.atlassian.net`
+        base_url: String,
+        /// Account email for basic auth
+        email: String,
+        /// Environment variable holding the API token
+        token_env: String,
+    },
+    /// Comment on Linear issues through the GraphQL API
+    Linear {
+        /// Environment variable holding the API key
+        api_key_env: String,
+    },
+    /// POST a JSON summary to any URL
+    Webhook { url: String },
+}
+
+/// Issue detection settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IssuesConfig {
+    /// Whether issue references are detected and tagged
+    pub enabled: bool,
+
+    /// Only these key prefixes (e.g. `["ABC", "OPS"]`); any when empty
+    pub keys: Vec<String>,
+
+    /// Whether `#456` counts as an issue reference
+    pub numbers: bool,
+
+    /// Where to post when a session touching an issue ends; none when unset
+    pub tracker: Option<IssueTracker>,
+
+    /// Transcript link in posts; `{session}` is the session ID. Defaults to
+    /// the export endpoint of this machine's API.
+    pub transcript_url: Option<String>,
+}
+
+impl Default for IssuesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keys: Vec::new(),
+            numbers: true,
+            tracker: None,
+            transcript_url: None,
+        }
+    }
+}
+
+/// Detects issue references and reports on them.
+pub struct IssueLinker {
+    config: IssuesConfig,
+    storage: Storage,
+    key_pattern: Regex,
+    number_pattern: Regex,
+    transcript_url: String,
+    client: reqwest::Client,
+}
+
+impl IssueLinker {
+    pub fn new(config: IssuesConfig, storage: Storage, http_port: u16) -> Self {
+        let transcript_url = config.transcript_url.clone().unwrap_or_else(|| {
+            format!("http://localhost:{}/api/v1/export?format=jsonl&session_id={{session}}", http_port)
+        });
+        Self {
+            config,
+            storage,
+            key_pattern: Regex::new(r"\b([A-Z][A-Z0-9]{0,9})-([1-9][0-9]{0,6})\b").unwrap(),
+            number_pattern: Regex::new(r"(?:^|[\s(\[])#([1-9][0-9]{0,6})\b").unwrap(),
+            transcript_url,
+            client: reqwest::Client::builder().timeout(Duration::from_secs(30)).build().unwrap_or_default(),
+        }
+    }
+
+    /// Issue references in `text`: uppercase keys and bare numbers, in order
+    /// and without repeats.
+    pub fn detect(&self, text: &str) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        for caps in self.key_pattern.captures_iter(text) {
+            let prefix = &caps[1];
+            let allowed = if self.config.keys.is_empty() {
+                !NOT_ISSUES.contains(&prefix)
+            } else {
+                self.config.keys.iter().any(|k| k == prefix)
+            };
+            if allowed {
+                found.push(caps[0].to_string());
+            }
+        }
+        if self.config.numbers {
+            found.extend(self.number_pattern.captures_iter(text).map(|caps| caps[1].to_string()));
+        }
+        let mut seen = BTreeSet::new();
+        found.retain(|issue| seen.insert(issue.clone()));
+        found
+    }
+
+    async fn tag(&self, session_id: &str, issues: &[String]) -> Result<()> {
+        for issue in issues {
+            let tag = format!("{}{}", TAG_PREFIX, issue.to_lowercase());
+            if self.storage.add_session_tag(&SessionTag::new(session_id, &tag, TAG_SOURCE)).await? {
+                debug!("Linked session {} to {}", session_id, issue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Issues the session's git branch names, e.g. `feature/ABC-123-login`.
+    fn branch_issues(&self, session: &Session) -> Vec<String> {
+        let branch = session.git_branch.as_deref().unwrap_or_default();
+        // Branch names are often lowercased
+        self.detect(&branch.to_uppercase().replace('_', "-"))
+            .into_iter()
+            .filter(|issue| issue.contains('-'))
+            .collect()
+    }
+
+    async fn on_event(&self, event: &SessionEvent) -> Result<()> {
+        match event.event_type {
+            EventType::PromptReceived => {
+                let issues = self.detect(event.content.as_deref().unwrap_or_default());
+                self.tag(&event.session_id, &issues).await?;
+            }
+            EventType::SessionStart => {
+                if let Some(session) = self.storage.get_session(&event.session_id).await? {
+                    self.tag(&session.id, &self.branch_issues(&session)).await?;
+                }
+            }
+            EventType::SessionEnd => {
+                let Some(session) = self.storage.get_session(&event.session_id).await? else {
+                    return Ok(());
+                };
+                self.tag(&session.id, &self.branch_issues(&session)).await?;
+                if self.config.tracker.is_some() {
+                    self.report(&session).await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Post to the tracker about every issue the session is linked to.
+    async fn report(&self, session: &Session) -> Result<()> {
+        let issues: Vec<String> = self
+            .storage
+            .list_session_tags(Some(&session.id), None)
+            .await?
+            .into_iter()
+            .filter(|t| t.source == TAG_SOURCE)
+            .filter_map(|t| t.tag.strip_prefix(TAG_PREFIX).map(|i| i.to_uppercase()))
+            .collect();
+        if issues.is_empty() {
+            return Ok(());
+        }
+        let transcript = self.transcript_url.replace("{session}", &session.id);
+        let note = completion_note(session, &transcript);
+
+        match self.config.tracker {
+            Some(IssueTracker::Jira { ref base_url, ref email, ref token_env }) => {
+                let token = std::env::var(token_env).map_err(|_| anyhow!("{} is not set", token_env))?;
+                // Jira issues always have a project key
+                for issue in issues.iter().filter(|i| i.contains('-')) {
+                    let url = format!("{}/rest/api/2/issue/{}/comment", base_url.trim_end_matches('/'), issue);
+                    let request = self.client.post(&url).basic_auth(email, Some(&token)).json(&json!({ "body": note }));
+                    send(request, &url).await?;
+                }
+            }
+            Some(IssueTracker::Linear { ref api_key_env }) => {
+                let key = std::env::var(api_key_env).map_err(|_| anyhow!("{} is not set", api_key_env))?;
+                let url = "https://api.linear.app/graphql";
+                for issue in issues.iter().filter(|i| i.contains('-')) {
+                    let body = json!({
+                        "query": "mutation($issue: String!, $body: String!) { commentCreate(input: { issueId: $issue, body: $body }) { success } }",
+                        "variables": { "issue": issue, "body": note },
+                    });
+                    send(self.client.post(url).header("Authorization", &key).json(&body), url).await?;
+                }
+            }
+            Some(IssueTracker::Webhook { ref url }) => {
+                let body = json!({
+                    "session_id": session.id,
+                    "project_path": session.project_path,
+                    "issues": issues,
+                    "status": session.status,
+                    "duration_seconds": session.duration_seconds,
+                    "estimated_cost": session.estimated_cost,
+                    "tokens_input": session.tokens_input,
+                    "tokens_output": session.tokens_output,
+                    "summary": session.summary,
+                    "transcript_url": transcript,
+                });
+                send(self.client.post(url).json(&body), url).await?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    pub async fn run(self, event_bus: EventBus) {
+        let mut events = event_bus.subscribe_filtered("Issue linker", EventFilter::all());
+        while let Some(event) = events.recv().await {
+            if let Err(e) = self.on_event(&event).await {
+                warn!("Issue linking failed for session {}: {:#}", event.session_id, e);
+            }
+        }
+    }
+}
+
+/// Comment text posted to each linked issue.
+fn completion_note(session: &Session, transcript: &str) -> String {
+    let mut note = format!(
+        "Agent session ({}) in {} worked on this issue for {:.0} min ({} messages, {} tool calls, {}).",
+        session.agent_type,
+        session.project_path,
+        session.duration_seconds / 60.0,
+        session.message_count,
+        session.tool_call_count,
+        humanize::cost(session.estimated_cost),
+    );
+    if let Some(ref summary) = session.summary {
+        note.push_str("\n\n");
+        note.push_str(summary);
+    }
+    note.push_str(&format!("\n\nTranscript: {}", transcript));
+    note
+}
+
+async fn send(request: reqwest::RequestBuilder, url: &str) -> Result<()> {
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        bail!("{} returned {}: {}", url, status, detail.trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[tokio::test]
+    async fn test_issue_references_become_tags() {
+        let storage = Storage::in_memory();
+        let linker = IssueLinker::new(IssuesConfig::default(), storage.clone(), 8080);
+        assert_eq!(
+            linker.detect("Fix ABC-123 and #45 (see ABC-123), UTF-8 only, not GPT-4 or a#3"),
+            vec!["ABC-123", "45"]
+        );
+        let only_ops = IssueLinker::new(IssuesConfig { keys: vec!["OPS".to_string()], ..IssuesConfig::default() }, storage.clone(), 8080);
+        assert_eq!(only_ops.detect("ABC-1 OPS-2"), vec!["OPS-2"]);
+
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "one");
+        session.git_branch = Some("feature/ops-77_login".to_string());
+        storage.upsert_session(&session).await.unwrap();
+        let mut prompt = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        prompt.content = Some("Pick up ABC-9 where we left off".to_string());
+        linker.on_event(&prompt).await.unwrap();
+        linker.on_event(&SessionEvent::new(&session.id, EventType::SessionEnd, AgentType::ClaudeCode)).await.unwrap();
+
+        let tags: Vec<String> = storage.list_session_tags(Some(&session.id), None).await.unwrap().into_iter().map(|t| t.tag).collect();
+        assert_eq!(tags.len(), 2);
+        assert!(tags.contains(&"issue:abc-9".to_string()));
+        assert!(tags.contains(&"issue:ops-77".to_string()));
+    }
+}
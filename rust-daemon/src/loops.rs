@@ -0,0 +1,358 @@
+//! Agents going round in circles.
+//!
+//! The circuit breaker catches sessions that stop making progress; an agent
+//! can also stay busy doing the same thing over and over. Two patterns are
+//! flagged with a Loop event: `repeats` near-identical calls of the same tool
+//! among a session's last `window` calls (inputs are compared by a
+//! similarity hash, so a changed timestamp or counter doesn't hide a
+//! repeat), and a file edited back and forth, where an edit restores text
+//! the file had before, `flips` times. The TUI shows a "possible loop"
+//! badge on sessions flagged in the last hour. Each pattern is flagged at
+//! most once per `cooldown_secs` per session.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use tracing::{info, warn};
+
+use crate::events::{EventBus, EventFilter};
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Event IDs remembered to skip events published again when a transcript
+/// is re-read.
+const MAX_SEEN_EVENTS: usize = 10_000;
+
+/// Older events are history being replayed, not an agent looping now.
+const MAX_EVENT_AGE_SECS: i64 = 600;
+
+/// Tools whose calls change a file.
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write"];
+
+/// Loop detection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoopConfig {
+    /// Whether sessions are watched for loops
+    pub enabled: bool,
+
+    /// Recent tool calls per session compared with each new one
+    pub window: usize,
+
+    /// Near-identical calls among them that make a loop
+    pub repeats: usize,
+
+    /// Differing bits (of 64) up to which two calls count as near-identical
+    pub max_distance: u32,
+
+    /// Edits restoring earlier text of a file that make a loop
+    pub flips: usize,
+
+    /// Seconds before the same pattern of a session is flagged again
+    pub cooldown_secs: u64,
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: 20,
+            repeats: 4,
+            max_distance: 6,
+            flips: 3,
+            cooldown_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopKind {
+    /// The same tool called with near-identical input
+    RepeatedCall,
+    /// A file edited back and forth
+    FlipFlop,
+}
+
+/// A pattern that looks like a loop.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoopSuspect {
+    pub session_id: String,
+    pub kind: LoopKind,
+    pub tool: String,
+    pub file: Option<String>,
+    /// Near-identical calls, or edits restoring earlier text
+    pub count: usize,
+}
+
+impl LoopSuspect {
+    pub fn describe(&self) -> String {
+        match self.kind {
+            LoopKind::RepeatedCall => {
+                format!("Possible loop: {} called {} times with near-identical input", self.tool, self.count)
+            }
+            LoopKind::FlipFlop => format!(
+                "Possible loop: {} edited back and forth {} times",
+                self.file.as_deref().unwrap_or("a file"),
+                self.count
+            ),
+        }
+    }
+}
+
+/// Recent calls of one session as one source reports them. Transcripts and
+/// hooks both report each call, so they are followed separately rather than
+/// counting every call twice.
+#[derive(Default)]
+struct Calls {
+    /// Tool and input hash of the latest calls, oldest first
+    recent: VecDeque<(String, u64)>,
+    /// Hashes of text each edited file has had
+    texts: HashMap<String, HashSet<u64>>,
+    /// Edits that restored earlier text, per file
+    flips: HashMap<String, usize>,
+}
+
+/// Recent tool calls per session, fed one event at a time.
+pub struct LoopDetector {
+    config: LoopConfig,
+    calls: HashMap<(String, EventType), Calls>,
+    /// When each session's patterns were last flagged
+    flagged: HashMap<(String, LoopKind), DateTime<Utc>>,
+}
+
+impl LoopDetector {
+    pub fn new(config: LoopConfig) -> Self {
+        Self {
+            config,
+            calls: HashMap::new(),
+            flagged: HashMap::new(),
+        }
+    }
+
+    /// Count `event`, returning the loops it completes.
+    pub fn observe(&mut self, event: &SessionEvent) -> Vec<LoopSuspect> {
+        if event.event_type == EventType::SessionEnd {
+            self.calls.retain(|(session, _), _| *session != event.session_id);
+            self.flagged.retain(|(session, _), _| *session != event.session_id);
+            return Vec::new();
+        }
+        let (Some(tool), Some(input)) = (event.tool_name.as_deref(), event.tool_input.as_ref()) else {
+            return Vec::new();
+        };
+        let window = self.config.window.max(1);
+        let calls = self.calls.entry((event.session_id.clone(), event.event_type)).or_default();
+        let mut found = Vec::new();
+
+        let hash = simhash(&format!("{} {}", tool, input));
+        let repeats = 1 + calls
+            .recent
+            .iter()
+            .filter(|(t, h)| t == tool && (h ^ hash).count_ones() <= self.config.max_distance)
+            .count();
+        calls.recent.push_back((tool.to_string(), hash));
+        if calls.recent.len() > window {
+            calls.recent.pop_front();
+        }
+        if repeats >= self.config.repeats {
+            found.push(LoopSuspect {
+                session_id: event.session_id.clone(),
+                kind: LoopKind::RepeatedCall,
+                tool: tool.to_string(),
+                file: event.file_path.clone(),
+                count: repeats,
+            });
+        }
+
+        if let (true, Some(file)) = (EDIT_TOOLS.contains(&tool), event.file_path.as_ref()) {
+            let (before, after) = edit_texts(input);
+            let texts = calls.texts.entry(file.clone()).or_default();
+            let restored = after.is_some_and(|a| before != Some(a) && texts.contains(&a));
+            texts.extend(before.into_iter().chain(after));
+            if restored {
+                let flips = calls.flips.entry(file.clone()).or_default();
+                *flips += 1;
+                if *flips >= self.config.flips {
+                    found.push(LoopSuspect {
+                        session_id: event.session_id.clone(),
+                        kind: LoopKind::FlipFlop,
+                        tool: tool.to_string(),
+                        file: Some(file.clone()),
+                        count: *flips,
+                    });
+                }
+            }
+        }
+
+        let cooldown = Duration::seconds(self.config.cooldown_secs as i64);
+        found.retain(|suspect| {
+            let key = (suspect.session_id.clone(), suspect.kind);
+            if self.flagged.get(&key).is_some_and(|at| event.timestamp - *at < cooldown) {
+                return false;
+            }
+            self.flagged.insert(key, event.timestamp);
+            true
+        });
+        found
+    }
+}
+
+/// Hashes of the text an edit replaces and the text it leaves.
+fn edit_texts(input: &Value) -> (Option<u64>, Option<u64>) {
+    let text = |key: &str| input.get(key).and_then(Value::as_str).map(exact_hash);
+    if let Some(edits) = input.get("edits").and_then(Value::as_array) {
+        let all = |key: &str| {
+            let parts: Vec<&str> = edits.iter().filter_map(|e| e.get(key).and_then(Value::as_str)).collect();
+            (!parts.is_empty()).then(|| exact_hash(&parts.join("\u{0}")))
+        };
+        return (all("old_string"), all("new_string"));
+    }
+    match text("content") {
+        Some(content) => (None, Some(content)),
+        None => (text("old_string"), text("new_string")),
+    }
+}
+
+/// Hash of `text` ignoring differences in whitespace.
+fn exact_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for word in text.split_whitespace() {
+        word.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// 64-bit similarity hash over pairs of adjacent words: texts that share
+/// most word pairs differ in few bits. Numbers count as the same word, so
+/// counters, timestamps and line numbers don't tell calls apart.
+fn simhash(text: &str) -> u64 {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| if w.chars().all(|c| c.is_ascii_digit()) { "0" } else { w })
+        .collect();
+    let mut weights = [0i32; 64];
+    let mut add = |feature: &[&str]| {
+        let mut hasher = DefaultHasher::new();
+        feature.hash(&mut hasher);
+        let hash = hasher.finish();
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    };
+    if words.len() < 2 {
+        add(&words);
+    }
+    for pair in words.windows(2) {
+        add(pair);
+    }
+    weights.iter().enumerate().filter(|(_, w)| **w > 0).fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Watches the event bus for loops.
+pub struct LoopWatcher {
+    detector: LoopDetector,
+    storage: Storage,
+    event_bus: EventBus,
+}
+
+impl LoopWatcher {
+    pub fn new(config: LoopConfig, storage: Storage, event_bus: EventBus) -> Self {
+        Self {
+            detector: LoopDetector::new(config),
+            storage,
+            event_bus,
+        }
+    }
+
+    /// Follow events until the daemon stops.
+    pub async fn run(mut self) {
+        let mut events = self.event_bus.subscribe_filtered("Loop detection", EventFilter::all());
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut seen_order: VecDeque<String> = VecDeque::new();
+        while let Some(event) = events.recv().await {
+            if event.timestamp < Utc::now() - Duration::seconds(MAX_EVENT_AGE_SECS) || !seen.insert(event.id.clone()) {
+                continue;
+            }
+            seen_order.push_back(event.id.clone());
+            if seen_order.len() > MAX_SEEN_EVENTS {
+                if let Some(old) = seen_order.pop_front() {
+                    seen.remove(&old);
+                }
+            }
+            for suspect in self.detector.observe(&event) {
+                if let Err(e) = self.record(&suspect, &event).await {
+                    warn!("Failed to record loop in session {}: {}", suspect.session_id, e);
+                }
+            }
+        }
+    }
+
+    async fn record(&self, suspect: &LoopSuspect, cause: &SessionEvent) -> Result<()> {
+        let description = suspect.describe();
+        info!("Session {}: {}", suspect.session_id, description);
+        let mut event = SessionEvent::new(&suspect.session_id, EventType::Loop, cause.agent_type);
+        event.content = Some(description);
+        event.working_directory = cause.working_directory.clone();
+        event.tool_name = Some(suspect.tool.clone());
+        event.file_path = suspect.file.clone();
+        event.raw_data = Some(json!({ "source": "loop", "loop": suspect }));
+        self.storage.record_event(event, &self.event_bus).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    fn call(tool: &str, input: Value) -> SessionEvent {
+        let mut event = SessionEvent::new("s1", EventType::ToolExecuted, AgentType::ClaudeCode);
+        event.file_path = input.get("file_path").and_then(Value::as_str).map(String::from);
+        event.tool_name = Some(tool.to_string());
+        event.tool_input = Some(input);
+        event
+    }
+
+    #[test]
+    fn test_repeated_calls_and_flip_flops() {
+        let mut detector = LoopDetector::new(LoopConfig::default());
+
+        // Reading different files is not a loop
+        for file in ["src/main.rs", "src/lib.rs", "src/api.rs", "src/tui.rs", "Cargo.toml"] {
+            assert!(detector.observe(&call("Read", json!({ "file_path": file }))).is_empty());
+        }
+
+        // The same test command with a changing seed is
+        let mut found = Vec::new();
+        for seed in 1..=4 {
+            let command = format!("cargo test --workspace parser::tests -- --nocapture --test-threads 1 seed={}", seed);
+            found.extend(detector.observe(&call("Bash", json!({ "command": command }))));
+        }
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, LoopKind::RepeatedCall);
+        assert_eq!(found[0].describe(), "Possible loop: Bash called 4 times with near-identical input");
+
+        // A file edited back and forth
+        let mut found = Vec::new();
+        for (old, new) in [("a + b", "a - b"), ("a - b", "a + b"), ("a + b", "a - b"), ("a - b", "a  +  b")] {
+            let input = json!({ "file_path": "src/calc.rs", "old_string": old, "new_string": new });
+            found.extend(detector.observe(&call("Edit", input)));
+        }
+        let flip = found.iter().find(|s| s.kind == LoopKind::FlipFlop).unwrap();
+        assert_eq!(flip.count, 3);
+        assert_eq!(flip.describe(), "Possible loop: src/calc.rs edited back and forth 3 times");
+
+        // Refining an edit restores nothing
+        let mut other = LoopDetector::new(LoopConfig::default());
+        for (old, new) in [("x", "y"), ("p", "q"), ("y", "z"), ("q", "r")] {
+            let input = json!({ "file_path": "src/calc.rs", "old_string": old, "new_string": new });
+            assert!(other.observe(&call("Edit", input)).is_empty());
+        }
+    }
+}
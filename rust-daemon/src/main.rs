@@ -2,37 +2,119 @@
 //!
 //! A high-performance daemon for monitoring AI agent sessions across multiple tools.
 
+mod anomaly;
 mod api;
+mod apierrors;
+mod archive;
 mod adapters;
 mod analytics;
+mod bench;
+mod calendar;
+mod capabilities;
+mod clear;
+mod commands;
+mod compare;
 mod config;
+mod context;
+mod demo;
+mod digest;
+mod doctor;
+mod drift;
+mod duplicates;
 mod events;
+mod exits;
+mod export;
+mod forecast;
+mod git;
+mod grouping;
+mod highlight;
+mod hooks;
+mod humanize;
+mod import;
 mod integration;
 mod integrations;
+mod issues;
+mod loops;
+mod markdown;
+mod mcp;
 mod models;
+mod network;
+mod notifications;
+mod objstore;
+mod otlp;
+mod output;
+mod plugins;
+mod policy;
+mod preview;
+mod procwatch;
+mod projects;
+mod prompts;
+mod refresh;
+mod related;
+mod remote;
+mod report;
+mod resources;
+mod rules;
+mod search;
+mod slash;
+mod sockets;
+mod spool;
+mod sso;
+mod statusline;
 mod storage;
+mod subscribe;
+mod summarize;
+mod table;
+#[cfg(test)]
+mod testkit;
+mod theme;
+mod throughput;
+mod timefmt;
+mod timeseries;
+mod timetrack;
+mod transcripts;
+mod trash;
 mod tui;
+mod turns;
+mod uninstall;
+mod uptime;
+mod watchmode;
 
 use anyhow::Result;
 use chrono::Utc;
 use clap::{Parser, Subcommand};
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufReader, Read};
 use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
 use std::io::Write;
+use std::time::Duration;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use crate::config::Config;
+use crate::output::{OutputArgs, OutputFormat};
+use crate::theme::Style;
 // Note: models types used via adapters and storage modules
 
-// Cosmic UI colors (for future use with colored output)
-const AURORA_BLUE: &str = "\x1b[38;5;117m";
-const COSMIC_VIOLET: &str = "\x1b[38;5;147m";
-const STELLAR_WHITE: &str = "\x1b[38;5;231m";
-const PULSE_CYAN: &str = "\x1b[38;5;51m";
-const RESET: &str = "\x1b[0m";
-const BOLD: &str = "\x1b[1m";
-const DIM: &str = "\x1b[2m";
+// Cosmic UI colors, printed per the active color profile (see theme.rs)
+const AURORA_BLUE: Style = Style::new("\x1b[38;5;117m", "\x1b[94m");
+const COSMIC_VIOLET: Style = Style::new("\x1b[38;5;147m", "\x1b[95m");
+const PULSE_CYAN: Style = Style::new("\x1b[38;5;51m", "\x1b[96m");
+const SOLAR_AMBER: Style = Style::new("\x1b[38;5;214m", "\x1b[33m");
+const NOVA_RED: Style = Style::new("\x1b[38;5;196m", "\x1b[91m");
+const RESET: Style = Style::new("\x1b[0m", "\x1b[0m");
+const BOLD: Style = Style::new("\x1b[1m", "\x1b[1m");
+const DIM: Style = Style::new("\x1b[2m", "\x1b[2m");
+
+/// Claude Code hook events that `install-hooks` forwards to the daemon.
+const HOOK_EVENTS: [&str; 6] = [
+    "SessionStart",
+    "UserPromptSubmit",
+    "PreToolUse",
+    "PostToolUse",
+    "SubagentStop",
+    "PreCompact",
+];
 
 #[derive(Parser)]
 #[command(name = "agent-monitor")]
@@ -49,6 +131,14 @@ struct Cli {
     /// Enable debug output
     #[arg(short, long, global = true)]
     debug: bool,
+
+    /// Disable colored output (also set by NO_COLOR or CLICOLOR=0)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Named profile with its own config, database and socket
+    #[arg(long, global = true, env = "AGENT_MONITOR_PROFILE")]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -66,15 +156,14 @@ enum Commands {
 
     /// Handle hook events from Claude Code
     Hook {
-        /// Hook event type (SessionStart, PreToolUse, PostToolUse, SubagentStop, etc.)
+        /// Hook event type (SessionStart, UserPromptSubmit, PreToolUse, PostToolUse, SubagentStop, etc.)
         event_type: String,
     },
 
     /// Show daemon status
     Status {
-        /// Output as JSON
-        #[arg(short, long)]
-        json: bool,
+        #[command(flatten)]
+        output: OutputArgs,
 
         /// Skip animations
         #[arg(long)]
@@ -91,14 +180,119 @@ enum Commands {
         #[arg(short, long)]
         all: bool,
 
-        /// Output as JSON
+        /// Only sessions with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Show sessions in groups, newest first (with --all, a week by day)
+        #[arg(short, long, value_enum)]
+        group_by: Option<grouping::GroupBy>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Summarize spend, projects, errors and long sessions
+    Report {
+        /// Days to cover
+        #[arg(long, default_value = "7")]
+        days: i64,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Output the HTML digest
+        #[arg(long)]
+        html: bool,
+
+        /// Send the digest to the configured email recipients now
+        #[arg(long)]
+        email: bool,
+
+        /// Only sessions with this tag
+        #[arg(short, long, conflicts_with = "email")]
+        tag: Option<String>,
+
+        /// Only sessions attributed to this user
+        #[arg(short, long, conflicts_with = "email")]
+        user: Option<String>,
+
+        /// Show changes since an earlier window: previous, yesterday, last-week, last-month or e.g. 14d
+        #[arg(long, conflicts_with = "email")]
+        compare: Option<String>,
+    },
+
+    /// Prompt efficiency per project or user: length, clarifications, edits per prompt
+    Prompts {
+        /// Days to cover
+        #[arg(long, default_value = "7")]
+        days: i64,
+
+        /// Group by project or by user
+        #[arg(long, value_enum, default_value = "project")]
+        by: prompts::PromptGroup,
+
+        /// Only sessions attributed to this user
+        #[arg(short, long)]
+        user: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Send a test notification to the desktop or a configured channel
+    Notify {
+        /// Message text
+        #[arg(default_value = "Test notification from agent-monitor")]
+        message: String,
+
+        /// Channel from notification_channels (default: desktop)
         #[arg(short, long)]
-        json: bool,
+        channel: Option<String>,
     },
 
     /// Install Claude Code hooks for real-time monitoring
     InstallHooks,
 
+    /// Check that the Claude Code hooks reach this daemon, and repair them
+    Hooks {
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(subcommand)]
+        command: Option<HooksCommand>,
+    },
+
+    /// Remove hooks, the background service and the socket; optionally data and config
+    Uninstall {
+        /// Also delete the monitor's data (sessions, trash, archives, keys)
+        #[arg(long)]
+        data: bool,
+
+        /// Also delete the config directory
+        #[arg(long)]
+        config: bool,
+
+        /// Don't ask before deleting data or config
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print Claude Code's statusline (cost, tokens, burn rate) from the JSON on stdin
+    ClaudeStatusline {
+        /// Set this command as the statusLine in ~/.claude/settings.json
+        #[arg(long)]
+        install: bool,
+
+        /// Replace a statusLine command that is already configured
+        #[arg(long, requires = "install")]
+        force: bool,
+    },
+
     /// Manage configuration
     Config {
         /// Show current configuration
@@ -108,6 +302,9 @@ enum Commands {
         /// Initialize default configuration file
         #[arg(short, long)]
         init: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
     },
 
     /// Launch web dashboard
@@ -119,10 +316,39 @@ enum Commands {
         /// Port to bind to
         #[arg(short, long, default_value = "8765")]
         port: u16,
+
+        /// Seconds between dashboard updates (overrides `refresh.dashboard_secs`)
+        #[arg(long, value_name = "SECS")]
+        refresh: Option<f64>,
     },
 
     /// Interactive live monitoring dashboard
-    Watch,
+    Watch {
+        /// Attach to a remote daemon's web API instead of the local database
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+        /// API key sent with remote requests, one of the server's `api_keys`
+        /// API key sent with remote requests
+        #[arg(long, requires = "remote")]
+        api_key: Option<String>,
+
+        /// Read from this database file instead of the configured one
+        #[arg(long, value_name = "PATH", conflicts_with = "remote")]
+        db: Option<PathBuf>,
+
+        /// Browse the database read-only as a snapshot, without live refresh
+        #[arg(long, conflicts_with = "remote")]
+        frozen: bool,
+
+        /// Wallboard display: large numbers, no key hints, views cycle on their own
+        #[arg(long)]
+        kiosk: bool,
+
+        /// Seconds between refreshes (overrides `refresh.tui_secs`; events
+        /// refresh twice as often)
+        #[arg(long, value_name = "SECS")]
+        refresh: Option<f64>,
+    },
 
     /// Clear sessions from database
     Clear {
@@ -130,21 +356,359 @@ enum Commands {
         #[arg(short, long)]
         agent_type: Option<String>,
 
+        /// Clear only sessions whose project path matches this glob (`**` crosses directories)
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Clear only sessions last active before this day (YYYY-MM-DD) or time
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Clear only sessions last active after this day (YYYY-MM-DD) or time
+        #[arg(long)]
+        after: Option<String>,
+
+        /// Clear only this session
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// List the sessions that would be cleared without deleting them
+        #[arg(long)]
+        dry_run: bool,
+
         /// Clear all sessions and events
-        #[arg(short = 'A', long)]
+        #[arg(short = 'A', long, conflicts_with_all = ["agent_type", "project", "before", "after", "session"])]
         all: bool,
     },
 
+    /// List, restore or permanently delete cleared sessions
+    Trash {
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(subcommand)]
+        command: Option<TrashCommand>,
+    },
+
+    /// Run the dashboard against synthetic sessions (nothing is persisted)
+    Demo {
+        /// Number of concurrently active sessions
+        #[arg(short, long, default_value = "6")]
+        sessions: usize,
+
+        /// Events generated per second
+        #[arg(short, long, default_value = "2.0")]
+        rate: f64,
+
+        /// Serve the web dashboard instead of the TUI
+        #[arg(long)]
+        web: bool,
+
+        /// Port for the web dashboard
+        #[arg(short, long, default_value = "8765")]
+        port: u16,
+    },
+
+    /// Inspect and control the running daemon's adapters
+    Adapters {
+        #[command(flatten)]
+        output: OutputArgs,
+
+        #[command(subcommand)]
+        command: Option<AdapterCommand>,
+    },
+
+    /// Read and write the cross-session memory shared with agents and scripts
+    Memory {
+        #[command(subcommand)]
+        command: MemoryCommand,
+    },
+
+    /// Label sessions, e.g. `experiment` or `prod-incident`
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+
+    /// Move idle sessions to a directory or bucket, keeping them searchable
+    #[command(args_conflicts_with_subcommands = true)]
+    Archive {
+        #[command(subcommand)]
+        command: Option<ArchiveCommand>,
+
+        /// Archive sessions idle for longer than this (e.g. 60d, 12h, 2w)
+        #[arg(long, default_value = "60d")]
+        older_than: String,
+
+        /// Directory, s3://bucket/prefix or gs://bucket/prefix [default: <data dir>/archive]
+        #[arg(long)]
+        to: Option<String>,
+
+        /// List the sessions that would be archived without moving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Write sessions and events to a directory or bucket as JSONL or Parquet
+    Export {
+        /// File format
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: export::ExportFormat,
+
+        /// Directory, s3://bucket/prefix or gs://bucket/prefix
+        #[arg(long, default_value = ".")]
+        to: String,
+
+        /// Only what changed in this long (e.g. 7d, 12h); everything by default
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Import history recorded by another tool
+    Import {
+        /// What recorded it
+        #[arg(long, value_enum)]
+        from: import::ImportSource,
+
+        /// Its database or data directory, or the JSON report or export
+        path: PathBuf,
+
+        /// Only what started before this day (YYYY-MM-DD), e.g. when the
+        /// monitor was installed
+        #[arg(long)]
+        before: Option<chrono::NaiveDate>,
+    },
+
+    /// Show how much of the time the daemon was running, and the gaps
+    Uptime {
+        /// Days to cover
+        #[arg(long, default_value = "7")]
+        days: i64,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Inspect the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+
+    /// Check config, database, daemon and hooks, and show storage savings
+    Doctor {
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Measure event pipeline throughput and latency with synthetic load
+    Bench {
+        /// Transcript lines written per second
+        #[arg(short, long, default_value = "100")]
+        rate: u32,
+
+        /// Seconds to generate load for
+        #[arg(long, default_value = "10")]
+        duration: u64,
+
+        /// Use the in-memory storage backend instead of SQLite
+        #[arg(long)]
+        memory: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
     /// Show version
     Version,
 }
 
+#[derive(Subcommand)]
+enum AdapterCommand {
+    /// Show adapter health
+    List,
+
+    /// Start an adapter (also enables adapters that are off by default)
+    Start {
+        /// Adapter name (claude_code, cursor, aider)
+        name: String,
+    },
+
+    /// Stop an adapter
+    Stop {
+        /// Adapter name (claude_code, cursor, aider)
+        name: String,
+    },
+
+    /// Restart an adapter, e.g. after fixing permissions on its directory
+    Restart {
+        /// Adapter name (claude_code, cursor, aider)
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MemoryCommand {
+    /// Print an entry's value, or the whole entry with `--output json|yaml`
+    Get {
+        key: String,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Write an entry (the value is parsed as JSON, falling back to a string)
+    Set {
+        key: String,
+        value: String,
+
+        /// Tag to attach (repeatable)
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Session the entry came from
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Scope the entry to a project directory, so new sessions there see it
+        #[arg(short, long)]
+        project: Option<PathBuf>,
+    },
+
+    /// List entries, most recently updated first
+    List {
+        /// Only entries with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Delete an entry
+    Delete { key: String },
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Tag a session
+    Add {
+        /// Session ID or a unique prefix of it
+        session: String,
+
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+
+    /// Remove tags from a session
+    Remove {
+        /// Session ID or a unique prefix of it
+        session: String,
+
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+
+    /// List a session's tags, or every tag with its spend
+    List {
+        /// Session ID or a unique prefix of it
+        session: Option<String>,
+
+        /// Days of sessions counted toward each tag's spend
+        #[arg(long, default_value = "30")]
+        days: i64,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Show size, rows per table, indexes, fragmentation and the heaviest sessions
+    Stats {
+        /// Number of heaviest sessions to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Write a read-only copy of the database for BI tools and DuckDB
+    Snapshot {
+        /// File to create
+        path: PathBuf,
+    },
+
+    /// Show the clear, prune, annotate and mark requests tools sent over the socket
+    Audit {
+        /// Number of entries to show
+        #[arg(long, default_value = "50")]
+        limit: usize,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksCommand {
+    /// Check each hook's script and settings entry, and send a probe event
+    Status {
+        /// Rewrite scripts and settings entries that are missing or stale
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// Show cleared sessions and when they will be deleted for good
+    List,
+
+    /// Load a cleared session back into the database
+    Restore {
+        /// Session ID or a unique prefix of it
+        session: String,
+    },
+
+    /// Delete the sessions in the trash for good
+    Empty {
+        /// Only those past their grace period
+        #[arg(long)]
+        expired: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ArchiveCommand {
+    /// List archived sessions, optionally only those matching a query
+    List {
+        query: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Load an archived session back into the database
+    Restore {
+        /// Session ID or a unique prefix of it
+        session: String,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    theme::init(cli.no_color);
+    config::set_profile(cli.profile.as_deref())?;
 
-    // Setup logging (skip for hook command to avoid polluting Claude Code)
-    let is_hook = matches!(cli.command, Commands::Hook { .. });
+    // Setup logging (skip for hook and statusline commands to avoid polluting Claude Code)
+    let is_hook = matches!(
+        cli.command,
+        Commands::Hook { .. } | Commands::ClaudeStatusline { install: false, .. }
+    );
 
     if !is_hook {
         let level = if cli.debug {
@@ -158,7 +722,15 @@ async fn main() -> Result<()> {
         let _ = FmtSubscriber::builder()
             .with_max_level(level)
             .with_target(false)
+            .with_ansi(theme::enabled())
             .try_init();
+
+        // The daemon takes its display settings from the config it runs with
+        if !matches!(cli.command, Commands::Daemon { .. }) {
+            let config = Config::load_or_default().unwrap_or_default();
+            timefmt::init(&config.timestamps);
+            humanize::init(&config.humanize);
+        }
     }
 
     match cli.command {
@@ -168,26 +740,92 @@ async fn main() -> Result<()> {
         Commands::Hook { event_type } => {
             handle_hook(&event_type).await?;
         }
-        Commands::Status { json, no_animation } => {
-            show_status(json, no_animation).await?;
+        Commands::Status { output, no_animation } => {
+            show_status(output.format(), no_animation).await?;
+        }
+        Commands::Sessions { limit, all, tag, group_by, output } => {
+            list_sessions(limit, all, tag.as_deref(), group_by, output.format()).await?;
+        }
+        Commands::Report { days, output, html, email, tag, user, compare } => {
+            show_report(days, output.format(), html, email, tag.as_deref(), user.as_deref(), compare.as_deref()).await?;
+        }
+        Commands::Prompts { days, by, user, output } => {
+            show_prompt_efficiency(days, by, user.as_deref(), output.format()).await?;
         }
-        Commands::Sessions { limit, all, json } => {
-            list_sessions(limit, all, json).await?;
+        Commands::Notify { message, channel } => {
+            send_test_notification(&message, channel.as_deref()).await?;
         }
         Commands::InstallHooks => {
             install_hooks().await?;
         }
-        Commands::Config { show, init } => {
-            manage_config(show, init).await?;
+        Commands::Hooks { output, command } => {
+            let HooksCommand::Status { repair } = command.unwrap_or(HooksCommand::Status { repair: false });
+            hooks_status(repair, output.format()).await?;
+        }
+        Commands::Uninstall { data, config, yes, dry_run } => {
+            run_uninstall(data, config, yes, dry_run).await?;
+        }
+        Commands::ClaudeStatusline { install, force } => {
+            if install {
+                install_statusline(force)?;
+            } else {
+                print_statusline().await;
+            }
+        }
+        Commands::Config { show, init, output } => {
+            manage_config(show, init, output.format()).await?;
+        }
+        Commands::Web { host, port, refresh } => {
+            run_web(&host, port, refresh).await?;
+        }
+        Commands::Watch { remote, api_key, db, frozen, kiosk, refresh } => {
+            run_watch(remote, api_key, db, frozen, kiosk, refresh).await?;
+        }
+        Commands::Clear { agent_type, project, before, after, session, dry_run, all } => {
+            let filter = clear::ClearFilter {
+                agent_type,
+                project,
+                before: before.as_deref().map(clear::parse_time).transpose()?,
+                after: after.as_deref().map(clear::parse_time).transpose()?,
+                session_id: session,
+            };
+            run_clear(filter, all, dry_run).await?;
+        }
+        Commands::Uptime { days, output } => {
+            show_uptime(days, output.format()).await?;
+        }
+        Commands::Trash { output, command } => {
+            manage_trash(command.unwrap_or(TrashCommand::List), output.format()).await?;
+        }
+        Commands::Demo { sessions, rate, web, port } => {
+            run_demo(sessions, rate, web, port).await?;
+        }
+        Commands::Adapters { output, command } => {
+            manage_adapters(command.unwrap_or(AdapterCommand::List), output.format()).await?;
+        }
+        Commands::Memory { command } => {
+            manage_memory(command).await?;
+        }
+        Commands::Tag { command } => {
+            manage_tags(command).await?;
+        }
+        Commands::Archive { command, older_than, to, dry_run } => {
+            manage_archive(command, &older_than, to, dry_run).await?;
+        }
+        Commands::Export { format, to, since } => {
+            run_export(format, &to, since.as_deref()).await?;
         }
-        Commands::Web { host, port } => {
-            run_web(&host, port).await?;
+        Commands::Import { from, path, before } => {
+            run_import(from, &path, before).await?;
         }
-        Commands::Watch => {
-            run_watch().await?;
+        Commands::Db { command } => {
+            manage_db(command).await?;
         }
-        Commands::Clear { agent_type, all } => {
-            run_clear(agent_type, all).await?;
+        Commands::Doctor { output } => {
+            run_doctor(output.format()).await?;
+        }
+        Commands::Bench { rate, duration, memory, output } => {
+            run_bench(rate, duration, memory, output.format()).await?;
         }
         Commands::Version => {
             print_version();
@@ -249,8 +887,11 @@ async fn run_daemon(config_path: Option<String>, no_animation: bool) -> Result<(
 
     let config = match config_path {
         Some(path) => Config::load(&path)?,
-        None => Config::default(),
+        None => Config::load_or_default()?,
     };
+    models::set_local_user(config.user.clone());
+    timefmt::init(&config.timestamps);
+    humanize::init(&config.humanize);
 
     println!(
         "{}╭─────────────────────────────────────────────────────╮{}",
@@ -285,21 +926,179 @@ async fn run_daemon(config_path: Option<String>, no_animation: bool) -> Result<(
     info!("Starting Agent Monitor Daemon");
 
     // Initialize storage
-    let storage = storage::Storage::new(&config.db_path).await?;
+    let storage = storage::Storage::connect(&config).await?;
     storage.initialize().await?;
+    let storage = plugins::with_plugins(storage, &config.plugins)?;
+
+    // Record this run, so gaps in monitoring show up in `agent-monitor uptime`
+    let heartbeat = uptime::Heartbeat::start(storage.clone()).await?;
+    let heartbeat_task = tokio::spawn(heartbeat.clone().run());
 
     // Initialize event bus
     let event_bus = events::EventBus::new();
 
+    // Hook events sent while the daemon was down, before transcripts are read;
+    // oversized payloads keep arriving in the spool while it runs
+    if let Err(e) = spool::replay(&spool::spool_dir(&config.data_dir), &storage, &event_bus).await {
+        tracing::warn!("Failed to replay spooled hook events: {}", e);
+    }
+    tokio::spawn(spool::run(spool::spool_dir(&config.data_dir), storage.clone(), event_bus.clone()));
+
     // Initialize adapters (all available)
-    let mut adapters = adapters::AdapterRegistry::new(&config, event_bus.clone(), storage.clone());
+    // One process scanner shared by every adapter
+    let processes = procwatch::ProcessWatcher::new(config.poll_interval);
+    tokio::spawn(processes.clone().run());
+
+    let mut adapters =
+        adapters::AdapterRegistry::new(&config, event_bus.clone(), storage.clone(), processes.clone());
     adapters.register_all().await?;
 
     // Start adapters
     adapters.start_all().await?;
+    let adapters = std::sync::Arc::new(tokio::sync::RwLock::new(adapters));
+
+    // Start automation rules
+    let rules = rules::RulesEngine::new(&config, storage.clone()).await?;
+    tokio::spawn(rules.clone().run(event_bus.clone()));
+
+    // Record the git branch each session works on
+    tokio::spawn(git::BranchTracker::new(storage.clone()).run(event_bus.clone()));
+
+    // Link sessions to the issues their prompts and branches mention
+    if config.issues.enabled {
+        let linker = issues::IssueLinker::new(config.issues.clone(), storage.clone(), config.http_port);
+        tokio::spawn(linker.run(event_bus.clone()));
+    }
+
+    // Flag bursts of errors, tool calls and token spend
+    if config.anomaly.enabled {
+        let notifier = notifications::Notifier::new(&config.notification_channels);
+        let watcher = anomaly::AnomalyWatcher::new(config.anomaly.clone(), storage.clone(), event_bus.clone(), notifier);
+        tokio::spawn(watcher.run());
+    }
+
+    // Flag agents repeating themselves
+    if config.loops.enabled {
+        tokio::spawn(loops::LoopWatcher::new(config.loops.clone(), storage.clone(), event_bus.clone()).run());
+    }
+
+    // Close sessions when their agent process exits
+    tokio::spawn(exits::ExitMonitor::new(storage.clone(), event_bus.clone(), processes.clone()).run());
+
+    // Start session summarizer
+    if config.summarizer.enabled {
+        let summarizer = summarize::Summarizer::new(config.summarizer.clone(), storage.clone());
+        tokio::spawn(summarizer.run());
+    }
+
+    // Start weekly email digest
+    if config.email_digest.enabled {
+        let scheduler = digest::DigestScheduler::new(
+            config.email_digest.clone(),
+            storage.clone(),
+            config.data_dir.clone(),
+        );
+        tokio::spawn(scheduler.run());
+    }
+
+    // Start scheduled exports
+    if config.export.enabled {
+        match export::Exporter::new(config.export.clone(), storage.clone(), config.data_dir.clone()) {
+            Ok(exporter) => {
+                tokio::spawn(exporter.run());
+            }
+            Err(e) => tracing::error!("Scheduled export disabled: {:#}", e),
+        }
+    }
+
+    // Start pushing finished sessions to the time tracker
+    if config.time_tracking.enabled {
+        match timetrack::TimeTrackingSync::new(config.time_tracking.clone(), storage.clone(), config.data_dir.clone()) {
+            Ok(sync) => {
+                tokio::spawn(sync.run());
+            }
+            Err(e) => tracing::error!("Time tracking disabled: {:#}", e),
+        }
+    }
+
+    // Start transcript embedding for semantic search
+    if config.embeddings.enabled {
+        let index = search::SemanticIndex::new(config.embeddings.clone(), storage.clone());
+        tokio::spawn(index.run());
+    }
+
+    // Start OTLP receiver for agents that export telemetry
+    if config.otlp.enabled {
+        let receiver = otlp::OtlpReceiver::new(config.otlp.clone(), storage.clone(), event_bus.clone());
+        tokio::spawn(async move {
+            if let Err(e) = receiver.run().await {
+                tracing::error!("OTLP receiver error: {}", e);
+            }
+        });
+    }
+
+    // Start CPU and memory sampling of active sessions
+    if config.resources.enabled {
+        let sampler = resources::ResourceSampler::new(config.resources.clone(), storage.clone(), processes.clone());
+        tokio::spawn(sampler.run());
+    }
+
+    // Start watching Claude Code settings for drift
+    if config.drift.enabled {
+        let watcher = drift::SettingsWatcher::new(
+            config.drift.clone(),
+            storage.clone(),
+            event_bus.clone(),
+            config.claude_home.clone(),
+            &config.data_dir,
+        );
+        tokio::spawn(watcher.run());
+    }
+
+    // Start polling the provider status page for incidents
+    if config.provider_status.enabled {
+        let poller = apierrors::StatusPoller::new(config.provider_status.clone(), &config.data_dir);
+        tokio::spawn(poller.run());
+    }
+
+    // Start emptying sessions that have been in the trash past their grace period
+    let trash = trash::Trash::open(&config)?;
+    tokio::spawn(trash.clone().run(storage.clone()));
+
+    // Start network sampling of active sessions
+    if config.network.enabled {
+        let sampler = network::NetworkSampler::new(config.network.clone(), storage.clone(), processes.clone());
+        tokio::spawn(sampler.run());
+    }
+
+    // Start following the commands agents run
+    let commands = commands::CommandTracker::new(
+        config.commands.clone(),
+        storage.clone(),
+        event_bus.clone(),
+        processes.clone(),
+    );
+    if config.commands.enabled {
+        tokio::spawn(commands.clone().run());
+    }
 
     // Start IPC server
-    let ipc_server = api::IpcServer::new(&config.socket_path, storage.clone());
+    let policy = policy::PolicyEngine::new(config.policy.clone())?;
+    if policy.is_enabled() {
+        info!("Policy mode enabled - hook events will receive decisions");
+    }
+    let ipc_server = api::IpcServer::new(
+        &config,
+        api::IpcServices {
+            storage: storage.clone(),
+            policy,
+            adapters: adapters.clone(),
+            rules,
+            commands,
+            events: event_bus.clone(),
+            trash,
+        },
+    )?;
     tokio::spawn(async move {
         if let Err(e) = ipc_server.run().await {
             tracing::error!("IPC server error: {}", e);
@@ -309,15 +1108,22 @@ async fn run_daemon(config_path: Option<String>, no_animation: bool) -> Result<(
     println!("  {}● Connected{} - Daemon running", PULSE_CYAN, RESET);
     info!("Daemon started successfully");
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c().await?;
+    // Wait for shutdown signal; service managers stop the daemon with SIGTERM
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
 
     println!();
     println!("{}─────────────────────────────────────────{}", AURORA_BLUE, RESET);
     println!("  {}✦ Shutting down gracefully...{}", COSMIC_VIOLET, RESET);
     println!("{}─────────────────────────────────────────{}", AURORA_BLUE, RESET);
 
-    adapters.stop_all().await?;
+    adapters.write().await.stop_all().await?;
+    heartbeat_task.abort();
+    let _ = heartbeat_task.await;
+    heartbeat.stop().await?;
 
     Ok(())
 }
@@ -325,69 +1131,118 @@ async fn run_daemon(config_path: Option<String>, no_animation: bool) -> Result<(
 /// Handle hook events from Claude Code.
 /// This is called by Claude Code hooks with event data on stdin.
 async fn handle_hook(event_type: &str) -> Result<()> {
-    // Read stdin (non-blocking check, then read)
-    let stdin = io::stdin();
-    let mut stdin_data = String::new();
-
-    // Try to read available data
-    for line in stdin.lock().lines() {
-        match line {
-            Ok(l) => stdin_data.push_str(&l),
-            Err(_) => break,
-        }
-        break; // Only read one line/block
-    }
+    let config = Config::load_or_default().unwrap_or_default();
+    let spool_dir = spool::spool_dir(&config.data_dir);
 
-    // Parse the event data
-    let event_data: serde_json::Value = if !stdin_data.is_empty() {
-        serde_json::from_str(&stdin_data).unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
-    };
+    // Read the whole payload, which may be pretty-printed over many lines
+    let mut stdin = io::stdin().lock();
+    let mut payload = Vec::new();
+    let _ = (&mut stdin).take(spool::MAX_PAYLOAD_BYTES + 1).read_to_end(&mut payload);
 
     // Build hook message
-    let message = serde_json::json!({
+    let mut message = serde_json::json!({
         "type": "hook_event",
+        "action": "hook_event",
         "event_type": event_type,
         "timestamp": Utc::now().to_rfc3339(),
-        "data": event_data,
     });
 
+    // Too large to send over the socket. A decision can't wait for replay,
+    // so the daemon decides on a shortened copy; anything else is spooled
+    if payload.len() as u64 > spool::MAX_PAYLOAD_BYTES {
+        if !policy::is_decision_event(event_type) {
+            let _ = spool::append_payload(&spool_dir, &message, &payload, &mut stdin);
+            return Ok(());
+        }
+        let _ = (&mut stdin).take(spool::MAX_SPOOLED_PAYLOAD_BYTES).read_to_end(&mut payload);
+        message["data"] = api::shorten_hook_payload(&payload);
+    } else {
+        message["data"] = api::parse_hook_payload(&payload);
+    }
+    let probe = message["data"].get(hooks::PROBE_FIELD).is_some();
+
     // Try to send to daemon via Unix socket
-    let socket_path = "/tmp/agent-monitor.sock";
-    if let Ok(mut stream) = UnixStream::connect(socket_path) {
-        let msg = serde_json::to_string(&message)? + "\n";
-        let _ = stream.write_all(msg.as_bytes());
+    let msg = serde_json::to_string(&message)? + "\n";
+    let sent = UnixStream::connect(&config.socket_path)
+        .and_then(|mut stream| stream.write_all(msg.as_bytes()).map(|_| stream));
+    match sent {
+        // `hooks status` checking the daemon answers: print its reply as is
+        Ok(stream) if probe => {
+            stream.set_read_timeout(Some(Duration::from_millis(2000)))?;
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_ok() {
+                print!("{}", line);
+            }
+        }
+        // A probe is never spooled
+        Err(_) if probe => {}
+        Ok(stream) => {
+            // Blockable events wait briefly for a policy decision, SessionStart for context
+            if policy::is_decision_event(event_type) || event_type == "SessionStart" {
+                if let Some(output) = read_hook_output(&stream, event_type) {
+                    println!("{}", output);
+                }
+            }
+        }
+        // Daemon isn't running: keep the event for its next start, without blocking Claude Code
+        Err(_) => {
+            let _ = spool::append(&spool_dir, &message);
+        }
     }
-    // Silently fail if daemon isn't running - don't block Claude Code
 
     Ok(())
 }
 
-async fn show_status(json_output: bool, no_animation: bool) -> Result<()> {
-    let config = Config::default();
+/// Read the daemon's reply to a hook event (a decision, or context for
+/// SessionStart), giving up after a short timeout.
+fn read_hook_output(stream: &UnixStream, event_type: &str) -> Option<serde_json::Value> {
+    stream.set_read_timeout(Some(Duration::from_millis(2000))).ok()?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+
+    let response: serde_json::Value = serde_json::from_str(&line).ok()?;
+    if event_type == "SessionStart" {
+        return response.get("context")?.as_str().map(context::to_hook_output);
+    }
+    let decision: policy::HookDecision = serde_json::from_value(response.get("decision")?.clone()).ok()?;
+    decision.to_hook_output(event_type)
+}
+
+async fn show_status(output: OutputFormat, no_animation: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
 
-    if !config.db_path.exists() {
-        if json_output {
-            println!(r#"{{"error": "Database not found"}}"#);
+    if config.uses_local_db() && !config.db_path.exists() {
+        if !output.is_table() {
+            output.print(&serde_json::json!({ "error": "Database not found" }))?;
         } else {
             println!("{}✗ Error:{} Database not found. Is the daemon running?",
-                "\x1b[38;5;196m", RESET);
+                NOVA_RED, RESET);
         }
         return Ok(());
     }
 
-    let storage = storage::Storage::new(&config.db_path).await?;
+    let storage = storage::Storage::connect(&config).await?;
     let sessions = storage.get_active_sessions(100).await?;
     let metrics = storage.get_summary_metrics(24).await?;
-
-    if json_output {
-        let output = serde_json::json!({
-            "active_sessions": sessions.len(),
-            "metrics": metrics,
-            "sessions": sessions,
-        });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+    // Databases from before runs were recorded have no table yet
+    let coverage = uptime::build(&storage, 7).await.ok();
+
+    // Adapter health lives in the daemon process; None if it isn't running
+    let adapters: Option<Vec<adapters::AdapterHealth>> =
+        api::ipc_request(&config.socket_path, &serde_json::json!({ "action": "get_adapters" }))
+            .await
+            .ok()
+            .and_then(|r| serde_json::from_value(r.get("adapters")?.clone()).ok());
+
+    let status = serde_json::json!({
+        "active_sessions": sessions.len(),
+        "metrics": metrics,
+        "adapters": adapters,
+        "coverage": coverage,
+        "sessions": sessions,
+    });
+    if output.print(&status)? {
         return Ok(());
     }
 
@@ -396,124 +1251,392 @@ async fn show_status(json_output: bool, no_animation: bool) -> Result<()> {
         println!("{}  ✦   ⋆  ★    ✧  ✶    ★   ⋆{}", DIM, RESET);
     }
 
-    println!(
-        "{}╭──────────────────────── ✦ Agent Monitor Status ✦ ────────────────────────╮{}",
-        AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}                                                                          {}│{}",
-        AURORA_BLUE, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}  {}● Active Sessions:{} {}{}                                                    {}│{}",
-        AURORA_BLUE, RESET, BOLD, RESET, PULSE_CYAN, sessions.len(), AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}                                                                          {}│{}",
-        AURORA_BLUE, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}  {}📊 24-Hour Summary{}                                                      {}│{}",
-        AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}  {}──────────────────────────────{}                                          {}│{}",
-        AURORA_BLUE, RESET, DIM, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}     Sessions:  {:>8}                                                  {}│{}",
-        AURORA_BLUE, RESET, metrics.total_sessions, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}     Messages:  {:>8}                                                  {}│{}",
-        AURORA_BLUE, RESET, metrics.total_messages, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}     Cost:      {}${:>7.2}{}                                                  {}│{}",
-        AURORA_BLUE, RESET, COSMIC_VIOLET, metrics.total_cost, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}│{}                                                                          {}│{}",
-        AURORA_BLUE, RESET, AURORA_BLUE, RESET
-    );
-    println!(
-        "{}╰──────────────────────────────────────────────────────────────────────────╯{}",
-        AURORA_BLUE, RESET
-    );
+    let mut summary = vec![
+        String::new(),
+        format!("{}● Active Sessions:{} {}{}{}", BOLD, RESET, PULSE_CYAN, sessions.len(), RESET),
+        String::new(),
+        format!("{}📊 24-Hour Summary{}", BOLD, RESET),
+        format!("{}──────────────────────────────{}", DIM, RESET),
+        format!("   Sessions:  {:>8}", metrics.total_sessions),
+        format!("   Messages:  {:>8}", metrics.total_messages),
+        format!("   Cost:      {}{:>8}{}", COSMIC_VIOLET, humanize::cost(metrics.total_cost), RESET),
+        String::new(),
+    ];
+    if let Some(running) = coverage.as_ref().and_then(|c| c.running) {
+        let color = if running < 0.95 { SOLAR_AMBER } else { PULSE_CYAN };
+        summary.push(format!("   Monitored: {}{:>7.0}%{} {}of the last 7 days{}", color, running * 100.0, RESET, DIM, RESET));
+        summary.push(String::new());
+    }
+    println!("{}", table::panel("✦ Agent Monitor Status ✦", &summary, theme::profile()));
+
+    print_adapter_health(adapters.as_deref());
 
     // Sessions table
     if !sessions.is_empty() {
-        println!();
-        println!(
-            "{}                         ✦ Active Sessions ✦{}",
-            AURORA_BLUE, RESET
-        );
-        println!(
-            "{}╭───────────────────┬─────────────┬──────────┬──────────┬────────╮{}",
-            AURORA_BLUE, RESET
-        );
-        println!(
-            "{}│{} {}Project{}           {}│{} {}Type{}        {}│{} {}Messages{} {}│{} {}Duration{} {}│{} {}Status{} {}│{}",
-            AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET, BOLD, RESET,
-            AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET, BOLD, RESET,
-            AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET
-        );
-        println!(
-            "{}├───────────────────┼─────────────┼──────────┼──────────┼────────┤{}",
-            AURORA_BLUE, RESET
-        );
-
+        let mut sessions_table = table::Table::new(&["Project", "Type", "Messages", "Duration", "Status"])
+            .title("✦ Active Sessions ✦")
+            .max_width(0, 24)
+            .align(2, table::Align::Right)
+            .align(3, table::Align::Right)
+            .align(4, table::Align::Center);
         for session in &sessions {
-            let project = session.project_path.split('/').last().unwrap_or("—");
-            let project_display = if project.len() > 17 {
-                format!("{}…", &project[..16])
+            let status = if session.status == models::SessionStatus::Active {
+                table::Cell::colored("🟢", PULSE_CYAN)
             } else {
-                format!("{:<17}", project)
+                table::Cell::new("⚪")
             };
+            sessions_table.row(vec![
+                session.project_path.split('/').next_back().unwrap_or("—").into(),
+                session.agent_type.to_string().into(),
+                session.message_count.to_string().into(),
+                humanize::duration(session.duration_seconds).into(),
+                status,
+            ]);
+        }
 
-            let status = if session.status.to_string() == "active" {
-                format!("{}🟢{}", PULSE_CYAN, RESET)
-            } else {
-                "⚪".to_string()
-            };
+        println!();
+        println!("{}", sessions_table.render(theme::profile()));
+        print_context_warnings(&sessions);
+    }
+
+    if !no_animation {
+        println!("{}  ⋆    ✶     ★   ⋆  ✧  ★{}", DIM, RESET);
+    }
 
-            let duration = format_duration(session.duration_seconds);
+    Ok(())
+}
 
-            println!(
-                "{}│{} {:<17} {}│{} {:<11} {}│{} {:>8} {}│{} {:>8} {}│{} {:^6} {}│{}",
-                AURORA_BLUE, RESET, project_display,
-                AURORA_BLUE, RESET, session.agent_type.to_string(),
-                AURORA_BLUE, RESET, session.message_count,
-                AURORA_BLUE, RESET, duration,
-                AURORA_BLUE, RESET, status, AURORA_BLUE, RESET
-            );
+/// Flag sessions whose context window is nearly full, before the agent
+/// compacts and loses detail.
+fn print_context_warnings(sessions: &[models::Session]) {
+    let near_limit: Vec<_> = sessions.iter().filter(|s| s.context_near_limit()).collect();
+    if near_limit.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}⚠ Context near limit{}", SOLAR_AMBER, RESET);
+    for session in near_limit {
+        println!(
+            "  {} {:<20} {} / {} {}({:.0}%, compaction soon){}",
+            &session.id[..8],
+            session.project_path.split('/').next_back().unwrap_or("—"),
+            humanize::tokens(session.context_tokens.unwrap_or(0)),
+            humanize::tokens(models::context_window(session.model_id.as_deref())),
+            DIM,
+            session.context_utilization().unwrap_or(0.0) * 100.0,
+            RESET
+        );
+    }
+}
+
+/// List, start, stop, or restart adapters in the running daemon
+async fn manage_adapters(command: AdapterCommand, output: OutputFormat) -> Result<()> {
+    let config = Config::load_or_default()?;
+
+    let (request, action) = match &command {
+        AdapterCommand::List => (serde_json::json!({ "action": "get_adapters" }), None),
+        AdapterCommand::Start { name } => (adapter_control("start", name), Some(("Started", name))),
+        AdapterCommand::Stop { name } => (adapter_control("stop", name), Some(("Stopped", name))),
+        AdapterCommand::Restart { name } => (adapter_control("restart", name), Some(("Restarted", name))),
+    };
+
+    // Controlling adapters needs the write token, readable by the daemon's user
+    let token = std::fs::read_to_string(capabilities::token_path(&config.data_dir)).ok();
+    let sent = match (&action, token) {
+        (Some(_), Some(token)) => api::ipc_write_request(&config.socket_path, token.trim(), &request).await,
+        _ => api::ipc_request(&config.socket_path, &request).await,
+    };
+    let response = match sent {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("{}✗ Error:{} {}", NOVA_RED, RESET, e);
+            if !config.socket_path.exists() {
+                eprintln!("{}  Hint:{} Is the daemon running? Start it with 'agent-monitor daemon'.",
+                    AURORA_BLUE, RESET);
+            }
+            return Ok(());
         }
+    };
+
+    let adapters: Vec<adapters::AdapterHealth> =
+        serde_json::from_value(response.get("adapters").cloned().unwrap_or_default())?;
+    if output.print(&adapters)? {
+        return Ok(());
+    }
+
+    if let Some((verb, name)) = action {
+        println!("{}✓ {} {}{}", AURORA_BLUE, verb, name, RESET);
+    }
+    print_adapter_health(Some(&adapters));
+
+    Ok(())
+}
+
+fn adapter_control(command: &str, name: &str) -> serde_json::Value {
+    serde_json::json!({ "action": "adapter_control", "command": command, "name": name })
+}
+
+/// Print one line per adapter with its state, lag, and last error
+fn print_adapter_health(adapters: Option<&[adapters::AdapterHealth]>) {
+    println!();
+    let Some(adapters) = adapters else {
+        println!("  {}Adapters:{} {}daemon not running{}", BOLD, RESET, DIM, RESET);
+        return;
+    };
 
+    println!("  {}Adapters{}", BOLD, RESET);
+    for adapter in adapters {
+        let color = match adapter.state.as_str() {
+            "ok" => PULSE_CYAN,
+            "stale" | "stopped" => COSMIC_VIOLET,
+            _ => NOVA_RED,
+        };
+        let lag = adapter
+            .lag_seconds
+            .map(|s| format!("scanned {} ago", humanize::duration(s as f64)))
+            .unwrap_or_else(|| "never scanned".to_string());
         println!(
-            "{}╰───────────────────┴─────────────┴──────────┴──────────┴────────╯{}",
-            AURORA_BLUE, RESET
+            "    {}●{} {:<12} {}{:<9}{} {}{}  {} events  {} errors  {} sessions in memory{}",
+            color, RESET, adapter.name, color, adapter.state, RESET,
+            DIM, lag, adapter.events_processed, adapter.error_count, adapter.sessions_in_memory, RESET
         );
+        let watching: Vec<&adapters::WatchedPath> = adapter.watched_paths.iter().filter(|w| w.watching).collect();
+        if adapter.watches_evicted > 0 {
+            println!(
+                "      {}{} directories watched, {} dropped as least recently active{}",
+                DIM, watching.len(), adapter.watches_evicted, RESET
+            );
+        }
+        let mut polled: Vec<(&str, Vec<&str>)> = Vec::new();
+        for watched in &watching {
+            let Some(ref reason) = watched.polled else {
+                continue;
+            };
+            match polled.iter_mut().find(|(r, _)| r == reason) {
+                Some((_, paths)) => paths.push(&watched.path),
+                None => polled.push((reason, vec![&watched.path])),
+            }
+        }
+        for (reason, paths) in polled {
+            let what = match paths.as_slice() {
+                [path] => path.to_string(),
+                _ => format!("{} directories", paths.len()),
+            };
+            println!("      {}↻ polling {} ({}){}", DIM, what, reason, RESET);
+        }
+        for watched in adapter.watched_paths.iter().filter(|w| !w.watching) {
+            let retry = watched
+                .retry_at
+                .map(|at| {
+                    let wait = (at - chrono::Utc::now()).num_seconds().max(0);
+                    format!(" (attempt {}, retrying in {})", watched.failures + 1, humanize::duration(wait as f64))
+                })
+                .unwrap_or_default();
+            if watched.exists {
+                println!(
+                    "      {}✗ not watching {}{}{}{}",
+                    DIM, watched.path,
+                    watched.error.as_ref().map(|e| format!(": {}", e)).unwrap_or_default(),
+                    retry, RESET
+                );
+            } else {
+                println!("      {}… waiting for {} to appear{}{}", DIM, watched.path, retry, RESET);
+            }
+        }
+        if let Some(ref error) = adapter.last_error {
+            println!("      {}last error: {}{}", DIM, error, RESET);
+        }
     }
+}
 
-    if !no_animation {
-        println!("{}  ⋆    ✶     ★   ⋆  ✧  ★{}", DIM, RESET);
+async fn manage_memory(command: MemoryCommand) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let memory = analytics::MemoryStore::new(storage);
+
+    match command {
+        MemoryCommand::Get { key, output } => {
+            let Some(entry) = memory.read(&key).await? else {
+                anyhow::bail!("No memory entry '{}'", key);
+            };
+            if !output.format().print(&entry)? {
+                match entry.value {
+                    serde_json::Value::String(s) => println!("{}", s),
+                    value => println!("{}", serde_json::to_string_pretty(&value)?),
+                }
+            }
+        }
+        MemoryCommand::Set { key, value, mut tags, session, project } => {
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            if let Some(project) = project {
+                let project = std::fs::canonicalize(&project).unwrap_or(project);
+                tags.push(context::project_tag(&project.to_string_lossy()));
+            }
+            memory.write(&key, value, session.as_deref(), tags).await?;
+            println!("{}✓ Saved {}{}", AURORA_BLUE, key, RESET);
+        }
+        MemoryCommand::List { tag, output } => {
+            let entries = memory.list(tag.as_deref()).await?;
+            if output.format().print(&entries)? {
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("{}✦ No memory entries{}", COSMIC_VIOLET, RESET);
+                return Ok(());
+            }
+            let mut memory_table = table::Table::new(&["Key", "Tags", "Value"])
+                .max_width(0, 32)
+                .max_width(1, 32)
+                .max_width(2, 60);
+            for entry in &entries {
+                memory_table.row(vec![
+                    entry.key.as_str().into(),
+                    table::Cell::colored(entry.tags.join(", "), COSMIC_VIOLET),
+                    table::Cell::colored(entry.value.to_string(), DIM),
+                ]);
+            }
+            println!("{}", memory_table.render(theme::profile()));
+        }
+        MemoryCommand::Delete { key } => {
+            if !memory.delete(&key).await? {
+                anyhow::bail!("No memory entry '{}'", key);
+            }
+            println!("{}✓ Deleted {}{}", AURORA_BLUE, key, RESET);
+        }
     }
 
     Ok(())
 }
 
-async fn list_sessions(limit: usize, all: bool, json_output: bool) -> Result<()> {
-    let config = Config::default();
-    let storage = storage::Storage::new(&config.db_path).await?;
+/// Resolve a full session ID or a unique prefix of one.
+async fn resolve_session_id(storage: &storage::Storage, id: &str) -> Result<String> {
+    if storage.get_session(id).await?.is_some() {
+        return Ok(id.to_string());
+    }
+    let matches: Vec<String> = storage
+        .get_all_sessions(100_000)
+        .await?
+        .into_iter()
+        .filter(|s| s.id.starts_with(id))
+        .map(|s| s.id)
+        .collect();
+    match matches.as_slice() {
+        [single] => Ok(single.clone()),
+        [] => anyhow::bail!("No session matching '{}'", id),
+        _ => anyhow::bail!("'{}' matches {} sessions; use more of the ID", id, matches.len()),
+    }
+}
 
-    let sessions = if all {
-        storage.get_recent_sessions(168, limit).await?
-    } else {
-        storage.get_active_sessions(limit).await?
+async fn manage_tags(command: TagCommand) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+
+    let normalize = |tag: &str| {
+        models::normalize_tag(tag).ok_or_else(|| {
+            anyhow::anyhow!("Invalid tag '{}' (letters, digits and -_.:/ only, up to {} characters)", tag, models::MAX_TAG_LEN)
+        })
+    };
+
+    match command {
+        TagCommand::Add { session, tags } => {
+            let session_id = resolve_session_id(&storage, &session).await?;
+            for tag in &tags {
+                let tag = normalize(tag)?;
+                storage
+                    .add_session_tag(&models::SessionTag::new(&session_id, &tag, "manual"))
+                    .await?;
+                println!("{}✓ Tagged {} {}#{}{}", AURORA_BLUE, &session_id[..8.min(session_id.len())], COSMIC_VIOLET, tag, RESET);
+            }
+        }
+        TagCommand::Remove { session, tags } => {
+            let session_id = resolve_session_id(&storage, &session).await?;
+            for tag in &tags {
+                let tag = normalize(tag)?;
+                if !storage.remove_session_tag(&session_id, &tag).await? {
+                    anyhow::bail!("Session {} is not tagged '{}'", session_id, tag);
+                }
+                println!("{}✓ Removed {}#{}{}", AURORA_BLUE, COSMIC_VIOLET, tag, RESET);
+            }
+        }
+        TagCommand::List { session: Some(session), output, .. } => {
+            let session_id = resolve_session_id(&storage, &session).await?;
+            let tags = storage.list_session_tags(Some(&session_id), None).await?;
+            if output.format().print(&tags)? {
+                return Ok(());
+            }
+            if tags.is_empty() {
+                println!("{}✦ Session has no tags{}", COSMIC_VIOLET, RESET);
+                return Ok(());
+            }
+            for tag in &tags {
+                println!("  {}#{}{}  {}{}{}", COSMIC_VIOLET, tag.tag, RESET, DIM, tag.source, RESET);
+            }
+        }
+        TagCommand::List { session: None, days, output } => {
+            let sessions = storage.get_recent_sessions(days * 24, 100_000).await?;
+            let usage = report::tag_usage(&sessions, &storage.tags_by_session().await?);
+            if output.format().print(&usage)? {
+                return Ok(());
+            }
+            if usage.is_empty() {
+                println!("{}✦ No tagged sessions in the last {} days{}", COSMIC_VIOLET, days, RESET);
+                return Ok(());
+            }
+            let mut tags_table = table::Table::new(&["Tag", "Sessions", "Tokens", "Cost"])
+                .align(1, table::Align::Right)
+                .align(2, table::Align::Right)
+                .align(3, table::Align::Right);
+            for tag in &usage {
+                tags_table.row(vec![
+                    table::Cell::colored(format!("#{}", tag.tag), COSMIC_VIOLET),
+                    tag.sessions.to_string().into(),
+                    humanize::tokens(tag.tokens).into(),
+                    humanize::cost(tag.cost).into(),
+                ]);
+            }
+            println!("{}", tags_table.render(theme::profile()));
+        }
+    }
+
+    Ok(())
+}
+
+async fn list_sessions(
+    limit: usize,
+    all: bool,
+    tag: Option<&str>,
+    group_by: Option<grouping::GroupBy>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+
+    let mut sessions = match tag {
+        // Filter before the limit so it counts tagged sessions
+        Some(tag) => {
+            storage.initialize().await?;
+            let tagged = storage.sessions_tagged(tag).await?;
+            let sessions = if all {
+                storage.get_recent_sessions(168, 100_000).await?
+            } else {
+                storage.get_active_sessions(100_000).await?
+            };
+            sessions.into_iter().filter(|s| tagged.contains(&s.id)).collect()
+        }
+        None if all => storage.get_recent_sessions(168, limit).await?,
+        None => storage.get_active_sessions(limit).await?,
     };
+    sessions.truncate(limit);
 
-    if json_output {
-        println!("{}", serde_json::to_string_pretty(&sessions)?);
+    let groups = group_by.map(|by| grouping::group(sessions.clone(), by));
+    let printed = match groups {
+        Some(ref groups) => output.print(groups)?,
+        None => output.print(&sessions)?,
+    };
+    if printed {
         return Ok(());
     }
 
@@ -523,142 +1646,639 @@ async fn list_sessions(limit: usize, all: bool, json_output: bool) -> Result<()>
     }
 
     println!("{}  ✦   ⋆  ★    ✧  ✶    ★   ⋆{}", DIM, RESET);
+    match groups {
+        Some(groups) => {
+            for group in groups {
+                let title = format!("✦ {} ✦", group.heading());
+                println!("{}", sessions_table(&group.sessions, &title).render(theme::profile()));
+            }
+        }
+        None => println!("{}", sessions_table(&sessions, "✦ Sessions ✦").render(theme::profile())),
+    }
+    println!("{}  ⋆    ✶     ★   ⋆  ✧  ★{}", DIM, RESET);
+    print_context_warnings(&sessions);
+
+    let summarized: Vec<_> = sessions.iter().filter(|s| s.summary.is_some()).collect();
+    if !summarized.is_empty() {
+        println!();
+        println!("{}✦ Summaries{}", AURORA_BLUE, RESET);
+        for session in summarized {
+            println!("  {}{}{}", BOLD, &session.id[..8], RESET);
+            for line in session.summary.as_deref().unwrap_or_default().lines() {
+                println!("    {}{}{}", DIM, line, RESET);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn sessions_table(sessions: &[models::Session], title: &str) -> table::Table {
+    let mut sessions_table =
+        table::Table::new(&["ID", "Project", "Type", "Status", "Messages", "Tokens", "Cost", "Last Active"])
+            .title(title)
+            .max_width(1, 24)
+            .align(4, table::Align::Right)
+            .align(5, table::Align::Right)
+            .align(6, table::Align::Right);
+    for session in sessions {
+        let status = match session.status {
+            models::SessionStatus::Active => table::Cell::colored("● active", PULSE_CYAN),
+            models::SessionStatus::Completed => table::Cell::colored("✓ done", COSMIC_VIOLET),
+            models::SessionStatus::Crashed => table::Cell::colored("✗ crash", NOVA_RED),
+            status => table::Cell::new(format!("○ {}", status)),
+        };
+        sessions_table.row(vec![
+            session.id[..8.min(session.id.len())].into(),
+            session.project_path.split('/').next_back().unwrap_or("—").into(),
+            session.agent_type.to_string().into(),
+            status,
+            session.message_count.to_string().into(),
+            humanize::tokens(session.tokens_input + session.tokens_output).into(),
+            humanize::cost(session.estimated_cost).into(),
+            timefmt::datetime(session.last_activity_at).into(),
+        ]);
+    }
+    sessions_table
+}
+
+async fn show_prompt_efficiency(
+    days: i64,
+    by: prompts::PromptGroup,
+    user: Option<&str>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let groups = prompts::efficiency(&storage, days, by, user).await?;
+
+    if output.print(&groups)? {
+        return Ok(());
+    }
+    if groups.is_empty() {
+        println!("{}✦ No prompts in the last {} days{}", COSMIC_VIOLET, days, RESET);
+        return Ok(());
+    }
+
+    let first = match by {
+        prompts::PromptGroup::Project => "Project",
+        prompts::PromptGroup::User => "User",
+    };
+    let mut efficiency_table = table::Table::new(&[
+        first,
+        "Sessions",
+        "Prompts",
+        "Words/Prompt",
+        "Clarifications/Task",
+        "Edits/Prompt",
+        "Cost/Prompt",
+        "Clarifying",
+    ])
+    .title(&format!("✦ Prompt Efficiency · {} days ✦", days))
+    .max_width(0, 28);
+    for column in 1..8 {
+        efficiency_table = efficiency_table.align(column, table::Align::Right);
+    }
+    for group in &groups {
+        let name = match by {
+            prompts::PromptGroup::Project => group.key.rsplit('/').find(|p| !p.is_empty()).unwrap_or("—"),
+            prompts::PromptGroup::User if group.key.is_empty() => "(none)",
+            prompts::PromptGroup::User => group.key.as_str(),
+        };
+        let clarifications = format!("{:.2}", group.clarifications_per_task);
+        efficiency_table.row(vec![
+            name.into(),
+            group.stats.sessions.to_string().into(),
+            group.stats.prompts.to_string().into(),
+            format!("{:.0}", group.words_per_prompt).into(),
+            if group.clarifications_per_task >= 0.5 {
+                table::Cell::colored(clarifications, SOLAR_AMBER)
+            } else {
+                clarifications.into()
+            },
+            format!("{:.1}", group.edits_per_prompt).into(),
+            humanize::cost(group.cost_per_prompt).into(),
+            humanize::cost(group.stats.clarification_cost).into(),
+        ]);
+    }
+    println!("{}", efficiency_table.render(theme::profile()));
     println!(
-        "{}                              ✦ Sessions ✦{}",
-        AURORA_BLUE, RESET
+        "{}  Clarifying: spend on prompts the agent only answered before being asked again{}",
+        DIM, RESET
     );
+    Ok(())
+}
+
+async fn show_report(
+    days: i64,
+    output: OutputFormat,
+    html: bool,
+    email: bool,
+    tag: Option<&str>,
+    user: Option<&str>,
+    compare: Option<&str>,
+) -> Result<()> {
+    let offset = compare.map(|c| report::parse_offset(c, days)).transpose()?;
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+
+    if email {
+        let mut digest_config = config.email_digest.clone();
+        digest_config.days = days;
+        let report = digest::send_digest(&digest_config, &storage).await?;
+        println!(
+            "{}✓ Digest sent to {} ({} sessions, {}){}",
+            PULSE_CYAN,
+            digest_config.recipients.join(", "),
+            report.sessions,
+            humanize::cost(report.cost),
+            RESET
+        );
+        return Ok(());
+    }
+
+    let mut report = report::build_report(&storage, days, tag, user).await?;
+    let forecast = forecast::build_forecast(&storage, &config.forecast).await?;
+    report.forecast = Some(forecast.clone());
+    if let Some(offset) = offset {
+        report.comparison = Some(report::compare(&storage, &report, offset, tag, user).await?);
+    }
+    if output.print(&report)? {
+        return Ok(());
+    }
+    if html {
+        println!("{}", report.to_html());
+        return Ok(());
+    }
+
     println!(
-        "{}╭──────────┬──────────┬─────────────┬──────────┬──────────┬─────────┬─────────╮{}",
+        "{}╭──────────────────────── ✦ Report ✦ ────────────────────────╮{}",
         AURORA_BLUE, RESET
     );
     println!(
-        "{}│{} {}ID{}       {}│{} {}Project{}  {}│{} {}Type{}        {}│{} {}Status{}   {}│{} {}Messages{} {}│{} {}Tokens{}  {}│{} {}Cost{}    {}│{}",
-        AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET, BOLD, RESET,
-        AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET, BOLD, RESET,
-        AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET, BOLD, RESET,
-        AURORA_BLUE, RESET, BOLD, RESET, AURORA_BLUE, RESET
+        "{}│{}  period:      last {} days ({} – {})",
+        AURORA_BLUE, RESET, days,
+        timefmt::date(report.period_start),
+        timefmt::date(report.period_end)
+    );
+    if let Some(tag) = tag {
+        println!("{}│{}  tag:         {}#{}{}", AURORA_BLUE, RESET, COSMIC_VIOLET, tag, RESET);
+    }
+    if let Some(user) = user {
+        println!("{}│{}  user:        {}{}{}", AURORA_BLUE, RESET, PULSE_CYAN, user, RESET);
+    }
+    println!("{}│{}  spend:       {}", AURORA_BLUE, RESET, humanize::cost(report.cost));
+    println!(
+        "{}│{}  sessions:    {} ({} messages, {} tool calls, {} tokens)",
+        AURORA_BLUE, RESET, report.sessions, report.messages, report.tool_calls,
+        humanize::tokens(report.tokens)
+    );
+    println!(
+        "{}│{}  success:     {}",
+        AURORA_BLUE, RESET,
+        report.success_rate.map(|r| format!("{:.0}%", r * 100.0)).unwrap_or_else(|| "—".to_string())
+    );
+
+    if let Some(ref comparison) = report.comparison {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!(
+            "{}│{}  {}Compared with{} {}({} – {}){}",
+            AURORA_BLUE, RESET, BOLD, RESET, DIM,
+            timefmt::date(comparison.previous_start),
+            timefmt::date(comparison.previous_end),
+            RESET
+        );
+        for (name, delta) in comparison.highlights() {
+            let value = |v: f64| match (name, delta.unit) {
+                (_, report::DeltaUnit::Points) => format!("{:.0}%", v * 100.0),
+                ("cost", _) => humanize::cost(v),
+                ("tokens", _) => humanize::tokens(v as i64),
+                _ => format!("{:.0}", v),
+            };
+            let color = match delta.trend {
+                report::Trend::Up => SOLAR_AMBER,
+                report::Trend::Down => PULSE_CYAN,
+                report::Trend::Flat => DIM,
+            };
+            println!(
+                "{}│{}    {:<14} {}{} {:<6}{} {}{} → {}{}",
+                AURORA_BLUE, RESET, name, color, delta.arrow(), delta.label(), RESET,
+                DIM, value(delta.previous), value(delta.current), RESET
+            );
+        }
+    }
+
+    println!("{}│{}", AURORA_BLUE, RESET);
+    println!("{}│{}  {}Forecast{} {}(last {} days){}", AURORA_BLUE, RESET, BOLD, RESET, DIM, forecast.window_days, RESET);
+    println!(
+        "{}│{}    burn rate:   {}/day, {} so far this month",
+        AURORA_BLUE, RESET, humanize::cost(forecast.total.daily_burn), humanize::cost(forecast.total.month_to_date)
     );
     println!(
-        "{}├──────────┼──────────┼─────────────┼──────────┼──────────┼─────────┼─────────┤{}",
+        "{}│{}    projected:   {} by month end{}",
+        AURORA_BLUE, RESET, humanize::cost(forecast.total.projected_month),
+        budget_note(&forecast.total)
+    );
+    for project in forecast.projects.iter().filter(|p| p.projection.budget.is_some()) {
+        println!(
+            "{}│{}    {:<28} {:>8}{}",
+            AURORA_BLUE, RESET,
+            project.project_path.split('/').next_back().unwrap_or("—"),
+            humanize::cost(project.projection.projected_month),
+            budget_note(&project.projection)
+        );
+    }
+
+    if !report.top_projects.is_empty() {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Top projects{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for project in &report.top_projects {
+            println!(
+                "{}│{}    {:<28} {:>3} sessions  {:>8}",
+                AURORA_BLUE, RESET,
+                project.project_path.split('/').next_back().unwrap_or("—"),
+                project.sessions,
+                humanize::cost(project.cost)
+            );
+        }
+    }
+
+    if report.users.len() > 1 {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Spend by user{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for usage in &report.users {
+            let user = if usage.user.is_empty() { "—" } else { usage.user.as_str() };
+            println!(
+                "{}│{}    {}{:<28}{} {:>3} sessions  {:>8}",
+                AURORA_BLUE, RESET, PULSE_CYAN, user, RESET,
+                usage.sessions,
+                humanize::cost(usage.cost)
+            );
+        }
+    }
+
+    if !report.tags.is_empty() {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Spend by tag{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for usage in &report.tags {
+            println!(
+                "{}│{}    {}#{:<27}{} {:>3} sessions  {:>8}",
+                AURORA_BLUE, RESET, COSMIC_VIOLET, usage.tag, RESET,
+                usage.sessions,
+                humanize::cost(usage.cost)
+            );
+        }
+    }
+
+    if report.branches.iter().any(|b| !b.branch.is_empty()) {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Spend by branch{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for usage in &report.branches {
+            let branch = if usage.branch.is_empty() { "—" } else { usage.branch.as_str() };
+            println!(
+                "{}│{}    {:<14} {}{:<13}{} {:>3} sessions  {:>8}",
+                AURORA_BLUE, RESET,
+                usage.project_path.split('/').next_back().unwrap_or("—"),
+                PULSE_CYAN, branch, RESET,
+                usage.sessions,
+                humanize::cost(usage.cost)
+            );
+        }
+    }
+
+    if !report.slash_commands.is_empty() {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Slash commands{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for command in &report.slash_commands {
+            println!(
+                "{}│{}    {}{:<28}{} {:>4} uses  {:>3} sessions{}",
+                AURORA_BLUE, RESET, PULSE_CYAN, command.command, RESET,
+                command.uses,
+                command.sessions,
+                if command.builtin { "" } else { "  (custom)" }
+            );
+        }
+    }
+
+    if !report.notable_errors.is_empty() {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Notable errors{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for error in &report.notable_errors {
+            let message: String = error.message.chars().take(50).collect();
+            println!("{}│{}    {}×{:<3}{} {}", AURORA_BLUE, RESET, COSMIC_VIOLET, error.count, RESET, message);
+        }
+    }
+
+    if !report.longest_sessions.is_empty() {
+        println!("{}│{}", AURORA_BLUE, RESET);
+        println!("{}│{}  {}Longest sessions{}", AURORA_BLUE, RESET, BOLD, RESET);
+        for session in &report.longest_sessions {
+            println!(
+                "{}│{}    {} {:<20} {:>8}  {}{}{}",
+                AURORA_BLUE, RESET,
+                &session.session_id[..8],
+                session.project_path.split('/').next_back().unwrap_or("—"),
+                humanize::duration(session.duration_seconds),
+                DIM, humanize::cost(session.cost), RESET
+            );
+        }
+    }
+
+    println!(
+        "{}╰─────────────────────────────────────────────────────────────╯{}",
         AURORA_BLUE, RESET
     );
 
-    for session in &sessions {
-        let project = session.project_path.split('/').last().unwrap_or("—");
-        let project_display = if project.len() > 8 {
-            format!("{}…", &project[..7])
-        } else {
-            format!("{:<8}", project)
-        };
+    Ok(())
+}
+
+/// Budget and days left for a forecast line, empty without a budget.
+fn budget_note(projection: &forecast::Projection) -> String {
+    let Some(budget) = projection.budget else {
+        return String::new();
+    };
+    let color = if projection.over_budget() { COSMIC_VIOLET } else { DIM };
+    match projection.days_until_exhausted {
+        Some(days) if days <= 0.0 => format!("  {}budget {} exhausted{}", color, humanize::cost(budget), RESET),
+        Some(days) => format!("  {}budget {}, {:.0} days left{}", color, humanize::cost(budget), days, RESET),
+        None => format!("  {}budget {}{}", DIM, humanize::cost(budget), RESET),
+    }
+}
+
+async fn send_test_notification(message: &str, channel: Option<&str>) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let notifier = notifications::Notifier::new(&config.notification_channels);
+    notifier.send(channel, "Agent Monitor", message).await?;
+    println!(
+        "{}✓ Sent to {}{}",
+        PULSE_CYAN,
+        channel.unwrap_or("desktop"),
+        RESET
+    );
+    Ok(())
+}
+
+/// `--profile` for commands Claude Code runs, so they reach this profile's daemon.
+fn profile_arg() -> String {
+    config::profile().map(|p| format!(" --profile {}", p)).unwrap_or_default()
+}
+
+async fn install_hooks() -> Result<()> {
+    println!("{}  ✦   ⋆  ★    ✧  ✶{}", DIM, RESET);
+    println!("  {}✦ Installing Claude Code Hooks...{}", AURORA_BLUE, RESET);
+
+    let setup = hooks::HookSetup::current(profile_arg())?;
+    for event in HOOK_EVENTS {
+        hooks::write_script(&setup.hooks_dir, event, &setup.exe, &setup.profile_arg)?;
+        println!("  {}✓{} Installed {}", PULSE_CYAN, RESET, event);
+    }
+
+    println!("  {}✦ Hooks installed successfully!{}", PULSE_CYAN, RESET);
+    let unregistered = setup.inspect()?.iter().filter(|c| !c.registered).count();
+    if unregistered > 0 {
+        println!(
+            "  {}⋆{} {} hooks are not in {}; run 'agent-monitor hooks status --repair'",
+            SOLAR_AMBER, RESET, unregistered, setup.settings_path.display()
+        );
+    }
+    println!("{}  ⋆    ✶     ★   ⋆{}", DIM, RESET);
+
+    Ok(())
+}
+
+async fn hooks_status(repair: bool, format: OutputFormat) -> Result<()> {
+    let setup = hooks::HookSetup::current(profile_arg())?;
+    let mut checks = setup.inspect()?;
+    let repaired = if repair { setup.repair(&checks)? } else { Vec::new() };
+    if !repaired.is_empty() {
+        checks = setup.inspect()?;
+    }
+    let probe = hooks::probe(&setup.hooks_dir).await;
+    let healthy = checks.iter().all(hooks::HookCheck::is_ok) && probe.ok;
+
+    if format.print(&serde_json::json!({
+        "hooks": checks,
+        "probe": probe,
+        "repaired": repaired,
+        "healthy": healthy,
+    }))? {
+        return Ok(());
+    }
+
+    let mut table = table::Table::new(&["Event", "Script", "Settings", "Problems"])
+        .title("✦ Claude Code Hooks ✦")
+        .max_width(3, 60);
+    for check in &checks {
+        let mark = |ok: bool| if ok { table::Cell::colored("✓", PULSE_CYAN) } else { table::Cell::colored("✗", NOVA_RED) };
+        table.row(vec![
+            check.event.clone().into(),
+            mark(check.script_ok),
+            mark(check.registered),
+            table::Cell::colored(check.problems.join("; "), SOLAR_AMBER),
+        ]);
+    }
+    println!("{}", table.render(theme::profile()));
+
+    for event in &repaired {
+        println!("  {}✓{} Repaired {}", PULSE_CYAN, RESET, event);
+    }
+    let (color, mark) = if probe.ok { (PULSE_CYAN, "✓") } else { (NOVA_RED, "✗") };
+    println!("  {}{}{} Round trip: {} {}({}ms){}", color, mark, RESET, probe.detail, DIM, probe.millis, RESET);
+    if !healthy && !repair && checks.iter().any(|c| !c.is_ok()) {
+        println!("  {}⋆{} Run 'agent-monitor hooks status --repair' to fix them", SOLAR_AMBER, RESET);
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; anything but yes is no.
+fn confirm(question: &str) -> bool {
+    print!("  {}?{} {} [y/N] ", SOLAR_AMBER, RESET, question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    let _ = io::stdin().read_line(&mut answer);
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
 
-        let status = match session.status.to_string().as_str() {
-            "active" => format!("{}● active{}", PULSE_CYAN, RESET),
-            "completed" => format!("{}✓ done{}", COSMIC_VIOLET, RESET),
-            "crashed" => format!("{}✗ crash{}", "\x1b[38;5;196m", RESET),
-            s => format!("○ {}", s),
-        };
+async fn run_uninstall(data: bool, config_dir: bool, yes: bool, dry_run: bool) -> Result<()> {
+    use std::io::IsTerminal;
+    use uninstall::ResidueKind;
 
-        let tokens = format_tokens(session.tokens_input + session.tokens_output);
-        let cost = format!("${:.2}", session.estimated_cost);
+    let config = Config::load_or_default().unwrap_or_default();
+    let setup = hooks::HookSetup::current(profile_arg())?;
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let mut residue = uninstall::plan(&config, &setup, &home)?;
 
+    // Removing a running daemon's socket would leave it unreachable
+    let has_service = residue.iter().any(|r| r.kind == ResidueKind::Service);
+    let running = api::ipc_request(&config.socket_path, &serde_json::json!({ "action": "get_adapters" })).await.is_ok();
+    if running && !has_service {
         println!(
-            "{}│{} {:<8} {}│{} {:<8} {}│{} {:<11} {}│{} {:<8} {}│{} {:>8} {}│{} {:>7} {}│{} {:>7} {}│{}",
-            AURORA_BLUE, RESET, &session.id[..8],
-            AURORA_BLUE, RESET, project_display,
-            AURORA_BLUE, RESET, session.agent_type.to_string(),
-            AURORA_BLUE, RESET, status,
-            AURORA_BLUE, RESET, session.message_count,
-            AURORA_BLUE, RESET, tokens,
-            AURORA_BLUE, RESET, cost, AURORA_BLUE, RESET
+            "  {}⋆{} The daemon is still running at {}; stop it and run uninstall again to remove its socket",
+            SOLAR_AMBER, RESET, config.socket_path.display()
         );
+        residue.retain(|r| r.kind != ResidueKind::Socket);
     }
 
-    println!(
-        "{}╰──────────┴──────────┴─────────────┴──────────┴──────────┴─────────┴─────────╯{}",
-        AURORA_BLUE, RESET
-    );
-    println!("{}  ⋆    ✶     ★   ⋆  ✧  ★{}", DIM, RESET);
+    if residue.is_empty() {
+        println!("{}Nothing of agent-monitor is left to remove{}", DIM, RESET);
+        return Ok(());
+    }
+
+    let interactive = io::stdin().is_terminal() && !yes && !dry_run;
+    let mut kept = Vec::new();
+    let mut removed = 0;
+    for item in residue.iter_mut() {
+        if item.kind.is_optional() {
+            let asked_for = if item.kind == ResidueKind::Data { data } else { config_dir };
+            let wanted = if dry_run {
+                asked_for
+            } else if interactive {
+                confirm(&format!("Delete {} ({})? This cannot be undone", item.path.display(), item.detail))
+            } else {
+                asked_for && yes
+            };
+            if !wanted {
+                kept.push(item);
+                continue;
+            }
+        }
+        // Archives written elsewhere can't be restored without the key
+        let key = storage::key_path(&config.data_dir);
+        if item.kind == ResidueKind::Data && item.entries.contains(&key) {
+            let keep = match uninstall::archives_needing_key(&config).await {
+                Ok(0) => None,
+                Ok(locked) => {
+                    let reason = format!("{} archived sessions outside {} are encrypted with it", locked, config.data_dir.display());
+                    let confirmed = interactive && confirm(&format!("{}; delete {} anyway?", reason, key.display()));
+                    (!confirmed).then_some(reason)
+                }
+                Err(e) => Some(format!("could not check for archives encrypted with it: {}", e)),
+            };
+            if let Some(reason) = keep {
+                println!("  {}⋆{} Keeping {}: {}", SOLAR_AMBER, RESET, key.display(), reason);
+                item.entries.retain(|e| *e != key);
+            }
+        }
+        if dry_run {
+            println!("  {}⋆{} Would remove {} {}{}{}", AURORA_BLUE, RESET, item.detail, DIM, item.path.display(), RESET);
+            continue;
+        }
+        match uninstall::remove(item) {
+            Ok(()) => {
+                removed += 1;
+                println!("  {}✓{} Removed {} {}{}{}", PULSE_CYAN, RESET, item.detail, DIM, item.path.display(), RESET);
+            }
+            Err(e) => println!("  {}✗{} Could not remove {}: {}", NOVA_RED, RESET, item.path.display(), e),
+        }
+    }
 
+    for item in kept {
+        println!("  {}⋆{} Kept {} {}{}{}", SOLAR_AMBER, RESET, item.detail, DIM, item.path.display(), RESET);
+    }
+    if !interactive && !dry_run && !yes && (data || config_dir) {
+        println!("  {}⋆{} Pass --yes to delete data or config without a terminal to confirm on", SOLAR_AMBER, RESET);
+    }
+    if !dry_run {
+        println!("{}✦ Removed {} items.{} The binary itself is {}", PULSE_CYAN, removed, RESET, setup.exe.display());
+    }
     Ok(())
 }
 
-async fn install_hooks() -> Result<()> {
-    println!("{}  ✦   ⋆  ★    ✧  ✶{}", DIM, RESET);
-    println!("  {}✦ Installing Claude Code Hooks...{}", AURORA_BLUE, RESET);
+/// Print the statusline for the session described on stdin. Failures fall
+/// back to an unmonitored line so Claude Code always has something to show.
+async fn print_statusline() {
+    let mut raw = String::new();
+    let _ = io::Read::read_to_string(&mut io::stdin(), &mut raw);
+    let input = statusline::StatusInput::parse(&raw);
 
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let hooks_dir = format!("{}/.claude/hooks", home);
+    let session = statusline_session(&input).await.unwrap_or(None);
 
-    // Create hooks directory
-    std::fs::create_dir_all(&hooks_dir)?;
+    println!(
+        "{}✦{} {}",
+        AURORA_BLUE,
+        RESET,
+        statusline::render(&input, session.as_ref(), Utc::now())
+    );
+}
 
-    // Get the path to our binary
-    let exe_path = std::env::current_exe()?;
-    let exe_str = exe_path.to_string_lossy();
-
-    // Hook events to install
-    let hook_events = [
-        "SessionStart",
-        "PreToolUse",
-        "PostToolUse",
-        "SubagentStop",
-    ];
+async fn statusline_session(input: &statusline::StatusInput) -> Result<Option<models::Session>> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        return Ok(None);
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    statusline::find_session(&storage, input).await
+}
 
-    for event in hook_events {
-        let hook_file = format!("{}/{}.sh", hooks_dir, event);
-        let hook_content = format!(
-            r#"#!/bin/bash
-# Agent Monitor hook for {}
-"{}" hook {} < /dev/stdin
-"#,
-            event, exe_str, event
-        );
+fn install_statusline(force: bool) -> Result<()> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    let settings_path = PathBuf::from(format!("{}/.claude/settings.json", home));
 
-        std::fs::write(&hook_file, hook_content)?;
+    let mut settings: serde_json::Value = if settings_path.exists() {
+        serde_json::from_str(&std::fs::read_to_string(&settings_path)?)?
+    } else {
+        serde_json::json!({})
+    };
 
-        // Make executable
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = std::fs::metadata(&hook_file)?.permissions();
-            perms.set_mode(0o755);
-            std::fs::set_permissions(&hook_file, perms)?;
-        }
+    let exe_path = std::env::current_exe()?;
+    let command = format!("\"{}\"{} claude-statusline", exe_path.to_string_lossy(), profile_arg());
+    let previous = statusline::install(&mut settings, &command, force)?;
 
-        println!("  {}✓{} Installed {}", PULSE_CYAN, RESET, event);
+    if let Some(parent) = settings_path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    std::fs::write(&settings_path, serde_json::to_string_pretty(&settings)? + "\n")?;
 
-    println!("  {}✦ Hooks installed successfully!{}", PULSE_CYAN, RESET);
-    println!("{}  ⋆    ✶     ★   ⋆{}", DIM, RESET);
-
+    if let Some(previous) = previous {
+        println!("  {}⋆{} Replaced statusLine: {}{}{}", SOLAR_AMBER, RESET, DIM, previous, RESET);
+    }
+    println!(
+        "  {}✓{} Statusline installed in {}",
+        PULSE_CYAN,
+        RESET,
+        settings_path.display()
+    );
     Ok(())
 }
 
-async fn manage_config(show: bool, init: bool) -> Result<()> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let config_path = format!("{}/.config/agent-monitor/config.json", home);
+async fn manage_config(show: bool, init: bool, output: OutputFormat) -> Result<()> {
+    let config_path = Config::user_config_path();
 
     if init {
         let config = Config::default();
-        let config_dir = format!("{}/.config/agent-monitor", home);
-        std::fs::create_dir_all(&config_dir)?;
-        config.save(&config_path)?;
-        println!("{}✦ Configuration created at {}{}", PULSE_CYAN, config_path, RESET);
+        if let Some(dir) = config_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        config.save(&config_path.to_string_lossy())?;
+        println!("{}✦ Configuration created at {}{}", PULSE_CYAN, config_path.display(), RESET);
         return Ok(());
     }
 
     if show || !init {
-        let config = if std::path::Path::new(&config_path).exists() {
-            Config::load(&config_path)?
-        } else {
+        let exists = config_path.exists();
+        let config = if exists { Config::load(&config_path.to_string_lossy())? } else { Config::default() };
+
+        let mut redacted = config.clone();
+        redacted.database_url = config.redacted_database_url();
+        if output.print(&redacted)? {
+            return Ok(());
+        }
+        if !exists {
             println!("{}✦ No config file found, showing defaults{}", COSMIC_VIOLET, RESET);
-            Config::default()
-        };
+        }
 
         println!(
             "{}╭────────────────────── ✦ Configuration ✦ ──────────────────────╮{}",
             AURORA_BLUE, RESET
         );
+        if let Some(profile) = config::profile() {
+            println!(
+                "{}│{}  profile:     {}",
+                AURORA_BLUE, RESET, profile
+            );
+        }
         println!(
             "{}│{}  data_dir:    {:?}",
             AURORA_BLUE, RESET, config.data_dir
@@ -667,14 +2287,114 @@ async fn manage_config(show: bool, init: bool) -> Result<()> {
             "{}│{}  socket_path: {:?}",
             AURORA_BLUE, RESET, config.socket_path
         );
+        if !config.socket.allowed_uids.is_empty() {
+            println!(
+                "{}│{}  socket uids: {:?} (and the daemon's own)",
+                AURORA_BLUE, RESET, config.socket.allowed_uids
+            );
+        }
         println!(
             "{}│{}  db_path:     {:?}",
             AURORA_BLUE, RESET, config.db_path
         );
+        if let Some(url) = config.redacted_database_url() {
+            println!(
+                "{}│{}  database:    {}",
+                AURORA_BLUE, RESET, url
+            );
+        }
+        if let Some(ref user) = config.user {
+            println!(
+                "{}│{}  user:        {}",
+                AURORA_BLUE, RESET, user
+            );
+        }
         println!(
             "{}│{}  http_port:   {}",
             AURORA_BLUE, RESET, config.http_port
         );
+        if config.encryption.enabled {
+            println!(
+                "{}│{}  encryption:  on",
+                AURORA_BLUE, RESET
+            );
+        }
+        for plugin in &config.plugins {
+            println!(
+                "{}│{}  plugin:      {} {}(fuel {}{}){}",
+                AURORA_BLUE,
+                RESET,
+                plugin.path.display(),
+                DIM,
+                plugin.fuel,
+                if plugin.enabled { "" } else { ", disabled" },
+                RESET
+            );
+        }
+        for channel in &config.notification_channels {
+            println!(
+                "{}│{}  channel:     {} {}({}){}",
+                AURORA_BLUE, RESET, channel.name, DIM, channel.describe(), RESET
+            );
+        }
+        if config.summarizer.enabled {
+            println!(
+                "{}│{}  summarizer:  {:?} {}",
+                AURORA_BLUE,
+                RESET,
+                config.summarizer.mode,
+                config.summarizer.endpoint.as_deref().unwrap_or_default()
+            );
+        }
+        if config.email_digest.enabled {
+            println!(
+                "{}│{}  digest:      {} {:?} {:02}:00 via {}",
+                AURORA_BLUE,
+                RESET,
+                config.email_digest.recipients.join(", "),
+                config.email_digest.weekday,
+                config.email_digest.hour,
+                config.email_digest.smtp_server
+            );
+        }
+        if config.export.enabled {
+            println!(
+                "{}│{}  export:      {} every {}h {}({}){}",
+                AURORA_BLUE, RESET, config.export.destination, config.export.interval_hours,
+                DIM, config.export.format.extension(), RESET
+            );
+        }
+        if config.time_tracking.enabled {
+            println!(
+                "{}│{}  time track:  {:?} workspace {} {}({} project rules){}",
+                AURORA_BLUE, RESET, config.time_tracking.provider, config.time_tracking.workspace_id,
+                DIM, config.time_tracking.projects.len(), RESET
+            );
+        }
+        if config.sso.enabled {
+            println!(
+                "{}│{}  sso:         {} {}({} admins, {} admin groups){}",
+                AURORA_BLUE, RESET, config.sso.issuer,
+                DIM, config.sso.admins.len(), config.sso.admin_groups.len(), RESET
+            );
+        }
+        if config.embeddings.enabled {
+            println!(
+                "{}│{}  embeddings:  {} {}({}){}",
+                AURORA_BLUE, RESET, config.embeddings.endpoint, DIM, config.embeddings.model, RESET
+            );
+        }
+        println!(
+            "{}│{}  refresh:     tui {}s, events {}s, dashboard {}s {}({}){}",
+            AURORA_BLUE, RESET, config.refresh.tui_secs, config.refresh.events_secs, config.refresh.dashboard_secs,
+            DIM, if config.refresh.adaptive { format!("backing off to {}s", config.refresh.max_secs) } else { "fixed".to_string() }, RESET
+        );
+        if let Some(budget) = config.forecast.monthly_budget {
+            println!(
+                "{}│{}  budget:      {}/month {}({} project budgets){}",
+                AURORA_BLUE, RESET, humanize::cost(budget), DIM, config.forecast.project_budgets.len(), RESET
+            );
+        }
         println!(
             "{}╰─────────────────────────────────────────────────────────────────╯{}",
             AURORA_BLUE, RESET
@@ -684,90 +2404,637 @@ async fn manage_config(show: bool, init: bool) -> Result<()> {
     Ok(())
 }
 
-async fn run_web(host: &str, port: u16) -> Result<()> {
+async fn run_web(host: &str, port: u16, refresh: Option<f64>) -> Result<()> {
     println!("{}  ✦   ⋆  ★    ✧  ✶{}", DIM, RESET);
     println!("  {}✦ Starting Web Dashboard{}", AURORA_BLUE, RESET);
     println!("  {}🌐 http://{}:{}{}", COSMIC_VIOLET, host, port, RESET);
     println!("{}  ⋆    ✶     ★   ⋆{}", DIM, RESET);
     println!();
 
-    let config = Config::default();
-    let storage = storage::Storage::new(&config.db_path).await?;
+    let config = Config::load_or_default()?;
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let sso = if config.sso.enabled {
+        println!("  {}🔒 Sign-in via {}{}", DIM, config.sso.issuer, RESET);
+        Some(sso::Sso::new(config.sso.clone(), host, port)?)
+    } else {
+        None
+    };
 
-    api::run_web_server(host, port, storage).await?;
+    // The dashboard follows the daemon's events
+    let events = events::EventBus::new();
+    let relayed = subscribe::relay(config.socket_path.clone(), events.clone());
+    let refresh = config.refresh.clone().with_dashboard_secs(refresh);
+    api::run_web_server(host, port, storage, events, relayed, sso, refresh).await?;
 
     Ok(())
 }
 
-/// Format duration in human-readable form
-fn format_duration(seconds: f64) -> String {
-    if seconds >= 3600.0 {
-        format!("{:.1}h", seconds / 3600.0)
-    } else if seconds >= 60.0 {
-        format!("{:.1}m", seconds / 60.0)
-    } else {
-        format!("{:.0}s", seconds)
+async fn run_watch(
+    remote: Option<String>,
+    api_key: Option<String>,
+    db: Option<PathBuf>,
+    frozen: bool,
+    kiosk: bool,
+    refresh: Option<f64>,
+) -> Result<()> {
+    let mut config = Config::load_or_default()?;
+    let refresh_config = config.refresh.clone().with_tui_secs(refresh);
+    // Pushes and polls decide when to re-read; a read cache would only delay them
+    config.cache.ttl_ms = 0;
+
+    if let Some(url) = remote {
+        let client = remote::RemoteClient::new(&url, api_key)?;
+
+        // Fail early with a readable message instead of inside the TUI
+        if let Err(e) = client.get_active_sessions(1).await {
+            eprintln!("{}✗ Error:{} Cannot reach {}: {}",
+                NOVA_RED, RESET, url, e);
+            eprintln!("{}  Hint:{} Run 'agent-monitor web --host 0.0.0.0' on the remote machine.",
+                AURORA_BLUE, RESET);
+            return Ok(());
+        }
+
+        tui::run_tui(tui::DataSource::Remote(client), kiosk, refresh_config, None).await?;
+        return Ok(());
     }
-}
 
-/// Format token count in human-readable form
-fn format_tokens(count: i64) -> String {
-    if count >= 1_000_000 {
-        format!("{:.1}M", count as f64 / 1_000_000.0)
-    } else if count >= 1_000 {
-        format!("{:.1}K", count as f64 / 1_000.0)
-    } else {
-        format!("{}", count)
+    // A shared database is used unless a specific file was asked for
+    if db.is_none() && !frozen && !config.uses_local_db() {
+        let storage = storage::Storage::connect(&config).await?;
+        tui::run_tui(tui::DataSource::Local(storage), kiosk, refresh_config, None).await?;
+        return Ok(());
     }
-}
 
-/// Run the interactive TUI watch mode
-async fn run_watch() -> Result<()> {
-    let config = Config::default();
+    let db_path = db.unwrap_or(config.db_path.clone());
 
     // Check if database exists
-    if !config.db_path.exists() {
+    if !db_path.exists() {
         eprintln!("{}✗ Error:{} Database not found at {:?}",
-            "\x1b[38;5;196m", RESET, config.db_path);
+            NOVA_RED, RESET, db_path);
         eprintln!("{}  Hint:{} Run 'agent-monitor daemon' first to initialize the database.",
             AURORA_BLUE, RESET);
         return Ok(());
     }
 
-    let storage = storage::Storage::new(&config.db_path).await?;
+    let source = if frozen {
+        tui::DataSource::Snapshot(storage::Storage::open_read_only(&db_path).await?.layered(&config)?)
+    } else {
+        tui::DataSource::Local(storage::Storage::new(&db_path).await?.layered(&config)?)
+    };
+
+    // The daemon pushes what it records to the database it writes
+    let subscription = (!frozen && db_path == config.db_path)
+        .then(|| subscribe::Subscription::start(config.socket_path.clone()));
 
     // Run the TUI
-    tui::run_tui(storage).await?;
+    tui::run_tui(source, kiosk, refresh_config, subscription).await?;
+
+    Ok(())
+}
+
+/// Run the TUI or web dashboard fed by the synthetic event generator
+async fn run_demo(sessions: usize, rate: f64, web: bool, port: u16) -> Result<()> {
+    let storage = storage::Storage::in_memory();
+    let event_bus = events::EventBus::new();
+    let generator = demo::DemoGenerator::new(
+        storage.clone(),
+        event_bus.clone(),
+        demo::DemoConfig { sessions, rate },
+    );
+    tokio::spawn(async move {
+        if let Err(e) = generator.run().await {
+            tracing::error!("Demo generator error: {}", e);
+        }
+    });
+
+    if web {
+        println!("{}  ✦   ⋆  ★    ✧  ✶{}", DIM, RESET);
+        println!("  {}✦ Demo Dashboard{} {}(synthetic data){}", AURORA_BLUE, RESET, DIM, RESET);
+        println!("  {}🌐 http://127.0.0.1:{}{}", COSMIC_VIOLET, port, RESET);
+        println!("{}  ⋆    ✶     ★   ⋆{}", DIM, RESET);
+        println!();
+        let relayed = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+        api::run_web_server("127.0.0.1", port, storage, event_bus, relayed, None, refresh::RefreshConfig::default())
+            .await?;
+    } else {
+        tui::run_tui(tui::DataSource::Local(storage), false, refresh::RefreshConfig::default(), None).await?;
+    }
+
+    Ok(())
+}
+
+/// Archive sessions older than `older_than` to `to` (the local archive
+/// directory by default), or list and restore archived ones
+async fn manage_archive(command: Option<ArchiveCommand>, older_than: &str, to: Option<String>, dry_run: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        eprintln!("{}✗ Error:{} Database not found at {:?}", NOVA_RED, RESET, config.db_path);
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let cipher = if config.encryption.enabled {
+        Some(storage::ContentCipher::load(&config)?)
+    } else {
+        None
+    };
+
+    match command {
+        None => {
+            let older_than = archive::parse_age(older_than)?;
+            let destination = match to {
+                Some(ref target) => objstore::Destination::parse(target)?,
+                None => objstore::Destination::Local(config.data_dir.join("archive")),
+            };
+            let sessions = archive::candidates(&storage, older_than).await?;
+            if sessions.is_empty() {
+                println!("{}No sessions to archive{}", DIM, RESET);
+                return Ok(());
+            }
+            if dry_run {
+                for session in &sessions {
+                    println!("  {}{}{}  {}  {}", PULSE_CYAN, &session.id[..8.min(session.id.len())], RESET,
+                        timefmt::date(session.last_activity_at), session.project_path);
+                }
+                println!("{}{} sessions would be archived{}", DIM, sessions.len(), RESET);
+                return Ok(());
+            }
+            let archived = archive::archive(&storage, &sessions, &destination, cipher.as_ref()).await?;
+            println!("{}✓{} Archived {} sessions", PULSE_CYAN, RESET, archived.len());
+        }
+        Some(ArchiveCommand::List { query, output }) => {
+            let archived: Vec<_> = storage
+                .list_archived_sessions()
+                .await?
+                .into_iter()
+                .filter(|a| query.as_deref().is_none_or(|q| archive::matches(a, q)))
+                .collect();
+            if output.format().print(&archived)? {
+                return Ok(());
+            }
+            if archived.is_empty() {
+                println!("{}No archived sessions{}", DIM, RESET);
+                return Ok(());
+            }
+            let mut sessions = table::Table::new(&["Session", "Project", "Last active", "Location"])
+                .title("✦ Archived Sessions ✦");
+            for entry in &archived {
+                let id = &entry.session.id;
+                sessions.row(vec![
+                    table::Cell::colored(format!("{} archived", &id[..8.min(id.len())]), COSMIC_VIOLET),
+                    entry.session.project_path.clone().into(),
+                    timefmt::datetime(entry.session.last_activity_at).into(),
+                    entry.location.clone().into(),
+                ]);
+            }
+            println!("{}", sessions.render(theme::profile()));
+        }
+        Some(ArchiveCommand::Restore { session }) => {
+            let matching: Vec<String> = storage
+                .list_archived_sessions()
+                .await?
+                .into_iter()
+                .map(|a| a.session.id)
+                .filter(|id| id.starts_with(&session))
+                .collect();
+            let id = match matching.as_slice() {
+                [id] => id.clone(),
+                [] => anyhow::bail!("No archived session matches '{}'", session),
+                _ => anyhow::bail!("'{}' matches {} archived sessions; use more of the ID", session, matching.len()),
+            };
+            let restored = archive::restore(&storage, &id, cipher.as_ref()).await?;
+            println!("{}✓{} Restored {} ({})", PULSE_CYAN, RESET, restored.id, restored.project_path);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_export(format: export::ExportFormat, to: &str, since: Option<&str>) -> Result<()> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        eprintln!("{}✗ Error:{} Database not found at {:?}", NOVA_RED, RESET, config.db_path);
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    let destination = objstore::Destination::parse(to)?;
+    let now = chrono::Utc::now();
+    let since = since.map(archive::parse_age).transpose()?.map(|age| now - age);
+
+    let counts = export::export(&storage, &destination, format, since, now).await?;
+    if counts.sessions == 0 {
+        println!("{}Nothing to export{}", DIM, RESET);
+    } else {
+        println!(
+            "{}✓{} Exported {} sessions and {} events to {} {}({}){}",
+            PULSE_CYAN, RESET, counts.sessions, counts.events, to, DIM, format.extension(), RESET
+        );
+    }
+    Ok(())
+}
+
+async fn run_import(from: import::ImportSource, path: &std::path::Path, before: Option<chrono::NaiveDate>) -> Result<()> {
+    let config = Config::load_or_default()?;
+    // The Python daemon used the same default path; reading and writing one
+    // file would mix the two schemas
+    let file = if path.is_dir() { path.join("sessions.db") } else { path.to_path_buf() };
+    let same_file = config.uses_local_db()
+        && file.canonicalize().ok().zip(config.db_path.canonicalize().ok()).is_some_and(|(a, b)| a == b);
+    if same_file {
+        eprintln!(
+            "{}✗ Error:{} {} is this monitor's own database; move it aside (e.g. to sessions-python.db) and import from there",
+            NOVA_RED, RESET, file.display()
+        );
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+
+    let before = before.and_then(import::start_of_day);
+    let counts = import::import(&storage, from, path, before).await?;
+    println!(
+        "{}✓{} Imported {} sessions and {} events from {}",
+        PULSE_CYAN, RESET, counts.sessions, counts.events, path.display()
+    );
+    if counts.skipped > 0 {
+        println!("{}  Skipped {} unreadable records{}", DIM, counts.skipped, RESET);
+    }
+    Ok(())
+}
+
+async fn manage_db(command: DbCommand) -> Result<()> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        eprintln!("{}✗ Error:{} Database not found at {:?}", NOVA_RED, RESET, config.db_path);
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+
+    match command {
+        DbCommand::Stats { top, output } => {
+            let stats = storage.get_database_stats(top).await?;
+            if output.format().print(&stats)? {
+                return Ok(());
+            }
+            print_database_stats(&stats);
+        }
+        DbCommand::Snapshot { path } => {
+            storage.write_snapshot(&path).await?;
+            let size = std::fs::metadata(&path).map(|m| m.len() as i64).unwrap_or(0);
+            println!("{}✓{} Wrote {} ({})", PULSE_CYAN, RESET, path.display(), humanize::bytes(size));
+            println!(
+                "{}  Views: daily_usage, tool_usage, session_tag_costs. In DuckDB: ATTACH '{}' (TYPE sqlite, READ_ONLY){}",
+                DIM, path.display(), RESET
+            );
+        }
+        DbCommand::Audit { limit, output } => {
+            let log = storage.get_audit_log(limit).await?;
+            if output.format().print(&log)? {
+                return Ok(());
+            }
+            if log.is_empty() {
+                println!("{}No write requests recorded{}", DIM, RESET);
+                return Ok(());
+            }
+            let mut entries = table::Table::new(&["Time", "UID", "Action", "Request", "Outcome"])
+                .title("✦ Audit Log ✦")
+                .max_width(3, 48)
+                .max_width(4, 32);
+            for entry in &log {
+                let outcome_color = if entry.outcome == "ok" { PULSE_CYAN } else { NOVA_RED };
+                entries.row(vec![
+                    timefmt::datetime(entry.timestamp).into(),
+                    entry.uid.to_string().into(),
+                    entry.action.clone().into(),
+                    entry.params.to_string().into(),
+                    table::Cell::colored(entry.outcome.clone(), outcome_color),
+                ]);
+            }
+            println!("{}", entries.render(theme::profile()));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_database_stats(stats: &models::DatabaseStats) {
+    let fragmentation_color = if stats.fragmentation >= 0.2 { SOLAR_AMBER } else { PULSE_CYAN };
+    let mut summary = vec![
+        String::new(),
+        format!("   Backend:        {}", stats.backend),
+        format!("   Size:           {}", humanize::bytes(stats.size_bytes)),
+        format!("   Fragmentation:  {}{:.1}%{}", fragmentation_color, stats.fragmentation * 100.0, RESET),
+    ];
+    if stats.index_problems.is_empty() {
+        summary.push(format!("   Indexes:        {}✓ {} sound{}", PULSE_CYAN, stats.indexes.len(), RESET));
+    } else {
+        summary.push(format!("   Indexes:        {}✗ {} problems{}", NOVA_RED, stats.index_problems.len(), RESET));
+        for problem in &stats.index_problems {
+            summary.push(format!("     {}{}{}", DIM, problem, RESET));
+        }
+    }
+    summary.push(String::new());
+    println!("{}", table::panel("✦ Database ✦", &summary, theme::profile()));
+
+    let size = |bytes: Option<i64>| bytes.map(humanize::bytes).unwrap_or_else(|| "—".to_string());
+    let mut tables = table::Table::new(&["Table", "Rows", "Size"])
+        .title("✦ Tables ✦")
+        .align(1, table::Align::Right)
+        .align(2, table::Align::Right);
+    for t in &stats.tables {
+        tables.row(vec![t.name.clone().into(), t.rows.to_string().into(), size(t.bytes).into()]);
+    }
+    println!();
+    println!("{}", tables.render(theme::profile()));
+
+    if !stats.indexes.is_empty() {
+        let mut indexes = table::Table::new(&["Index", "Table", "Size"])
+            .title("✦ Indexes ✦")
+            .align(2, table::Align::Right);
+        for index in &stats.indexes {
+            indexes.row(vec![index.name.clone().into(), index.table.clone().into(), size(index.bytes).into()]);
+        }
+        println!();
+        println!("{}", indexes.render(theme::profile()));
+    }
+
+    if !stats.heaviest_sessions.is_empty() {
+        let mut sessions = table::Table::new(&["Session", "Project", "Events", "Stored"])
+            .title("✦ Heaviest Sessions ✦")
+            .max_width(1, 32)
+            .align(2, table::Align::Right)
+            .align(3, table::Align::Right);
+        for session in &stats.heaviest_sessions {
+            sessions.row(vec![
+                session.session_id[..8.min(session.session_id.len())].into(),
+                session.project_path.as_deref().unwrap_or("—").into(),
+                session.events.to_string().into(),
+                table::Cell::colored(humanize::bytes(session.stored_bytes), COSMIC_VIOLET),
+            ]);
+        }
+        println!();
+        println!("{}", sessions.render(theme::profile()));
+    }
+
+    if stats.fragmentation >= 0.2 {
+        println!("{}⚠ {:.0}% of the database is reclaimable; run VACUUM when the daemon is idle{}",
+            SOLAR_AMBER, stats.fragmentation * 100.0, RESET);
+    }
+}
+
+async fn run_doctor(output: OutputFormat) -> Result<()> {
+    let report = doctor::run().await;
+    if output.print(&report)? {
+        return Ok(());
+    }
+
+    let mut lines = vec![String::new()];
+    for check in &report.checks {
+        let (mark, color) = match check.status {
+            doctor::CheckStatus::Ok => ("✓", PULSE_CYAN),
+            doctor::CheckStatus::Warn => ("⚠", SOLAR_AMBER),
+            doctor::CheckStatus::Fail => ("✗", NOVA_RED),
+        };
+        lines.push(format!("{}{}{} {:<10} {}", color, mark, RESET, check.name, check.detail));
+    }
+    if let Some(ref stats) = report.content {
+        lines.push(String::new());
+        lines.push(format!("{}Content blobs{}", BOLD, RESET));
+        lines.push(format!("   stored:    {:>10} {}({} uncompressed){}",
+            humanize::bytes(stats.stored_bytes), DIM, humanize::bytes(stats.content_bytes), RESET));
+        lines.push(format!("   inline:    {:>10} {}(what {} events would take){}",
+            humanize::bytes(stats.referenced_bytes), DIM, stats.references, RESET));
+        lines.push(format!("   saved:     {}{:>10}{}",
+            COSMIC_VIOLET, humanize::bytes(stats.saved_bytes().max(0)), RESET));
+    }
+    lines.push(String::new());
+    println!("{}", table::panel("✦ Agent Monitor Doctor ✦", &lines, theme::profile()));
+
+    if !report.healthy() {
+        println!("{}✗ Some checks failed; see the hints above{}", NOVA_RED, RESET);
+    }
+    Ok(())
+}
+
+/// Run the event pipeline benchmark and print the report
+async fn run_bench(rate: u32, duration: u64, memory: bool, output: OutputFormat) -> Result<()> {
+    if output.is_table() {
+        println!("{}⟳ Writing {} events/sec for {}s through watcher → storage → event bus...{}",
+            PULSE_CYAN, rate, duration, RESET);
+    }
+
+    let report = bench::run(bench::BenchConfig {
+        rate,
+        duration: Duration::from_secs(duration),
+        in_memory: memory,
+    })
+    .await?;
+
+    if output.print(&report)? {
+        return Ok(());
+    }
+
+    println!(
+        "{}╭──────────────────── ✦ Pipeline Benchmark ✦ ────────────────────╮{}",
+        AURORA_BLUE, RESET
+    );
+    println!("{}│{}  backend:     {}", AURORA_BLUE, RESET, report.backend);
+    println!("{}│{}  sent:        {} ({}/s target)", AURORA_BLUE, RESET, report.sent, report.target_rate);
+    println!("{}│{}  received:    {} ({:.1}/s)", AURORA_BLUE, RESET, report.received, report.throughput_per_sec);
+    println!("{}│{}  stored:      {}", AURORA_BLUE, RESET, report.stored);
+    println!("{}│{}  duplicates:  {}", AURORA_BLUE, RESET, report.duplicate_publishes);
+    println!("{}│{}  lagged:      {}", AURORA_BLUE, RESET, report.lagged);
+    println!(
+        "{}│{}  latency:     p50 {:.1}ms  p95 {:.1}ms  p99 {:.1}ms  max {:.1}ms",
+        AURORA_BLUE, RESET,
+        report.latency_p50_ms, report.latency_p95_ms, report.latency_p99_ms, report.latency_max_ms
+    );
+    println!(
+        "{}╰─────────────────────────────────────────────────────────────────╯{}",
+        AURORA_BLUE, RESET
+    );
+
+    if report.received < report.sent {
+        println!("{}⚠ {} events never reached subscribers{}",
+            COSMIC_VIOLET, report.sent - report.received, RESET);
+    }
 
     Ok(())
 }
 
 /// Clear sessions from database
-async fn run_clear(agent_type: Option<String>, all: bool) -> Result<()> {
-    let config = Config::default();
+async fn run_clear(filter: clear::ClearFilter, all: bool, dry_run: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
 
-    if !config.db_path.exists() {
+    if config.uses_local_db() && !config.db_path.exists() {
         eprintln!("{}✗ Error:{} Database not found at {:?}",
-            "\x1b[38;5;196m", RESET, config.db_path);
+            NOVA_RED, RESET, config.db_path);
         return Ok(());
     }
 
-    let storage = storage::Storage::new(&config.db_path).await?;
+    let storage = storage::Storage::connect(&config).await?;
+    let trash = trash::Trash::open(&config)?;
 
     if all {
+        if dry_run {
+            let sessions = storage.get_all_sessions(i32::MAX as usize).await?;
+            println!("{}Would clear all {} sessions and their events{}", PULSE_CYAN, sessions.len(), RESET);
+            return Ok(());
+        }
         println!("{}⟳ Clearing all sessions and events...{}", PULSE_CYAN, RESET);
-        storage.clear_all().await?;
-        println!("{}✓ All sessions cleared{}", AURORA_BLUE, RESET);
-    } else if let Some(agent) = agent_type {
-        println!("{}⟳ Clearing {} sessions...{}", PULSE_CYAN, agent, RESET);
-        let count = storage.delete_sessions_by_type(&agent).await?;
-        println!("{}✓ Cleared {} {} sessions{}", AURORA_BLUE, count, agent, RESET);
+        let cleared = clear::clear_all(&storage, &trash).await?;
+        println!("{}✓ All {} sessions cleared{}", AURORA_BLUE, cleared, RESET);
+        if trash.is_enabled() && cleared > 0 {
+            println!("{}  They stay in the trash for {} days; see `agent-monitor trash list`{}",
+                DIM, config.trash.grace_days, RESET);
+        }
+    } else if !filter.is_empty() {
+        if !dry_run {
+            println!("{}⟳ Clearing matching sessions...{}", PULSE_CYAN, RESET);
+        }
+        let sessions = clear::clear(&storage, &trash, &filter, dry_run).await?;
+        if dry_run && !sessions.is_empty() {
+            let mut table = table::Table::new(&["Session", "Agent", "Project", "Last Active", "Messages"])
+                .title("✦ Would Clear ✦")
+                .max_width(2, 48)
+                .align(4, table::Align::Right);
+            for session in &sessions {
+                table.row(vec![
+                    session.id[..8.min(session.id.len())].into(),
+                    session.agent_type.to_string().into(),
+                    session.project_path.clone().into(),
+                    timefmt::datetime(session.last_activity_at).into(),
+                    session.message_count.to_string().into(),
+                ]);
+            }
+            println!("{}", table.render(theme::profile()));
+        }
+        let verb = if dry_run { "Would clear" } else { "✓ Cleared" };
+        println!("{}{} {} sessions{}", AURORA_BLUE, verb, sessions.len(), RESET);
+        if !dry_run && trash.is_enabled() && !sessions.is_empty() {
+            println!("{}  Restore them within {} days with `agent-monitor trash restore <session>`{}",
+                DIM, config.trash.grace_days, RESET);
+        }
     } else {
-        eprintln!("{}✗ Error:{} Please specify --agent-type or --all", "\x1b[38;5;196m", RESET);
+        eprintln!("{}✗ Error:{} Please specify what to clear, or --all", NOVA_RED, RESET);
         eprintln!("  Examples:");
         eprintln!("    agent-monitor clear --agent-type cursor");
+        eprintln!("    agent-monitor clear --project '/work/old-*' --before 2026-01-01 --dry-run");
+        eprintln!("    agent-monitor clear --session 3f2a9c1e-...");
         eprintln!("    agent-monitor clear --all");
     }
 
     Ok(())
 }
+
+async fn manage_trash(command: TrashCommand, format: OutputFormat) -> Result<()> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        eprintln!("{}✗ Error:{} Database not found at {:?}", NOVA_RED, RESET, config.db_path);
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let trash = trash::Trash::open(&config)?;
+
+    match command {
+        TrashCommand::List => {
+            let trashed = storage.list_trashed_sessions().await?;
+            if format.print(&trashed)? {
+                return Ok(());
+            }
+            if trashed.is_empty() {
+                println!("{}The trash is empty{}", DIM, RESET);
+                return Ok(());
+            }
+            let mut table = table::Table::new(&["Session", "Agent", "Project", "Reason", "Deleted", "Expires"])
+                .title("✦ Trash ✦")
+                .max_width(2, 48);
+            for entry in &trashed {
+                table.row(vec![
+                    entry.session.id[..8.min(entry.session.id.len())].into(),
+                    entry.session.agent_type.to_string().into(),
+                    entry.session.project_path.clone().into(),
+                    entry.reason.clone().into(),
+                    timefmt::datetime(entry.deleted_at).into(),
+                    table::Cell::colored(timefmt::datetime(trash.expires_at(entry)), SOLAR_AMBER),
+                ]);
+            }
+            println!("{}", table.render(theme::profile()));
+        }
+        TrashCommand::Restore { session } => {
+            let matching: Vec<String> = storage
+                .list_trashed_sessions()
+                .await?
+                .into_iter()
+                .map(|t| t.session.id)
+                .filter(|id| id.starts_with(&session))
+                .collect();
+            let id = match matching.as_slice() {
+                [id] => id.clone(),
+                [] => anyhow::bail!("No session in the trash matches '{}'", session),
+                _ => anyhow::bail!("'{}' matches {} sessions in the trash; use more of the ID", session, matching.len()),
+            };
+            let restored = trash.restore(&storage, &id).await?;
+            println!("{}✓{} Restored {} ({})", PULSE_CYAN, RESET, restored.id, restored.project_path);
+        }
+        TrashCommand::Empty { expired } => {
+            let emptied = trash.empty(&storage, expired).await?;
+            println!("{}✓{} Deleted {} sessions for good", PULSE_CYAN, RESET, emptied.len());
+        }
+    }
+
+    Ok(())
+}
+
+async fn show_uptime(days: i64, output: OutputFormat) -> Result<()> {
+    let config = Config::load_or_default()?;
+    if config.uses_local_db() && !config.db_path.exists() {
+        eprintln!("{}✗ Error:{} Database not found at {:?}", NOVA_RED, RESET, config.db_path);
+        return Ok(());
+    }
+    let storage = storage::Storage::connect(&config).await?;
+    storage.initialize().await?;
+    let coverage = uptime::build(&storage, days).await?;
+    if output.print(&coverage)? {
+        return Ok(());
+    }
+
+    let color = match coverage.running {
+        Some(running) if running >= 0.95 => PULSE_CYAN,
+        Some(_) => SOLAR_AMBER,
+        None => DIM,
+    };
+    println!("{}✦{} {}{}{}", AURORA_BLUE, RESET, color, coverage.summary(), RESET);
+    if coverage.running.is_some() {
+        println!(
+            "{}  {} runs, {} crashed; measured since {}{}",
+            DIM, coverage.runs, coverage.crashes,
+            timefmt::datetime(coverage.from), RESET
+        );
+    }
+    if coverage.gaps.is_empty() {
+        return Ok(());
+    }
+    let mut table = table::Table::new(&["From", "To", "Length", "After"])
+        .title("✦ Monitoring Gaps ✦")
+        .align(2, table::Align::Right);
+    for gap in &coverage.gaps {
+        table.row(vec![
+            timefmt::datetime(gap.start).into(),
+            timefmt::datetime(gap.end).into(),
+            humanize::duration(gap.duration().num_seconds() as f64).into(),
+            if gap.after_crash {
+                table::Cell::colored("crash", NOVA_RED)
+            } else {
+                table::Cell::new("stop")
+            },
+        ]);
+    }
+    println!();
+    println!("{}", table.render(theme::profile()));
+    println!("{}  Sessions during these gaps may be missing or incomplete{}", DIM, RESET);
+    Ok(())
+}
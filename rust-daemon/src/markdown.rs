@@ -0,0 +1,317 @@
+//! Markdown rendering of agent responses.
+//!
+//! Responses are written in Markdown. The TUI shows them as styled ratatui
+//! lines (headings, lists, quotes, emphasis, tables flattened to rows) and
+//! the web dashboard as HTML; fenced code goes through `highlight` in both.
+//! Raw HTML in a response is shown as text, never passed through.
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::highlight;
+
+/// Color of inline code and list markers in the TUI.
+const ACCENT: Color = Color::Rgb(255, 176, 0);
+
+/// Color of quote bars, rules and code block edges in the TUI.
+const MUTED: Color = Color::Rgb(110, 110, 110);
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// Builds TUI lines from parser events.
+#[derive(Default)]
+struct LineWriter {
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>,
+    /// Inline styles currently open (emphasis, strong, links, headings)
+    styles: Vec<Style>,
+    /// Next number of each open list; None for bullet lists
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    /// Language and text of the code block being read
+    code: Option<(String, String)>,
+    /// Whether the current table row has a cell yet
+    row_started: bool,
+}
+
+impl LineWriter {
+    fn style(&self) -> Style {
+        self.styles.iter().fold(Style::default(), |acc, s| acc.patch(*s))
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        if !text.is_empty() {
+            self.current.push(Span::styled(text.to_string(), style));
+        }
+    }
+
+    /// Line prefix for block quotes.
+    fn quote_prefix(&self) -> Option<Span<'static>> {
+        (self.quote_depth > 0).then(|| Span::styled("│ ".repeat(self.quote_depth), Style::default().fg(MUTED)))
+    }
+
+    fn flush(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        let mut spans: Vec<Span> = self.quote_prefix().into_iter().collect();
+        spans.append(&mut self.current);
+        self.lines.push(Line::from(spans));
+    }
+
+    /// A blank line between blocks, never doubled or leading.
+    fn gap(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|l| l.width() > 0) && self.lists.is_empty() {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn code_block(&mut self, info: &str, code: &str) {
+        self.flush();
+        let edge = Style::default().fg(MUTED);
+        let label = if info.is_empty() { String::new() } else { format!(" {} ", info) };
+        self.lines.push(Line::from(Span::styled(format!("┌─{}", label), edge)));
+        for runs in highlight::highlight_code(code, info) {
+            let mut spans = vec![Span::styled("│ ", edge)];
+            spans.extend(runs.into_iter().map(|(color, text)| match color {
+                Some((r, g, b)) => Span::styled(text.to_string(), Style::default().fg(Color::Rgb(r, g, b))),
+                None => Span::raw(text.to_string()),
+            }));
+            self.lines.push(Line::from(spans));
+        }
+        self.lines.push(Line::from(Span::styled("└─", edge)));
+    }
+
+    fn event(&mut self, event: Event) {
+        if let Some((_, ref mut code)) = self.code {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    let (info, code) = self.code.take().unwrap_or_default();
+                    self.code_block(&info, &code);
+                    self.gap();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush();
+                let mut style = Style::default().add_modifier(Modifier::BOLD);
+                if (level as usize) <= 2 {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                self.styles.push(style);
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                self.styles.pop();
+                self.gap();
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::HtmlBlock) => self.gap(),
+            Event::Start(Tag::BlockQuote(_)) => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                self.flush();
+                self.quote_depth -= 1;
+                self.gap();
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((info, String::new()));
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.flush();
+                self.lists.pop();
+                self.gap();
+            }
+            Event::Start(Tag::Item) => {
+                self.flush();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}{}. ", indent, *n - 1)
+                    }
+                    _ => format!("{}• ", indent),
+                };
+                self.push_text(&marker, Style::default().fg(ACCENT));
+            }
+            Event::End(TagEnd::Item) => self.flush(),
+            Event::Start(Tag::Emphasis) => self.styles.push(Style::default().add_modifier(Modifier::ITALIC)),
+            Event::Start(Tag::Strong) => self.styles.push(Style::default().add_modifier(Modifier::BOLD)),
+            Event::Start(Tag::Strikethrough) => self.styles.push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Event::Start(Tag::Link { .. }) => self.styles.push(Style::default().add_modifier(Modifier::UNDERLINED)),
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link) => {
+                self.styles.pop();
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                self.flush();
+                self.row_started = false;
+                if matches!(event, Event::Start(Tag::TableHead)) {
+                    self.styles.push(Style::default().add_modifier(Modifier::BOLD));
+                }
+            }
+            Event::End(TagEnd::TableHead) => {
+                self.styles.pop();
+                self.flush();
+            }
+            Event::End(TagEnd::TableRow) => self.flush(),
+            Event::End(TagEnd::Table) => self.gap(),
+            Event::Start(Tag::TableCell) => {
+                if self.row_started {
+                    self.push_text(" │ ", Style::default().fg(MUTED));
+                }
+                self.row_started = true;
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                let style = self.style();
+                // Only HTML blocks span lines here
+                let mut parts = text.split('\n').peekable();
+                while let Some(part) = parts.next() {
+                    self.push_text(part, style);
+                    if parts.peek().is_some() {
+                        self.flush();
+                    }
+                }
+            }
+            Event::Code(code) => {
+                let style = self.style().fg(ACCENT);
+                self.push_text(&code, style);
+            }
+            Event::SoftBreak => {
+                let style = self.style();
+                self.push_text(" ", style);
+            }
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                self.lines.push(Line::from(Span::styled("─".repeat(40), Style::default().fg(MUTED))));
+                self.gap();
+            }
+            Event::TaskListMarker(done) => self.push_text(if done { "[x] " } else { "[ ] " }, Style::default().fg(ACCENT)),
+            _ => {}
+        }
+    }
+}
+
+/// `markdown` as styled lines for the TUI.
+pub fn to_lines(markdown: &str) -> Vec<Line<'static>> {
+    let mut writer = LineWriter::default();
+    for event in Parser::new_ext(markdown, options()) {
+        writer.event(event);
+    }
+    writer.flush();
+    while writer.lines.last().is_some_and(|l| l.width() == 0) {
+        writer.lines.pop();
+    }
+    writer.lines
+}
+
+/// Whether a link target is safe to put in an `href`.
+fn safe_url(url: &str) -> bool {
+    let scheme = url.split_once(':').map(|(scheme, _)| scheme.to_ascii_lowercase());
+    match scheme {
+        Some(scheme) if !scheme.contains('/') => matches!(scheme.as_str(), "http" | "https" | "mailto"),
+        _ => true,
+    }
+}
+
+/// `markdown` as HTML for the web dashboard.
+pub fn to_html(markdown: &str) -> String {
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(markdown, options()) {
+        if let Some((_, ref mut text)) = code {
+            match event {
+                Event::Text(t) => text.push_str(&t),
+                Event::End(TagEnd::CodeBlock) => {
+                    let (info, text) = code.take().unwrap_or_default();
+                    let html = highlight::runs_to_html(highlight::highlight_code(&text, &info));
+                    events.push(Event::Html(CowStr::from(format!("<pre><code>{}</code></pre>\n", html))));
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((info, String::new()));
+            }
+            // Raw HTML is shown, not rendered
+            Event::Html(html) | Event::InlineHtml(html) => events.push(Event::Text(html)),
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) if !safe_url(&dest_url) => {
+                events.push(Event::Start(Tag::Link { link_type, dest_url: CowStr::from("#"), title, id }))
+            }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) if !safe_url(&dest_url) => {
+                events.push(Event::Start(Tag::Image { link_type, dest_url: CowStr::from("#"), title, id }))
+            }
+            other => events.push(other),
+        }
+    }
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_markdown_rendering() {
+        let markdown = "# Plan\n\nI'll **fix** the `parser`:\n\n1. Read it\n2. Patch it\n   - carefully\n\n> note\n\n```rust\nlet x = 1;\n```\n\n<script>alert(1)</script>\n\n[x](javascript:alert(1))";
+        let lines: Vec<String> = to_lines(markdown).iter().map(text).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Plan",
+                "",
+                "I'll fix the parser:",
+                "",
+                "1. Read it",
+                "2. Patch it",
+                "  • carefully",
+                "",
+                "│ note",
+                "",
+                "┌─ rust ",
+                "│ let x = 1;",
+                "└─",
+                "",
+                "<script>alert(1)</script>",
+                "",
+                "x",
+            ]
+        );
+
+        let html = to_html(markdown);
+        assert!(html.contains("<h1>Plan</h1>"));
+        assert!(html.contains("<strong>fix</strong>"));
+        assert!(html.contains("<pre><code><span style="));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("javascript:"));
+    }
+}
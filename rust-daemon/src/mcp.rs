@@ -0,0 +1,308 @@
+//! MCP server usage.
+//!
+//! Claude Code names the tools of an MCP server `mcp__<server>__<tool>`.
+//! Calls are read from transcript events, which keep each tool_use block's
+//! ID and each tool_result's error flag: a call's latency is the time from
+//! the response that made it to the message carrying its result. Sessions
+//! without transcript calls contribute the ToolExecuted events of the
+//! PostToolUse hook instead, with the duration and exit code it reported.
+//! Responses recorded before IDs were kept count as calls of unknown latency
+//! that did not fail.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Most events of one type considered for a report.
+const MAX_ROWS: usize = 20_000;
+
+/// Server and tool of an MCP tool name, e.g. `mcp__github__create_issue`.
+pub fn parse_tool_name(name: &str) -> Option<(&str, &str)> {
+    name.strip_prefix("mcp__")?.split_once("__")
+}
+
+/// One call to an MCP tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpCall {
+    pub session_id: String,
+    pub server: String,
+    pub tool: String,
+    pub timestamp: DateTime<Utc>,
+    /// None until the result arrives, or when it was not recorded
+    pub duration_ms: Option<i64>,
+    pub error: bool,
+}
+
+impl McpCall {
+    fn new(event: &SessionEvent, name: &str) -> Option<Self> {
+        let (server, tool) = parse_tool_name(name)?;
+        Some(Self {
+            session_id: event.session_id.clone(),
+            server: server.to_string(),
+            tool: tool.to_string(),
+            timestamp: event.timestamp,
+            duration_ms: None,
+            error: false,
+        })
+    }
+}
+
+/// Usage of one tool of a server.
+#[derive(Debug, Clone, Serialize)]
+pub struct McpToolUsage {
+    pub tool: String,
+    pub calls: usize,
+    pub errors: usize,
+    pub avg_duration_ms: Option<f64>,
+}
+
+/// Usage of one MCP server.
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerUsage {
+    pub server: String,
+    pub calls: usize,
+    pub errors: usize,
+    /// Share of calls (0–1) whose result was an error
+    pub error_rate: f64,
+    /// Over the calls whose latency is known
+    pub avg_duration_ms: Option<f64>,
+    pub p95_duration_ms: Option<i64>,
+    pub sessions: usize,
+    pub last_used: DateTime<Utc>,
+    /// Most calls first
+    pub tools: Vec<McpToolUsage>,
+}
+
+/// MCP usage over a period, or of one session.
+#[derive(Debug, Clone, Serialize)]
+pub struct McpReport {
+    pub generated_at: DateTime<Utc>,
+    pub days: i64,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    pub calls: usize,
+    /// Most calls first
+    pub servers: Vec<McpServerUsage>,
+}
+
+/// MCP usage of one session, or of the last `days` (optionally within one
+/// project).
+pub async fn build_report(
+    storage: &Storage,
+    project: Option<&str>,
+    session_id: Option<&str>,
+    days: i64,
+) -> Result<McpReport> {
+    let days = days.max(1);
+    let events = match session_id {
+        Some(id) => storage.get_session_events(id, MAX_ROWS).await?,
+        None => {
+            let mut events = Vec::new();
+            for event_type in [EventType::ResponseGenerated, EventType::PromptReceived, EventType::ToolExecuted] {
+                events.extend(storage.get_recent_events_of_type(event_type, days * 24, MAX_ROWS).await?);
+            }
+            events.retain(|e| project.is_none_or(|p| e.working_directory.as_deref() == Some(p)));
+            events
+        }
+    };
+    let calls = calls(&events);
+    Ok(McpReport {
+        generated_at: Utc::now(),
+        days,
+        project: project.map(str::to_string),
+        session_id: session_id.map(str::to_string),
+        calls: calls.len(),
+        servers: usage(&calls),
+    })
+}
+
+/// The MCP calls recorded in `events`, oldest first per session.
+pub fn calls(events: &[SessionEvent]) -> Vec<McpCall> {
+    let mut sessions: BTreeMap<&str, Vec<&SessionEvent>> = BTreeMap::new();
+    for event in events {
+        sessions.entry(event.session_id.as_str()).or_default().push(event);
+    }
+    let mut calls = Vec::new();
+    for mut events in sessions.into_values() {
+        events.sort_by_key(|e| e.timestamp);
+        let transcript = transcript_calls(&events);
+        if transcript.is_empty() {
+            calls.extend(hook_calls(&events));
+        } else {
+            calls.extend(transcript);
+        }
+    }
+    calls
+}
+
+fn transcript_calls(events: &[&SessionEvent]) -> Vec<McpCall> {
+    let mut calls: Vec<McpCall> = Vec::new();
+    // Calls waiting for their result, by tool_use ID
+    let mut open: HashMap<&str, usize> = HashMap::new();
+    for event in events {
+        let raw = event.raw_data.as_ref();
+        if let Some(uses) = raw.and_then(|r| r.get("tool_uses")).and_then(Value::as_array) {
+            for tool_use in uses {
+                let Some(call) = tool_use.get("name").and_then(Value::as_str).and_then(|n| McpCall::new(event, n)) else {
+                    continue;
+                };
+                if let Some(id) = tool_use.get("id").and_then(Value::as_str) {
+                    open.insert(id, calls.len());
+                }
+                calls.push(call);
+            }
+        } else if event.event_type == EventType::ResponseGenerated {
+            // Recorded before tool IDs were kept: only the names are known
+            let names = event.content.iter().flat_map(|c| c.lines()).filter_map(|line| {
+                line.strip_prefix("[TOOL: ")?.strip_suffix(']')
+            });
+            calls.extend(names.filter_map(|n| McpCall::new(event, n)));
+        }
+
+        for result in raw.and_then(|r| r.get("tool_results")).and_then(Value::as_array).into_iter().flatten() {
+            let Some(index) = result.get("tool_use_id").and_then(Value::as_str).and_then(|id| open.remove(id)) else {
+                continue;
+            };
+            let call = &mut calls[index];
+            call.duration_ms = Some((event.timestamp - call.timestamp).num_milliseconds().max(0));
+            call.error = result.get("is_error").and_then(Value::as_bool).unwrap_or(false);
+        }
+    }
+    calls
+}
+
+fn hook_calls(events: &[&SessionEvent]) -> Vec<McpCall> {
+    events
+        .iter()
+        .filter(|e| e.event_type == EventType::ToolExecuted)
+        .filter_map(|event| {
+            let mut call = McpCall::new(event, event.tool_name.as_deref()?)?;
+            call.duration_ms = event.duration_ms;
+            call.error = event.exit_code.is_some_and(|c| c != 0) || event.error_message.is_some();
+            Some(call)
+        })
+        .collect()
+}
+
+/// Calls grouped by server, most used first.
+pub fn usage(calls: &[McpCall]) -> Vec<McpServerUsage> {
+    let mut servers: BTreeMap<&str, Vec<&McpCall>> = BTreeMap::new();
+    for call in calls {
+        servers.entry(call.server.as_str()).or_default().push(call);
+    }
+    let mut usage: Vec<McpServerUsage> = servers
+        .into_iter()
+        .map(|(server, calls)| {
+            let mut tools: BTreeMap<&str, Vec<&McpCall>> = BTreeMap::new();
+            for call in &calls {
+                tools.entry(call.tool.as_str()).or_default().push(call);
+            }
+            let mut tools: Vec<McpToolUsage> = tools
+                .into_iter()
+                .map(|(tool, calls)| McpToolUsage {
+                    tool: tool.to_string(),
+                    calls: calls.len(),
+                    errors: calls.iter().filter(|c| c.error).count(),
+                    avg_duration_ms: average(&durations(&calls)),
+                })
+                .collect();
+            tools.sort_by_key(|t| std::cmp::Reverse(t.calls));
+
+            let durations = durations(&calls);
+            let errors = calls.iter().filter(|c| c.error).count();
+            McpServerUsage {
+                server: server.to_string(),
+                calls: calls.len(),
+                errors,
+                error_rate: errors as f64 / calls.len() as f64,
+                avg_duration_ms: average(&durations),
+                p95_duration_ms: durations.get((durations.len() * 95).div_ceil(100).saturating_sub(1)).copied(),
+                sessions: calls.iter().map(|c| c.session_id.as_str()).collect::<HashSet<_>>().len(),
+                last_used: calls.iter().map(|c| c.timestamp).max().unwrap_or_else(Utc::now),
+                tools,
+            }
+        })
+        .collect();
+    usage.sort_by_key(|s| std::cmp::Reverse(s.calls));
+    usage
+}
+
+/// Known latencies, shortest first.
+fn durations(calls: &[&McpCall]) -> Vec<i64> {
+    let mut durations: Vec<i64> = calls.iter().filter_map(|c| c.duration_ms).collect();
+    durations.sort_unstable();
+    durations
+}
+
+fn average(values: &[i64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use serde_json::json;
+
+    fn event(session: &str, event_type: EventType, secs: i64, raw: Value) -> SessionEvent {
+        let mut event = SessionEvent::new(session, event_type, AgentType::ClaudeCode);
+        event.timestamp = DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        event.raw_data = Some(raw);
+        event
+    }
+
+    #[test]
+    fn test_calls_and_usage() {
+        assert_eq!(parse_tool_name("mcp__github__create_issue"), Some(("github", "create_issue")));
+        assert_eq!(parse_tool_name("mcp__claude_ai_Notion__search"), Some(("claude_ai_Notion", "search")));
+        assert_eq!(parse_tool_name("Bash"), None);
+
+        let uses = |calls: &[(&str, &str)]| json!({ "tool_uses": calls.iter().map(|(id, name)| json!({"id": id, "name": name})).collect::<Vec<_>>() });
+        let results = |results: &[(&str, bool)]| json!({ "tool_results": results.iter().map(|(id, err)| json!({"tool_use_id": id, "is_error": err})).collect::<Vec<_>>() });
+
+        let mut legacy = SessionEvent::new("c", EventType::ResponseGenerated, AgentType::ClaudeCode);
+        legacy.content = Some("Looking it up\n\n[TOOL: mcp__github__search]\n{}".to_string());
+        let mut hook = SessionEvent::new("c", EventType::ToolExecuted, AgentType::ClaudeCode);
+        hook.tool_name = Some("mcp__github__search".to_string());
+        let mut hook_only = hook.clone();
+        hook_only.session_id = "d".to_string();
+        hook_only.duration_ms = Some(300);
+        hook_only.exit_code = Some(1);
+
+        let events = vec![
+            event("a", EventType::ResponseGenerated, 0, uses(&[("t1", "mcp__github__search"), ("t2", "Bash")])),
+            event("a", EventType::PromptReceived, 2, results(&[("t1", false), ("t2", true)])),
+            event("a", EventType::ResponseGenerated, 3, uses(&[("t3", "mcp__github__create_issue"), ("t4", "mcp__linear__list")])),
+            event("a", EventType::PromptReceived, 7, results(&[("t4", true)])),
+            event("b", EventType::ResponseGenerated, 0, uses(&[("t1", "mcp__github__search")])),
+            event("b", EventType::PromptReceived, 1, results(&[("t1", false)])),
+            legacy,
+            hook,
+            hook_only,
+        ];
+
+        let calls = calls(&events);
+        // Session c's hook event repeats its transcript call and is skipped
+        assert_eq!(calls.len(), 6);
+        let usage = usage(&calls);
+        assert_eq!(usage.iter().map(|s| s.server.as_str()).collect::<Vec<_>>(), vec!["github", "linear"]);
+
+        let github = &usage[0];
+        assert_eq!((github.calls, github.errors, github.sessions), (5, 1, 4));
+        assert_eq!(github.avg_duration_ms, Some(1100.0));
+        assert_eq!(github.p95_duration_ms, Some(2000));
+        assert_eq!(github.tools[0].tool, "search");
+        assert_eq!(github.tools[0].calls, 4);
+        // The issue was created but its result never arrived
+        assert_eq!(github.tools[1].avg_duration_ms, None);
+
+        let linear = &usage[1];
+        assert_eq!((linear.calls, linear.errors, linear.error_rate), (1, 1, 1.0));
+        assert_eq!(linear.avg_duration_ms, Some(4000.0));
+    }
+}
@@ -3,6 +3,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 /// Types of AI agents that can be monitored.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -53,7 +54,7 @@ impl std::fmt::Display for SessionStatus {
 }
 
 /// Types of events in a session.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EventType {
     SessionStart,
@@ -67,9 +68,49 @@ pub enum EventType {
     FileRead,
     FileModified,
     Error,
+    /// The model provider's API failed (rate limit, overload, server error), including retried requests
+    ApiError,
+    PolicyDecision,
+    /// The conversation was compacted into a summary; detail before this point is lost
+    Compaction,
+    /// Claude Code's settings or shell environment changed while the session ran or before it started
+    ConfigChanged,
+    /// An unusual burst of errors, tool calls or token spend within the session
+    Anomaly,
+    /// The agent repeated near-identical tool calls or edited a file back and forth
+    Loop,
     Custom,
 }
 
+/// Content of a compaction event, e.g. "Context compacted (auto, 167000 tokens before)".
+pub fn describe_compaction(trigger: Option<&str>, tokens_before: Option<i64>) -> String {
+    match (trigger, tokens_before) {
+        (Some(trigger), Some(tokens)) => format!("Context compacted ({}, {} tokens before)", trigger, tokens),
+        (Some(trigger), None) => format!("Context compacted ({})", trigger),
+        (None, Some(tokens)) => format!("Context compacted ({} tokens before)", tokens),
+        (None, None) => "Context compacted".to_string(),
+    }
+}
+
+/// Context utilization at which a session is flagged as close to compaction.
+/// Claude Code compacts automatically a little above this.
+pub const CONTEXT_WARN_RATIO: f64 = 0.8;
+
+/// Context window size in tokens for a model ID.
+pub fn context_window(model_id: Option<&str>) -> i64 {
+    let model = model_id.unwrap_or_default().to_lowercase();
+    if model.contains("[1m]") || model.contains("gemini") {
+        1_000_000
+    } else if model.starts_with("gpt-4.1") {
+        1_047_576
+    } else if model.starts_with("gpt-4o") || model.starts_with("o1") || model.starts_with("o3") {
+        128_000
+    } else {
+        // Claude models, and the default for unknown ones
+        200_000
+    }
+}
+
 /// A unified session across all agent types.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -93,6 +134,50 @@ pub struct Session {
     pub current_task: Option<String>,
     pub progress: f64,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// Short digest written by the summarizer once the session completes
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Tokens in the model's context as of the latest response; None until
+    /// the agent reports usage, and again right after a compaction
+    #[serde(default)]
+    pub context_tokens: Option<i64>,
+    /// Times the conversation has been compacted; maintained by storage as
+    /// compaction events arrive
+    #[serde(default)]
+    pub compactions: i64,
+    /// Responses cut off by the user, a stop hook or an API error;
+    /// maintained by storage as interrupted events arrive
+    #[serde(default)]
+    pub interruptions: i64,
+    /// Git branch checked out in the project, as last seen during the
+    /// session; an update without one keeps the stored branch
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// Person the session is attributed to, so a database shared by a team
+    /// can be split per user; the first user stored is kept
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// User attributed to sessions created by this process, set once at startup.
+static LOCAL_USER: OnceLock<Option<String>> = OnceLock::new();
+
+/// Attribute new sessions to `user` instead of the login name. Only the first
+/// call has an effect.
+pub fn set_local_user(user: Option<String>) {
+    let _ = LOCAL_USER.set(user.filter(|u| !u.trim().is_empty()).or_else(login_name));
+}
+
+/// User new sessions are attributed to: the configured `user`, else the
+/// login name.
+pub fn local_user() -> Option<String> {
+    LOCAL_USER.get_or_init(login_name).clone()
+}
+
+fn login_name() -> Option<String> {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
 }
 
 impl Session {
@@ -120,6 +205,12 @@ impl Session {
             current_task: None,
             progress: 0.0,
             metadata: HashMap::new(),
+            summary: None,
+            context_tokens: None,
+            compactions: 0,
+            interruptions: 0,
+            git_branch: None,
+            user: local_user(),
         }
     }
 
@@ -129,6 +220,17 @@ impl Session {
         self.duration_seconds = (self.last_activity_at - self.started_at).num_seconds() as f64;
     }
 
+    /// Share of the model's context window in use (0–1), if known.
+    pub fn context_utilization(&self) -> Option<f64> {
+        let tokens = self.context_tokens?;
+        Some(tokens as f64 / context_window(self.model_id.as_deref()) as f64)
+    }
+
+    /// Whether the context is full enough that compaction is near.
+    pub fn context_near_limit(&self) -> bool {
+        self.context_utilization().is_some_and(|u| u >= CONTEXT_WARN_RATIO)
+    }
+
     /// End the session.
     pub fn end(&mut self) {
         let now = Utc::now();
@@ -138,6 +240,10 @@ impl Session {
     }
 }
 
+/// Most tool output kept on an event. Transcripts keep the whole output in
+/// the event content, where large content is offloaded to the blob table.
+pub const MAX_TOOL_OUTPUT: usize = 4096;
+
 /// An event within a session.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionEvent {
@@ -154,6 +260,18 @@ pub struct SessionEvent {
     pub tokens_output: Option<i64>,
     pub error_message: Option<String>,
     pub raw_data: Option<serde_json::Value>,
+    /// Arguments of the tool call, as the agent passed them
+    #[serde(default)]
+    pub tool_input: Option<serde_json::Value>,
+    /// What the tool returned, up to `MAX_TOOL_OUTPUT` bytes
+    #[serde(default)]
+    pub tool_output: Option<String>,
+    /// Exit code of a command the tool ran
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// How long the tool ran
+    #[serde(default)]
+    pub duration_ms: Option<i64>,
 }
 
 impl SessionEvent {
@@ -173,6 +291,10 @@ impl SessionEvent {
             tokens_output: None,
             error_message: None,
             raw_data: None,
+            tool_input: None,
+            tool_output: None,
+            exit_code: None,
+            duration_ms: None,
         }
     }
 
@@ -214,8 +336,39 @@ impl SessionEvent {
             tokens_output: None,
             error_message: None,
             raw_data: None,
+            tool_input: None,
+            tool_output: None,
+            exit_code: None,
+            duration_ms: None,
         }
     }
+
+    /// Record `output` as the tool's output, cut to `MAX_TOOL_OUTPUT` bytes.
+    pub fn set_tool_output(&mut self, output: &str) {
+        let mut end = output.len().min(MAX_TOOL_OUTPUT);
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.tool_output = Some(output[..end].to_string());
+    }
+
+    /// Why the response this event marks was cut off (`user`, `stop_hook`,
+    /// `api_error` or `max_tokens`), if it was.
+    pub fn interruption(&self) -> Option<&str> {
+        self.raw_data.as_ref()?.get("interrupted")?.as_str()
+    }
+}
+
+/// Events and tokens one session logged in one hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRollup {
+    /// Start of the hour (UTC)
+    pub hour: DateTime<Utc>,
+    pub session_id: String,
+    pub agent_type: AgentType,
+    pub events: i64,
+    /// Input plus output tokens reported on the events
+    pub tokens: i64,
 }
 
 /// Summary metrics.
@@ -228,3 +381,269 @@ pub struct SummaryMetrics {
     pub total_cost: f64,
     pub today_messages: i64,
 }
+
+/// Session field to count sessions by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionGroup {
+    AgentType,
+    Status,
+    Project,
+}
+
+impl SessionGroup {
+    /// The session's value of this field.
+    pub fn key(self, session: &Session) -> String {
+        match self {
+            SessionGroup::AgentType => session.agent_type.to_string(),
+            SessionGroup::Status => session.status.to_string(),
+            SessionGroup::Project => session.project_path.clone(),
+        }
+    }
+}
+
+/// What the content blob table holds and what sharing it saves.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BlobStats {
+    /// Distinct contents stored
+    pub blobs: i64,
+    /// Their size in the table, compressed
+    pub stored_bytes: i64,
+    /// Their size uncompressed
+    pub content_bytes: i64,
+    /// Events pointing at a blob
+    pub references: i64,
+    /// What those events' content would take stored inline
+    pub referenced_bytes: i64,
+}
+
+impl BlobStats {
+    /// Bytes not written thanks to deduplication and compression.
+    pub fn saved_bytes(&self) -> i64 {
+        self.referenced_bytes - self.stored_bytes
+    }
+}
+
+/// A session moved to cold storage. Its row stays behind so it can still be
+/// found and restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedSession {
+    /// The session as it was when archived
+    pub session: Session,
+    /// Opening prompt, for finding the session later
+    pub first_prompt: Option<String>,
+    /// Where the bundle was written (`s3://`, `gs://` or a file path)
+    pub location: String,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// A cleared session kept in the trash until its grace period runs out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedSession {
+    /// The session as it was when cleared
+    pub session: Session,
+    /// Where its events and tags were written
+    pub location: String,
+    /// What removed it: `clear` or `prune`
+    pub reason: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A mutating IPC request, kept in the audit log whether or not it ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// User the connecting process ran as
+    pub uid: u32,
+    pub action: String,
+    /// The request without its action
+    pub params: serde_json::Value,
+    /// "ok", "denied", or the error it failed with
+    pub outcome: String,
+}
+
+impl AuditEntry {
+    pub fn new(uid: u32, action: &str, params: serde_json::Value, outcome: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            uid,
+            action: action.to_string(),
+            params,
+            outcome: outcome.to_string(),
+        }
+    }
+}
+
+/// One run of the daemon, from start to stop or crash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaemonRun {
+    pub id: String,
+    pub pid: u32,
+    /// agent-monitor version
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+    /// Last heartbeat; a crashed run ended around then
+    pub last_seen_at: DateTime<Utc>,
+    /// None while running, or if it crashed and no later run noticed yet
+    pub stopped_at: Option<DateTime<Utc>>,
+    /// Whether it ended without shutting down
+    pub crashed: bool,
+}
+
+impl DaemonRun {
+    /// A run of this process starting now.
+    pub fn start() -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: now,
+            last_seen_at: now,
+            stopped_at: None,
+            crashed: false,
+        }
+    }
+}
+
+/// Size and shape of the database, for deciding what to prune.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DatabaseStats {
+    pub backend: String,
+    pub size_bytes: i64,
+    /// Share of the database that VACUUM would reclaim: free pages in SQLite,
+    /// dead rows in Postgres
+    pub fragmentation: f64,
+    pub tables: Vec<TableStats>,
+    pub indexes: Vec<IndexStats>,
+    /// Problems found checking the indexes; empty when they are sound
+    pub index_problems: Vec<String>,
+    /// Sessions taking the most space, largest first
+    pub heaviest_sessions: Vec<SessionSize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: i64,
+    /// None when the backend can't measure it
+    pub bytes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub name: String,
+    pub table: String,
+    pub bytes: Option<i64>,
+}
+
+/// Bytes a session's events take: their content, errors and raw data, and
+/// the blobs they point to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSize {
+    pub session_id: String,
+    pub project_path: Option<String>,
+    pub events: i64,
+    pub stored_bytes: i64,
+}
+
+/// Memory entry for cross-session persistence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// CPU and memory of a session's agent processes (and their children) at
+/// one moment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceSample {
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Summed over the processes; 100 is one full core
+    pub cpu_percent: f64,
+    /// Resident memory in bytes
+    pub memory_bytes: i64,
+    pub process_count: i64,
+}
+
+/// Network traffic of a session's agent processes over one sampling interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSample {
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+    /// The part of the traffic exchanged with the configured API hosts
+    pub api_bytes_sent: i64,
+    pub api_bytes_received: i64,
+    /// Connections opened since the previous sample; a burst suggests retries
+    pub new_connections: i64,
+}
+
+/// An upstream log file a session's events were read from, and how far.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSource {
+    pub session_id: String,
+    pub path: String,
+    /// Byte offset just past the last line read
+    pub offset: i64,
+    /// Entries read from the file for this session
+    pub entries: i64,
+    pub first_read_at: DateTime<Utc>,
+    pub last_read_at: DateTime<Utc>,
+}
+
+/// A label attached to a session, by hand or by a rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTag {
+    pub session_id: String,
+    pub tag: String,
+    /// "manual", "rule:<name>" for tags added by an automation rule, or
+    /// "ipc" for tags added by a tool over the socket
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Longest tag accepted.
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Canonical form of a tag: trimmed and lowercased. None if empty, too long,
+/// or containing anything but letters, digits and `-_.:/`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    let valid = !tag.is_empty()
+        && tag.chars().count() <= MAX_TAG_LEN
+        && tag.chars().all(|c| c.is_alphanumeric() || "-_.:/".contains(c));
+    valid.then_some(tag)
+}
+
+impl SessionTag {
+    pub fn new(session_id: &str, tag: &str, source: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            tag: tag.to_string(),
+            source: source.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Embedding of one prompt or response, for semantic search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEmbedding {
+    pub event_id: String,
+    pub session_id: String,
+    /// Embedding model that produced `vector`; vectors from different models aren't comparable
+    pub model: String,
+    pub vector: Vec<f32>,
+    /// Start of the embedded text, shown in search results
+    pub snippet: String,
+    pub created_at: DateTime<Utc>,
+}
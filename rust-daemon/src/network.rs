@@ -0,0 +1,289 @@
+//! Network usage of agent sessions (optional, Linux only).
+//!
+//! Every `interval_secs` the sampler reads TCP socket statistics from `ss`
+//! (iproute2), which reports each connection's owning processes and byte
+//! counters. Connections owned by a session's agent processes or their
+//! children are attributed to it, and the bytes moved since the previous
+//! sample are stored along with the part exchanged with the API hosts. The
+//! counts are estimates: bytes moved by a connection after the last sample
+//! before it closed are missed. Many new connections with little traffic
+//! usually mean the agent is stuck retrying.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use tokio::process::Command;
+use tokio::time::{interval, Duration};
+use tracing::{debug, warn};
+
+use crate::models::NetworkSample;
+use crate::procwatch::{descendants, ProcessWatcher};
+use crate::resources::session_roots;
+use crate::storage::Storage;
+
+/// Most active sessions sampled per round.
+const MAX_ACTIVE: usize = 500;
+
+/// Network sampling settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Whether agents' network traffic is sampled (requires `ss`)
+    pub enabled: bool,
+
+    /// Seconds between samples
+    pub interval_secs: u64,
+
+    /// Hosts whose traffic is also counted separately as API traffic
+    pub api_hosts: Vec<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 30,
+            api_hosts: vec!["api.anthropic.com".to_string()],
+        }
+    }
+}
+
+/// One TCP connection as reported by `ss`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Connection {
+    local: String,
+    peer: String,
+    peer_ip: Option<IpAddr>,
+    pids: Vec<u32>,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+impl Connection {
+    fn key(&self) -> (String, String) {
+        (self.local.clone(), self.peer.clone())
+    }
+}
+
+/// Byte counters of each connection at the previous sample.
+type Counters = HashMap<(String, String), (u64, u64)>;
+
+/// Periodically records the network traffic of active sessions.
+pub struct NetworkSampler {
+    config: NetworkConfig,
+    storage: Storage,
+    processes: ProcessWatcher,
+    previous: Option<Counters>,
+}
+
+impl NetworkSampler {
+    pub fn new(config: NetworkConfig, storage: Storage, processes: ProcessWatcher) -> Self {
+        Self {
+            config,
+            storage,
+            processes,
+            previous: None,
+        }
+    }
+
+    /// Sample until the daemon stops, or until `ss` turns out to be unusable.
+    pub async fn run(mut self) {
+        if !cfg!(target_os = "linux") {
+            warn!("Network tracking is only supported on Linux");
+            return;
+        }
+        let mut ticker = interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            let connections = match read_connections().await {
+                Ok(connections) => connections,
+                Err(e) => {
+                    warn!("Network tracking stopped: {:#}", e);
+                    return;
+                }
+            };
+            if let Err(e) = self.sample(connections).await {
+                warn!("Network sampling failed: {}", e);
+            }
+        }
+    }
+
+    async fn sample(&mut self, connections: Vec<Connection>) -> Result<()> {
+        let counters: Counters = connections
+            .iter()
+            .map(|c| (c.key(), (c.bytes_sent, c.bytes_received)))
+            .collect();
+        // The first round only records where each connection's counters start
+        let Some(previous) = self.previous.replace(counters) else {
+            return Ok(());
+        };
+
+        let sessions = self.storage.get_active_sessions(MAX_ACTIVE).await?;
+        if sessions.is_empty() {
+            return Ok(());
+        }
+        let api_ips = resolve(&self.config.api_hosts).await;
+        let processes = self.processes.snapshot();
+        let now = Utc::now();
+
+        for session in &sessions {
+            let tree: HashSet<u32> = descendants(&processes, &session_roots(session, &processes))
+                .into_iter()
+                .collect();
+            if tree.is_empty() {
+                continue;
+            }
+            let mut sample = attribute(&connections, &previous, &tree, &api_ips);
+            if sample.bytes_sent + sample.bytes_received + sample.new_connections == 0 {
+                continue;
+            }
+            sample.session_id = session.id.clone();
+            sample.timestamp = now;
+            self.storage.insert_network_sample(&sample).await?;
+        }
+        debug!("Sampled network traffic of {} connections", connections.len());
+        Ok(())
+    }
+}
+
+/// Current TCP connections with their owners and byte counters.
+async fn read_connections() -> Result<Vec<Connection>> {
+    let output = Command::new("ss")
+        .args(["-tinpH"])
+        .output()
+        .await
+        .context("could not run ss (is iproute2 installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!("ss failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_ss(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Addresses of the API hosts. Hosts that fail to resolve are skipped.
+async fn resolve(hosts: &[String]) -> HashSet<IpAddr> {
+    let mut ips = HashSet::new();
+    for host in hosts {
+        match tokio::net::lookup_host((host.as_str(), 443)).await {
+            Ok(addrs) => ips.extend(addrs.map(|a| a.ip().to_canonical())),
+            Err(e) => debug!("Could not resolve {}: {}", host, e),
+        }
+    }
+    ips
+}
+
+/// Parse `ss -tinpH`: a line per connection, followed by an indented line of
+/// TCP info holding its byte counters.
+fn parse_ss(output: &str) -> Vec<Connection> {
+    let mut connections: Vec<Connection> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if let Some(connection) = connections.last_mut() {
+                for field in line.split_whitespace() {
+                    if let Some(bytes) = field.strip_prefix("bytes_sent:") {
+                        connection.bytes_sent = bytes.parse().unwrap_or(0);
+                    } else if let Some(bytes) = field.strip_prefix("bytes_received:") {
+                        connection.bytes_received = bytes.parse().unwrap_or(0);
+                    }
+                }
+            }
+            continue;
+        }
+
+        // State, Recv-Q, Send-Q, local address, peer address, process
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            continue;
+        }
+        connections.push(Connection {
+            local: fields[3].to_string(),
+            peer: fields[4].to_string(),
+            peer_ip: parse_ip(fields[4]),
+            pids: line
+                .split("pid=")
+                .skip(1)
+                .filter_map(|rest| rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok())
+                .collect(),
+            ..Connection::default()
+        });
+    }
+    connections
+}
+
+/// The IP of an `ss` address such as `10.0.0.1:443` or `[::ffff:10.0.0.1]:443`.
+fn parse_ip(address: &str) -> Option<IpAddr> {
+    let (host, _port) = address.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next()?;
+    host.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+/// Traffic of the connections owned by `tree` since `previous`. A connection
+/// not seen before is new, and all its bytes count.
+fn attribute(
+    connections: &[Connection],
+    previous: &Counters,
+    tree: &HashSet<u32>,
+    api_ips: &HashSet<IpAddr>,
+) -> NetworkSample {
+    let mut sample = NetworkSample {
+        session_id: String::new(),
+        timestamp: Utc::now(),
+        bytes_sent: 0,
+        bytes_received: 0,
+        api_bytes_sent: 0,
+        api_bytes_received: 0,
+        new_connections: 0,
+    };
+    for connection in connections.iter().filter(|c| c.pids.iter().any(|pid| tree.contains(pid))) {
+        let (sent_before, received_before) = match previous.get(&connection.key()) {
+            Some(counters) => *counters,
+            None => {
+                sample.new_connections += 1;
+                (0, 0)
+            }
+        };
+        let sent = connection.bytes_sent.saturating_sub(sent_before) as i64;
+        let received = connection.bytes_received.saturating_sub(received_before) as i64;
+        sample.bytes_sent += sent;
+        sample.bytes_received += received;
+        if connection.peer_ip.is_some_and(|ip| api_ips.contains(&ip)) {
+            sample.api_bytes_sent += sent;
+            sample.api_bytes_received += received;
+        }
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_attribute() {
+        let output = "\
+ESTAB 0      0      10.0.0.5:51000   [::ffff:160.79.104.10]:443 users:((\"claude\",pid=10,fd=22))
+\t cubic wscale:7,7 rto:204 bytes_sent:5000 bytes_acked:5000 bytes_received:90000 segs_out:40
+ESTAB 0      0      10.0.0.5:51002   140.82.112.3:443 users:((\"git\",pid=12,fd=3),(\"git\",pid=13,fd=3))
+\t cubic bytes_sent:700 bytes_received:1200
+ESTAB 0      0      10.0.0.5:51004   10.0.0.9:5432 users:((\"postgres\",pid=99,fd=9))
+\t cubic bytes_sent:1 bytes_received:1
+";
+        let connections = parse_ss(output);
+        assert_eq!(connections.len(), 3);
+        assert_eq!(connections[0].peer_ip, "160.79.104.10".parse().ok());
+        assert_eq!(connections[1].pids, vec![12, 13]);
+        assert_eq!((connections[0].bytes_sent, connections[0].bytes_received), (5000, 90000));
+
+        let previous: Counters = HashMap::from([(connections[0].key(), (4000, 60000))]);
+        let tree: HashSet<u32> = [10, 11, 12].into_iter().collect();
+        let api_ips: HashSet<IpAddr> = ["160.79.104.10".parse().unwrap()].into_iter().collect();
+
+        let sample = attribute(&connections, &previous, &tree, &api_ips);
+        assert_eq!(sample.bytes_sent, 1000 + 700);
+        assert_eq!(sample.bytes_received, 30000 + 1200);
+        assert_eq!((sample.api_bytes_sent, sample.api_bytes_received), (1000, 30000));
+        assert_eq!(sample.new_connections, 1);
+    }
+}
@@ -0,0 +1,274 @@
+//! Notification delivery: the local desktop plus HTTP push services.
+//!
+//! Push channels are declared once in the config under `notification_channels`
+//! and referenced by name from rule `notify` actions, so each trigger can
+//! pick where its alert goes:
+//!
+//! ```json
+//! "notification_channels": [
+//!   { "name": "phone", "provider": "ntfy", "topic": "my-agents" },
+//!   { "name": "pushover", "provider": "pushover", "token_env": "PUSHOVER_TOKEN", "user_key": "u123" },
+//!   { "name": "gotify", "provider": "gotify", "server": "https://gotify.example.com", "token_env": "GOTIFY_TOKEN" }
+//! ]
+//! ```
+//!
+//! Tokens can be given inline (`token`) or read from an environment variable
+//! (`token_env`) when the message is sent.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+/// HTTP push service settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum PushProvider {
+    /// ntfy.sh or a self-hosted ntfy server
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        /// Access token for protected topics
+        #[serde(default)]
+        token: Option<String>,
+        #[serde(default)]
+        token_env: Option<String>,
+        /// 1 (min) to 5 (max)
+        #[serde(default)]
+        priority: Option<u8>,
+    },
+    /// Pushover application token and user (or group) key
+    Pushover {
+        #[serde(default)]
+        token: Option<String>,
+        #[serde(default)]
+        token_env: Option<String>,
+        user_key: String,
+        /// -2 (lowest) to 1 (high)
+        #[serde(default)]
+        priority: Option<i8>,
+    },
+    /// Self-hosted Gotify server with an application token
+    Gotify {
+        server: String,
+        #[serde(default)]
+        token: Option<String>,
+        #[serde(default)]
+        token_env: Option<String>,
+        /// 0 to 10
+        #[serde(default)]
+        priority: Option<u8>,
+    },
+}
+
+impl PushProvider {
+    fn kind(&self) -> &'static str {
+        match self {
+            PushProvider::Ntfy { .. } => "ntfy",
+            PushProvider::Pushover { .. } => "pushover",
+            PushProvider::Gotify { .. } => "gotify",
+        }
+    }
+}
+
+/// A named push channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub name: String,
+    #[serde(flatten)]
+    pub provider: PushProvider,
+}
+
+impl NotificationChannel {
+    /// Provider and destination, for display.
+    pub fn describe(&self) -> String {
+        match &self.provider {
+            PushProvider::Ntfy { server, topic, .. } => format!("ntfy {}/{}", server.trim_end_matches('/'), topic),
+            PushProvider::Pushover { .. } => "pushover".to_string(),
+            PushProvider::Gotify { server, .. } => format!("gotify {}", server),
+        }
+    }
+}
+
+/// Resolve an inline token or one read from `token_env`.
+fn resolve_token(token: &Option<String>, token_env: &Option<String>) -> Result<Option<String>> {
+    if let Some(token) = token {
+        return Ok(Some(token.clone()));
+    }
+    match token_env {
+        Some(var) => std::env::var(var)
+            .map(Some)
+            .map_err(|_| anyhow!("{} is not set", var)),
+        None => Ok(None),
+    }
+}
+
+/// Sends notifications to the desktop or a named push channel.
+#[derive(Clone)]
+pub struct Notifier {
+    channels: Arc<HashMap<String, NotificationChannel>>,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(channels: &[NotificationChannel]) -> Self {
+        Self {
+            channels: Arc::new(channels.iter().map(|c| (c.name.clone(), c.clone())).collect()),
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Fail unless `channel` is configured.
+    pub fn check_channel(&self, channel: &str) -> Result<()> {
+        if !self.channels.contains_key(channel) {
+            bail!("Unknown notification channel '{}'", channel);
+        }
+        Ok(())
+    }
+
+    /// Send to `channel`, or to the desktop when None.
+    pub async fn send(&self, channel: Option<&str>, title: &str, message: &str) -> Result<()> {
+        let Some(name) = channel else {
+            return desktop(title, message).await;
+        };
+        let channel = self
+            .channels
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown notification channel '{}'", name))?;
+
+        let request = match &channel.provider {
+            PushProvider::Ntfy { server, topic, token, token_env, priority } => {
+                let mut request = self
+                    .client
+                    .post(format!("{}/{}", server.trim_end_matches('/'), topic))
+                    .header("Title", title)
+                    .body(message.to_string());
+                if let Some(priority) = priority {
+                    request = request.header("Priority", priority.to_string());
+                }
+                if let Some(token) = resolve_token(token, token_env)? {
+                    request = request.bearer_auth(token);
+                }
+                request
+            }
+            PushProvider::Pushover { token, token_env, user_key, priority } => {
+                let token = resolve_token(token, token_env)?
+                    .ok_or_else(|| anyhow!("Pushover channel '{}' needs token or token_env", name))?;
+                let mut form = vec![
+                    ("token", token),
+                    ("user", user_key.clone()),
+                    ("title", title.to_string()),
+                    ("message", message.to_string()),
+                ];
+                if let Some(priority) = priority {
+                    form.push(("priority", priority.to_string()));
+                }
+                self.client.post(PUSHOVER_URL).form(&form)
+            }
+            PushProvider::Gotify { server, token, token_env, priority } => {
+                let token = resolve_token(token, token_env)?
+                    .ok_or_else(|| anyhow!("Gotify channel '{}' needs token or token_env", name))?;
+                self.client
+                    .post(format!("{}/message", server.trim_end_matches('/')))
+                    .header("X-Gotify-Key", token)
+                    .json(&serde_json::json!({
+                        "title": title,
+                        "message": message,
+                        "priority": priority.unwrap_or(5),
+                    }))
+            }
+        };
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {} for channel '{}'", channel.provider.kind(), name))?;
+        if !response.status().is_success() {
+            bail!("{} returned status {} for channel '{}'", channel.provider.kind(), response.status(), name);
+        }
+        Ok(())
+    }
+}
+
+/// Show a desktop notification with the platform's native tool.
+async fn desktop(title: &str, message: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        // Passed as arguments, so nothing in them is read as AppleScript
+        let mut command = tokio::process::Command::new("osascript");
+        command
+            .args(["-e", "on run argv", "-e", "display notification (item 1 of argv) with title (item 2 of argv)"])
+            .args(["-e", "end run", message, title]);
+        command
+    } else {
+        let mut command = tokio::process::Command::new("notify-send");
+        command.arg(title).arg(message);
+        command
+    };
+    let status = command
+        .status()
+        .await
+        .context("Failed to run the desktop notification command")?;
+    if !status.success() {
+        bail!("Notification command exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Path, http::HeaderMap, routing::post, Router};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_ntfy_and_gotify_requests() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let ntfy_tx = tx.clone();
+        let app = Router::new()
+            .route(
+                "/message",
+                post(move |headers: HeaderMap, body: String| async move {
+                    let key = headers.get("X-Gotify-Key").unwrap().to_str().unwrap().to_string();
+                    tx.send(format!("gotify {} {}", key, body)).unwrap();
+                }),
+            )
+            .route(
+                "/:topic",
+                post(move |Path(topic): Path<String>, headers: HeaderMap, body: String| async move {
+                    let title = headers.get("Title").unwrap().to_str().unwrap().to_string();
+                    ntfy_tx.send(format!("ntfy {} {} {}", topic, title, body)).unwrap();
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let channels: Vec<NotificationChannel> = serde_json::from_value(serde_json::json!([
+            { "name": "phone", "provider": "ntfy", "server": server, "topic": "agents" },
+            { "name": "home", "provider": "gotify", "server": server, "token": "app-token" },
+        ]))
+        .unwrap();
+        let notifier = Notifier::new(&channels);
+
+        notifier.send(Some("phone"), "Cost", "Session over $5").await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "ntfy agents Cost Session over $5");
+
+        notifier.send(Some("home"), "Idle", "Session idle").await.unwrap();
+        let gotify = rx.recv().await.unwrap();
+        assert!(gotify.starts_with("gotify app-token"));
+        assert!(gotify.contains("\"message\":\"Session idle\""));
+
+        assert!(notifier.check_channel("pager").is_err());
+        assert!(notifier.send(Some("pager"), "x", "y").await.is_err());
+    }
+}
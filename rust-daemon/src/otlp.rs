@@ -0,0 +1,568 @@
+//! OTLP/HTTP receiver for agents that emit OpenTelemetry.
+//!
+//! Claude Code (and other agents) can export their telemetry over OTLP
+//! instead of leaving transcripts on disk, which also works from containers
+//! and remote machines. When enabled, the daemon listens on `/v1/logs` and
+//! `/v1/metrics` (JSON encoding, `OTEL_EXPORTER_OTLP_PROTOCOL=http/json`).
+//!
+//! Each log record with a `session.id` becomes an event on the session of that
+//! ID: prompts, API requests (tokens and cost), API errors, tool results and
+//! tool decisions. Metrics only keep their sessions alive, since the same
+//! usage is reported in the logs. The project comes from the `project.path`
+//! or `process.cwd` resource attribute, e.g.
+//! `OTEL_RESOURCE_ATTRIBUTES=project.path=$PWD`. Counters are only updated on
+//! sessions first seen here, so a session that is also file-watched is not
+//! counted twice.
+
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::events::EventBus;
+use crate::models::{AgentType, EventType, Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+/// OTLP receiver settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// Whether the daemon accepts OTLP telemetry
+    pub enabled: bool,
+
+    /// Address to listen on; use 0.0.0.0 to accept remote agents
+    pub host: String,
+
+    /// Port to listen on (4318 is the OTLP/HTTP default)
+    pub port: u16,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 4318,
+        }
+    }
+}
+
+/// Sessions searched for an agent's session ID the first time it is seen.
+const SCAN_LIMIT: usize = 500;
+
+/// One log record from an agent, with resource and record attributes merged.
+#[derive(Debug, Clone)]
+pub struct TelemetryRecord {
+    pub agent_type: AgentType,
+    pub session_id: String,
+    pub project: Option<String>,
+    /// Person the agent reported, for sessions first seen here
+    pub user: Option<String>,
+    /// Event name without the agent prefix, e.g. `api_request`
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    pub attributes: Map<String, Value>,
+}
+
+impl TelemetryRecord {
+    fn text(&self, key: &str) -> Option<&str> {
+        self.attributes.get(key).and_then(Value::as_str)
+    }
+
+    /// Numeric attribute; OTLP sends 64-bit integers as strings.
+    fn number(&self, key: &str) -> Option<f64> {
+        match self.attributes.get(key)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn count(&self, key: &str) -> Option<i64> {
+        self.number(key).map(|n| n as i64)
+    }
+
+    fn flag(&self, key: &str) -> Option<bool> {
+        match self.attributes.get(key)? {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Records in an OTLP/JSON logs payload that carry a session ID.
+pub fn parse_logs(payload: &Value) -> Vec<TelemetryRecord> {
+    let mut records = Vec::new();
+    for resource_logs in array(payload, "resourceLogs") {
+        let resource = attributes(resource_logs.pointer("/resource/attributes"));
+        for scope_logs in array(resource_logs, "scopeLogs") {
+            for log in array(scope_logs, "logRecords") {
+                let mut attrs = resource.clone();
+                attrs.extend(attributes(log.get("attributes")));
+
+                let Some(session_id) = attrs.get("session.id").and_then(Value::as_str).map(str::to_string) else {
+                    continue;
+                };
+                let name = attrs
+                    .get("event.name")
+                    .and_then(Value::as_str)
+                    .or_else(|| log.pointer("/body/stringValue").and_then(Value::as_str))
+                    .unwrap_or("log");
+                let name = name.rsplit('.').next().unwrap_or(name).to_string();
+                let timestamp = ["timeUnixNano", "observedTimeUnixNano"]
+                    .iter()
+                    .find_map(|key| unix_nanos(log.get(*key)?))
+                    .unwrap_or_else(Utc::now);
+
+                records.push(TelemetryRecord {
+                    agent_type: agent_type(attrs.get("service.name").and_then(Value::as_str)),
+                    session_id,
+                    project: project(&attrs),
+                    user: user(&attrs),
+                    name,
+                    timestamp,
+                    attributes: attrs,
+                });
+            }
+        }
+    }
+    records
+}
+
+/// Sessions mentioned in an OTLP/JSON metrics payload, as
+/// (agent, session ID, project, user).
+pub fn parse_metrics(payload: &Value) -> Vec<(AgentType, String, Option<String>, Option<String>)> {
+    let mut sessions = Vec::new();
+    for resource_metrics in array(payload, "resourceMetrics") {
+        let resource = attributes(resource_metrics.pointer("/resource/attributes"));
+        for scope_metrics in array(resource_metrics, "scopeMetrics") {
+            for metric in array(scope_metrics, "metrics") {
+                let points = ["sum", "gauge", "histogram"]
+                    .iter()
+                    .filter_map(|kind| metric.get(*kind))
+                    .flat_map(|data| array(data, "dataPoints"));
+                for point in points {
+                    let mut attrs = resource.clone();
+                    attrs.extend(attributes(point.get("attributes")));
+                    let Some(session_id) = attrs.get("session.id").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    let entry = (
+                        agent_type(attrs.get("service.name").and_then(Value::as_str)),
+                        session_id.to_string(),
+                        project(&attrs),
+                        user(&attrs),
+                    );
+                    if !sessions.contains(&entry) {
+                        sessions.push(entry);
+                    }
+                }
+            }
+        }
+    }
+    sessions
+}
+
+/// The event recorded for a telemetry record.
+pub fn to_event(record: &TelemetryRecord, session_id: &str) -> SessionEvent {
+    let tool_name = record.text("tool_name").map(str::to_string);
+    let (event_type, content) = match record.name.as_str() {
+        "user_prompt" => (
+            EventType::PromptReceived,
+            record.text("prompt").map(str::to_string).unwrap_or_else(|| {
+                format!("Prompt ({} chars)", record.count("prompt_length").unwrap_or(0))
+            }),
+        ),
+        "api_request" => (
+            EventType::ResponseGenerated,
+            format!(
+                "{} · ${:.4} · {}ms",
+                record.text("model").unwrap_or("unknown model"),
+                record.number("cost_usd").unwrap_or(0.0),
+                record.count("duration_ms").unwrap_or(0)
+            ),
+        ),
+        "api_error" => (
+            EventType::Error,
+            format!(
+                "API error {}: {}",
+                record.count("status_code").map_or_else(|| "-".to_string(), |c| c.to_string()),
+                record.text("error").unwrap_or("unknown")
+            ),
+        ),
+        "tool_result" => (
+            EventType::ToolComplete,
+            format!(
+                "{} {} in {}ms",
+                tool_name.as_deref().unwrap_or("tool"),
+                if record.flag("success") == Some(false) { "failed" } else { "succeeded" },
+                record.count("duration_ms").unwrap_or(0)
+            ),
+        ),
+        "tool_decision" => (
+            EventType::Custom,
+            format!(
+                "{} {} ({})",
+                record.text("decision").unwrap_or("decided").to_uppercase(),
+                tool_name.as_deref().unwrap_or("tool"),
+                record.text("source").unwrap_or("unknown")
+            ),
+        ),
+        other => (EventType::Custom, other.to_string()),
+    };
+
+    let mut event = SessionEvent::new_with_stable_id(
+        session_id,
+        event_type,
+        record.agent_type,
+        record.timestamp,
+        Some(&content),
+    );
+    event.tool_name = tool_name;
+    event.working_directory = record.project.clone();
+    if record.name == "api_request" {
+        event.tokens_input = record.count("input_tokens");
+        event.tokens_output = record.count("output_tokens");
+    }
+    if record.name == "api_error" || record.flag("success") == Some(false) {
+        event.error_message = record.text("error").map(str::to_string);
+    }
+    event.raw_data = Some(serde_json::json!({
+        "source": "otlp",
+        "event": record.name,
+        "attributes": record.attributes,
+    }));
+    event
+}
+
+/// Fold a telemetry record into its session's counters.
+pub fn apply(session: &mut Session, record: &TelemetryRecord) {
+    if record.timestamp > session.last_activity_at {
+        session.last_activity_at = record.timestamp;
+        session.duration_seconds = (session.last_activity_at - session.started_at).num_seconds() as f64;
+    }
+    session.status = SessionStatus::Active;
+
+    match record.name.as_str() {
+        "user_prompt" => session.message_count += 1,
+        "api_request" => {
+            session.message_count += 1;
+            let input = record.count("input_tokens").unwrap_or(0);
+            session.tokens_input += input;
+            session.tokens_output += record.count("output_tokens").unwrap_or(0);
+            session.estimated_cost += record.number("cost_usd").unwrap_or(0.0);
+            session.context_tokens = Some(
+                input
+                    + record.count("cache_read_tokens").unwrap_or(0)
+                    + record.count("cache_creation_tokens").unwrap_or(0),
+            );
+            if let Some(model) = record.text("model") {
+                session.model_id = Some(model.to_string());
+            }
+        }
+        "tool_result" => session.tool_call_count += 1,
+        _ => {}
+    }
+}
+
+/// A session the receiver has seen, and whether it owns the counters.
+struct TrackedSession {
+    session: Session,
+    owned: bool,
+}
+
+/// OTLP/HTTP listener that turns agent telemetry into sessions and events.
+#[derive(Clone)]
+pub struct OtlpReceiver {
+    config: OtlpConfig,
+    storage: Storage,
+    event_bus: EventBus,
+    /// Sessions by the agent's session ID
+    sessions: Arc<Mutex<HashMap<String, TrackedSession>>>,
+}
+
+impl OtlpReceiver {
+    pub fn new(config: OtlpConfig, storage: Storage, event_bus: EventBus) -> Self {
+        Self {
+            config,
+            storage,
+            event_bus,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Serve until the listener fails.
+    pub async fn run(self) -> Result<()> {
+        let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port).parse()?;
+        let app = Router::new()
+            .route("/v1/logs", post(logs_handler))
+            .route("/v1/metrics", post(metrics_handler))
+            .with_state(self);
+
+        info!("OTLP receiver listening on http://{}", addr);
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app).await?;
+        Ok(())
+    }
+
+    async fn ingest_logs(&self, records: Vec<TelemetryRecord>) -> Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        for record in records {
+            let tracked = self
+                .tracked(&mut sessions, record.agent_type, &record.session_id, record.project.as_deref(), record.user.as_deref())
+                .await?;
+            let event = to_event(&record, &tracked.session.id);
+            if tracked.owned {
+                apply(&mut tracked.session, &record);
+                self.storage.upsert_session(&tracked.session).await?;
+            }
+            self.storage.record_event(event, &self.event_bus).await?;
+        }
+        Ok(())
+    }
+
+    async fn ingest_metrics(&self, seen: Vec<(AgentType, String, Option<String>, Option<String>)>) -> Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        for (agent_type, session_id, project, user) in seen {
+            let tracked = self
+                .tracked(&mut sessions, agent_type, &session_id, project.as_deref(), user.as_deref())
+                .await?;
+            if tracked.owned {
+                tracked.session.update_activity();
+                tracked.session.status = SessionStatus::Active;
+                self.storage.upsert_session(&tracked.session).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The tracked session for an agent session ID: cached, already stored
+    /// (by another adapter, or before a restart), or new.
+    async fn tracked<'a>(
+        &self,
+        sessions: &'a mut HashMap<String, TrackedSession>,
+        agent_type: AgentType,
+        session_id: &str,
+        project: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<&'a mut TrackedSession> {
+        if !sessions.contains_key(session_id) {
+            let stored = self
+                .storage
+                .get_all_sessions(SCAN_LIMIT)
+                .await?
+                .into_iter()
+                .find(|s| s.external_id == session_id && s.agent_type == agent_type);
+            let tracked = match stored {
+                Some(session) => TrackedSession {
+                    owned: session.metadata.get("source").and_then(Value::as_str) == Some("otlp"),
+                    session,
+                },
+                None => {
+                    let mut session = Session::new(agent_type, project.unwrap_or("unknown"), session_id);
+                    session.metadata.insert("source".to_string(), Value::String("otlp".to_string()));
+                    // Telemetry may come from other machines than this one
+                    if let Some(user) = user {
+                        session.user = Some(user.to_string());
+                    }
+                    self.storage.upsert_session(&session).await?;
+                    TrackedSession { session, owned: true }
+                }
+            };
+            sessions.insert(session_id.to_string(), tracked);
+        }
+        Ok(sessions.get_mut(session_id).expect("inserted above"))
+    }
+}
+
+async fn logs_handler(State(receiver): State<OtlpReceiver>, headers: HeaderMap, body: Bytes) -> Response {
+    let payload = match decode(&headers, &body) {
+        Ok(payload) => payload,
+        Err(rejection) => return rejection.into_response(),
+    };
+    match receiver.ingest_logs(parse_logs(&payload)).await {
+        Ok(()) => Json(serde_json::json!({})).into_response(),
+        Err(e) => failure(e),
+    }
+}
+
+async fn metrics_handler(State(receiver): State<OtlpReceiver>, headers: HeaderMap, body: Bytes) -> Response {
+    let payload = match decode(&headers, &body) {
+        Ok(payload) => payload,
+        Err(rejection) => return rejection.into_response(),
+    };
+    match receiver.ingest_metrics(parse_metrics(&payload)).await {
+        Ok(()) => Json(serde_json::json!({})).into_response(),
+        Err(e) => failure(e),
+    }
+}
+
+/// Parse a JSON request body, rejecting the protobuf encoding.
+fn decode(headers: &HeaderMap, body: &[u8]) -> std::result::Result<Value, (StatusCode, String)> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json");
+    if content_type.contains("protobuf") {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Only OTLP/JSON is supported; set OTEL_EXPORTER_OTLP_PROTOCOL=http/json".to_string(),
+        ));
+    }
+    serde_json::from_slice(body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid OTLP/JSON: {}", e)))
+}
+
+fn failure(e: anyhow::Error) -> Response {
+    warn!("Failed to ingest OTLP telemetry: {}", e);
+    (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response()
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Flatten an OTLP `KeyValue` list into plain JSON values.
+fn attributes(list: Option<&Value>) -> Map<String, Value> {
+    list.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|kv| Some((kv.get("key")?.as_str()?.to_string(), any_value(kv.get("value")?))))
+        .collect()
+}
+
+/// Convert an OTLP `AnyValue` to plain JSON.
+fn any_value(value: &Value) -> Value {
+    if let Some(v) = value.get("stringValue").or_else(|| value.get("boolValue")) {
+        v.clone()
+    } else if let Some(v) = value.get("intValue") {
+        // 64-bit integers are encoded as strings
+        v.as_str().and_then(|s| s.parse::<i64>().ok()).map(Value::from).unwrap_or_else(|| v.clone())
+    } else if let Some(v) = value.get("doubleValue") {
+        v.clone()
+    } else if let Some(values) = value.pointer("/arrayValue/values").and_then(Value::as_array) {
+        Value::Array(values.iter().map(any_value).collect())
+    } else if let Some(kvs) = value.pointer("/kvlistValue/values") {
+        Value::Object(attributes(Some(kvs)))
+    } else {
+        Value::Null
+    }
+}
+
+fn unix_nanos(value: &Value) -> Option<DateTime<Utc>> {
+    let nanos = match value {
+        Value::String(s) => s.parse::<i64>().ok()?,
+        other => other.as_i64()?,
+    };
+    (nanos > 0).then(|| Utc.timestamp_nanos(nanos))
+}
+
+fn agent_type(service_name: Option<&str>) -> AgentType {
+    match service_name.unwrap_or_default() {
+        "claude-code" | "claude_code" => AgentType::ClaudeCode,
+        "gemini-cli" | "gemini_cli" => AgentType::GeminiCli,
+        "codex" | "codex-cli" | "codex_cli_rs" | "openai-codex" => AgentType::OpenaiCodex,
+        "aider" => AgentType::Aider,
+        "cursor" => AgentType::Cursor,
+        _ => AgentType::Custom,
+    }
+}
+
+fn project(attrs: &Map<String, Value>) -> Option<String> {
+    ["project.path", "process.cwd"]
+        .iter()
+        .find_map(|key| attrs.get(*key)?.as_str())
+        .map(str::to_string)
+}
+
+/// The person behind the agent: OTel's `enduser.id`, else the account
+/// email Claude Code reports.
+fn user(attrs: &Map<String, Value>) -> Option<String> {
+    ["enduser.id", "user.email"]
+        .iter()
+        .find_map(|key| attrs.get(*key)?.as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply_logs() {
+        let payload = serde_json::json!({
+            "resourceLogs": [{
+                "resource": { "attributes": [
+                    { "key": "service.name", "value": { "stringValue": "claude-code" } },
+                    { "key": "project.path", "value": { "stringValue": "/work/api" } },
+                    { "key": "user.email", "value": { "stringValue": "dev@example.com" } }
+                ]},
+                "scopeLogs": [{ "logRecords": [
+                    {
+                        "timeUnixNano": "1760000000000000000",
+                        "body": { "stringValue": "claude_code.api_request" },
+                        "attributes": [
+                            { "key": "session.id", "value": { "stringValue": "abc" } },
+                            { "key": "model", "value": { "stringValue": "claude-sonnet-4-5" } },
+                            { "key": "cost_usd", "value": { "doubleValue": 0.25 } },
+                            { "key": "input_tokens", "value": { "intValue": "1200" } },
+                            { "key": "output_tokens", "value": { "intValue": "300" } },
+                            { "key": "cache_read_tokens", "value": { "intValue": "40000" } }
+                        ]
+                    },
+                    {
+                        "body": { "stringValue": "claude_code.tool_result" },
+                        "attributes": [
+                            { "key": "session.id", "value": { "stringValue": "abc" } },
+                            { "key": "tool_name", "value": { "stringValue": "Bash" } },
+                            { "key": "success", "value": { "stringValue": "false" } },
+                            { "key": "error", "value": { "stringValue": "exit 1" } }
+                        ]
+                    },
+                    { "body": { "stringValue": "no session" } }
+                ]}]
+            }]
+        });
+
+        let records = parse_logs(&payload);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].agent_type, AgentType::ClaudeCode);
+        assert_eq!(records[0].name, "api_request");
+        assert_eq!(records[0].project.as_deref(), Some("/work/api"));
+        assert_eq!(records[0].user.as_deref(), Some("dev@example.com"));
+        assert_eq!(records[0].timestamp.timestamp(), 1_760_000_000);
+
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        session.started_at = records[0].timestamp;
+        for record in &records {
+            apply(&mut session, record);
+        }
+        assert_eq!(session.tokens_input, 1200);
+        assert_eq!(session.tokens_output, 300);
+        assert!((session.estimated_cost - 0.25).abs() < 1e-9);
+        assert_eq!(session.context_tokens, Some(41_200));
+        assert_eq!(session.model_id.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!((session.message_count, session.tool_call_count), (1, 1));
+
+        let response = to_event(&records[0], &session.id);
+        assert_eq!(response.event_type, EventType::ResponseGenerated);
+        assert_eq!(response.tokens_input, Some(1200));
+        let tool = to_event(&records[1], &session.id);
+        assert_eq!(tool.event_type, EventType::ToolComplete);
+        assert_eq!(tool.error_message.as_deref(), Some("exit 1"));
+        assert_eq!(tool.content.as_deref(), Some("Bash failed in 0ms"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Output formats of the read commands.
+//!
+//! Commands that print data take `--output table|json|yaml` through
+//! [`OutputArgs`]. `table` is the styled default for people; `json` and
+//! `yaml` print the command's data unstyled, for scripts. `--json` stays as
+//! shorthand for `--output json`.
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::io::{ErrorKind, Write};
+
+/// How a command prints its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
+/// The `--output` flag, flattened into each read command.
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    /// Same as `--output json`
+    #[arg(short, long, conflicts_with = "output")]
+    json: bool,
+}
+
+impl OutputArgs {
+    pub fn format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.output
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn is_table(self) -> bool {
+        self == OutputFormat::Table
+    }
+
+    /// Print `value` as JSON or YAML and return true. Under `table` nothing is
+    /// printed and the caller renders its own view. A closed pipe (`| head`)
+    /// is not an error.
+    pub fn print<T: Serialize + ?Sized>(self, value: &T) -> Result<bool> {
+        let Some(text) = self.render(value)? else {
+            return Ok(false);
+        };
+        match writeln!(std::io::stdout().lock(), "{}", text.trim_end()) {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e.into()),
+            _ => Ok(true),
+        }
+    }
+
+    fn render<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<String>> {
+        Ok(match self {
+            OutputFormat::Table => None,
+            OutputFormat::Json => Some(serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => Some(serde_yaml::to_string(value)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        output: OutputArgs,
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let format = |args: &[&str]| {
+            Cli::try_parse_from(std::iter::once("test").chain(args.iter().copied()))
+                .map(|cli| cli.output.format())
+        };
+        assert_eq!(format(&[]).unwrap(), OutputFormat::Table);
+        assert_eq!(format(&["--output", "yaml"]).unwrap(), OutputFormat::Yaml);
+        assert_eq!(format(&["-o", "json"]).unwrap(), OutputFormat::Json);
+        assert_eq!(format(&["--json"]).unwrap(), OutputFormat::Json);
+        assert!(format(&["--json", "--output", "yaml"]).is_err());
+
+        let value = serde_json::json!({ "sessions": 2, "cost": 1.5, "tags": ["prod"] });
+        assert_eq!(OutputFormat::Table.render(&value).unwrap(), None);
+        let yaml = OutputFormat::Yaml.render(&value).unwrap().unwrap();
+        assert_eq!(yaml, "cost: 1.5\nsessions: 2\ntags:\n- prod\n");
+        let json: serde_json::Value =
+            serde_json::from_str(&OutputFormat::Json.render(&value).unwrap().unwrap()).unwrap();
+        assert_eq!(json, value);
+    }
+}
@@ -0,0 +1,589 @@
+//! WASM plugin stage for event processing.
+//!
+//! User-provided WebAssembly modules can transform, enrich, or drop events
+//! (custom redaction, custom cost models, ...) before they are stored and
+//! published to subscribers.
+//! Each call runs in a fresh store with a fuel budget, so a buggy or hostile
+//! plugin traps instead of hanging the daemon.
+//!
+//! Plugins are only executed when built with the `wasm-plugins` feature.
+//!
+//! # Guest ABI
+//!
+//! A plugin module must export:
+//!
+//! - `memory`: its linear memory
+//! - `alloc(len: i32) -> i32`: reserve `len` bytes and return the offset
+//! - `process(ptr: i32, len: i32) -> i64`: inspect the event JSON at `ptr`
+//!
+//! `process` returns `-1` to drop the event, `0` to keep it unchanged, or
+//! `(ptr << 32) | len` pointing at replacement event JSON in guest memory.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::storage::Storage;
+
+/// A single configured plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    /// Path to the `.wasm` (or `.wat`) module
+    pub path: PathBuf,
+
+    /// Whether the plugin runs
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Fuel budget per event; roughly one unit per WASM instruction
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+
+    /// Maximum linear memory the plugin may grow to, in MiB
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: usize,
+
+    /// Drop the event when the plugin fails instead of storing it unmodified
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_fuel() -> u64 {
+    10_000_000
+}
+
+fn default_max_memory_mb() -> usize {
+    64
+}
+
+impl PluginConfig {
+    /// Short name for log messages.
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+}
+
+/// Wrap `storage` so events pass through the enabled plugins before being
+/// inserted. Returns `storage` unchanged when no plugins are enabled.
+pub fn with_plugins(storage: Storage, plugins: &[PluginConfig]) -> Result<Storage> {
+    let enabled: Vec<PluginConfig> = plugins.iter().filter(|p| p.enabled).cloned().collect();
+    if enabled.is_empty() {
+        return Ok(storage);
+    }
+    wrap(storage, &enabled)
+}
+
+#[cfg(not(feature = "wasm-plugins"))]
+fn wrap(storage: Storage, enabled: &[PluginConfig]) -> Result<Storage> {
+    tracing::warn!(
+        "Plugins configured ({}) but this build lacks the wasm-plugins feature; events are stored unmodified",
+        enabled.iter().map(|p| p.name()).collect::<Vec<_>>().join(", ")
+    );
+    Ok(storage)
+}
+
+#[cfg(feature = "wasm-plugins")]
+fn wrap(storage: Storage, enabled: &[PluginConfig]) -> Result<Storage> {
+    let pipeline = wasm::PluginPipeline::load(enabled)?;
+    tracing::info!(
+        "Loaded {} event plugin(s): {}",
+        enabled.len(),
+        enabled.iter().map(|p| p.name()).collect::<Vec<_>>().join(", ")
+    );
+    Ok(Storage::from_backend(wasm::PluginStorage::new(storage, pipeline)))
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod wasm {
+    use anyhow::{anyhow, bail, Context, Result};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use tracing::warn;
+    use wasmtime::{Engine, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+    use super::PluginConfig;
+    use crate::events::EventBus;
+    use crate::models::{
+        ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample,
+        ResourceSample, Session, SessionEvent, SessionGroup, SessionSource, SessionTag, SummaryMetrics, TrashedSession,
+    };
+    use crate::storage::{Storage, StorageBackend};
+
+    /// What a plugin decided to do with an event.
+    #[derive(Debug)]
+    pub enum Outcome {
+        Keep,
+        Replace(Box<SessionEvent>),
+        Drop,
+    }
+
+    struct Plugin {
+        config: PluginConfig,
+        name: String,
+        instance_pre: InstancePre<StoreLimits>,
+    }
+
+    /// Compiled plugins, run in configuration order.
+    pub struct PluginPipeline {
+        engine: Engine,
+        plugins: Vec<Plugin>,
+    }
+
+    impl PluginPipeline {
+        /// Compile every plugin up front so bad modules fail at startup.
+        pub fn load(configs: &[PluginConfig]) -> Result<Self> {
+            let mut engine_config = wasmtime::Config::new();
+            engine_config.consume_fuel(true);
+            let engine = Engine::new(&engine_config)?;
+            // Plugins get no host imports: they see only the bytes they are given
+            let linker: Linker<StoreLimits> = Linker::new(&engine);
+
+            let mut plugins = Vec::new();
+            for config in configs {
+                let module = Module::from_file(&engine, &config.path)
+                    .with_context(|| format!("Failed to load plugin {}", config.path.display()))?;
+                let instance_pre = linker
+                    .instantiate_pre(&module)
+                    .with_context(|| format!("Plugin {} has unsupported imports", config.path.display()))?;
+                plugins.push(Plugin {
+                    name: config.name(),
+                    config: config.clone(),
+                    instance_pre,
+                });
+            }
+
+            Ok(Self { engine, plugins })
+        }
+
+        /// Run an event through every plugin. Returns `None` if it was dropped.
+        pub fn process(&self, event: &SessionEvent) -> Option<SessionEvent> {
+            let mut current = event.clone();
+            for plugin in &self.plugins {
+                match self.run(plugin, &current) {
+                    Ok(Outcome::Keep) => {}
+                    Ok(Outcome::Replace(replacement)) => current = *replacement,
+                    Ok(Outcome::Drop) => return None,
+                    Err(e) if plugin.config.fail_closed => {
+                        warn!("Plugin {} failed, dropping event {}: {:#}", plugin.name, event.id, e);
+                        return None;
+                    }
+                    Err(e) => {
+                        warn!("Plugin {} failed, keeping event {}: {:#}", plugin.name, event.id, e);
+                    }
+                }
+            }
+            Some(current)
+        }
+
+        /// Execute one plugin in a fresh, fuel-limited store.
+        fn run(&self, plugin: &Plugin, event: &SessionEvent) -> Result<Outcome> {
+            let limits = StoreLimitsBuilder::new()
+                .memory_size(plugin.config.max_memory_mb * 1024 * 1024)
+                .build();
+            let mut store = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(plugin.config.fuel)?;
+
+            let instance = plugin.instance_pre.instantiate(&mut store)?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| anyhow!("plugin does not export `memory`"))?;
+            let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+            let process = instance.get_typed_func::<(i32, i32), i64>(&mut store, "process")?;
+
+            let input = serde_json::to_vec(event)?;
+            let len = i32::try_from(input.len())?;
+            let ptr = alloc.call(&mut store, len)?;
+            memory.write(&mut store, ptr as u32 as usize, &input)?;
+
+            let result = process.call(&mut store, (ptr, len))?;
+            match result {
+                -1 => Ok(Outcome::Drop),
+                0 => Ok(Outcome::Keep),
+                packed if packed > 0 => {
+                    let out_ptr = (packed >> 32) as u32 as usize;
+                    let out_len = (packed & 0xffff_ffff) as u32 as usize;
+                    let mut output = vec![0u8; out_len];
+                    memory.read(&store, out_ptr, &mut output)?;
+                    let mut replacement: SessionEvent =
+                        serde_json::from_slice(&output).context("plugin returned invalid event JSON")?;
+                    // Plugins may rewrite content but not re-home the event
+                    replacement.id = event.id.clone();
+                    replacement.session_id = event.session_id.clone();
+                    Ok(Outcome::Replace(Box::new(replacement)))
+                }
+                other => bail!("plugin returned unexpected value {}", other),
+            }
+        }
+    }
+
+    /// Storage decorator that runs events through the plugin pipeline on insert,
+    /// publishing the result rather than the original.
+    pub struct PluginStorage {
+        inner: Storage,
+        pipeline: PluginPipeline,
+    }
+
+    impl PluginStorage {
+        pub fn new(inner: Storage, pipeline: PluginPipeline) -> Self {
+            Self { inner, pipeline }
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for PluginStorage {
+        async fn initialize(&self) -> Result<()> {
+            self.inner.initialize().await
+        }
+
+        async fn upsert_session(&self, session: &Session) -> Result<()> {
+            self.inner.upsert_session(session).await
+        }
+
+        async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+            self.inner.set_session_summary(session_id, summary).await
+        }
+
+        async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+            self.inner.set_session_branch(session_id, branch).await
+        }
+
+        async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+            self.inner.get_active_sessions(limit).await
+        }
+
+        async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+            self.inner.get_all_sessions(limit).await
+        }
+
+        async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+            self.inner.get_session(session_id).await
+        }
+
+        async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+            self.inner.get_active_session_for_project(project_path).await
+        }
+
+        async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+            self.inner.get_session_by_external_id(external_id).await
+        }
+
+        async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+            self.inner.get_recent_sessions(hours, limit).await
+        }
+
+        async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+            self.inner.get_summary_metrics(hours).await
+        }
+
+        async fn count_sessions_grouped_by(
+            &self,
+            group: SessionGroup,
+            hours: Option<i64>,
+            active_only: bool,
+        ) -> Result<HashMap<String, i64>> {
+            self.inner.count_sessions_grouped_by(group, hours, active_only).await
+        }
+
+        async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+            match self.pipeline.process(event) {
+                Some(processed) => self.inner.insert_event(&processed).await,
+                None => Ok(()),
+            }
+        }
+
+        async fn record_event(&self, event: SessionEvent, bus: &EventBus) -> Result<()> {
+            match self.pipeline.process(&event) {
+                Some(processed) => self.inner.record_event(processed, bus).await,
+                None => Ok(()),
+            }
+        }
+
+        async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+            self.inner.get_recent_events(limit).await
+        }
+
+        async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+            self.inner.get_session_events(session_id, limit).await
+        }
+
+        async fn get_recent_events_of_type(
+            &self,
+            event_type: EventType,
+            hours: i64,
+            limit: usize,
+        ) -> Result<Vec<SessionEvent>> {
+            self.inner.get_recent_events_of_type(event_type, hours, limit).await
+        }
+
+        async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+            self.inner.get_event_rollups(hours).await
+        }
+
+        async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+            self.inner.delete_sessions_by_type(agent_type).await
+        }
+
+        async fn clear_all(&self) -> Result<()> {
+            self.inner.clear_all().await
+        }
+
+        async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+            self.inner.upsert_memory(entry).await
+        }
+
+        async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+            self.inner.get_memory(key).await
+        }
+
+        async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+            self.inner.list_memory(tag).await
+        }
+
+        async fn delete_memory(&self, key: &str) -> Result<bool> {
+            self.inner.delete_memory(key).await
+        }
+
+        async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+            self.inner.get_unembedded_events(model, limit).await
+        }
+
+        async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+            self.inner.insert_embedding(embedding).await
+        }
+
+        async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+            self.inner.list_embeddings(model).await
+        }
+
+        async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+            self.inner.add_session_tag(tag).await
+        }
+
+        async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+            self.inner.remove_session_tag(session_id, tag).await
+        }
+
+        async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+            self.inner.list_session_tags(session_id, tag).await
+        }
+
+        async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+            self.inner.insert_resource_sample(sample).await
+        }
+
+        async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+            self.inner.get_resource_samples(session_id, limit).await
+        }
+
+        async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+            self.inner.insert_network_sample(sample).await
+        }
+
+        async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+            self.inner.get_network_samples(session_id, limit).await
+        }
+
+        async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+            self.inner.record_session_source(source).await
+        }
+
+        async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+            self.inner.get_session_sources(session_id).await
+        }
+
+        async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+            self.inner.put_blob(hash, size, data).await
+        }
+
+        async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+            self.inner.get_blob(hash).await
+        }
+
+        async fn get_blob_stats(&self) -> Result<BlobStats> {
+            self.inner.get_blob_stats().await
+        }
+
+        async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+            self.inner.get_database_stats(top).await
+        }
+
+        async fn delete_session(&self, session_id: &str) -> Result<bool> {
+            self.inner.delete_session(session_id).await
+        }
+
+        async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+            self.inner.insert_archived_session(archived).await
+        }
+
+        async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+            self.inner.get_archived_session(session_id).await
+        }
+
+        async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+            self.inner.list_archived_sessions().await
+        }
+
+        async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+            self.inner.delete_archived_session(session_id).await
+        }
+
+        async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+            self.inner.insert_trashed_session(trashed).await
+        }
+
+        async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+            self.inner.list_trashed_sessions().await
+        }
+
+        async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+            self.inner.delete_trashed_session(session_id).await
+        }
+
+        async fn write_snapshot(&self, path: &std::path::Path) -> Result<()> {
+            self.inner.write_snapshot(path).await
+        }
+
+        async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+            self.inner.insert_audit_entry(entry).await
+        }
+
+        async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+            self.inner.get_audit_log(limit).await
+        }
+
+        async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+            self.inner.upsert_daemon_run(run).await
+        }
+
+        async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>> {
+            self.inner.get_daemon_runs(since).await
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wasm-plugins"))]
+mod tests {
+    use super::*;
+    use crate::events::EventBus;
+    use crate::models::{AgentType, EventType, Session, SessionEvent};
+
+    /// Bump allocator shared by the test modules.
+    const ALLOC: &str = r#"
+        (memory (export "memory") 2)
+        (global $next (mut i32) (i32.const 1024))
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+    "#;
+
+    fn plugin(dir: &tempfile::TempDir, name: &str, process: &str) -> PluginConfig {
+        let path = dir.path().join(format!("{}.wat", name));
+        std::fs::write(&path, format!("(module {} {})", ALLOC, process)).unwrap();
+        PluginConfig {
+            path,
+            enabled: true,
+            fuel: 100_000,
+            max_memory_mb: 16,
+            fail_closed: false,
+        }
+    }
+
+    async fn store_one(plugins: &[PluginConfig]) -> usize {
+        let storage = with_plugins(Storage::in_memory(), plugins).unwrap();
+        let session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+        let mut event = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        event.content = Some("hello".to_string());
+        storage.insert_event(&event).await.unwrap();
+        storage.get_session_events(&session.id, 10).await.unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn test_filter_plugin_drops_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let drop_all = plugin(
+            &dir,
+            "drop",
+            r#"(func (export "process") (param i32 i32) (result i64) (i64.const -1))"#,
+        );
+        assert_eq!(store_one(&[drop_all]).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_see_processed_events() {
+        let dir = tempfile::tempdir().unwrap();
+        // Blank out the event's content by answering with fixed JSON
+        let event = SessionEvent::new("s1", EventType::PromptReceived, AgentType::ClaudeCode);
+        let replacement = serde_json::to_string(&event).unwrap();
+        let redact = plugin(
+            &dir,
+            "redact",
+            &format!(
+                r#"(data (i32.const 0) "{}")
+                (func (export "process") (param i32 i32) (result i64) (i64.const {}))"#,
+                replacement.replace('\\', "\\\\").replace('"', "\\\""),
+                replacement.len()
+            ),
+        );
+        let drop_all = plugin(
+            &dir,
+            "drop",
+            r#"(func (export "process") (param i32 i32) (result i64) (i64.const -1))"#,
+        );
+
+        let bus = EventBus::new();
+        let mut subscriber = bus.subscribe();
+        let mut secret = event.clone();
+        secret.content = Some("AWS_SECRET=hunter2".to_string());
+
+        let storage = with_plugins(Storage::in_memory(), &[redact]).unwrap();
+        storage.record_event(secret.clone(), &bus).await.unwrap();
+        assert_eq!(subscriber.try_recv().unwrap().content, None);
+
+        let storage = with_plugins(Storage::in_memory(), &[drop_all]).unwrap();
+        storage.record_event(secret, &bus).await.unwrap();
+        assert!(subscriber.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replacement_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        // Hand the input bytes straight back as the replacement event
+        let echo = plugin(
+            &dir,
+            "echo",
+            r#"(func (export "process") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))"#,
+        );
+        assert_eq!(store_one(&[echo]).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fuel_exhaustion_does_not_hang() {
+        let dir = tempfile::tempdir().unwrap();
+        let spin = r#"(func (export "process") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0))"#;
+
+        let fail_open = plugin(&dir, "spin", spin);
+        assert_eq!(store_one(std::slice::from_ref(&fail_open)).await, 1);
+
+        let fail_closed = PluginConfig {
+            fail_closed: true,
+            ..fail_open
+        };
+        assert_eq!(store_one(&[fail_closed]).await, 0);
+    }
+}
@@ -0,0 +1,187 @@
+//! One-line previews of event content for event lists and pushed summaries.
+//!
+//! Adapters store a response as prose interleaved with `[THINKING]`,
+//! `[TOOL: name]` and `[RESULT]` sections. A preview keeps the prose as its
+//! text and lifts the thinking and the tool call into fields of their own;
+//! for tool events the text is the call itself, `Bash(cargo test)`. Text is
+//! cut on grapheme boundaries, so multi-byte characters and emoji never split.
+
+use serde::Serialize;
+use serde_json::Value;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::models::{EventType, SessionEvent};
+
+/// Longest preview text, in graphemes.
+pub const MAX_TEXT: usize = 100;
+
+/// Longest tool argument shown, in graphemes.
+const MAX_ARGUMENT: usize = 60;
+
+/// Tool input fields worth showing, most telling first.
+const ARGUMENT_KEYS: &[&str] = &["command", "file_path", "notebook_path", "path", "pattern", "url", "query", "description", "prompt"];
+
+/// What a list shows for an event.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Preview {
+    pub text: String,
+    /// First line of the agent's thinking
+    pub thinking: Option<String>,
+    pub tool: Option<String>,
+    /// The tool input that says most about the call: its command, file, ...
+    pub tool_argument: Option<String>,
+}
+
+/// `text` cut to `max` graphemes, ending in `...` when cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    let mut graphemes = text.grapheme_indices(true);
+    match graphemes.nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn first_line(text: &str) -> Option<&str> {
+    text.lines().map(str::trim).find(|line| !line.is_empty())
+}
+
+/// The most telling field of a tool's input.
+pub fn tool_argument(input: &Value) -> Option<String> {
+    let value = match input {
+        Value::Object(fields) => ARGUMENT_KEYS
+            .iter()
+            .find_map(|key| fields.get(*key).filter(|v| v.is_string()))
+            .or_else(|| fields.values().find(|v| v.is_string()))?,
+        other => other,
+    };
+    let line = first_line(value.as_str()?)?;
+    Some(truncate(line, MAX_ARGUMENT))
+}
+
+/// A section marker line: `[THINKING]` gives ("THINKING", None),
+/// `[TOOL: Bash]` gives ("TOOL", Some("Bash")).
+fn marker(line: &str) -> Option<(&str, Option<&str>)> {
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (name, label) = match inner.split_once(':') {
+        Some((name, label)) => (name, Some(label.trim())),
+        None => (inner, None),
+    };
+    let is_marker = !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+    is_marker.then_some((name, label))
+}
+
+/// Build the preview of `event`.
+pub fn build(event: &SessionEvent) -> Preview {
+    let mut prose = None;
+    let mut thinking = None;
+    let mut tool = event.tool_name.clone();
+    let mut tool_section = String::new();
+    let mut tool_sections = 0;
+    let mut fallback = None;
+
+    let mut section = None;
+    for line in event.content.as_deref().unwrap_or("").lines() {
+        if let Some((name, label)) = marker(line) {
+            if name == "TOOL" {
+                tool_sections += 1;
+                if tool.is_none() {
+                    tool = label.filter(|l| !l.is_empty()).map(str::to_string);
+                }
+            }
+            section = Some(name);
+            continue;
+        }
+        let text = line.trim();
+        match section {
+            None if !text.is_empty() => {
+                prose.get_or_insert(text);
+            }
+            Some("THINKING") if !text.is_empty() => {
+                thinking.get_or_insert(text);
+            }
+            // The input of the first tool call, which may span lines
+            Some("TOOL") if tool_sections == 1 => {
+                tool_section.push_str(line);
+                tool_section.push('\n');
+            }
+            _ if !text.is_empty() => {
+                fallback.get_or_insert(text);
+            }
+            _ => {}
+        }
+    }
+
+    // Events from before tool inputs were stored apart carry them in the
+    // raw data or the content
+    let input = event
+        .tool_input
+        .clone()
+        .or_else(|| event.raw_data.as_ref().and_then(|data| data.get("tool_input")).cloned())
+        .or_else(|| serde_json::from_str(&tool_section).ok());
+    let argument = input.as_ref().and_then(tool_argument);
+
+    let call = tool.as_ref().map(|name| match &argument {
+        Some(argument) => format!("{}({})", name, argument),
+        None => name.clone(),
+    });
+    let is_tool_event = matches!(
+        event.event_type,
+        EventType::ToolStart | EventType::ToolComplete | EventType::ToolExecuted
+    );
+    let text = if is_tool_event { call.as_deref().or(prose) } else { prose.or(call.as_deref()) }
+        .or(thinking)
+        .or(fallback)
+        .unwrap_or("");
+
+    Preview {
+        text: truncate(text, MAX_TEXT),
+        thinking: thinking.map(|t| truncate(t, MAX_TEXT)),
+        tool,
+        tool_argument: argument,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    fn event(event_type: EventType, content: &str) -> SessionEvent {
+        let mut event = SessionEvent::new("s", event_type, AgentType::ClaudeCode);
+        event.content = Some(content.to_string());
+        event
+    }
+
+    #[test]
+    fn test_truncate_keeps_graphemes_whole() {
+        assert_eq!(truncate("héllo", 10), "héllo");
+        assert_eq!(truncate("héllo", 2), "hé...");
+        // A family emoji is several code points but one grapheme
+        assert_eq!(truncate("👨‍👩‍👧‍👦👍 ok", 1), "👨‍👩‍👧‍👦...");
+        let long = "日本語".repeat(50);
+        assert_eq!(truncate(&long, MAX_TEXT).chars().count(), MAX_TEXT + 3);
+    }
+
+    #[test]
+    fn test_preview_splits_sections() {
+        let response = event(
+            EventType::ResponseGenerated,
+            "[THINKING]\nLooking at main.rs next.\n\n[TOOL: Read]\n{\n  \"file_path\": \"/work/src/main.rs\",\n  \"limit\": 50\n}",
+        );
+        let preview = build(&response);
+        assert_eq!(preview.thinking.as_deref(), Some("Looking at main.rs next."));
+        assert_eq!(preview.tool.as_deref(), Some("Read"));
+        assert_eq!(preview.tool_argument.as_deref(), Some("/work/src/main.rs"));
+        assert_eq!(preview.text, "Read(/work/src/main.rs)");
+
+        let answer = event(EventType::ResponseGenerated, "\nAll tests pass now.\n\n[THINKING]\ndone");
+        assert_eq!(build(&answer).text, "All tests pass now.");
+
+        let mut tool = event(EventType::ToolStart, "running");
+        tool.tool_name = Some("Bash".to_string());
+        tool.tool_input = Some(serde_json::json!({ "description": "Run tests", "command": "cargo test\n--all" }));
+        let preview = build(&tool);
+        assert_eq!(preview.text, "Bash(cargo test)");
+        assert_eq!(preview.thinking, None);
+    }
+}
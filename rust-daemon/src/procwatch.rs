@@ -0,0 +1,321 @@
+//! Shared process watcher.
+//!
+//! One `System` is kept for the daemon's lifetime instead of each adapter
+//! building a fresh one on every scan. The full process list is refreshed
+//! every scan interval, reading only the command line and working directory,
+//! once per process. Processes that match a subscriber are refreshed on their
+//! own every few seconds, so exits are noticed without rescanning everything.
+//! Subscribers register a `ProcessMatcher` and receive `Started` and `Exited`
+//! events for the processes it matches.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use sysinfo::{Pid, ProcessRefreshKind, System, UpdateKind};
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration};
+use tracing::debug;
+
+/// Seconds between refreshes of matched processes.
+const TRACK_INTERVAL_SECS: u64 = 5;
+
+/// A running process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    /// Arguments joined with spaces
+    pub cmd: String,
+    pub cwd: Option<String>,
+    /// Seconds since the epoch; tells a reused PID from the original process
+    pub start_time: u64,
+}
+
+impl ProcessInfo {
+    fn from_process(pid: Pid, process: &sysinfo::Process) -> Self {
+        Self {
+            pid: pid.as_u32(),
+            parent: process.parent().map(|p| p.as_u32()),
+            name: process.name().to_string(),
+            cmd: process.cmd().join(" "),
+            cwd: process
+                .cwd()
+                .map(|p| p.to_string_lossy().to_string())
+                .filter(|p| !p.is_empty()),
+            start_time: process.start_time(),
+        }
+    }
+}
+
+/// A change in the set of processes a subscriber matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessEvent {
+    Started(ProcessInfo),
+    Exited(ProcessInfo),
+}
+
+/// Which processes a subscriber is interested in. A process matches when its
+/// name or command line contains one of the patterns (case-insensitive) and
+/// neither contains an excluded one.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessMatcher {
+    names: Vec<String>,
+    commands: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl ProcessMatcher {
+    pub fn new(names: &[&str], commands: &[&str], exclude: &[&str]) -> Self {
+        let lower = |patterns: &[&str]| patterns.iter().map(|p| p.to_lowercase()).collect();
+        Self {
+            names: lower(names),
+            commands: lower(commands),
+            exclude: lower(exclude),
+        }
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let name = process.name.to_lowercase();
+        let cmd = process.cmd.to_lowercase();
+        let included = self.names.iter().any(|p| name.contains(p.as_str()))
+            || self.commands.iter().any(|p| cmd.contains(p.as_str()));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|p| name.contains(p.as_str()) || cmd.contains(p.as_str()))
+    }
+}
+
+struct Subscriber {
+    matcher: ProcessMatcher,
+    sender: mpsc::UnboundedSender<ProcessEvent>,
+    /// Processes currently matched, by PID
+    matched: HashMap<u32, ProcessInfo>,
+}
+
+/// Process scanning shared by every adapter.
+#[derive(Clone)]
+pub struct ProcessWatcher {
+    system: Arc<Mutex<System>>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    scan_interval: Duration,
+}
+
+impl ProcessWatcher {
+    /// Create a watcher that rescans the process list every `scan_interval_secs`.
+    pub fn new(scan_interval_secs: u64) -> Self {
+        Self {
+            system: Arc::new(Mutex::new(System::new())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            scan_interval: Duration::from_secs(scan_interval_secs.max(1)),
+        }
+    }
+
+    /// Running processes matching `matcher`, from a fresh scan.
+    pub fn matching(&self, matcher: &ProcessMatcher) -> Vec<ProcessInfo> {
+        self.refresh_all()
+            .into_iter()
+            .filter(|p| matcher.matches(p))
+            .collect()
+    }
+
+    /// Receive events for processes matching `matcher`. Processes already
+    /// running count as seen, so only later starts are reported.
+    pub fn subscribe(&self, matcher: ProcessMatcher) -> mpsc::UnboundedReceiver<ProcessEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let matched = self
+            .snapshot()
+            .into_iter()
+            .filter(|p| matcher.matches(p))
+            .map(|p| (p.pid, p))
+            .collect();
+        self.subscribers.lock().unwrap().push(Subscriber { matcher, sender, matched });
+        receiver
+    }
+
+    /// Scan for the daemon's lifetime.
+    pub async fn run(self) {
+        let mut scan = interval(self.scan_interval);
+        let mut track = interval(Duration::from_secs(TRACK_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                _ = scan.tick() => self.scan(),
+                _ = track.tick() => self.check_matched(),
+            }
+        }
+    }
+
+    /// Refresh the full process list and report starts and exits.
+    fn scan(&self) {
+        let processes = self.refresh_all();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        for subscriber in subscribers.iter_mut() {
+            let current: HashMap<u32, ProcessInfo> = processes
+                .iter()
+                .filter(|p| subscriber.matcher.matches(p))
+                .map(|p| (p.pid, p.clone()))
+                .collect();
+            let events = diff(&subscriber.matched, &current);
+            subscriber.matched = current;
+            for event in events {
+                let _ = subscriber.sender.send(event);
+            }
+        }
+        subscribers.retain(|s| !s.sender.is_closed());
+        debug!("Process scan complete ({} processes)", processes.len());
+    }
+
+    /// Refresh only the matched processes and report those that exited.
+    fn check_matched(&self) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let mut system = self.system.lock().unwrap();
+        let mut alive: HashMap<u32, bool> = HashMap::new();
+        for subscriber in subscribers.iter_mut() {
+            let exited: Vec<ProcessInfo> = subscriber
+                .matched
+                .values()
+                .filter(|p| {
+                    !*alive.entry(p.pid).or_insert_with(|| {
+                        let pid = Pid::from_u32(p.pid);
+                        system.refresh_process_specifics(pid, ProcessRefreshKind::new())
+                            && system.process(pid).is_some_and(|q| q.start_time() == p.start_time)
+                    })
+                })
+                .cloned()
+                .collect();
+            for process in exited {
+                subscriber.matched.remove(&process.pid);
+                let _ = subscriber.sender.send(ProcessEvent::Exited(process));
+            }
+        }
+    }
+
+    fn refresh_all(&self) -> Vec<ProcessInfo> {
+        let mut system = self.system.lock().unwrap();
+        system.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_cwd(UpdateKind::OnlyIfNotSet),
+        );
+        list(&system)
+    }
+
+    /// Processes as of the last refresh, scanning first if there has been none.
+    pub fn snapshot(&self) -> Vec<ProcessInfo> {
+        let cached = list(&self.system.lock().unwrap());
+        if cached.is_empty() {
+            self.refresh_all()
+        } else {
+            cached
+        }
+    }
+}
+
+/// Every process in `system`. Linux lists threads alongside processes; they
+/// share their process's memory and command line, so they are left out.
+fn list(system: &System) -> Vec<ProcessInfo> {
+    system
+        .processes()
+        .iter()
+        .filter(|(_, process)| process.thread_kind().is_none())
+        .map(|(pid, process)| ProcessInfo::from_process(*pid, process))
+        .collect()
+}
+
+/// PIDs of `roots` and every process descending from them. Roots missing
+/// from `processes` are left out.
+pub fn descendants(processes: &[ProcessInfo], roots: &[u32]) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for process in processes {
+        if let Some(parent) = process.parent {
+            children.entry(parent).or_default().push(process.pid);
+        }
+    }
+    let running: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+
+    let mut stack: Vec<u32> = roots.iter().copied().filter(|pid| running.contains(pid)).collect();
+    let mut seen = HashSet::new();
+    let mut tree = Vec::new();
+    while let Some(pid) = stack.pop() {
+        if seen.insert(pid) {
+            tree.push(pid);
+            stack.extend(children.get(&pid).into_iter().flatten());
+        }
+    }
+    tree
+}
+
+/// Events turning the `previous` matched set into `current`. A PID whose
+/// start time changed was reused: the old process exited and a new one started.
+fn diff(previous: &HashMap<u32, ProcessInfo>, current: &HashMap<u32, ProcessInfo>) -> Vec<ProcessEvent> {
+    let mut events: Vec<ProcessEvent> = previous
+        .values()
+        .filter(|p| current.get(&p.pid).is_none_or(|c| c.start_time != p.start_time))
+        .cloned()
+        .map(ProcessEvent::Exited)
+        .collect();
+    events.extend(
+        current
+            .values()
+            .filter(|c| previous.get(&c.pid).is_none_or(|p| p.start_time != c.start_time))
+            .cloned()
+            .map(ProcessEvent::Started),
+    );
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cmd: &str, start_time: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent: None,
+            name: name.to_string(),
+            cmd: cmd.to_string(),
+            cwd: Some("/work".to_string()),
+            start_time,
+        }
+    }
+
+    #[test]
+    fn test_matcher_and_diff() {
+        let aider = ProcessMatcher::new(&[], &["aider"], &["aider-"]);
+        assert!(aider.matches(&process(1, "python3", "/usr/bin/python3 -m Aider --model x", 0)));
+        assert!(!aider.matches(&process(2, "python3", "aider-lsp", 0)));
+        assert!(!aider.matches(&process(3, "bash", "ls", 0)));
+
+        let previous: HashMap<u32, ProcessInfo> =
+            [process(10, "claude", "", 100), process(11, "claude", "", 100)]
+                .into_iter()
+                .map(|p| (p.pid, p))
+                .collect();
+        let current: HashMap<u32, ProcessInfo> =
+            [process(11, "claude", "", 200), process(12, "claude", "", 300)]
+                .into_iter()
+                .map(|p| (p.pid, p))
+                .collect();
+
+        let events = diff(&previous, &current);
+        let exited: Vec<u32> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProcessEvent::Exited(p) => Some(p.pid),
+                _ => None,
+            })
+            .collect();
+        let mut started: Vec<u32> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProcessEvent::Started(p) => Some(p.pid),
+                _ => None,
+            })
+            .collect();
+        started.sort();
+        assert_eq!(exited.len(), 2);
+        assert!(exited.contains(&10) && exited.contains(&11));
+        assert_eq!(started, vec![11, 12]);
+    }
+}
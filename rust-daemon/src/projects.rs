@@ -0,0 +1,138 @@
+//! Per-project rollups for the project leaderboard.
+//!
+//! Sessions are grouped by project path. Each project reports its active
+//! sessions, spend over the last seven days, last activity and success rate:
+//! the share of finished sessions (completed or crashed) that completed.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::models::{Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+/// Most sessions considered for the leaderboard.
+const MAX_SESSIONS: usize = 20_000;
+
+/// Sessions whose events are gathered for a project page.
+const EVENT_SESSIONS: usize = 20;
+
+/// Rollup for one project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectStats {
+    pub project_path: String,
+    pub project_name: String,
+    pub sessions: usize,
+    pub active_sessions: usize,
+    pub cost_7d: f64,
+    pub total_cost: f64,
+    pub last_activity_at: DateTime<Utc>,
+    /// None until a session has completed or crashed
+    pub success_rate: Option<f64>,
+}
+
+/// Projects active in the last `days`, most 7-day spend first.
+pub async fn build_leaderboard(storage: &Storage, days: i64) -> Result<Vec<ProjectStats>> {
+    let sessions = storage.get_recent_sessions(days.max(1) * 24, MAX_SESSIONS).await?;
+    Ok(leaderboard(&sessions, Utc::now()))
+}
+
+/// Sessions from the last `days` in exactly `project_path`, newest first.
+pub async fn project_sessions(storage: &Storage, project_path: &str, days: i64) -> Result<Vec<Session>> {
+    let mut sessions: Vec<Session> = storage
+        .get_recent_sessions(days.max(1) * 24, MAX_SESSIONS)
+        .await?
+        .into_iter()
+        .filter(|s| s.project_path == project_path)
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_activity_at));
+    Ok(sessions)
+}
+
+/// Latest events across the most recent of `sessions`, newest first.
+pub async fn project_events(storage: &Storage, sessions: &[Session], limit: usize) -> Result<Vec<SessionEvent>> {
+    let mut events = Vec::new();
+    for session in sessions.iter().take(EVENT_SESSIONS) {
+        events.extend(storage.get_session_events(&session.id, limit).await?);
+    }
+    events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    events.truncate(limit);
+    Ok(events)
+}
+
+/// Group `sessions` by project, most 7-day spend first.
+pub fn leaderboard(sessions: &[Session], now: DateTime<Utc>) -> Vec<ProjectStats> {
+    let week_ago = now - Duration::days(7);
+    let mut by_project: HashMap<&str, Vec<&Session>> = HashMap::new();
+    for session in sessions {
+        by_project.entry(session.project_path.as_str()).or_default().push(session);
+    }
+
+    let mut projects: Vec<ProjectStats> = by_project
+        .into_iter()
+        .filter_map(|(path, sessions)| {
+            let last_activity_at = sessions.iter().map(|s| s.last_activity_at).max()?;
+            let completed = sessions.iter().filter(|s| s.status == SessionStatus::Completed).count();
+            let crashed = sessions.iter().filter(|s| s.status == SessionStatus::Crashed).count();
+            Some(ProjectStats {
+                project_path: path.to_string(),
+                project_name: path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or(path).to_string(),
+                sessions: sessions.len(),
+                active_sessions: sessions.iter().filter(|s| s.status == SessionStatus::Active).count(),
+                cost_7d: sessions
+                    .iter()
+                    .filter(|s| s.last_activity_at >= week_ago)
+                    .fold(0.0, |total, s| total + s.estimated_cost),
+                total_cost: sessions.iter().fold(0.0, |total, s| total + s.estimated_cost),
+                last_activity_at,
+                success_rate: (completed + crashed > 0)
+                    .then(|| completed as f64 / (completed + crashed) as f64),
+            })
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.cost_7d
+            .total_cmp(&a.cost_7d)
+            .then(b.last_activity_at.cmp(&a.last_activity_at))
+    });
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_leaderboard() {
+        let now = Utc::now();
+        let session = |path: &str, status: SessionStatus, cost: f64, days_ago: i64| {
+            let mut session = Session::new(AgentType::ClaudeCode, path, "x");
+            session.status = status;
+            session.estimated_cost = cost;
+            session.last_activity_at = now - Duration::days(days_ago);
+            session
+        };
+        let sessions = vec![
+            session("/work/api", SessionStatus::Completed, 2.0, 1),
+            session("/work/api", SessionStatus::Crashed, 1.0, 2),
+            session("/work/api", SessionStatus::Completed, 5.0, 10),
+            session("/work/web", SessionStatus::Active, 4.0, 0),
+        ];
+
+        let projects = leaderboard(&sessions, now);
+        assert_eq!(projects.len(), 2);
+
+        let web = &projects[0];
+        assert_eq!(web.project_name, "web");
+        assert_eq!(web.active_sessions, 1);
+        assert_eq!(web.success_rate, None);
+
+        let api = &projects[1];
+        assert!((api.cost_7d - 3.0).abs() < 1e-9);
+        assert!((api.total_cost - 8.0).abs() < 1e-9);
+        assert!((api.success_rate.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(api.last_activity_at, now - Duration::days(1));
+    }
+}
@@ -0,0 +1,239 @@
+//! How efficiently prompts turn into work.
+//!
+//! Each session is split into turns (see `turns`). A prompt the agent only
+//! answered, followed by another prompt, is a clarification: the spec was
+//! missing something and a round trip was spent finding out. Per project or
+//! per user this adds up to prompt length, clarifications per task (turns
+//! where the agent acted), files edited per prompt and what the clarifying
+//! rounds cost, their share of tokens times the session's cost. Groups that
+//! spend the most on clarifying come first; those are where better specs
+//! would save money.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::models::Session;
+use crate::storage::Storage;
+use crate::turns::{self, Turn};
+
+/// Most sessions looked at.
+const MAX_SESSIONS: usize = 2_000;
+
+/// Most events read per session.
+const MAX_EVENTS: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PromptGroup {
+    Project,
+    User,
+}
+
+/// Prompt counts of one session or a group of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PromptStats {
+    pub sessions: usize,
+    pub prompts: usize,
+    /// Words over all prompts
+    pub words: usize,
+    /// Prompts the agent acted on
+    pub tasks: usize,
+    /// Prompts only answered and followed by another prompt
+    pub clarifications: usize,
+    /// Files edited, counted once per prompt
+    pub edits: usize,
+    pub cost: f64,
+    /// Share of `cost` spent in clarifying rounds
+    pub clarification_cost: f64,
+}
+
+impl PromptStats {
+    fn add(&mut self, other: &PromptStats) {
+        self.sessions += other.sessions;
+        self.prompts += other.prompts;
+        self.words += other.words;
+        self.tasks += other.tasks;
+        self.clarifications += other.clarifications;
+        self.edits += other.edits;
+        self.cost += other.cost;
+        self.clarification_cost += other.clarification_cost;
+    }
+
+    fn ratio(count: usize, of: usize) -> f64 {
+        if of == 0 {
+            0.0
+        } else {
+            count as f64 / of as f64
+        }
+    }
+
+    pub fn words_per_prompt(&self) -> f64 {
+        Self::ratio(self.words, self.prompts)
+    }
+
+    pub fn clarifications_per_task(&self) -> f64 {
+        Self::ratio(self.clarifications, self.tasks)
+    }
+
+    pub fn edits_per_prompt(&self) -> f64 {
+        Self::ratio(self.edits, self.prompts)
+    }
+
+    pub fn cost_per_prompt(&self) -> f64 {
+        if self.prompts == 0 {
+            0.0
+        } else {
+            self.cost / self.prompts as f64
+        }
+    }
+}
+
+/// Stats of one project or user.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptEfficiency {
+    /// Project path or user name; empty for sessions without a user
+    pub key: String,
+    #[serde(flatten)]
+    pub stats: PromptStats,
+    pub words_per_prompt: f64,
+    pub clarifications_per_task: f64,
+    pub edits_per_prompt: f64,
+    pub cost_per_prompt: f64,
+}
+
+/// Prompt stats of a session from its turns and cost.
+pub fn session_stats(turns: &[Turn], cost: f64) -> PromptStats {
+    let prompted: Vec<&Turn> = turns.iter().filter(|t| t.number > 0).collect();
+    let total_tokens: i64 = turns.iter().map(Turn::tokens_added).sum();
+    let mut stats = PromptStats {
+        sessions: 1,
+        prompts: prompted.len(),
+        cost,
+        ..PromptStats::default()
+    };
+    for (i, turn) in prompted.iter().enumerate() {
+        stats.words += turn.prompt_words;
+        stats.edits += turn.modified_files.len();
+        if turn.acted {
+            stats.tasks += 1;
+        } else if i + 1 < prompted.len() {
+            stats.clarifications += 1;
+            if total_tokens > 0 {
+                stats.clarification_cost += cost * turn.tokens_added() as f64 / total_tokens as f64;
+            }
+        }
+    }
+    stats
+}
+
+/// Prompt efficiency per project or user over sessions active in the last
+/// `days`, most spent on clarifying first.
+pub async fn efficiency(storage: &Storage, days: i64, by: PromptGroup, user: Option<&str>) -> Result<Vec<PromptEfficiency>> {
+    let since = Utc::now() - Duration::days(days);
+    let mut sessions = storage.get_recent_sessions(days * 24 + 1, MAX_SESSIONS).await?;
+    sessions.retain(|s| s.last_activity_at >= since);
+    if let Some(user) = user {
+        sessions.retain(|s| s.user.as_deref() == Some(user));
+    }
+
+    let mut per_session = Vec::new();
+    for session in sessions {
+        let events = storage.get_session_events(&session.id, MAX_EVENTS).await?;
+        let stats = session_stats(&turns::turns(&events), session.estimated_cost);
+        per_session.push((session, stats));
+    }
+    Ok(group(per_session, by))
+}
+
+fn group(per_session: Vec<(Session, PromptStats)>, by: PromptGroup) -> Vec<PromptEfficiency> {
+    let mut groups: HashMap<String, PromptStats> = HashMap::new();
+    for (session, stats) in per_session.iter().filter(|(_, s)| s.prompts > 0) {
+        let key = match by {
+            PromptGroup::Project => session.project_path.clone(),
+            PromptGroup::User => session.user.clone().unwrap_or_default(),
+        };
+        groups.entry(key).or_default().add(stats);
+    }
+
+    let mut groups: Vec<PromptEfficiency> = groups
+        .into_iter()
+        .map(|(key, stats)| PromptEfficiency {
+            key,
+            words_per_prompt: stats.words_per_prompt(),
+            clarifications_per_task: stats.clarifications_per_task(),
+            edits_per_prompt: stats.edits_per_prompt(),
+            cost_per_prompt: stats.cost_per_prompt(),
+            stats,
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.stats
+            .clarification_cost
+            .total_cmp(&a.stats.clarification_cost)
+            .then(b.clarifications_per_task.total_cmp(&a.clarifications_per_task))
+            .then(a.key.cmp(&b.key))
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType, SessionEvent};
+
+    #[test]
+    fn test_clarifications_edits_and_grouping() {
+        let start = Utc::now();
+        let mut at = 0;
+        let mut event = |event_type: EventType, content: Option<&str>, tool: Option<&str>, tokens: i64| {
+            let mut event = SessionEvent::new("s", event_type, AgentType::ClaudeCode);
+            at += 1;
+            event.timestamp = start + Duration::seconds(at);
+            event.content = content.map(str::to_string);
+            event.tool_name = tool.map(str::to_string);
+            event.file_path = tool.map(|_| "src/parser.rs".to_string());
+            event.tokens_output = (tokens > 0).then_some(tokens);
+            event
+        };
+        let events = vec![
+            event(EventType::PromptReceived, Some("fix the bug"), None, 0),
+            event(EventType::ResponseGenerated, Some("Which bug?"), None, 1_000),
+            event(EventType::PromptReceived, Some("the parser drops trailing commas in arrays"), None, 0),
+            event(EventType::ResponseGenerated, None, Some("Edit"), 3_000),
+            event(EventType::ToolExecuted, None, Some("Edit"), 0),
+            event(EventType::PromptReceived, Some("thanks"), None, 0),
+            event(EventType::ResponseGenerated, Some("You're welcome"), None, 0),
+        ];
+
+        let stats = session_stats(&turns::turns(&events), 2.0);
+        assert_eq!((stats.prompts, stats.tasks, stats.clarifications), (3, 1, 1));
+        assert_eq!(stats.words, 11);
+        assert_eq!(stats.edits, 1);
+        // The clarifying round used a quarter of the tokens
+        assert!((stats.clarification_cost - 0.5).abs() < 1e-9);
+
+        let session = |project: &str, user: &str| {
+            let mut s = Session::new(AgentType::ClaudeCode, project, project);
+            s.user = Some(user.to_string());
+            s
+        };
+        let clear = PromptStats { sessions: 1, prompts: 4, tasks: 4, edits: 6, cost: 1.0, ..PromptStats::default() };
+        let groups = group(
+            vec![
+                (session("/src/web", "ana"), clear.clone()),
+                (session("/src/api", "ana"), stats.clone()),
+                (session("/src/api", "bo"), stats),
+            ],
+            PromptGroup::Project,
+        );
+        assert_eq!(groups[0].key, "/src/api");
+        assert_eq!(groups[0].stats.sessions, 2);
+        assert_eq!(groups[0].clarifications_per_task, 1.0);
+        assert_eq!(groups[1].edits_per_prompt, 1.5);
+
+        let users = group(vec![(session("/src/web", "ana"), clear)], PromptGroup::User);
+        assert_eq!(users[0].key, "ana");
+        assert_eq!(users[0].cost_per_prompt, 0.25);
+    }
+}
@@ -0,0 +1,142 @@
+//! How often the TUI and web dashboard re-read the database.
+//!
+//! Each view polls on a base interval. With `adaptive` on, a poll that finds
+//! nothing new doubles the wait, up to `max_secs`, and any change drops it
+//! back to the base interval (so does a key press in the TUI). An idle
+//! dashboard on a laptop then queries twice a minute instead of every couple
+//! of seconds.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Refresh interval settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// Seconds between session list refreshes in the TUI
+    pub tui_secs: f64,
+
+    /// Seconds between event refreshes in the TUI's session view
+    pub events_secs: f64,
+
+    /// Seconds between updates pushed to web dashboard clients
+    pub dashboard_secs: f64,
+
+    /// Back off while nothing changes
+    pub adaptive: bool,
+
+    /// Longest wait when backed off
+    pub max_secs: f64,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self {
+            tui_secs: 2.0,
+            events_secs: 1.0,
+            dashboard_secs: 5.0,
+            adaptive: true,
+            max_secs: 30.0,
+        }
+    }
+}
+
+impl RefreshConfig {
+    /// Shortest interval allowed, so a typo can't spin on the database.
+    const MIN_SECS: f64 = 0.1;
+
+    /// Use `secs` (from `--refresh`) for the TUI session list, with events
+    /// refreshed twice as often as before.
+    pub fn with_tui_secs(mut self, secs: Option<f64>) -> Self {
+        if let Some(secs) = secs {
+            self.tui_secs = secs;
+            self.events_secs = secs / 2.0;
+        }
+        self
+    }
+
+    /// Use `secs` (from `--refresh`) for the web dashboard.
+    pub fn with_dashboard_secs(mut self, secs: Option<f64>) -> Self {
+        if let Some(secs) = secs {
+            self.dashboard_secs = secs;
+        }
+        self
+    }
+
+    /// A poller starting at `secs`.
+    pub fn poller(&self, secs: f64) -> Poller {
+        let base = Duration::from_secs_f64(secs.max(Self::MIN_SECS));
+        let max = if self.adaptive { Duration::from_secs_f64(self.max_secs.max(secs)).max(base) } else { base };
+        Poller { base, max, current: base, next: Instant::now() + base }
+    }
+}
+
+/// When to poll next.
+#[derive(Debug, Clone)]
+pub struct Poller {
+    base: Duration,
+    max: Duration,
+    current: Duration,
+    next: Instant,
+}
+
+impl Poller {
+    /// Whether a poll is due at `now`.
+    pub fn due(&self, now: Instant) -> bool {
+        now >= self.next
+    }
+
+    /// Wait before the next poll.
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Record a poll at `now`; unchanged data doubles the wait.
+    pub fn polled(&mut self, now: Instant, changed: bool) {
+        self.current = if changed { self.base } else { (self.current * 2).min(self.max) };
+        self.next = now + self.current;
+    }
+
+    /// Back to the base interval, e.g. when the user is interacting.
+    pub fn reset(&mut self, now: Instant) {
+        if self.current > self.base {
+            self.current = self.base;
+            self.next = self.next.min(now + self.base);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poller_backs_off_and_resets() {
+        let config = RefreshConfig { max_secs: 10.0, ..RefreshConfig::default() };
+        let mut poller = config.poller(2.0);
+        let start = Instant::now();
+        assert!(!poller.due(start));
+        assert!(poller.due(start + Duration::from_secs(2)));
+
+        poller.polled(start, false);
+        assert_eq!(poller.interval(), Duration::from_secs(4));
+        poller.polled(start, false);
+        poller.polled(start, false);
+        // Capped at max_secs
+        assert_eq!(poller.interval(), Duration::from_secs(10));
+        assert!(!poller.due(start + Duration::from_secs(9)));
+
+        poller.reset(start);
+        assert_eq!(poller.interval(), Duration::from_secs(2));
+        assert!(poller.due(start + Duration::from_secs(2)));
+
+        poller.polled(start, false);
+        poller.polled(start, true);
+        assert_eq!(poller.interval(), Duration::from_secs(2));
+
+        // Fixed interval without adaptive
+        let mut fixed = RefreshConfig { adaptive: false, ..RefreshConfig::default() }.poller(5.0);
+        fixed.polled(start, false);
+        assert_eq!(fixed.interval(), Duration::from_secs(5));
+    }
+}
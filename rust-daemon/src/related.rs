@@ -0,0 +1,74 @@
+//! Other sessions of the same project.
+//!
+//! Work on a feature often spans several sessions: one runs out of context,
+//! the next picks up the plan. The TUI's detail view steps through a
+//! project's sessions in the order they started, so a workstream can be
+//! followed without going back to the list.
+
+use crate::models::Session;
+
+/// Where a session stands among its project's sessions.
+#[derive(Debug, Clone)]
+pub struct ProjectPosition {
+    /// Position of the session, from 0 for the project's first session
+    pub index: usize,
+    pub total: usize,
+    /// The session started just before, if any
+    pub previous: Option<Session>,
+    /// The session started just after, if any
+    pub next: Option<Session>,
+}
+
+/// Where `session` stands among `sessions`, which may contain sessions of
+/// other projects and need not contain `session` itself.
+pub fn position(sessions: Vec<Session>, session: &Session) -> ProjectPosition {
+    let mut project: Vec<Session> = sessions
+        .into_iter()
+        .filter(|s| s.project_path == session.project_path && s.id != session.id)
+        .collect();
+    project.push(session.clone());
+    project.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)));
+
+    let index = project.iter().position(|s| s.id == session.id).unwrap_or_default();
+    ProjectPosition {
+        index,
+        total: project.len(),
+        previous: index.checked_sub(1).map(|i| project[i].clone()),
+        next: project.get(index + 1).cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_position_among_project_sessions() {
+        let now = Utc::now();
+        let session = |project: &str, hours_ago: i64| {
+            let mut s = Session::new(AgentType::ClaudeCode, project, &format!("{}-{}", project, hours_ago));
+            s.started_at = now - Duration::hours(hours_ago);
+            s
+        };
+        let first = session("/src/api", 30);
+        let middle = session("/src/api", 10);
+        let last = session("/src/api", 1);
+        let sessions = vec![last.clone(), session("/src/web", 5), first.clone(), middle.clone()];
+
+        let at = position(sessions.clone(), &middle);
+        assert_eq!((at.index, at.total), (1, 3));
+        assert_eq!(at.previous.unwrap().id, first.id);
+        assert_eq!(at.next.unwrap().id, last.id);
+
+        let at = position(sessions, &first);
+        assert_eq!(at.index, 0);
+        assert!(at.previous.is_none());
+
+        // A session missing from the list still counts
+        let alone = position(vec![session("/src/web", 5)], &last);
+        assert_eq!((alone.index, alone.total), (0, 1));
+        assert!(alone.next.is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! HTTP client for reading session data from a remote daemon's web API.
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+use crate::apierrors::ApiErrorHour;
+use crate::duplicates::DuplicatePrompt;
+use crate::models::{ResourceSample, Session, SessionEvent, SessionTag, SummaryMetrics};
+use crate::report::ReportComparison;
+use crate::timeseries::HourlyUsage;
+
+/// Client for the REST API served by `agent-monitor web`.
+#[derive(Clone)]
+pub struct RemoteClient {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RemoteClient {
+    /// Create a new client for a daemon at `base_url` (e.g. http://host:8765).
+    pub fn new(base_url: &str, api_key: Option<String>) -> Result<Self> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(anyhow!("Remote URL must start with http:// or https://"));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+
+        Ok(Self {
+            base_url,
+            api_key,
+            client,
+        })
+    }
+
+    /// Host portion of the base URL, for display.
+    pub fn host(&self) -> &str {
+        self.base_url
+            .split("://")
+            .nth(1)
+            .unwrap_or(&self.base_url)
+    }
+
+    /// Issue a GET request and deserialize the field `key` of the response.
+    async fn get_field<T: DeserializeOwned>(&self, path: &str, key: &str) -> Result<T> {
+        self.send_for_field(self.client.get(format!("{}{}", self.base_url, path)), key)
+            .await
+    }
+
+    /// Send `request` with the API key and deserialize the field `key` of the response.
+    async fn send_for_field<T: DeserializeOwned>(&self, mut request: reqwest::RequestBuilder, key: &str) -> Result<T> {
+        if let Some(ref api_key) = self.api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let mut body: serde_json::Value = response.json().await?;
+
+        if let Some(error) = body.get("error").and_then(|e| e.as_str()) {
+            return Err(anyhow!("Remote error ({}): {}", status, error));
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Remote request failed: {}", status));
+        }
+
+        let value = body
+            .get_mut(key)
+            .map(serde_json::Value::take)
+            .ok_or_else(|| anyhow!("Remote response missing '{}'", key))?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Get active sessions.
+    pub async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.get_field(&format!("/api/sessions?active_only=true&limit={}", limit), "sessions")
+            .await
+    }
+
+    /// Sessions active in the last week, newest first.
+    pub async fn get_recent_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.get_field(&format!("/api/sessions?limit={}", limit), "sessions").await
+    }
+
+    /// Totals over sessions active in the last `hours` hours.
+    pub async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        self.get_field(&format!("/api/metrics/summary?hours={}", hours), "metrics")
+            .await
+    }
+
+    /// Hourly usage for the last `hours`, oldest first.
+    pub async fn get_hourly_usage(&self, hours: i64) -> Result<Vec<HourlyUsage>> {
+        self.get_field(&format!("/api/v1/analytics/hourly?hours={}", hours), "data").await
+    }
+
+    /// API errors per hour for the last `hours`, oldest first.
+    pub async fn get_api_errors(&self, hours: i64) -> Result<Vec<ApiErrorHour>> {
+        self.get_field(&format!("/api/v1/analytics/api-errors?hours={}", hours), "data").await
+    }
+
+    /// Changes over the last `days` since the window `compare` names.
+    pub async fn get_report_comparison(&self, days: i64, compare: &str) -> Result<Option<ReportComparison>> {
+        let report: serde_json::Value = self
+            .get_field(&format!("/api/v1/analytics/report?days={}&compare={}", days, compare), "data")
+            .await?;
+        Ok(serde_json::from_value(report.get("comparison").cloned().unwrap_or_default())?)
+    }
+
+    /// Errors logged since `since`, among the daemon's recent events.
+    pub async fn count_errors_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<usize> {
+        let since = since.to_rfc3339();
+        let since = percent_encoding::utf8_percent_encode(&since, percent_encoding::NON_ALPHANUMERIC);
+        let page: serde_json::Value = self
+            .get_field(&format!("/api/v1/events?event_type=error&per_page=100&since={}", since), "data")
+            .await?;
+        Ok(page.get("total").and_then(serde_json::Value::as_u64).unwrap_or(0) as usize)
+    }
+
+    /// Sessions with an event of `event_type` (like `loop`) since `since`.
+    pub async fn sessions_with_events_since(
+        &self,
+        event_type: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<std::collections::HashSet<String>> {
+        let since = since.to_rfc3339();
+        let since = percent_encoding::utf8_percent_encode(&since, percent_encoding::NON_ALPHANUMERIC);
+        let page: serde_json::Value = self
+            .get_field(&format!("/api/v1/events?event_type={}&per_page=100&since={}", event_type, since), "data")
+            .await?;
+        let items = page.get("items").and_then(serde_json::Value::as_array).cloned().unwrap_or_default();
+        Ok(items
+            .iter()
+            .filter_map(|item| item.get("session_id").and_then(serde_json::Value::as_str).map(String::from))
+            .collect())
+    }
+
+    /// Get events for a specific session (newest first).
+    pub async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        self.get_field(
+            &format!("/api/sessions/{}/events?limit={}", session_id, limit),
+            "events",
+        )
+        .await
+    }
+
+    /// Prompts the given session shares with other sessions.
+    pub async fn get_duplicate_prompts(&self, session_id: &str) -> Result<Vec<DuplicatePrompt>> {
+        self.get_field(&format!("/api/v1/prompts/duplicates?session_id={}", session_id), "data")
+            .await
+    }
+
+    /// Tags on a session.
+    pub async fn get_session_tags(&self, session_id: &str) -> Result<Vec<SessionTag>> {
+        self.get_field(&format!("/api/v1/sessions/{}/tags", session_id), "data")
+            .await
+    }
+
+    /// A session's latest resource samples, newest first.
+    pub async fn get_session_resources(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        self.get_field(&format!("/api/v1/sessions/{}/resources?limit={}", session_id, limit), "data")
+            .await
+    }
+
+    /// Tag a session.
+    pub async fn add_session_tag(&self, session_id: &str, tag: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(format!("{}/api/v1/sessions/{}/tags", self.base_url, session_id))
+            .json(&serde_json::json!({ "tag": tag }));
+        self.send_for_field::<serde_json::Value>(request, "data").await?;
+        Ok(())
+    }
+
+    /// Remove a tag from a session.
+    pub async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<()> {
+        let request = self
+            .client
+            // '/' is the only character tags allow that needs escaping in a path
+            .delete(format!(
+                "{}/api/v1/sessions/{}/tags/{}",
+                self.base_url,
+                session_id,
+                tag.replace('/', "%2F")
+            ));
+        self.send_for_field::<serde_json::Value>(request, "data").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,841 @@
+//! Usage reports over a time window.
+//!
+//! A `Report` rolls up the sessions active in the last N days: spend, the
+//! most expensive projects, how many finished sessions completed rather than
+//! crashed, spend per user, per session tag and per git branch, the most
+//! used slash commands, the most frequent errors and the longest sessions. It backs the `report` command and the weekly email digest.
+//!
+//! A report can be compared with the same window shifted back (`--compare
+//! last-week`): each headline metric gets its change, relative for amounts,
+//! absolute for the session count and in percentage points for rates.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::archive;
+use crate::forecast::Forecast;
+use crate::humanize;
+use crate::models::{EventType, Session, SessionStatus};
+use crate::slash::{self, SlashCommandUsage};
+use crate::storage::Storage;
+use crate::timefmt;
+
+/// Entries in each ranked section.
+const TOP_N: usize = 5;
+
+/// Most sessions considered for one report.
+const MAX_SESSIONS: usize = 10_000;
+
+/// Most error events grouped for one report.
+const MAX_ERRORS: usize = 2000;
+
+/// Most prompts searched for slash commands in one report.
+const MAX_PROMPTS: usize = 20_000;
+
+/// Spend and activity in one project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectUsage {
+    pub project_path: String,
+    pub sessions: usize,
+    pub cost: f64,
+    pub tokens: i64,
+}
+
+/// Spend and activity of one user. Sessions with no user count toward an
+/// empty user name.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserUsage {
+    pub user: String,
+    pub sessions: usize,
+    pub active_sessions: usize,
+    pub messages: i64,
+    pub tool_calls: i64,
+    pub tokens: i64,
+    pub cost: f64,
+    pub last_active_at: DateTime<Utc>,
+}
+
+/// Spend across the sessions carrying one tag. A session with several tags
+/// counts toward each.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub sessions: usize,
+    pub cost: f64,
+    pub tokens: i64,
+}
+
+/// Spend on one git branch of a project. Sessions with no known branch
+/// count toward an empty branch.
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchUsage {
+    pub project_path: String,
+    pub branch: String,
+    pub sessions: usize,
+    pub cost: f64,
+    pub tokens: i64,
+}
+
+/// An error message and how often it occurred.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorCount {
+    pub message: String,
+    pub count: usize,
+    /// Project of the latest occurrence
+    pub project_path: String,
+}
+
+/// One of the longest sessions in the window.
+#[derive(Debug, Clone, Serialize)]
+pub struct LongSession {
+    pub session_id: String,
+    pub project_path: String,
+    pub duration_seconds: f64,
+    pub cost: f64,
+    pub summary: Option<String>,
+}
+
+/// Activity over a time window.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub sessions: usize,
+    pub messages: i64,
+    pub tool_calls: i64,
+    pub tokens: i64,
+    pub cost: f64,
+    /// Share of finished sessions that completed rather than crashed; None if none finished
+    pub success_rate: Option<f64>,
+    /// Share of sessions that logged an error; None if there were none
+    pub error_rate: Option<f64>,
+    pub top_projects: Vec<ProjectUsage>,
+    /// Most expensive users first
+    pub users: Vec<UserUsage>,
+    /// Most expensive tags first
+    pub tags: Vec<TagUsage>,
+    /// Most expensive branches first
+    pub branches: Vec<BranchUsage>,
+    /// Most used first
+    pub slash_commands: Vec<SlashCommandUsage>,
+    pub notable_errors: Vec<ErrorCount>,
+    pub longest_sessions: Vec<LongSession>,
+    /// Month-end spend projection, when the caller adds one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forecast: Option<Forecast>,
+    /// Changes since an earlier window, when the caller adds one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<ReportComparison>,
+}
+
+/// How a change is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaUnit {
+    /// Relative to the earlier value
+    Percent,
+    /// Difference in count
+    Count,
+    /// Difference between rates, in percentage points
+    Points,
+}
+
+/// Which way a metric moved.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+/// One metric in two windows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delta {
+    pub current: f64,
+    pub previous: f64,
+    pub unit: DeltaUnit,
+    pub trend: Trend,
+}
+
+impl Delta {
+    pub fn new(current: f64, previous: f64, unit: DeltaUnit) -> Self {
+        let change = current - previous;
+        // Changes too small to show as a whole number are flat
+        let flat = match unit {
+            DeltaUnit::Percent => (previous == 0.0 && current == 0.0) || (previous != 0.0 && (change / previous).abs() < 0.005),
+            DeltaUnit::Count => change.abs() < 0.5,
+            DeltaUnit::Points => change.abs() < 0.005,
+        };
+        let trend = match () {
+            _ if flat => Trend::Flat,
+            _ if change > 0.0 => Trend::Up,
+            _ => Trend::Down,
+        };
+        Self { current, previous, unit, trend }
+    }
+
+    /// `↑`, `↓` or `→`.
+    pub fn arrow(&self) -> &'static str {
+        match self.trend {
+            Trend::Up => "↑",
+            Trend::Down => "↓",
+            Trend::Flat => "→",
+        }
+    }
+
+    /// The change, like `+32%`, `−5` or `−2pp`.
+    pub fn label(&self) -> String {
+        let change = self.current - self.previous;
+        let sign = if self.trend == Trend::Flat {
+            "±"
+        } else if change > 0.0 {
+            "+"
+        } else {
+            "−"
+        };
+        match self.unit {
+            DeltaUnit::Percent if self.previous == 0.0 && self.current != 0.0 => "new".to_string(),
+            DeltaUnit::Percent if self.trend == Trend::Flat => "±0%".to_string(),
+            DeltaUnit::Percent => format!("{}{:.0}%", sign, (change / self.previous * 100.0).abs()),
+            DeltaUnit::Count => format!("{}{:.0}", sign, change.abs()),
+            DeltaUnit::Points => format!("{}{:.0}pp", sign, (change * 100.0).abs()),
+        }
+    }
+}
+
+/// Headline metrics of a report against the same metrics in an earlier window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportComparison {
+    pub previous_start: DateTime<Utc>,
+    pub previous_end: DateTime<Utc>,
+    pub cost: Delta,
+    pub sessions: Delta,
+    pub messages: Delta,
+    pub tool_calls: Delta,
+    pub tokens: Delta,
+    /// None unless sessions finished in both windows
+    pub success_rate: Option<Delta>,
+    /// None unless there were sessions in both windows
+    pub error_rate: Option<Delta>,
+}
+
+impl ReportComparison {
+    pub fn new(current: &Report, previous: &Report) -> Self {
+        let rate = |current: Option<f64>, previous: Option<f64>| {
+            Some(Delta::new(current?, previous?, DeltaUnit::Points))
+        };
+        Self {
+            previous_start: previous.period_start,
+            previous_end: previous.period_end,
+            cost: Delta::new(current.cost, previous.cost, DeltaUnit::Percent),
+            sessions: Delta::new(current.sessions as f64, previous.sessions as f64, DeltaUnit::Count),
+            messages: Delta::new(current.messages as f64, previous.messages as f64, DeltaUnit::Percent),
+            tool_calls: Delta::new(current.tool_calls as f64, previous.tool_calls as f64, DeltaUnit::Percent),
+            tokens: Delta::new(current.tokens as f64, previous.tokens as f64, DeltaUnit::Percent),
+            success_rate: rate(current.success_rate, previous.success_rate),
+            error_rate: rate(current.error_rate, previous.error_rate),
+        }
+    }
+
+    /// The metrics worth a glance, by name.
+    pub fn highlights(&self) -> Vec<(&'static str, &Delta)> {
+        let mut highlights = vec![("cost", &self.cost), ("sessions", &self.sessions), ("tokens", &self.tokens)];
+        highlights.extend(self.success_rate.as_ref().map(|d| ("success rate", d)));
+        highlights.extend(self.error_rate.as_ref().map(|d| ("error rate", d)));
+        highlights
+    }
+
+    /// One line, like `cost ↑ +32%, sessions ↓ −5, error rate ↓ −2pp`.
+    pub fn summary(&self) -> String {
+        self.highlights()
+            .iter()
+            .map(|(name, delta)| format!("{} {} {}", name, delta.arrow(), delta.label()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// How far back `--compare` looks: `previous` (the window just before),
+/// `yesterday`, `last-week`, `last-month` or an age like `14d`.
+pub fn parse_offset(text: &str, days: i64) -> Result<Duration> {
+    match text {
+        "previous" => Ok(Duration::days(days.max(1))),
+        "yesterday" => Ok(Duration::days(1)),
+        "last-week" => Ok(Duration::weeks(1)),
+        "last-month" => Ok(Duration::days(30)),
+        _ => match archive::parse_age(text) {
+            Ok(offset) if offset > Duration::zero() => Ok(offset),
+            _ => bail!("Invalid comparison '{}' (expected previous, yesterday, last-week, last-month or e.g. 14d)", text),
+        },
+    }
+}
+
+/// Compare `report` with the same window `offset` earlier, with the same
+/// tag and user filters.
+pub async fn compare(
+    storage: &Storage,
+    report: &Report,
+    offset: Duration,
+    tag: Option<&str>,
+    user: Option<&str>,
+) -> Result<ReportComparison> {
+    let previous =
+        build_report_between(storage, report.period_start - offset, report.period_end - offset, tag, user).await?;
+    Ok(ReportComparison::new(report, &previous))
+}
+
+/// Build a report of the last `days` days, optionally only of sessions
+/// tagged `tag` or attributed to `user`.
+pub async fn build_report(storage: &Storage, days: i64, tag: Option<&str>, user: Option<&str>) -> Result<Report> {
+    let now = Utc::now();
+    build_report_between(storage, now - Duration::days(days), now, tag, user).await
+}
+
+/// Build a report of the sessions last active between `start` and `end`.
+pub async fn build_report_between(
+    storage: &Storage,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tag: Option<&str>,
+    user: Option<&str>,
+) -> Result<Report> {
+    let hours = (Utc::now() - start).num_hours() + 1;
+    let mut sessions = storage.get_recent_sessions(hours, MAX_SESSIONS).await?;
+    let mut errors = storage
+        .get_recent_events_of_type(EventType::Error, hours, MAX_ERRORS)
+        .await?;
+    let mut prompts = storage
+        .get_recent_events_of_type(EventType::PromptReceived, hours, MAX_PROMPTS)
+        .await?;
+    sessions.retain(|s| s.last_activity_at >= start && s.last_activity_at < end);
+    errors.retain(|e| e.timestamp >= start && e.timestamp < end);
+    prompts.retain(|e| e.timestamp >= start && e.timestamp < end);
+    if let Some(user) = user {
+        sessions.retain(|s| s.user.as_deref() == Some(user));
+        let ids: std::collections::HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        errors.retain(|e| ids.contains(e.session_id.as_str()));
+        prompts.retain(|e| ids.contains(e.session_id.as_str()));
+    }
+    if let Some(tag) = tag {
+        let tagged = storage.sessions_tagged(tag).await?;
+        sessions.retain(|s| tagged.contains(&s.id));
+        errors.retain(|e| tagged.contains(&e.session_id));
+        prompts.retain(|e| tagged.contains(&e.session_id));
+    }
+    let tags_by_session = storage.tags_by_session().await?;
+
+    let projects_by_session: HashMap<&str, &str> = sessions
+        .iter()
+        .map(|s| (s.id.as_str(), s.project_path.as_str()))
+        .collect();
+
+    // Errors arrive newest first, so the first project seen is the latest
+    let mut error_counts: HashMap<String, ErrorCount> = HashMap::new();
+    for event in &errors {
+        let Some(message) = event
+            .error_message
+            .as_deref()
+            .or(event.content.as_deref())
+            .and_then(|m| m.lines().map(str::trim).find(|l| !l.is_empty()))
+        else {
+            continue;
+        };
+        let message: String = message.chars().take(200).collect();
+        error_counts
+            .entry(message.clone())
+            .or_insert_with(|| ErrorCount {
+                message,
+                count: 0,
+                project_path: projects_by_session
+                    .get(event.session_id.as_str())
+                    .map(|p| p.to_string())
+                    .unwrap_or_default(),
+            })
+            .count += 1;
+    }
+    let with_errors: std::collections::HashSet<&str> = errors
+        .iter()
+        .map(|e| e.session_id.as_str())
+        .filter(|id| projects_by_session.contains_key(id))
+        .collect();
+    let mut notable_errors: Vec<ErrorCount> = error_counts.into_values().collect();
+    notable_errors.sort_by(|a, b| b.count.cmp(&a.count).then(a.message.cmp(&b.message)));
+    notable_errors.truncate(TOP_N);
+
+    Ok(Report {
+        period_start: start,
+        period_end: end,
+        sessions: sessions.len(),
+        messages: sessions.iter().map(|s| s.message_count).sum(),
+        tool_calls: sessions.iter().map(|s| s.tool_call_count).sum(),
+        tokens: sessions.iter().map(|s| s.tokens_input + s.tokens_output).sum(),
+        cost: sessions.iter().map(|s| s.estimated_cost).sum(),
+        success_rate: success_rate(&sessions),
+        error_rate: (!sessions.is_empty()).then(|| with_errors.len() as f64 / sessions.len() as f64),
+        top_projects: top_projects(&sessions),
+        users: {
+            let mut users = user_usage(&sessions);
+            users.truncate(TOP_N);
+            users
+        },
+        tags: {
+            let mut tags = tag_usage(&sessions, &tags_by_session);
+            tags.truncate(TOP_N);
+            tags
+        },
+        branches: {
+            let mut branches = branch_usage(&sessions);
+            branches.truncate(TOP_N);
+            branches
+        },
+        slash_commands: {
+            let mut commands = slash::usage(&prompts);
+            commands.truncate(TOP_N);
+            commands
+        },
+        notable_errors,
+        longest_sessions: longest_sessions(&sessions),
+        forecast: None,
+        comparison: None,
+    })
+}
+
+fn success_rate(sessions: &[Session]) -> Option<f64> {
+    let completed = sessions.iter().filter(|s| s.status == SessionStatus::Completed).count();
+    let crashed = sessions.iter().filter(|s| s.status == SessionStatus::Crashed).count();
+    let finished = completed + crashed;
+    (finished > 0).then(|| completed as f64 / finished as f64)
+}
+
+fn top_projects(sessions: &[Session]) -> Vec<ProjectUsage> {
+    let mut projects: HashMap<&str, ProjectUsage> = HashMap::new();
+    for session in sessions {
+        let usage = projects
+            .entry(session.project_path.as_str())
+            .or_insert_with(|| ProjectUsage {
+                project_path: session.project_path.clone(),
+                sessions: 0,
+                cost: 0.0,
+                tokens: 0,
+            });
+        usage.sessions += 1;
+        usage.cost += session.estimated_cost;
+        usage.tokens += session.tokens_input + session.tokens_output;
+    }
+
+    let mut projects: Vec<ProjectUsage> = projects.into_values().collect();
+    projects.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(b.sessions.cmp(&a.sessions)));
+    projects.truncate(TOP_N);
+    projects
+}
+
+/// Spend and activity per user across `sessions`, most expensive first.
+pub fn user_usage(sessions: &[Session]) -> Vec<UserUsage> {
+    let mut usage: HashMap<&str, UserUsage> = HashMap::new();
+    for session in sessions {
+        let user = session.user.as_deref().unwrap_or_default();
+        let entry = usage.entry(user).or_insert_with(|| UserUsage {
+            user: user.to_string(),
+            sessions: 0,
+            active_sessions: 0,
+            messages: 0,
+            tool_calls: 0,
+            tokens: 0,
+            cost: 0.0,
+            last_active_at: session.last_activity_at,
+        });
+        entry.sessions += 1;
+        if session.status == SessionStatus::Active {
+            entry.active_sessions += 1;
+        }
+        entry.messages += session.message_count;
+        entry.tool_calls += session.tool_call_count;
+        entry.tokens += session.tokens_input + session.tokens_output;
+        entry.cost += session.estimated_cost;
+        entry.last_active_at = entry.last_active_at.max(session.last_activity_at);
+    }
+
+    let mut usage: Vec<UserUsage> = usage.into_values().collect();
+    usage.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(a.user.cmp(&b.user)));
+    usage
+}
+
+/// Spend per tag across `sessions`, most expensive first.
+pub fn tag_usage(sessions: &[Session], tags_by_session: &HashMap<String, Vec<String>>) -> Vec<TagUsage> {
+    let mut usage: HashMap<&str, TagUsage> = HashMap::new();
+    for session in sessions {
+        for tag in tags_by_session.get(&session.id).into_iter().flatten() {
+            let entry = usage.entry(tag.as_str()).or_insert_with(|| TagUsage {
+                tag: tag.clone(),
+                sessions: 0,
+                cost: 0.0,
+                tokens: 0,
+            });
+            entry.sessions += 1;
+            entry.cost += session.estimated_cost;
+            entry.tokens += session.tokens_input + session.tokens_output;
+        }
+    }
+
+    let mut usage: Vec<TagUsage> = usage.into_values().collect();
+    usage.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(a.tag.cmp(&b.tag)));
+    usage
+}
+
+/// Spend per project branch across `sessions`, most expensive first.
+pub fn branch_usage(sessions: &[Session]) -> Vec<BranchUsage> {
+    let mut usage: HashMap<(&str, &str), BranchUsage> = HashMap::new();
+    for session in sessions {
+        let branch = session.git_branch.as_deref().unwrap_or_default();
+        let entry = usage
+            .entry((session.project_path.as_str(), branch))
+            .or_insert_with(|| BranchUsage {
+                project_path: session.project_path.clone(),
+                branch: branch.to_string(),
+                sessions: 0,
+                cost: 0.0,
+                tokens: 0,
+            });
+        entry.sessions += 1;
+        entry.cost += session.estimated_cost;
+        entry.tokens += session.tokens_input + session.tokens_output;
+    }
+
+    let mut usage: Vec<BranchUsage> = usage.into_values().collect();
+    usage.sort_by(|a, b| {
+        b.cost
+            .total_cmp(&a.cost)
+            .then_with(|| a.project_path.cmp(&b.project_path))
+            .then_with(|| a.branch.cmp(&b.branch))
+    });
+    usage
+}
+
+fn longest_sessions(sessions: &[Session]) -> Vec<LongSession> {
+    let mut sorted: Vec<&Session> = sessions.iter().collect();
+    sorted.sort_by(|a, b| b.duration_seconds.total_cmp(&a.duration_seconds));
+    sorted
+        .into_iter()
+        .take(TOP_N)
+        .map(|s| LongSession {
+            session_id: s.id.clone(),
+            project_path: s.project_path.clone(),
+            duration_seconds: s.duration_seconds,
+            cost: s.estimated_cost,
+            summary: s.summary.clone(),
+        })
+        .collect()
+}
+
+impl Report {
+    /// Render the report as a self-contained HTML page (inline styles only,
+    /// so it survives email clients).
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str(
+            "<!DOCTYPE html><html><body style=\"font-family:-apple-system,Segoe UI,sans-serif;\
+             color:#1f2330;max-width:640px;margin:0 auto;padding:16px\">",
+        );
+        html.push_str(&format!(
+            "<h2 style=\"margin-bottom:4px\">✦ Agent Monitor digest</h2>\
+             <p style=\"color:#6b7080;margin-top:0\">{} – {}</p>",
+            timefmt::absolute(self.period_start, "%b %-d"),
+            timefmt::absolute(self.period_end, "%b %-d, %Y")
+        ));
+
+        let success = self
+            .success_rate
+            .map(|r| format!("{:.0}%", r * 100.0))
+            .unwrap_or_else(|| "–".to_string());
+        html.push_str("<table style=\"width:100%;border-collapse:collapse;margin-bottom:16px\"><tr>");
+        for (label, value) in [
+            ("Spend", humanize::cost(self.cost)),
+            ("Sessions", self.sessions.to_string()),
+            ("Messages", self.messages.to_string()),
+            ("Success rate", success),
+        ] {
+            html.push_str(&format!(
+                "<td style=\"padding:8px;background:#f3f4f8;text-align:center\">\
+                 <div style=\"font-size:20px;font-weight:bold\">{}</div>\
+                 <div style=\"color:#6b7080;font-size:12px\">{}</div></td>",
+                value, label
+            ));
+        }
+        html.push_str("</tr></table>");
+
+        if let Some(ref comparison) = self.comparison {
+            html.push_str(&format!(
+                "<p>Since {} – {}: {}.</p>",
+                timefmt::absolute(comparison.previous_start, "%b %-d"),
+                timefmt::absolute(comparison.previous_end, "%b %-d"),
+                escape(&comparison.summary())
+            ));
+        }
+
+        if let Some(ref forecast) = self.forecast {
+            let budget = forecast
+                .total
+                .budget
+                .map(|b| format!(" of a {} budget", humanize::cost(b)))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<p>Burning <b>{}/day</b>; projected <b>{}</b> this month{}.</p>",
+                humanize::cost(forecast.total.daily_burn), humanize::cost(forecast.total.projected_month), budget
+            ));
+        }
+
+        html.push_str(&section("Top projects"));
+        if self.top_projects.is_empty() {
+            html.push_str(&empty_note("No sessions this period."));
+        } else {
+            html.push_str(&table_start(&["Project", "Sessions", "Tokens", "Cost"]));
+            for project in &self.top_projects {
+                html.push_str(&row(&[
+                    escape(project_name(&project.project_path)),
+                    project.sessions.to_string(),
+                    project.tokens.to_string(),
+                    humanize::cost(project.cost),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        // A single user's report needs no breakdown
+        if self.users.len() > 1 {
+            html.push_str(&section("Spend by user"));
+            html.push_str(&table_start(&["User", "Sessions", "Tokens", "Cost"]));
+            for user in &self.users {
+                html.push_str(&row(&[
+                    escape(if user.user.is_empty() { "–" } else { &user.user }),
+                    user.sessions.to_string(),
+                    user.tokens.to_string(),
+                    humanize::cost(user.cost),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        if !self.tags.is_empty() {
+            html.push_str(&section("Spend by tag"));
+            html.push_str(&table_start(&["Tag", "Sessions", "Tokens", "Cost"]));
+            for tag in &self.tags {
+                html.push_str(&row(&[
+                    escape(&tag.tag),
+                    tag.sessions.to_string(),
+                    tag.tokens.to_string(),
+                    humanize::cost(tag.cost),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        if self.branches.iter().any(|b| !b.branch.is_empty()) {
+            html.push_str(&section("Spend by branch"));
+            html.push_str(&table_start(&["Project", "Branch", "Sessions", "Tokens", "Cost"]));
+            for branch in &self.branches {
+                html.push_str(&row(&[
+                    escape(project_name(&branch.project_path)),
+                    escape(if branch.branch.is_empty() { "–" } else { &branch.branch }),
+                    branch.sessions.to_string(),
+                    branch.tokens.to_string(),
+                    humanize::cost(branch.cost),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        if !self.slash_commands.is_empty() {
+            html.push_str(&section("Slash commands"));
+            html.push_str(&table_start(&["Command", "Uses", "Sessions"]));
+            for command in &self.slash_commands {
+                html.push_str(&row(&[
+                    escape(&command.command),
+                    command.uses.to_string(),
+                    command.sessions.to_string(),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        html.push_str(&section("Notable errors"));
+        if self.notable_errors.is_empty() {
+            html.push_str(&empty_note("No errors recorded."));
+        } else {
+            html.push_str(&table_start(&["Error", "Count", "Project"]));
+            for error in &self.notable_errors {
+                html.push_str(&row(&[
+                    escape(&error.message),
+                    error.count.to_string(),
+                    escape(project_name(&error.project_path)),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        html.push_str(&section("Longest sessions"));
+        if self.longest_sessions.is_empty() {
+            html.push_str(&empty_note("No sessions this period."));
+        } else {
+            html.push_str(&table_start(&["Project", "Duration", "Cost", "Summary"]));
+            for session in &self.longest_sessions {
+                let summary = session
+                    .summary
+                    .as_deref()
+                    .and_then(|s| s.lines().next())
+                    .unwrap_or("");
+                html.push_str(&row(&[
+                    escape(project_name(&session.project_path)),
+                    humanize::report_duration(session.duration_seconds),
+                    humanize::cost(session.cost),
+                    escape(summary),
+                ]));
+            }
+            html.push_str("</table>");
+        }
+
+        html.push_str("</body></html>");
+        html
+    }
+}
+
+fn section(title: &str) -> String {
+    format!("<h3 style=\"margin:20px 0 8px\">{}</h3>", title)
+}
+
+fn empty_note(text: &str) -> String {
+    format!("<p style=\"color:#6b7080\">{}</p>", text)
+}
+
+fn table_start(headers: &[&str]) -> String {
+    let cells: String = headers
+        .iter()
+        .map(|h| format!("<th style=\"text-align:left;padding:4px 8px;border-bottom:1px solid #d8dae3\">{}</th>", h))
+        .collect();
+    format!("<table style=\"width:100%;border-collapse:collapse;font-size:14px\"><tr>{}</tr>", cells)
+}
+
+fn row(cells: &[String]) -> String {
+    let cells: String = cells
+        .iter()
+        .map(|c| format!("<td style=\"padding:4px 8px;border-bottom:1px solid #eceef3\">{}</td>", c))
+        .collect();
+    format!("<tr>{}</tr>", cells)
+}
+
+fn project_name(path: &str) -> &str {
+    path.rsplit('/').find(|p| !p.is_empty()).unwrap_or(path)
+}
+
+/// Escape text for inclusion in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, SessionEvent, SessionTag};
+
+    #[tokio::test]
+    async fn test_build_report() {
+        let storage = Storage::in_memory();
+        let mut api = Session::new(AgentType::ClaudeCode, "/work/api", "1");
+        api.estimated_cost = 4.0;
+        api.duration_seconds = 7200.0;
+        api.status = SessionStatus::Completed;
+        api.git_branch = Some("feature/invoices".to_string());
+        api.user = Some("ana".to_string());
+        let mut web = Session::new(AgentType::ClaudeCode, "/work/web", "2");
+        web.user = Some("ben".to_string());
+        web.estimated_cost = 1.5;
+        web.status = SessionStatus::Crashed;
+        storage.upsert_session(&api).await.unwrap();
+        storage.upsert_session(&web).await.unwrap();
+
+        for _ in 0..2 {
+            let mut error = SessionEvent::new(&web.id, EventType::Error, AgentType::ClaudeCode);
+            error.error_message = Some("rate limit exceeded <429>\ntrace".to_string());
+            storage.insert_event(&error).await.unwrap();
+        }
+
+        storage
+            .add_session_tag(&SessionTag::new(&api.id, "experiment", "manual"))
+            .await
+            .unwrap();
+
+        let report = build_report(&storage, 7, None, None).await.unwrap();
+        assert_eq!(report.sessions, 2);
+        assert!((report.cost - 5.5).abs() < 1e-9);
+        assert_eq!(report.success_rate, Some(0.5));
+        assert_eq!(report.top_projects[0].project_path, "/work/api");
+        assert_eq!(report.notable_errors[0].count, 2);
+        assert_eq!(report.notable_errors[0].project_path, "/work/web");
+        assert_eq!(report.longest_sessions[0].session_id, api.id);
+        assert_eq!(report.tags.len(), 1);
+        assert_eq!(report.tags[0].tag, "experiment");
+        assert!((report.tags[0].cost - 4.0).abs() < 1e-9);
+        assert_eq!(report.users.len(), 2);
+        assert_eq!(report.users[0].user, "ana");
+        assert_eq!(report.branches.len(), 2);
+        assert_eq!(report.branches[0].branch, "feature/invoices");
+        assert_eq!(report.branches[1].branch, "");
+
+        let html = report.to_html();
+        assert!(html.contains("rate limit exceeded &lt;429&gt;"));
+        assert!(html.contains("$5.50"));
+        assert!(html.contains("feature/invoices"));
+
+        let tagged = build_report(&storage, 7, Some("experiment"), None).await.unwrap();
+        assert_eq!(tagged.sessions, 1);
+        assert!((tagged.cost - 4.0).abs() < 1e-9);
+
+        let ben = build_report(&storage, 7, None, Some("ben")).await.unwrap();
+        assert_eq!(ben.sessions, 1);
+        assert_eq!(ben.notable_errors[0].count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_compare_with_last_week() {
+        let storage = Storage::in_memory();
+        for (days_ago, cost, crashed) in [(1, 2.0, false), (2, 2.0, true), (8, 1.0, false), (9, 1.0, false), (10, 1.0, true)] {
+            let mut session = Session::new(AgentType::ClaudeCode, "/work/api", &format!("{}", days_ago));
+            session.last_activity_at = Utc::now() - Duration::days(days_ago);
+            session.estimated_cost = cost;
+            session.status = if crashed { SessionStatus::Crashed } else { SessionStatus::Completed };
+            storage.upsert_session(&session).await.unwrap();
+            if crashed {
+                let mut error = SessionEvent::new(&session.id, EventType::Error, AgentType::ClaudeCode);
+                error.timestamp = session.last_activity_at;
+                error.error_message = Some("boom".to_string());
+                storage.insert_event(&error).await.unwrap();
+            }
+        }
+
+        let report = build_report(&storage, 7, None, None).await.unwrap();
+        assert_eq!(report.sessions, 2);
+        assert_eq!(report.error_rate, Some(0.5));
+        let offset = parse_offset("last-week", 7).unwrap();
+        assert_eq!(offset, parse_offset("previous", 7).unwrap());
+        assert!(parse_offset("someday", 7).is_err());
+
+        let comparison = compare(&storage, &report, offset, None, None).await.unwrap();
+        assert_eq!((comparison.sessions.previous, comparison.sessions.label()), (3.0, "−1".to_string()));
+        assert_eq!((comparison.cost.arrow(), comparison.cost.label()), ("↑", "+33%".to_string()));
+        assert_eq!(comparison.error_rate.as_ref().unwrap().label(), "+17pp");
+        assert_eq!(
+            comparison.summary(),
+            "cost ↑ +33%, sessions ↓ −1, tokens → ±0%, success rate ↓ −17pp, error rate ↑ +17pp"
+        );
+        assert_eq!(Delta::new(3.0, 0.0, DeltaUnit::Percent).label(), "new");
+
+        let mut report = report;
+        report.comparison = Some(comparison);
+        assert!(report.to_html().contains("cost ↑ +33%"));
+    }
+}
@@ -0,0 +1,174 @@
+//! CPU and memory tracking for agent sessions.
+//!
+//! Every `interval_secs` the sampler finds each active session's agent
+//! processes, adds everything they spawned (tool runs, shells, language
+//! servers) and records the summed CPU and resident memory as a resource
+//! sample. A session's processes are its PID when it has one, otherwise the
+//! agent's processes running in the session's project directory. CPU is
+//! measured between samples, so a process's first sample reads zero.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use tokio::time::{interval, Duration};
+use tracing::{debug, warn};
+
+use crate::adapters::process_matcher;
+use crate::models::{ResourceSample, Session};
+use crate::procwatch::{descendants, ProcessInfo, ProcessWatcher};
+use crate::storage::Storage;
+
+/// Most active sessions sampled per round.
+const MAX_ACTIVE: usize = 500;
+
+/// Resource sampling settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourcesConfig {
+    /// Whether active sessions' CPU and memory are sampled
+    pub enabled: bool,
+
+    /// Seconds between samples
+    pub interval_secs: u64,
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 15,
+        }
+    }
+}
+
+/// Periodically records the resource usage of active sessions.
+pub struct ResourceSampler {
+    config: ResourcesConfig,
+    storage: Storage,
+    processes: ProcessWatcher,
+    /// Separate from the watcher's, since refreshing a few PIDs drops every
+    /// other process from a `System`
+    system: System,
+}
+
+impl ResourceSampler {
+    pub fn new(config: ResourcesConfig, storage: Storage, processes: ProcessWatcher) -> Self {
+        Self {
+            config,
+            storage,
+            processes,
+            system: System::new(),
+        }
+    }
+
+    /// Sample until the daemon stops.
+    pub async fn run(mut self) {
+        let mut ticker = interval(Duration::from_secs(self.config.interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.sample().await {
+                warn!("Resource sampling failed: {}", e);
+            }
+        }
+    }
+
+    async fn sample(&mut self) -> Result<()> {
+        let sessions = self.storage.get_active_sessions(MAX_ACTIVE).await?;
+        if sessions.is_empty() {
+            return Ok(());
+        }
+
+        let processes = self.processes.snapshot();
+        let trees: Vec<(&Session, Vec<u32>)> = sessions
+            .iter()
+            .map(|s| (s, descendants(&processes, &session_roots(s, &processes))))
+            .filter(|(_, tree)| !tree.is_empty())
+            .collect();
+
+        let pids: Vec<Pid> = trees
+            .iter()
+            .flat_map(|(_, tree)| tree.iter().map(|pid| Pid::from_u32(*pid)))
+            .collect();
+        self.system
+            .refresh_pids_specifics(&pids, ProcessRefreshKind::new().with_cpu().with_memory());
+
+        let now = Utc::now();
+        for (session, tree) in trees {
+            let mut sample = ResourceSample {
+                session_id: session.id.clone(),
+                timestamp: now,
+                cpu_percent: 0.0,
+                memory_bytes: 0,
+                process_count: 0,
+            };
+            for process in tree.iter().filter_map(|pid| self.system.process(Pid::from_u32(*pid))) {
+                sample.cpu_percent += process.cpu_usage() as f64;
+                sample.memory_bytes += process.memory() as i64;
+                sample.process_count += 1;
+            }
+            if sample.process_count > 0 {
+                self.storage.insert_resource_sample(&sample).await?;
+            }
+        }
+        debug!("Sampled resources of {} processes", pids.len());
+        Ok(())
+    }
+}
+
+/// The agent processes a session runs in: its PID when known, otherwise the
+/// agent's processes in its project directory.
+pub fn session_roots(session: &Session, processes: &[ProcessInfo]) -> Vec<u32> {
+    if let Some(pid) = session.pid {
+        return vec![pid as u32];
+    }
+    let Some(matcher) = process_matcher(session.agent_type) else {
+        return Vec::new();
+    };
+    processes
+        .iter()
+        .filter(|p| p.cwd.as_deref() == Some(session.project_path.as_str()) && matcher.matches(p))
+        .map(|p| p.pid)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_session_roots_and_tree() {
+        let process = |pid: u32, parent: u32, name: &str, cwd: &str| ProcessInfo {
+            pid,
+            parent: Some(parent),
+            name: name.to_string(),
+            cmd: name.to_string(),
+            cwd: Some(cwd.to_string()),
+            start_time: 0,
+        };
+        let processes = vec![
+            process(10, 1, "claude", "/work/api"),
+            process(11, 10, "bash", "/work/api"),
+            process(12, 11, "cargo", "/work/api"),
+            process(20, 1, "claude", "/work/web"),
+            process(21, 20, "node", "/work/web"),
+            process(30, 1, "bash", "/work/api"),
+        ];
+
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        let roots = session_roots(&session, &processes);
+        assert_eq!(roots, vec![10]);
+        let mut tree = descendants(&processes, &roots);
+        tree.sort();
+        assert_eq!(tree, vec![10, 11, 12]);
+
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "def");
+        session.pid = Some(20);
+        assert_eq!(descendants(&processes, &session_roots(&session, &processes)).len(), 2);
+
+        // A PID that is no longer running has no tree
+        session.pid = Some(99);
+        assert!(descendants(&processes, &session_roots(&session, &processes)).is_empty());
+    }
+}
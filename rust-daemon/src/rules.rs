@@ -0,0 +1,954 @@
+//! Automation rules: "if this then that" for agent sessions.
+//!
+//! A rule pairs a trigger (an event arrives, a session gets expensive, goes
+//! idle or nears its context limit, a session's circuit breaker opens) with actions (webhook, shell
+//! command, desktop or push notification, mark or tag the session, write a
+//! memory entry).
+//!
+//! Rules come from the `rules` list in the config file or are managed at
+//! runtime through `/api/v1/rules`; runtime rules are saved to `rules.json`
+//! in the config directory. Event triggers fire on every matching event,
+//! state triggers fire once per session (idle re-arms when the session
+//! becomes active again, context once it drops back below the threshold
+//! after a compaction). Every execution is logged and kept for the API.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::analytics::{AnalyticsManager, MemoryStore};
+use crate::config::Config;
+use crate::context;
+use crate::events::{EventBus, EventFilter};
+use crate::humanize;
+use crate::models::{normalize_tag, EventType, Session, SessionEvent, SessionStatus, SessionTag};
+use crate::notifications::Notifier;
+use crate::storage::Storage;
+
+/// How often cost and idle triggers are evaluated.
+const CHECK_INTERVAL_SECS: u64 = 15;
+
+/// Executions kept for `/api/v1/rules/log`.
+const MAX_LOG_ENTRIES: usize = 200;
+
+/// Event IDs remembered to skip duplicate publishes from re-read transcripts.
+const MAX_SEEN_EVENTS: usize = 10_000;
+
+/// Longest a command action may run.
+const COMMAND_TIMEOUT_SECS: u64 = 30;
+
+/// Tools whose use counts as progress for the circuit breaker.
+const WRITE_TOOLS: &[&str] = &["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
+/// A trigger and the actions it runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationRule {
+    /// Stable identifier; generated for API-created rules when empty
+    #[serde(default)]
+    pub id: String,
+
+    /// Human-readable name used in logs
+    pub name: String,
+
+    /// Whether the rule is evaluated
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// What makes the rule fire
+    pub trigger: Trigger,
+
+    /// What happens when it fires, run in order
+    pub actions: Vec<Action>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Conditions that fire a rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// An event arrives. Every condition that is set must match.
+    Event {
+        /// Event types such as `tool_executed` or `error` (empty matches any)
+        #[serde(default)]
+        event_types: Vec<String>,
+        /// Tool name the event must carry
+        #[serde(default)]
+        tool: Option<String>,
+        /// Regex matched against the event content
+        #[serde(default)]
+        content: Option<String>,
+    },
+    /// A session's estimated cost goes above `above` USD
+    Cost { above: f64 },
+    /// An active session has had no activity for `minutes`
+    Idle { minutes: i64 },
+    /// A session's context window is more than `above` percent full
+    Context { above: f64 },
+    /// A session's circuit breaker opens (no progress or repeated errors)
+    CircuitOpen,
+}
+
+impl Trigger {
+    fn describe(&self) -> String {
+        match self {
+            Trigger::Event { event_types, .. } if event_types.is_empty() => "event".to_string(),
+            Trigger::Event { event_types, .. } => format!("event {}", event_types.join("|")),
+            Trigger::Cost { above } => format!("cost > {}", humanize::cost(*above)),
+            Trigger::Idle { minutes } => format!("idle > {}m", minutes),
+            Trigger::Context { above } => format!("context > {:.0}%", above),
+            Trigger::CircuitOpen => "circuit open".to_string(),
+        }
+    }
+}
+
+/// Things a rule can do. String fields accept `{rule}`, `{session_id}`,
+/// `{project}`, `{cost}`, `{event_type}`, `{tool}` and `{reason}` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    /// POST the firing (rule, reason, session, event) as JSON
+    Webhook { url: String },
+    /// Run a shell command; placeholders are substituted shell-quoted, and
+    /// details are also passed as `AGENT_MONITOR_*` env vars
+    Command { command: String },
+    /// Show a desktop notification, or push it to a configured channel
+    /// (ntfy, Pushover, Gotify)
+    Notify {
+        #[serde(default)]
+        title: Option<String>,
+        message: String,
+        #[serde(default)]
+        channel: Option<String>,
+    },
+    /// Record a mark event on the session's timeline
+    Mark { tag: String },
+    /// Attach a tag to the session, e.g. `expensive` or `tool:{tool}`
+    Tag { tag: String },
+    /// Write a cross-session memory entry (defaults to the firing payload),
+    /// scoped to the session's project
+    Memory {
+        key: String,
+        #[serde(default)]
+        value: Option<Value>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+impl Action {
+    fn kind(&self) -> &'static str {
+        match self {
+            Action::Webhook { .. } => "webhook",
+            Action::Command { .. } => "command",
+            Action::Notify { .. } => "notify",
+            Action::Mark { .. } => "mark",
+            Action::Tag { .. } => "tag",
+            Action::Memory { .. } => "memory",
+        }
+    }
+}
+
+/// A rule as listed by the API, with where it was defined.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleInfo {
+    #[serde(flatten)]
+    pub rule: AutomationRule,
+    /// `config` (read-only) or `api`
+    pub source: String,
+}
+
+/// Outcome of one action within an execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionResult {
+    pub action: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// A logged rule execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleExecution {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub session_id: Option<String>,
+    pub reason: String,
+    pub actions: Vec<ActionResult>,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// A rule with its content regex compiled.
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    rule: AutomationRule,
+    content: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(rule: AutomationRule, notifier: &Notifier) -> Result<Self> {
+        if rule.name.trim().is_empty() {
+            bail!("Rule name must not be empty");
+        }
+        if rule.actions.is_empty() {
+            bail!("Rule '{}' has no actions", rule.name);
+        }
+        for action in &rule.actions {
+            match action {
+                Action::Notify { channel: Some(channel), .. } => notifier
+                    .check_channel(channel)
+                    .with_context(|| format!("Invalid notify action in rule '{}'", rule.name))?,
+                // Templates are checked once expanded
+                Action::Tag { tag } if !tag.contains('{') && normalize_tag(tag).is_none() => {
+                    bail!("Invalid tag '{}' in rule '{}'", tag, rule.name)
+                }
+                _ => {}
+            }
+        }
+        let content = match &rule.trigger {
+            Trigger::Event {
+                content: Some(pattern), ..
+            } => Some(
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid content regex in rule '{}'", rule.name))?,
+            ),
+            _ => None,
+        };
+        Ok(Self { rule, content })
+    }
+
+    fn matches_event(&self, event: &SessionEvent) -> bool {
+        let Trigger::Event { event_types, tool, .. } = &self.rule.trigger else {
+            return false;
+        };
+        if !event_types.is_empty() {
+            let name = event_type_name(event.event_type);
+            if !event_types.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                return false;
+            }
+        }
+        if let Some(tool) = tool {
+            if event.tool_name.as_deref() != Some(tool.as_str()) {
+                return false;
+            }
+        }
+        if let Some(ref content) = self.content {
+            if !event.content.as_deref().is_some_and(|c| content.is_match(c)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Serialized (snake_case) name of an event type.
+fn event_type_name(event_type: EventType) -> String {
+    serde_json::to_value(event_type)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// `value` as one word for `sh`: project paths and tool names come from
+/// transcripts and hooks, and must not run as commands.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Everything an action can refer to about one firing.
+struct Firing<'a> {
+    rule: &'a AutomationRule,
+    session: Option<Session>,
+    event: Option<&'a SessionEvent>,
+    reason: String,
+}
+
+impl Firing<'_> {
+    fn expand(&self, template: &str) -> String {
+        self.expand_with(template, str::to_string)
+    }
+
+    /// `template` with each placeholder replaced by `quote` of its value, in
+    /// one pass so that values are never expanded themselves.
+    fn expand_with(&self, template: &str, quote: impl Fn(&str) -> String) -> String {
+        let mut expanded = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let placeholder = rest.find('}').and_then(|end| Some((end, self.value(&rest[1..end])?)));
+            match placeholder {
+                Some((end, value)) => {
+                    expanded.push_str(&quote(&value));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    expanded.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// The value of the placeholder `name`, if there is one by that name.
+    fn value(&self, name: &str) -> Option<String> {
+        let session = self.session.as_ref();
+        Some(match name {
+            "rule" => self.rule.name.clone(),
+            "session_id" => session.map(|s| s.id.clone()).unwrap_or_default(),
+            "project" => session.map(|s| s.project_path.clone()).unwrap_or_default(),
+            "cost" => format!("{:.2}", session.map(|s| s.estimated_cost).unwrap_or(0.0)),
+            "event_type" => self.event.map(|e| event_type_name(e.event_type)).unwrap_or_default(),
+            "tool" => self.event.and_then(|e| e.tool_name.clone()).unwrap_or_default(),
+            "reason" => self.reason.clone(),
+            _ => return None,
+        })
+    }
+
+    fn payload(&self) -> Value {
+        serde_json::json!({
+            "rule": { "id": self.rule.id, "name": self.rule.name },
+            "reason": self.reason,
+            "session": self.session,
+            "event": self.event,
+            "timestamp": Utc::now(),
+        })
+    }
+}
+
+struct EngineInner {
+    config_rules: Vec<CompiledRule>,
+    api_rules: RwLock<Vec<CompiledRule>>,
+    rules_path: PathBuf,
+    storage: Storage,
+    memory: MemoryStore,
+    analytics: AnalyticsManager,
+    /// (rule, session) pairs a state trigger already fired for, with the
+    /// session's last activity at the time
+    fired: RwLock<HashMap<(String, String), DateTime<Utc>>>,
+    log: RwLock<VecDeque<RuleExecution>>,
+    client: reqwest::Client,
+    notifier: Notifier,
+}
+
+/// Evaluates automation rules against live sessions and events.
+#[derive(Clone)]
+pub struct RulesEngine {
+    inner: Arc<EngineInner>,
+}
+
+impl RulesEngine {
+    /// Compile the config rules and load runtime rules from the config directory.
+    pub async fn new(config: &Config, storage: Storage) -> Result<Self> {
+        let notifier = Notifier::new(&config.notification_channels);
+        let config_rules = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let mut rule = rule.clone();
+                if rule.id.is_empty() {
+                    rule.id = format!("config-{}", i + 1);
+                }
+                CompiledRule::compile(rule, &notifier)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let rules_path = config.config_dir.join("rules.json");
+        let api_rules = if rules_path.exists() {
+            let content = std::fs::read_to_string(&rules_path)?;
+            let rules: Vec<AutomationRule> = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", rules_path.display()))?;
+            rules
+                .into_iter()
+                .map(|rule| CompiledRule::compile(rule, &notifier))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            inner: Arc::new(EngineInner {
+                config_rules,
+                api_rules: RwLock::new(api_rules),
+                rules_path,
+                memory: MemoryStore::new(storage.clone()),
+                storage,
+                analytics: AnalyticsManager::new(0),
+                fired: RwLock::new(HashMap::new()),
+                log: RwLock::new(VecDeque::new()),
+                client: reqwest::Client::builder()
+                    .timeout(std::time::Duration::from_secs(10))
+                    .build()
+                    .unwrap_or_default(),
+                notifier,
+            }),
+        })
+    }
+
+    /// All rules with their source.
+    pub async fn list(&self) -> Vec<RuleInfo> {
+        let config = self.inner.config_rules.iter().map(|c| RuleInfo {
+            rule: c.rule.clone(),
+            source: "config".to_string(),
+        });
+        let api: Vec<RuleInfo> = self
+            .inner
+            .api_rules
+            .read()
+            .await
+            .iter()
+            .map(|c| RuleInfo {
+                rule: c.rule.clone(),
+                source: "api".to_string(),
+            })
+            .collect();
+        config.chain(api).collect()
+    }
+
+    /// Create or replace a runtime rule and save the runtime rule set.
+    pub async fn upsert(&self, mut rule: AutomationRule) -> Result<AutomationRule> {
+        if rule.id.is_empty() {
+            rule.id = uuid::Uuid::new_v4().to_string();
+        }
+        if self.inner.config_rules.iter().any(|c| c.rule.id == rule.id) {
+            bail!("Rule '{}' is defined in the config file and cannot be changed via the API", rule.id);
+        }
+        let compiled = CompiledRule::compile(rule.clone(), &self.inner.notifier)?;
+
+        let mut rules = self.inner.api_rules.write().await;
+        match rules.iter_mut().find(|c| c.rule.id == rule.id) {
+            Some(existing) => *existing = compiled,
+            None => rules.push(compiled),
+        }
+        self.save(&rules)?;
+        Ok(rule)
+    }
+
+    /// Remove a runtime rule. Returns false if no such rule exists.
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        if self.inner.config_rules.iter().any(|c| c.rule.id == id) {
+            bail!("Rule '{}' is defined in the config file and cannot be deleted via the API", id);
+        }
+        let mut rules = self.inner.api_rules.write().await;
+        let before = rules.len();
+        rules.retain(|c| c.rule.id != id);
+        if rules.len() == before {
+            return Ok(false);
+        }
+        self.save(&rules)?;
+        Ok(true)
+    }
+
+    /// Most recent executions, newest first.
+    pub async fn log(&self, limit: usize) -> Vec<RuleExecution> {
+        self.inner.log.read().await.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn save(&self, rules: &[CompiledRule]) -> Result<()> {
+        let rules: Vec<&AutomationRule> = rules.iter().map(|c| &c.rule).collect();
+        if let Some(dir) = self.inner.rules_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.inner.rules_path, serde_json::to_string_pretty(&rules)?)?;
+        Ok(())
+    }
+
+    /// Enabled rules from both sources.
+    async fn active_rules(&self) -> Vec<CompiledRule> {
+        let api = self.inner.api_rules.read().await;
+        self.inner
+            .config_rules
+            .iter()
+            .chain(api.iter())
+            .filter(|c| c.rule.enabled)
+            .cloned()
+            .collect()
+    }
+
+    /// Evaluate rules until the event bus closes.
+    pub async fn run(self, event_bus: EventBus) {
+        let mut events = event_bus.subscribe_filtered("Rules engine", EventFilter::all());
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        let mut seen_order: VecDeque<String> = VecDeque::new();
+        let mut seen: HashSet<String> = HashSet::new();
+
+        loop {
+            tokio::select! {
+                received = events.recv() => {
+                    let Some(event) = received else { break };
+                    if !seen.insert(event.id.clone()) {
+                        continue;
+                    }
+                    seen_order.push_back(event.id.clone());
+                    if seen_order.len() > MAX_SEEN_EVENTS {
+                        if let Some(oldest) = seen_order.pop_front() {
+                            seen.remove(&oldest);
+                        }
+                    }
+                    self.on_event(&event).await;
+                },
+                _ = ticker.tick() => self.check_sessions().await,
+            }
+        }
+    }
+
+    /// Evaluate event and circuit breaker triggers for one event.
+    pub async fn on_event(&self, event: &SessionEvent) {
+        let rules = self.active_rules().await;
+        if rules.is_empty() {
+            return;
+        }
+
+        let mut session = None;
+        for compiled in rules.iter().filter(|c| c.matches_event(event)) {
+            if session.is_none() {
+                session = self.inner.storage.get_session(&event.session_id).await.ok().flatten();
+            }
+            let reason = format!("{} ({})", compiled.rule.trigger.describe(), event_type_name(event.event_type));
+            self.fire(Firing {
+                rule: &compiled.rule,
+                session: session.clone(),
+                event: Some(event),
+                reason,
+            })
+            .await;
+        }
+
+        let circuit_rules: Vec<&CompiledRule> = rules
+            .iter()
+            .filter(|c| matches!(c.rule.trigger, Trigger::CircuitOpen))
+            .collect();
+        if circuit_rules.is_empty() || event.event_type != EventType::ResponseGenerated {
+            return;
+        }
+
+        let files_changed = event
+            .tool_name
+            .as_deref()
+            .is_some_and(|t| WRITE_TOOLS.contains(&t)) as u32;
+        let opened = self
+            .inner
+            .analytics
+            .record_loop(
+                &event.session_id,
+                event.content.as_deref().unwrap_or(""),
+                files_changed,
+                event.tokens_output.unwrap_or(0),
+            )
+            .await;
+        if !opened {
+            return;
+        }
+
+        let Some(session) = self.inner.storage.get_session(&event.session_id).await.ok().flatten() else {
+            return;
+        };
+        for compiled in circuit_rules {
+            self.fire_once(&compiled.rule, &session).await;
+        }
+    }
+
+    /// Evaluate cost, idle and context triggers against recently active sessions.
+    pub async fn check_sessions(&self) {
+        let rules: Vec<CompiledRule> = self
+            .active_rules()
+            .await
+            .into_iter()
+            .filter(|c| {
+                matches!(
+                    c.rule.trigger,
+                    Trigger::Cost { .. } | Trigger::Idle { .. } | Trigger::Context { .. }
+                )
+            })
+            .collect();
+        if rules.is_empty() {
+            return;
+        }
+
+        let sessions = match self.inner.storage.get_recent_sessions(24, 500).await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                warn!("Rules engine could not load sessions: {}", e);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        for session in &sessions {
+            for compiled in &rules {
+                let triggered = match compiled.rule.trigger {
+                    Trigger::Cost { above } => session.estimated_cost > above,
+                    Trigger::Idle { minutes } => {
+                        session.status == SessionStatus::Active
+                            && now - session.last_activity_at > Duration::minutes(minutes)
+                    }
+                    Trigger::Context { above } => session
+                        .context_utilization()
+                        .is_some_and(|u| u * 100.0 > above),
+                    _ => false,
+                };
+                if triggered {
+                    self.fire_once(&compiled.rule, session).await;
+                } else if matches!(compiled.rule.trigger, Trigger::Context { .. }) {
+                    // Re-arm once the context shrinks again (compaction)
+                    self.inner
+                        .fired
+                        .write()
+                        .await
+                        .remove(&(compiled.rule.id.clone(), session.id.clone()));
+                }
+            }
+        }
+    }
+
+    /// Fire a state trigger unless it already fired for this session.
+    async fn fire_once(&self, rule: &AutomationRule, session: &Session) {
+        let key = (rule.id.clone(), session.id.clone());
+        {
+            let mut fired = self.inner.fired.write().await;
+            if let Some(activity_at_fire) = fired.get(&key) {
+                // Idle re-arms once the session has done something since
+                let rearmed = matches!(rule.trigger, Trigger::Idle { .. })
+                    && session.last_activity_at > *activity_at_fire;
+                if !rearmed {
+                    return;
+                }
+            }
+            fired.insert(key, session.last_activity_at);
+        }
+
+        self.fire(Firing {
+            rule,
+            session: Some(session.clone()),
+            event: None,
+            reason: rule.trigger.describe(),
+        })
+        .await;
+    }
+
+    /// Run a rule's actions and log the execution.
+    async fn fire(&self, firing: Firing<'_>) {
+        let mut results = Vec::new();
+        for action in &firing.rule.actions {
+            let outcome = self.run_action(action, &firing).await;
+            results.push(ActionResult {
+                action: action.kind().to_string(),
+                ok: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+            });
+        }
+
+        let failed = results.iter().filter(|r| !r.ok).count();
+        let session_id = firing.session.as_ref().map(|s| s.id.clone());
+        if failed > 0 {
+            warn!(
+                "Rule '{}' fired ({}): {} of {} actions failed",
+                firing.rule.name,
+                firing.reason,
+                failed,
+                results.len()
+            );
+        } else {
+            info!("Rule '{}' fired ({})", firing.rule.name, firing.reason);
+        }
+
+        let mut log = self.inner.log.write().await;
+        log.push_back(RuleExecution {
+            rule_id: firing.rule.id.clone(),
+            rule_name: firing.rule.name.clone(),
+            session_id,
+            reason: firing.reason,
+            actions: results,
+            executed_at: Utc::now(),
+        });
+        if log.len() > MAX_LOG_ENTRIES {
+            log.pop_front();
+        }
+    }
+
+    async fn run_action(&self, action: &Action, firing: &Firing<'_>) -> Result<()> {
+        match action {
+            Action::Webhook { url } => {
+                let response = self
+                    .inner
+                    .client
+                    .post(firing.expand(url))
+                    .header("X-Agent-Monitor-Rule", &firing.rule.name)
+                    .json(&firing.payload())
+                    .send()
+                    .await?;
+                if !response.status().is_success() {
+                    bail!("Webhook returned status {}", response.status());
+                }
+                Ok(())
+            }
+            Action::Command { command } => {
+                let session = firing.session.as_ref();
+                let mut child = tokio::process::Command::new("sh");
+                child
+                    .arg("-c")
+                    .arg(firing.expand_with(command, shell_quote))
+                    .env("AGENT_MONITOR_RULE", &firing.rule.name)
+                    .env("AGENT_MONITOR_REASON", &firing.reason)
+                    .env("AGENT_MONITOR_SESSION_ID", session.map(|s| s.id.as_str()).unwrap_or(""))
+                    .env("AGENT_MONITOR_PROJECT", session.map(|s| s.project_path.as_str()).unwrap_or(""))
+                    .env("AGENT_MONITOR_TOOL", firing.event.and_then(|e| e.tool_name.as_deref()).unwrap_or(""))
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .kill_on_drop(true);
+                let status = tokio::time::timeout(
+                    std::time::Duration::from_secs(COMMAND_TIMEOUT_SECS),
+                    child.status(),
+                )
+                .await
+                .map_err(|_| anyhow::anyhow!("Command timed out after {}s", COMMAND_TIMEOUT_SECS))??;
+                if !status.success() {
+                    bail!("Command exited with {}", status);
+                }
+                Ok(())
+            }
+            Action::Notify { title, message, channel } => {
+                let title = firing.expand(title.as_deref().unwrap_or("Agent Monitor"));
+                self.inner
+                    .notifier
+                    .send(channel.as_deref(), &title, &firing.expand(message))
+                    .await
+            }
+            Action::Mark { tag } => {
+                let Some(ref session) = firing.session else {
+                    bail!("No session to mark");
+                };
+                let tag = firing.expand(tag);
+                let mut event = SessionEvent::new(&session.id, EventType::Custom, session.agent_type);
+                event.content = Some(format!("[MARK] {} (rule: {})", tag, firing.rule.name));
+                event.working_directory = Some(session.project_path.clone());
+                event.raw_data = Some(serde_json::json!({
+                    "mark": tag,
+                    "rule": firing.rule.name,
+                    "reason": firing.reason,
+                }));
+                self.inner.storage.insert_event(&event).await
+            }
+            Action::Tag { tag } => {
+                let Some(ref session) = firing.session else {
+                    bail!("No session to tag");
+                };
+                let expanded = firing.expand(tag);
+                let tag = normalize_tag(&expanded).ok_or_else(|| anyhow::anyhow!("Invalid tag '{}'", expanded))?;
+                let source = format!("rule:{}", firing.rule.name);
+                self.inner
+                    .storage
+                    .add_session_tag(&SessionTag::new(&session.id, &tag, &source))
+                    .await?;
+                Ok(())
+            }
+            Action::Memory { key, value, tags } => {
+                let value = value.clone().unwrap_or_else(|| firing.payload());
+                let mut tags = tags.clone();
+                tags.push(format!("rule:{}", firing.rule.name));
+                if let Some(ref session) = firing.session {
+                    tags.push(context::project_tag(&session.project_path));
+                }
+                let session_id = firing.session.as_ref().map(|s| s.id.as_str());
+                self.inner.memory.write(&firing.expand(key), value, session_id, tags).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    async fn engine(dir: &tempfile::TempDir, rules: Vec<AutomationRule>) -> (RulesEngine, Storage) {
+        let config = Config {
+            config_dir: dir.path().join("config"),
+            data_dir: dir.path().join("data"),
+            rules,
+            ..Config::default()
+        };
+        let storage = Storage::in_memory();
+        (RulesEngine::new(&config, storage.clone()).await.unwrap(), storage)
+    }
+
+    fn mark_rule(name: &str, trigger: Trigger) -> AutomationRule {
+        AutomationRule {
+            id: String::new(),
+            name: name.to_string(),
+            enabled: true,
+            trigger,
+            actions: vec![Action::Mark {
+                tag: "{rule}".to_string(),
+            }],
+        }
+    }
+
+    async fn marks(storage: &Storage, session: &Session) -> usize {
+        storage
+            .get_session_events(&session.id, 100)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|e| e.event_type == EventType::Custom)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_event_trigger_matches_type_and_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        let rule = mark_rule(
+            "bash-used",
+            Trigger::Event {
+                event_types: vec!["tool_executed".to_string()],
+                tool: Some("Bash".to_string()),
+                content: None,
+            },
+        );
+        let (engine, storage) = engine(&dir, vec![rule]).await;
+        let session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+
+        let mut bash = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+        bash.tool_name = Some("Bash".to_string());
+        let mut read = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+        read.tool_name = Some("Read".to_string());
+
+        engine.on_event(&bash).await;
+        engine.on_event(&read).await;
+
+        assert_eq!(marks(&storage, &session).await, 1);
+        let log = engine.log(10).await;
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].rule_name, "bash-used");
+        assert!(log[0].actions[0].ok);
+    }
+
+    #[tokio::test]
+    async fn test_command_placeholders_are_quoted() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let rule = AutomationRule {
+            id: "log".to_string(),
+            name: "log".to_string(),
+            enabled: true,
+            trigger: Trigger::Event { event_types: Vec::new(), tool: None, content: None },
+            actions: vec![Action::Command {
+                command: format!("printf '%s|%s' {{project}} \"$AGENT_MONITOR_TOOL\" > {}", out.display()),
+            }],
+        };
+        let (engine, storage) = engine(&dir, vec![rule]).await;
+        let project = format!("x';touch {}/pwned;'", dir.path().display());
+        let session = Session::new(AgentType::ClaudeCode, &project, "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+        let mut event = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+        event.tool_name = Some("$(id)".to_string());
+
+        engine.on_event(&event).await;
+        assert!(engine.log(1).await[0].actions[0].ok);
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), format!("{}|$(id)", project));
+        assert!(!dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn test_placeholder_values_are_not_expanded_again() {
+        let rule = mark_rule("{reason}", Trigger::Event { event_types: Vec::new(), tool: None, content: None });
+        let session = Session::new(AgentType::ClaudeCode, "/work/{tool}", "ext-1");
+        let mut event = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+        event.tool_name = Some("$(id)".to_string());
+        let firing = Firing { rule: &rule, session: Some(session), event: Some(&event), reason: "{tool}".to_string() };
+
+        assert_eq!(
+            firing.expand_with("{project} {reason} {rule} {tool} {unknown} {", shell_quote),
+            "'/work/{tool}' '{tool}' '{reason}' '$(id)' {unknown} {"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cost_trigger_fires_once_per_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rule = mark_rule("pricey", Trigger::Cost { above: 5.0 });
+        rule.actions.push(Action::Tag {
+            tag: "Over-Budget".to_string(),
+        });
+        let (engine, storage) = engine(&dir, vec![rule]).await;
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        session.estimated_cost = 7.5;
+        storage.upsert_session(&session).await.unwrap();
+
+        engine.check_sessions().await;
+        engine.check_sessions().await;
+
+        assert_eq!(marks(&storage, &session).await, 1);
+        let tags = storage.list_session_tags(Some(&session.id), None).await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].tag, "over-budget");
+        assert_eq!(tags[0].source, "rule:pricey");
+    }
+
+    #[tokio::test]
+    async fn test_context_trigger_rearms_after_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let (engine, storage) = engine(&dir, vec![mark_rule("full", Trigger::Context { above: 80.0 })]).await;
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        session.context_tokens = Some(170_000);
+        storage.upsert_session(&session).await.unwrap();
+
+        engine.check_sessions().await;
+        engine.check_sessions().await;
+        assert_eq!(marks(&storage, &session).await, 1);
+
+        session.context_tokens = Some(30_000);
+        storage.upsert_session(&session).await.unwrap();
+        engine.check_sessions().await;
+        session.context_tokens = Some(180_000);
+        storage.upsert_session(&session).await.unwrap();
+        engine.check_sessions().await;
+        assert_eq!(marks(&storage, &session).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_api_rules_persist_and_config_rules_are_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let (engine, storage) = engine(&dir, vec![mark_rule("from-config", Trigger::CircuitOpen)]).await;
+
+        let saved = engine
+            .upsert(mark_rule("from-api", Trigger::Idle { minutes: 10 }))
+            .await
+            .unwrap();
+        assert!(!saved.id.is_empty());
+        assert!(engine.delete("config-1").await.is_err());
+
+        let mut bad = mark_rule("bad-regex", Trigger::Event {
+            event_types: vec![],
+            tool: None,
+            content: Some("(".to_string()),
+        });
+        bad.id = "bad".to_string();
+        assert!(engine.upsert(bad).await.is_err());
+
+        // A fresh engine picks the runtime rule back up from disk
+        let config = Config {
+            config_dir: dir.path().join("config"),
+            data_dir: dir.path().join("data"),
+            ..Config::default()
+        };
+        let reloaded = RulesEngine::new(&config, storage).await.unwrap();
+        let rules = reloaded.list().await;
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].rule.name, "from-api");
+        assert_eq!(rules[0].source, "api");
+    }
+}
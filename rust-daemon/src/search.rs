@@ -0,0 +1,357 @@
+//! Semantic search over session transcripts.
+//!
+//! When enabled, the daemon embeds every prompt and response through an
+//! OpenAI-compatible `/embeddings` endpoint (a hosted API, or a local one such
+//! as Ollama or llama.cpp) and stores the vectors next to the events. A query
+//! is embedded the same way and compared against every stored vector; each
+//! session is ranked by its best-matching message. Vectors are tagged with the
+//! model that produced them, so switching models re-indexes instead of mixing
+//! incomparable vectors.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::archive;
+use crate::models::{EventEmbedding, Session};
+use crate::storage::Storage;
+
+/// Embeddings index settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingsConfig {
+    /// Whether transcripts are indexed and `/api/v1/search/semantic` is served
+    pub enabled: bool,
+
+    /// Embeddings URL (OpenAI-compatible, e.g. `http://localhost:11434/v1/embeddings`)
+    pub endpoint: String,
+
+    /// Model name sent to the endpoint
+    pub model: String,
+
+    /// Environment variable holding the endpoint's API key
+    pub api_key_env: Option<String>,
+
+    /// Messages embedded per request
+    pub batch_size: usize,
+
+    /// Seconds between indexing passes
+    pub interval_secs: u64,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:11434/v1/embeddings".to_string(),
+            model: "nomic-embed-text".to_string(),
+            api_key_env: None,
+            batch_size: 32,
+            interval_secs: 30,
+        }
+    }
+}
+
+/// Longest text sent for one message; embedding models truncate anyway.
+const MAX_INPUT_CHARS: usize = 4000;
+
+/// Length of the snippet stored for search results.
+const SNIPPET_CHARS: usize = 240;
+
+/// Batches embedded per indexing pass.
+const MAX_BATCHES_PER_PASS: usize = 10;
+
+/// A session matching a semantic query.
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticMatch {
+    pub session: Session,
+    /// Cosine similarity of the best-matching message
+    pub score: f32,
+    pub event_id: String,
+    pub snippet: String,
+    pub indexed_at: DateTime<Utc>,
+    /// In cold storage; matched by text rather than by meaning
+    pub archived: bool,
+}
+
+/// Embeds transcripts and answers semantic queries.
+#[derive(Clone)]
+pub struct SemanticIndex {
+    config: EmbeddingsConfig,
+    storage: Storage,
+    client: reqwest::Client,
+}
+
+impl SemanticIndex {
+    pub fn new(config: EmbeddingsConfig, storage: Storage) -> Self {
+        Self {
+            config,
+            storage,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Index new messages every `interval_secs`.
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(5)));
+        loop {
+            ticker.tick().await;
+            match self.index_pending().await {
+                Ok(0) => {}
+                Ok(count) => debug!("Embedded {} messages", count),
+                Err(e) => warn!("Embedding transcripts failed: {}", e),
+            }
+        }
+    }
+
+    /// Embed prompts and responses that have no vector from the configured
+    /// model yet. Returns how many were stored.
+    pub async fn index_pending(&self) -> Result<usize> {
+        let batch_size = self.config.batch_size.max(1);
+        let mut indexed = 0;
+
+        for _ in 0..MAX_BATCHES_PER_PASS {
+            let events = self
+                .storage
+                .get_unembedded_events(&self.config.model, batch_size)
+                .await?;
+            if events.is_empty() {
+                break;
+            }
+
+            let texts: Vec<String> = events
+                .iter()
+                .map(|e| e.content.as_deref().unwrap_or_default().chars().take(MAX_INPUT_CHARS).collect())
+                .collect();
+            let vectors = self.embed(&texts).await?;
+
+            for ((event, text), vector) in events.iter().zip(&texts).zip(vectors) {
+                self.storage
+                    .insert_embedding(&EventEmbedding {
+                        event_id: event.id.clone(),
+                        session_id: event.session_id.clone(),
+                        model: self.config.model.clone(),
+                        vector,
+                        snippet: text.chars().take(SNIPPET_CHARS).collect(),
+                        created_at: Utc::now(),
+                    })
+                    .await?;
+                indexed += 1;
+            }
+
+            if events.len() < batch_size {
+                break;
+            }
+        }
+
+        Ok(indexed)
+    }
+
+    /// Sessions whose messages are closest in meaning to `query`, best first.
+    /// `project` restricts results to one project directory.
+    pub async fn search(&self, query: &str, limit: usize, project: Option<&str>) -> Result<Vec<SemanticMatch>> {
+        let query_vector = self
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("endpoint returned no embedding"))?;
+
+        let embeddings = self.storage.list_embeddings(&self.config.model).await?;
+        let mut matches = Vec::new();
+        for (embedding, score) in rank_sessions(&query_vector, embeddings) {
+            if matches.len() >= limit {
+                break;
+            }
+            let Some(session) = self.storage.get_session(&embedding.session_id).await? else {
+                continue;
+            };
+            if project.is_some_and(|p| session.project_path != p) {
+                continue;
+            }
+            matches.push(SemanticMatch {
+                session,
+                score,
+                event_id: embedding.event_id,
+                snippet: embedding.snippet,
+                indexed_at: embedding.created_at,
+                archived: false,
+            });
+        }
+
+        // Archived sessions lose their embeddings, so fill any room left with
+        // those whose prompt or summary mention the query
+        if matches.len() < limit {
+            for entry in self.storage.list_archived_sessions().await? {
+                if matches.len() >= limit {
+                    break;
+                }
+                if project.is_some_and(|p| entry.session.project_path != p) || !archive::matches(&entry, query) {
+                    continue;
+                }
+                let snippet = entry.first_prompt.clone().or_else(|| entry.session.summary.clone()).unwrap_or_default();
+                matches.push(SemanticMatch {
+                    session: entry.session,
+                    score: 0.0,
+                    event_id: String::new(),
+                    snippet,
+                    indexed_at: entry.archived_at,
+                    archived: true,
+                });
+            }
+        }
+
+        Ok(matches)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = serde_json::json!({
+            "model": self.config.model,
+            "input": texts,
+        });
+
+        let mut request = self.client.post(&self.config.endpoint).json(&body);
+        if let Some(ref var) = self.config.api_key_env {
+            let key = std::env::var(var).map_err(|_| anyhow!("{} is not set", var))?;
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            bail!("embedding endpoint returned {}", response.status());
+        }
+        let reply: Value = response.json().await?;
+        let data = reply["data"]
+            .as_array()
+            .ok_or_else(|| anyhow!("embedding response has no data array"))?;
+
+        let mut vectors: Vec<(usize, Vec<f32>)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let index = item["index"].as_u64().map(|n| n as usize).unwrap_or(i);
+                let vector = item["embedding"]
+                    .as_array()
+                    .map(|v| v.iter().filter_map(|x| x.as_f64()).map(|x| x as f32).collect())
+                    .unwrap_or_default();
+                (index, vector)
+            })
+            .collect();
+        vectors.sort_by_key(|(index, _)| *index);
+
+        if vectors.len() != texts.len() {
+            bail!("expected {} embeddings, endpoint returned {}", texts.len(), vectors.len());
+        }
+        Ok(vectors.into_iter().map(|(_, v)| v).collect())
+    }
+}
+
+/// Best-matching embedding per session, ordered by descending similarity.
+fn rank_sessions(query: &[f32], embeddings: Vec<EventEmbedding>) -> Vec<(EventEmbedding, f32)> {
+    let mut best: HashMap<String, (EventEmbedding, f32)> = HashMap::new();
+    for embedding in embeddings {
+        let score = cosine_similarity(query, &embedding.vector);
+        match best.get(&embedding.session_id) {
+            Some((_, existing)) if *existing >= score => {}
+            _ => {
+                best.insert(embedding.session_id.clone(), (embedding, score));
+            }
+        }
+    }
+
+    let mut ranked: Vec<(EventEmbedding, f32)> = best.into_values().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType, SessionEvent};
+    use axum::{routing::post, Json, Router};
+
+    /// Toy embedding endpoint: one dimension per keyword.
+    async fn fake_embeddings(Json(body): Json<Value>) -> Json<Value> {
+        const KEYWORDS: [&str; 4] = ["payment", "retry", "css", "layout"];
+        let data: Vec<Value> = body["input"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let text = text.as_str().unwrap().to_lowercase();
+                let vector: Vec<f32> = KEYWORDS
+                    .iter()
+                    .map(|k| if text.contains(k) { 1.0 } else { 0.01 })
+                    .collect();
+                serde_json::json!({ "index": i, "embedding": vector })
+            })
+            .collect();
+        Json(serde_json::json!({ "data": data }))
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/embeddings", post(fake_embeddings)))
+                .await
+                .unwrap();
+        });
+
+        let storage = Storage::in_memory();
+        let mut ids = Vec::new();
+        for prompt in ["Make payment retry logic back off", "Fix the CSS layout on mobile"] {
+            let session = Session::new(AgentType::ClaudeCode, "/work/shop", prompt);
+            storage.upsert_session(&session).await.unwrap();
+            let mut event = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+            event.content = Some(prompt.to_string());
+            storage.insert_event(&event).await.unwrap();
+            ids.push(session.id);
+        }
+
+        let config = EmbeddingsConfig {
+            enabled: true,
+            endpoint: format!("http://{}/embeddings", addr),
+            ..Default::default()
+        };
+        let index = SemanticIndex::new(config, storage);
+        assert_eq!(index.index_pending().await.unwrap(), 2);
+        assert_eq!(index.index_pending().await.unwrap(), 0);
+
+        let results = index.search("where did we refactor retrying payments", 5, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].session.id, ids[0]);
+        assert!(results[0].score > results[1].score);
+
+        let elsewhere = index.search("payment", 5, Some("/other")).await.unwrap();
+        assert!(elsewhere.is_empty());
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+    }
+}
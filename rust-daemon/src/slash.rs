@@ -0,0 +1,156 @@
+//! Slash command usage.
+//!
+//! Claude Code records a slash command as a user message naming it in a
+//! `<command-name>` tag; agents that keep prompts verbatim just start the
+//! prompt with it. Commands are counted per prompt event, by name without
+//! arguments, and marked built-in when Claude Code ships them, so a team's
+//! own commands (`.claude/commands/*.md`, plugin commands) stand out.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Most prompts considered for a report.
+const MAX_PROMPTS: usize = 20_000;
+
+/// Commands Claude Code ships with.
+const BUILTIN: &[&str] = &[
+    "add-dir", "agents", "bashes", "bug", "clear", "compact", "config", "context", "cost", "doctor",
+    "exit", "export", "help", "hooks", "ide", "init", "install-github-app", "login", "logout", "mcp",
+    "memory", "model", "output-style", "permissions", "plugin", "pr-comments", "release-notes",
+    "resume", "review", "rewind", "security-review", "status", "statusline", "terminal-setup",
+    "todos", "upgrade", "usage", "vim",
+];
+
+/// The slash command a prompt runs, without its leading `/` or arguments.
+pub fn parse_command(prompt: &str) -> Option<&str> {
+    let command = match prompt.split_once("<command-name>") {
+        Some((_, rest)) => rest.split_once("</command-name>")?.0.trim(),
+        None => prompt.split_whitespace().next()?,
+    };
+    let name = command.strip_prefix('/')?;
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'));
+    valid.then_some(name)
+}
+
+/// Uses of one command.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlashCommandUsage {
+    /// With its leading `/`
+    pub command: String,
+    /// Whether Claude Code ships it, rather than a project, user or plugin
+    pub builtin: bool,
+    pub uses: usize,
+    pub sessions: usize,
+    pub last_used: DateTime<Utc>,
+}
+
+/// Slash commands over a period, or of one session.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlashCommandReport {
+    pub generated_at: DateTime<Utc>,
+    pub days: i64,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    pub uses: usize,
+    /// Most used first
+    pub commands: Vec<SlashCommandUsage>,
+}
+
+/// Slash commands of one session, or of the last `days` (optionally within
+/// one project).
+pub async fn build_report(
+    storage: &Storage,
+    project: Option<&str>,
+    session_id: Option<&str>,
+    days: i64,
+) -> Result<SlashCommandReport> {
+    let days = days.max(1);
+    let mut prompts = match session_id {
+        Some(id) => storage.get_session_events(id, MAX_PROMPTS).await?,
+        None => storage.get_recent_events_of_type(EventType::PromptReceived, days * 24, MAX_PROMPTS).await?,
+    };
+    if session_id.is_none() {
+        prompts.retain(|e| project.is_none_or(|p| e.working_directory.as_deref() == Some(p)));
+    }
+    let commands = usage(&prompts);
+    Ok(SlashCommandReport {
+        generated_at: Utc::now(),
+        days,
+        project: project.map(str::to_string),
+        session_id: session_id.map(str::to_string),
+        uses: commands.iter().map(|c| c.uses).sum(),
+        commands,
+    })
+}
+
+/// Slash commands run in the prompts among `events`, most used first.
+pub fn usage(events: &[SessionEvent]) -> Vec<SlashCommandUsage> {
+    let mut commands: BTreeMap<&str, (usize, HashSet<&str>, DateTime<Utc>)> = BTreeMap::new();
+    for event in events.iter().filter(|e| e.event_type == EventType::PromptReceived) {
+        let Some(command) = event.content.as_deref().and_then(parse_command) else {
+            continue;
+        };
+        let entry = commands.entry(command).or_insert_with(|| (0, HashSet::new(), event.timestamp));
+        entry.0 += 1;
+        entry.1.insert(event.session_id.as_str());
+        entry.2 = entry.2.max(event.timestamp);
+    }
+    let mut usage: Vec<SlashCommandUsage> = commands
+        .into_iter()
+        .map(|(command, (uses, sessions, last_used))| SlashCommandUsage {
+            command: format!("/{}", command),
+            builtin: BUILTIN.contains(&command),
+            uses,
+            sessions: sessions.len(),
+            last_used,
+        })
+        .collect();
+    usage.sort_by_key(|c| std::cmp::Reverse(c.uses));
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_parse_and_count_commands() {
+        let tagged = "<command-message>review is running…</command-message>\n<command-name>/review</command-name>\n<command-args>42</command-args>";
+        assert_eq!(parse_command(tagged), Some("review"));
+        assert_eq!(parse_command("/compact keep the API notes"), Some("compact"));
+        assert_eq!(parse_command("  /frontend:component Button"), Some("frontend:component"));
+        assert_eq!(parse_command("/usr/bin/env is missing"), None);
+        assert_eq!(parse_command("please run /review"), None);
+        assert_eq!(parse_command("/"), None);
+
+        let prompt = |session: &str, content: &str| {
+            let mut event = SessionEvent::new(session, EventType::PromptReceived, AgentType::ClaudeCode);
+            event.content = Some(content.to_string());
+            event
+        };
+        let mut response = SessionEvent::new("a", EventType::ResponseGenerated, AgentType::ClaudeCode);
+        response.content = Some("/compact".to_string());
+        let events = vec![
+            prompt("a", tagged),
+            prompt("a", "/deploy staging"),
+            prompt("b", "/deploy prod"),
+            prompt("b", "/deploy prod"),
+            prompt("b", "fix the tests"),
+            response,
+        ];
+
+        let usage = usage(&events);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].command, "/deploy");
+        assert_eq!((usage[0].uses, usage[0].sessions, usage[0].builtin), (3, 2, false));
+        assert_eq!(usage[1].command, "/review");
+        assert!(usage[1].builtin);
+    }
+}
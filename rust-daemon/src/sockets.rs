@@ -0,0 +1,104 @@
+//! Access to the daemon's Unix sockets.
+//!
+//! Anyone who can connect to the IPC socket can read every session and
+//! transcript, so sockets are created readable and writable by their owner
+//! only (0600), and each connection's peer credentials are checked before a
+//! request is read: the daemon's own user may connect, and so may the UIDs
+//! in `allowed_uids`. The permissions are set right after binding, before the
+//! first connection is accepted; the peer check covers the moment between.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use tokio::net::{UnixListener, UnixStream};
+
+/// Who may connect to the daemon's sockets, and whether they may write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocketConfig {
+    /// Users besides the daemon's own allowed to connect
+    pub allowed_uids: Vec<u32>,
+
+    /// Whether clients holding the write token may clear, prune, annotate
+    /// and mark sessions
+    pub write_actions: bool,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            allowed_uids: Vec::new(),
+            write_actions: true,
+        }
+    }
+}
+
+/// Listen at `path`, replacing a socket left by an earlier run, with
+/// owner-only permissions.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// The users allowed to connect to one socket.
+#[derive(Debug, Clone)]
+pub struct PeerPolicy {
+    owner: u32,
+    allowed: Vec<u32>,
+}
+
+impl PeerPolicy {
+    /// The owner of the socket at `path` (the daemon's user) and
+    /// `allowed_uids`.
+    pub fn new(path: &Path, allowed_uids: &[u32]) -> Result<Self> {
+        Ok(Self {
+            owner: std::fs::metadata(path)?.uid(),
+            allowed: allowed_uids.to_vec(),
+        })
+    }
+
+    pub fn allows(&self, uid: u32) -> bool {
+        uid == self.owner || self.allowed.contains(&uid)
+    }
+
+    /// The UID of the process at the other end of `stream`, if it may use
+    /// the socket.
+    pub fn check(&self, stream: &UnixStream) -> Result<u32> {
+        let uid = stream.peer_cred()?.uid();
+        if !self.allows(uid) {
+            bail!("UID {} is not allowed to connect (add it to socket.allowed_uids)", uid);
+        }
+        Ok(uid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_socket_is_private_and_peers_are_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.sock");
+        std::fs::write(&path, "stale").unwrap();
+
+        let listener = bind(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let policy = PeerPolicy::new(&path, &[]).unwrap();
+        let _client = UnixStream::connect(&path).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        assert_eq!(policy.check(&stream).unwrap(), policy.owner);
+
+        // Another user's daemon only lets in the UIDs it lists
+        let other = PeerPolicy { owner: policy.owner.wrapping_add(1), allowed: vec![] };
+        assert!(other.check(&stream).is_err());
+        let listed = PeerPolicy { owner: policy.owner.wrapping_add(1), allowed: vec![policy.owner] };
+        assert!(listed.check(&stream).is_ok());
+    }
+}
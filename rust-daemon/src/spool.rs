@@ -0,0 +1,242 @@
+//! Hook events buffered while the daemon is down.
+//!
+//! When a hook can't reach the daemon's socket it appends its message to a
+//! daily JSONL file under `<data_dir>/spool`. Payloads too large to send over
+//! the socket are streamed to a file of their own next to it. On startup,
+//! before adapters begin reading transcripts, the daemon replays the spool and
+//! deletes it, then drains it every minute. Replay records what a running
+//! daemon would have stored (compactions and tool calls); policy decisions
+//! and SessionStart context only matter while the agent waits on the hook,
+//! so those are skipped.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+use crate::api::{record_compaction, record_tool_use};
+use crate::events::EventBus;
+use crate::storage::Storage;
+
+/// Largest hook payload sent over the socket; larger ones are spooled.
+pub const MAX_PAYLOAD_BYTES: u64 = 1024 * 1024;
+
+/// Largest spooled payload; the rest is cut off, and the payload dropped at replay.
+pub const MAX_SPOOLED_PAYLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Largest a day's spool file grows; later hooks that day are dropped.
+const MAX_SPOOL_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Extension of files taken for replay, so hooks start a new file meanwhile.
+const REPLAYING: &str = "replaying";
+
+/// Time given to a hook that opened a spool file just before it was taken.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// Seconds between drains while the daemon runs.
+const DRAIN_INTERVAL_SECS: u64 = 60;
+
+pub fn spool_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("spool")
+}
+
+/// Append a hook message to today's spool file.
+pub fn append(dir: &Path, message: &serde_json::Value) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("hooks-{}.jsonl", Utc::now().format("%Y%m%d")));
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_SPOOL_BYTES) {
+        anyhow::bail!("spool file {} is full", path.display());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("could not open {}", path.display()))?;
+    // One write per line, so concurrent hooks don't interleave
+    file.write_all((serde_json::to_string(message)? + "\n").as_bytes())?;
+    Ok(())
+}
+
+/// Spool a hook whose payload is too large to send: `head` is what was read
+/// so far, and the rest is streamed from `rest`. `message` is the hook
+/// message without its data, which replay loads from the payload file.
+pub fn append_payload(dir: &Path, message: &serde_json::Value, head: &[u8], rest: &mut impl Read) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let name = format!("payload-{}-{}.json", Utc::now().format("%Y%m%d%H%M%S%f"), std::process::id());
+    let path = dir.join(&name);
+    let mut file = fs::File::create(&path)?;
+    let written = file
+        .write_all(head)
+        .and_then(|_| std::io::copy(&mut rest.take(MAX_SPOOLED_PAYLOAD_BYTES - head.len() as u64), &mut file));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(e.into());
+    }
+
+    let mut message = message.clone();
+    message["payload_file"] = serde_json::Value::String(name);
+    append(dir, &message).inspect_err(|_| {
+        let _ = fs::remove_file(&path);
+    })
+}
+
+/// Drain the spool every minute until the daemon stops.
+pub async fn run(dir: PathBuf, storage: Storage, events: EventBus) {
+    let mut ticker = interval(Duration::from_secs(DRAIN_INTERVAL_SECS));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = replay(&dir, &storage, &events).await {
+            warn!("Failed to replay spooled hook events: {}", e);
+        }
+    }
+}
+
+/// Replay and delete every spooled hook message. Returns how many were read.
+pub async fn replay(dir: &Path, storage: &Storage, events: &EventBus) -> Result<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl" || ext == REPLAYING))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    // A file left mid-replay by a crash is picked up as is
+    let mut taken = Vec::new();
+    for path in files {
+        let replaying = path.with_extension(REPLAYING);
+        if path != replaying {
+            fs::rename(&path, &replaying)?;
+        }
+        taken.push(replaying);
+    }
+    tokio::time::sleep(SETTLE).await;
+
+    let mut count = 0;
+    for path in taken {
+        let content = fs::read_to_string(&path)?;
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(mut message) => {
+                    if load_payload(dir, &mut message) {
+                        replay_message(&message, storage, events).await;
+                        count += 1;
+                    }
+                }
+                Err(e) => warn!("Skipping malformed spooled hook in {}: {}", path.display(), e),
+            }
+        }
+        fs::remove_file(&path)?;
+    }
+    if count > 0 {
+        info!("Replayed {} hook events spooled while the daemon was down", count);
+    }
+    Ok(count)
+}
+
+/// Fill in the data of a hook spooled with a separate payload file, and
+/// delete the file. False if the payload can't be read.
+fn load_payload(dir: &Path, message: &mut serde_json::Value) -> bool {
+    let Some(name) = message.get("payload_file").and_then(|v| v.as_str()).map(String::from) else {
+        return true;
+    };
+    // Only files this module wrote, never a path from elsewhere
+    if !name.starts_with("payload-") || name.contains(['/', '\\']) {
+        warn!("Ignoring spooled hook with payload file {:?}", name);
+        return false;
+    }
+    let path = dir.join(&name);
+    let data = fs::read(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| Ok(serde_json::from_slice::<serde_json::Value>(&bytes)?));
+    let _ = fs::remove_file(&path);
+    match data {
+        Ok(data) => {
+            message["data"] = data;
+            true
+        }
+        Err(e) => {
+            warn!("Skipping spooled hook payload {}: {}", name, e);
+            false
+        }
+    }
+}
+
+async fn replay_message(message: &serde_json::Value, storage: &Storage, events: &EventBus) {
+    match message.get("event_type").and_then(|v| v.as_str()) {
+        Some("PreCompact") => record_compaction(message, storage, events).await,
+        Some("PostToolUse") => record_tool_use(message, storage, events).await,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType, Session};
+
+    #[tokio::test]
+    async fn test_spool_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = spool_dir(dir.path());
+        let storage = Storage::in_memory();
+        let bus = EventBus::new();
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+
+        let hook = |event_type: &str, timestamp: &str| {
+            serde_json::json!({
+                "action": "hook_event",
+                "event_type": event_type,
+                "timestamp": timestamp,
+                "data": { "cwd": "/work/api", "trigger": "auto" },
+            })
+        };
+        append(&spool, &hook("UserPromptSubmit", "2026-01-05T10:00:00Z")).unwrap();
+        append(&spool, &hook("PreCompact", "2026-01-05T10:01:00Z")).unwrap();
+        let mut tool_use = hook("PostToolUse", "2026-01-05T10:01:30Z");
+        tool_use["data"] = serde_json::json!({
+            "cwd": "/work/api",
+            "tool_name": "Bash",
+            "tool_input": { "command": "cargo test" },
+            "tool_response": { "stdout": "ok", "exit_code": 0 },
+        });
+        append(&spool, &tool_use).unwrap();
+        fs::write(spool.join("hooks-20260104.jsonl"), "{\"event_type\": \"PreCo").unwrap();
+
+        // A large pretty-printed payload, partly read before spooling
+        let payload = serde_json::to_string_pretty(&serde_json::json!({
+            "cwd": "/work/api",
+            "trigger": "manual",
+            "tool_response": "x".repeat(4096),
+        }))
+        .unwrap();
+        let (head, rest) = payload.split_at(100);
+        let mut message = hook("PreCompact", "2026-01-05T10:02:00Z");
+        message.as_object_mut().unwrap().remove("data");
+        append_payload(&spool, &message, head.as_bytes(), &mut rest.as_bytes()).unwrap();
+
+        assert_eq!(replay(&spool, &storage, &bus).await.unwrap(), 4);
+        assert_eq!(fs::read_dir(&spool).unwrap().count(), 0);
+
+        // Newest first, recorded at the time of the hook rather than of the replay
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].content.as_deref(), Some("Context compacted (manual)"));
+        assert_eq!(events[1].event_type, EventType::ToolExecuted);
+        assert_eq!(events[1].content.as_deref(), Some("Bash: cargo test"));
+        assert_eq!(events[1].timestamp.to_rfc3339(), "2026-01-05T10:01:30+00:00");
+        assert_eq!(events[2].event_type, EventType::Compaction);
+        assert_eq!(events[2].timestamp.to_rfc3339(), "2026-01-05T10:01:00+00:00");
+
+        assert_eq!(replay(&spool, &storage, &bus).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,529 @@
+//! OpenID Connect login for the web dashboard and API.
+//!
+//! With `sso.enabled`, every page and API route except `/health` and the
+//! `/auth` routes needs a login through the configured provider
+//! (authorization code flow with PKCE). The signed-in name comes from
+//! `user_claim`; pick the claim that matches how sessions are attributed
+//! (the `user` setting or login name), e.g. `preferred_username`, so a
+//! login lines up with the sessions it owns in `?user=` filters and
+//! `/api/v1/analytics/users`. Users in `admins` or in one of `admin_groups`
+//! are admins; everyone else is a viewer, who can read but not change
+//! anything (POST, PUT and DELETE need an admin). Logins are kept in memory
+//! for `session_hours`, so a restart signs everyone out.
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::capabilities::ApiKeyHolder;
+
+/// Cookie holding the login token.
+const COOKIE: &str = "agent_monitor_login";
+
+/// How long a started login may take at the provider.
+const LOGIN_TIMEOUT_MINUTES: i64 = 10;
+
+/// Single sign-on settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SsoConfig {
+    /// Whether the dashboard and API require a login
+    pub enabled: bool,
+
+    /// Provider issuer URL, e.g. `https://accounts.google.com`
+    pub issuer: String,
+
+    pub client_id: String,
+
+    /// Environment variable holding the client secret
+    pub client_secret_env: String,
+
+    /// Address browsers reach the dashboard at, for the redirect URI
+    /// (`<public_url>/auth/callback`); defaults to the listen address
+    pub public_url: Option<String>,
+
+    pub scopes: Vec<String>,
+
+    /// ID token claim naming the user
+    pub user_claim: String,
+
+    /// ID token claim listing the user's groups
+    pub groups_claim: String,
+
+    /// Users (as named by `user_claim`) who are admins
+    pub admins: Vec<String>,
+
+    /// Groups whose members are admins
+    pub admin_groups: Vec<String>,
+
+    /// Hours a login lasts
+    pub session_hours: i64,
+}
+
+impl Default for SsoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            issuer: String::new(),
+            client_id: String::new(),
+            client_secret_env: "AGENT_MONITOR_OIDC_SECRET".to_string(),
+            public_url: None,
+            scopes: vec!["openid".to_string(), "email".to_string(), "profile".to_string()],
+            user_claim: "email".to_string(),
+            groups_claim: "groups".to_string(),
+            admins: Vec::new(),
+            admin_groups: Vec::new(),
+            session_hours: 12,
+        }
+    }
+}
+
+/// What a signed-in user may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Everything
+    Admin,
+    /// Read only
+    Viewer,
+}
+
+/// A signed-in user.
+#[derive(Debug, Clone, Serialize)]
+pub struct Login {
+    pub user: String,
+    pub role: Role,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The provider endpoints we use, from its discovery document.
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+/// A login sent to the provider and not yet back.
+struct PendingLogin {
+    verifier: String,
+    nonce: String,
+    next: String,
+    started_at: DateTime<Utc>,
+}
+
+/// Login state shared by the auth routes and the middleware.
+#[derive(Clone)]
+pub struct Sso {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: SsoConfig,
+    redirect_uri: String,
+    secure_cookie: bool,
+    client: reqwest::Client,
+    discovery: OnceCell<Discovery>,
+    /// By `state` parameter
+    pending: Mutex<HashMap<String, PendingLogin>>,
+    /// By cookie token
+    logins: Mutex<HashMap<String, Login>>,
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl Sso {
+    /// Set up login for a dashboard listening on `host:port`.
+    pub fn new(config: SsoConfig, host: &str, port: u16) -> Result<Self> {
+        if config.issuer.is_empty() || config.client_id.is_empty() {
+            bail!("sso needs an issuer and a client_id");
+        }
+        let public_url = config
+            .public_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{}:{}", host, port));
+        let public_url = public_url.trim_end_matches('/');
+        Ok(Self {
+            inner: Arc::new(Inner {
+                redirect_uri: format!("{}/auth/callback", public_url),
+                secure_cookie: public_url.starts_with("https://"),
+                config,
+                client: reqwest::Client::builder().timeout(std::time::Duration::from_secs(30)).build()?,
+                discovery: OnceCell::new(),
+                pending: Mutex::new(HashMap::new()),
+                logins: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    async fn discovery(&self) -> Result<&Discovery> {
+        self.inner
+            .discovery
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.inner.config.issuer.trim_end_matches('/')
+                );
+                let response = self.inner.client.get(&url).send().await?.error_for_status()?;
+                response.json::<Discovery>().await.with_context(|| format!("Invalid discovery document at {}", url))
+            })
+            .await
+    }
+
+    /// The unexpired login a request's cookie names.
+    pub fn current(&self, headers: &HeaderMap) -> Option<Login> {
+        let token = cookie(headers)?;
+        let mut logins = self.inner.logins.lock().unwrap();
+        match logins.get(token) {
+            Some(login) if login.expires_at > Utc::now() => Some(login.clone()),
+            Some(_) => {
+                logins.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Role for a user with these ID token claims.
+    fn role(&self, user: &str, claims: &Map<String, Value>) -> Role {
+        let config = &self.inner.config;
+        let in_admin_group = claims
+            .get(&config.groups_claim)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .any(|group| config.admin_groups.iter().any(|g| g == group));
+        if in_admin_group || config.admins.iter().any(|a| a.eq_ignore_ascii_case(user)) {
+            Role::Admin
+        } else {
+            Role::Viewer
+        }
+    }
+
+    /// Provider URL that starts a login returning to `next`.
+    async fn start(&self, next: &str) -> Result<String> {
+        let discovery = self.discovery().await?;
+        let state = random_token();
+        let verifier = random_token();
+        let nonce = random_token();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        let url = reqwest::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.inner.config.client_id.as_str()),
+                ("redirect_uri", self.inner.redirect_uri.as_str()),
+                ("scope", &self.inner.config.scopes.join(" ")),
+                ("state", &state),
+                ("nonce", &nonce),
+                ("code_challenge", &challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )?;
+
+        let now = Utc::now();
+        let mut pending = self.inner.pending.lock().unwrap();
+        pending.retain(|_, p| now - p.started_at < Duration::minutes(LOGIN_TIMEOUT_MINUTES));
+        pending.insert(
+            state,
+            PendingLogin { verifier, nonce, next: next.to_string(), started_at: now },
+        );
+        Ok(url.into())
+    }
+
+    /// Exchange the provider's code for an ID token and sign the user in.
+    /// Returns the cookie token, the login and where to send the browser.
+    async fn finish(&self, code: &str, state: &str) -> Result<(String, Login, String)> {
+        let pending = self
+            .inner
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|p| Utc::now() - p.started_at < Duration::minutes(LOGIN_TIMEOUT_MINUTES))
+            .ok_or_else(|| anyhow!("Unknown or expired login; start again"))?;
+        let config = &self.inner.config;
+        let secret = std::env::var(&config.client_secret_env)
+            .map_err(|_| anyhow!("{} is not set", config.client_secret_env))?;
+        let discovery = self.discovery().await?;
+
+        let response = self
+            .inner
+            .client
+            .post(&discovery.token_endpoint)
+            .basic_auth(&config.client_id, Some(&secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.inner.redirect_uri),
+                ("code_verifier", &pending.verifier),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            bail!("Token endpoint returned {}: {}", status, detail.trim());
+        }
+        let tokens: Value = response.json().await?;
+        let id_token = tokens
+            .get("id_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Token response has no id_token"))?;
+        let claims = verify_claims(id_token, &discovery.issuer, &config.client_id, &pending.nonce, Utc::now())?;
+
+        let user = claims
+            .get(&config.user_claim)
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("ID token has no {} claim", config.user_claim))?
+            .to_string();
+        let login = Login {
+            role: self.role(&user, &claims),
+            user,
+            expires_at: Utc::now() + Duration::hours(config.session_hours.max(1)),
+        };
+        let token = random_token();
+        let mut logins = self.inner.logins.lock().unwrap();
+        let now = Utc::now();
+        logins.retain(|_, l| l.expires_at > now);
+        logins.insert(token.clone(), login.clone());
+        Ok((token, login, pending.next))
+    }
+
+    fn set_cookie(&self, token: &str, max_age: i64) -> String {
+        let secure = if self.inner.secure_cookie { "; Secure" } else { "" };
+        format!("{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}", COOKIE, token, max_age, secure)
+    }
+}
+
+/// The login token in a request's cookies.
+fn cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(COOKIE)?.strip_prefix('='))
+}
+
+/// Claims of an ID token received straight from the token endpoint, after
+/// checking issuer, audience, expiry and nonce. The signature is not
+/// checked: the token came over TLS from the provider itself, which OIDC
+/// Core (§3.1.3.7) accepts in its place.
+fn verify_claims(
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+    now: DateTime<Utc>,
+) -> Result<Map<String, Value>> {
+    let payload = id_token.split('.').nth(1).ok_or_else(|| anyhow!("Malformed ID token"))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("Malformed ID token")?;
+    let claims: Map<String, Value> = serde_json::from_slice(&payload).context("Malformed ID token")?;
+
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        bail!("ID token is from another issuer");
+    }
+    let audience_ok = match claims.get("aud") {
+        Some(Value::String(aud)) => aud == client_id,
+        Some(Value::Array(auds)) => auds.iter().any(|a| a.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !audience_ok {
+        bail!("ID token is for another client");
+    }
+    if claims.get("exp").and_then(Value::as_i64).is_none_or(|exp| exp <= now.timestamp()) {
+        bail!("ID token has expired");
+    }
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        bail!("ID token nonce does not match the login");
+    }
+    Ok(claims)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginParams {
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// A local path to return to, never another site.
+fn local_path(next: Option<&str>) -> &str {
+    next.filter(|n| n.starts_with('/') && !n.starts_with("//") && !n.starts_with("/\\")).unwrap_or("/")
+}
+
+fn failure(status: StatusCode, message: &str) -> Response {
+    let message = message.replace('&', "&amp;").replace('<', "&lt;");
+    let page = format!("<h2>Sign-in failed</h2><p>{}</p><p><a href=\"/auth/login\">Try again</a></p>", message);
+    (status, Html(page)).into_response()
+}
+
+async fn login_handler(State(sso): State<Sso>, Query(params): Query<LoginParams>) -> Response {
+    match sso.start(local_path(params.next.as_deref())).await {
+        Ok(url) => Redirect::to(&url).into_response(),
+        Err(e) => {
+            warn!("Could not start SSO login: {:#}", e);
+            failure(StatusCode::BAD_GATEWAY, "The identity provider is unreachable.")
+        }
+    }
+}
+
+async fn callback_handler(State(sso): State<Sso>, Query(params): Query<CallbackParams>) -> Response {
+    if let Some(error) = params.error {
+        return failure(StatusCode::UNAUTHORIZED, params.error_description.as_deref().unwrap_or(&error));
+    }
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return failure(StatusCode::BAD_REQUEST, "The provider sent no code.");
+    };
+    match sso.finish(&code, &state).await {
+        Ok((token, login, next)) => {
+            info!("{} signed in as {:?}", login.user, login.role);
+            let max_age = (login.expires_at - Utc::now()).num_seconds();
+            ([(header::SET_COOKIE, sso.set_cookie(&token, max_age))], Redirect::to(&next)).into_response()
+        }
+        Err(e) => {
+            warn!("SSO login failed: {:#}", e);
+            failure(StatusCode::UNAUTHORIZED, &e.to_string())
+        }
+    }
+}
+
+async fn logout_handler(State(sso): State<Sso>, headers: HeaderMap) -> Response {
+    if let Some(token) = cookie(&headers) {
+        sso.inner.logins.lock().unwrap().remove(token);
+    }
+    (
+        [(header::SET_COOKIE, sso.set_cookie("", 0))],
+        Html("<h2>Signed out</h2><p><a href=\"/auth/login\">Sign in again</a></p>"),
+    )
+        .into_response()
+}
+
+async fn me_handler(State(sso): State<Sso>, headers: HeaderMap) -> Response {
+    match sso.current(&headers) {
+        Some(login) => Json(login).into_response(),
+        None => (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "error": "Not signed in" }))).into_response(),
+    }
+}
+
+/// Login, callback, logout and who-am-I routes.
+pub fn router(sso: Sso) -> Router {
+    Router::new()
+        .route("/auth/login", get(login_handler))
+        .route("/auth/callback", get(callback_handler))
+        .route("/auth/logout", get(logout_handler))
+        .route("/auth/me", get(me_handler))
+        .with_state(sso)
+}
+
+/// Middleware turning away requests without a login, and changes from
+/// viewers. Pages redirect to the login; API calls get a 401. Reads with a
+/// valid API key need no login.
+pub async fn require_login(State(sso): State<Sso>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if path == "/health" || path.starts_with("/auth/") {
+        return next.run(request).await;
+    }
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if read_only && request.extensions().get::<ApiKeyHolder>().is_some() {
+        return next.run(request).await;
+    }
+    let Some(login) = sso.current(request.headers()) else {
+        if path.starts_with("/api") || path == "/openapi.yaml" {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({ "error": "Sign in at /auth/login" })),
+            )
+                .into_response();
+        }
+        let next_path = request.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        let encoded = percent_encoding::utf8_percent_encode(next_path, percent_encoding::NON_ALPHANUMERIC);
+        return Redirect::to(&format!("/auth/login?next={}", encoded)).into_response();
+    };
+    if !read_only && login.role != Role::Admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": format!("{} is a viewer; changes need an admin", login.user) })),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_id_token_claims_and_roles() {
+        let now = Utc::now();
+        let token = |claims: Value| {
+            format!("eyJhbGciOiJSUzI1NiJ9.{}.signature", URL_SAFE_NO_PAD.encode(claims.to_string()))
+        };
+        let claims = json!({
+            "iss": "https://id.example.com",
+            "aud": ["dashboard", "other"],
+            "exp": now.timestamp() + 300,
+            "nonce": "n1",
+            "email": "ana@example.com",
+            "groups": ["eng", "platform-admins"],
+        });
+        let verified = verify_claims(&token(claims.clone()), "https://id.example.com", "dashboard", "n1", now).unwrap();
+        assert_eq!(verified["email"], "ana@example.com");
+
+        assert!(verify_claims(&token(claims.clone()), "https://evil.example.com", "dashboard", "n1", now).is_err());
+        assert!(verify_claims(&token(claims.clone()), "https://id.example.com", "cli", "n1", now).is_err());
+        assert!(verify_claims(&token(claims.clone()), "https://id.example.com", "dashboard", "n2", now).is_err());
+        let later = now + Duration::minutes(10);
+        assert!(verify_claims(&token(claims), "https://id.example.com", "dashboard", "n1", later).is_err());
+
+        let config = SsoConfig {
+            issuer: "https://id.example.com".to_string(),
+            client_id: "dashboard".to_string(),
+            admins: vec!["Ben@example.com".to_string()],
+            admin_groups: vec!["platform-admins".to_string()],
+            ..SsoConfig::default()
+        };
+        let sso = Sso::new(config, "127.0.0.1", 3000).unwrap();
+        assert_eq!(sso.role("ana@example.com", &verified), Role::Admin);
+        assert_eq!(sso.role("ben@example.com", &Map::new()), Role::Admin);
+        assert_eq!(sso.role("cy@example.com", &Map::new()), Role::Viewer);
+
+        assert_eq!(local_path(Some("/projects?x=1")), "/projects?x=1");
+        assert_eq!(local_path(Some("//evil.example.com")), "/");
+        assert_eq!(local_path(Some("https://evil.example.com")), "/");
+    }
+}
@@ -0,0 +1,187 @@
+//! Claude Code statusline.
+//!
+//! Claude Code runs its `statusLine` command with JSON about the current
+//! session on stdin and shows the first line printed. The session is matched
+//! by Claude's `session_id` (the monitor's `external_id`), falling back to the
+//! active session in the working directory, and rendered as model, cost,
+//! tokens, burn rate and context use. Burn rate is the session's cost per hour
+//! since it started.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::humanize;
+use crate::models::Session;
+use crate::storage::Storage;
+
+/// Sessions searched for Claude's session ID.
+const MAX_SESSIONS: usize = 500;
+
+/// The parts of Claude Code's statusline JSON that are used.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusInput {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub model: Option<StatusModel>,
+    pub workspace: Option<StatusWorkspace>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusModel {
+    pub id: Option<String>,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusWorkspace {
+    pub current_dir: Option<String>,
+    pub project_dir: Option<String>,
+}
+
+impl StatusInput {
+    /// Parse stdin, treating anything unreadable as an empty input.
+    pub fn parse(raw: &str) -> Self {
+        serde_json::from_str(raw).unwrap_or_default()
+    }
+
+    fn directory(&self) -> Option<&str> {
+        let workspace = self.workspace.as_ref();
+        workspace
+            .and_then(|w| w.project_dir.as_deref())
+            .or_else(|| workspace.and_then(|w| w.current_dir.as_deref()))
+            .or(self.cwd.as_deref())
+    }
+
+    fn model_name(&self) -> Option<&str> {
+        let model = self.model.as_ref()?;
+        model.display_name.as_deref().or(model.id.as_deref())
+    }
+}
+
+/// The monitored session Claude Code is asking about, if known.
+pub async fn find_session(storage: &Storage, input: &StatusInput) -> Result<Option<Session>> {
+    if let Some(ref external_id) = input.session_id {
+        let session = storage
+            .get_all_sessions(MAX_SESSIONS)
+            .await?
+            .into_iter()
+            .find(|s| &s.external_id == external_id);
+        if session.is_some() {
+            return Ok(session);
+        }
+    }
+    match input.directory() {
+        Some(dir) => storage.get_active_session_for_project(dir).await,
+        None => Ok(None),
+    }
+}
+
+/// One statusline, e.g. `Opus · $1.24 · 312.4K tok · $2.10/h · ctx 41%`.
+pub fn render(input: &StatusInput, session: Option<&Session>, now: DateTime<Utc>) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(model) = input.model_name() {
+        parts.push(model.to_string());
+    }
+
+    let Some(session) = session else {
+        let project = input
+            .directory()
+            .and_then(|d| Path::new(d).file_name())
+            .map(|n| n.to_string_lossy().into_owned());
+        parts.extend(project);
+        parts.push("not monitored".to_string());
+        return parts.join(" · ");
+    };
+
+    parts.push(humanize::cost(session.estimated_cost));
+    parts.push(format!("{} tok", humanize::tokens(session.tokens_input + session.tokens_output)));
+    let hours = (session.ended_at.unwrap_or(now) - session.started_at).num_seconds() as f64 / 3600.0;
+    if hours >= 1.0 / 60.0 {
+        parts.push(format!("{}/h", humanize::cost(session.estimated_cost / hours)));
+    }
+    if let Some(utilization) = session.context_utilization() {
+        parts.push(format!("ctx {:.0}%", utilization * 100.0));
+    }
+    parts.join(" · ")
+}
+
+/// Point Claude Code's `statusLine` in `settings` at `command`. Returns the
+/// command it replaces, if different; a different command is only replaced
+/// with `force`.
+pub fn install(settings: &mut Value, command: &str, force: bool) -> Result<Option<String>> {
+    let object = settings
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("settings.json is not a JSON object"))?;
+    let previous = object
+        .get("statusLine")
+        .and_then(|s| s.get("command"))
+        .and_then(Value::as_str)
+        .filter(|c| *c != command)
+        .map(str::to_string);
+    if let Some(previous) = previous.as_deref().filter(|_| !force) {
+        anyhow::bail!("statusLine already runs '{}' (use --force to replace it)", previous);
+    }
+    object.insert(
+        "statusLine".to_string(),
+        serde_json::json!({ "type": "command", "command": command, "padding": 0 }),
+    );
+    Ok(previous)
+}
+
+/// Remove the `statusLine` from `settings` if it runs this monitor,
+/// returning its command.
+pub fn uninstall(settings: &mut Value) -> Option<String> {
+    let command = settings.pointer("/statusLine/command").and_then(Value::as_str)?;
+    if !(command.contains("agent-monitor") && command.contains(" claude-statusline")) {
+        return None;
+    }
+    let command = command.to_string();
+    settings.as_object_mut()?.remove("statusLine");
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::Duration;
+
+    #[test]
+    fn test_render_and_install() {
+        let input = StatusInput::parse(
+            r#"{"session_id":"abc","model":{"id":"claude-opus-4-1","display_name":"Opus"},
+                "workspace":{"current_dir":"/work/api","project_dir":"/work/api"}}"#,
+        );
+        let now = Utc::now();
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        session.started_at = now - Duration::minutes(30);
+        session.estimated_cost = 1.5;
+        session.tokens_input = 300_000;
+        session.tokens_output = 12_400;
+        session.context_tokens = Some(82_000);
+
+        assert_eq!(
+            render(&input, Some(&session), now),
+            "Opus · $1.50 · 312.4K tok · $3.00/h · ctx 41%"
+        );
+        assert_eq!(render(&input, None, now), "Opus · api · not monitored");
+
+        let mut settings = serde_json::json!({ "statusLine": { "type": "command", "command": "other" } });
+        assert!(install(&mut settings, "agent-monitor claude-statusline", false).is_err());
+        let previous = install(&mut settings, "agent-monitor claude-statusline", true).unwrap();
+        assert_eq!(previous.as_deref(), Some("other"));
+        assert_eq!(settings["statusLine"]["command"], "agent-monitor claude-statusline");
+        assert_eq!(install(&mut settings, "agent-monitor claude-statusline", false).unwrap(), None);
+
+        assert_eq!(uninstall(&mut settings).as_deref(), Some("agent-monitor claude-statusline"));
+        assert!(settings.get("statusLine").is_none());
+        let mut other = serde_json::json!({ "statusLine": { "type": "command", "command": "other" } });
+        assert_eq!(uninstall(&mut other), None);
+    }
+}
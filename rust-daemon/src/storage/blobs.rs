@@ -0,0 +1,402 @@
+//! Large event content kept out of the events table.
+//!
+//! Tool outputs can run to megabytes, and stored inline they bloat the
+//! database and slow every query that reads events. Agents also re-read the
+//! same files, storing the same output again and again. `BlobStorage` wraps
+//! the backend and moves event content longer than
+//! `content.offload_threshold` bytes into the `content_blobs` table,
+//! zlib-compressed and keyed by its SHA-256, leaving a `blob1:<hash>`
+//! reference in the event row. Identical content is stored once however many
+//! events carry it. References are resolved when events are read, so callers
+//! never see them.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use tracing::warn;
+
+use super::{Storage, StorageBackend};
+use crate::models::{
+    ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample,
+    ResourceSample, Session, SessionEvent, SessionGroup, SessionSource, SessionTag, SummaryMetrics, TrashedSession,
+};
+
+/// Marks offloaded content: the prefix, then the hex SHA-256 of the content.
+pub(super) const PREFIX: &str = "blob1:";
+
+/// Shown in place of offloaded content that can't be read back.
+const MISSING: &str = "[content unavailable]";
+
+/// Where large event content is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentConfig {
+    /// Event content longer than this many bytes is stored once in the blob
+    /// table, compressed; 0 keeps all content inline
+    #[serde(default = "default_offload_threshold")]
+    pub offload_threshold: usize,
+}
+
+fn default_offload_threshold() -> usize {
+    4 * 1024
+}
+
+impl Default for ContentConfig {
+    fn default() -> Self {
+        Self { offload_threshold: default_offload_threshold() }
+    }
+}
+
+/// The hash `content` refers to, if it is a reference to a blob.
+pub(super) fn blob_hash(content: &str) -> Option<&str> {
+    content.strip_prefix(PREFIX)
+}
+
+/// Hex SHA-256 of `content`, its key in the blob table.
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn compress(content: &str) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    Ok(encoder.finish()?)
+}
+
+fn decompress(data: &[u8]) -> Result<String> {
+    let mut content = String::new();
+    ZlibDecoder::new(data).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// A backend whose large event content lives in the blob table.
+pub struct BlobStorage {
+    inner: Storage,
+    threshold: usize,
+}
+
+impl BlobStorage {
+    pub fn new(inner: Storage, threshold: usize) -> Self {
+        Self { inner, threshold }
+    }
+
+    /// `event` with its content swapped for a reference if it is too large.
+    async fn offload(&self, event: &SessionEvent) -> Result<Option<SessionEvent>> {
+        let Some(ref content) = event.content else {
+            return Ok(None);
+        };
+        if content.len() <= self.threshold {
+            return Ok(None);
+        }
+        let hash = content_hash(content);
+        self.inner.put_blob(&hash, content.len() as i64, &compress(content)?).await?;
+        let mut event = event.clone();
+        event.content = Some(format!("{}{}", PREFIX, hash));
+        Ok(Some(event))
+    }
+
+    /// `events` with references replaced by the content they point to.
+    async fn resolve(&self, mut events: Vec<SessionEvent>) -> Vec<SessionEvent> {
+        for event in &mut events {
+            let Some(hash) = event.content.as_deref().and_then(blob_hash) else {
+                continue;
+            };
+            let content = match self.inner.get_blob(hash).await {
+                Ok(Some(data)) => decompress(&data),
+                Ok(None) => Err(anyhow!("not found")),
+                Err(e) => Err(e),
+            };
+            event.content = Some(content.unwrap_or_else(|e| {
+                warn!("Could not read content blob {} of event {}: {}", hash, event.id, e);
+                MISSING.to_string()
+            }));
+        }
+        events
+    }
+}
+
+#[async_trait]
+impl StorageBackend for BlobStorage {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn upsert_session(&self, session: &Session) -> Result<()> {
+        self.inner.upsert_session(session).await
+    }
+
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        self.inner.set_session_summary(session_id, summary).await
+    }
+
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+        self.inner.set_session_branch(session_id, branch).await
+    }
+
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.inner.get_active_sessions(limit).await
+    }
+
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.inner.get_all_sessions(limit).await
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        self.inner.get_session(session_id).await
+    }
+
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+        self.inner.get_active_session_for_project(project_path).await
+    }
+
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+        self.inner.get_session_by_external_id(external_id).await
+    }
+
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        self.inner.get_recent_sessions(hours, limit).await
+    }
+
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        self.inner.get_summary_metrics(hours).await
+    }
+
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>> {
+        self.inner.count_sessions_grouped_by(group, hours, active_only).await
+    }
+
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+        match self.offload(event).await? {
+            Some(event) => self.inner.insert_event(&event).await,
+            None => self.inner.insert_event(event).await,
+        }
+    }
+
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.resolve(self.inner.get_recent_events(limit).await?).await)
+    }
+
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.resolve(self.inner.get_session_events(session_id, limit).await?).await)
+    }
+
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>> {
+        Ok(self
+            .resolve(self.inner.get_recent_events_of_type(event_type, hours, limit).await?)
+            .await)
+    }
+
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+        self.inner.get_event_rollups(hours).await
+    }
+
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+        self.inner.delete_sessions_by_type(agent_type).await
+    }
+
+    async fn clear_all(&self) -> Result<()> {
+        self.inner.clear_all().await
+    }
+
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+        self.inner.upsert_memory(entry).await
+    }
+
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        self.inner.get_memory(key).await
+    }
+
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+        self.inner.list_memory(tag).await
+    }
+
+    async fn delete_memory(&self, key: &str) -> Result<bool> {
+        self.inner.delete_memory(key).await
+    }
+
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.resolve(self.inner.get_unembedded_events(model, limit).await?).await)
+    }
+
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+        self.inner.insert_embedding(embedding).await
+    }
+
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+        self.inner.list_embeddings(model).await
+    }
+
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+        self.inner.add_session_tag(tag).await
+    }
+
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+        self.inner.remove_session_tag(session_id, tag).await
+    }
+
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+        self.inner.list_session_tags(session_id, tag).await
+    }
+
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+        self.inner.insert_resource_sample(sample).await
+    }
+
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        self.inner.get_resource_samples(session_id, limit).await
+    }
+
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+        self.inner.insert_network_sample(sample).await
+    }
+
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+        self.inner.get_network_samples(session_id, limit).await
+    }
+
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+        self.inner.record_session_source(source).await
+    }
+
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+        self.inner.get_session_sources(session_id).await
+    }
+
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+        self.inner.put_blob(hash, size, data).await
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        self.inner.get_blob(hash).await
+    }
+
+    async fn get_blob_stats(&self) -> Result<BlobStats> {
+        self.inner.get_blob_stats().await
+    }
+
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+        self.inner.get_database_stats(top).await
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_session(session_id).await
+    }
+
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+        self.inner.insert_archived_session(archived).await
+    }
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+        self.inner.get_archived_session(session_id).await
+    }
+
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+        self.inner.list_archived_sessions().await
+    }
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_archived_session(session_id).await
+    }
+
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+        self.inner.insert_trashed_session(trashed).await
+    }
+
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+        self.inner.list_trashed_sessions().await
+    }
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_trashed_session(session_id).await
+    }
+
+    async fn write_snapshot(&self, path: &Path) -> Result<()> {
+        self.inner.write_snapshot(path).await
+    }
+
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.inner.insert_audit_entry(entry).await
+    }
+
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        self.inner.get_audit_log(limit).await
+    }
+
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+        self.inner.upsert_daemon_run(run).await
+    }
+
+    async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>> {
+        self.inner.get_daemon_runs(since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use crate::storage::{ContentCipher, EncryptedStorage};
+
+    #[tokio::test]
+    async fn test_large_content_is_offloaded() {
+        let plain = Storage::in_memory();
+        let encrypted = Storage::from_backend(EncryptedStorage::new(plain.clone(), ContentCipher::new(&[7; 32])));
+        let storage = Storage::from_backend(BlobStorage::new(encrypted, 1024));
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+
+        let output = "test result: ok. 412 passed\n".repeat(2000);
+        let mut large = SessionEvent::new(&session.id, EventType::ToolComplete, AgentType::ClaudeCode);
+        large.content = Some(output.clone());
+        let mut small = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        small.content = Some("run the tests".to_string());
+        let mut reread = SessionEvent::new(&session.id, EventType::ToolComplete, AgentType::ClaudeCode);
+        reread.content = Some(output.clone());
+        storage.insert_event(&large).await.unwrap();
+        storage.insert_event(&small).await.unwrap();
+        storage.insert_event(&reread).await.unwrap();
+
+        // The rows hold a reference; the blob is compressed and encrypted
+        let hash = content_hash(&output);
+        let blob = plain.get_blob(&hash).await.unwrap().unwrap();
+        assert!(blob.len() < output.len() / 10);
+        assert!(decompress(&blob).is_err());
+
+        // Both copies share one blob
+        let stats = storage.get_blob_stats().await.unwrap();
+        assert_eq!((stats.blobs, stats.references), (1, 2));
+        assert_eq!(stats.referenced_bytes, 2 * output.len() as i64);
+        assert_eq!(stats.saved_bytes(), 2 * output.len() as i64 - blob.len() as i64);
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        let read = |id: &str| events.iter().find(|e| e.id == id).unwrap().content.clone();
+        assert_eq!(read(&large.id), Some(output.clone()));
+        assert_eq!(read(&reread.id), Some(output));
+        assert_eq!(read(&small.id), small.content);
+
+        // A reference whose blob is gone reads as a placeholder
+        plain.clear_all().await.unwrap();
+        plain.upsert_session(&session).await.unwrap();
+        let mut dangling = SessionEvent::new(&session.id, EventType::ToolComplete, AgentType::ClaudeCode);
+        dangling.content = Some(format!("{}{}", PREFIX, hash));
+        plain.insert_event(&dangling).await.unwrap();
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events[0].content.as_deref(), Some(MISSING));
+    }
+}
@@ -0,0 +1,371 @@
+//! Short-lived cache of hot reads.
+//!
+//! The dashboard broadcaster, every WebSocket and SSE client, the status
+//! handlers and the TUI all ask for the active sessions and the last day's
+//! metrics, over and over, with the same arguments. `CachedStorage` wraps the
+//! backend and answers those reads from memory for `cache.ttl_ms`. Writes
+//! through it drop everything cached, so a process sees its own writes at
+//! once; writes by another process (the daemon, while the web dashboard
+//! reads) show up within the TTL.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{Storage, StorageBackend};
+use crate::models::{
+    ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample,
+    ResourceSample, Session, SessionEvent, SessionGroup, SessionSource, SessionTag, SummaryMetrics, TrashedSession,
+};
+
+/// Read caching settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// How long cached reads are served, in milliseconds; 0 turns the cache
+    /// off
+    #[serde(default = "default_ttl_ms")]
+    pub ttl_ms: u64,
+}
+
+fn default_ttl_ms() -> u64 {
+    2_000
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { ttl_ms: default_ttl_ms() }
+    }
+}
+
+/// A cached read and its arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    ActiveSessions(usize),
+    RecentSessions(i64, usize),
+    SummaryMetrics(i64),
+}
+
+/// A cached result and when it was read.
+type Entry = (Instant, Arc<dyn Any + Send + Sync>);
+
+/// A backend whose hot reads are served from memory for a while.
+pub struct CachedStorage {
+    inner: Storage,
+    ttl: Duration,
+    entries: Mutex<HashMap<Key, Entry>>,
+    /// Bumped by every write, so a read that overlapped one isn't cached
+    generation: AtomicU64,
+}
+
+impl CachedStorage {
+    pub fn new(inner: Storage, ttl: Duration) -> Self {
+        Self { inner, ttl, entries: Mutex::new(HashMap::new()), generation: AtomicU64::new(0) }
+    }
+
+    /// The cached result of `key`, or `read`'s, cached if no write overlapped.
+    async fn cached<T, F>(&self, key: Key, read: impl FnOnce() -> F) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Future<Output = Result<T>>,
+    {
+        if let Ok(entries) = self.entries.lock() {
+            let hit = entries
+                .get(&key)
+                .filter(|(at, _)| at.elapsed() < self.ttl)
+                .and_then(|(_, value)| value.downcast_ref::<T>());
+            if let Some(value) = hit {
+                return Ok(value.clone());
+            }
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let value = read().await?;
+        if let Ok(mut entries) = self.entries.lock() {
+            if self.generation.load(Ordering::SeqCst) == generation {
+                entries.retain(|_, (at, _)| at.elapsed() < self.ttl);
+                entries.insert(key, (Instant::now(), Arc::new(value.clone())));
+            }
+        }
+        Ok(value)
+    }
+
+    /// Forget everything cached after a write.
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for CachedStorage {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn upsert_session(&self, session: &Session) -> Result<()> {
+        let result = self.inner.upsert_session(session).await;
+        self.invalidate();
+        result
+    }
+
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        let result = self.inner.set_session_summary(session_id, summary).await;
+        self.invalidate();
+        result
+    }
+
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+        let result = self.inner.set_session_branch(session_id, branch).await;
+        self.invalidate();
+        result
+    }
+
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.cached(Key::ActiveSessions(limit), || self.inner.get_active_sessions(limit)).await
+    }
+
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        self.inner.get_all_sessions(limit).await
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        self.inner.get_session(session_id).await
+    }
+
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+        self.inner.get_active_session_for_project(project_path).await
+    }
+
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+        self.inner.get_session_by_external_id(external_id).await
+    }
+
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        self.cached(Key::RecentSessions(hours, limit), || self.inner.get_recent_sessions(hours, limit))
+            .await
+    }
+
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        self.cached(Key::SummaryMetrics(hours), || self.inner.get_summary_metrics(hours)).await
+    }
+
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>> {
+        self.inner.count_sessions_grouped_by(group, hours, active_only).await
+    }
+
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+        let result = self.inner.insert_event(event).await;
+        self.invalidate();
+        result
+    }
+
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+        self.inner.get_recent_events(limit).await
+    }
+
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        self.inner.get_session_events(session_id, limit).await
+    }
+
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>> {
+        self.inner.get_recent_events_of_type(event_type, hours, limit).await
+    }
+
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+        self.inner.get_event_rollups(hours).await
+    }
+
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+        let result = self.inner.delete_sessions_by_type(agent_type).await;
+        self.invalidate();
+        result
+    }
+
+    async fn clear_all(&self) -> Result<()> {
+        let result = self.inner.clear_all().await;
+        self.invalidate();
+        result
+    }
+
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+        self.inner.upsert_memory(entry).await
+    }
+
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        self.inner.get_memory(key).await
+    }
+
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+        self.inner.list_memory(tag).await
+    }
+
+    async fn delete_memory(&self, key: &str) -> Result<bool> {
+        self.inner.delete_memory(key).await
+    }
+
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        self.inner.get_unembedded_events(model, limit).await
+    }
+
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+        self.inner.insert_embedding(embedding).await
+    }
+
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+        self.inner.list_embeddings(model).await
+    }
+
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+        self.inner.add_session_tag(tag).await
+    }
+
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+        self.inner.remove_session_tag(session_id, tag).await
+    }
+
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+        self.inner.list_session_tags(session_id, tag).await
+    }
+
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+        self.inner.insert_resource_sample(sample).await
+    }
+
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        self.inner.get_resource_samples(session_id, limit).await
+    }
+
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+        self.inner.insert_network_sample(sample).await
+    }
+
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+        self.inner.get_network_samples(session_id, limit).await
+    }
+
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+        self.inner.record_session_source(source).await
+    }
+
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+        self.inner.get_session_sources(session_id).await
+    }
+
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+        self.inner.put_blob(hash, size, data).await
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        self.inner.get_blob(hash).await
+    }
+
+    async fn get_blob_stats(&self) -> Result<BlobStats> {
+        self.inner.get_blob_stats().await
+    }
+
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+        self.inner.get_database_stats(top).await
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        let result = self.inner.delete_session(session_id).await;
+        self.invalidate();
+        result
+    }
+
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+        self.inner.insert_archived_session(archived).await
+    }
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+        self.inner.get_archived_session(session_id).await
+    }
+
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+        self.inner.list_archived_sessions().await
+    }
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_archived_session(session_id).await
+    }
+
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+        self.inner.insert_trashed_session(trashed).await
+    }
+
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+        self.inner.list_trashed_sessions().await
+    }
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_trashed_session(session_id).await
+    }
+
+    async fn write_snapshot(&self, path: &Path) -> Result<()> {
+        self.inner.write_snapshot(path).await
+    }
+
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.inner.insert_audit_entry(entry).await
+    }
+
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        self.inner.get_audit_log(limit).await
+    }
+
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+        self.inner.upsert_daemon_run(run).await
+    }
+
+    async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>> {
+        self.inner.get_daemon_runs(since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[tokio::test]
+    async fn test_reads_cached_until_write_or_ttl() {
+        let plain = Storage::in_memory();
+        let storage = Storage::from_backend(CachedStorage::new(plain.clone(), Duration::from_millis(100)));
+        let first = Session::new(AgentType::ClaudeCode, "/work/api", "one");
+        storage.upsert_session(&first).await.unwrap();
+        assert_eq!(storage.get_active_sessions(10).await.unwrap().len(), 1);
+
+        // Writes elsewhere are not seen until the entry expires
+        plain.upsert_session(&Session::new(AgentType::Cursor, "/work/web", "two")).await.unwrap();
+        assert_eq!(storage.get_active_sessions(10).await.unwrap().len(), 1);
+        // Other arguments are a different entry
+        assert_eq!(storage.get_active_sessions(5).await.unwrap().len(), 2);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(storage.get_active_sessions(10).await.unwrap().len(), 2);
+
+        // Writes through the cache are seen at once
+        let metrics = storage.get_summary_metrics(24).await.unwrap();
+        storage.upsert_session(&Session::new(AgentType::ClaudeCode, "/work/cli", "three")).await.unwrap();
+        assert_eq!(storage.get_active_sessions(10).await.unwrap().len(), 3);
+        assert_eq!(storage.get_summary_metrics(24).await.unwrap().total_sessions, metrics.total_sessions + 1);
+    }
+}
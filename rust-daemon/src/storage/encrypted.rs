@@ -0,0 +1,563 @@
+//! Encryption of transcript content at rest.
+//!
+//! With `encryption.enabled` in the config, `EncryptedStorage` wraps the
+//! backend and encrypts what transcripts carry (event content, error messages,
+//! raw data and tool calls, session summaries, search snippets and content blobs) with
+//! ChaCha20-Poly1305 before it reaches the database, decrypting it on the way out. Projects, timestamps,
+//! tokens and costs stay plain so queries and aggregates keep working. Rows
+//! written before encryption was turned on are read as they are.
+//!
+//! The 256-bit key comes from `AGENT_MONITOR_DB_KEY` (base64), else the
+//! configured `key_file`, else the OS keychain when built with the `keychain`
+//! feature, else a key file in the data directory readable only by the user.
+//! Keys that don't exist yet are generated on first use.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::blobs::blob_hash;
+use super::{Storage, StorageBackend};
+use crate::config::Config;
+use crate::models::{
+    ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample,
+    ResourceSample, Session, SessionEvent, SessionGroup, SessionSource, SessionTag, SummaryMetrics, TrashedSession,
+};
+
+/// Marks an encrypted value: the prefix, then base64 of nonce and ciphertext.
+const PREFIX: &str = "enc1:";
+
+/// Shown in place of content encrypted with a key other than the current one.
+const UNREADABLE: &str = "[encrypted with another key]";
+
+/// Environment variable holding the key, base64-encoded.
+const KEY_ENV: &str = "AGENT_MONITOR_DB_KEY";
+
+/// Encryption settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// Encrypt transcript content before storing it
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// File holding the base64 key, created if missing
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+}
+
+/// Encrypts and decrypts stored text with one key.
+pub struct ContentCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl ContentCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { cipher: ChaCha20Poly1305::new(Key::from_slice(key)) }
+    }
+
+    /// The cipher for `config`, with the key from the first source that has one.
+    pub fn load(config: &Config) -> Result<Self> {
+        if let Ok(encoded) = std::env::var(KEY_ENV) {
+            return Ok(Self::new(&decode_key(&encoded).with_context(|| format!("Invalid {}", KEY_ENV))?));
+        }
+        if let Some(ref path) = config.encryption.key_file {
+            return Ok(Self::new(&file_key(path)?));
+        }
+        #[cfg(feature = "keychain")]
+        {
+            Ok(Self::new(&keychain_key()?))
+        }
+        #[cfg(not(feature = "keychain"))]
+        {
+            Ok(Self::new(&file_key(&key_path(&config.data_dir))?))
+        }
+    }
+
+    pub fn encrypt(&self, text: &str) -> Result<String> {
+        Ok(format!("{}{}", PREFIX, BASE64.encode(self.encrypt_bytes(text.as_bytes())?)))
+    }
+
+    /// `text` decrypted; plain text is returned as is.
+    pub fn decrypt(&self, text: &str) -> String {
+        let Some(encoded) = text.strip_prefix(PREFIX) else {
+            return text.to_string();
+        };
+        BASE64
+            .decode(encoded)
+            .ok()
+            .and_then(|sealed| self.decrypt_bytes(&sealed))
+            .and_then(|plain| String::from_utf8(plain).ok())
+            .unwrap_or_else(|| UNREADABLE.to_string())
+    }
+
+    /// `plain` encrypted: the nonce followed by the ciphertext.
+    pub fn encrypt_bytes(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plain)
+            .map_err(|_| anyhow!("Failed to encrypt content"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    /// `sealed` decrypted; None if it was encrypted with another key.
+    pub fn decrypt_bytes(&self, sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() <= 12 {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+    }
+
+    fn seal_event(&self, event: &SessionEvent) -> Result<SessionEvent> {
+        let mut event = event.clone();
+        // A blob reference only names the blob, whose content is encrypted
+        if event.content.as_deref().and_then(blob_hash).is_none() {
+            event.content = event.content.map(|c| self.encrypt(&c)).transpose()?;
+        }
+        event.error_message = event.error_message.map(|e| self.encrypt(&e)).transpose()?;
+        event.raw_data = event
+            .raw_data
+            .map(|raw| self.encrypt(&raw.to_string()).map(serde_json::Value::String))
+            .transpose()?;
+        event.tool_input = event
+            .tool_input
+            .map(|input| self.encrypt(&input.to_string()).map(serde_json::Value::String))
+            .transpose()?;
+        event.tool_output = event.tool_output.map(|o| self.encrypt(&o)).transpose()?;
+        Ok(event)
+    }
+
+    fn open_event(&self, mut event: SessionEvent) -> SessionEvent {
+        event.content = event.content.map(|c| self.decrypt(&c));
+        event.error_message = event.error_message.map(|e| self.decrypt(&e));
+        event.raw_data = event.raw_data.map(|raw| self.open_json(raw));
+        event.tool_input = event.tool_input.map(|input| self.open_json(input));
+        event.tool_output = event.tool_output.map(|o| self.decrypt(&o));
+        event
+    }
+
+    /// JSON stored as an encrypted string, decrypted and parsed.
+    fn open_json(&self, value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::String(sealed) if sealed.starts_with(PREFIX) => {
+                let plain = self.decrypt(&sealed);
+                serde_json::from_str(&plain).unwrap_or(serde_json::Value::String(plain))
+            }
+            other => other,
+        }
+    }
+
+    fn open_events(&self, events: Vec<SessionEvent>) -> Vec<SessionEvent> {
+        events.into_iter().map(|e| self.open_event(e)).collect()
+    }
+
+    fn open_session(&self, mut session: Session) -> Session {
+        session.summary = session.summary.map(|s| self.decrypt(&s));
+        session
+    }
+
+    fn open_sessions(&self, sessions: Vec<Session>) -> Vec<Session> {
+        sessions.into_iter().map(|s| self.open_session(s)).collect()
+    }
+
+    fn open_archived(&self, mut archived: ArchivedSession) -> ArchivedSession {
+        archived.session = self.open_session(archived.session);
+        archived.first_prompt = archived.first_prompt.map(|p| self.decrypt(&p));
+        archived
+    }
+}
+
+/// The key file kept in the data directory when no other source is set up.
+pub fn key_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("db.key")
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32]> {
+    let bytes = BASE64.decode(encoded.trim())?;
+    bytes.try_into().map_err(|_| anyhow!("key must be 32 bytes"))
+}
+
+fn new_key() -> [u8; 32] {
+    ChaCha20Poly1305::generate_key(&mut OsRng).into()
+}
+
+/// The key in `path`, created readable only by the user if missing.
+fn file_key(path: &Path) -> Result<[u8; 32]> {
+    if path.exists() {
+        let encoded = std::fs::read_to_string(path)?;
+        return decode_key(&encoded).with_context(|| format!("Invalid key in {}", path.display()));
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let key = new_key();
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Could not create key file {}", path.display()))?;
+    std::io::Write::write_all(&mut file, BASE64.encode(key).as_bytes())?;
+    Ok(key)
+}
+
+/// The key in the OS keychain, one per profile, created if missing.
+#[cfg(feature = "keychain")]
+fn keychain_key() -> Result<[u8; 32]> {
+    let entry = keyring::Entry::new("agent-monitor", crate::config::profile().unwrap_or("default"))?;
+    match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded).context("Invalid key in the keychain"),
+        Err(keyring::Error::NoEntry) => {
+            let key = new_key();
+            entry.set_password(&BASE64.encode(key))?;
+            Ok(key)
+        }
+        Err(e) => Err(anyhow!("Could not read the database key from the keychain: {}", e)),
+    }
+}
+
+/// A backend whose transcript content is encrypted.
+pub struct EncryptedStorage {
+    inner: Storage,
+    cipher: ContentCipher,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Storage, cipher: ContentCipher) -> Self {
+        Self { inner, cipher }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for EncryptedStorage {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn upsert_session(&self, session: &Session) -> Result<()> {
+        let mut session = session.clone();
+        session.summary = session.summary.map(|s| self.cipher.encrypt(&s)).transpose()?;
+        self.inner.upsert_session(&session).await
+    }
+
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        self.inner.set_session_summary(session_id, &self.cipher.encrypt(summary)?).await
+    }
+
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+        self.inner.set_session_branch(session_id, branch).await
+    }
+
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        Ok(self.cipher.open_sessions(self.inner.get_active_sessions(limit).await?))
+    }
+
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        Ok(self.cipher.open_sessions(self.inner.get_all_sessions(limit).await?))
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        Ok(self.inner.get_session(session_id).await?.map(|s| self.cipher.open_session(s)))
+    }
+
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+        Ok(self
+            .inner
+            .get_active_session_for_project(project_path)
+            .await?
+            .map(|s| self.cipher.open_session(s)))
+    }
+
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+        Ok(self.inner.get_session_by_external_id(external_id).await?.map(|s| self.cipher.open_session(s)))
+    }
+
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        Ok(self.cipher.open_sessions(self.inner.get_recent_sessions(hours, limit).await?))
+    }
+
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        self.inner.get_summary_metrics(hours).await
+    }
+
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>> {
+        self.inner.count_sessions_grouped_by(group, hours, active_only).await
+    }
+
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+        self.inner.insert_event(&self.cipher.seal_event(event)?).await
+    }
+
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.cipher.open_events(self.inner.get_recent_events(limit).await?))
+    }
+
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.cipher.open_events(self.inner.get_session_events(session_id, limit).await?))
+    }
+
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>> {
+        Ok(self
+            .cipher
+            .open_events(self.inner.get_recent_events_of_type(event_type, hours, limit).await?))
+    }
+
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+        self.inner.get_event_rollups(hours).await
+    }
+
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+        self.inner.delete_sessions_by_type(agent_type).await
+    }
+
+    async fn clear_all(&self) -> Result<()> {
+        self.inner.clear_all().await
+    }
+
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+        self.inner.upsert_memory(entry).await
+    }
+
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        self.inner.get_memory(key).await
+    }
+
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+        self.inner.list_memory(tag).await
+    }
+
+    async fn delete_memory(&self, key: &str) -> Result<bool> {
+        self.inner.delete_memory(key).await
+    }
+
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.cipher.open_events(self.inner.get_unembedded_events(model, limit).await?))
+    }
+
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+        let mut sealed = embedding.clone();
+        sealed.snippet = self.cipher.encrypt(&embedding.snippet)?;
+        self.inner.insert_embedding(&sealed).await
+    }
+
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+        let mut embeddings = self.inner.list_embeddings(model).await?;
+        for embedding in &mut embeddings {
+            embedding.snippet = self.cipher.decrypt(&embedding.snippet);
+        }
+        Ok(embeddings)
+    }
+
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+        self.inner.add_session_tag(tag).await
+    }
+
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+        self.inner.remove_session_tag(session_id, tag).await
+    }
+
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+        self.inner.list_session_tags(session_id, tag).await
+    }
+
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+        self.inner.insert_resource_sample(sample).await
+    }
+
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        self.inner.get_resource_samples(session_id, limit).await
+    }
+
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+        self.inner.insert_network_sample(sample).await
+    }
+
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+        self.inner.get_network_samples(session_id, limit).await
+    }
+
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+        self.inner.record_session_source(source).await
+    }
+
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+        self.inner.get_session_sources(session_id).await
+    }
+
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+        self.inner.put_blob(hash, size, &self.cipher.encrypt_bytes(data)?).await
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        match self.inner.get_blob(hash).await? {
+            Some(sealed) => Ok(Some(
+                self.cipher.decrypt_bytes(&sealed).ok_or_else(|| anyhow!("Blob {} is encrypted with another key", hash))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_blob_stats(&self) -> Result<BlobStats> {
+        self.inner.get_blob_stats().await
+    }
+
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+        self.inner.get_database_stats(top).await
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_session(session_id).await
+    }
+
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+        let mut archived = archived.clone();
+        archived.session.summary = archived.session.summary.map(|s| self.cipher.encrypt(&s)).transpose()?;
+        archived.first_prompt = archived.first_prompt.map(|p| self.cipher.encrypt(&p)).transpose()?;
+        self.inner.insert_archived_session(&archived).await
+    }
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+        Ok(self.inner.get_archived_session(session_id).await?.map(|a| self.cipher.open_archived(a)))
+    }
+
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+        Ok(self
+            .inner
+            .list_archived_sessions()
+            .await?
+            .into_iter()
+            .map(|a| self.cipher.open_archived(a))
+            .collect())
+    }
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_archived_session(session_id).await
+    }
+
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+        let mut trashed = trashed.clone();
+        trashed.session.summary = trashed.session.summary.map(|s| self.cipher.encrypt(&s)).transpose()?;
+        self.inner.insert_trashed_session(&trashed).await
+    }
+
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+        Ok(self
+            .inner
+            .list_trashed_sessions()
+            .await?
+            .into_iter()
+            .map(|mut t| {
+                t.session = self.cipher.open_session(t.session);
+                t
+            })
+            .collect())
+    }
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+        self.inner.delete_trashed_session(session_id).await
+    }
+
+    async fn write_snapshot(&self, path: &Path) -> Result<()> {
+        self.inner.write_snapshot(path).await
+    }
+
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.inner.insert_audit_entry(entry).await
+    }
+
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        self.inner.get_audit_log(limit).await
+    }
+
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+        self.inner.upsert_daemon_run(run).await
+    }
+
+    async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>> {
+        self.inner.get_daemon_runs(since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[tokio::test]
+    async fn test_content_is_encrypted_at_rest() {
+        let plain = Storage::in_memory();
+        let storage = Storage::from_backend(EncryptedStorage::new(plain.clone(), ContentCipher::new(&[7; 32])));
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+        storage.set_session_summary(&session.id, "Rotated the AWS keys").await.unwrap();
+
+        let mut event = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        event.content = Some("export AWS_SECRET=hunter2".to_string());
+        event.raw_data = Some(serde_json::json!({ "tool_input": { "command": "env" } }));
+        event.tool_input = Some(serde_json::json!({ "command": "env" }));
+        event.set_tool_output("AWS_SECRET=hunter2");
+        storage.insert_event(&event).await.unwrap();
+
+        // The backend only sees ciphertext
+        let stored = &plain.get_session_events(&session.id, 10).await.unwrap()[0];
+        assert!(stored.content.as_deref().unwrap().starts_with(PREFIX));
+        assert!(!stored.raw_data.as_ref().unwrap().to_string().contains("tool_input"));
+        assert!(!stored.tool_input.as_ref().unwrap().to_string().contains("env"));
+        assert!(stored.tool_output.as_deref().unwrap().starts_with(PREFIX));
+        let summary = plain.get_session(&session.id).await.unwrap().unwrap().summary.unwrap();
+        assert!(summary.starts_with(PREFIX));
+
+        let embedding = EventEmbedding {
+            event_id: event.id.clone(),
+            session_id: session.id.clone(),
+            model: "test".to_string(),
+            vector: vec![0.6, 0.8],
+            snippet: "export AWS_SECRET=hunter2".to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        storage.insert_embedding(&embedding).await.unwrap();
+        assert!(plain.list_embeddings("test").await.unwrap()[0].snippet.starts_with(PREFIX));
+        assert_eq!(storage.list_embeddings("test").await.unwrap()[0].snippet, embedding.snippet);
+
+        let read = &storage.get_session_events(&session.id, 10).await.unwrap()[0];
+        assert_eq!(read.content, event.content);
+        assert_eq!(read.raw_data, event.raw_data);
+        assert_eq!(read.tool_input, event.tool_input);
+        assert_eq!(read.tool_output, event.tool_output);
+        let session = storage.get_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(session.summary.as_deref(), Some("Rotated the AWS keys"));
+
+        // Older plain rows read as they are; another key can't read ours
+        let cipher = ContentCipher::new(&[7; 32]);
+        assert_eq!(cipher.decrypt("plain text"), "plain text");
+        let other = ContentCipher::new(&[8; 32]);
+        assert_eq!(other.decrypt(stored.content.as_deref().unwrap()), UNREADABLE);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys/db.key");
+        assert_eq!(file_key(&path).unwrap(), file_key(&path).unwrap());
+    }
+}
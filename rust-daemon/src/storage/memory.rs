@@ -0,0 +1,682 @@
+//! In-memory storage backend for tests and demo mode.
+//!
+//! Nothing is persisted; data lives as long as the `Storage` handle does.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::blobs::blob_hash;
+use super::{StorageBackend, EMBEDDED_EVENT_TYPES};
+use crate::models::{
+    ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample, ResourceSample, Session,
+    SessionEvent, SessionGroup, SessionSize, SessionSource, SessionStatus, SessionTag, SummaryMetrics, TableStats, TrashedSession,
+};
+
+/// Session store held entirely in memory.
+#[derive(Default)]
+pub struct MemoryStorage {
+    sessions: RwLock<HashMap<String, Session>>,
+    events: RwLock<Vec<SessionEvent>>,
+    memory: RwLock<HashMap<String, MemoryEntry>>,
+    /// Keyed by (event ID, model)
+    embeddings: RwLock<HashMap<(String, String), EventEmbedding>>,
+    /// In insertion order
+    tags: RwLock<Vec<SessionTag>>,
+    /// In insertion order
+    resource_samples: RwLock<Vec<ResourceSample>>,
+    /// In insertion order
+    network_samples: RwLock<Vec<NetworkSample>>,
+    /// In order of first read
+    sources: RwLock<Vec<SessionSource>>,
+    /// Compressed content and its size, keyed by hash
+    blobs: RwLock<HashMap<String, (i64, Vec<u8>)>>,
+    /// Keyed by session ID
+    archived: RwLock<HashMap<String, ArchivedSession>>,
+    /// Keyed by session ID
+    trashed: RwLock<HashMap<String, TrashedSession>>,
+    /// In insertion order
+    audit: RwLock<Vec<AuditEntry>>,
+    /// Keyed by run ID
+    daemon_runs: RwLock<HashMap<String, DaemonRun>>,
+}
+
+impl MemoryStorage {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sessions matching `filter`, most recently active first.
+    fn sessions_where(&self, limit: usize, filter: impl Fn(&Session) -> bool) -> Vec<Session> {
+        let sessions = self.sessions.read().unwrap();
+        let mut matching: Vec<Session> = sessions.values().filter(|s| filter(s)).cloned().collect();
+        matching.sort_by_key(|s| std::cmp::Reverse(s.last_activity_at));
+        matching.truncate(limit);
+        matching
+    }
+
+    /// Events matching `filter`, newest first.
+    fn events_where(&self, limit: usize, filter: impl Fn(&SessionEvent) -> bool) -> Vec<SessionEvent> {
+        let events = self.events.read().unwrap();
+        let mut matching: Vec<SessionEvent> = events.iter().filter(|e| filter(e)).cloned().collect();
+        matching.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+        matching.truncate(limit);
+        matching
+    }
+}
+
+#[async_trait]
+impl StorageBackend for MemoryStorage {
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn upsert_session(&self, session: &Session) -> Result<()> {
+        let mut sessions = self.sessions.write().unwrap();
+        match sessions.get_mut(&session.id) {
+            // Same columns the SQL backends update on conflict
+            Some(existing) => {
+                existing.status = session.status;
+                existing.last_activity_at = session.last_activity_at;
+                existing.ended_at = session.ended_at;
+                existing.duration_seconds = session.duration_seconds;
+                existing.message_count = session.message_count;
+                existing.tool_call_count = session.tool_call_count;
+                existing.file_operations = session.file_operations;
+                existing.tokens_input = session.tokens_input;
+                existing.tokens_output = session.tokens_output;
+                existing.estimated_cost = session.estimated_cost;
+                existing.current_task = session.current_task.clone();
+                existing.progress = session.progress;
+                existing.metadata = session.metadata.clone();
+                existing.context_tokens = session.context_tokens;
+                if session.model_id.is_some() {
+                    existing.model_id = session.model_id.clone();
+                }
+                if session.summary.is_some() {
+                    existing.summary = session.summary.clone();
+                }
+                if session.git_branch.is_some() {
+                    existing.git_branch = session.git_branch.clone();
+                }
+                if existing.user.is_none() {
+                    existing.user = session.user.clone();
+                }
+            }
+            None => {
+                sessions.insert(session.id.clone(), session.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        if let Some(session) = self.sessions.write().unwrap().get_mut(session_id) {
+            session.summary = Some(summary.to_string());
+        }
+        Ok(())
+    }
+
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+        if let Some(session) = self.sessions.write().unwrap().get_mut(session_id) {
+            session.git_branch = Some(branch.to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        Ok(self.sessions_where(limit, |s| s.status == SessionStatus::Active))
+    }
+
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        Ok(self.sessions_where(limit, |_| true))
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        Ok(self.sessions.read().unwrap().get(session_id).cloned())
+    }
+
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+        Ok(self
+            .sessions_where(1, |s| s.project_path == project_path && s.status == SessionStatus::Active)
+            .pop())
+    }
+
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+        Ok(self.sessions_where(1, |s| s.external_id == external_id).pop())
+    }
+
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        let cutoff = Utc::now() - Duration::hours(hours);
+        Ok(self.sessions_where(limit, |s| s.last_activity_at > cutoff))
+    }
+
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        let cutoff = Utc::now() - Duration::hours(hours);
+        let sessions = self.sessions.read().unwrap();
+        let recent: Vec<&Session> = sessions.values().filter(|s| s.last_activity_at > cutoff).collect();
+
+        Ok(SummaryMetrics {
+            total_sessions: recent.len() as i64,
+            active_sessions: recent.iter().filter(|s| s.status == SessionStatus::Active).count() as i64,
+            total_messages: recent.iter().map(|s| s.message_count).sum(),
+            total_tools: recent.iter().map(|s| s.tool_call_count).sum(),
+            total_cost: recent.iter().map(|s| s.estimated_cost).sum(),
+            today_messages: 0,
+        })
+    }
+
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>> {
+        let cutoff = hours.map(|hours| Utc::now() - Duration::hours(hours));
+        let mut counts = HashMap::new();
+        for session in self.sessions.read().unwrap().values() {
+            if cutoff.is_none_or(|cutoff| session.last_activity_at > cutoff)
+                && (!active_only || session.status == SessionStatus::Active)
+            {
+                *counts.entry(group.key(session)).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+        let inserted = {
+            let mut events = self.events.write().unwrap();
+            let new = !events.iter().any(|e| e.id == event.id);
+            if new {
+                events.push(event.clone());
+            }
+            new
+        };
+        if inserted && event.event_type == EventType::Compaction {
+            if let Some(session) = self.sessions.write().unwrap().get_mut(&event.session_id) {
+                session.compactions += 1;
+            }
+        }
+        if inserted && event.interruption().is_some() {
+            if let Some(session) = self.sessions.write().unwrap().get_mut(&event.session_id) {
+                session.interruptions += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.events_where(limit, |_| true))
+    }
+
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        Ok(self.events_where(limit, |e| e.session_id == session_id))
+    }
+
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>> {
+        let cutoff = Utc::now() - Duration::hours(hours);
+        Ok(self.events_where(limit, |e| e.event_type == event_type && e.timestamp > cutoff))
+    }
+
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+        let cutoff = Utc::now() - Duration::hours(hours);
+        let mut rollups: BTreeMap<(DateTime<Utc>, String), EventRollup> = BTreeMap::new();
+        for event in self.events.read().unwrap().iter().filter(|e| e.timestamp > cutoff) {
+            let hour = event.timestamp.duration_trunc(Duration::hours(1))?;
+            let rollup = rollups.entry((hour, event.session_id.clone())).or_insert_with(|| EventRollup {
+                hour,
+                session_id: event.session_id.clone(),
+                agent_type: event.agent_type,
+                events: 0,
+                tokens: 0,
+            });
+            rollup.events += 1;
+            rollup.tokens += event.tokens_input.unwrap_or(0) + event.tokens_output.unwrap_or(0);
+        }
+        Ok(rollups.into_values().collect())
+    }
+
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+        let mut sessions = self.sessions.write().unwrap();
+        let removed: Vec<String> = sessions
+            .values()
+            .filter(|s| s.agent_type.to_string() == agent_type)
+            .map(|s| s.id.clone())
+            .collect();
+        for id in &removed {
+            sessions.remove(id);
+        }
+
+        self.events
+            .write()
+            .unwrap()
+            .retain(|e| !removed.contains(&e.session_id));
+        self.embeddings
+            .write()
+            .unwrap()
+            .retain(|_, e| !removed.contains(&e.session_id));
+        self.tags
+            .write()
+            .unwrap()
+            .retain(|t| !removed.contains(&t.session_id));
+        self.resource_samples
+            .write()
+            .unwrap()
+            .retain(|s| !removed.contains(&s.session_id));
+        self.network_samples
+            .write()
+            .unwrap()
+            .retain(|s| !removed.contains(&s.session_id));
+        self.sources
+            .write()
+            .unwrap()
+            .retain(|s| !removed.contains(&s.session_id));
+
+        Ok(removed.len() as i64)
+    }
+
+    async fn clear_all(&self) -> Result<()> {
+        self.sessions.write().unwrap().clear();
+        self.events.write().unwrap().clear();
+        self.embeddings.write().unwrap().clear();
+        self.tags.write().unwrap().clear();
+        self.resource_samples.write().unwrap().clear();
+        self.network_samples.write().unwrap().clear();
+        self.sources.write().unwrap().clear();
+        self.blobs.write().unwrap().clear();
+        self.archived.write().unwrap().clear();
+        Ok(())
+    }
+
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+        let mut memory = self.memory.write().unwrap();
+        match memory.get_mut(&entry.key) {
+            Some(existing) => {
+                existing.value = entry.value.clone();
+                existing.tags = entry.tags.clone();
+                existing.updated_at = entry.updated_at;
+            }
+            None => {
+                memory.insert(entry.key.clone(), entry.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        Ok(self.memory.read().unwrap().get(key).cloned())
+    }
+
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+        let memory = self.memory.read().unwrap();
+        let mut entries: Vec<MemoryEntry> = memory
+            .values()
+            .filter(|e| tag.is_none_or(|t| e.tags.iter().any(|et| et == t)))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.updated_at));
+        Ok(entries)
+    }
+
+    async fn delete_memory(&self, key: &str) -> Result<bool> {
+        Ok(self.memory.write().unwrap().remove(key).is_some())
+    }
+
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        let embeddings = self.embeddings.read().unwrap();
+        Ok(self.events_where(limit, |e| {
+            EMBEDDED_EVENT_TYPES.contains(&e.event_type)
+                && e.content.as_deref().is_some_and(|c| !c.is_empty())
+                && !embeddings.contains_key(&(e.id.clone(), model.to_string()))
+        }))
+    }
+
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+        self.embeddings.write().unwrap().insert(
+            (embedding.event_id.clone(), embedding.model.clone()),
+            embedding.clone(),
+        );
+        Ok(())
+    }
+
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+        Ok(self
+            .embeddings
+            .read()
+            .unwrap()
+            .values()
+            .filter(|e| e.model == model)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+        let mut tags = self.tags.write().unwrap();
+        if tags.iter().any(|t| t.session_id == tag.session_id && t.tag == tag.tag) {
+            return Ok(false);
+        }
+        tags.push(tag.clone());
+        Ok(true)
+    }
+
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+        let mut tags = self.tags.write().unwrap();
+        let before = tags.len();
+        tags.retain(|t| !(t.session_id == session_id && t.tag == tag));
+        Ok(tags.len() < before)
+    }
+
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+        Ok(self
+            .tags
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|t| session_id.is_none_or(|id| t.session_id == id) && tag.is_none_or(|tag| t.tag == tag))
+            .cloned()
+            .collect())
+    }
+
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+        self.resource_samples.write().unwrap().push(sample.clone());
+        Ok(())
+    }
+
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        let samples = self.resource_samples.read().unwrap();
+        let mut matching: Vec<ResourceSample> =
+            samples.iter().filter(|s| s.session_id == session_id).cloned().collect();
+        matching.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+        matching.truncate(limit);
+        Ok(matching)
+    }
+
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+        self.network_samples.write().unwrap().push(sample.clone());
+        Ok(())
+    }
+
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+        let samples = self.network_samples.read().unwrap();
+        let mut matching: Vec<NetworkSample> =
+            samples.iter().filter(|s| s.session_id == session_id).cloned().collect();
+        matching.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+        matching.truncate(limit);
+        Ok(matching)
+    }
+
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+        let mut sources = self.sources.write().unwrap();
+        match sources
+            .iter_mut()
+            .find(|s| s.session_id == source.session_id && s.path == source.path)
+        {
+            Some(existing) => {
+                existing.offset = source.offset;
+                existing.entries += source.entries;
+                existing.last_read_at = source.last_read_at;
+            }
+            None => sources.push(source.clone()),
+        }
+        Ok(())
+    }
+
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+        let sources = self.sources.read().unwrap();
+        Ok(sources
+            .iter()
+            .filter(|s| session_id.is_none_or(|id| s.session_id == id))
+            .cloned()
+            .collect())
+    }
+
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+        self.blobs
+            .write()
+            .unwrap()
+            .entry(hash.to_string())
+            .or_insert_with(|| (size, data.to_vec()));
+        Ok(())
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.blobs.read().unwrap().get(hash).map(|(_, data)| data.clone()))
+    }
+
+    async fn get_blob_stats(&self) -> Result<BlobStats> {
+        let blobs = self.blobs.read().unwrap();
+        let mut stats = BlobStats {
+            blobs: blobs.len() as i64,
+            stored_bytes: blobs.values().map(|(_, data)| data.len() as i64).sum(),
+            content_bytes: blobs.values().map(|(size, _)| size).sum(),
+            ..Default::default()
+        };
+        for event in self.events.read().unwrap().iter() {
+            let Some((size, _)) = event.content.as_deref().and_then(blob_hash).and_then(|h| blobs.get(h)) else {
+                continue;
+            };
+            stats.references += 1;
+            stats.referenced_bytes += size;
+        }
+        Ok(stats)
+    }
+
+    /// Nothing is stored, so sizes are what the events' text would take.
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+        let blobs = self.blobs.read().unwrap();
+        let sessions = self.sessions.read().unwrap();
+        let events = self.events.read().unwrap();
+
+        let mut sizes: HashMap<&str, SessionSize> = HashMap::new();
+        for event in events.iter() {
+            let blob = event.content.as_deref().and_then(blob_hash).and_then(|h| blobs.get(h));
+            let bytes = event.content.as_ref().map_or(0, |c| c.len())
+                + event.error_message.as_ref().map_or(0, |e| e.len())
+                + event.raw_data.as_ref().map_or(0, |r| r.to_string().len())
+                + blob.map_or(0, |(_, data)| data.len());
+            let size = sizes.entry(&event.session_id).or_insert_with(|| SessionSize {
+                session_id: event.session_id.clone(),
+                project_path: sessions.get(&event.session_id).map(|s| s.project_path.clone()),
+                events: 0,
+                stored_bytes: 0,
+            });
+            size.events += 1;
+            size.stored_bytes += bytes as i64;
+        }
+        let mut heaviest: Vec<SessionSize> = sizes.into_values().collect();
+        let size_bytes = heaviest.iter().map(|s| s.stored_bytes).sum();
+        heaviest.sort_by_key(|s| std::cmp::Reverse(s.stored_bytes));
+        heaviest.truncate(top);
+
+        let table = |name: &str, rows: usize| TableStats { name: name.to_string(), rows: rows as i64, bytes: None };
+        Ok(DatabaseStats {
+            backend: "memory".to_string(),
+            size_bytes,
+            tables: vec![
+                table("sessions", sessions.len()),
+                table("session_events", events.len()),
+                table("memory_entries", self.memory.read().unwrap().len()),
+                table("event_embeddings", self.embeddings.read().unwrap().len()),
+                table("session_tags", self.tags.read().unwrap().len()),
+                table("resource_samples", self.resource_samples.read().unwrap().len()),
+                table("network_samples", self.network_samples.read().unwrap().len()),
+                table("session_sources", self.sources.read().unwrap().len()),
+                table("content_blobs", blobs.len()),
+                table("audit_log", self.audit.read().unwrap().len()),
+            ],
+            heaviest_sessions: heaviest,
+            ..Default::default()
+        })
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        let removed = self.sessions.write().unwrap().remove(session_id).is_some();
+        self.events.write().unwrap().retain(|e| e.session_id != session_id);
+        self.embeddings.write().unwrap().retain(|_, e| e.session_id != session_id);
+        self.tags.write().unwrap().retain(|t| t.session_id != session_id);
+        self.resource_samples.write().unwrap().retain(|s| s.session_id != session_id);
+        self.network_samples.write().unwrap().retain(|s| s.session_id != session_id);
+        self.sources.write().unwrap().retain(|s| s.session_id != session_id);
+        Ok(removed)
+    }
+
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+        self.archived
+            .write()
+            .unwrap()
+            .insert(archived.session.id.clone(), archived.clone());
+        Ok(())
+    }
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+        Ok(self.archived.read().unwrap().get(session_id).cloned())
+    }
+
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+        let mut archived: Vec<ArchivedSession> = self.archived.read().unwrap().values().cloned().collect();
+        archived.sort_by_key(|a| std::cmp::Reverse(a.session.last_activity_at));
+        Ok(archived)
+    }
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+        Ok(self.archived.write().unwrap().remove(session_id).is_some())
+    }
+
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+        self.trashed
+            .write()
+            .unwrap()
+            .insert(trashed.session.id.clone(), trashed.clone());
+        Ok(())
+    }
+
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+        let mut trashed: Vec<TrashedSession> = self.trashed.read().unwrap().values().cloned().collect();
+        trashed.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+        Ok(trashed)
+    }
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+        Ok(self.trashed.write().unwrap().remove(session_id).is_some())
+    }
+
+    async fn write_snapshot(&self, _path: &Path) -> Result<()> {
+        anyhow::bail!("The in-memory store has no database to snapshot")
+    }
+
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        self.audit.write().unwrap().push(entry.clone());
+        Ok(())
+    }
+
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        Ok(self.audit.read().unwrap().iter().rev().take(limit).cloned().collect())
+    }
+
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+        self.daemon_runs.write().unwrap().insert(run.id.clone(), run.clone());
+        Ok(())
+    }
+
+    async fn get_daemon_runs(&self, since: DateTime<Utc>) -> Result<Vec<DaemonRun>> {
+        let mut runs: Vec<DaemonRun> = self
+            .daemon_runs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.last_seen_at >= since || r.stopped_at.is_none())
+            .cloned()
+            .collect();
+        runs.sort_by_key(|r| r.started_at);
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType};
+    use crate::storage::Storage;
+
+    #[tokio::test]
+    async fn test_upsert_keeps_identity_fields() {
+        let storage = Storage::in_memory();
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+
+        session.project_path = "/elsewhere".to_string();
+        session.message_count = 5;
+        storage.upsert_session(&session).await.unwrap();
+
+        let stored = storage.get_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(stored.project_path, "/work/app");
+        assert_eq!(stored.message_count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_events_deduplicated_and_newest_first() {
+        let storage = Storage::in_memory();
+        let session = Session::new(AgentType::ClaudeCode, "/work/app", "ext-1");
+        storage.upsert_session(&session).await.unwrap();
+
+        let mut older = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+        older.timestamp = Utc::now() - Duration::minutes(5);
+        let newer = SessionEvent::new(&session.id, EventType::ToolStart, AgentType::ClaudeCode);
+
+        storage.insert_event(&older).await.unwrap();
+        storage.insert_event(&newer).await.unwrap();
+        storage.insert_event(&older).await.unwrap();
+
+        let events = storage.get_session_events(&session.id, 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, newer.id);
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_type_removes_events() {
+        let storage = Storage::in_memory();
+        let claude = Session::new(AgentType::ClaudeCode, "/a", "1");
+        let aider = Session::new(AgentType::Aider, "/b", "2");
+        storage.upsert_session(&claude).await.unwrap();
+        storage.upsert_session(&aider).await.unwrap();
+        storage
+            .insert_event(&SessionEvent::new(&aider.id, EventType::Thinking, AgentType::Aider))
+            .await
+            .unwrap();
+
+        assert_eq!(storage.delete_sessions_by_type("aider").await.unwrap(), 1);
+        assert_eq!(storage.get_all_sessions(10).await.unwrap().len(), 1);
+        assert!(storage.get_recent_events(10).await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_database_stats_rank_sessions_by_size() {
+        let storage = Storage::in_memory();
+        let small = Session::new(AgentType::ClaudeCode, "/small", "1");
+        let large = Session::new(AgentType::ClaudeCode, "/large", "2");
+        for (session, content) in [(&small, "ls"), (&large, "cargo test\n"), (&large, "test result: ok")] {
+            storage.upsert_session(session).await.unwrap();
+            let mut event = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+            event.content = Some(content.to_string());
+            storage.insert_event(&event).await.unwrap();
+        }
+
+        let stats = storage.get_database_stats(1).await.unwrap();
+        assert_eq!(stats.heaviest_sessions.len(), 1);
+        let heaviest = &stats.heaviest_sessions[0];
+        assert_eq!(heaviest.project_path.as_deref(), Some("/large"));
+        assert_eq!((heaviest.events, heaviest.stored_bytes), (2, 26));
+        assert_eq!(stats.size_bytes, 28);
+        let events = stats.tables.iter().find(|t| t.name == "session_events").unwrap();
+        assert_eq!(events.rows, 3);
+    }
+}
@@ -0,0 +1,403 @@
+//! Storage layer for session and event persistence.
+//!
+//! `Storage` is a cheap, cloneable handle over a `StorageBackend`. SQLite is
+//! the default; setting `database_url` in the config to a Postgres URL lets a
+//! team share one central database. An in-memory backend serves tests and
+//! demo mode. With encryption on, transcript content is encrypted before it
+//! reaches any of them (see `encrypted`), large content is kept out of the
+//! events table (see `blobs`), and hot reads are served from memory for a
+//! moment (see `cache`).
+
+mod blobs;
+mod cache;
+mod encrypted;
+mod memory;
+mod postgres;
+mod sqlite;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::events::EventBus;
+use crate::models::{
+    normalize_tag, AgentType, ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, MemoryEntry, NetworkSample,
+    ResourceSample, Session, SessionEvent, SessionGroup, SessionSource, SessionStatus, SessionTag, SummaryMetrics, TrashedSession,
+};
+
+pub use blobs::{BlobStorage, ContentConfig};
+pub use cache::{CacheConfig, CachedStorage};
+pub use encrypted::{key_path, ContentCipher, EncryptedStorage, EncryptionConfig};
+pub use memory::MemoryStorage;
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+
+/// Operations every storage backend must provide.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Initialize the database schema.
+    async fn initialize(&self) -> Result<()>;
+
+    /// Insert or update a session. A session without a summary keeps the
+    /// stored one, so adapter updates don't erase it.
+    async fn upsert_session(&self, session: &Session) -> Result<()>;
+
+    /// Store the summary of a session.
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()>;
+
+    /// Record the git branch checked out during a session.
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()>;
+
+    /// Get active sessions.
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>>;
+
+    /// Get sessions regardless of status or age, most recent first.
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>>;
+
+    /// Get a single session by ID.
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>>;
+
+    /// Get the most recently active session for a project directory.
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>>;
+
+    /// Get the most recently active session with the agent's own ID.
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>>;
+
+    /// Get sessions active within the last `hours`.
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>>;
+
+    /// Get summary metrics.
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics>;
+
+    /// Number of sessions per agent type, status or project, counting those
+    /// active within the last `hours` (all of them if `None`), optionally
+    /// only sessions still running.
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>>;
+
+    /// Insert an event (ignores duplicates based on ID). A new compaction
+    /// event also bumps its session's compaction counter.
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()>;
+
+    /// Insert an event and publish it to `bus`. Layers that rewrite events
+    /// do so once here, so subscribers see what was stored.
+    async fn record_event(&self, event: SessionEvent, bus: &EventBus) -> Result<()> {
+        self.insert_event(&event).await?;
+        bus.publish(event);
+        Ok(())
+    }
+
+    /// Get recent events.
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>>;
+
+    /// Get events for a specific session (newest first).
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>>;
+
+    /// Get events of one type from the last `hours`, newest first.
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>>;
+
+    /// Per-session event and token counts for each hour of the last `hours`,
+    /// oldest hour first.
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>>;
+
+    /// Delete all sessions by agent type.
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64>;
+
+    /// Clear all sessions and events.
+    async fn clear_all(&self) -> Result<()>;
+
+    /// Insert or update a memory entry. An existing key keeps its
+    /// `created_at` and `session_id`; value, tags and `updated_at` change.
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()>;
+
+    /// Get a memory entry by key.
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>>;
+
+    /// List memory entries, optionally only those tagged `tag`, most recently updated first.
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>>;
+
+    /// Delete a memory entry. Returns false if the key did not exist.
+    async fn delete_memory(&self, key: &str) -> Result<bool>;
+
+    /// Prompts and responses with content that have no embedding from
+    /// `model` yet, newest first.
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>>;
+
+    /// Insert or replace an event's embedding.
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()>;
+
+    /// All embeddings produced by `model`.
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>>;
+
+    /// Tag a session. Returns false if it already had the tag.
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool>;
+
+    /// Remove a tag from a session. Returns false if it didn't have the tag.
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool>;
+
+    /// Session tags, optionally only one session's or only one tag's,
+    /// oldest first.
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>>;
+
+    /// Record a resource sample for a session.
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()>;
+
+    /// A session's latest resource samples, newest first.
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>>;
+
+    /// Record a network sample for a session.
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()>;
+
+    /// A session's latest network samples, newest first.
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>>;
+
+    /// Record that `source.entries` more entries of a session were read from
+    /// `source.path`, up to `source.offset`.
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()>;
+
+    /// The files sessions were read from, optionally only one session's,
+    /// first read first.
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>>;
+
+    /// Store compressed content under its hash, with its uncompressed size.
+    /// Storing a hash that already exists does nothing.
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()>;
+
+    /// Compressed content stored under `hash`.
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Size of the blob table and how many events share its content.
+    async fn get_blob_stats(&self) -> Result<BlobStats>;
+
+    /// Size, tables, indexes and fragmentation, with the `top` heaviest sessions.
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats>;
+
+    /// Delete a session with its events, tags, embeddings, samples and sources.
+    async fn delete_session(&self, session_id: &str) -> Result<bool>;
+
+    /// Record a session moved to cold storage, replacing an earlier record.
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()>;
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>>;
+
+    /// Archived sessions, most recently active first.
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>>;
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool>;
+
+    /// Record a session moved to the trash, replacing an earlier record.
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()>;
+
+    /// Sessions in the trash, most recently deleted first.
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>>;
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool>;
+
+    /// Write a consistent, standalone copy of the database to `path` for
+    /// analytical tools. Only SQLite supports this.
+    async fn write_snapshot(&self, path: &Path) -> Result<()>;
+
+    /// Record a mutating IPC request. Clearing the database keeps the log.
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()>;
+
+    /// The latest audit log entries, newest first.
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>>;
+
+    /// Record or update a daemon run. Clearing the database keeps runs.
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()>;
+
+    /// Daemon runs last seen since `since`, and runs never stopped, oldest first.
+    async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>>;
+}
+
+/// Storage manager for session data.
+#[derive(Clone)]
+pub struct Storage {
+    backend: Arc<dyn StorageBackend>,
+}
+
+impl Storage {
+    /// Create a new SQLite storage instance.
+    pub async fn new(db_path: &Path) -> Result<Self> {
+        Ok(Self::from_backend(SqliteStorage::open(db_path).await?))
+    }
+
+    /// Open an existing SQLite database without write access (for snapshots).
+    pub async fn open_read_only(db_path: &Path) -> Result<Self> {
+        Ok(Self::from_backend(SqliteStorage::open_read_only(db_path).await?))
+    }
+
+    /// Connect to the backend selected by the config: `database_url` if set,
+    /// otherwise the local SQLite file at `db_path`.
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let storage = match config.database_url.as_deref() {
+            Some(url) if url.starts_with("postgres://") || url.starts_with("postgresql://") => {
+                Ok(Self::from_backend(PostgresStorage::connect(url).await?))
+            }
+            Some(url) => Err(anyhow!(
+                "Unsupported database_url '{}' (expected postgres:// or postgresql://)",
+                url
+            )),
+            None => Self::new(&config.db_path).await,
+        }?;
+        storage.layered(config)
+    }
+
+    /// Add the layers the config turns on: encryption of transcript content,
+    /// then offloading of large content to the blob table, then caching of
+    /// hot reads.
+    pub fn layered(self, config: &Config) -> Result<Self> {
+        let mut storage = self;
+        if config.encryption.enabled {
+            storage = Self::from_backend(EncryptedStorage::new(storage, ContentCipher::load(config)?));
+        }
+        if config.content.offload_threshold > 0 {
+            storage = Self::from_backend(BlobStorage::new(storage, config.content.offload_threshold));
+        }
+        if config.cache.ttl_ms > 0 {
+            storage = Self::from_backend(CachedStorage::new(storage, Duration::from_millis(config.cache.ttl_ms)));
+        }
+        Ok(storage)
+    }
+
+    /// Create a non-persistent in-memory store.
+    pub fn in_memory() -> Self {
+        Self::from_backend(MemoryStorage::new())
+    }
+
+    /// Tags of every tagged session, keyed by session ID.
+    pub async fn tags_by_session(&self) -> Result<HashMap<String, Vec<String>>> {
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for tag in self.list_session_tags(None, None).await? {
+            tags.entry(tag.session_id).or_default().push(tag.tag);
+        }
+        Ok(tags)
+    }
+
+    /// IDs of the sessions tagged `tag` (matched in its canonical form).
+    pub async fn sessions_tagged(&self, tag: &str) -> Result<HashSet<String>> {
+        let tag = normalize_tag(tag).unwrap_or_else(|| tag.to_string());
+        Ok(self
+            .list_session_tags(None, Some(&tag))
+            .await?
+            .into_iter()
+            .map(|t| t.session_id)
+            .collect())
+    }
+
+    /// Wrap an existing backend.
+    pub fn from_backend(backend: impl StorageBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+}
+
+impl Deref for Storage {
+    type Target = dyn StorageBackend;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.backend
+    }
+}
+
+/// Column value stored for an event type.
+fn event_type_key(event_type: &EventType) -> String {
+    format!("{:?}", event_type).to_lowercase()
+}
+
+/// Event types whose content is embedded for semantic search.
+const EMBEDDED_EVENT_TYPES: [EventType; 2] = [EventType::PromptReceived, EventType::ResponseGenerated];
+
+/// Pack a vector as little-endian f32 bytes for a BLOB column.
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn parse_status(s: &str) -> SessionStatus {
+    match s {
+        "active" => SessionStatus::Active,
+        "idle" => SessionStatus::Idle,
+        "completed" => SessionStatus::Completed,
+        "crashed" => SessionStatus::Crashed,
+        _ => SessionStatus::Unknown,
+    }
+}
+
+fn parse_agent_type(s: &str) -> AgentType {
+    match s {
+        "claude_code" => AgentType::ClaudeCode,
+        "cursor" => AgentType::Cursor,
+        "aider" => AgentType::Aider,
+        _ => AgentType::Custom,
+    }
+}
+
+fn parse_event_type(s: &str) -> EventType {
+    match s {
+        "sessionstart" | "session_start" => EventType::SessionStart,
+        "sessionend" | "session_end" => EventType::SessionEnd,
+        "promptreceived" | "prompt_received" => EventType::PromptReceived,
+        "responsegenerated" | "response_generated" => EventType::ResponseGenerated,
+        "thinking" => EventType::Thinking,
+        "toolstart" | "tool_start" => EventType::ToolStart,
+        "toolcomplete" | "tool_complete" => EventType::ToolComplete,
+        "toolexecuted" | "tool_executed" => EventType::ToolExecuted,
+        "fileread" | "file_read" => EventType::FileRead,
+        "filemodified" | "file_modified" => EventType::FileModified,
+        "error" => EventType::Error,
+        "policydecision" | "policy_decision" => EventType::PolicyDecision,
+        "compaction" => EventType::Compaction,
+        "apierror" | "api_error" => EventType::ApiError,
+        "configchanged" | "config_changed" => EventType::ConfigChanged,
+        "anomaly" => EventType::Anomaly,
+        "loop" => EventType::Loop,
+        _ => EventType::Custom,
+    }
+}
+
+fn parse_timestamp(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    Ok(chrono::DateTime::parse_from_rfc3339(s)?.with_timezone(&chrono::Utc))
+}
+
+/// Column holding a session group's values.
+fn group_column(group: SessionGroup) -> &'static str {
+    match group {
+        SessionGroup::AgentType => "agent_type",
+        SessionGroup::Status => "status",
+        SessionGroup::Project => "project_path",
+    }
+}
+
+/// Session timestamps as stored: UTC with microseconds and a `Z` suffix.
+/// Every value has the same width, so comparing them as text orders them in
+/// time and a range filter on `last_activity_at` can use its index.
+fn format_timestamp(t: &chrono::DateTime<chrono::Utc>) -> String {
+    t.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+/// Stored form of the time `hours` ago, for filtering on session activity.
+fn hours_ago(hours: i64) -> String {
+    format_timestamp(&(chrono::Utc::now() - chrono::Duration::hours(hours)))
+}
@@ -0,0 +1,1902 @@
+//! SQLite storage backend (the default, single-machine store).
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool};
+use sqlx::{Connection, Row};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+use super::blobs::PREFIX as BLOB_PREFIX;
+use super::{
+    decode_vector, encode_vector, event_type_key, format_timestamp, group_column, hours_ago,
+    parse_agent_type, parse_event_type, parse_status, parse_timestamp, StorageBackend, EMBEDDED_EVENT_TYPES,
+};
+use crate::models::{
+    ArchivedSession, AuditEntry, BlobStats, DaemonRun, DatabaseStats, EventEmbedding, EventRollup, EventType, IndexStats, MemoryEntry, NetworkSample, ResourceSample,
+    Session, SessionEvent, SessionGroup, SessionSize, SessionSource, SessionTag, SummaryMetrics, TableStats, TrashedSession,
+};
+
+/// SQLite-backed session store.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pool: Arc<SqlitePool>,
+}
+
+impl SqliteStorage {
+    /// Open (creating if needed) the database at `db_path`.
+    pub async fn open(db_path: &Path) -> Result<Self> {
+        // Ensure parent directory exists
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
+        let pool = SqlitePool::connect(&db_url).await?;
+
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Open an existing database without write access (for snapshots).
+    pub async fn open_read_only(db_path: &Path) -> Result<Self> {
+        if !db_path.exists() {
+            anyhow::bail!("Database not found at {}", db_path.display());
+        }
+
+        let db_url = format!("sqlite:{}?mode=ro", db_path.display());
+        let pool = SqlitePool::connect(&db_url).await?;
+
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Rewrite session timestamps stored by older versions (any RFC 3339
+    /// offset or precision) in the fixed-width form range filters rely on.
+    async fn normalize_session_timestamps(&self) -> Result<()> {
+        const CANONICAL: &str =
+            "[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]T[0-9][0-9]:[0-9][0-9]:[0-9][0-9].[0-9][0-9][0-9][0-9][0-9][0-9]Z";
+        let rows = sqlx::query(
+            r#"
+            SELECT id, started_at, last_activity_at, ended_at FROM sessions
+            WHERE started_at NOT GLOB ?1 OR last_activity_at NOT GLOB ?1 OR ended_at NOT GLOB ?1
+            "#,
+        )
+        .bind(CANONICAL)
+        .fetch_all(&*self.pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let normalize = |s: String| parse_timestamp(&s).map(|t| format_timestamp(&t)).unwrap_or(s);
+        let mut tx = self.pool.begin().await?;
+        for row in &rows {
+            sqlx::query("UPDATE sessions SET started_at = ?, last_activity_at = ?, ended_at = ? WHERE id = ?")
+                .bind(normalize(row.get("started_at")))
+                .bind(normalize(row.get("last_activity_at")))
+                .bind(row.get::<Option<String>, _>("ended_at").map(normalize))
+                .bind(row.get::<String, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        info!("Normalized timestamps of {} sessions", rows.len());
+        Ok(())
+    }
+
+    fn row_to_session(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Session> {
+        let metadata_json: String = row.get("metadata_json");
+        let metadata = serde_json::from_str(&metadata_json).unwrap_or_default();
+
+        let status: String = row.get("status");
+        let agent_type: String = row.get("agent_type");
+        let started_at: String = row.get("started_at");
+        let last_activity_at: String = row.get("last_activity_at");
+        let ended_at: Option<String> = row.get("ended_at");
+
+        Ok(Session {
+            id: row.get("id"),
+            agent_type: parse_agent_type(&agent_type),
+            external_id: row.get("external_id"),
+            project_path: row.get("project_path"),
+            status: parse_status(&status),
+            started_at: parse_timestamp(&started_at)?,
+            last_activity_at: parse_timestamp(&last_activity_at)?,
+            ended_at: ended_at.and_then(|s| parse_timestamp(&s).ok()),
+            duration_seconds: row.get("duration_seconds"),
+            message_count: row.get("message_count"),
+            tool_call_count: row.get("tool_call_count"),
+            file_operations: row.get("file_operations"),
+            tokens_input: row.get("tokens_input"),
+            tokens_output: row.get("tokens_output"),
+            estimated_cost: row.get("estimated_cost"),
+            model_id: row.get("model_id"),
+            pid: row.get("pid"),
+            current_task: row.get("current_task"),
+            progress: row.get("progress"),
+            metadata,
+            summary: row.try_get("summary").unwrap_or(None),
+            context_tokens: row.try_get("context_tokens").unwrap_or(None),
+            compactions: row.try_get::<Option<i64>, _>("compactions").unwrap_or(None).unwrap_or(0),
+            interruptions: row.try_get::<Option<i64>, _>("interruptions").unwrap_or(None).unwrap_or(0),
+            git_branch: row.try_get("git_branch").unwrap_or(None),
+            user: row.try_get("user_name").unwrap_or(None),
+        })
+    }
+
+    fn row_to_memory(&self, row: &sqlx::sqlite::SqliteRow) -> Result<MemoryEntry> {
+        let value_json: String = row.get("value_json");
+        let tags_json: String = row.get("tags_json");
+        let created_at: String = row.get("created_at");
+        let updated_at: String = row.get("updated_at");
+
+        Ok(MemoryEntry {
+            key: row.get("key"),
+            value: serde_json::from_str(&value_json)?,
+            created_at: parse_timestamp(&created_at)?,
+            updated_at: parse_timestamp(&updated_at)?,
+            session_id: row.get("session_id"),
+            tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        })
+    }
+
+    fn row_to_event(&self, row: &sqlx::sqlite::SqliteRow) -> Result<SessionEvent> {
+        let event_type: String = row.get("event_type");
+        let agent_type: String = row.get("agent_type");
+        let timestamp: String = row.get("timestamp");
+        let raw_data_json: Option<String> = row.get("raw_data_json");
+        let raw_data = raw_data_json.and_then(|s| serde_json::from_str(&s).ok());
+        let tool_input_json: Option<String> = row.try_get("tool_input_json").unwrap_or(None);
+
+        Ok(SessionEvent {
+            id: row.get("id"),
+            session_id: row.get("session_id"),
+            event_type: parse_event_type(&event_type),
+            timestamp: parse_timestamp(&timestamp)?,
+            agent_type: parse_agent_type(&agent_type),
+            content: row.get("content"),
+            working_directory: row.get("working_directory"),
+            tool_name: row.get("tool_name"),
+            file_path: row.get("file_path"),
+            tokens_input: row.get("tokens_input"),
+            tokens_output: row.get("tokens_output"),
+            error_message: row.get("error_message"),
+            raw_data,
+            tool_input: tool_input_json.and_then(|s| serde_json::from_str(&s).ok()),
+            tool_output: row.try_get("tool_output").unwrap_or(None),
+            exit_code: row.try_get("exit_code").unwrap_or(None),
+            duration_ms: row.try_get("duration_ms").unwrap_or(None),
+        })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SqliteStorage {
+    /// Initialize the database schema.
+    async fn initialize(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                agent_type TEXT NOT NULL,
+                external_id TEXT NOT NULL,
+                project_path TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'unknown',
+                started_at TEXT NOT NULL,
+                last_activity_at TEXT NOT NULL,
+                ended_at TEXT,
+                duration_seconds REAL DEFAULT 0,
+                message_count INTEGER DEFAULT 0,
+                tool_call_count INTEGER DEFAULT 0,
+                file_operations INTEGER DEFAULT 0,
+                tokens_input INTEGER DEFAULT 0,
+                tokens_output INTEGER DEFAULT 0,
+                estimated_cost REAL DEFAULT 0,
+                model_id TEXT,
+                pid INTEGER,
+                current_task TEXT,
+                progress REAL DEFAULT 0,
+                metadata_json TEXT DEFAULT '{}',
+                summary TEXT,
+                context_tokens INTEGER,
+                compactions INTEGER DEFAULT 0,
+                interruptions INTEGER DEFAULT 0,
+                git_branch TEXT,
+                user_name TEXT,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Databases created by older versions lack the newer columns
+        for (column, column_type) in [
+            ("summary", "TEXT"),
+            ("context_tokens", "INTEGER"),
+            ("compactions", "INTEGER DEFAULT 0"),
+            ("interruptions", "INTEGER DEFAULT 0"),
+            ("git_branch", "TEXT"),
+            ("user_name", "TEXT"),
+        ] {
+            let exists: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = ?",
+            )
+            .bind(column)
+            .fetch_one(&*self.pool)
+            .await?;
+            if exists == 0 {
+                sqlx::query(&format!("ALTER TABLE sessions ADD COLUMN {} {}", column, column_type))
+                    .execute(&*self.pool)
+                    .await?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_events (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                agent_type TEXT NOT NULL,
+                content TEXT,
+                working_directory TEXT,
+                tool_name TEXT,
+                file_path TEXT,
+                tokens_input INTEGER,
+                tokens_output INTEGER,
+                error_message TEXT,
+                raw_data_json TEXT,
+                tool_input_json TEXT,
+                tool_output TEXT,
+                exit_code INTEGER,
+                duration_ms INTEGER,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        for (column, column_type) in [
+            ("tool_input_json", "TEXT"),
+            ("tool_output", "TEXT"),
+            ("exit_code", "INTEGER"),
+            ("duration_ms", "INTEGER"),
+        ] {
+            let exists: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM pragma_table_info('session_events') WHERE name = ?",
+            )
+            .bind(column)
+            .fetch_one(&*self.pool)
+            .await?;
+            if exists == 0 {
+                sqlx::query(&format!("ALTER TABLE session_events ADD COLUMN {} {}", column, column_type))
+                    .execute(&*self.pool)
+                    .await?;
+            }
+        }
+
+        // Create indexes
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_status ON sessions(status)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_agent_type ON sessions(agent_type)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_last_activity ON sessions(last_activity_at)")
+            .execute(&*self.pool)
+            .await?;
+
+        self.normalize_session_timestamps().await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_session_id ON session_events(session_id)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_timestamp ON session_events(timestamp)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS memory_entries (
+                key TEXT PRIMARY KEY,
+                value_json TEXT NOT NULL,
+                session_id TEXT,
+                tags_json TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS event_embeddings (
+                event_id TEXT NOT NULL,
+                model TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                snippet TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (event_id, model)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_embeddings_model ON event_embeddings(model)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_tags (
+                session_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                source TEXT NOT NULL DEFAULT 'manual',
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (session_id, tag),
+                FOREIGN KEY (session_id) REFERENCES sessions(id)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_session_tags_tag ON session_tags(tag)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS resource_samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                cpu_percent REAL NOT NULL,
+                memory_bytes INTEGER NOT NULL,
+                process_count INTEGER NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_resource_samples_session ON resource_samples(session_id, timestamp)",
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS network_samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                bytes_sent INTEGER NOT NULL,
+                bytes_received INTEGER NOT NULL,
+                api_bytes_sent INTEGER NOT NULL,
+                api_bytes_received INTEGER NOT NULL,
+                new_connections INTEGER NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(id)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_network_samples_session ON network_samples(session_id, timestamp)",
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_sources (
+                session_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                byte_offset INTEGER NOT NULL,
+                entries INTEGER NOT NULL,
+                first_read_at TEXT NOT NULL,
+                last_read_at TEXT NOT NULL,
+                PRIMARY KEY (session_id, path),
+                FOREIGN KEY (session_id) REFERENCES sessions(id)
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS content_blobs (
+                hash TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS archived_sessions (
+                id TEXT PRIMARY KEY,
+                project_path TEXT NOT NULL,
+                last_activity_at TEXT NOT NULL,
+                session_json TEXT NOT NULL,
+                first_prompt TEXT,
+                location TEXT NOT NULL,
+                archived_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS trashed_sessions (
+                id TEXT PRIMARY KEY,
+                project_path TEXT NOT NULL,
+                session_json TEXT NOT NULL,
+                location TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                deleted_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                params TEXT NOT NULL,
+                outcome TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp)")
+            .execute(&*self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS daemon_runs (
+                id TEXT PRIMARY KEY,
+                pid INTEGER NOT NULL,
+                version TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                last_seen_at TEXT NOT NULL,
+                stopped_at TEXT,
+                crashed INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Insert or update a session.
+    async fn upsert_session(&self, session: &Session) -> Result<()> {
+        let metadata_json = serde_json::to_string(&session.metadata)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (
+                id, agent_type, external_id, project_path, status,
+                started_at, last_activity_at, ended_at, duration_seconds,
+                message_count, tool_call_count, file_operations,
+                tokens_input, tokens_output, estimated_cost,
+                model_id, pid, current_task, progress, metadata_json, summary, context_tokens, git_branch, user_name
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                last_activity_at = excluded.last_activity_at,
+                ended_at = excluded.ended_at,
+                duration_seconds = excluded.duration_seconds,
+                message_count = excluded.message_count,
+                tool_call_count = excluded.tool_call_count,
+                file_operations = excluded.file_operations,
+                tokens_input = excluded.tokens_input,
+                tokens_output = excluded.tokens_output,
+                estimated_cost = excluded.estimated_cost,
+                current_task = excluded.current_task,
+                progress = excluded.progress,
+                metadata_json = excluded.metadata_json,
+                model_id = COALESCE(excluded.model_id, sessions.model_id),
+                summary = COALESCE(excluded.summary, sessions.summary),
+                context_tokens = excluded.context_tokens,
+                git_branch = COALESCE(excluded.git_branch, sessions.git_branch),
+                user_name = COALESCE(sessions.user_name, excluded.user_name),
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&session.id)
+        .bind(session.agent_type.to_string())
+        .bind(&session.external_id)
+        .bind(&session.project_path)
+        .bind(session.status.to_string())
+        .bind(format_timestamp(&session.started_at))
+        .bind(format_timestamp(&session.last_activity_at))
+        .bind(session.ended_at.as_ref().map(format_timestamp))
+        .bind(session.duration_seconds)
+        .bind(session.message_count)
+        .bind(session.tool_call_count)
+        .bind(session.file_operations)
+        .bind(session.tokens_input)
+        .bind(session.tokens_output)
+        .bind(session.estimated_cost)
+        .bind(&session.model_id)
+        .bind(session.pid)
+        .bind(&session.current_task)
+        .bind(session.progress)
+        .bind(&metadata_json)
+        .bind(&session.summary)
+        .bind(session.context_tokens)
+        .bind(&session.git_branch)
+        .bind(&session.user)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store the summary of a session.
+    async fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        sqlx::query("UPDATE sessions SET summary = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(summary)
+            .bind(session_id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_session_branch(&self, session_id: &str, branch: &str) -> Result<()> {
+        sqlx::query("UPDATE sessions SET git_branch = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(branch)
+            .bind(session_id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get active sessions.
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM sessions
+            WHERE status = 'active'
+            ORDER BY last_activity_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let sessions = rows
+            .iter()
+            .filter_map(|row| self.row_to_session(row).ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get sessions regardless of status or age, most recent first.
+    async fn get_all_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM sessions
+            ORDER BY last_activity_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let sessions = rows
+            .iter()
+            .filter_map(|row| self.row_to_session(row).ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get a single session by ID.
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM sessions WHERE id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(self.row_to_session(&r)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the most recently active session for a project directory.
+    async fn get_active_session_for_project(&self, project_path: &str) -> Result<Option<Session>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM sessions
+            WHERE project_path = ? AND status = 'active'
+            ORDER BY last_activity_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(project_path)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(self.row_to_session(&r)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the most recently active session with the agent's own ID.
+    async fn get_session_by_external_id(&self, external_id: &str) -> Result<Option<Session>> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM sessions
+            WHERE external_id = ?
+            ORDER BY last_activity_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(external_id)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(self.row_to_session(&r)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get recent sessions.
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM sessions
+            WHERE last_activity_at > ?
+            ORDER BY last_activity_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(hours_ago(hours))
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let sessions = rows
+            .iter()
+            .filter_map(|row| self.row_to_session(row).ok())
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get summary metrics.
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as total_sessions,
+                SUM(CASE WHEN status = 'active' THEN 1 ELSE 0 END) as active_sessions,
+                SUM(message_count) as total_messages,
+                SUM(tool_call_count) as total_tools,
+                SUM(estimated_cost) as total_cost
+            FROM sessions
+            WHERE last_activity_at > ?
+            "#,
+        )
+        .bind(hours_ago(hours))
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(SummaryMetrics {
+            total_sessions: row.get::<i64, _>("total_sessions"),
+            active_sessions: row.get::<i64, _>("active_sessions"),
+            total_messages: row.get::<Option<i64>, _>("total_messages").unwrap_or(0),
+            total_tools: row.get::<Option<i64>, _>("total_tools").unwrap_or(0),
+            total_cost: row.get::<Option<f64>, _>("total_cost").unwrap_or(0.0),
+            today_messages: 0, // TODO: Calculate from today
+        })
+    }
+
+    async fn count_sessions_grouped_by(
+        &self,
+        group: SessionGroup,
+        hours: Option<i64>,
+        active_only: bool,
+    ) -> Result<HashMap<String, i64>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {0} AS key, COUNT(*) AS sessions FROM sessions
+            WHERE (?1 IS NULL OR last_activity_at > ?1) AND (?2 = 0 OR status = 'active')
+            GROUP BY {0}
+            "#,
+            group_column(group)
+        ))
+        .bind(hours.map(hours_ago))
+        .bind(active_only)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| (row.get("key"), row.get("sessions"))).collect())
+    }
+
+    /// Insert an event (ignores duplicates based on ID).
+    async fn insert_event(&self, event: &SessionEvent) -> Result<()> {
+        let raw_data_json = event
+            .raw_data
+            .as_ref()
+            .map(|d| serde_json::to_string(d).unwrap_or_default());
+        let tool_input_json = event.tool_input.as_ref().map(|i| i.to_string());
+
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO session_events (
+                id, session_id, event_type, timestamp, agent_type,
+                content, working_directory, tool_name, file_path,
+                tokens_input, tokens_output, error_message, raw_data_json,
+                tool_input_json, tool_output, exit_code, duration_ms
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&event.id)
+        .bind(&event.session_id)
+        .bind(event_type_key(&event.event_type))
+        .bind(event.timestamp.to_rfc3339())
+        .bind(event.agent_type.to_string())
+        .bind(&event.content)
+        .bind(&event.working_directory)
+        .bind(&event.tool_name)
+        .bind(&event.file_path)
+        .bind(event.tokens_input)
+        .bind(event.tokens_output)
+        .bind(&event.error_message)
+        .bind(&raw_data_json)
+        .bind(&tool_input_json)
+        .bind(&event.tool_output)
+        .bind(event.exit_code)
+        .bind(event.duration_ms)
+        .execute(&*self.pool)
+        .await?;
+
+        if result.rows_affected() > 0 && event.event_type == EventType::Compaction {
+            sqlx::query("UPDATE sessions SET compactions = COALESCE(compactions, 0) + 1 WHERE id = ?")
+                .bind(&event.session_id)
+                .execute(&*self.pool)
+                .await?;
+        }
+        if result.rows_affected() > 0 && event.interruption().is_some() {
+            sqlx::query("UPDATE sessions SET interruptions = COALESCE(interruptions, 0) + 1 WHERE id = ?")
+                .bind(&event.session_id)
+                .execute(&*self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get recent events.
+    async fn get_recent_events(&self, limit: usize) -> Result<Vec<SessionEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_events
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .filter_map(|row| self.row_to_event(row).ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Get events of one type from the last `hours`.
+    async fn get_recent_events_of_type(
+        &self,
+        event_type: EventType,
+        hours: i64,
+        limit: usize,
+    ) -> Result<Vec<SessionEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_events
+            WHERE event_type = ?
+              AND datetime(timestamp) > datetime('now', ? || ' hours')
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(event_type_key(&event_type))
+        .bind(-hours)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .filter_map(|row| self.row_to_event(row).ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    async fn get_event_rollups(&self, hours: i64) -> Result<Vec<EventRollup>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT strftime('%Y-%m-%dT%H:00:00Z', timestamp) AS hour, session_id, agent_type,
+                   COUNT(*) AS events,
+                   COALESCE(SUM(COALESCE(tokens_input, 0) + COALESCE(tokens_output, 0)), 0) AS tokens
+            FROM session_events
+            WHERE datetime(timestamp) > datetime('now', ? || ' hours')
+            GROUP BY hour, session_id, agent_type
+            ORDER BY hour
+            "#,
+        )
+        .bind(-hours)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let hour: String = row.get("hour");
+                let agent_type: String = row.get("agent_type");
+                Ok(EventRollup {
+                    hour: parse_timestamp(&hour)?,
+                    session_id: row.get("session_id"),
+                    agent_type: parse_agent_type(&agent_type),
+                    events: row.get("events"),
+                    tokens: row.get("tokens"),
+                })
+            })
+            .collect()
+    }
+
+    /// Get events for a specific session (newest first).
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_events
+            WHERE session_id = ?
+            ORDER BY timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(session_id)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .filter_map(|row| self.row_to_event(row).ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Delete all sessions by agent type.
+    async fn delete_sessions_by_type(&self, agent_type: &str) -> Result<i64> {
+        // First delete related samples, tags, embeddings and events
+        sqlx::query(
+            r#"
+            DELETE FROM network_samples
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM resource_samples
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM session_tags
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+        sqlx::query(
+            r#"
+            DELETE FROM session_sources
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM event_embeddings
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM session_events
+            WHERE session_id IN (SELECT id FROM sessions WHERE agent_type = ?)
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        // Then delete sessions
+        let result = sqlx::query(
+            r#"
+            DELETE FROM sessions WHERE agent_type = ?
+            "#,
+        )
+        .bind(agent_type)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    /// Clear all sessions and events.
+    async fn clear_all(&self) -> Result<()> {
+        sqlx::query("DELETE FROM network_samples")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM resource_samples")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM session_tags")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM session_sources")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM event_embeddings")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM session_events")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM sessions")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM content_blobs")
+            .execute(&*self.pool)
+            .await?;
+        sqlx::query("DELETE FROM archived_sessions")
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Insert or update a memory entry.
+    async fn upsert_memory(&self, entry: &MemoryEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO memory_entries (key, value_json, session_id, tags_json, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(key) DO UPDATE SET
+                value_json = excluded.value_json,
+                tags_json = excluded.tags_json,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&entry.key)
+        .bind(serde_json::to_string(&entry.value)?)
+        .bind(&entry.session_id)
+        .bind(serde_json::to_string(&entry.tags)?)
+        .bind(entry.created_at.to_rfc3339())
+        .bind(entry.updated_at.to_rfc3339())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get a memory entry by key.
+    async fn get_memory(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        let row = sqlx::query("SELECT * FROM memory_entries WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&*self.pool)
+            .await?;
+
+        match row {
+            Some(r) => Ok(Some(self.row_to_memory(&r)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List memory entries, optionally filtered by tag.
+    async fn list_memory(&self, tag: Option<&str>) -> Result<Vec<MemoryEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM memory_entries
+            WHERE ?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(tags_json) WHERE value = ?1)
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .bind(tag)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let entries = rows
+            .iter()
+            .filter_map(|row| self.row_to_memory(row).ok())
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Delete a memory entry.
+    async fn delete_memory(&self, key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM memory_entries WHERE key = ?")
+            .bind(key)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Prompts and responses not yet embedded by `model`.
+    async fn get_unembedded_events(&self, model: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_events e
+            WHERE e.event_type IN (?, ?)
+              AND e.content IS NOT NULL AND e.content != ''
+              AND NOT EXISTS (
+                  SELECT 1 FROM event_embeddings x WHERE x.event_id = e.id AND x.model = ?
+              )
+            ORDER BY e.timestamp DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(event_type_key(&EMBEDDED_EVENT_TYPES[0]))
+        .bind(event_type_key(&EMBEDDED_EVENT_TYPES[1]))
+        .bind(model)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .filter_map(|row| self.row_to_event(row).ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Insert or replace an event's embedding.
+    async fn insert_embedding(&self, embedding: &EventEmbedding) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO event_embeddings (event_id, model, session_id, vector, snippet, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&embedding.event_id)
+        .bind(&embedding.model)
+        .bind(&embedding.session_id)
+        .bind(encode_vector(&embedding.vector))
+        .bind(&embedding.snippet)
+        .bind(embedding.created_at.to_rfc3339())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// All embeddings produced by `model`.
+    async fn list_embeddings(&self, model: &str) -> Result<Vec<EventEmbedding>> {
+        let rows = sqlx::query("SELECT * FROM event_embeddings WHERE model = ?")
+            .bind(model)
+            .fetch_all(&*self.pool)
+            .await?;
+
+        let embeddings = rows
+            .iter()
+            .filter_map(|row| {
+                let vector: Vec<u8> = row.get("vector");
+                let created_at: String = row.get("created_at");
+                Some(EventEmbedding {
+                    event_id: row.get("event_id"),
+                    session_id: row.get("session_id"),
+                    model: row.get("model"),
+                    vector: decode_vector(&vector),
+                    snippet: row.get("snippet"),
+                    created_at: parse_timestamp(&created_at).ok()?,
+                })
+            })
+            .collect();
+
+        Ok(embeddings)
+    }
+
+    /// Tag a session.
+    async fn add_session_tag(&self, tag: &SessionTag) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO session_tags (session_id, tag, source, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(&tag.session_id)
+        .bind(&tag.tag)
+        .bind(&tag.source)
+        .bind(tag.created_at.to_rfc3339())
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a tag from a session.
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM session_tags WHERE session_id = ? AND tag = ?")
+            .bind(session_id)
+            .bind(tag)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Session tags, optionally filtered by session or tag.
+    async fn list_session_tags(&self, session_id: Option<&str>, tag: Option<&str>) -> Result<Vec<SessionTag>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_tags
+            WHERE (?1 IS NULL OR session_id = ?1) AND (?2 IS NULL OR tag = ?2)
+            ORDER BY created_at, tag
+            "#,
+        )
+        .bind(session_id)
+        .bind(tag)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let tags = rows
+            .iter()
+            .filter_map(|row| {
+                let created_at: String = row.get("created_at");
+                Some(SessionTag {
+                    session_id: row.get("session_id"),
+                    tag: row.get("tag"),
+                    source: row.get("source"),
+                    created_at: parse_timestamp(&created_at).ok()?,
+                })
+            })
+            .collect();
+
+        Ok(tags)
+    }
+
+    async fn insert_resource_sample(&self, sample: &ResourceSample) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO resource_samples (session_id, timestamp, cpu_percent, memory_bytes, process_count)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&sample.session_id)
+        .bind(sample.timestamp.to_rfc3339())
+        .bind(sample.cpu_percent)
+        .bind(sample.memory_bytes)
+        .bind(sample.process_count)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_resource_samples(&self, session_id: &str, limit: usize) -> Result<Vec<ResourceSample>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM resource_samples
+            WHERE session_id = ?
+            ORDER BY timestamp DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(session_id)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let samples = rows
+            .iter()
+            .filter_map(|row| {
+                let timestamp: String = row.get("timestamp");
+                Some(ResourceSample {
+                    session_id: row.get("session_id"),
+                    timestamp: parse_timestamp(&timestamp).ok()?,
+                    cpu_percent: row.get("cpu_percent"),
+                    memory_bytes: row.get("memory_bytes"),
+                    process_count: row.get("process_count"),
+                })
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    async fn insert_network_sample(&self, sample: &NetworkSample) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO network_samples (
+                session_id, timestamp, bytes_sent, bytes_received, api_bytes_sent, api_bytes_received,
+                new_connections
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&sample.session_id)
+        .bind(sample.timestamp.to_rfc3339())
+        .bind(sample.bytes_sent)
+        .bind(sample.bytes_received)
+        .bind(sample.api_bytes_sent)
+        .bind(sample.api_bytes_received)
+        .bind(sample.new_connections)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_network_samples(&self, session_id: &str, limit: usize) -> Result<Vec<NetworkSample>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM network_samples
+            WHERE session_id = ?
+            ORDER BY timestamp DESC, id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(session_id)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let samples = rows
+            .iter()
+            .filter_map(|row| {
+                let timestamp: String = row.get("timestamp");
+                Some(NetworkSample {
+                    session_id: row.get("session_id"),
+                    timestamp: parse_timestamp(&timestamp).ok()?,
+                    bytes_sent: row.get("bytes_sent"),
+                    bytes_received: row.get("bytes_received"),
+                    api_bytes_sent: row.get("api_bytes_sent"),
+                    api_bytes_received: row.get("api_bytes_received"),
+                    new_connections: row.get("new_connections"),
+                })
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    async fn record_session_source(&self, source: &SessionSource) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_sources (session_id, path, byte_offset, entries, first_read_at, last_read_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (session_id, path) DO UPDATE SET
+                byte_offset = excluded.byte_offset,
+                entries = session_sources.entries + excluded.entries,
+                last_read_at = excluded.last_read_at
+            "#,
+        )
+        .bind(&source.session_id)
+        .bind(&source.path)
+        .bind(source.offset)
+        .bind(source.entries)
+        .bind(format_timestamp(&source.first_read_at))
+        .bind(format_timestamp(&source.last_read_at))
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_session_sources(&self, session_id: Option<&str>) -> Result<Vec<SessionSource>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM session_sources
+            WHERE ?1 IS NULL OR session_id = ?1
+            ORDER BY first_read_at, path
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let first_read_at: String = row.get("first_read_at");
+                let last_read_at: String = row.get("last_read_at");
+                Ok(SessionSource {
+                    session_id: row.get("session_id"),
+                    path: row.get("path"),
+                    offset: row.get("byte_offset"),
+                    entries: row.get("entries"),
+                    first_read_at: parse_timestamp(&first_read_at)?,
+                    last_read_at: parse_timestamp(&last_read_at)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn put_blob(&self, hash: &str, size: i64, data: &[u8]) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO content_blobs (hash, size, data) VALUES (?, ?, ?)")
+            .bind(hash)
+            .bind(size)
+            .bind(data)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let data = sqlx::query_scalar("SELECT data FROM content_blobs WHERE hash = ?")
+            .bind(hash)
+            .fetch_optional(&*self.pool)
+            .await?;
+        Ok(data)
+    }
+
+    async fn get_blob_stats(&self) -> Result<BlobStats> {
+        let blobs = sqlx::query(
+            "SELECT COUNT(*) as blobs, SUM(LENGTH(data)) as stored, SUM(size) as content FROM content_blobs",
+        )
+        .fetch_one(&*self.pool)
+        .await?;
+        let references = sqlx::query(
+            r#"
+            SELECT COUNT(*) as refs, SUM(b.size) as bytes
+            FROM session_events e
+            JOIN content_blobs b ON b.hash = substr(e.content, ?)
+            WHERE e.content LIKE ?
+            "#,
+        )
+        .bind(BLOB_PREFIX.len() as i64 + 1)
+        .bind(format!("{}%", BLOB_PREFIX))
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(BlobStats {
+            blobs: blobs.get::<i64, _>("blobs"),
+            stored_bytes: blobs.get::<Option<i64>, _>("stored").unwrap_or(0),
+            content_bytes: blobs.get::<Option<i64>, _>("content").unwrap_or(0),
+            references: references.get::<i64, _>("refs"),
+            referenced_bytes: references.get::<Option<i64>, _>("bytes").unwrap_or(0),
+        })
+    }
+
+    async fn get_database_stats(&self, top: usize) -> Result<DatabaseStats> {
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size").fetch_one(&*self.pool).await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count").fetch_one(&*self.pool).await?;
+        let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count").fetch_one(&*self.pool).await?;
+
+        // Space per table and index; dbstat is missing from some SQLite builds
+        let pages: HashMap<String, i64> =
+            sqlx::query_as::<_, (String, i64)>("SELECT name, SUM(pgsize) FROM dbstat GROUP BY name")
+                .fetch_all(&*self.pool)
+                .await
+                .map(|rows| rows.into_iter().collect())
+                .unwrap_or_default();
+
+        let objects: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT type, name, tbl_name FROM sqlite_master WHERE type IN ('table', 'index') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+        let mut tables = Vec::new();
+        let mut indexes = Vec::new();
+        for (kind, name, table) in objects {
+            let bytes = pages.get(&name).copied();
+            if kind == "table" {
+                let rows = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")))
+                    .fetch_one(&*self.pool)
+                    .await?;
+                tables.push(TableStats { name, rows, bytes });
+            } else {
+                indexes.push(IndexStats { name, table, bytes });
+            }
+        }
+
+        let index_problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check(20)")
+            .fetch_all(&*self.pool)
+            .await?
+            .into_iter()
+            .filter(|line: &String| line != "ok")
+            .collect();
+
+        let heaviest_sessions = sqlx::query(
+            r#"
+            SELECT e.session_id, s.project_path, COUNT(*) as events,
+                SUM(COALESCE(LENGTH(CAST(e.content AS BLOB)), 0)
+                    + COALESCE(LENGTH(CAST(e.error_message AS BLOB)), 0)
+                    + COALESCE(LENGTH(CAST(e.raw_data_json AS BLOB)), 0)
+                    + COALESCE(LENGTH(CAST(e.tool_input_json AS BLOB)), 0)
+                    + COALESCE(LENGTH(CAST(e.tool_output AS BLOB)), 0)
+                    + COALESCE(LENGTH(b.data), 0)) as bytes
+            FROM session_events e
+            LEFT JOIN sessions s ON s.id = e.session_id
+            LEFT JOIN content_blobs b ON e.content LIKE ? AND b.hash = substr(e.content, ?)
+            GROUP BY e.session_id
+            ORDER BY bytes DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(format!("{}%", BLOB_PREFIX))
+        .bind(BLOB_PREFIX.len() as i64 + 1)
+        .bind(top as i64)
+        .fetch_all(&*self.pool)
+        .await?
+        .into_iter()
+        .map(|row| SessionSize {
+            session_id: row.get("session_id"),
+            project_path: row.get("project_path"),
+            events: row.get("events"),
+            stored_bytes: row.get::<Option<i64>, _>("bytes").unwrap_or(0),
+        })
+        .collect();
+
+        Ok(DatabaseStats {
+            backend: "sqlite".to_string(),
+            size_bytes: page_size * page_count,
+            fragmentation: if page_count > 0 { free_pages as f64 / page_count as f64 } else { 0.0 },
+            tables,
+            indexes,
+            index_problems,
+            heaviest_sessions,
+        })
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        for table in [
+            "network_samples",
+            "resource_samples",
+            "session_sources",
+            "session_tags",
+            "event_embeddings",
+            "session_events",
+        ] {
+            sqlx::query(&format!("DELETE FROM {} WHERE session_id = ?", table))
+                .bind(session_id)
+                .execute(&*self.pool)
+                .await?;
+        }
+        let result = sqlx::query("DELETE FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn insert_archived_session(&self, archived: &ArchivedSession) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO archived_sessions
+                (id, project_path, last_activity_at, session_json, first_prompt, location, archived_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&archived.session.id)
+        .bind(&archived.session.project_path)
+        .bind(archived.session.last_activity_at.to_rfc3339())
+        .bind(serde_json::to_string(&archived.session)?)
+        .bind(&archived.first_prompt)
+        .bind(&archived.location)
+        .bind(archived.archived_at.to_rfc3339())
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_archived_session(&self, session_id: &str) -> Result<Option<ArchivedSession>> {
+        let row = sqlx::query("SELECT * FROM archived_sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_optional(&*self.pool)
+            .await?;
+        row.map(|r| row_to_archived(&r)).transpose()
+    }
+
+    async fn list_archived_sessions(&self) -> Result<Vec<ArchivedSession>> {
+        let rows = sqlx::query("SELECT * FROM archived_sessions ORDER BY last_activity_at DESC")
+            .fetch_all(&*self.pool)
+            .await?;
+        rows.iter().map(row_to_archived).collect()
+    }
+
+    async fn delete_archived_session(&self, session_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM archived_sessions WHERE id = ?")
+            .bind(session_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn insert_trashed_session(&self, trashed: &TrashedSession) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO trashed_sessions (id, project_path, session_json, location, reason, deleted_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&trashed.session.id)
+        .bind(&trashed.session.project_path)
+        .bind(serde_json::to_string(&trashed.session)?)
+        .bind(&trashed.location)
+        .bind(&trashed.reason)
+        .bind(trashed.deleted_at.to_rfc3339())
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_trashed_sessions(&self) -> Result<Vec<TrashedSession>> {
+        let rows = sqlx::query("SELECT * FROM trashed_sessions ORDER BY deleted_at DESC")
+            .fetch_all(&*self.pool)
+            .await?;
+        rows.iter().map(row_to_trashed).collect()
+    }
+
+    async fn delete_trashed_session(&self, session_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM trashed_sessions WHERE id = ?")
+            .bind(session_id)
+            .execute(&*self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn write_snapshot(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&*self.pool).await?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.display().to_string())
+            .execute(&*self.pool)
+            .await?;
+
+        // A single file with no WAL beside it, so copies of it are complete
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .journal_mode(SqliteJournalMode::Delete);
+        let mut conn = SqliteConnection::connect_with(&options).await?;
+        for view in ANALYTICS_VIEWS {
+            sqlx::query(view).execute(&mut conn).await?;
+        }
+        conn.close().await?;
+
+        let mut permissions = std::fs::metadata(path)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(path, permissions)?;
+        Ok(())
+    }
+
+    async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        sqlx::query("INSERT INTO audit_log (id, timestamp, uid, action, params, outcome) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(&entry.id)
+            .bind(format_timestamp(&entry.timestamp))
+            .bind(entry.uid as i64)
+            .bind(&entry.action)
+            .bind(serde_json::to_string(&entry.params)?)
+            .bind(&entry.outcome)
+            .execute(&*self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let rows = sqlx::query("SELECT * FROM audit_log ORDER BY timestamp DESC LIMIT ?")
+            .bind(limit as i64)
+            .fetch_all(&*self.pool)
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let timestamp: String = row.get("timestamp");
+                let params: String = row.get("params");
+                Ok(AuditEntry {
+                    id: row.get("id"),
+                    timestamp: parse_timestamp(&timestamp)?,
+                    uid: row.get::<i64, _>("uid") as u32,
+                    action: row.get("action"),
+                    params: serde_json::from_str(&params)?,
+                    outcome: row.get("outcome"),
+                })
+            })
+            .collect()
+    }
+
+    async fn upsert_daemon_run(&self, run: &DaemonRun) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO daemon_runs (id, pid, version, started_at, last_seen_at, stopped_at, crashed)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&run.id)
+        .bind(run.pid as i64)
+        .bind(&run.version)
+        .bind(format_timestamp(&run.started_at))
+        .bind(format_timestamp(&run.last_seen_at))
+        .bind(run.stopped_at.as_ref().map(format_timestamp))
+        .bind(run.crashed)
+        .execute(&*self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_daemon_runs(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<DaemonRun>> {
+        let rows = sqlx::query("SELECT * FROM daemon_runs WHERE last_seen_at >= ? OR stopped_at IS NULL ORDER BY started_at")
+            .bind(format_timestamp(&since))
+            .fetch_all(&*self.pool)
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let started_at: String = row.get("started_at");
+                let last_seen_at: String = row.get("last_seen_at");
+                let stopped_at: Option<String> = row.get("stopped_at");
+                Ok(DaemonRun {
+                    id: row.get("id"),
+                    pid: row.get::<i64, _>("pid") as u32,
+                    version: row.get("version"),
+                    started_at: parse_timestamp(&started_at)?,
+                    last_seen_at: parse_timestamp(&last_seen_at)?,
+                    stopped_at: stopped_at.as_deref().map(parse_timestamp).transpose()?,
+                    crashed: row.get("crashed"),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Views added to snapshots for BI tools and DuckDB's sqlite extension.
+const ANALYTICS_VIEWS: [&str; 3] = [
+    r#"
+    CREATE VIEW daily_usage AS
+    SELECT date(started_at) AS day, agent_type, project_path,
+           COUNT(*) AS sessions, SUM(message_count) AS messages, SUM(tool_call_count) AS tool_calls,
+           SUM(tokens_input) AS tokens_input, SUM(tokens_output) AS tokens_output,
+           SUM(estimated_cost) AS cost, SUM(duration_seconds) AS duration_seconds
+    FROM sessions
+    GROUP BY day, agent_type, project_path
+    "#,
+    r#"
+    CREATE VIEW tool_usage AS
+    SELECT e.tool_name, s.agent_type, s.project_path, COUNT(*) AS calls,
+           SUM(e.error_message IS NOT NULL) AS errors
+    FROM session_events e JOIN sessions s ON s.id = e.session_id
+    WHERE e.tool_name IS NOT NULL AND e.event_type = 'toolstart'
+    GROUP BY e.tool_name, s.agent_type, s.project_path
+    "#,
+    r#"
+    CREATE VIEW session_tag_costs AS
+    SELECT t.tag, COUNT(*) AS sessions, SUM(s.estimated_cost) AS cost,
+           SUM(s.tokens_input + s.tokens_output) AS tokens
+    FROM session_tags t JOIN sessions s ON s.id = t.session_id
+    GROUP BY t.tag
+    "#,
+];
+
+fn row_to_archived(row: &sqlx::sqlite::SqliteRow) -> Result<ArchivedSession> {
+    let session_json: String = row.get("session_json");
+    let archived_at: String = row.get("archived_at");
+    Ok(ArchivedSession {
+        session: serde_json::from_str(&session_json)?,
+        first_prompt: row.get("first_prompt"),
+        location: row.get("location"),
+        archived_at: parse_timestamp(&archived_at)?,
+    })
+}
+
+fn row_to_trashed(row: &sqlx::sqlite::SqliteRow) -> Result<TrashedSession> {
+    let session_json: String = row.get("session_json");
+    let deleted_at: String = row.get("deleted_at");
+    Ok(TrashedSession {
+        session: serde_json::from_str(&session_json)?,
+        location: row.get("location"),
+        reason: row.get("reason"),
+        deleted_at: parse_timestamp(&deleted_at)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn test_recent_sessions_use_the_activity_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("sessions.db")).await.unwrap();
+        storage.initialize().await.unwrap();
+
+        let mut recent = Session::new(AgentType::ClaudeCode, "/work/api", "recent");
+        recent.last_activity_at = Utc::now() - Duration::minutes(30);
+        let mut old = Session::new(AgentType::ClaudeCode, "/work/api", "old");
+        old.last_activity_at = Utc::now() - Duration::hours(3);
+        storage.upsert_session(&recent).await.unwrap();
+        storage.upsert_session(&old).await.unwrap();
+
+        // As an older version stored them: other offsets, no fraction
+        let an_hour_ago = (Utc::now() - Duration::hours(1)).with_timezone(&chrono::FixedOffset::east_opt(2 * 3600).unwrap());
+        sqlx::query("UPDATE sessions SET last_activity_at = ? WHERE id = ?")
+            .bind(an_hour_ago.to_rfc3339_opts(chrono::SecondsFormat::Secs, false))
+            .bind(&recent.id)
+            .execute(&*storage.pool)
+            .await
+            .unwrap();
+        storage.initialize().await.unwrap();
+
+        let stored: String = sqlx::query_scalar("SELECT last_activity_at FROM sessions WHERE id = ?")
+            .bind(&recent.id)
+            .fetch_one(&*storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), "2026-01-01T00:00:00.000000Z".len());
+        assert!(stored.ends_with('Z'));
+
+        let sessions = storage.get_recent_sessions(2, 10).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, recent.id);
+        assert_eq!(storage.get_summary_metrics(4).await.unwrap().total_sessions, 2);
+
+        let plan: Vec<(i64, i64, i64, String)> = sqlx::query_as(
+            "EXPLAIN QUERY PLAN SELECT * FROM sessions WHERE last_activity_at > ? ORDER BY last_activity_at DESC LIMIT 10",
+        )
+        .bind(hours_ago(2))
+        .fetch_all(&*storage.pool)
+        .await
+        .unwrap();
+        assert!(plan.iter().any(|(_, _, _, detail)| detail.contains("idx_sessions_last_activity")));
+    }
+
+    #[tokio::test]
+    async fn test_session_sources_accumulate() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("sessions.db")).await.unwrap();
+        storage.initialize().await.unwrap();
+
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "abc");
+        storage.upsert_session(&session).await.unwrap();
+        let started = Utc::now() - Duration::minutes(5);
+        let read = |offset, entries, at| SessionSource {
+            session_id: session.id.clone(),
+            path: "/home/me/.claude/projects/-work-api/abc.jsonl".to_string(),
+            offset,
+            entries,
+            first_read_at: at,
+            last_read_at: at,
+        };
+        storage.record_session_source(&read(1200, 50, started)).await.unwrap();
+        storage.record_session_source(&read(1500, 3, Utc::now())).await.unwrap();
+
+        let sources = storage.get_session_sources(Some(&session.id)).await.unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!((sources[0].offset, sources[0].entries), (1500, 53));
+        assert_eq!(sources[0].first_read_at.timestamp(), started.timestamp());
+        assert!(sources[0].last_read_at > started);
+        assert_eq!(storage.get_session_sources(None).await.unwrap().len(), 1);
+        assert!(storage.get_session_sources(Some("other")).await.unwrap().is_empty());
+
+        storage.delete_session(&session.id).await.unwrap();
+        assert!(storage.get_session_sources(None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_count_sessions_grouped_by() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("sessions.db")).await.unwrap();
+        storage.initialize().await.unwrap();
+
+        let mut sessions = vec![
+            Session::new(AgentType::ClaudeCode, "/work/api", "a"),
+            Session::new(AgentType::ClaudeCode, "/work/web", "b"),
+            Session::new(AgentType::Cursor, "/work/api", "c"),
+        ];
+        sessions[1].status = crate::models::SessionStatus::Completed;
+        sessions[2].last_activity_at = Utc::now() - Duration::days(3);
+        for session in &sessions {
+            storage.upsert_session(session).await.unwrap();
+        }
+
+        let by_agent = storage.count_sessions_grouped_by(SessionGroup::AgentType, None, false).await.unwrap();
+        assert_eq!(by_agent.get("claude_code"), Some(&2));
+        assert_eq!(by_agent.get("cursor"), Some(&1));
+
+        let by_project = storage.count_sessions_grouped_by(SessionGroup::Project, Some(24), false).await.unwrap();
+        assert_eq!(by_project, HashMap::from([("/work/api".to_string(), 1), ("/work/web".to_string(), 1)]));
+
+        let running = storage.count_sessions_grouped_by(SessionGroup::Status, None, true).await.unwrap();
+        assert_eq!(running, HashMap::from([("active".to_string(), 2)]));
+    }
+
+    #[tokio::test]
+    async fn test_tool_fields_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("sessions.db")).await.unwrap();
+        storage.initialize().await.unwrap();
+        let session = Session::new(AgentType::ClaudeCode, "/work/api", "a");
+        storage.upsert_session(&session).await.unwrap();
+
+        let mut event = SessionEvent::new(&session.id, EventType::ToolExecuted, AgentType::ClaudeCode);
+        event.tool_input = Some(serde_json::json!({ "command": "ls" }));
+        event.set_tool_output(&"é".repeat(crate::models::MAX_TOOL_OUTPUT));
+        event.exit_code = Some(2);
+        event.duration_ms = Some(40);
+        storage.insert_event(&event).await.unwrap();
+
+        let read = &storage.get_session_events(&session.id, 1).await.unwrap()[0];
+        assert_eq!(read.tool_input, event.tool_input);
+        assert_eq!(read.tool_output.as_ref().unwrap().len(), crate::models::MAX_TOOL_OUTPUT);
+        assert_eq!((read.exit_code, read.duration_ms), (Some(2), Some(40)));
+    }
+}
@@ -0,0 +1,273 @@
+//! Events pushed from the daemon to the TUI.
+//!
+//! A client sends `{"action": "subscribe"}` on the daemon's IPC socket and
+//! the connection turns into a stream: `{"subscribed": true}`, then a line
+//! per event the daemon records (`{"event": {...}}`, the event's summary),
+//! with a keep-alive line every half minute so dead clients are noticed.
+//! The TUI on the local database follows the stream and only re-reads the
+//! database when something was pushed. When the daemon isn't running, or
+//! stops, the subscription reports itself disconnected, the TUI goes back to
+//! polling, and the connection is retried in the background.
+//!
+//! A subscribe request may carry a `filter` (see `events::EventFilter`) and
+//! `"full": true` for whole events instead of summaries. The web server,
+//! which runs in a process of its own, relays the daemon's full events onto
+//! an event bus of its own that the dashboard, SSE and webhooks follow.
+
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+use tokio::time::{interval, sleep, Duration};
+
+use crate::events::{self, EventBus, Received};
+use crate::integrations::EventSummary;
+use crate::models::SessionEvent;
+
+/// Time between keep-alive lines on an idle stream.
+const KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// Wait before reconnecting to the daemon.
+const RETRY: Duration = Duration::from_secs(5);
+
+/// Stream `events` to a subscriber until it goes away. With `full` each
+/// line carries the whole event rather than its summary.
+pub async fn serve<W: AsyncWrite + Unpin>(writer: &mut W, mut events: events::Subscription, full: bool) -> Result<()> {
+    let mut keepalive = interval(KEEPALIVE);
+    keepalive.tick().await;
+    let mut line = serde_json::json!({ "subscribed": true });
+    loop {
+        // A failed write means the subscriber has gone
+        if writer.write_all((serde_json::to_string(&line)? + "\n").as_bytes()).await.is_err() {
+            return Ok(());
+        }
+        line = tokio::select! {
+            received = events.recv_or_lag() => match received {
+                Some(Received::Event(event)) if full => serde_json::json!({ "event": event }),
+                Some(Received::Event(event)) => serde_json::json!({ "event": EventSummary::from(event.as_ref()) }),
+                // The subscriber missed some, so it has to re-read everything
+                Some(Received::Lagged(missed)) => serde_json::json!({ "lagged": missed }),
+                None => return Ok(()),
+            },
+            _ = keepalive.tick() => serde_json::json!({ "keepalive": true }),
+        };
+    }
+}
+
+enum Update {
+    /// Anything may have changed
+    Everything,
+    /// A session has new events
+    Session(String),
+}
+
+/// What was pushed since the last check.
+#[derive(Debug, Default)]
+pub struct Pushed {
+    /// Anything may have changed: just (re)connected, or events were missed
+    pub everything: bool,
+    /// Sessions with new events
+    pub sessions: HashSet<String>,
+}
+
+impl Pushed {
+    pub fn is_empty(&self) -> bool {
+        !self.everything && self.sessions.is_empty()
+    }
+
+    /// Whether `session_id` may have changed.
+    pub fn touches(&self, session_id: &str) -> bool {
+        self.everything || self.sessions.contains(session_id)
+    }
+
+    pub fn merge(&mut self, other: Pushed) {
+        self.everything |= other.everything;
+        self.sessions.extend(other.sessions);
+    }
+}
+
+/// A subscription to the daemon's events, reconnecting as needed.
+pub struct Subscription {
+    updates: mpsc::UnboundedReceiver<Update>,
+    connected: Arc<AtomicBool>,
+}
+
+impl Subscription {
+    /// Follow the daemon listening on `socket_path`.
+    pub fn start(socket_path: PathBuf) -> Self {
+        let (tx, updates) = mpsc::unbounded_channel();
+        let connected = Arc::new(AtomicBool::new(false));
+        let flag = connected.clone();
+        tokio::spawn(async move {
+            while !tx.is_closed() {
+                if let Ok(stream) = UnixStream::connect(&socket_path).await {
+                    let (reader, mut writer) = stream.into_split();
+                    if writer.write_all(b"{\"action\":\"subscribe\"}\n").await.is_ok() {
+                        let _ = follow(BufReader::new(reader), &tx, &flag).await;
+                    }
+                    flag.store(false, Ordering::Relaxed);
+                }
+                sleep(RETRY).await;
+            }
+        });
+        Self { updates, connected }
+    }
+
+    /// Whether pushes are arriving; otherwise the caller should poll.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Everything pushed since the last call.
+    pub fn take(&mut self) -> Pushed {
+        let mut pushed = Pushed::default();
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                Update::Everything => pushed.everything = true,
+                Update::Session(id) => {
+                    pushed.sessions.insert(id);
+                }
+            }
+        }
+        pushed
+    }
+}
+
+/// Read a subscription stream into `tx` until it ends.
+async fn follow<R: AsyncBufRead + Unpin>(
+    mut reader: R,
+    tx: &mpsc::UnboundedSender<Update>,
+    connected: &AtomicBool,
+) -> Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let message: serde_json::Value = serde_json::from_str(&line)?;
+        // A daemon from before subscriptions answers with an error
+        if let Some(error) = message.get("error").and_then(|e| e.as_str()) {
+            bail!("{}", error);
+        }
+        let update = if message.get("subscribed").is_some() {
+            connected.store(true, Ordering::Relaxed);
+            // Catch up on whatever happened while not subscribed
+            Update::Everything
+        } else if message.get("lagged").is_some() {
+            Update::Everything
+        } else if let Some(id) = message.pointer("/event/session_id").and_then(|v| v.as_str()) {
+            Update::Session(id.to_string())
+        } else {
+            continue;
+        };
+        if tx.send(update).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Publish the events of the daemon listening on `socket_path` on `bus`,
+/// reconnecting as needed. The flag tells whether events are arriving.
+pub fn relay(socket_path: PathBuf, bus: EventBus) -> Arc<AtomicBool> {
+    let connected = Arc::new(AtomicBool::new(false));
+    let flag = connected.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Ok(stream) = UnixStream::connect(&socket_path).await {
+                let (reader, mut writer) = stream.into_split();
+                if writer.write_all(b"{\"action\":\"subscribe\",\"full\":true}\n").await.is_ok() {
+                    let _ = republish(BufReader::new(reader), &bus, &flag).await;
+                }
+                flag.store(false, Ordering::Relaxed);
+            }
+            sleep(RETRY).await;
+        }
+    });
+    connected
+}
+
+/// Read a stream of full events onto `bus` until it ends.
+async fn republish<R: AsyncBufRead + Unpin>(mut reader: R, bus: &EventBus, connected: &AtomicBool) -> Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let mut message: serde_json::Value = serde_json::from_str(&line)?;
+        if let Some(error) = message.get("error").and_then(|e| e.as_str()) {
+            bail!("{}", error);
+        }
+        if message.get("subscribed").is_some() {
+            connected.store(true, Ordering::Relaxed);
+        } else if let Some(event) = message.get_mut("event").map(serde_json::Value::take) {
+            bus.publish(serde_json::from_value::<SessionEvent>(event)?);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType};
+
+    #[tokio::test]
+    async fn test_subscription_stream() {
+        let bus = EventBus::new();
+        let events_rx = bus.subscribe_filtered("test", events::EventFilter::all());
+        let (server, client) = tokio::io::duplex(4096);
+        let (updates_tx, updates_rx) = mpsc::unbounded_channel();
+        let mut subscription = Subscription { updates: updates_rx, connected: Arc::new(AtomicBool::new(false)) };
+
+        let serving = tokio::spawn(async move {
+            let mut server = server;
+            serve(&mut server, events_rx, false).await
+        });
+        bus.publish(SessionEvent::new("one", EventType::ToolStart, AgentType::ClaudeCode));
+        bus.publish(SessionEvent::new("two", EventType::PromptReceived, AgentType::Cursor));
+        drop(bus);
+
+        let connected = subscription.connected.clone();
+        follow(BufReader::new(client), &updates_tx, &connected).await.unwrap();
+        serving.await.unwrap().unwrap();
+
+        assert!(subscription.is_connected());
+        let pushed = subscription.take();
+        assert!(pushed.everything);
+        assert!(pushed.touches("one") && pushed.sessions.contains("two"));
+        assert!(subscription.take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_relay_of_filtered_full_events() {
+        let daemon = EventBus::new();
+        let events_rx = daemon.subscribe_filtered("test", events::EventFilter::all().session("two"));
+        let (server, client) = tokio::io::duplex(4096);
+        let serving = tokio::spawn(async move {
+            let mut server = server;
+            serve(&mut server, events_rx, true).await
+        });
+        let mut event = SessionEvent::new("two", EventType::PromptReceived, AgentType::Cursor);
+        event.content = Some("Add a health check endpoint".to_string());
+        daemon.publish(SessionEvent::new("one", EventType::ToolStart, AgentType::ClaudeCode));
+        daemon.publish(event.clone());
+        drop(daemon);
+
+        let web = EventBus::new();
+        let mut relayed = web.subscribe();
+        let connected = AtomicBool::new(false);
+        republish(BufReader::new(client), &web, &connected).await.unwrap();
+        serving.await.unwrap().unwrap();
+
+        assert!(connected.load(Ordering::Relaxed));
+        let received = relayed.try_recv().unwrap();
+        assert_eq!(received.id, event.id);
+        assert_eq!(received.content, event.content);
+        assert!(relayed.try_recv().is_err());
+    }
+}
@@ -0,0 +1,364 @@
+//! Automatic summaries of completed sessions.
+//!
+//! When enabled, the daemon periodically picks up completed or crashed
+//! sessions that have no summary yet and writes a 3–5 line digest: what was
+//! asked, what changed, and how it ended. The default `template` mode builds
+//! it from the stored events; `llm` mode sends an excerpt of the session to an
+//! OpenAI-compatible chat completions endpoint (a hosted API, Ollama,
+//! llama.cpp, ...) and falls back to the template if the call fails.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::humanize;
+use crate::models::{EventType, Session, SessionEvent, SessionStatus};
+use crate::storage::Storage;
+
+/// How a summary is produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummarizerMode {
+    /// Built locally from the session's events
+    Template,
+    /// Written by a configured LLM endpoint
+    Llm,
+}
+
+/// Session summarizer settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizerConfig {
+    /// Whether completed sessions are summarized
+    pub enabled: bool,
+
+    /// `template` or `llm`
+    pub mode: SummarizerMode,
+
+    /// Chat completions URL for `llm` mode
+    pub endpoint: Option<String>,
+
+    /// Model name sent to the endpoint
+    pub model: Option<String>,
+
+    /// Environment variable holding the endpoint's API key
+    pub api_key_env: Option<String>,
+
+    /// Seconds between passes over completed sessions
+    pub interval_secs: u64,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: SummarizerMode::Template,
+            endpoint: None,
+            model: None,
+            api_key_env: None,
+            interval_secs: 60,
+        }
+    }
+}
+
+/// Sessions summarized per pass, so a backlog doesn't flood the endpoint.
+const MAX_PER_PASS: usize = 20;
+
+/// Most recent sessions scanned for missing summaries.
+const SCAN_LIMIT: usize = 200;
+
+/// Events read per session.
+const MAX_EVENTS: usize = 2000;
+
+/// Upper bound on the session excerpt sent to the LLM.
+const MAX_EXCERPT_CHARS: usize = 8000;
+
+/// Longest prompt or response line in a template summary.
+const MAX_LINE_CHARS: usize = 160;
+
+const LLM_INSTRUCTIONS: &str = "Summarize this coding agent session in 3 to 5 short lines: \
+what was asked, what changed, and the outcome. Plain text, no preamble, no markdown headings.";
+
+/// Writes summaries for completed sessions.
+#[derive(Clone)]
+pub struct Summarizer {
+    config: SummarizerConfig,
+    storage: Storage,
+    client: reqwest::Client,
+}
+
+impl Summarizer {
+    pub fn new(config: SummarizerConfig, storage: Storage) -> Self {
+        Self {
+            config,
+            storage,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Summarize pending sessions every `interval_secs`.
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(5)));
+        loop {
+            ticker.tick().await;
+            match self.summarize_pending().await {
+                Ok(0) => {}
+                Ok(count) => debug!("Summarized {} sessions", count),
+                Err(e) => warn!("Session summarization failed: {}", e),
+            }
+        }
+    }
+
+    /// Summarize completed sessions that don't have a summary yet. Returns
+    /// how many were written.
+    pub async fn summarize_pending(&self) -> Result<usize> {
+        let pending: Vec<Session> = self
+            .storage
+            .get_all_sessions(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|s| matches!(s.status, SessionStatus::Completed | SessionStatus::Crashed))
+            .filter(|s| s.summary.is_none() && s.message_count > 0)
+            .take(MAX_PER_PASS)
+            .collect();
+
+        for session in &pending {
+            let summary = self.summarize(session).await?;
+            self.storage.set_session_summary(&session.id, &summary).await?;
+        }
+
+        Ok(pending.len())
+    }
+
+    /// Produce the summary for one session.
+    pub async fn summarize(&self, session: &Session) -> Result<String> {
+        let mut events = self.storage.get_session_events(&session.id, MAX_EVENTS).await?;
+        events.reverse();
+
+        let template = template_summary(session, &events);
+        if self.config.mode == SummarizerMode::Template {
+            return Ok(template);
+        }
+
+        match self.llm_summary(session, &events, &template).await {
+            Ok(summary) => Ok(summary),
+            Err(e) => {
+                warn!("LLM summary for session {} failed, using template: {}", session.id, e);
+                Ok(template)
+            }
+        }
+    }
+
+    async fn llm_summary(&self, session: &Session, events: &[SessionEvent], template: &str) -> Result<String> {
+        let endpoint = self
+            .config
+            .endpoint
+            .as_deref()
+            .ok_or_else(|| anyhow!("summarizer.endpoint is not set"))?;
+
+        let body = serde_json::json!({
+            "model": self.config.model.as_deref().unwrap_or("gpt-4o-mini"),
+            "messages": [
+                { "role": "system", "content": LLM_INSTRUCTIONS },
+                { "role": "user", "content": session_excerpt(session, events, template) },
+            ],
+            "temperature": 0.2,
+        });
+
+        let mut request = self.client.post(endpoint).json(&body);
+        if let Some(ref var) = self.config.api_key_env {
+            let key = std::env::var(var).map_err(|_| anyhow!("{} is not set", var))?;
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            bail!("endpoint returned {}", response.status());
+        }
+        let reply: Value = response.json().await?;
+        let content = reply["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("response has no choices[0].message.content"))?;
+
+        let summary: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(5)
+            .collect();
+        if summary.is_empty() {
+            bail!("endpoint returned an empty summary");
+        }
+        Ok(summary.join("\n"))
+    }
+}
+
+/// Build a summary from the session's events (oldest first).
+pub fn template_summary(session: &Session, events: &[SessionEvent]) -> String {
+    let mut lines = Vec::new();
+
+    let asked = events
+        .iter()
+        .find(|e| e.event_type == EventType::PromptReceived)
+        .and_then(|e| e.content.as_deref())
+        .or(session.current_task.as_deref())
+        .and_then(first_line);
+    if let Some(asked) = asked {
+        lines.push(format!("Asked: {}", asked));
+    }
+
+    let mut files: Vec<&str> = Vec::new();
+    let mut tools: HashMap<&str, usize> = HashMap::new();
+    for event in events {
+        if let Some(ref tool) = event.tool_name {
+            if matches!(event.event_type, EventType::ToolStart | EventType::ToolExecuted) {
+                *tools.entry(tool.as_str()).or_default() += 1;
+            }
+        }
+        let modifies = event.event_type == EventType::FileModified
+            || matches!(event.tool_name.as_deref(), Some("Edit" | "MultiEdit" | "Write" | "NotebookEdit"));
+        if let (true, Some(path)) = (modifies, event.file_path.as_deref()) {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            if !files.contains(&name) {
+                files.push(name);
+            }
+        }
+    }
+
+    let mut changed = match files.len() {
+        0 => "Changed: no files".to_string(),
+        n if n <= 4 => format!("Changed: {}", files.join(", ")),
+        n => format!("Changed: {} and {} more", files[..3].join(", "), n - 3),
+    };
+    if !tools.is_empty() {
+        let mut tools: Vec<(&str, usize)> = tools.into_iter().collect();
+        tools.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let top: Vec<String> = tools.iter().take(3).map(|(t, n)| format!("{} ×{}", t, n)).collect();
+        changed.push_str(&format!(" (tools: {})", top.join(", ")));
+    }
+    lines.push(changed);
+
+    let last = events.iter().rev().find(|e| {
+        matches!(e.event_type, EventType::ResponseGenerated | EventType::Error | EventType::SessionEnd)
+    });
+    let outcome = match last {
+        Some(e) if e.event_type == EventType::Error => {
+            let message = e.error_message.as_deref().or(e.content.as_deref()).and_then(first_line);
+            format!("Outcome: ended with an error{}", message.map(|m| format!(": {}", m)).unwrap_or_default())
+        }
+        Some(e) => match e.content.as_deref().and_then(first_line) {
+            Some(reply) => format!("Outcome: {}", reply),
+            None => format!("Outcome: {}", session.status),
+        },
+        None if session.status == SessionStatus::Crashed => "Outcome: crashed".to_string(),
+        None => format!("Outcome: {}", session.status),
+    };
+    lines.push(outcome);
+
+    lines.push(format!(
+        "{} messages, {} tool calls, {}, {}",
+        session.message_count,
+        session.tool_call_count,
+        humanize::report_duration(session.duration_seconds),
+        humanize::cost(session.estimated_cost)
+    ));
+
+    lines.join("\n")
+}
+
+/// Prompts, replies and errors from the session, trimmed for an LLM request.
+fn session_excerpt(session: &Session, events: &[SessionEvent], template: &str) -> String {
+    let mut text = format!("Project: {}\nStatistics:\n{}\n\nTranscript:\n", session.project_path, template);
+    for event in events {
+        let (label, content) = match event.event_type {
+            EventType::PromptReceived => ("User", event.content.as_deref()),
+            EventType::ResponseGenerated => ("Agent", event.content.as_deref()),
+            EventType::Error | EventType::ApiError => {
+                ("Error", event.error_message.as_deref().or(event.content.as_deref()))
+            }
+            _ => continue,
+        };
+        let Some(content) = content else { continue };
+        let content: String = content.chars().take(600).collect();
+        text.push_str(&format!("{}: {}\n", label, content));
+        if text.len() > MAX_EXCERPT_CHARS {
+            break;
+        }
+    }
+    text
+}
+
+fn first_line(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return Some(line.to_string());
+    }
+    let mut truncated: String = line.chars().take(MAX_LINE_CHARS - 1).collect();
+    truncated.push('…');
+    Some(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    fn event(session: &Session, event_type: EventType, content: Option<&str>) -> SessionEvent {
+        let mut event = SessionEvent::new(&session.id, event_type, AgentType::ClaudeCode);
+        event.content = content.map(str::to_string);
+        event
+    }
+
+    #[test]
+    fn test_template_summary() {
+        let mut session = Session::new(AgentType::ClaudeCode, "/work/app", "run-1");
+        session.message_count = 8;
+        session.duration_seconds = 600.0;
+
+        let mut edit = event(&session, EventType::ToolStart, None);
+        edit.tool_name = Some("Edit".to_string());
+        edit.file_path = Some("/work/app/src/users.rs".to_string());
+        let events = vec![
+            event(&session, EventType::PromptReceived, Some("Add pagination to /users\nwith cursors")),
+            edit,
+            event(&session, EventType::ResponseGenerated, Some("Pagination is in place and tests pass.")),
+        ];
+
+        let summary = template_summary(&session, &events);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "Asked: Add pagination to /users");
+        assert_eq!(lines[1], "Changed: users.rs (tools: Edit ×1)");
+        assert_eq!(lines[2], "Outcome: Pagination is in place and tests pass.");
+        assert!(lines[3].starts_with("8 messages"));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_pending_only_completed() {
+        let storage = Storage::in_memory();
+        let mut done = Session::new(AgentType::ClaudeCode, "/work/app", "done");
+        done.status = SessionStatus::Completed;
+        done.message_count = 3;
+        done.current_task = Some("Fix the flaky test".to_string());
+        let mut running = Session::new(AgentType::ClaudeCode, "/work/app", "running");
+        running.message_count = 3;
+        storage.upsert_session(&done).await.unwrap();
+        storage.upsert_session(&running).await.unwrap();
+
+        let config = SummarizerConfig { enabled: true, ..Default::default() };
+        let summarizer = Summarizer::new(config, storage.clone());
+        assert_eq!(summarizer.summarize_pending().await.unwrap(), 1);
+        assert_eq!(summarizer.summarize_pending().await.unwrap(), 0);
+
+        // Later adapter updates must not erase the summary
+        storage.upsert_session(&done).await.unwrap();
+        let stored = storage.get_session(&done.id).await.unwrap().unwrap();
+        assert!(stored.summary.unwrap().starts_with("Asked: Fix the flaky test"));
+        assert!(storage.get_session(&running.id).await.unwrap().unwrap().summary.is_none());
+    }
+}
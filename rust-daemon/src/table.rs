@@ -0,0 +1,280 @@
+//! Tables and boxed panels for CLI output.
+//!
+//! Column widths follow the content, so a cost like `$1234.56` widens its
+//! column instead of pushing the border out of line. Cells longer than a
+//! column's limit are cut with an ellipsis, and widths are measured in
+//! terminal columns so emoji and CJK text line up. Under the plain profile
+//! colors are dropped, leaving box-drawing text.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::theme::{ColorProfile, Style};
+use crate::{AURORA_BLUE, BOLD, RESET};
+
+/// Horizontal alignment of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// One table cell, optionally colored.
+#[derive(Debug, Clone)]
+pub struct Cell {
+    text: String,
+    style: Option<Style>,
+}
+
+impl Cell {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), style: None }
+    }
+
+    pub fn colored(text: impl Into<String>, style: Style) -> Self {
+        Self { text: text.into(), style: Some(style) }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::new(text)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::new(text)
+    }
+}
+
+struct Column {
+    header: String,
+    align: Align,
+    max_width: Option<usize>,
+}
+
+/// A bordered table with a header row.
+pub struct Table {
+    title: Option<String>,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    /// A table with these column headers, all left-aligned.
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            title: None,
+            columns: headers
+                .iter()
+                .map(|h| Column { header: h.to_string(), align: Align::Left, max_width: None })
+                .collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Centered above the table, e.g. `✦ Sessions ✦`.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn align(mut self, column: usize, align: Align) -> Self {
+        self.columns[column].align = align;
+        self
+    }
+
+    /// Cut cells in `column` to `width` terminal columns.
+    pub fn max_width(mut self, column: usize, width: usize) -> Self {
+        self.columns[column].max_width = Some(width.max(1));
+        self
+    }
+
+    /// Add a row; missing cells are left empty and extra ones dropped.
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    /// The table as lines of text, colored per `profile`.
+    pub fn render(&self, profile: ColorProfile) -> String {
+        let paint = |text: &str, style: Style| {
+            if profile != ColorProfile::None {
+                format!("{}{}{}", style.code(profile), text, RESET.code(profile))
+            } else {
+                text.to_string()
+            }
+        };
+
+        let cell_text = |column: &Column, text: &str| match column.max_width {
+            Some(max) => truncate(text, max),
+            None => text.to_string(),
+        };
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell_text(column, &cell.text).width())
+                    .chain(std::iter::once(column.header.width()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let rule = |left: &str, middle: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            paint(&format!("{}{}{}", left, segments.join(middle), right), AURORA_BLUE)
+        };
+        let bar = paint("│", AURORA_BLUE);
+
+        let mut lines = Vec::new();
+        if let Some(ref title) = self.title {
+            let total: usize = widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+            let padding = total.saturating_sub(title.width()) / 2;
+            lines.push(format!("{}{}", " ".repeat(padding), paint(title, AURORA_BLUE)));
+        }
+
+        lines.push(rule("╭", "┬", "╮"));
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| paint(&pad(&column.header, *width, column.align), BOLD))
+            .collect();
+        lines.push(format!("{} {} {}", bar, header.join(&format!(" {} ", bar)), bar));
+        lines.push(rule("├", "┼", "┤"));
+
+        for row in &self.rows {
+            let cells: Vec<String> = self
+                .columns
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (column, width))| {
+                    let cell = row.get(i);
+                    let text = pad(&cell_text(column, cell.map_or("", |c| &c.text)), *width, column.align);
+                    match cell.and_then(|c| c.style) {
+                        Some(style) => paint(&text, style),
+                        None => text,
+                    }
+                })
+                .collect();
+            lines.push(format!("{} {} {}", bar, cells.join(&format!(" {} ", bar)), bar));
+        }
+        lines.push(rule("╰", "┴", "╯"));
+        lines.join("\n")
+    }
+}
+
+/// A box around `lines`, with `title` in the top border, as wide as its
+/// content. Lines may hold ANSI colors; they don't count toward the width and
+/// are stripped under the plain profile.
+pub fn panel(title: &str, lines: &[String], profile: ColorProfile) -> String {
+    let color = profile != ColorProfile::None;
+    let paint = |text: &str| {
+        if color {
+            format!("{}{}{}", AURORA_BLUE.code(profile), text, RESET.code(profile))
+        } else {
+            text.to_string()
+        }
+    };
+    let content_width = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+    let title = format!(" {} ", title);
+    let width = content_width.max(title.width() + 4) + 4;
+
+    let left = (width - title.width()) / 2;
+    let right = width - title.width() - left;
+    let mut out = vec![paint(&format!("╭{}{}{}╮", "─".repeat(left), title, "─".repeat(right)))];
+    for line in lines {
+        let line = if color { line.clone() } else { strip_ansi(line) };
+        let padding = width - 2 - visible_width(&line);
+        out.push(format!("{}  {}{}{}", paint("│"), line, " ".repeat(padding), paint("│")));
+    }
+    out.push(paint(&format!("╰{}╯", "─".repeat(width))));
+    out.join("\n")
+}
+
+/// Terminal columns taken by `text`, skipping ANSI escape sequences.
+pub fn visible_width(text: &str) -> usize {
+    strip_ansi(text).width()
+}
+
+/// `text` without ANSI escape sequences.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a letter, e.g. \x1b[38;5;117m
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `text` cut to `max` terminal columns, ending in `…` when cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.width() <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if width + w + 1 > max {
+            break;
+        }
+        out.push(c);
+        width += w;
+    }
+    out.push('…');
+    out
+}
+
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let space = width.saturating_sub(text.width());
+    match align {
+        Align::Left => format!("{}{}", text, " ".repeat(space)),
+        Align::Right => format!("{}{}", " ".repeat(space), text),
+        Align::Center => format!("{}{}{}", " ".repeat(space / 2), text, " ".repeat(space - space / 2)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_widths() {
+        let mut table = Table::new(&["Project", "Cost"]).align(1, Align::Right).max_width(0, 8);
+        table.row(vec!["api".into(), "$1.50".into()]);
+        table.row(vec!["a-very-long-project".into(), Cell::colored("$1234.56", AURORA_BLUE)]);
+
+        let plain = table.render(ColorProfile::None);
+        let lines: Vec<&str> = plain.lines().collect();
+        assert_eq!(lines[1], "│ Project  │     Cost │");
+        assert_eq!(lines[3], "│ api      │    $1.50 │");
+        assert_eq!(lines[4], "│ a-very-… │ $1234.56 │");
+        assert!(lines.iter().all(|l| l.width() == lines[0].width()));
+        assert!(!plain.contains('\x1b'));
+
+        // Colors don't change the layout
+        let colored = table.render(ColorProfile::Extended);
+        assert!(colored.lines().all(|l| visible_width(l) == lines[0].width()));
+
+        let boxed = panel("Status", &["Sessions: 12".to_string(), format!("{}Cost:{} $9999.99", BOLD, RESET)], ColorProfile::None);
+        assert!(boxed.lines().all(|l| l.width() == boxed.lines().next().unwrap().width()));
+        assert!(!boxed.contains('\x1b'));
+        assert_eq!(truncate("🟢 active", 4), "🟢 …");
+    }
+}
@@ -0,0 +1,338 @@
+//! End-to-end test fixtures.
+//!
+//! [`FakeClaude`] builds a scratch `~/.claude` tree and writes synthetic
+//! `history.jsonl` entries and project transcripts the way Claude Code does.
+//! [`FakeClaude::start`] runs the real Claude Code adapter (file watcher,
+//! parsing, storage, event bus) against it with a scratch SQLite database,
+//! and [`Pipeline`] waits for what it ingested. [`json_entry`] generates
+//! arbitrary log entries for property tests. New fixtures for other agents
+//! or entry types belong here, next to the tests that use them.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use crate::adapters::{Adapter, ClaudeCodeAdapter};
+use crate::config::Config;
+use crate::events::EventBus;
+use crate::models::{Session, SessionEvent};
+use crate::procwatch::ProcessWatcher;
+use crate::storage::Storage;
+
+/// How long [`Pipeline`] waits for the adapter to catch up.
+const WAIT: Duration = Duration::from_secs(10);
+
+/// A scratch Claude Code home with its own database.
+pub struct FakeClaude {
+    _dir: TempDir,
+    pub config: Config,
+}
+
+impl FakeClaude {
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let claude_home = dir.path().join("claude");
+        std::fs::create_dir_all(claude_home.join("projects"))?;
+        let config = Config {
+            claude_home,
+            data_dir: dir.path().to_path_buf(),
+            db_path: dir.path().join("sessions.db"),
+            ..Config::default()
+        };
+        Ok(Self { _dir: dir, config })
+    }
+
+    /// A project transcript, as Claude Code writes to
+    /// `projects/<project path with / as ->/<session>.jsonl`. Create it before
+    /// [`FakeClaude::start`], like a project Claude Code has been used in: a
+    /// file written right after its directory appears can be missed until
+    /// the watcher has picked up the directory.
+    pub fn transcript(&self, project_path: &str, session_id: &str) -> Result<Transcript> {
+        let dir = self.config.claude_home.join("projects").join(project_path.replace('/', "-"));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.jsonl", session_id));
+        std::fs::File::create(&path)?;
+        Ok(Transcript {
+            path,
+            project_path: project_path.to_string(),
+            session_id: session_id.to_string(),
+            clock: Utc::now() - ChronoDuration::minutes(5),
+            lines: Vec::new(),
+        })
+    }
+
+    /// Append prompts to `history.jsonl`, one entry per prompt.
+    pub fn history(&self, project_path: &str, session_id: &str, prompts: &[&str]) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.config.claude_home.join("history.jsonl"))?;
+        for prompt in prompts {
+            let entry = json!({
+                "display": prompt,
+                "timestamp": Utc::now().timestamp_millis(),
+                "project": project_path,
+                "sessionId": session_id,
+            });
+            writeln!(file, "{}", entry)?;
+        }
+        Ok(())
+    }
+
+    /// Start the Claude Code adapter on this home.
+    pub async fn start(&self) -> Result<Pipeline> {
+        let storage = Storage::new(&self.config.db_path).await?;
+        storage.initialize().await?;
+        let event_bus = EventBus::new();
+        let mut adapter = ClaudeCodeAdapter::new(
+            &self.config,
+            event_bus.clone(),
+            storage.clone(),
+            ProcessWatcher::new(self.config.poll_interval),
+        );
+        adapter.start().await?;
+        // Give the watcher a moment to register before anything is written
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Ok(Pipeline {
+            storage,
+            event_bus,
+            adapter,
+        })
+    }
+}
+
+/// A transcript being written. Entries are buffered until [`Transcript::flush`],
+/// which appends them in one write, like Claude Code finishing a turn.
+pub struct Transcript {
+    path: PathBuf,
+    project_path: String,
+    session_id: String,
+    clock: DateTime<Utc>,
+    lines: Vec<Value>,
+}
+
+impl Transcript {
+    pub fn user(&mut self, text: &str) -> &mut Self {
+        let message = json!({ "role": "user", "content": text });
+        self.entry("user", message)
+    }
+
+    /// An assistant reply with its token usage.
+    pub fn assistant(&mut self, text: &str, input_tokens: i64, output_tokens: i64) -> &mut Self {
+        let message = json!({
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [{ "type": "text", "text": text }],
+            "usage": { "input_tokens": input_tokens, "output_tokens": output_tokens },
+        });
+        self.entry("assistant", message)
+    }
+
+    /// An assistant turn that calls a tool.
+    pub fn tool_use(&mut self, name: &str, input: Value) -> &mut Self {
+        let message = json!({
+            "role": "assistant",
+            "model": "claude-sonnet-4-5",
+            "content": [{ "type": "tool_use", "id": format!("toolu_{}", self.lines.len()), "name": name, "input": input }],
+            "usage": { "input_tokens": 0, "output_tokens": 0 },
+        });
+        self.entry("assistant", message)
+    }
+
+    /// The tool's output, sent back as a user turn.
+    pub fn tool_result(&mut self, content: &str) -> &mut Self {
+        let message = json!({
+            "role": "user",
+            "content": [{ "type": "tool_result", "tool_use_id": format!("toolu_{}", self.lines.len()), "content": content }],
+        });
+        self.entry("user", message)
+    }
+
+    /// The boundary Claude Code writes after compacting the context.
+    pub fn compact_boundary(&mut self, trigger: &str, pre_tokens: i64) -> &mut Self {
+        self.clock += ChronoDuration::seconds(1);
+        self.lines.push(json!({
+            "type": "system",
+            "subtype": "compact_boundary",
+            "cwd": self.project_path,
+            "sessionId": self.session_id,
+            "timestamp": self.clock.to_rfc3339(),
+            "compactMetadata": { "trigger": trigger, "preTokens": pre_tokens },
+        }));
+        self
+    }
+
+    /// Append the buffered entries to the file.
+    pub fn flush(&mut self) -> Result<()> {
+        let mut content = String::new();
+        for line in self.lines.drain(..) {
+            content.push_str(&line.to_string());
+            content.push('\n');
+        }
+        let mut file = std::fs::OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    fn entry(&mut self, entry_type: &str, message: Value) -> &mut Self {
+        self.clock += ChronoDuration::seconds(1);
+        self.lines.push(json!({
+            "type": entry_type,
+            "cwd": self.project_path,
+            "sessionId": self.session_id,
+            "timestamp": self.clock.to_rfc3339(),
+            "message": message,
+        }));
+        self
+    }
+}
+
+/// The adapter running on a [`FakeClaude`].
+pub struct Pipeline {
+    pub storage: Storage,
+    pub event_bus: EventBus,
+    adapter: ClaudeCodeAdapter,
+}
+
+impl Pipeline {
+    /// The project's active session once it has at least `count` events,
+    /// with its events oldest first.
+    pub async fn wait_for_events(&self, project_path: &str, count: usize) -> Result<(Session, Vec<SessionEvent>)> {
+        let deadline = tokio::time::Instant::now() + WAIT;
+        loop {
+            if let Some(session) = self.storage.get_active_session_for_project(project_path).await? {
+                let mut events = self.storage.get_session_events(&session.id, 10_000).await?;
+                if events.len() >= count {
+                    // Let any duplicate processing of the same write land first
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    let session = self.storage.get_session(&session.id).await?.unwrap_or(session);
+                    events = self.storage.get_session_events(&session.id, 10_000).await?;
+                    events.reverse();
+                    return Ok((session, events));
+                }
+            }
+            if tokio::time::Instant::now() > deadline {
+                bail!("timed out waiting for {} events in {}", count, project_path);
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Sessions stored for a project. Agents really running on the machine
+    /// are discovered too, so tests look sessions up by project.
+    pub async fn sessions(&self, project_path: &str) -> Result<Vec<Session>> {
+        let sessions = self.storage.get_all_sessions(10_000).await?;
+        Ok(sessions.into_iter().filter(|s| s.project_path == project_path).collect())
+    }
+
+    pub async fn stop(mut self) -> Result<()> {
+        self.adapter.stop().await
+    }
+}
+
+/// Keys of Claude Code transcripts, history and hook payloads.
+const KEYS: &[&str] = &[
+    "type", "subtype", "cwd", "project", "sessionId", "session_id", "timestamp", "message", "role",
+    "model", "content", "usage", "input_tokens", "output_tokens", "cache_read_input_tokens",
+    "cache_creation_input_tokens", "text", "thinking", "name", "input", "display", "compactMetadata",
+    "trigger", "preTokens", "isCompactSummary", "tool_name", "tool_input", "command", "file_path",
+    "toolUseResult", "durationMs", "exitCode",
+];
+
+/// An arbitrary JSON object, mostly with the keys agent logs use, so
+/// generated entries reach the parsing code instead of missing every field.
+pub fn json_entry() -> impl Strategy<Value = Value> {
+    let key = prop_oneof![
+        4 => prop::sample::select(KEYS).prop_map(String::from),
+        1 => "[a-zA-Z_]{1,12}",
+    ];
+    // Floats are left out: they don't survive a round trip through text exactly
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        (0i64..2_000_000).prop_map(Value::from),
+        ".{0,24}".prop_map(Value::from),
+        "(user|assistant|system|text|tool_use|tool_result|thinking|compact_boundary|auto|/work/[a-z]{1,4})"
+            .prop_map(Value::from),
+        Just(Value::from(Utc::now().to_rfc3339())),
+    ];
+    let value = leaf.prop_recursive(4, 64, 8, move |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::vec((key.clone(), inner), 0..8)
+                .prop_map(|fields| Value::Object(fields.into_iter().collect())),
+        ]
+    });
+    prop::collection::vec((prop::sample::select(KEYS).prop_map(String::from), value), 0..10)
+        .prop_map(|fields| Value::Object(fields.into_iter().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventType, SessionStatus};
+
+    #[tokio::test]
+    async fn test_transcript_to_sessions_and_costs() {
+        let claude = FakeClaude::new().unwrap();
+        let mut transcript = claude.transcript("/work/api", "5f1c2d3e").unwrap();
+        let pipeline = claude.start().await.unwrap();
+        let mut published = pipeline.event_bus.subscribe();
+
+        transcript
+            .user("Add a health check endpoint")
+            .tool_use("Read", json!({ "file_path": "/work/api/src/main.rs" }))
+            .tool_result("fn main() {}")
+            .assistant("Added GET /health", 1_000_000, 100_000)
+            .flush()
+            .unwrap();
+
+        let (session, events) = pipeline.wait_for_events("/work/api", 4).await.unwrap();
+        let types: Vec<EventType> = events.iter().map(|e| e.event_type).collect();
+        assert_eq!(
+            types,
+            [EventType::PromptReceived, EventType::ResponseGenerated, EventType::PromptReceived, EventType::ResponseGenerated]
+        );
+        assert_eq!(events[1].tool_name.as_deref(), Some("Read"));
+        assert_eq!(events[1].tool_input, Some(json!({ "file_path": "/work/api/src/main.rs" })));
+        assert_eq!(events[2].tool_output.as_deref(), Some("fn main() {}"));
+        assert_eq!(published.recv().await.unwrap().id, events[0].id);
+        assert_eq!(session.status, SessionStatus::Active);
+        assert_eq!(session.model_id.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(session.tool_call_count, 1);
+        assert_eq!((session.tokens_input, session.tokens_output), (1_000_000, 100_000));
+        // $3 per million input tokens, $15 per million output tokens
+        assert!((session.estimated_cost - 4.5).abs() < 1e-9);
+
+        // A later turn is added to the same session
+        transcript.compact_boundary("auto", 150_000).user("Now add tests").flush().unwrap();
+        let (session, events) = pipeline.wait_for_events("/work/api", 6).await.unwrap();
+        assert_eq!(events[4].event_type, EventType::Compaction);
+        assert_eq!(events[4].content.as_deref(), Some("Context compacted (auto, 150000 tokens before)"));
+        assert_eq!(session.message_count, 6);
+
+        pipeline.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_discovery() {
+        let claude = FakeClaude::new().unwrap();
+        claude.history("/work/web", "a1b2", &["fix the navbar", "run the tests"]).unwrap();
+        let pipeline = claude.start().await.unwrap();
+
+        let sessions = pipeline.sessions("/work/web").await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].external_id, "a1b2");
+        assert_eq!(sessions[0].message_count, 2);
+        // Prompts in the last half hour mean the session is still going
+        assert_eq!(sessions[0].status, SessionStatus::Active);
+
+        pipeline.stop().await.unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+//! Colors of CLI output.
+//!
+//! The cosmic palette is written as [`Style`]s, which print their escape code
+//! for the active color profile and nothing when color is off, so `println!`
+//! calls use them like plain strings. The profile is picked once at startup:
+//! `--no-color`, `NO_COLOR` and `CLICOLOR=0` turn color off, as does output
+//! that isn't a terminal unless `CLICOLOR_FORCE` is set. Terminals limited to
+//! 16 colors (`TERM=linux`, `*-16color`) get the nearest basic colors.
+
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// How much color the terminal gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorProfile {
+    /// Plain text
+    None,
+    /// The 16 basic ANSI colors
+    Basic,
+    /// The 256-color palette
+    Extended,
+}
+
+static PROFILE: OnceLock<ColorProfile> = OnceLock::new();
+
+/// Pick the profile for this run. Later calls have no effect.
+pub fn init(no_color: bool) -> ColorProfile {
+    let detected = detect(no_color, std::io::stdout().is_terminal(), |name| std::env::var(name).ok());
+    *PROFILE.get_or_init(|| detected)
+}
+
+/// The active profile; 256 colors until [`init`] runs.
+pub fn profile() -> ColorProfile {
+    PROFILE.get().copied().unwrap_or(ColorProfile::Extended)
+}
+
+/// Whether output is colored at all.
+pub fn enabled() -> bool {
+    profile() != ColorProfile::None
+}
+
+fn detect(no_color: bool, is_terminal: bool, env: impl Fn(&str) -> Option<String>) -> ColorProfile {
+    let var = |name: &str| env(name).filter(|v| !v.is_empty());
+    if no_color || var("NO_COLOR").is_some() {
+        return ColorProfile::None;
+    }
+    let forced = var("CLICOLOR_FORCE").is_some_and(|v| v != "0");
+    let term = var("TERM").unwrap_or_default();
+    if !forced && (var("CLICOLOR").as_deref() == Some("0") || !is_terminal || term == "dumb") {
+        return ColorProfile::None;
+    }
+    if term == "linux" || term.ends_with("-16color") {
+        ColorProfile::Basic
+    } else {
+        ColorProfile::Extended
+    }
+}
+
+/// An ANSI style with a 256-color code and its closest basic equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    extended: &'static str,
+    basic: &'static str,
+}
+
+impl Style {
+    pub const fn new(extended: &'static str, basic: &'static str) -> Self {
+        Self { extended, basic }
+    }
+
+    /// The escape code under `profile`, empty for plain text.
+    pub fn code(self, profile: ColorProfile) -> &'static str {
+        match profile {
+            ColorProfile::None => "",
+            ColorProfile::Basic => self.basic,
+            ColorProfile::Extended => self.extended,
+        }
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code(profile()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_profile() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+        };
+
+        assert_eq!(detect(false, true, env(&[("TERM", "xterm-256color")])), ColorProfile::Extended);
+        assert_eq!(detect(true, true, env(&[])), ColorProfile::None);
+        assert_eq!(detect(false, true, env(&[("NO_COLOR", "1")])), ColorProfile::None);
+        // An empty NO_COLOR doesn't count
+        assert_eq!(detect(false, true, env(&[("NO_COLOR", "")])), ColorProfile::Extended);
+        assert_eq!(detect(false, true, env(&[("CLICOLOR", "0")])), ColorProfile::None);
+        assert_eq!(detect(false, true, env(&[("TERM", "linux")])), ColorProfile::Basic);
+
+        // Piped output is plain unless forced, but NO_COLOR still wins
+        assert_eq!(detect(false, false, env(&[])), ColorProfile::None);
+        assert_eq!(detect(false, false, env(&[("CLICOLOR_FORCE", "1")])), ColorProfile::Extended);
+        assert_eq!(
+            detect(false, false, env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")])),
+            ColorProfile::None
+        );
+
+        let amber = Style::new("\x1b[38;5;214m", "\x1b[33m");
+        assert_eq!(amber.code(ColorProfile::Basic), "\x1b[33m");
+        assert_eq!(amber.code(ColorProfile::None), "");
+    }
+}
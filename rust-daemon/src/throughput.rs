@@ -0,0 +1,253 @@
+//! Streaming throughput: output tokens per second of each response.
+//!
+//! A response's duration is the time from the message that started it (a
+//! prompt, or the tool results the agent sent back) to the last entry of the
+//! response; Claude Code writes one entry per content block, all carrying the
+//! message's usage. The duration includes the time to the first token, so
+//! short responses are skipped: their rate is mostly latency. Throughput
+//! falling for one model across days is an early sign the provider's API is
+//! degraded.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Most events of one type considered for a report.
+const MAX_ROWS: usize = 20_000;
+
+/// Responses with fewer output tokens are not measured.
+const MIN_TOKENS: i64 = 20;
+
+/// Longest plausible response; longer gaps mean the agent was idle.
+const MAX_RESPONSE_MS: i64 = 10 * 60 * 1000;
+
+/// Throughput of one response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResponseThroughput {
+    pub session_id: String,
+    /// None when neither the response nor its session names one
+    pub model: Option<String>,
+    /// When the response ended
+    pub timestamp: DateTime<Utc>,
+    pub tokens_output: i64,
+    pub duration_ms: i64,
+    pub tokens_per_sec: f64,
+}
+
+/// Median and 95th percentile throughput of some responses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Throughput {
+    pub responses: usize,
+    pub p50_tokens_per_sec: f64,
+    pub p95_tokens_per_sec: f64,
+}
+
+/// Throughput of one model on one day (UTC).
+#[derive(Debug, Clone, Serialize)]
+pub struct ThroughputDay {
+    pub day: NaiveDate,
+    pub model: String,
+    #[serde(flatten)]
+    pub throughput: Throughput,
+}
+
+/// Throughput per model over a period, or of one session.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThroughputReport {
+    pub generated_at: DateTime<Utc>,
+    pub days: i64,
+    pub project: Option<String>,
+    pub session_id: Option<String>,
+    /// Over the whole period, by model
+    pub models: BTreeMap<String, Throughput>,
+    /// Oldest day first, then by model
+    pub daily: Vec<ThroughputDay>,
+}
+
+/// Throughput of one session, or of the last `days` (optionally within one
+/// project).
+pub async fn build_report(
+    storage: &Storage,
+    project: Option<&str>,
+    session_id: Option<&str>,
+    days: i64,
+) -> Result<ThroughputReport> {
+    let days = days.max(1);
+    let mut events = match session_id {
+        Some(id) => storage.get_session_events(id, MAX_ROWS).await?,
+        None => {
+            let mut events = Vec::new();
+            for event_type in [EventType::PromptReceived, EventType::ResponseGenerated] {
+                events.extend(storage.get_recent_events_of_type(event_type, days * 24, MAX_ROWS).await?);
+            }
+            events.retain(|e| project.is_none_or(|p| e.working_directory.as_deref() == Some(p)));
+            events
+        }
+    };
+    events.retain(|e| matches!(e.event_type, EventType::PromptReceived | EventType::ResponseGenerated));
+
+    // Responses recorded before their model was kept use the session's
+    let mut measured = responses(&events);
+    let mut session_models: HashMap<String, Option<String>> = HashMap::new();
+    for response in measured.iter_mut().filter(|r| r.model.is_none()) {
+        if !session_models.contains_key(&response.session_id) {
+            let model = storage.get_session(&response.session_id).await?.and_then(|s| s.model_id);
+            session_models.insert(response.session_id.clone(), model);
+        }
+        response.model = session_models[&response.session_id].clone();
+    }
+
+    let mut models: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for response in &measured {
+        models.entry(model_name(response)).or_default().push(response.tokens_per_sec);
+    }
+    Ok(ThroughputReport {
+        generated_at: Utc::now(),
+        days,
+        project: project.map(str::to_string),
+        session_id: session_id.map(str::to_string),
+        models: models.into_iter().map(|(model, rates)| (model, summarize(rates))).collect(),
+        daily: daily(&measured),
+    })
+}
+
+/// The measurable responses among `events`, oldest first per session.
+pub fn responses(events: &[SessionEvent]) -> Vec<ResponseThroughput> {
+    let mut sessions: BTreeMap<&str, Vec<&SessionEvent>> = BTreeMap::new();
+    for event in events {
+        sessions.entry(event.session_id.as_str()).or_default().push(event);
+    }
+    let mut measured = Vec::new();
+    for mut events in sessions.into_values() {
+        events.sort_by_key(|e| e.timestamp);
+        // The message the current response answers, and the response so far
+        let mut started: Option<&SessionEvent> = None;
+        let mut response: Vec<&SessionEvent> = Vec::new();
+        for event in events {
+            if event.event_type == EventType::ResponseGenerated {
+                if started.is_some() {
+                    response.push(event);
+                }
+                continue;
+            }
+            if let Some(start) = started {
+                measured.extend(measure(start, &response));
+            }
+            response.clear();
+            started = (event.event_type == EventType::PromptReceived).then_some(event);
+        }
+        if let Some(start) = started {
+            measured.extend(measure(start, &response));
+        }
+    }
+    measured
+}
+
+fn measure(start: &SessionEvent, response: &[&SessionEvent]) -> Option<ResponseThroughput> {
+    let last = response.last()?;
+    let tokens_output = response.iter().filter_map(|e| e.tokens_output).max()?;
+    let duration_ms = (last.timestamp - start.timestamp).num_milliseconds();
+    if tokens_output < MIN_TOKENS || duration_ms <= 0 || duration_ms > MAX_RESPONSE_MS {
+        return None;
+    }
+    let model = response
+        .iter()
+        .find_map(|e| e.raw_data.as_ref()?.get("model")?.as_str())
+        .map(str::to_string);
+    Some(ResponseThroughput {
+        session_id: last.session_id.clone(),
+        model,
+        timestamp: last.timestamp,
+        tokens_output,
+        duration_ms,
+        tokens_per_sec: tokens_output as f64 * 1000.0 / duration_ms as f64,
+    })
+}
+
+/// Median and 95th percentile of `responses`; None if there are none.
+pub fn summary(responses: &[ResponseThroughput]) -> Option<Throughput> {
+    (!responses.is_empty()).then(|| summarize(responses.iter().map(|r| r.tokens_per_sec).collect()))
+}
+
+/// Throughput per day and model, oldest day first.
+pub fn daily(responses: &[ResponseThroughput]) -> Vec<ThroughputDay> {
+    let mut days: BTreeMap<(NaiveDate, String), Vec<f64>> = BTreeMap::new();
+    for response in responses {
+        days.entry((response.timestamp.date_naive(), model_name(response)))
+            .or_default()
+            .push(response.tokens_per_sec);
+    }
+    days.into_iter()
+        .map(|((day, model), rates)| ThroughputDay { day, model, throughput: summarize(rates) })
+        .collect()
+}
+
+fn model_name(response: &ResponseThroughput) -> String {
+    response.model.clone().unwrap_or_else(|| "unknown".to_string())
+}
+
+fn summarize(mut rates: Vec<f64>) -> Throughput {
+    rates.sort_by(f64::total_cmp);
+    let percentile = |p: usize| rates[(rates.len() * p).div_ceil(100).saturating_sub(1)];
+    Throughput {
+        responses: rates.len(),
+        p50_tokens_per_sec: percentile(50),
+        p95_tokens_per_sec: percentile(95),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    fn event(session: &str, event_type: EventType, secs: i64, tokens: Option<i64>, model: Option<&str>) -> SessionEvent {
+        let mut event = SessionEvent::new(session, event_type, AgentType::ClaudeCode);
+        event.timestamp = DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        event.tokens_output = tokens;
+        event.raw_data = model.map(|m| serde_json::json!({ "model": m }));
+        event
+    }
+
+    #[test]
+    fn test_responses_and_percentiles() {
+        let opus = Some("claude-opus-4");
+        let events = vec![
+            // Thinking then text: one response of 400 tokens over 8s
+            event("a", EventType::PromptReceived, 0, None, None),
+            event("a", EventType::ResponseGenerated, 5, Some(400), opus),
+            event("a", EventType::ResponseGenerated, 8, Some(400), opus),
+            // Tool results start the next response: 300 tokens in 3s
+            event("a", EventType::PromptReceived, 10, None, None),
+            event("a", EventType::ResponseGenerated, 13, Some(300), opus),
+            // Too short to measure
+            event("a", EventType::PromptReceived, 20, None, None),
+            event("a", EventType::ResponseGenerated, 21, Some(5), opus),
+            // Idle for an hour: not a response time
+            event("a", EventType::PromptReceived, 30, None, None),
+            event("a", EventType::ResponseGenerated, 3630, Some(500), opus),
+            // No model recorded
+            event("b", EventType::PromptReceived, 0, None, None),
+            event("b", EventType::ResponseGenerated, 2, Some(100), None),
+        ];
+
+        let measured = responses(&events);
+        let rates: Vec<f64> = measured.iter().map(|r| r.tokens_per_sec).collect();
+        assert_eq!(rates, vec![50.0, 100.0, 50.0]);
+        assert_eq!(measured[0].duration_ms, 8000);
+        assert_eq!(measured[2].model, None);
+
+        let overall = summary(&measured).unwrap();
+        assert_eq!((overall.responses, overall.p50_tokens_per_sec, overall.p95_tokens_per_sec), (3, 50.0, 100.0));
+        assert_eq!(summary(&[]), None);
+
+        let daily = daily(&measured);
+        assert_eq!(daily.iter().map(|d| d.model.as_str()).collect::<Vec<_>>(), vec!["claude-opus-4", "unknown"]);
+        assert_eq!(daily[0].throughput.p50_tokens_per_sec, 50.0);
+        assert_eq!(daily[0].throughput.p95_tokens_per_sec, 100.0);
+    }
+}
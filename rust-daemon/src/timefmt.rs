@@ -0,0 +1,186 @@
+//! How timestamps are shown.
+//!
+//! Everything is stored in UTC; people read times in their own timezone, so
+//! the CLI, the TUI and the dashboard show them in the local timezone unless
+//! `timestamps.timezone` is `utc`, using the configured strftime formats. In
+//! relative mode recent times read like `3m ago` instead. The settings are
+//! picked once at startup like the color profile; relative mode can be
+//! toggled afterwards (the TUI's `a` key). Machine-readable output (JSON,
+//! exports, file names) keeps RFC 3339 UTC.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Older times are shown as dates even in relative mode.
+const RELATIVE_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeZoneSetting {
+    #[default]
+    Local,
+    Utc,
+}
+
+/// How timestamps are shown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampConfig {
+    /// Show times in the local timezone or in UTC
+    pub timezone: TimeZoneSetting,
+
+    /// Show recent times as "3m ago" instead of clock times
+    pub relative: bool,
+
+    /// strftime format of dates with times
+    pub format: String,
+
+    /// strftime format of times where the day is clear from context
+    pub time_format: String,
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        Self {
+            timezone: TimeZoneSetting::Local,
+            relative: false,
+            format: "%Y-%m-%d %H:%M".to_string(),
+            time_format: "%H:%M:%S".to_string(),
+        }
+    }
+}
+
+impl TimestampConfig {
+    /// The config with invalid format strings replaced by the defaults, so a
+    /// typo can't make every timestamp fail to print.
+    fn validated(&self) -> Self {
+        let defaults = Self::default();
+        let valid = |format: &str| !StrftimeItems::new(format).any(|item| matches!(item, Item::Error));
+        Self {
+            timezone: self.timezone,
+            relative: self.relative,
+            format: if valid(&self.format) { self.format.clone() } else { defaults.format },
+            time_format: if valid(&self.time_format) { self.time_format.clone() } else { defaults.time_format },
+        }
+    }
+
+    /// `time` in the configured timezone with `format`.
+    pub fn absolute(&self, time: DateTime<Utc>, format: &str) -> String {
+        match self.timezone {
+            TimeZoneSetting::Local => time.with_timezone(&Local).format(format).to_string(),
+            TimeZoneSetting::Utc => time.format(format).to_string(),
+        }
+    }
+
+    /// `time` with `format`, or relative to `now` if `relative` and recent.
+    fn show(&self, time: DateTime<Utc>, now: DateTime<Utc>, format: &str, relative: bool) -> String {
+        if relative && (now - time).num_days().abs() < RELATIVE_DAYS {
+            return ago(time, now);
+        }
+        self.absolute(time, format)
+    }
+}
+
+static CONFIG: OnceLock<TimestampConfig> = OnceLock::new();
+static RELATIVE: AtomicBool = AtomicBool::new(false);
+
+/// Use `config` for this run. Later calls have no effect.
+pub fn init(config: &TimestampConfig) {
+    let config = CONFIG.get_or_init(|| config.validated());
+    RELATIVE.store(config.relative, Ordering::Relaxed);
+}
+
+/// The active settings; the defaults until [`init`] runs.
+pub fn config() -> &'static TimestampConfig {
+    CONFIG.get_or_init(TimestampConfig::default)
+}
+
+pub fn is_relative() -> bool {
+    RELATIVE.load(Ordering::Relaxed)
+}
+
+/// Switch between relative and clock times, returning whether times are
+/// now relative.
+pub fn toggle_relative() -> bool {
+    !RELATIVE.fetch_xor(true, Ordering::Relaxed)
+}
+
+/// A date with a time, like `2026-03-14 09:30` or `3m ago`.
+pub fn datetime(time: DateTime<Utc>) -> String {
+    let config = config();
+    config.show(time, Utc::now(), &config.format, is_relative())
+}
+
+/// A time of day, like `09:30:12` or `3m ago`.
+pub fn time(time: DateTime<Utc>) -> String {
+    let config = config();
+    config.show(time, Utc::now(), &config.time_format, is_relative())
+}
+
+/// A time of day, always as a clock time.
+pub fn clock(time: DateTime<Utc>) -> String {
+    config().absolute(time, &config().time_format)
+}
+
+/// The day of `time`, like `2026-03-14`.
+pub fn date(time: DateTime<Utc>) -> String {
+    config().absolute(time, "%Y-%m-%d")
+}
+
+/// `time` in the configured timezone with any `format`.
+pub fn absolute(time: DateTime<Utc>, format: &str) -> String {
+    config().absolute(time, format)
+}
+
+/// How long before (or after) `now` `time` is, like `3m ago` or `in 2h`.
+pub fn ago(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let delta = now - time;
+    let seconds = delta.num_seconds().abs();
+    if seconds < 10 {
+        return "just now".to_string();
+    }
+    let amount = if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m", seconds / 60)
+    } else if seconds < 86_400 {
+        format!("{}h", seconds / 3600)
+    } else {
+        format!("{}d", seconds / 86_400)
+    };
+    if delta < Duration::zero() {
+        format!("in {}", amount)
+    } else {
+        format!("{} ago", amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_and_absolute_times() {
+        let now = DateTime::parse_from_rfc3339("2026-03-14T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(ago(now - Duration::seconds(4), now), "just now");
+        assert_eq!(ago(now - Duration::seconds(42), now), "42s ago");
+        assert_eq!(ago(now - Duration::minutes(3), now), "3m ago");
+        assert_eq!(ago(now - Duration::minutes(150), now), "2h ago");
+        assert_eq!(ago(now - Duration::days(3), now), "3d ago");
+        assert_eq!(ago(now + Duration::minutes(5), now), "in 5m");
+
+        let utc = TimestampConfig { timezone: TimeZoneSetting::Utc, ..TimestampConfig::default() };
+        let time = now - Duration::minutes(3);
+        assert_eq!(utc.show(time, now, &utc.format, false), "2026-03-14 11:57");
+        assert_eq!(utc.show(time, now, &utc.time_format, true), "3m ago");
+        // Old times stay dates in relative mode
+        assert_eq!(utc.show(now - Duration::days(45), now, "%b %-d", true), "Jan 28");
+
+        let broken = TimestampConfig { format: "%Y-%Q".to_string(), ..utc.clone() }.validated();
+        assert_eq!(broken.format, TimestampConfig::default().format);
+        assert_eq!(broken.time_format, "%H:%M:%S");
+    }
+}
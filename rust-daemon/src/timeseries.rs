@@ -0,0 +1,126 @@
+//! Hourly time series for charts.
+//!
+//! Events are rolled up per session and hour by the storage backend; this
+//! module turns those rollups into one point per hour with tokens, events per
+//! agent and cost. Sessions only carry a total cost, so each hour gets the
+//! session's cost in proportion to the tokens it logged then (or its events,
+//! for agents that report no tokens).
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{EventRollup, Session};
+use crate::storage::Storage;
+
+/// Most sessions looked up to price the rollups.
+const MAX_SESSIONS: usize = 10_000;
+
+/// Usage in one hour.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlyUsage {
+    /// Start of the hour (UTC)
+    pub hour: DateTime<Utc>,
+    pub cost: f64,
+    pub tokens: i64,
+    /// Events per agent type
+    pub events: BTreeMap<String, i64>,
+}
+
+/// One point per hour for the last `hours`, oldest first.
+pub async fn hourly_usage(storage: &Storage, hours: i64) -> Result<Vec<HourlyUsage>> {
+    let hours = hours.max(1);
+    let rollups = storage.get_event_rollups(hours).await?;
+    let sessions = storage.get_recent_sessions(hours, MAX_SESSIONS).await?;
+    Ok(series(&rollups, &sessions, hours, Utc::now()))
+}
+
+/// Fill `hours` hourly points ending with the hour containing `now`.
+pub fn series(rollups: &[EventRollup], sessions: &[Session], hours: i64, now: DateTime<Utc>) -> Vec<HourlyUsage> {
+    let current = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+    let first = current - Duration::hours(hours - 1);
+    let mut points: Vec<HourlyUsage> = (0..hours)
+        .map(|i| HourlyUsage { hour: first + Duration::hours(i), ..HourlyUsage::default() })
+        .collect();
+
+    // What each session logged in the window, to split its cost by
+    let mut logged: HashMap<&str, (i64, i64)> = HashMap::new();
+    for rollup in rollups {
+        let (tokens, events) = logged.entry(rollup.session_id.as_str()).or_default();
+        *tokens += rollup.tokens;
+        *events += rollup.events;
+    }
+    let sessions: HashMap<&str, &Session> = sessions.iter().map(|s| (s.id.as_str(), s)).collect();
+
+    for rollup in rollups {
+        let index = (rollup.hour - first).num_hours();
+        let Some(point) = usize::try_from(index).ok().and_then(|i| points.get_mut(i)) else {
+            continue;
+        };
+        point.tokens += rollup.tokens;
+        *point.events.entry(rollup.agent_type.to_string()).or_insert(0) += rollup.events;
+
+        let Some(session) = sessions.get(rollup.session_id.as_str()) else {
+            continue;
+        };
+        let (window_tokens, window_events) = logged[rollup.session_id.as_str()];
+        // Session totals cover hours before the window too
+        let total_tokens = (session.tokens_input + session.tokens_output).max(window_tokens);
+        let share = if total_tokens > 0 {
+            rollup.tokens as f64 / total_tokens as f64
+        } else {
+            rollup.events as f64 / window_events.max(1) as f64
+        };
+        point.cost += session.estimated_cost * share;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::TimeZone;
+
+    fn rollup(hour: DateTime<Utc>, session: &Session, events: i64, tokens: i64) -> EventRollup {
+        EventRollup {
+            hour,
+            session_id: session.id.clone(),
+            agent_type: session.agent_type,
+            events,
+            tokens,
+        }
+    }
+
+    #[test]
+    fn test_series_splits_cost_by_tokens() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 14, 20, 0).unwrap();
+        let hour = |h: u32| Utc.with_ymd_and_hms(2026, 3, 2, h, 0, 0).unwrap();
+
+        // Half of this session's tokens predate the window
+        let mut claude = Session::new(AgentType::ClaudeCode, "/work/api", "one");
+        claude.tokens_input = 3_000;
+        claude.tokens_output = 1_000;
+        claude.estimated_cost = 8.0;
+        // No tokens at all: cost follows events
+        let mut cursor = Session::new(AgentType::Cursor, "/work/web", "two");
+        cursor.estimated_cost = 1.0;
+
+        let rollups = vec![
+            rollup(hour(12), &claude, 10, 1_500),
+            rollup(hour(13), &claude, 4, 500),
+            rollup(hour(13), &cursor, 3, 0),
+            rollup(hour(14), &cursor, 1, 0),
+        ];
+        let points = series(&rollups, &[claude, cursor], 3, now);
+
+        assert_eq!(points.iter().map(|p| p.hour).collect::<Vec<_>>(), vec![hour(12), hour(13), hour(14)]);
+        assert_eq!(points[0].tokens, 1_500);
+        assert!((points[0].cost - 3.0).abs() < 1e-9);
+        assert!((points[1].cost - 1.75).abs() < 1e-9);
+        assert!((points[2].cost - 0.25).abs() < 1e-9);
+        assert_eq!(points[1].events.len(), 2);
+        assert_eq!(points[2].events.get("cursor"), Some(&1));
+    }
+}
@@ -0,0 +1,316 @@
+//! Time entries in Toggl Track or Clockify for finished sessions.
+//!
+//! Every `interval_secs` the daemon pushes sessions that completed (or
+//! crashed) since the last pass as time entries, from start to end. The
+//! first rule in `projects` whose pattern matches the session's project path
+//! picks the tracker project, billability and tags; sessions no rule matches
+//! go to `default_project`. The end time of the last pushed session is kept
+//! in the data directory, so entries are pushed once and, when the
+//! integration is first enabled, only sessions finishing after that are.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::models::{Session, SessionStatus};
+use crate::storage::Storage;
+
+/// Time tracking service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeTracker {
+    Toggl,
+    Clockify,
+}
+
+/// Tracker project for sessions whose project path matches `pattern`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMapping {
+    /// Regex matched against the session's project path
+    pub pattern: String,
+
+    /// Tracker project ID
+    pub project_id: String,
+
+    #[serde(default = "default_billable")]
+    pub billable: bool,
+
+    /// Toggl tag names (Clockify ignores these)
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_billable() -> bool {
+    true
+}
+
+/// Time tracking settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeTrackingConfig {
+    /// Whether finished sessions are pushed as time entries
+    pub enabled: bool,
+
+    /// `toggl` or `clockify`
+    pub provider: TimeTracker,
+
+    /// Environment variable holding the API token
+    pub api_token_env: String,
+
+    /// Workspace the entries go to
+    pub workspace_id: String,
+
+    /// Rules mapping project paths to tracker projects; the first match wins
+    pub projects: Vec<ProjectMapping>,
+
+    /// Tracker project for sessions no rule matches (none when unset)
+    pub default_project: Option<String>,
+
+    /// Entry description; `{project}`, `{agent}` and `{summary}` are filled in
+    pub description: String,
+
+    /// Sessions shorter than this are not pushed
+    pub min_seconds: i64,
+
+    /// Seconds between passes
+    pub interval_secs: u64,
+}
+
+impl Default for TimeTrackingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: TimeTracker::Toggl,
+            api_token_env: "AGENT_MONITOR_TIMETRACK_TOKEN".to_string(),
+            workspace_id: String::new(),
+            projects: Vec::new(),
+            default_project: None,
+            description: "AI pair programming: {project}".to_string(),
+            min_seconds: 60,
+            interval_secs: 300,
+        }
+    }
+}
+
+/// Sessions looked at per pass.
+const SCAN_LIMIT: usize = 5000;
+
+/// Project, billability and tags chosen for a session.
+struct Target<'a> {
+    project_id: Option<&'a str>,
+    billable: bool,
+    tags: &'a [String],
+}
+
+/// Pushes finished sessions on schedule.
+pub struct TimeTrackingSync {
+    config: TimeTrackingConfig,
+    rules: Vec<(Regex, ProjectMapping)>,
+    storage: Storage,
+    client: reqwest::Client,
+    state_path: PathBuf,
+}
+
+impl TimeTrackingSync {
+    pub fn new(config: TimeTrackingConfig, storage: Storage, data_dir: PathBuf) -> Result<Self> {
+        if config.workspace_id.is_empty() {
+            bail!("time_tracking needs a workspace_id");
+        }
+        let rules = config
+            .projects
+            .iter()
+            .map(|m| {
+                let regex = Regex::new(&m.pattern).with_context(|| format!("Invalid project pattern {:?}", m.pattern))?;
+                Ok((regex, m.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            config,
+            rules,
+            storage,
+            client: reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?,
+            state_path: data_dir.join("timetrack_last_pushed"),
+        })
+    }
+
+    fn target(&self, session: &Session) -> Target<'_> {
+        match self.rules.iter().find(|(regex, _)| regex.is_match(&session.project_path)) {
+            Some((_, mapping)) => Target {
+                project_id: Some(&mapping.project_id),
+                billable: mapping.billable,
+                tags: &mapping.tags,
+            },
+            None => Target {
+                project_id: self.config.default_project.as_deref(),
+                billable: false,
+                tags: &[],
+            },
+        }
+    }
+
+    fn description(&self, session: &Session) -> String {
+        let project = session.project_path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or(&session.project_path);
+        let summary = session.summary.as_deref().and_then(|s| s.lines().next()).unwrap_or_default();
+        self.config
+            .description
+            .replace("{project}", project)
+            .replace("{agent}", &session.agent_type.to_string())
+            .replace("{summary}", summary)
+            .trim()
+            .to_string()
+    }
+
+    /// URL and JSON body of the time entry for `session`.
+    fn entry(&self, session: &Session, end: DateTime<Utc>) -> Result<(String, Value)> {
+        let target = self.target(session);
+        let description = self.description(session);
+        let workspace = &self.config.workspace_id;
+        match self.config.provider {
+            TimeTracker::Toggl => {
+                let workspace_id: i64 = workspace.parse().map_err(|_| anyhow!("Toggl workspace_id must be a number"))?;
+                let project_id = target
+                    .project_id
+                    .map(|id| id.parse::<i64>().map_err(|_| anyhow!("Toggl project IDs must be numbers, not {:?}", id)))
+                    .transpose()?;
+                let body = json!({
+                    "created_with": "agent-monitor",
+                    "description": description,
+                    "workspace_id": workspace_id,
+                    "project_id": project_id,
+                    "start": session.started_at.to_rfc3339(),
+                    "stop": end.to_rfc3339(),
+                    "duration": (end - session.started_at).num_seconds(),
+                    "billable": target.billable,
+                    "tags": target.tags,
+                });
+                Ok((format!("https://api.track.toggl.com/api/v9/workspaces/{}/time_entries", workspace), body))
+            }
+            TimeTracker::Clockify => {
+                let body = json!({
+                    "description": description,
+                    "projectId": target.project_id,
+                    "start": session.started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    "end": end.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    "billable": target.billable,
+                });
+                Ok((format!("https://api.clockify.me/api/v1/workspaces/{}/time-entries", workspace), body))
+            }
+        }
+    }
+
+    async fn push(&self, session: &Session, end: DateTime<Utc>) -> Result<()> {
+        let token = std::env::var(&self.config.api_token_env)
+            .map_err(|_| anyhow!("{} is not set", self.config.api_token_env))?;
+        let (url, body) = self.entry(session, end)?;
+        let request = match self.config.provider {
+            TimeTracker::Toggl => self.client.post(&url).basic_auth(token, Some("api_token")),
+            TimeTracker::Clockify => self.client.post(&url).header("X-Api-Key", token),
+        };
+        let response = request.json(&body).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            bail!("{} returned {}: {}", url, status, detail.trim());
+        }
+        Ok(())
+    }
+
+    fn last_pushed(&self) -> Option<DateTime<Utc>> {
+        let content = std::fs::read_to_string(&self.state_path).ok()?;
+        DateTime::parse_from_rfc3339(content.trim()).ok().map(|t| t.with_timezone(&Utc))
+    }
+
+    fn record_pushed(&self, at: DateTime<Utc>) {
+        if let Err(e) = std::fs::write(&self.state_path, at.to_rfc3339()) {
+            warn!("Failed to record time tracking progress in {}: {}", self.state_path.display(), e);
+        }
+    }
+
+    /// Push sessions that finished after `since`, oldest first, recording
+    /// each as it goes and stopping at the first failure. Returns how many
+    /// were pushed.
+    pub async fn push_finished(&self, since: DateTime<Utc>) -> Result<usize> {
+        let mut finished: Vec<(DateTime<Utc>, Session)> = self
+            .storage
+            .get_all_sessions(SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|s| matches!(s.status, SessionStatus::Completed | SessionStatus::Crashed))
+            .map(|s| (s.ended_at.unwrap_or(s.last_activity_at), s))
+            .filter(|(end, _)| *end > since)
+            .collect();
+        finished.sort_by_key(|(end, _)| *end);
+
+        let mut pushed = 0;
+        for (end, session) in finished {
+            if (end - session.started_at).num_seconds() >= self.config.min_seconds {
+                self.push(&session, end)
+                    .await
+                    .with_context(|| format!("Could not push session {}", session.id))?;
+                pushed += 1;
+            }
+            self.record_pushed(end);
+        }
+        Ok(pushed)
+    }
+
+    pub async fn run(self) {
+        if self.last_pushed().is_none() {
+            self.record_pushed(Utc::now());
+        }
+        let mut ticker = tokio::time::interval(Duration::from_secs(self.config.interval_secs.max(30)));
+        loop {
+            ticker.tick().await;
+            let since = self.last_pushed().unwrap_or_else(Utc::now);
+            match self.push_finished(since).await {
+                Ok(0) => {}
+                Ok(count) => info!("Pushed {} sessions to {:?}", count, self.config.provider),
+                // Retried at the next pass, after the last session pushed
+                Err(e) => warn!("Time tracking sync failed: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+
+    #[test]
+    fn test_first_matching_rule_picks_the_project() {
+        let mapping = |pattern: &str, id: &str| ProjectMapping {
+            pattern: pattern.to_string(),
+            project_id: id.to_string(),
+            billable: true,
+            tags: vec!["ai".to_string()],
+        };
+        let config = TimeTrackingConfig {
+            workspace_id: "42".to_string(),
+            projects: vec![mapping("/clients/acme/", "100"), mapping("/clients/", "200")],
+            default_project: Some("300".to_string()),
+            description: "AI: {project} ({agent})".to_string(),
+            ..TimeTrackingConfig::default()
+        };
+        let sync = TimeTrackingSync::new(config, Storage::in_memory(), PathBuf::from("/tmp")).unwrap();
+
+        let session = Session::new(AgentType::ClaudeCode, "/clients/acme/web", "one");
+        let end = session.started_at + chrono::Duration::minutes(45);
+        let (url, body) = sync.entry(&session, end).unwrap();
+        assert!(url.ends_with("/workspaces/42/time_entries"));
+        assert_eq!(body["project_id"], 100);
+        assert_eq!(body["duration"], 2700);
+        assert_eq!(body["description"], "AI: web (claude_code)");
+        assert_eq!(body["tags"][0], "ai");
+
+        let other = Session::new(AgentType::ClaudeCode, "/home/me/notes", "two");
+        let (_, body) = sync.entry(&other, end).unwrap();
+        assert_eq!(body["project_id"], 300);
+        assert_eq!(body["billable"], false);
+    }
+}
@@ -0,0 +1,119 @@
+//! The upstream transcript files a session was read from.
+//!
+//! Claude Code writes each conversation to
+//! `~/.claude/projects/<project>/<session id>.jsonl`. The adapter stores
+//! every such file a session's entries came from as one of its sources, and
+//! `/api/v1/sessions/:id/raw` streams them back unmodified, for users who
+//! want to run their own analysis. Sessions recorded before sources were
+//! stored fall back to the file named after their Claude session ID. Only
+//! `.jsonl` files under the projects directory are ever served.
+
+use axum::body::Bytes;
+use futures_util::stream::{self, Stream};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::models::{Session, SessionSource};
+
+/// Claude Code's directory name for a project: every character other than
+/// a letter or digit becomes `-`.
+pub fn project_dir_name(project_path: &str) -> String {
+    project_path.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect()
+}
+
+/// Whether `path` is a transcript under `projects_dir`.
+fn is_transcript(path: &Path, projects_dir: &Path) -> bool {
+    let (Ok(path), Ok(projects_dir)) = (path.canonicalize(), projects_dir.canonicalize()) else {
+        return false;
+    };
+    path.starts_with(projects_dir) && path.extension().is_some_and(|e| e == "jsonl") && path.is_file()
+}
+
+/// Transcript files of `session`, given its stored `sources`, oldest first.
+pub fn files(session: &Session, sources: &[SessionSource], projects_dir: &Path) -> Vec<PathBuf> {
+    let recorded: Vec<PathBuf> = sources
+        .iter()
+        .map(|source| PathBuf::from(&source.path))
+        .filter(|path| is_transcript(path, projects_dir))
+        .collect();
+    if !recorded.is_empty() {
+        return recorded;
+    }
+
+    let name = format!("{}.jsonl", session.external_id);
+    let expected = projects_dir.join(project_dir_name(&session.project_path)).join(&name);
+    if is_transcript(&expected, projects_dir) {
+        return vec![expected];
+    }
+    // The project directory may have been named by another version
+    std::fs::read_dir(projects_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|dir| dir.path().join(&name))
+        .filter(|path| is_transcript(path, projects_dir))
+        .take(1)
+        .collect()
+}
+
+/// The lines of `files` in order, each ending in a newline.
+pub fn stream(files: Vec<PathBuf>) -> impl Stream<Item = std::io::Result<Bytes>> {
+    let files = files.into_iter();
+    stream::try_unfold((files, None), |(mut files, mut lines)| async move {
+        loop {
+            let Some(current) = lines.as_mut() else {
+                let Some(path) = files.next() else {
+                    return Ok(None);
+                };
+                lines = Some(BufReader::new(tokio::fs::File::open(path).await?).lines());
+                continue;
+            };
+            match current.next_line().await? {
+                Some(line) => return Ok(Some((Bytes::from(line + "\n"), (files, lines)))),
+                None => lines = None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::Utc;
+    use futures_util::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_session_transcripts() {
+        let claude = tempfile::tempdir().unwrap();
+        let projects = claude.path().join("projects");
+        let project = projects.join("-work-my-api");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("abc.jsonl"), "{\"n\":1}\n{\"n\":2}").unwrap();
+        std::fs::write(project.join("def.jsonl"), "{\"n\":3}\n").unwrap();
+        let outside = claude.path().join("secrets.jsonl");
+        std::fs::write(&outside, "{}\n").unwrap();
+
+        // Found by name before anything was recorded
+        let session = Session::new(AgentType::ClaudeCode, "/work/my.api", "abc");
+        assert_eq!(files(&session, &[], &projects), vec![project.join("abc.jsonl")]);
+
+        let source = |path: &Path| SessionSource {
+            session_id: session.id.clone(),
+            path: path.to_string_lossy().into_owned(),
+            offset: 0,
+            entries: 1,
+            first_read_at: Utc::now(),
+            last_read_at: Utc::now(),
+        };
+        let sources = [source(&project.join("abc.jsonl")), source(&outside), source(&project.join("def.jsonl"))];
+        let found = files(&session, &sources, &projects);
+        assert_eq!(found, vec![project.join("abc.jsonl"), project.join("def.jsonl")]);
+
+        let chunks: Vec<Bytes> = stream(found).try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), b"{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n");
+
+        let missing = Session::new(AgentType::ClaudeCode, "/work/other", "zzz");
+        assert!(files(&missing, &[], &projects).is_empty());
+    }
+}
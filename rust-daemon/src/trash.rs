@@ -0,0 +1,195 @@
+//! Soft deletion: cleared sessions wait in the trash before they are gone.
+//!
+//! Clearing or pruning a session writes it, with its events and tags, to a
+//! bundle in `<data dir>/trash` (the archive format, encrypted with the
+//! database key when encryption is on), records it in `trashed_sessions` and
+//! deletes it, so it drops out of every query at once but `agent-monitor
+//! trash restore` can load it back. The daemon empties sessions that have
+//! been in the trash for `grace_days`; `trash empty` does so right away. With
+//! `grace_days = 0` clears delete immediately.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::time::interval;
+use tracing::{info, warn};
+
+use crate::archive;
+use crate::config::Config;
+use crate::models::{Session, TrashedSession};
+use crate::objstore::Destination;
+use crate::storage::{ContentCipher, Storage};
+
+/// How often the daemon looks for expired sessions.
+const PURGE_INTERVAL_SECS: u64 = 3600;
+
+/// How long cleared sessions are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashConfig {
+    /// Days a cleared session can be restored; 0 deletes immediately
+    pub grace_days: i64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { grace_days: 30 }
+    }
+}
+
+/// Where cleared sessions go, and for how long.
+#[derive(Clone)]
+pub struct Trash {
+    dir: PathBuf,
+    grace: Duration,
+    cipher: Option<Arc<ContentCipher>>,
+}
+
+impl Trash {
+    pub fn new(dir: PathBuf, grace_days: i64, cipher: Option<ContentCipher>) -> Self {
+        Self { dir, grace: Duration::days(grace_days.max(0)), cipher: cipher.map(Arc::new) }
+    }
+
+    /// The trash in `config`'s data directory.
+    pub fn open(config: &Config) -> Result<Self> {
+        let cipher = if config.encryption.enabled {
+            Some(ContentCipher::load(config)?)
+        } else {
+            None
+        };
+        Ok(Self::new(config.data_dir.join("trash"), config.trash.grace_days, cipher))
+    }
+
+    /// Whether cleared sessions are kept at all.
+    pub fn is_enabled(&self) -> bool {
+        self.grace > Duration::zero()
+    }
+
+    /// When `trashed` will be emptied from the trash.
+    pub fn expires_at(&self, trashed: &TrashedSession) -> DateTime<Utc> {
+        trashed.deleted_at + self.grace
+    }
+
+    /// Move `sessions` to the trash, or delete them if the trash is off.
+    /// `reason` is the operation removing them. A failed write stops the run.
+    pub async fn discard(&self, storage: &Storage, sessions: &[Session], reason: &str) -> Result<()> {
+        let destination = Destination::Local(self.dir.clone());
+        for session in sessions {
+            if self.is_enabled() {
+                let (location, _) = archive::write_bundle(storage, session, &destination, self.cipher.as_deref())
+                    .await
+                    .with_context(|| format!("Could not move session {} to the trash", session.id))?;
+                let trashed = TrashedSession {
+                    session: session.clone(),
+                    location,
+                    reason: reason.to_string(),
+                    deleted_at: Utc::now(),
+                };
+                storage.insert_trashed_session(&trashed).await?;
+            }
+            storage.delete_session(&session.id).await?;
+        }
+        Ok(())
+    }
+
+    /// Load a session back from the trash.
+    pub async fn restore(&self, storage: &Storage, session_id: &str) -> Result<Session> {
+        let trashed = storage
+            .list_trashed_sessions()
+            .await?
+            .into_iter()
+            .find(|t| t.session.id == session_id)
+            .ok_or_else(|| anyhow!("Session {} is not in the trash", session_id))?;
+        let session = archive::load_bundle(storage, &trashed.location, self.cipher.as_deref()).await?;
+        self.remove(storage, &trashed).await?;
+        Ok(session)
+    }
+
+    /// Delete sessions from the trash for good: all of them, or with
+    /// `expired_only` those past their grace period.
+    pub async fn empty(&self, storage: &Storage, expired_only: bool) -> Result<Vec<TrashedSession>> {
+        let now = Utc::now();
+        let mut emptied = Vec::new();
+        for trashed in storage.list_trashed_sessions().await? {
+            if expired_only && self.expires_at(&trashed) > now {
+                continue;
+            }
+            self.remove(storage, &trashed).await?;
+            emptied.push(trashed);
+        }
+        Ok(emptied)
+    }
+
+    async fn remove(&self, storage: &Storage, trashed: &TrashedSession) -> Result<()> {
+        match std::fs::remove_file(&trashed.location) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Could not delete {}", trashed.location));
+            }
+            _ => {}
+        }
+        storage.delete_trashed_session(&trashed.session.id).await?;
+        Ok(())
+    }
+
+    /// Empty expired sessions until the daemon stops.
+    pub async fn run(self, storage: Storage) {
+        let mut ticker = interval(std::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            match self.empty(&storage, true).await {
+                Ok(emptied) if !emptied.is_empty() => info!("Emptied {} expired sessions from the trash", emptied.len()),
+                Ok(_) => {}
+                Err(e) => warn!("Emptying the trash failed: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, EventType, SessionEvent};
+
+    #[tokio::test]
+    async fn test_discard_restore_and_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().to_path_buf(), 30, None);
+        let storage = Storage::in_memory();
+        let mut sessions = Vec::new();
+        for project in ["/work/api", "/work/web"] {
+            let session = Session::new(AgentType::ClaudeCode, project, project);
+            storage.upsert_session(&session).await.unwrap();
+            let mut event = SessionEvent::new(&session.id, EventType::PromptReceived, AgentType::ClaudeCode);
+            event.content = Some("fix the tests".to_string());
+            storage.insert_event(&event).await.unwrap();
+            sessions.push(session);
+        }
+
+        trash.discard(&storage, &sessions, "clear").await.unwrap();
+        assert!(storage.get_all_sessions(10).await.unwrap().is_empty());
+        let trashed = storage.list_trashed_sessions().await.unwrap();
+        assert_eq!(trashed.len(), 2);
+        assert_eq!(trashed[0].reason, "clear");
+        assert!(trash.expires_at(&trashed[0]) > Utc::now() + Duration::days(29));
+
+        let restored = trash.restore(&storage, &sessions[0].id).await.unwrap();
+        assert_eq!(restored.project_path, "/work/api");
+        assert_eq!(storage.get_session_events(&sessions[0].id, 10).await.unwrap().len(), 1);
+        assert!(trash.restore(&storage, &sessions[0].id).await.is_err());
+
+        // Nothing has expired yet
+        assert!(trash.empty(&storage, true).await.unwrap().is_empty());
+        let emptied = trash.empty(&storage, false).await.unwrap();
+        assert_eq!(emptied.len(), 1);
+        assert!(!std::path::Path::new(&emptied[0].location).exists());
+        assert!(storage.list_trashed_sessions().await.unwrap().is_empty());
+
+        // Without a grace period sessions are deleted outright
+        let off = Trash::new(dir.path().to_path_buf(), 0, None);
+        off.discard(&storage, &sessions[..1], "prune").await.unwrap();
+        assert!(storage.get_all_sessions(10).await.unwrap().is_empty());
+        assert!(storage.list_trashed_sessions().await.unwrap().is_empty());
+    }
+}
@@ -1,10 +1,23 @@
 //! Terminal User Interface for interactive agent monitoring.
 //! Retro terminal style - green/red on black like classic computers.
-
+//!
+//! Kiosk mode (`watch --kiosk`) is for a wallboard: no tabs or key hints,
+//! large numbers for active sessions, cost and errors today, and the views
+//! cycle on their own so nobody needs to touch the keyboard.
+//!
+//! The History tab lists the last week's sessions under a separator per day;
+//! days fold and unfold with Space so older ones stay out of the way.
+//!
+//! In the detail view `<` and `>` open the project's previous and next
+//! session, for following work that spans several sessions.
+
+use std::collections::HashSet;
 use std::io;
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use regex::Regex;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
@@ -16,12 +29,36 @@ use ratatui::{
     style::{Color, Modifier, Style},
     symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Gauge, List, ListItem, Paragraph, Row, Sparkline, Table, Tabs, Clear as ClearWidget},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Cell, Chart, Dataset, Gauge, GraphType, List, ListItem, Paragraph, Row,
+        Sparkline, Table, Tabs, Clear as ClearWidget,
+    },
     Frame, Terminal,
 };
 
-use crate::models::{EventType, Session, SessionEvent, SessionStatus};
+use crate::apierrors::{self, ApiErrorHour};
+use crate::config::Config;
+use crate::duplicates::{self, DuplicatePrompt, DuplicatesConfig};
+use crate::grouping::{self, GroupBy, SessionGroup};
+use crate::highlight;
+use crate::humanize;
+use crate::markdown;
+use crate::mcp::{self, McpServerUsage};
+use crate::models::{
+    context_window, normalize_tag, EventType, ResourceSample, Session, SessionEvent, SessionStatus, SessionTag,
+    SummaryMetrics,
+};
+use crate::refresh::RefreshConfig;
+use crate::related::{self, ProjectPosition};
+use crate::slash::{self, SlashCommandUsage};
+use crate::remote::RemoteClient;
+use crate::report::{self, ReportComparison, Trend};
 use crate::storage::Storage;
+use crate::subscribe::{Pushed, Subscription};
+use crate::throughput::{self, Throughput};
+use crate::timefmt;
+use crate::timeseries::{self, HourlyUsage};
+use crate::turns::{self, Turn};
 
 // Retro Terminal Color Palette - Classic Green on Black
 const TERM_GREEN: Color = Color::Rgb(0, 255, 65);        // Bright phosphor green
@@ -29,12 +66,234 @@ const TERM_GREEN_DIM: Color = Color::Rgb(0, 180, 45);    // Dimmer green
 const TERM_GREEN_DARK: Color = Color::Rgb(0, 100, 25);   // Dark green for backgrounds
 const TERM_RED: Color = Color::Rgb(255, 50, 50);         // Alert red
 const TERM_AMBER: Color = Color::Rgb(255, 176, 0);       // Amber for warnings
+const TERM_MAGENTA: Color = Color::Rgb(255, 110, 200);   // Magenta for compactions
 const TERM_BLACK: Color = Color::Rgb(0, 0, 0);           // Pure black background
 const TERM_DARK: Color = Color::Rgb(8, 8, 8);            // Slightly lighter black
 
+/// Seconds each kiosk view is shown before the next.
+const KIOSK_VIEW_SECS: u64 = 15;
+
+/// Kiosk views, in the order they cycle.
+const KIOSK_VIEWS: [&str; 3] = ["OVERVIEW", "SESSIONS", "METRICS"];
+
+/// Hours covered by the metrics charts.
+const CHART_HOURS: i64 = 24;
+
+/// How often the hourly chart data is reloaded.
+const CHART_REFRESH: Duration = Duration::from_secs(60);
+
+/// Least time between re-reads triggered by pushed events.
+const PUSH_MIN_GAP: Duration = Duration::from_millis(250);
+
+/// Hours of sessions listed in the History tab.
+const HISTORY_HOURS: i64 = 168;
+
+/// Most sessions listed in the History tab.
+const HISTORY_LIMIT: usize = 500;
+
+/// Index of the History tab.
+const HISTORY_TAB: usize = 3;
+
+/// Hours a Loop event keeps the "possible loop" badge on its session.
+const LOOP_BADGE_HOURS: i64 = 1;
+
+/// Most sessions searched for others of the same project.
+const RELATED_LIMIT: usize = 1000;
+
+/// Positions the detail view's timeline cursor can stop at.
+const TIMELINE_STEPS: usize = 100;
+
+/// Events of the selected session read for its MCP, slash command and
+/// throughput breakdown.
+const MCP_EVENTS: usize = 2000;
+
+/// Series colors for agents in the activity chart, in legend order.
+const AGENT_COLORS: [Color; 6] = [TERM_GREEN, TERM_AMBER, TERM_MAGENTA, TERM_RED, TERM_GREEN_DIM, Color::Rgb(80, 200, 255)];
+
+/// Where the TUI reads session data from.
+#[derive(Clone)]
+pub enum DataSource {
+    /// Direct access to the local SQLite database
+    Local(Storage),
+    /// REST API of a remote daemon
+    Remote(RemoteClient),
+    /// Read-only database snapshot, browsed without live refresh
+    Snapshot(Storage),
+}
+
+impl DataSource {
+    async fn get_active_sessions(&self, limit: usize) -> Result<Vec<Session>> {
+        match self {
+            DataSource::Local(storage) => storage.get_active_sessions(limit).await,
+            DataSource::Remote(client) => client.get_active_sessions(limit).await,
+            // Sessions in a snapshot have usually ended, so list everything
+            DataSource::Snapshot(storage) => storage.get_all_sessions(limit).await,
+        }
+    }
+
+    async fn get_recent_sessions(&self, hours: i64, limit: usize) -> Result<Vec<Session>> {
+        match self {
+            DataSource::Local(storage) => storage.get_recent_sessions(hours, limit).await,
+            // The daemon lists a week of sessions
+            DataSource::Remote(client) => client.get_recent_sessions(limit).await,
+            DataSource::Snapshot(storage) => storage.get_all_sessions(limit).await,
+        }
+    }
+
+    /// Sessions of `project`, as far as the source lists them.
+    async fn get_project_sessions(&self, project: &str) -> Result<Vec<Session>> {
+        let sessions = match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => storage.get_all_sessions(RELATED_LIMIT).await?,
+            DataSource::Remote(client) => client.get_recent_sessions(RELATED_LIMIT).await?,
+        };
+        Ok(sessions.into_iter().filter(|s| s.project_path == project).collect())
+    }
+
+    async fn get_summary_metrics(&self, hours: i64) -> Result<SummaryMetrics> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => storage.get_summary_metrics(hours).await,
+            DataSource::Remote(client) => client.get_summary_metrics(hours).await,
+        }
+    }
+
+    async fn get_hourly_usage(&self, hours: i64) -> Result<Vec<HourlyUsage>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => timeseries::hourly_usage(storage, hours).await,
+            DataSource::Remote(client) => client.get_hourly_usage(hours).await,
+        }
+    }
+
+    async fn get_api_errors(&self, hours: i64) -> Result<Vec<ApiErrorHour>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                let data_dir = Config::load_or_default().unwrap_or_default().data_dir;
+                let incidents = apierrors::load_incidents(&apierrors::incidents_path(&data_dir));
+                apierrors::hourly_api_errors(storage, hours, &incidents).await
+            }
+            DataSource::Remote(client) => client.get_api_errors(hours).await,
+        }
+    }
+
+    /// The last week against the week before.
+    async fn get_weekly_trend(&self) -> Result<Option<ReportComparison>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                let report = report::build_report(storage, 7, None, None).await?;
+                Ok(Some(report::compare(storage, &report, chrono::Duration::weeks(1), None, None).await?))
+            }
+            DataSource::Remote(client) => client.get_report_comparison(7, "previous").await,
+        }
+    }
+
+    async fn count_errors(&self, hours: i64) -> Result<usize> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => Ok(storage
+                .get_recent_events_of_type(EventType::Error, hours, 10_000)
+                .await?
+                .len()),
+            DataSource::Remote(client) => {
+                client.count_errors_since(chrono::Utc::now() - chrono::Duration::hours(hours)).await
+            }
+        }
+    }
+
+    /// Sessions flagged as possibly looping in the last `hours`.
+    async fn get_looping_sessions(&self, hours: i64) -> Result<HashSet<String>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => Ok(storage
+                .get_recent_events_of_type(EventType::Loop, hours, 1000)
+                .await?
+                .into_iter()
+                .map(|e| e.session_id)
+                .collect()),
+            DataSource::Remote(client) => {
+                client.sessions_with_events_since("loop", chrono::Utc::now() - chrono::Duration::hours(hours)).await
+            }
+        }
+    }
+
+    async fn get_session_events(&self, session_id: &str, limit: usize) -> Result<Vec<SessionEvent>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                storage.get_session_events(session_id, limit).await
+            }
+            DataSource::Remote(client) => client.get_session_events(session_id, limit).await,
+        }
+    }
+
+    async fn get_duplicate_prompts(
+        &self,
+        session_id: &str,
+        config: &DuplicatesConfig,
+    ) -> Result<Vec<DuplicatePrompt>> {
+        match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                duplicates::find_duplicates(storage, config, Some(session_id)).await
+            }
+            DataSource::Remote(client) => client.get_duplicate_prompts(session_id).await,
+        }
+    }
+
+    async fn get_session_tags(&self, session_id: &str) -> Result<Vec<String>> {
+        let tags = match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                storage.list_session_tags(Some(session_id), None).await?
+            }
+            DataSource::Remote(client) => client.get_session_tags(session_id).await?,
+        };
+        Ok(tags.into_iter().map(|t| t.tag).collect())
+    }
+
+    async fn get_latest_resources(&self, session_id: &str) -> Result<Option<ResourceSample>> {
+        let samples = match self {
+            DataSource::Local(storage) | DataSource::Snapshot(storage) => {
+                storage.get_resource_samples(session_id, 1).await?
+            }
+            DataSource::Remote(client) => client.get_session_resources(session_id, 1).await?,
+        };
+        Ok(samples.into_iter().next())
+    }
+
+    async fn add_session_tag(&self, session_id: &str, tag: &str) -> Result<()> {
+        match self {
+            DataSource::Local(storage) => {
+                storage.add_session_tag(&SessionTag::new(session_id, tag, "manual")).await?;
+                Ok(())
+            }
+            DataSource::Remote(client) => client.add_session_tag(session_id, tag).await,
+            DataSource::Snapshot(_) => anyhow::bail!("Snapshots are read-only"),
+        }
+    }
+
+    async fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<()> {
+        match self {
+            DataSource::Local(storage) => {
+                storage.remove_session_tag(session_id, tag).await?;
+                Ok(())
+            }
+            DataSource::Remote(client) => client.remove_session_tag(session_id, tag).await,
+            DataSource::Snapshot(_) => anyhow::bail!("Snapshots are read-only"),
+        }
+    }
+
+    /// Whether the data is a frozen snapshot that should not auto-refresh.
+    fn is_frozen(&self) -> bool {
+        matches!(self, DataSource::Snapshot(_))
+    }
+
+    /// Short label shown in the header for non-local sources.
+    fn label(&self) -> Option<String> {
+        match self {
+            DataSource::Local(_) => None,
+            DataSource::Remote(client) => Some(format!("@{}", client.host())),
+            DataSource::Snapshot(_) => Some("[FROZEN]".to_string()),
+        }
+    }
+}
+
 /// App state for the TUI
 pub struct App {
-    storage: Storage,
+    source: DataSource,
     sessions: Vec<Session>,
     selected_index: usize,
     session_scroll_offset: usize,  // For scrolling sessions list
@@ -43,6 +302,10 @@ pub struct App {
     sparkline_data: Vec<u64>,
     should_quit: bool,
     last_update: Instant,
+    /// Last refresh failure, shown until the next successful refresh
+    refresh_error: Option<String>,
+    /// Whether the daemon is pushing updates (otherwise data is polled)
+    live: bool,
     animation_frame: usize,
     // Detail view state
     show_detail_view: bool,
@@ -53,12 +316,79 @@ pub struct App {
     expanded_event_index: Option<usize>,
     expanded_vertical_scroll: usize,  // Vertical scroll within expanded event
     expanded_content_lines: usize,    // Total lines in expanded content
+    duplicates_config: DuplicatesConfig,
+    /// "Asked before" hint for the session open in the detail view
+    duplicate_hint: Option<String>,
+    /// The open session's place among its project's sessions
+    related: Option<ProjectPosition>,
+    /// Sessions with a recent Loop event
+    looping: HashSet<String>,
+    /// Tags of the selected session
+    selected_tags: Vec<String>,
+    /// Latest CPU and memory sample of the selected session
+    selected_resources: Option<ResourceSample>,
+    /// MCP servers the selected session called, most calls first
+    selected_mcp: Vec<McpServerUsage>,
+    /// Slash commands the selected session ran, most used first
+    selected_commands: Vec<SlashCommandUsage>,
+    /// Output tokens per second of the selected session's responses
+    selected_throughput: Option<Throughput>,
+    /// Tag prompt text while the prompt is open
+    tag_input: Option<String>,
+    /// Timeline cursor step (0..=TIMELINE_STEPS); None while it follows the
+    /// selected event
+    timeline_cursor: Option<usize>,
+    /// Goto prompt text while the prompt is open
+    goto_input: Option<String>,
+    /// Show what each prompt added to the context instead of the event list
+    show_turns: bool,
+    /// Search prompt text while the prompt is open in the expanded event
+    search_input: Option<String>,
+    /// Pattern highlighted in the expanded event
+    search: Option<Regex>,
+    /// Index of the current match among the expanded event's matches
+    search_match: usize,
+    /// Why the last search pattern was rejected
+    search_error: Option<String>,
+    /// Show expanded events as plain text instead of rendered Markdown or
+    /// highlighted code
+    raw_view: bool,
+    /// Wallboard mode: no chrome, big numbers, views cycle by themselves
+    kiosk: bool,
+    /// Index into `KIOSK_VIEWS`
+    kiosk_view: usize,
+    /// Totals since midnight, refreshed in kiosk mode
+    today: SummaryMetrics,
+    errors_today: usize,
+    /// Hourly points for the metrics charts, oldest first
+    hourly: Vec<HourlyUsage>,
+    /// Last week against the week before, refreshed with the charts
+    weekly_trend: Option<ReportComparison>,
+    /// API errors per hour, over the same hours
+    api_errors: Vec<ApiErrorHour>,
+    hourly_loaded_at: Option<Instant>,
+    /// The History tab's sessions by day, newest first
+    history: Vec<SessionGroup>,
+    /// Days folded in the History tab
+    folded_days: HashSet<String>,
+    /// Selected row of the History tab, counting day separators
+    history_index: usize,
+    history_scroll_offset: usize,
+}
+
+/// A row of the History tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryRow {
+    /// Separator of the day at this index of `App::history`
+    Day(usize),
+    /// A session: day index, then index within the day
+    Session(usize, usize),
 }
 
 impl App {
-    pub fn new(storage: Storage) -> Self {
+    pub fn new(source: DataSource) -> Self {
         Self {
-            storage,
+            source,
             sessions: Vec::new(),
             selected_index: 0,
             session_scroll_offset: 0,
@@ -67,6 +397,8 @@ impl App {
             sparkline_data: vec![0; 60],
             should_quit: false,
             last_update: Instant::now(),
+            refresh_error: None,
+            live: false,
             animation_frame: 0,
             show_detail_view: false,
             session_events: Vec::new(),
@@ -76,6 +408,36 @@ impl App {
             expanded_event_index: None,
             expanded_vertical_scroll: 0,
             expanded_content_lines: 0,
+            duplicates_config: Config::load_or_default().unwrap_or_default().duplicates,
+            duplicate_hint: None,
+            related: None,
+            looping: HashSet::new(),
+            selected_tags: Vec::new(),
+            selected_resources: None,
+            selected_mcp: Vec::new(),
+            selected_commands: Vec::new(),
+            selected_throughput: None,
+            tag_input: None,
+            timeline_cursor: None,
+            goto_input: None,
+            show_turns: false,
+            search_input: None,
+            search: None,
+            search_match: 0,
+            search_error: None,
+            raw_view: false,
+            kiosk: false,
+            kiosk_view: 0,
+            today: SummaryMetrics::default(),
+            errors_today: 0,
+            hourly: Vec::new(),
+            weekly_trend: None,
+            api_errors: Vec::new(),
+            hourly_loaded_at: None,
+            history: Vec::new(),
+            folded_days: HashSet::new(),
+            history_index: 0,
+            history_scroll_offset: 0,
         }
     }
 
@@ -84,30 +446,80 @@ impl App {
         if self.show_detail_view {
             // Close detail view
             self.show_detail_view = false;
+            self.show_turns = false;
             self.session_events.clear();
             self.event_scroll_offset = 0;
             self.selected_event_index = 0;
             self.event_horizontal_scroll = 0;
             self.expanded_event_index = None;
+            self.timeline_cursor = None;
         } else {
-            // Open detail view - load events for selected session
-            if !self.sessions.is_empty() && self.selected_index < self.sessions.len() {
-                let session_id = &self.sessions[self.selected_index].id;
-                self.session_events = self.storage.get_session_events(session_id, 200).await?;
-                self.event_scroll_offset = 0;
-                self.selected_event_index = 0;
-                self.event_horizontal_scroll = 0;
-                self.expanded_event_index = None;
-                self.show_detail_view = true;
-            }
+            self.open_detail_view().await?;
         }
         Ok(())
     }
 
+    /// Open the detail view on the selected session and load its events.
+    async fn open_detail_view(&mut self) -> Result<()> {
+        let Some(session) = self.sessions.get(self.selected_index).cloned() else {
+            return Ok(());
+        };
+        self.session_events = self.source.get_session_events(&session.id, 200).await?;
+        self.event_scroll_offset = 0;
+        self.selected_event_index = 0;
+        self.event_horizontal_scroll = 0;
+        self.expanded_event_index = None;
+        self.timeline_cursor = None;
+        self.show_turns = false;
+        self.duplicate_hint = self.duplicate_hint_for(&session.id).await;
+        // Without the project's other sessions there is just nowhere to step
+        let project = self.source.get_project_sessions(&session.project_path).await.unwrap_or_default();
+        self.related = Some(related::position(project, &session));
+        self.show_detail_view = true;
+        Ok(())
+    }
+
+    /// Open the project's previous (`forward` false) or next session in the
+    /// detail view.
+    pub async fn open_related_session(&mut self, forward: bool) -> Result<()> {
+        let target = self.related.as_ref().and_then(|r| if forward { r.next.clone() } else { r.previous.clone() });
+        let Some(target) = target else {
+            return Ok(());
+        };
+        // Sessions that are no longer active are kept in the list while open
+        self.selected_index = match self.sessions.iter().position(|s| s.id == target.id) {
+            Some(index) => index,
+            None => {
+                self.sessions.push(target);
+                self.sessions.len() - 1
+            }
+        };
+        self.open_detail_view().await?;
+        self.refresh_selected().await;
+        Ok(())
+    }
+
+    /// Point at an earlier session that was sent the same prompt.
+    async fn duplicate_hint_for(&self, session_id: &str) -> Option<String> {
+        let groups = self
+            .source
+            .get_duplicate_prompts(session_id, &self.duplicates_config)
+            .await
+            .ok()?;
+        let earlier = groups.iter().find_map(|g| g.earlier_than(session_id))?;
+        let project = earlier.project_path.rsplit('/').next().unwrap_or("UNKNOWN");
+        Some(format!(
+            " ⚠ ASKED BEFORE - SEE SESSION {} ({}) ",
+            &earlier.session_id[..8.min(earlier.session_id.len())],
+            project.to_uppercase()
+        ))
+    }
+
     /// Move selection up in detail view
     pub fn select_previous_event(&mut self) {
         if self.selected_event_index > 0 {
             self.selected_event_index -= 1;
+            self.timeline_cursor = None;
             self.event_horizontal_scroll = 0; // Reset horizontal scroll on selection change
             // Scroll view if needed
             if self.selected_event_index < self.event_scroll_offset {
@@ -120,6 +532,7 @@ impl App {
     pub fn select_next_event(&mut self) {
         if !self.session_events.is_empty() && self.selected_event_index < self.session_events.len() - 1 {
             self.selected_event_index += 1;
+            self.timeline_cursor = None;
             self.event_horizontal_scroll = 0; // Reset horizontal scroll on selection change
             // Scroll view if needed (keep 2 lines margin at bottom)
             let visible_height = 15; // approximate visible rows
@@ -129,6 +542,210 @@ impl App {
         }
     }
 
+    /// First and last moment of the session open in the detail view.
+    fn timeline_span(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let session = self.sessions.get(self.selected_index)?;
+        let oldest = self.session_events.iter().map(|e| e.timestamp).min()?;
+        let newest = self.session_events.iter().map(|e| e.timestamp).max()?;
+        Some((session.started_at.min(oldest), session.last_activity_at.max(newest)))
+    }
+
+    /// Timeline step at `time`, or the time at `step`, within `span`.
+    fn timeline_step(span: (DateTime<Utc>, DateTime<Utc>), time: DateTime<Utc>) -> usize {
+        let total = (span.1 - span.0).num_milliseconds().max(1) as f64;
+        let offset = (time - span.0).num_milliseconds() as f64;
+        ((offset / total) * TIMELINE_STEPS as f64).round().clamp(0.0, TIMELINE_STEPS as f64) as usize
+    }
+
+    fn timeline_time(span: (DateTime<Utc>, DateTime<Utc>), step: usize) -> DateTime<Utc> {
+        let total = (span.1 - span.0).num_milliseconds();
+        span.0 + chrono::Duration::milliseconds(total * step as i64 / TIMELINE_STEPS as i64)
+    }
+
+    /// Where the timeline cursor is: its own step, or the selected event's.
+    fn timeline_cursor_step(&self) -> Option<usize> {
+        let span = self.timeline_span()?;
+        self.timeline_cursor.or_else(|| {
+            let event = self.session_events.get(self.selected_event_index)?;
+            Some(Self::timeline_step(span, event.timestamp))
+        })
+    }
+
+    /// Move the timeline cursor by `delta` steps and select the event
+    /// closest to its time.
+    pub fn move_timeline_cursor(&mut self, delta: isize) {
+        let (Some(span), Some(current)) = (self.timeline_span(), self.timeline_cursor_step()) else {
+            return;
+        };
+        let step = current.saturating_add_signed(delta).min(TIMELINE_STEPS);
+        if let Some(index) = self.nearest_event(Self::timeline_time(span, step)) {
+            self.select_event(index, 15);
+        }
+        self.timeline_cursor = Some(step);
+    }
+
+    /// Index of the loaded event closest in time to `time`.
+    fn nearest_event(&self, time: DateTime<Utc>) -> Option<usize> {
+        self.session_events
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, e)| (e.timestamp - time).num_milliseconds().abs())
+            .map(|(i, _)| i)
+    }
+
+    /// Select the event at `index` (clamped), scrolling the list, which shows
+    /// `visible_height` rows, to keep it on screen.
+    pub fn select_event(&mut self, index: usize, visible_height: usize) {
+        let Some(last) = self.session_events.len().checked_sub(1) else {
+            return;
+        };
+        self.selected_event_index = index.min(last);
+        self.event_horizontal_scroll = 0;
+        self.timeline_cursor = None;
+        let visible_height = visible_height.max(1);
+        if self.selected_event_index < self.event_scroll_offset {
+            self.event_scroll_offset = self.selected_event_index;
+        } else if self.selected_event_index >= self.event_scroll_offset + visible_height {
+            self.event_scroll_offset = self.selected_event_index + 1 - visible_height;
+        }
+    }
+
+    /// Index into `turns` of the turn the selected event belongs to.
+    fn selected_turn(&self, turns: &[Turn]) -> Option<usize> {
+        let selected = self.session_events.get(self.selected_event_index)?;
+        turns.iter().rposition(|t| t.started_at <= selected.timestamp).or((!turns.is_empty()).then_some(0))
+    }
+
+    /// Select the prompt `delta` turns away from the selected event's turn.
+    pub fn step_turn(&mut self, delta: isize, visible_height: usize) {
+        let turns = turns::turns(&self.session_events);
+        let Some(current) = self.selected_turn(&turns) else {
+            return;
+        };
+        let next = current.saturating_add_signed(delta).min(turns.len() - 1);
+        if let Some(index) = self.nearest_event(turns[next].started_at) {
+            self.select_event(index, visible_height);
+        }
+    }
+
+    /// Jump to the event closest to a goto entry (see `parse_goto`). False
+    /// when the entry can't be read.
+    pub fn goto(&mut self, input: &str, visible_height: usize) -> bool {
+        let Some(span) = self.timeline_span() else {
+            return false;
+        };
+        let from = self.session_events.get(self.selected_event_index).map_or(span.1, |e| e.timestamp);
+        let Some(time) = parse_goto(input, from, span.1) else {
+            return false;
+        };
+        if let Some(index) = self.nearest_event(time) {
+            self.select_event(index, visible_height);
+        }
+        true
+    }
+
+    /// Content shown when the event at `index` is expanded.
+    fn expanded_content(&self, index: usize) -> &str {
+        self.session_events
+            .get(index)
+            .and_then(|e| e.content.as_deref().or(e.tool_name.as_deref()).or(e.file_path.as_deref()))
+            .unwrap_or("(no content)")
+    }
+
+    /// Whether the expanded event at `index` is shown as rendered Markdown:
+    /// agent responses, unless raw view or a search is on.
+    fn shows_markdown(&self, index: usize) -> bool {
+        !self.raw_view
+            && self.search.is_none()
+            && self
+                .session_events
+                .get(index)
+                .is_some_and(|e| e.event_type == EventType::ResponseGenerated && e.content.is_some())
+    }
+
+    /// Number of lines the event at `index` takes up when expanded.
+    fn expanded_line_count(&self, index: usize) -> usize {
+        let content = self.expanded_content(index);
+        if self.shows_markdown(index) {
+            markdown::to_lines(content).len()
+        } else {
+            content.lines().count()
+        }
+    }
+
+    /// Switch the expanded event between raw and rendered text.
+    pub fn toggle_raw_view(&mut self) {
+        self.raw_view = !self.raw_view;
+        self.refresh_expanded_lines();
+    }
+
+    fn refresh_expanded_lines(&mut self) {
+        if let Some(index) = self.expanded_event_index {
+            self.expanded_content_lines = self.expanded_line_count(index);
+            self.expanded_vertical_scroll = self.expanded_vertical_scroll.min(self.expanded_content_lines.saturating_sub(1));
+        }
+    }
+
+    /// Matches of the search pattern in the expanded event, as line number
+    /// and byte range within the line.
+    fn search_matches(&self) -> Vec<(usize, std::ops::Range<usize>)> {
+        let (Some(pattern), Some(index)) = (&self.search, self.expanded_event_index) else {
+            return Vec::new();
+        };
+        self.expanded_content(index)
+            .lines()
+            .enumerate()
+            .flat_map(|(n, line)| pattern.find_iter(line).map(move |m| (n, m.range())))
+            .collect()
+    }
+
+    /// Search the expanded event for `pattern`, starting at the first match
+    /// at or below the top of the view. False if the pattern is invalid.
+    pub fn start_search(&mut self, pattern: &str) -> bool {
+        match Regex::new(pattern) {
+            Ok(regex) => {
+                self.search = Some(regex);
+                self.search_error = None;
+                // Matches are found in the raw text
+                self.refresh_expanded_lines();
+                let top = self.expanded_vertical_scroll;
+                let first = self.search_matches().iter().position(|(line, _)| *line >= top).unwrap_or(0);
+                self.show_search_match(first);
+                true
+            }
+            Err(e) => {
+                self.search_error = Some(e.to_string().lines().last().unwrap_or_default().trim().to_string());
+                false
+            }
+        }
+    }
+
+    /// Move to the next (`forward`) or previous match, wrapping around.
+    pub fn step_search(&mut self, forward: bool) {
+        let count = self.search_matches().len();
+        if count == 0 {
+            return;
+        }
+        let next = if forward { (self.search_match + 1) % count } else { (self.search_match + count - 1) % count };
+        self.show_search_match(next);
+    }
+
+    /// Make match `index` current and scroll it into view with some context.
+    fn show_search_match(&mut self, index: usize) {
+        self.search_match = index;
+        if let Some((line, _)) = self.search_matches().get(index) {
+            self.expanded_vertical_scroll = line.saturating_sub(2);
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search_input = None;
+        self.search = None;
+        self.search_match = 0;
+        self.search_error = None;
+        self.refresh_expanded_lines();
+    }
+
     /// Scroll text left (show earlier content)
     pub fn scroll_event_left(&mut self) {
         if self.event_horizontal_scroll > 0 {
@@ -150,12 +767,7 @@ impl App {
             self.expanded_event_index = Some(self.selected_event_index);
             self.expanded_vertical_scroll = 0;
             // Calculate content lines for the expanded event
-            if let Some(event) = self.session_events.get(self.selected_event_index) {
-                self.expanded_content_lines = event.content
-                    .as_ref()
-                    .map(|c| c.lines().count())
-                    .unwrap_or(0);
-            }
+            self.expanded_content_lines = self.expanded_line_count(self.selected_event_index);
         }
     }
 
@@ -170,14 +782,9 @@ impl App {
                 self.selected_event_index -= 1;
                 self.expanded_event_index = Some(self.selected_event_index);
                 // Calculate new content lines and scroll to bottom
-                if let Some(event) = self.session_events.get(self.selected_event_index) {
-                    self.expanded_content_lines = event.content
-                        .as_ref()
-                        .map(|c| c.lines().count())
-                        .unwrap_or(0);
-                    // Start at bottom of previous event
-                    self.expanded_vertical_scroll = self.expanded_content_lines.saturating_sub(visible_lines);
-                }
+                self.expanded_content_lines = self.expanded_line_count(self.selected_event_index);
+                // Start at bottom of previous event
+                self.expanded_vertical_scroll = self.expanded_content_lines.saturating_sub(visible_lines);
                 // Adjust scroll offset if needed
                 if self.selected_event_index < self.event_scroll_offset {
                     self.event_scroll_offset = self.selected_event_index;
@@ -199,23 +806,36 @@ impl App {
                 self.expanded_event_index = Some(self.selected_event_index);
                 self.expanded_vertical_scroll = 0;
                 // Calculate new content lines
-                if let Some(event) = self.session_events.get(self.selected_event_index) {
-                    self.expanded_content_lines = event.content
-                        .as_ref()
-                        .map(|c| c.lines().count())
-                        .unwrap_or(0);
-                }
+                self.expanded_content_lines = self.expanded_line_count(self.selected_event_index);
             }
         }
     }
 
-    pub async fn refresh_data(&mut self) -> Result<()> {
+    /// Reload sessions and totals. True when the session list changed.
+    pub async fn refresh_data(&mut self) -> Result<bool> {
         // Remember currently selected session ID to preserve selection
         let selected_session_id = self.sessions
             .get(self.selected_index)
             .map(|s| s.id.clone());
 
-        self.sessions = self.storage.get_active_sessions(50).await?;
+        let mut sessions = self.source.get_active_sessions(50).await?;
+        // The session open in the detail view stays listed after it ends,
+        // or when it was opened from another session of its project
+        if self.show_detail_view {
+            if let Some(open) = self.sessions.get(self.selected_index) {
+                if !sessions.iter().any(|s| s.id == open.id) {
+                    sessions.push(open.clone());
+                }
+            }
+        }
+        let changed = sessions.len() != self.sessions.len()
+            || sessions.iter().zip(&self.sessions).any(|(new, old)| {
+                new.id != old.id || new.last_activity_at != old.last_activity_at || new.status != old.status
+            });
+        self.sessions = sessions;
+
+        // Older daemons have no loop detection
+        self.looping = self.source.get_looping_sessions(LOOP_BADGE_HOURS).await.unwrap_or_default();
 
         // Update sparkline with active session count
         self.sparkline_data.remove(0);
@@ -232,15 +852,33 @@ impl App {
         if !self.sessions.is_empty() && self.selected_index >= self.sessions.len() {
             self.selected_index = self.sessions.len() - 1;
         }
+        self.refresh_selected().await;
+        if self.tab_index == HISTORY_TAB {
+            self.refresh_history().await?;
+        }
+        if self.kiosk {
+            let hours = hours_today();
+            self.today = self.source.get_summary_metrics(hours).await?;
+            self.errors_today = self.source.count_errors(hours).await?;
+        }
+        if self.hourly_loaded_at.is_none_or(|at| at.elapsed() >= CHART_REFRESH) {
+            self.hourly = self.source.get_hourly_usage(CHART_HOURS).await?;
+            // Older daemons do not serve API errors
+            self.api_errors = self.source.get_api_errors(CHART_HOURS).await.unwrap_or_default();
+            self.weekly_trend = self.source.get_weekly_trend().await.unwrap_or_default();
+            self.hourly_loaded_at = Some(Instant::now());
+        }
 
         self.last_update = Instant::now();
-        Ok(())
+        Ok(changed)
     }
 
     /// Refresh events for current session (live updates in detail view)
     /// Events are newest-first (ORDER BY DESC), so new events appear at top (index 0)
     /// Preserves user's current selection by tracking event ID.
-    pub async fn refresh_events(&mut self) -> Result<()> {
+    /// True when events were added or removed.
+    pub async fn refresh_events(&mut self) -> Result<bool> {
+        let mut changed = false;
         if !self.sessions.is_empty() && self.selected_index < self.sessions.len() {
             let session_id = &self.sessions[self.selected_index].id;
 
@@ -250,8 +888,11 @@ impl App {
                 .map(|e| e.id.clone());
 
             let old_count = self.session_events.len();
-            self.session_events = self.storage.get_session_events(session_id, 500).await?;
+            let first_id = self.session_events.first().map(|e| e.id.clone());
+            self.session_events = self.source.get_session_events(session_id, 500).await?;
             let new_count = self.session_events.len();
+            changed = new_count != old_count
+                || self.session_events.first().map(|e| &e.id) != first_id.as_ref();
 
             // Try to find the previously selected event in the new list
             if let Some(ref old_id) = selected_event_id {
@@ -275,6 +916,47 @@ impl App {
                 self.selected_event_index = self.session_events.len().saturating_sub(1);
             }
         }
+        Ok(changed)
+    }
+
+    /// Reload the selected session's tags and latest resource sample. An
+    /// older database or daemon has neither, so failures just clear them.
+    async fn refresh_selected(&mut self) {
+        let Some(session_id) = self.sessions.get(self.selected_index).map(|s| s.id.clone()) else {
+            self.selected_tags = Vec::new();
+            self.selected_resources = None;
+            self.selected_mcp = Vec::new();
+            self.selected_commands = Vec::new();
+            self.selected_throughput = None;
+            return;
+        };
+        self.selected_tags = self.source.get_session_tags(&session_id).await.unwrap_or_default();
+        self.selected_resources = self.source.get_latest_resources(&session_id).await.ok().flatten();
+        let events = self.source.get_session_events(&session_id, MCP_EVENTS).await.unwrap_or_default();
+        self.selected_mcp = mcp::usage(&mcp::calls(&events));
+        self.selected_commands = slash::usage(&events);
+        self.selected_throughput = throughput::summary(&throughput::responses(&events));
+    }
+
+    /// Apply the tag prompt to the selected session: each word adds a tag,
+    /// or removes it when prefixed with `-`.
+    async fn apply_tag_input(&mut self, input: &str) -> Result<()> {
+        let Some(session_id) = self.sessions.get(self.selected_index).map(|s| s.id.clone()) else {
+            return Ok(());
+        };
+        for word in input.split_whitespace() {
+            let (remove, word) = match word.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, word),
+            };
+            let tag = normalize_tag(word).ok_or_else(|| anyhow::anyhow!("Invalid tag '{}'", word))?;
+            if remove {
+                self.source.remove_session_tag(&session_id, &tag).await?;
+            } else {
+                self.source.add_session_tag(&session_id, &tag).await?;
+            }
+        }
+        self.refresh_selected().await;
         Ok(())
     }
 
@@ -302,21 +984,136 @@ impl App {
     }
 
     pub fn next_tab(&mut self) {
-        self.tab_index = (self.tab_index + 1) % 3;
+        self.tab_index = (self.tab_index + 1) % 4;
     }
 
     pub fn previous_tab(&mut self) {
-        self.tab_index = if self.tab_index == 0 { 2 } else { self.tab_index - 1 };
+        self.tab_index = if self.tab_index == 0 { 3 } else { self.tab_index - 1 };
+    }
+
+    /// Reload the History tab's sessions, keeping the selected row's day or
+    /// session selected.
+    pub async fn refresh_history(&mut self) -> Result<()> {
+        let selected = self.history_rows().get(self.history_index).map(|row| self.history_row_key(*row));
+        let sessions = self.source.get_recent_sessions(HISTORY_HOURS, HISTORY_LIMIT).await?;
+        self.history = grouping::group(sessions, GroupBy::Day);
+        let rows = self.history_rows();
+        if let Some(key) = selected {
+            if let Some(index) = rows.iter().position(|row| self.history_row_key(*row) == key) {
+                self.history_index = index;
+            }
+        }
+        self.history_index = self.history_index.min(rows.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Rows of the History tab: each day's separator, then its sessions
+    /// unless the day is folded.
+    fn history_rows(&self) -> Vec<HistoryRow> {
+        let mut rows = Vec::new();
+        for (day, group) in self.history.iter().enumerate() {
+            rows.push(HistoryRow::Day(day));
+            if !self.folded_days.contains(&group.key) {
+                rows.extend((0..group.sessions.len()).map(|i| HistoryRow::Session(day, i)));
+            }
+        }
+        rows
+    }
+
+    /// What a row shows, stable across reloads.
+    fn history_row_key(&self, row: HistoryRow) -> String {
+        match row {
+            HistoryRow::Day(day) => self.history[day].key.clone(),
+            HistoryRow::Session(day, i) => self.history[day].sessions[i].id.clone(),
+        }
+    }
+
+    pub fn select_history_row(&mut self, index: usize, visible_rows: usize) {
+        let rows = self.history_rows().len();
+        self.history_index = index.min(rows.saturating_sub(1));
+        if self.history_index < self.history_scroll_offset {
+            self.history_scroll_offset = self.history_index;
+        } else if self.history_index >= self.history_scroll_offset + visible_rows {
+            self.history_scroll_offset = self.history_index + 1 - visible_rows;
+        }
+    }
+
+    /// Fold or unfold the day of the selected row, selecting its separator.
+    pub fn toggle_history_day(&mut self) {
+        let day = match self.history_rows().get(self.history_index) {
+            Some(HistoryRow::Day(day) | HistoryRow::Session(day, _)) => *day,
+            None => return,
+        };
+        let key = self.history[day].key.clone();
+        if !self.folded_days.remove(&key) {
+            self.folded_days.insert(key);
+        }
+        if let Some(index) = self.history_rows().iter().position(|row| *row == HistoryRow::Day(day)) {
+            self.history_index = index;
+            self.history_scroll_offset = self.history_scroll_offset.min(index);
+        }
     }
 
     pub fn tick(&mut self) {
         self.tick_count += 1;
         self.animation_frame = (self.animation_frame + 1) % 8;
+        if self.kiosk && self.tick_count.is_multiple_of(KIOSK_VIEW_SECS * 10) {
+            self.kiosk_view = (self.kiosk_view + 1) % KIOSK_VIEWS.len();
+        }
     }
 }
 
-/// Run the interactive TUI
-pub async fn run_tui(storage: Storage) -> Result<()> {
+/// Time named by a goto entry: `HH:MM` or `HH:MM:SS` (UTC, like the event
+/// list) on the day of `last`, or the day before when that would be later
+/// than `last`; or an offset such as `-10m`, `+90s` or `-2h` from `from`.
+fn parse_goto(input: &str, from: DateTime<Utc>, last: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    if let Some(sign) = input.strip_prefix('-').map(|_| -1).or(input.strip_prefix('+').map(|_| 1)) {
+        let body = &input[1..];
+        let (digits, unit) = body.split_at(body.find(|c: char| !c.is_ascii_digit()).unwrap_or(body.len()));
+        let amount: i64 = digits.parse().ok()?;
+        let offset = match unit {
+            "s" => chrono::Duration::seconds(amount),
+            "" | "m" => chrono::Duration::minutes(amount),
+            "h" => chrono::Duration::hours(amount),
+            _ => return None,
+        };
+        return Some(from + offset * sign);
+    }
+    let time = chrono::NaiveTime::parse_from_str(input, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(input, "%H:%M"))
+        .ok()?;
+    let at = last.date_naive().and_time(time).and_utc();
+    Some(if at > last { at - chrono::Duration::days(1) } else { at })
+}
+
+/// Rows the detail view's event list shows: the screen less the header,
+/// timeline, footer and list borders.
+fn detail_list_height<B: ratatui::backend::Backend>(terminal: &Terminal<B>) -> usize {
+    terminal.size().map(|s| s.height.saturating_sub(12) as usize).unwrap_or(15)
+}
+
+/// Rows of the History tab's list that fit on screen.
+fn history_list_height<B: ratatui::backend::Backend>(terminal: &Terminal<B>) -> usize {
+    terminal.size().map(|s| s.height.saturating_sub(11) as usize).unwrap_or(15)
+}
+
+/// Hours since local midnight, rounded up, for "today" totals.
+fn hours_today() -> i64 {
+    let now = chrono::Local::now();
+    let midnight = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+    ((now.naive_local() - midnight).num_minutes() + 59) / 60
+}
+
+/// Run the interactive TUI, or the wallboard when `kiosk` is set. With a
+/// `subscription` to the daemon the data is re-read when the daemon pushes
+/// events, and polled while it can't.
+pub async fn run_tui(
+    source: DataSource,
+    kiosk: bool,
+    refresh: RefreshConfig,
+    mut subscription: Option<Subscription>,
+) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -325,11 +1122,18 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new(storage);
+    let mut app = App::new(source);
+    app.kiosk = kiosk;
     app.refresh_data().await?;
 
     let tick_rate = Duration::from_millis(100);
     let mut last_tick = Instant::now();
+    let mut data_poll = refresh.poller(refresh.tui_secs);
+    let mut events_poll = refresh.poller(refresh.events_secs);
+    // Pushed since the last re-read
+    let mut pending = Pushed::default();
+    let mut last_pushed_refresh = Instant::now();
+    let mut events_stale = false;
 
     loop {
         terminal.draw(|f| ui(f, &app))?;
@@ -338,9 +1142,70 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
 
         if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                if app.show_detail_view {
+                // Someone is looking: poll at full rate again
+                data_poll.reset(Instant::now());
+                events_poll.reset(Instant::now());
+                if app.kiosk {
+                    // Only quitting; the wallboard runs unattended
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        app.should_quit = true;
+                    }
+                } else if let Some(ref mut input) = app.tag_input {
+                    // Tag prompt takes every key until it closes
+                    match key.code {
+                        KeyCode::Esc => app.tag_input = None,
+                        KeyCode::Enter => {
+                            let input = app.tag_input.take().unwrap_or_default();
+                            app.refresh_error = app.apply_tag_input(&input).await.err().map(|e| e.to_string());
+                        }
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Char(c) => input.push(c),
+                        _ => {}
+                    }
+                } else if let Some(ref mut input) = app.goto_input {
+                    match key.code {
+                        KeyCode::Esc => app.goto_input = None,
+                        KeyCode::Enter => {
+                            // Unreadable entries leave the prompt open to fix
+                            let entry = input.clone();
+                            if app.goto(&entry, detail_list_height(&terminal)) {
+                                app.goto_input = None;
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Char(c) => input.push(c),
+                        _ => {}
+                    }
+                } else if app.show_detail_view {
                     // Detail view controls - check if in expanded mode first
-                    if app.expanded_event_index.is_some() {
+                    if let Some(ref mut input) = app.search_input {
+                        // Search prompt in the expanded event
+                        match key.code {
+                            KeyCode::Esc => {
+                                app.search_input = None;
+                                app.search_error = None;
+                            }
+                            KeyCode::Enter => {
+                                // Invalid patterns leave the prompt open to fix
+                                let pattern = input.clone();
+                                if pattern.is_empty() {
+                                    app.clear_search();
+                                } else if app.start_search(&pattern) {
+                                    app.search_input = None;
+                                }
+                            }
+                            KeyCode::Backspace => {
+                                input.pop();
+                            }
+                            KeyCode::Char(c) => input.push(c),
+                            _ => {}
+                        }
+                    } else if app.expanded_event_index.is_some() {
                         // Expanded event view controls
                         // Approximate visible lines (terminal height - chrome)
                         let visible_lines = terminal.size().map(|s| s.height.saturating_sub(8) as usize).unwrap_or(20);
@@ -350,12 +1215,18 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
                                 app.expanded_event_index = None;
                                 app.expanded_vertical_scroll = 0;
                                 app.event_horizontal_scroll = 0;
+                                app.clear_search();
                             }
                             KeyCode::Enter => {
                                 // Collapse and stay on current event
                                 app.expanded_event_index = None;
                                 app.expanded_vertical_scroll = 0;
+                                app.clear_search();
                             }
+                            KeyCode::Char('/') => app.search_input = Some(String::new()),
+                            KeyCode::Char('n') => app.step_search(true),
+                            KeyCode::Char('N') => app.step_search(false),
+                            KeyCode::Char('r') => app.toggle_raw_view(),
                             KeyCode::Up | KeyCode::Char('k') => app.scroll_expanded_up(visible_lines),
                             KeyCode::Down | KeyCode::Char('j') => app.scroll_expanded_down(visible_lines),
                             KeyCode::Left | KeyCode::Char('h') => app.scroll_event_left(),
@@ -371,15 +1242,45 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
                             KeyCode::Char('q') | KeyCode::Esc => {
                                 // Close detail view and go back to sessions
                                 app.show_detail_view = false;
+                                app.show_turns = false;
                                 app.session_events.clear();
                                 app.selected_event_index = 0;
                                 app.event_scroll_offset = 0;
                             }
+                            // Back to the events, at the selected turn's prompt
+                            KeyCode::Enter if app.show_turns => app.show_turns = false,
                             KeyCode::Enter => app.toggle_event_expansion(),
+                            KeyCode::Char('t') => app.show_turns = !app.show_turns,
+                            KeyCode::Down | KeyCode::Char('j') if app.show_turns => {
+                                app.step_turn(1, detail_list_height(&terminal))
+                            }
+                            KeyCode::Up | KeyCode::Char('k') if app.show_turns => {
+                                app.step_turn(-1, detail_list_height(&terminal))
+                            }
                             KeyCode::Down | KeyCode::Char('j') => app.select_next_event(),
                             KeyCode::Up | KeyCode::Char('k') => app.select_previous_event(),
                             KeyCode::Left | KeyCode::Char('h') => app.scroll_event_left(),
                             KeyCode::Right | KeyCode::Char('l') => app.scroll_event_right(),
+                            KeyCode::Char('g') => app.goto_input = Some(String::new()),
+                            KeyCode::Char('a') => {
+                                timefmt::toggle_relative();
+                            }
+                            KeyCode::PageDown => {
+                                let page = detail_list_height(&terminal);
+                                app.select_event(app.selected_event_index + page, page);
+                            }
+                            KeyCode::PageUp => {
+                                let page = detail_list_height(&terminal);
+                                app.select_event(app.selected_event_index.saturating_sub(page), page);
+                            }
+                            KeyCode::Home => app.select_event(0, detail_list_height(&terminal)),
+                            KeyCode::End => app.select_event(usize::MAX, detail_list_height(&terminal)),
+                            KeyCode::Char('[') => app.move_timeline_cursor(-1),
+                            KeyCode::Char(']') => app.move_timeline_cursor(1),
+                            KeyCode::Char('{') => app.move_timeline_cursor(-10),
+                            KeyCode::Char('}') => app.move_timeline_cursor(10),
+                            KeyCode::Char('<') => app.open_related_session(false).await?,
+                            KeyCode::Char('>') => app.open_related_session(true).await?,
                             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                                 app.should_quit = true
                             }
@@ -393,10 +1294,35 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
                         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                             app.should_quit = true
                         }
-                        KeyCode::Down | KeyCode::Char('j') => app.next_session(),
-                        KeyCode::Up | KeyCode::Char('k') => app.previous_session(),
-                        KeyCode::Tab => app.next_tab(),
-                        KeyCode::BackTab => app.previous_tab(),
+                        KeyCode::Down | KeyCode::Char('j') if app.tab_index == HISTORY_TAB => {
+                            app.select_history_row(app.history_index + 1, history_list_height(&terminal))
+                        }
+                        KeyCode::Up | KeyCode::Char('k') if app.tab_index == HISTORY_TAB => {
+                            app.select_history_row(app.history_index.saturating_sub(1), history_list_height(&terminal))
+                        }
+                        KeyCode::Char(' ') | KeyCode::Enter if app.tab_index == HISTORY_TAB => app.toggle_history_day(),
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            app.next_session();
+                            app.refresh_selected().await;
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            app.previous_session();
+                            app.refresh_selected().await;
+                        }
+                        KeyCode::Char('t') if !app.sessions.is_empty() => app.tag_input = Some(String::new()),
+                        KeyCode::Char('a') => {
+                            timefmt::toggle_relative();
+                        }
+                        KeyCode::Tab | KeyCode::BackTab => {
+                            if key.code == KeyCode::Tab {
+                                app.next_tab();
+                            } else {
+                                app.previous_tab();
+                            }
+                            if app.tab_index == HISTORY_TAB {
+                                app.refresh_history().await?;
+                            }
+                        }
                         KeyCode::Enter => {
                             app.toggle_detail_view().await?;
                         }
@@ -412,15 +1338,42 @@ pub async fn run_tui(storage: Storage) -> Result<()> {
         if last_tick.elapsed() >= tick_rate {
             app.tick();
 
-            // Refresh data every 2 seconds
-            if app.tick_count % 20 == 0 {
-                app.refresh_data().await?;
+            // Refresh data on the configured interval, backing off while
+            // nothing changes. A remote daemon can be briefly unreachable,
+            // so keep showing the last data instead of exiting. While the
+            // daemon pushes events, re-read when it does (a few times a
+            // second at most); polling then only catches changes that come
+            // without events.
+            let now = Instant::now();
+            if let Some(ref mut subscription) = subscription {
+                pending.merge(subscription.take());
+                app.live = subscription.is_connected();
+            }
+            let pushed = app.live && !pending.is_empty() && now.duration_since(last_pushed_refresh) >= PUSH_MIN_GAP;
+            if (data_poll.due(now) || pushed) && !app.source.is_frozen() {
+                if let Some(session) = app.sessions.get(app.selected_index) {
+                    events_stale |= pending.touches(&session.id);
+                }
+                pending = Pushed::default();
+                last_pushed_refresh = now;
+                let result = app.refresh_data().await;
+                data_poll.polled(now, !app.live && !matches!(result, Ok(false)));
+                app.refresh_error = result.err().map(|e| e.to_string());
             }
 
-            // Refresh events every 1 second when in detail view (live updates)
+            // Refresh events when in detail view (live updates)
             // BUT pause refresh when user has an event expanded (reading)
-            if app.show_detail_view && app.expanded_event_index.is_none() && app.tick_count % 10 == 0 {
-                app.refresh_events().await?;
+            if app.show_detail_view
+                && app.expanded_event_index.is_none()
+                && !app.source.is_frozen()
+                && (events_poll.due(now) || (app.live && events_stale))
+            {
+                events_stale = false;
+                let result = app.refresh_events().await;
+                events_poll.polled(now, !app.live && !matches!(result, Ok(false)));
+                if let Err(e) = result {
+                    app.refresh_error = Some(e.to_string());
+                }
             }
 
             last_tick = Instant::now();
@@ -453,6 +1406,11 @@ fn ui(f: &mut Frame, app: &App) {
         size
     );
 
+    if app.kiosk {
+        render_kiosk(f, size, app);
+        return;
+    }
+
     // Show detail view if active
     if app.show_detail_view {
         render_full_detail_view(f, size, app);
@@ -481,6 +1439,7 @@ fn ui(f: &mut Frame, app: &App) {
         0 => render_sessions_tab(f, chunks[2], app),
         1 => render_details_tab(f, chunks[2], app),
         2 => render_metrics_tab(f, chunks[2], app),
+        HISTORY_TAB => render_history_tab(f, chunks[2], app),
         _ => {}
     }
 
@@ -499,8 +1458,13 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
     };
 
     let title = format!(
-        " {} AGENT MONITOR v0.1.0 {} Active: {} {}",
-        scan_line, cursor, app.sessions.len(), scan_line
+        " {} AGENT MONITOR v0.1.0 {}{}{} Active: {} {}",
+        scan_line,
+        app.source.label().map(|l| format!("{} ", l)).unwrap_or_default(),
+        if app.live { "◉ LIVE " } else { "" },
+        cursor,
+        app.sessions.len(),
+        scan_line
     );
 
     let header = Paragraph::new(title)
@@ -515,7 +1479,7 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn render_tabs(f: &mut Frame, area: Rect, app: &App) {
-    let titles = vec!["[1] SESSIONS", "[2] DETAILS", "[3] METRICS"];
+    let titles = vec!["[1] SESSIONS", "[2] DETAILS", "[3] METRICS", "[4] HISTORY"];
     let tabs = Tabs::new(titles)
         .block(
             Block::default()
@@ -542,7 +1506,7 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
         .split(area);
 
     // Sessions table with selector indicator and scrolling
-    let header_cells = [" ", "AGENT", "PROJECT", "STATUS", "MSGS", "TOKENS", "COST"]
+    let header_cells = [" ", "AGENT", "PROJECT", "STATUS", "MSGS", "TOKENS", "COST", "CTX"]
         .iter()
         .map(|h| Cell::from(*h).style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK).add_modifier(Modifier::BOLD)));
     let header = Row::new(header_cells)
@@ -558,7 +1522,7 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
         .skip(app.session_scroll_offset)
         .take(visible_rows)
         .map(|(i, session)| {
-            let is_selected = i == app.selected_index;
+            let is_selected = !app.kiosk && i == app.selected_index;
 
             let (fg, bg, selector) = if is_selected {
                 (TERM_BLACK, TERM_GREEN, "▶")  // Inverted colors + arrow for selection
@@ -567,24 +1531,33 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
             };
 
             let project_name = session.project_path.split('/').last().unwrap_or("---");
-            let status_display = match session.status {
-                SessionStatus::Active => "[LIVE]",
-                SessionStatus::Idle => "[IDLE]",
-                SessionStatus::Completed => "[DONE]",
-                SessionStatus::Crashed => "[ERR!]",
-                SessionStatus::Unknown => "[????]",
+            let looping = app.looping.contains(&session.id);
+            let status_display = if looping { "[LOOP]" } else { status_label(session.status) };
+            let tokens = humanize::tokens(session.tokens_input + session.tokens_output);
+            let cost = humanize::cost(session.estimated_cost);
+            let context = match session.context_utilization() {
+                Some(u) => format!("{:>3.0}%", u * 100.0),
+                None => "  --".to_string(),
+            };
+            let context_style = if session.context_near_limit() && !is_selected {
+                Style::default().fg(TERM_AMBER).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
             };
-            let tokens = format_tokens(session.tokens_input + session.tokens_output);
-            let cost = format!("${:.2}", session.estimated_cost);
 
             Row::new(vec![
                 Cell::from(selector).style(Style::default().fg(TERM_GREEN).bg(bg).add_modifier(Modifier::BOLD)),
                 Cell::from(format!("{:<10}", truncate_str(&session.agent_type.to_string(), 10))),
                 Cell::from(truncate_str(project_name, 12)),
-                Cell::from(status_display),
+                Cell::from(status_display).style(if looping && !is_selected {
+                    Style::default().fg(TERM_AMBER).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                }),
                 Cell::from(format!("{:>4}", session.message_count)),
                 Cell::from(format!("{:>6}", tokens)),
                 Cell::from(format!("{:>6}", cost)),
+                Cell::from(context).style(context_style),
             ])
             .style(Style::default().fg(fg).bg(bg))
             .height(1)
@@ -597,6 +1570,8 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
             (app.session_scroll_offset + visible_rows).min(app.sessions.len()),
             app.sessions.len()
         )
+    } else if app.source.is_frozen() {
+        format!(" SESSIONS ({}) ", app.sessions.len())
     } else {
         format!(" ACTIVE SESSIONS ({}) ", app.sessions.len())
     };
@@ -611,6 +1586,7 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
             Constraint::Length(5),   // Msgs
             Constraint::Length(7),   // Tokens
             Constraint::Length(7),   // Cost
+            Constraint::Length(5),   // Context
         ],
     )
     .header(header)
@@ -653,11 +1629,11 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
 
     let summary_text = vec![
         Line::from(Span::styled(
-            format!("TOKENS: {}", format_tokens(total_tokens)),
+            format!("TOKENS: {}", humanize::tokens(total_tokens)),
             Style::default().fg(TERM_GREEN)
         )),
         Line::from(Span::styled(
-            format!("COST:   ${:.2}", total_cost),
+            format!("COST:   {}", humanize::cost(total_cost)),
             Style::default().fg(TERM_AMBER)
         )),
         Line::from(Span::styled(
@@ -665,10 +1641,16 @@ fn render_sessions_tab(f: &mut Frame, area: Rect, app: &App) {
             Style::default().fg(TERM_GREEN)
         )),
         Line::from(""),
-        Line::from(Span::styled(
-            format!("UPD: {}s ago", app.last_update.elapsed().as_secs()),
-            Style::default().fg(TERM_GREEN_DIM)
-        )),
+        match app.refresh_error {
+            Some(ref e) => Line::from(Span::styled(
+                format!("ERR: {}", truncate_str(e, 40)),
+                Style::default().fg(TERM_RED)
+            )),
+            None => Line::from(Span::styled(
+                format!("UPD: {}s ago", app.last_update.elapsed().as_secs()),
+                Style::default().fg(TERM_GREEN_DIM)
+            )),
+        },
     ];
 
     let summary = Paragraph::new(summary_text)
@@ -708,7 +1690,7 @@ fn render_details_tab(f: &mut Frame, area: Rect, app: &App) {
 
     // Session info
     let project_name = session.project_path.split('/').last().unwrap_or("UNKNOWN");
-    let details = vec![
+    let mut details = vec![
         Line::from(vec![
             Span::styled("PROJECT: ", Style::default().fg(TERM_GREEN_DIM)),
             Span::styled(project_name, Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
@@ -752,35 +1734,114 @@ fn render_details_tab(f: &mut Frame, area: Rect, app: &App) {
         Line::from(vec![
             Span::styled("STARTED: ", Style::default().fg(TERM_GREEN_DIM)),
             Span::styled(
-                session.started_at.format("%H:%M:%S").to_string(),
+                timefmt::time(session.started_at),
                 Style::default().fg(TERM_GREEN),
             ),
         ]),
         Line::from(vec![
             Span::styled("DURATION: ", Style::default().fg(TERM_GREEN_DIM)),
             Span::styled(
-                format_duration(session.duration_seconds),
+                humanize::duration(session.duration_seconds),
                 Style::default().fg(TERM_GREEN),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("COMPACTIONS: ", Style::default().fg(TERM_GREEN_DIM)),
+            Span::styled(
+                session.compactions.to_string(),
+                Style::default().fg(if session.compactions > 0 { TERM_MAGENTA } else { TERM_GREEN }),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("INTERRUPTIONS: ", Style::default().fg(TERM_GREEN_DIM)),
+            Span::styled(
+                session.interruptions.to_string(),
+                Style::default().fg(if session.interruptions > 0 { TERM_AMBER } else { TERM_GREEN }),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("TAGS: ", Style::default().fg(TERM_GREEN_DIM)),
+            if app.selected_tags.is_empty() {
+                Span::styled("NONE (t TO ADD)", Style::default().fg(TERM_GREEN_DIM))
+            } else {
+                Span::styled(
+                    app.selected_tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "),
+                    Style::default().fg(TERM_AMBER),
+                )
+            },
+        ]),
     ];
-
-    let details_widget = Paragraph::new(details)
-        .style(Style::default().bg(TERM_BLACK))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(TERM_GREEN_DIM))
-                .style(Style::default().bg(TERM_BLACK))
-                .title(format!(" {} ", project_name.to_uppercase()))
-                .title_style(Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
-        );
+    if let Some(ref rate) = app.selected_throughput {
+        details.push(Line::from(vec![
+            Span::styled("THROUGHPUT: ", Style::default().fg(TERM_GREEN_DIM)),
+            Span::styled(
+                format!(
+                    "{:.0} TOK/S P50 · {:.0} P95 · {} RESPONSES",
+                    rate.p50_tokens_per_sec, rate.p95_tokens_per_sec, rate.responses
+                ),
+                Style::default().fg(TERM_GREEN),
+            ),
+        ]));
+    }
+    if !app.selected_commands.is_empty() {
+        details.push(Line::from(vec![
+            Span::styled("COMMANDS: ", Style::default().fg(TERM_GREEN_DIM)),
+            Span::styled(
+                app.selected_commands
+                    .iter()
+                    .map(|c| format!("{} ×{}", c.command, c.uses))
+                    .collect::<Vec<_>>()
+                    .join("  "),
+                Style::default().fg(TERM_GREEN),
+            ),
+        ]));
+    }
+    if !app.selected_mcp.is_empty() {
+        details.push(Line::from(""));
+        details.push(Line::from(Span::styled("MCP SERVERS:", Style::default().fg(TERM_GREEN_DIM))));
+        for server in &app.selected_mcp {
+            let mut stats = format!(" {} CALL{}", server.calls, if server.calls == 1 { "" } else { "S" });
+            if let Some(avg) = server.avg_duration_ms {
+                stats.push_str(&format!(" · AVG {}", humanize::duration_ms(avg)));
+            }
+            if server.errors > 0 {
+                stats.push_str(&format!(" · {} ERR", server.errors));
+            }
+            details.push(Line::from(vec![
+                Span::styled(format!("  {}", server.server), Style::default().fg(TERM_GREEN)),
+                Span::styled(stats, Style::default().fg(if server.errors > 0 { TERM_AMBER } else { TERM_GREEN_DIM })),
+            ]));
+        }
+    }
+    if let Some(ref summary) = session.summary {
+        details.push(Line::from(""));
+        details.push(Line::from(Span::styled("SUMMARY:", Style::default().fg(TERM_GREEN_DIM))));
+        for line in summary.lines() {
+            details.push(Line::from(Span::styled(line, Style::default().fg(TERM_GREEN))));
+        }
+    }
+
+    let details_widget = Paragraph::new(details)
+        .style(Style::default().bg(TERM_BLACK))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(TERM_GREEN_DIM))
+                .style(Style::default().bg(TERM_BLACK))
+                .title(format!(" {} ", project_name.to_uppercase()))
+                .title_style(Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
+        );
     f.render_widget(details_widget, chunks[0]);
 
     // Token usage breakdown
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(8), Constraint::Min(4)])
+        .constraints([
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(3),
+        ])
         .split(chunks[1]);
 
     let total_tokens = session.tokens_input + session.tokens_output;
@@ -793,15 +1854,15 @@ fn render_details_tab(f: &mut Frame, area: Rect, app: &App) {
     let token_info = vec![
         Line::from(vec![
             Span::styled("INPUT:  ", Style::default().fg(TERM_GREEN_DIM)),
-            Span::styled(format_tokens(session.tokens_input), Style::default().fg(TERM_GREEN)),
+            Span::styled(humanize::tokens(session.tokens_input), Style::default().fg(TERM_GREEN)),
         ]),
         Line::from(vec![
             Span::styled("OUTPUT: ", Style::default().fg(TERM_GREEN_DIM)),
-            Span::styled(format_tokens(session.tokens_output), Style::default().fg(TERM_GREEN)),
+            Span::styled(humanize::tokens(session.tokens_output), Style::default().fg(TERM_GREEN)),
         ]),
         Line::from(vec![
             Span::styled("TOTAL:  ", Style::default().fg(TERM_GREEN_DIM)),
-            Span::styled(format_tokens(total_tokens), Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
+            Span::styled(humanize::tokens(total_tokens), Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -822,6 +1883,71 @@ fn render_details_tab(f: &mut Frame, area: Rect, app: &App) {
         );
     f.render_widget(tokens_widget, right_chunks[0]);
 
+    // Context window utilization
+    let window = context_window(session.model_id.as_deref());
+    let (context_percent, context_label) = match (session.context_tokens, session.context_utilization()) {
+        (Some(tokens), Some(u)) => (
+            (u * 100.0).min(100.0) as u16,
+            format!(
+                "{}{} / {} ({:.0}%)",
+                if session.context_near_limit() { "COMPACTION NEAR: " } else { "" },
+                humanize::tokens(tokens),
+                humanize::tokens(window),
+                u * 100.0
+            ),
+        ),
+        _ => (0, "NO USAGE REPORTED".to_string()),
+    };
+    let context_color = if session.context_near_limit() { TERM_AMBER } else { TERM_GREEN };
+    let context_gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(TERM_GREEN_DIM))
+                .style(Style::default().bg(TERM_BLACK))
+                .title(" CONTEXT ")
+                .title_style(Style::default().fg(TERM_GREEN)),
+        )
+        .gauge_style(Style::default().fg(context_color).bg(TERM_DARK))
+        .percent(context_percent)
+        .label(Span::styled(
+            context_label,
+            Style::default().fg(context_color).add_modifier(Modifier::BOLD)
+        ));
+    f.render_widget(context_gauge, right_chunks[1]);
+
+    // CPU and memory of the agent's processes
+    let (cpu_percent, resources_label) = match app.selected_resources {
+        Some(ref sample) => (
+            sample.cpu_percent.clamp(0.0, 100.0) as u16,
+            format!(
+                "CPU {:.0}% · {} RSS · {} PROC{}",
+                sample.cpu_percent,
+                humanize::bytes(sample.memory_bytes),
+                sample.process_count,
+                if sample.process_count == 1 { "" } else { "S" }
+            ),
+        ),
+        None => (0, "NOT SAMPLED".to_string()),
+    };
+    let resources_color = if cpu_percent >= 80 { TERM_AMBER } else { TERM_GREEN };
+    let resources_gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(TERM_GREEN_DIM))
+                .style(Style::default().bg(TERM_BLACK))
+                .title(" RESOURCES ")
+                .title_style(Style::default().fg(TERM_GREEN)),
+        )
+        .gauge_style(Style::default().fg(resources_color).bg(TERM_DARK))
+        .percent(cpu_percent)
+        .label(Span::styled(
+            resources_label,
+            Style::default().fg(resources_color).add_modifier(Modifier::BOLD)
+        ));
+    f.render_widget(resources_gauge, right_chunks[2]);
+
     // Token ratio gauge
     let gauge = Gauge::default()
         .block(
@@ -838,82 +1964,448 @@ fn render_details_tab(f: &mut Frame, area: Rect, app: &App) {
             format!("{}% IN / {}% OUT", input_ratio, 100 - input_ratio),
             Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)
         ));
-    f.render_widget(gauge, right_chunks[1]);
+    f.render_widget(gauge, right_chunks[3]);
+}
+
+fn status_label(status: SessionStatus) -> &'static str {
+    match status {
+        SessionStatus::Active => "[LIVE]",
+        SessionStatus::Idle => "[IDLE]",
+        SessionStatus::Completed => "[DONE]",
+        SessionStatus::Crashed => "[ERR!]",
+        SessionStatus::Unknown => "[????]",
+    }
+}
+
+/// The last week's sessions under a separator per day.
+fn render_history_tab(f: &mut Frame, area: Rect, app: &App) {
+    let visible_rows = (area.height as usize).saturating_sub(2);
+    let rows = app.history_rows();
+    let items: Vec<ListItem> = rows
+        .iter()
+        .enumerate()
+        .skip(app.history_scroll_offset)
+        .take(visible_rows)
+        .map(|(i, row)| {
+            let selected = i == app.history_index;
+            let (text, style) = match *row {
+                HistoryRow::Day(day) => {
+                    let group = &app.history[day];
+                    let marker = if app.folded_days.contains(&group.key) { "▸" } else { "▾" };
+                    (
+                        format!("{} {} ", marker, group.heading()),
+                        Style::default().fg(TERM_AMBER).add_modifier(Modifier::BOLD),
+                    )
+                }
+                HistoryRow::Session(day, index) => {
+                    let session = &app.history[day].sessions[index];
+                    let project = session.project_path.split('/').next_back().unwrap_or("---");
+                    (
+                        format!(
+                            "    {:<10} {:<14} {} {:>5} {:>7} {:>7}  {}",
+                            truncate_str(&session.agent_type.to_string(), 10),
+                            truncate_str(project, 14),
+                            status_label(session.status),
+                            session.message_count,
+                            humanize::tokens(session.tokens_input + session.tokens_output),
+                            humanize::cost(session.estimated_cost),
+                            timefmt::time(session.last_activity_at),
+                        ),
+                        Style::default().fg(TERM_GREEN),
+                    )
+                }
+            };
+            let style = if selected { Style::default().fg(TERM_BLACK).bg(TERM_GREEN).add_modifier(Modifier::BOLD) } else { style };
+            ListItem::new(Line::from(Span::styled(text, style)))
+        })
+        .collect();
+
+    let sessions: usize = app.history.iter().map(|g| g.sessions.len()).sum();
+    let title = format!(" HISTORY ({} sessions, {} days) ", sessions, app.history.len());
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(TERM_GREEN_DIM))
+            .style(Style::default().bg(TERM_BLACK))
+            .title(title)
+            .title_style(Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
+    );
+    f.render_widget(list, area);
 }
 
 fn render_metrics_tab(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+        ])
         .split(area);
 
-    // Agent type distribution - use BTreeMap for stable ordering
-    let mut agent_counts: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
-    for session in &app.sessions {
-        *agent_counts.entry(session.agent_type.to_string()).or_insert(0) += 1;
+    render_cost_chart(f, chunks[0], app);
+    render_tokens_chart(f, chunks[1], app);
+    render_activity_chart(f, chunks[2], app);
+    render_api_errors_chart(f, chunks[3], app);
+}
+
+fn chart_block(title: String) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(TERM_GREEN_DIM))
+        .style(Style::default().bg(TERM_BLACK))
+        .title(title)
+        .title_style(Style::default().fg(TERM_GREEN))
+}
+
+/// Local-time labels for the first, middle and last hour of the charts.
+fn hour_labels(hourly: &[HourlyUsage]) -> Vec<Span<'static>> {
+    let label = |i: usize| {
+        hourly
+            .get(i)
+            .map(|p| timefmt::absolute(p.hour, "%H:%M"))
+            .unwrap_or_default()
+    };
+    let last = hourly.len().saturating_sub(1);
+    [label(0), label(last / 2), label(last)]
+        .into_iter()
+        .map(|l| Span::styled(l, Style::default().fg(TERM_GREEN_DIM)))
+        .collect()
+}
+
+fn x_axis(hourly: &[HourlyUsage]) -> Axis<'static> {
+    Axis::default()
+        .style(Style::default().fg(TERM_GREEN_DARK))
+        .bounds([0.0, hourly.len().saturating_sub(1).max(1) as f64])
+        .labels(hour_labels(hourly))
+}
+
+/// Upper y bound with some headroom, never zero.
+fn y_bound(max: f64) -> f64 {
+    if max > 0.0 { max * 1.1 } else { 1.0 }
+}
+
+/// Cost per hour over the last day, as a braille line.
+fn render_cost_chart(f: &mut Frame, area: Rect, app: &App) {
+    let points: Vec<(f64, f64)> = app.hourly.iter().enumerate().map(|(i, p)| (i as f64, p.cost)).collect();
+    let total: f64 = app.hourly.iter().map(|p| p.cost).sum();
+    let max = y_bound(points.iter().map(|(_, c)| *c).fold(0.0, f64::max));
+
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(TERM_AMBER))
+        .data(&points);
+    let mut block = chart_block(format!(" COST / HOUR · LAST {}H · {} ", CHART_HOURS, humanize::cost(total)));
+    if let Some(ref trend) = app.weekly_trend {
+        let mut spans = vec![Span::styled(" 7D VS PRIOR 7D: ", Style::default().fg(TERM_GREEN_DIM))];
+        for (i, (name, delta)) in trend.highlights().into_iter().enumerate() {
+            let color = match delta.trend {
+                Trend::Up => TERM_AMBER,
+                Trend::Down => TERM_GREEN,
+                Trend::Flat => TERM_GREEN_DIM,
+            };
+            if i > 0 {
+                spans.push(Span::styled(" · ", Style::default().fg(TERM_GREEN_DIM)));
+            }
+            spans.push(Span::styled(format!("{} ", name.to_uppercase()), Style::default().fg(TERM_GREEN_DIM)));
+            spans.push(Span::styled(format!("{} {}", delta.arrow(), delta.label()), Style::default().fg(color)));
+        }
+        spans.push(Span::raw(" "));
+        block = block.title_bottom(Line::from(spans));
     }
+    let chart = Chart::new(vec![dataset])
+        .style(Style::default().bg(TERM_BLACK))
+        .block(block)
+        .x_axis(x_axis(&app.hourly))
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(TERM_GREEN_DARK))
+                .bounds([0.0, max])
+                .labels(vec![
+                    Span::styled("$0", Style::default().fg(TERM_GREEN_DIM)),
+                    Span::styled(humanize::cost(max), Style::default().fg(TERM_GREEN_DIM)),
+                ]),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Tokens per hour as bars, one per hour.
+fn render_tokens_chart(f: &mut Frame, area: Rect, app: &App) {
+    let total: i64 = app.hourly.iter().map(|p| p.tokens).sum();
+    let block = chart_block(format!(" TOKENS / HOUR · {} ", humanize::tokens(total)));
+    let inner_width = block.inner(area).width as usize;
+    let bar_width = (inner_width / app.hourly.len().max(1)).saturating_sub(1).max(1) as u16;
 
-    let items: Vec<ListItem> = agent_counts
+    let bars: Vec<Bar> = app
+        .hourly
         .iter()
-        .map(|(agent, count)| {
-            let bar_len = (*count as f64 / app.sessions.len().max(1) as f64 * 20.0) as usize;
-            let bar = "█".repeat(bar_len) + &"░".repeat(20 - bar_len);
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{:12}", agent.to_uppercase()), Style::default().fg(TERM_GREEN)),
-                Span::styled(bar, Style::default().fg(TERM_GREEN)),
-                Span::styled(format!(" {}", count), Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
-            ]))
+        .enumerate()
+        .map(|(i, p)| {
+            // Label every sixth hour, when the bars are wide enough to hold it
+            let label = if bar_width >= 2 && i % 6 == 0 { timefmt::absolute(p.hour, "%H") } else { String::new() };
+            Bar::default()
+                .value(p.tokens.max(0) as u64)
+                .text_value(String::new())
+                .label(Line::from(label))
+                .style(Style::default().fg(TERM_GREEN))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(block)
+        .style(Style::default().bg(TERM_BLACK))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .label_style(Style::default().fg(TERM_GREEN_DIM))
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// Events per hour stacked by agent: each line is the running total of the
+/// agents listed before it, busiest agent at the bottom.
+fn render_activity_chart(f: &mut Frame, area: Rect, app: &App) {
+    let mut totals: std::collections::BTreeMap<&str, i64> = std::collections::BTreeMap::new();
+    for point in &app.hourly {
+        for (agent, events) in &point.events {
+            *totals.entry(agent.as_str()).or_insert(0) += events;
+        }
+    }
+    let mut agents: Vec<(&str, i64)> = totals.into_iter().collect();
+    agents.sort_by_key(|(_, events)| std::cmp::Reverse(*events));
+    agents.truncate(AGENT_COLORS.len());
+
+    let mut stacked = vec![0i64; app.hourly.len()];
+    let series: Vec<Vec<(f64, f64)>> = agents
+        .iter()
+        .map(|(agent, _)| {
+            app.hourly
+                .iter()
+                .zip(stacked.iter_mut())
+                .enumerate()
+                .map(|(i, (point, height))| {
+                    *height += point.events.get(*agent).copied().unwrap_or(0);
+                    (i as f64, *height as f64)
+                })
+                .collect()
         })
         .collect();
+    let max = y_bound(stacked.iter().copied().max().unwrap_or(0) as f64);
 
-    let agent_list = List::new(items)
+    let datasets: Vec<Dataset> = agents
+        .iter()
+        .zip(series.iter())
+        .zip(AGENT_COLORS)
+        .map(|(((agent, events), data), color)| {
+            Dataset::default()
+                .name(format!("{} {}", agent.to_uppercase(), events))
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(color))
+                .data(data)
+        })
+        .collect();
+    let chart = Chart::new(datasets)
         .style(Style::default().bg(TERM_BLACK))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(TERM_GREEN_DIM))
-                .style(Style::default().bg(TERM_BLACK))
-                .title(" AGENT DISTRIBUTION ")
-                .title_style(Style::default().fg(TERM_GREEN)),
+        .block(chart_block(" ACTIVITY BY AGENT · EVENTS / HOUR ".to_string()))
+        // The legend names the agents, so keep it on short charts too
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 1)))
+        .x_axis(x_axis(&app.hourly))
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(TERM_GREEN_DARK))
+                .bounds([0.0, max])
+                .labels(vec![
+                    Span::styled("0", Style::default().fg(TERM_GREEN_DIM)),
+                    Span::styled(format!("{:.0}", max), Style::default().fg(TERM_GREEN_DIM)),
+                ]),
         );
-    f.render_widget(agent_list, chunks[0]);
+    f.render_widget(chart, area);
+}
 
-    // Cost and token breakdown - use BTreeMap for stable ordering
-    let mut costs_by_agent: std::collections::BTreeMap<String, f64> = std::collections::BTreeMap::new();
-    for session in &app.sessions {
-        *costs_by_agent.entry(session.agent_type.to_string()).or_insert(0.0) += session.estimated_cost;
+/// API errors per hour as bars; hours during a provider incident are amber.
+fn render_api_errors_chart(f: &mut Frame, area: Rect, app: &App) {
+    let total: i64 = app.api_errors.iter().map(|p| p.errors).sum();
+    let mut kinds: std::collections::BTreeMap<&str, i64> = std::collections::BTreeMap::new();
+    for point in &app.api_errors {
+        for (kind, errors) in &point.kinds {
+            *kinds.entry(kind.as_str()).or_insert(0) += errors;
+        }
+    }
+    let mut title = format!(" API ERRORS / HOUR · {} ", total);
+    for (kind, errors) in &kinds {
+        title.push_str(&format!("· {} {} ", kind.to_uppercase(), errors));
     }
+    // The latest incident that overlaps an hour with errors
+    let incident = app
+        .api_errors
+        .iter()
+        .rev()
+        .filter(|p| p.errors > 0)
+        .find_map(|p| p.incidents.last());
+    let block = match incident {
+        Some(name) => chart_block(title).title(Line::from(Span::styled(
+            format!(" PROVIDER INCIDENT: {} ", name),
+            Style::default().fg(TERM_AMBER).add_modifier(Modifier::BOLD),
+        ))),
+        None => chart_block(title),
+    };
+    let inner_width = block.inner(area).width as usize;
+    let bar_width = (inner_width / app.api_errors.len().max(1)).saturating_sub(1).max(1) as u16;
 
-    let cost_items: Vec<ListItem> = costs_by_agent
+    let bars: Vec<Bar> = app
+        .api_errors
         .iter()
-        .map(|(agent, cost)| {
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{:12}", agent.to_uppercase()), Style::default().fg(TERM_GREEN)),
-                Span::styled(format!("${:.4}", cost), Style::default().fg(TERM_AMBER)),
-            ]))
+        .map(|p| {
+            let color = if p.incidents.is_empty() { TERM_RED } else { TERM_AMBER };
+            Bar::default()
+                .value(p.errors.max(0) as u64)
+                .text_value(String::new())
+                .style(Style::default().fg(color))
         })
         .collect();
-
-    let cost_list = List::new(cost_items)
+    let chart = BarChart::default()
+        .block(block)
         .style(Style::default().bg(TERM_BLACK))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// Wallboard: a title line with the clock, then the current view.
+fn render_kiosk(f: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(5)])
+        .split(area);
+
+    let dots: String = (0..KIOSK_VIEWS.len())
+        .map(|i| if i == app.kiosk_view { "● " } else { "○ " })
+        .collect();
+    let mut title = vec![
+        Span::styled(" AGENT MONITOR ", Style::default().fg(TERM_BLACK).bg(TERM_GREEN).add_modifier(Modifier::BOLD)),
+        Span::styled(format!(" {}  ", KIOSK_VIEWS[app.kiosk_view]), Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD)),
+        Span::styled(dots, Style::default().fg(TERM_GREEN_DIM)),
+        Span::styled(chrono::Local::now().format(" %H:%M ").to_string(), Style::default().fg(TERM_GREEN)),
+    ];
+    if let Some(label) = app.source.label() {
+        title.push(Span::styled(format!(" {} ", label), Style::default().fg(TERM_GREEN_DIM)));
+    }
+    if let Some(ref e) = app.refresh_error {
+        title.push(Span::styled(format!(" ERR: {} ", truncate_str(e, 60)), Style::default().fg(TERM_RED)));
+    }
+    f.render_widget(Paragraph::new(Line::from(title)).style(Style::default().bg(TERM_BLACK)), chunks[0]);
+
+    match app.kiosk_view {
+        0 => render_kiosk_overview(f, chunks[1], app),
+        1 => render_sessions_tab(f, chunks[1], app),
+        _ => render_metrics_tab(f, chunks[1], app),
+    }
+}
+
+/// The three numbers that matter, as large as they fit, over the activity
+/// sparkline.
+fn render_kiosk_overview(f: &mut Frame, area: Rect, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(9), Constraint::Length(6)])
+        .split(area);
+    let tiles = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)])
+        .split(rows[0]);
+
+    let errors_color = if app.errors_today > 0 { TERM_RED } else { TERM_GREEN };
+    let numbers = [
+        ("ACTIVE SESSIONS", app.sessions.len().to_string(), TERM_GREEN),
+        ("COST TODAY", humanize::cost(app.today.total_cost), TERM_AMBER),
+        ("ERRORS TODAY", app.errors_today.to_string(), errors_color),
+    ];
+    for ((label, value, color), tile) in numbers.into_iter().zip(tiles.iter()) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(TERM_GREEN_DARK))
+            .style(Style::default().bg(TERM_BLACK))
+            .title(format!(" {} ", label))
+            .title_style(Style::default().fg(TERM_GREEN_DIM).add_modifier(Modifier::BOLD));
+        let inner = block.inner(*tile);
+        f.render_widget(block, *tile);
+
+        let style = Style::default().fg(color).bg(TERM_BLACK).add_modifier(Modifier::BOLD);
+        let big = big_text(&value);
+        let lines: Vec<Line> = if big[0].chars().count() <= inner.width as usize && inner.height >= 5 {
+            big.into_iter().map(|row| Line::from(Span::styled(row, style))).collect()
+        } else {
+            vec![Line::from(Span::styled(value, style))]
+        };
+        let top = inner.height.saturating_sub(lines.len() as u16) / 2;
+        let area = Rect { y: inner.y + top, height: inner.height - top, ..inner };
+        f.render_widget(Paragraph::new(lines).alignment(ratatui::layout::Alignment::Center), area);
+    }
+
+    let messages = format!(
+        " ACTIVITY · {} MSGS · {} TOOL CALLS TODAY ",
+        app.today.total_messages, app.today.total_tools
+    );
+    let sparkline = Sparkline::default()
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TERM_GREEN_DIM))
+                .border_style(Style::default().fg(TERM_GREEN_DARK))
                 .style(Style::default().bg(TERM_BLACK))
-                .title(" COST BY AGENT ")
-                .title_style(Style::default().fg(TERM_GREEN)),
-        );
-    f.render_widget(cost_list, chunks[1]);
+                .title(messages)
+                .title_style(Style::default().fg(TERM_GREEN_DIM)),
+        )
+        .data(&app.sparkline_data)
+        .style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK));
+    f.render_widget(sparkline, rows[1]);
+}
+
+/// `text` in a five-row block font; characters without a glyph are blank.
+fn big_text(text: &str) -> [String; 5] {
+    let mut rows: [String; 5] = Default::default();
+    for c in text.chars() {
+        let glyph: [&str; 5] = match c {
+            '0' => ["███", "█ █", "█ █", "█ █", "███"],
+            '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+            '2' => ["███", "  █", "███", "█  ", "███"],
+            '3' => ["███", "  █", "███", "  █", "███"],
+            '4' => ["█ █", "█ █", "███", "  █", "  █"],
+            '5' => ["███", "█  ", "███", "  █", "███"],
+            '6' => ["███", "█  ", "███", "█ █", "███"],
+            '7' => ["███", "  █", "  █", "  █", "  █"],
+            '8' => ["███", "█ █", "███", "█ █", "███"],
+            '9' => ["███", "█ █", "███", "  █", "███"],
+            '$' => ["▄█▄", "█▄ ", " ▀▄", "▄▄█", " ▀ "],
+            '.' => ["   ", "   ", "   ", "   ", " █ "],
+            _ => ["   ", "   ", "   ", "   ", "   "],
+        };
+        for (row, part) in rows.iter_mut().zip(glyph) {
+            row.push_str(part);
+            row.push(' ');
+        }
+    }
+    rows
 }
 
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
     let blink = if app.animation_frame % 4 < 2 { "█" } else { " " };
 
-    let help_text = format!(
-        " READY{} | ↑↓/jk:NAV | ENTER:VIEW | TAB:SWITCH | r:REFRESH | q:QUIT ",
-        blink
-    );
+    let help_text = match app.tag_input {
+        Some(ref input) => format!(
+            " TAG> {}{} | ENTER:APPLY | ESC:CANCEL | -TAG REMOVES ",
+            input, blink
+        ),
+        None if app.tab_index == HISTORY_TAB => format!(
+            " READY{} | ↑↓/jk:NAV | SPACE:FOLD DAY | TAB:SWITCH | a:TIMES | r:REFRESH | q:QUIT ",
+            blink
+        ),
+        None => format!(
+            " READY{} | ↑↓/jk:NAV | ENTER:VIEW | TAB:SWITCH | t:TAG | a:TIMES | r:REFRESH | q:QUIT ",
+            blink
+        ),
+    };
 
     let footer = Paragraph::new(help_text)
         .style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK))
@@ -938,6 +2430,7 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),  // Header
+            Constraint::Length(4),  // Timeline
             Constraint::Min(10),    // Events/conversation
             Constraint::Length(3),  // Footer
         ])
@@ -953,29 +2446,78 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
     // Header with session info
     let title = if let Some(s) = session {
         let project_name = s.project_path.split('/').last().unwrap_or("UNKNOWN");
+        let compactions = match s.compactions {
+            0 => String::new(),
+            1 => " | ⟲ 1 COMPACTION".to_string(),
+            n => format!(" | ⟲ {} COMPACTIONS", n),
+        };
+        let looping = if app.looping.contains(&s.id) { " | ⟳ POSSIBLE LOOP" } else { "" };
+        let related = match app.related {
+            Some(ref r) if r.total > 1 => format!(" | SESSION {}/{} OF PROJECT", r.index + 1, r.total),
+            _ => String::new(),
+        };
         format!(
-            " {} | {} | {} msgs | ${:.4} ",
+            " {} | {} | {} msgs | ${:.4}{}{}{} ",
             project_name.to_uppercase(),
             s.agent_type.to_string().to_uppercase(),
             s.message_count,
-            s.estimated_cost
+            s.estimated_cost,
+            compactions,
+            looping,
+            related
         )
     } else {
         " NO SESSION ".to_string()
     };
 
+    let mut header_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(TERM_GREEN))
+        .style(Style::default().bg(TERM_GREEN));
+    if let Some(ref hint) = app.duplicate_hint {
+        header_block = header_block.title(Span::styled(
+            hint.as_str(),
+            Style::default().fg(TERM_AMBER).bg(TERM_BLACK).add_modifier(Modifier::BOLD),
+        ));
+    }
     let header = Paragraph::new(title)
         .style(Style::default().fg(TERM_BLACK).bg(TERM_GREEN).add_modifier(Modifier::BOLD))
+        .block(header_block);
+    f.render_widget(header, chunks[0]);
+
+    render_timeline(f, chunks[1], app);
+
+    if app.show_turns {
+        render_turns(f, chunks[2], app);
+    } else {
+        render_event_list(f, chunks[2], app);
+    }
+
+    // Footer with controls
+    let footer_text = match app.goto_input {
+        Some(ref input) => {
+            let blink = if app.animation_frame % 4 < 2 { "█" } else { " " };
+            format!(" GOTO> {}{} | HH:MM[:SS] OR -10m/+1h | ENTER:JUMP | ESC:CANCEL ", input, blink)
+        }
+        None if app.show_turns => " ↑↓:SELECT TURN | ENTER/t:EVENTS | ESC/q:CLOSE ".to_string(),
+        None => " ↑↓:SELECT | PGUP/PGDN/HOME/END | g:GOTO | ←→:SCROLL | [ ]:TIMELINE | < >:PROJECT SESSIONS | t:TURNS | a:TIMES | ENTER:EXPAND | ESC/q:CLOSE "
+            .to_string(),
+    };
+    let footer = Paragraph::new(footer_text)
+        .style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TERM_GREEN))
-                .style(Style::default().bg(TERM_GREEN)),
+                .border_style(Style::default().fg(TERM_GREEN_DARK))
+                .style(Style::default().bg(TERM_BLACK)),
         );
-    f.render_widget(header, chunks[0]);
+    f.render_widget(footer, chunks[3]);
+}
 
+/// The session's events, one per row, with the selected one highlighted.
+fn render_event_list(f: &mut Frame, area: Rect, app: &App) {
     // Events/conversation list with selection
-    let visible_count = (chunks[1].height as usize).saturating_sub(2);
+    let visible_count = (area.height as usize).saturating_sub(2);
     let content_width = (area.width as usize).saturating_sub(4);
     let h_scroll = app.event_horizontal_scroll;
 
@@ -987,21 +2529,10 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
         .map(|(idx, event)| {
             let is_selected = idx == app.selected_event_index;
 
-            let (icon, color) = match event.event_type {
-                EventType::PromptReceived => ("→ USER  ", TERM_AMBER),
-                EventType::ResponseGenerated => ("← AGENT ", TERM_GREEN),
-                EventType::Thinking => ("◊ THINK ", Color::Rgb(150, 150, 255)),
-                EventType::ToolStart => ("▶ TOOL  ", Color::Rgb(100, 200, 255)),
-                EventType::ToolComplete | EventType::ToolExecuted => ("◀ DONE  ", Color::Rgb(100, 200, 255)),
-                EventType::FileRead => ("◉ READ  ", Color::Rgb(255, 200, 100)),
-                EventType::FileModified => ("◉ WRITE ", Color::Rgb(255, 150, 100)),
-                EventType::Error => ("✗ ERR   ", TERM_RED),
-                EventType::SessionStart => ("● START ", TERM_GREEN),
-                EventType::SessionEnd => ("○ END   ", TERM_GREEN_DIM),
-                EventType::Custom => ("? MISC  ", TERM_GREEN_DIM),
-            };
+            let (icon, color) = event_label(event.event_type);
 
-            let time = event.timestamp.format("%H:%M:%S").to_string();
+            // Relative times are padded to line up with clock times
+            let time = format!("{:>8}", timefmt::time(event.timestamp));
 
             // Get full content
             let content = event.content.as_deref()
@@ -1022,11 +2553,16 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
 
             // Truncate for display (but show ... to indicate more)
             let max_width = content_width.saturating_sub(20);
-            let display_text = if content_display.len() > max_width {
+            let mut display_text = if content_display.len() > max_width {
                 format!("{}→", &content_display[..max_width.saturating_sub(1)])
             } else {
                 content_display.to_string()
             };
+            // Everything above a compaction was summarized away
+            let is_compaction = event.event_type == EventType::Compaction;
+            if is_compaction {
+                display_text = format!("━━ MEMORY LOSS ━━ {}", display_text);
+            }
 
             // Selection indicator
             let selector = if is_selected { "▶" } else { " " };
@@ -1034,6 +2570,8 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
             // Style based on selection
             let (fg, bg) = if is_selected {
                 (TERM_BLACK, color)
+            } else if is_compaction {
+                (TERM_MAGENTA, TERM_BLACK)
             } else {
                 (TERM_GREEN, TERM_BLACK)
             };
@@ -1063,19 +2601,267 @@ fn render_full_detail_view(f: &mut Frame, area: Rect, app: &App) {
                 .title(scroll_info)
                 .title_style(Style::default().fg(TERM_GREEN)),
         );
-    f.render_widget(events_list, chunks[1]);
+    f.render_widget(events_list, area);
+}
 
-    // Footer with controls
-    let footer_text = " ↑↓:SELECT | ←→:SCROLL | ENTER:EXPAND | ESC/q:CLOSE ";
-    let footer = Paragraph::new(footer_text)
+/// What each prompt added to the context: a row per turn with a bar scaled
+/// to the largest turn's tokens, and the selected turn's files and tools.
+fn render_turns(f: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(area);
+
+    let turns = turns::turns(&app.session_events);
+    let selected = app.selected_turn(&turns);
+    let max_tokens = turns.iter().map(Turn::tokens_added).max().unwrap_or(0).max(1);
+    const BAR_WIDTH: usize = 12;
+
+    let header = Row::new(["#", "TIME", "+TOKENS", "", "NEW FILES", "TOOLS", "PROMPT"].iter().map(|h| {
+        Cell::from(*h).style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK).add_modifier(Modifier::BOLD))
+    }));
+
+    // Keep the selected turn in view
+    let visible_rows = (chunks[0].height as usize).saturating_sub(3).max(1);
+    let offset = selected.map_or(0, |i| (i + 1).saturating_sub(visible_rows));
+    let rows: Vec<Row> = turns
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible_rows)
+        .map(|(i, turn)| {
+            let is_selected = selected == Some(i);
+            let (fg, bg) = if is_selected { (TERM_BLACK, TERM_GREEN) } else { (TERM_GREEN, TERM_BLACK) };
+            let tokens = turn.tokens_added();
+            let bar_len = (tokens as f64 / max_tokens as f64 * BAR_WIDTH as f64).ceil() as usize;
+            // The biggest turns are the likely culprits
+            let bar_color = if is_selected {
+                TERM_BLACK
+            } else if tokens * 2 >= max_tokens {
+                TERM_AMBER
+            } else {
+                TERM_GREEN_DIM
+            };
+            let prompt = match turn.number {
+                0 => "(before first prompt)".to_string(),
+                _ => turn.prompt.clone().unwrap_or_default(),
+            };
+            let marker = if turn.compacted { "⟲ " } else { "" };
+            Row::new(vec![
+                Cell::from(format!("{:>3}", turn.number)),
+                Cell::from(timefmt::time(turn.started_at)),
+                Cell::from(format!("{:>7}", humanize::tokens(tokens))),
+                Cell::from("█".repeat(bar_len.min(BAR_WIDTH))).style(Style::default().fg(bar_color)),
+                Cell::from(format!("{:>5}", turn.new_files.len())),
+                Cell::from(format!("{:>5}", turn.tool_runs())),
+                Cell::from(format!("{}{}", marker, prompt)),
+            ])
+            .style(Style::default().fg(fg).bg(bg))
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Length(BAR_WIDTH as u16),
+            Constraint::Length(9),
+            Constraint::Length(5),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(TERM_GREEN_DIM))
+            .style(Style::default().bg(TERM_BLACK))
+            .title(format!(" CONTEXT PER PROMPT [{}/{}] ", selected.map_or(0, |i| i + 1), turns.len()))
+            .title_style(Style::default().fg(TERM_GREEN)),
+    )
+    .style(Style::default().bg(TERM_BLACK));
+    f.render_widget(table, chunks[0]);
+
+    // What the selected turn pulled in
+    let label = |text: &str| Span::styled(format!("{:<10}", text), Style::default().fg(TERM_GREEN_DIM));
+    let mut lines = Vec::new();
+    if let Some(turn) = selected.and_then(|i| turns.get(i)) {
+        lines.push(Line::from(vec![
+            label("TOKENS"),
+            Span::styled(
+                format!("+{} ({} in / {} out)", humanize::tokens(turn.tokens_added()), humanize::tokens(turn.tokens_input), humanize::tokens(turn.tokens_output)),
+                Style::default().fg(TERM_GREEN).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        lines.push(Line::from(vec![label("EVENTS"), Span::raw(turn.events.to_string())]));
+        if turn.errors > 0 {
+            lines.push(Line::from(vec![label("ERRORS"), Span::styled(turn.errors.to_string(), Style::default().fg(TERM_RED))]));
+        }
+        if turn.compacted {
+            lines.push(Line::from(Span::styled("⟲ CONTEXT COMPACTED IN THIS TURN", Style::default().fg(TERM_MAGENTA))));
+        }
+        lines.push(Line::default());
+
+        let mut tools: Vec<(&String, &usize)> = turn.tools.iter().collect();
+        tools.sort_by(|a, b| b.1.cmp(a.1));
+        lines.push(Line::from(Span::styled(format!("TOOLS RUN ({})", turn.tool_runs()), Style::default().fg(TERM_AMBER))));
+        for (name, count) in tools {
+            lines.push(Line::from(format!("  {:>3}× {}", count, name)));
+        }
+        lines.push(Line::default());
+
+        let reread = match turn.reread_files {
+            0 => String::new(),
+            n => format!(", {} re-read", n),
+        };
+        lines.push(Line::from(Span::styled(
+            format!("NEW FILES READ ({}{})", turn.new_files.len(), reread),
+            Style::default().fg(TERM_AMBER),
+        )));
+        lines.extend(turn.new_files.iter().map(|path| Line::from(format!("  + {}", path))));
+        if !turn.modified_files.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(
+                format!("FILES MODIFIED ({})", turn.modified_files.len()),
+                Style::default().fg(TERM_AMBER),
+            )));
+            lines.extend(turn.modified_files.iter().map(|path| Line::from(format!("  ~ {}", path))));
+        }
+    }
+
+    let detail = Paragraph::new(lines)
         .style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TERM_GREEN_DARK))
-                .style(Style::default().bg(TERM_BLACK)),
+                .border_style(Style::default().fg(TERM_GREEN_DIM))
+                .style(Style::default().bg(TERM_BLACK))
+                .title(" ADDED THIS TURN ")
+                .title_style(Style::default().fg(TERM_GREEN)),
         );
-    f.render_widget(footer, chunks[2]);
+    f.render_widget(detail, chunks[1]);
+}
+
+/// A syntax-highlighted line, less its first `skip` bytes (horizontal
+/// scroll).
+fn highlighted_line(runs: Vec<highlight::Run>, skip: usize) -> Line<'static> {
+    let spans: Vec<Span> = runs
+        .into_iter()
+        .map(|(color, text)| {
+            let style = match color {
+                Some((r, g, b)) => Style::default().fg(Color::Rgb(r, g, b)),
+                None => Style::default(),
+            };
+            Span::styled(text.to_string(), style)
+        })
+        .collect();
+    scrolled_line(Line::from(spans), skip)
+}
+
+/// `line` without its first `skip` bytes, keeping span styles.
+fn scrolled_line(line: Line<'static>, mut skip: usize) -> Line<'static> {
+    let spans: Vec<Span> = line
+        .spans
+        .into_iter()
+        .filter_map(|span| {
+            if skip >= span.content.len() {
+                skip -= span.content.len();
+                return None;
+            }
+            let shown = span.content.get(skip..).unwrap_or("").to_string();
+            skip = 0;
+            Some(Span::styled(shown, span.style))
+        })
+        .collect();
+    Line::from(spans)
+}
+
+/// List label and color of an event type.
+fn event_label(event_type: EventType) -> (&'static str, Color) {
+    match event_type {
+        EventType::PromptReceived => ("→ USER  ", TERM_AMBER),
+        EventType::ResponseGenerated => ("← AGENT ", TERM_GREEN),
+        EventType::Thinking => ("◊ THINK ", Color::Rgb(150, 150, 255)),
+        EventType::ToolStart => ("▶ TOOL  ", Color::Rgb(100, 200, 255)),
+        EventType::ToolComplete | EventType::ToolExecuted => ("◀ DONE  ", Color::Rgb(100, 200, 255)),
+        EventType::FileRead => ("◉ READ  ", Color::Rgb(255, 200, 100)),
+        EventType::FileModified => ("◉ WRITE ", Color::Rgb(255, 150, 100)),
+        EventType::Error => ("✗ ERR   ", TERM_RED),
+        EventType::ApiError => ("✗ API   ", TERM_RED),
+        EventType::PolicyDecision => ("⊘ POLICY", TERM_AMBER),
+        EventType::Compaction => ("⟲ COMPCT", TERM_MAGENTA),
+        EventType::ConfigChanged => ("⚙ CONFIG", TERM_AMBER),
+        EventType::Anomaly => ("⚠ BURST ", TERM_RED),
+        EventType::Loop => ("⟳ LOOP  ", TERM_AMBER),
+        EventType::SessionStart => ("● START ", TERM_GREEN),
+        EventType::SessionEnd => ("○ END   ", TERM_GREEN_DIM),
+        EventType::Custom => ("? MISC  ", TERM_GREEN_DIM),
+    }
+}
+
+/// Event density across the session, one column per slice of time, colored
+/// by the slice's most common event type (red if anything failed), with the
+/// timeline cursor underneath.
+fn render_timeline(f: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(TERM_GREEN_DIM))
+        .style(Style::default().bg(TERM_BLACK));
+    let inner = block.inner(area);
+    let (Some(span), Some(cursor)) = (app.timeline_span(), app.timeline_cursor_step()) else {
+        f.render_widget(block.title(" TIMELINE ").title_style(Style::default().fg(TERM_GREEN)), area);
+        return;
+    };
+    let width = inner.width.max(1) as usize;
+    let column = |step: usize| step * (width - 1) / TIMELINE_STEPS;
+
+    let mut columns: Vec<Vec<EventType>> = vec![Vec::new(); width];
+    for event in &app.session_events {
+        columns[column(App::timeline_step(span, event.timestamp))].push(event.event_type);
+    }
+    let busiest = columns.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+    const LEVELS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    let density: Vec<Span> = columns
+        .iter()
+        .map(|types| {
+            let Some(&first) = types.first() else {
+                return Span::styled("·", Style::default().fg(TERM_GREEN_DARK));
+            };
+            let level = (types.len() * 8).div_ceil(busiest).clamp(1, 8);
+            let color = if types.contains(&EventType::Error) {
+                TERM_RED
+            } else {
+                let common = types
+                    .iter()
+                    .copied()
+                    .max_by_key(|t| types.iter().filter(|other| *other == t).count())
+                    .unwrap_or(first);
+                event_label(common).1
+            };
+            Span::styled(LEVELS[level], Style::default().fg(color))
+        })
+        .collect();
+
+    let cursor_col = column(cursor);
+    let cursor_color = if app.timeline_cursor.is_some() { TERM_AMBER } else { TERM_GREEN_DIM };
+    let marker = Line::from(vec![
+        Span::raw(" ".repeat(cursor_col)),
+        Span::styled("▲", Style::default().fg(cursor_color).add_modifier(Modifier::BOLD)),
+    ]);
+
+    let title = format!(
+        " TIMELINE {} → {} · ▲ {} ",
+        timefmt::clock(span.0),
+        timefmt::clock(span.1),
+        timefmt::clock(App::timeline_time(span, cursor))
+    );
+    let strip = Paragraph::new(vec![Line::from(density), marker])
+        .style(Style::default().bg(TERM_BLACK))
+        .block(block.title(title).title_style(Style::default().fg(TERM_GREEN)));
+    f.render_widget(strip, area);
 }
 
 /// Render an expanded event showing full content
@@ -1104,10 +2890,20 @@ fn render_expanded_event(f: &mut Frame, area: Rect, app: &App, event_idx: usize)
         EventType::FileRead => ("FILE READ", Color::Rgb(255, 200, 100)),
         EventType::FileModified => ("FILE WRITE", Color::Rgb(255, 150, 100)),
         EventType::Error => ("ERROR", TERM_RED),
+        EventType::ApiError => ("API ERROR", TERM_RED),
+        EventType::PolicyDecision => ("POLICY DECISION", TERM_AMBER),
+        EventType::Compaction => ("CONTEXT COMPACTED", TERM_MAGENTA),
+        EventType::ConfigChanged => ("SETTINGS CHANGED", TERM_AMBER),
+        EventType::Anomaly => ("UNUSUAL ACTIVITY", TERM_RED),
+        EventType::Loop => ("POSSIBLE LOOP", TERM_AMBER),
         _ => ("EVENT", TERM_GREEN_DIM),
     };
 
-    let time = event.timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+    let time = format!(
+        "{} · {}",
+        timefmt::absolute(event.timestamp, "%Y-%m-%d %H:%M:%S"),
+        timefmt::ago(event.timestamp, Utc::now())
+    );
     let title = format!(" {} | {} ", icon, time);
 
     let header = Paragraph::new(title)
@@ -1121,30 +2917,66 @@ fn render_expanded_event(f: &mut Frame, area: Rect, app: &App, event_idx: usize)
     f.render_widget(header, chunks[0]);
 
     // Full content with word wrap
-    let content = event.content.as_deref()
-        .or(event.tool_name.as_deref())
-        .or(event.file_path.as_deref())
-        .unwrap_or("(no content)");
+    let content = app.expanded_content(event_idx);
 
-    let total_lines = content.lines().count();
     let visible_lines = chunks[1].height.saturating_sub(2) as usize;
     let v_scroll = app.expanded_vertical_scroll;
     let h_scroll = app.event_horizontal_scroll;
 
-    // Apply horizontal scroll to each line
-    let display_content: String = if h_scroll > 0 {
-        content.lines()
-            .map(|line| {
-                if h_scroll < line.len() {
-                    &line[h_scroll..]
-                } else {
-                    ""
+    // Search matches per line, the current one marked
+    let matches = app.search_matches();
+    let mut by_line: std::collections::HashMap<usize, Vec<(std::ops::Range<usize>, bool)>> = std::collections::HashMap::new();
+    for (i, (line, range)) in matches.iter().enumerate() {
+        by_line.entry(*line).or_default().push((range.clone(), i == app.search_match));
+    }
+
+    // Apply horizontal scroll to each line, then highlight what's left of
+    // each match
+    let display_content: Vec<Line> = if app.shows_markdown(event_idx) {
+        markdown::to_lines(content)
+            .into_iter()
+            .map(|line| scrolled_line(line, h_scroll))
+            .collect()
+    } else if !app.raw_view && app.search.is_none() && highlight::has_code(content) {
+        highlight::highlight(content)
+            .into_iter()
+            .map(|runs| highlighted_line(runs, h_scroll))
+            .collect()
+    } else {
+        content
+            .lines()
+            .enumerate()
+            .map(|(n, line)| {
+                let shown = line.get(h_scroll..).unwrap_or("");
+                let Some(ranges) = by_line.get(&n) else {
+                    return Line::from(shown.to_string());
+                };
+                let mut spans = Vec::new();
+                let mut at = 0;
+                for (range, current) in ranges {
+                    let start = range.start.saturating_sub(h_scroll).max(at);
+                    let end = range.end.saturating_sub(h_scroll);
+                    let (Some(before), Some(found)) = (shown.get(at..start), shown.get(start..end)) else {
+                        continue;
+                    };
+                    let bg = if *current { TERM_AMBER } else { TERM_GREEN_DIM };
+                    spans.push(Span::raw(before.to_string()));
+                    spans.push(Span::styled(found.to_string(), Style::default().fg(TERM_BLACK).bg(bg).add_modifier(Modifier::BOLD)));
+                    at = end;
                 }
+                spans.push(Span::raw(shown.get(at..).unwrap_or("").to_string()));
+                Line::from(spans)
             })
-            .collect::<Vec<_>>()
-            .join("\n")
-    } else {
-        content.to_string()
+            .collect()
+    };
+
+    let total_lines = display_content.len();
+    let view = if app.raw_view { "| RAW " } else { "" };
+
+    let search_info = match (&app.search, matches.len()) {
+        (None, _) => String::new(),
+        (Some(pattern), 0) => format!("| /{}/ NO MATCHES ", pattern),
+        (Some(pattern), n) => format!("| /{}/ {}/{} ", pattern, app.search_match + 1, n),
     };
 
     let content_para = Paragraph::new(display_content)
@@ -1156,8 +2988,8 @@ fn render_expanded_event(f: &mut Frame, area: Rect, app: &App, event_idx: usize)
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(TERM_GREEN_DIM))
                 .style(Style::default().bg(TERM_BLACK))
-                .title(format!(" LINE {}/{} | {} chars | h:{} ",
-                    v_scroll + 1, total_lines, content.len(), h_scroll))
+                .title(format!(" LINE {}/{} | {} chars | h:{} {}{}",
+                    v_scroll + 1, total_lines, content.len(), h_scroll, view, search_info))
                 .title_style(Style::default().fg(TERM_GREEN)),
         );
     f.render_widget(content_para, chunks[1]);
@@ -1174,7 +3006,18 @@ fn render_expanded_event(f: &mut Frame, area: Rect, app: &App, event_idx: usize)
     } else {
         "↑↓:SCROLL"
     };
-    let footer_text = format!(" {} | ←→:H-SCROLL | ENTER:COLLAPSE | ESC:CLOSE ", nav_hint);
+    let footer_text = match (&app.search_input, &app.search_error) {
+        (Some(input), Some(error)) => format!(" /{}█ | {} ", input, error.to_uppercase()),
+        (Some(input), None) => format!(" /{}█ | REGEX | ENTER:SEARCH | ESC:CANCEL ", input),
+        (None, _) if app.search.is_some() => {
+            format!(" {} | n/N:NEXT/PREV MATCH | /:SEARCH | ENTER:COLLAPSE | ESC:CLOSE ", nav_hint)
+        }
+        (None, _) => format!(
+            " {} | ←→:H-SCROLL | /:SEARCH | r:{} | ENTER:COLLAPSE | ESC:CLOSE ",
+            nav_hint,
+            if app.raw_view { "RENDERED" } else { "RAW" }
+        ),
+    };
     let footer = Paragraph::new(footer_text)
         .style(Style::default().fg(TERM_GREEN).bg(TERM_BLACK))
         .block(
@@ -1194,22 +3037,3 @@ fn truncate_str(s: &str, max_len: usize) -> String {
     }
 }
 
-fn format_tokens(count: i64) -> String {
-    if count >= 1_000_000 {
-        format!("{:.1}M", count as f64 / 1_000_000.0)
-    } else if count >= 1_000 {
-        format!("{:.1}K", count as f64 / 1_000.0)
-    } else {
-        format!("{}", count)
-    }
-}
-
-fn format_duration(seconds: f64) -> String {
-    if seconds >= 3600.0 {
-        format!("{:.1}h", seconds / 3600.0)
-    } else if seconds >= 60.0 {
-        format!("{:.1}m", seconds / 60.0)
-    } else {
-        format!("{:.0}s", seconds)
-    }
-}
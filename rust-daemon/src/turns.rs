@@ -0,0 +1,218 @@
+//! What each user prompt added to a session's context.
+//!
+//! A turn is one prompt and everything up to the next: the files the agent
+//! read, the tools it ran and the tokens exchanged. Files already read in an
+//! earlier turn aren't counted as new, so a turn that pulled a lot into the
+//! context stands out next to the ones before it. Activity before the first
+//! prompt forms a turn of its own.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::models::{EventType, SessionEvent};
+use crate::storage::Storage;
+
+/// Most events read to build a session's turns.
+const MAX_EVENTS: usize = 5_000;
+
+/// Tools that read a file, for agents that log reads as tool calls.
+const READ_TOOLS: &[&str] = &["Read", "read_file", "view"];
+
+/// Tools that write a file.
+const WRITE_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit", "edit_file", "write_file"];
+
+/// One prompt and what followed it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Turn {
+    /// 1-based prompt number; 0 for activity before the first prompt
+    pub number: usize,
+    pub started_at: DateTime<Utc>,
+    /// First line of the prompt
+    pub prompt: Option<String>,
+    /// Words in the whole prompt
+    pub prompt_words: usize,
+    pub tokens_input: i64,
+    pub tokens_output: i64,
+    /// Files read for the first time in the session
+    pub new_files: Vec<String>,
+    /// Reads of files an earlier turn already read
+    pub reread_files: usize,
+    pub modified_files: Vec<String>,
+    /// Runs per tool name
+    pub tools: BTreeMap<String, usize>,
+    pub events: usize,
+    pub errors: usize,
+    /// Whether the context was compacted during the turn
+    pub compacted: bool,
+    /// Whether the agent called a tool or changed a file, rather than only
+    /// answering
+    pub acted: bool,
+}
+
+impl Turn {
+    fn new(number: usize, started_at: DateTime<Utc>, prompt: Option<String>, prompt_words: usize) -> Self {
+        Self {
+            number,
+            started_at,
+            prompt,
+            prompt_words,
+            tokens_input: 0,
+            tokens_output: 0,
+            new_files: Vec::new(),
+            reread_files: 0,
+            modified_files: Vec::new(),
+            tools: BTreeMap::new(),
+            events: 0,
+            errors: 0,
+            compacted: false,
+            acted: false,
+        }
+    }
+
+    /// Tokens exchanged during the turn.
+    pub fn tokens_added(&self) -> i64 {
+        self.tokens_input + self.tokens_output
+    }
+
+    /// Tool runs during the turn.
+    pub fn tool_runs(&self) -> usize {
+        self.tools.values().sum()
+    }
+}
+
+/// Turns of a session, oldest first.
+pub async fn session_turns(storage: &Storage, session_id: &str) -> Result<Vec<Turn>> {
+    let events = storage.get_session_events(session_id, MAX_EVENTS).await?;
+    Ok(turns(&events))
+}
+
+fn is_tool(event: &SessionEvent, names: &[&str]) -> bool {
+    matches!(event.event_type, EventType::ToolStart | EventType::ToolExecuted)
+        && event.tool_name.as_deref().is_some_and(|name| names.contains(&name))
+}
+
+/// Split `events` (in any order) into turns, oldest first.
+pub fn turns(events: &[SessionEvent]) -> Vec<Turn> {
+    let mut events: Vec<&SessionEvent> = events.iter().collect();
+    events.sort_by_key(|e| e.timestamp);
+
+    let mut turns: Vec<Turn> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    // Files touched in the current turn, to count each once
+    let mut read: BTreeSet<&str> = BTreeSet::new();
+    let mut modified: BTreeSet<&str> = BTreeSet::new();
+    let mut prompts = 0;
+
+    for event in events {
+        if event.event_type == EventType::PromptReceived {
+            prompts += 1;
+            read.clear();
+            modified.clear();
+            let prompt = event.content.as_deref().and_then(|c| c.lines().next()).map(str::to_string);
+            let words = event.content.as_deref().map_or(0, |c| c.split_whitespace().count());
+            turns.push(Turn::new(prompts, event.timestamp, prompt, words));
+        }
+        if turns.is_empty() {
+            turns.push(Turn::new(0, event.timestamp, None, 0));
+        }
+        let Some(turn) = turns.last_mut() else {
+            continue;
+        };
+
+        turn.events += 1;
+        // Transcripts log tool calls on the response that made them
+        turn.acted |= event.event_type != EventType::PromptReceived
+            && (event.tool_name.is_some() || event.event_type == EventType::FileModified);
+        turn.tokens_input += event.tokens_input.unwrap_or(0);
+        turn.tokens_output += event.tokens_output.unwrap_or(0);
+        match event.event_type {
+            EventType::ToolStart | EventType::ToolExecuted => {
+                let name = event.tool_name.as_deref().unwrap_or("unknown");
+                *turn.tools.entry(name.to_string()).or_insert(0) += 1;
+            }
+            EventType::Error | EventType::ApiError => turn.errors += 1,
+            EventType::Compaction => turn.compacted = true,
+            _ => {}
+        }
+
+        let Some(path) = event.file_path.as_deref() else {
+            continue;
+        };
+        if event.event_type == EventType::FileRead || is_tool(event, READ_TOOLS) {
+            if !read.insert(path) {
+                continue;
+            }
+            if seen.insert(path) {
+                turn.new_files.push(path.to_string());
+            } else {
+                turn.reread_files += 1;
+            }
+        } else if (event.event_type == EventType::FileModified || is_tool(event, WRITE_TOOLS)) && modified.insert(path) {
+            turn.modified_files.push(path.to_string());
+        }
+    }
+    turns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AgentType;
+    use chrono::Duration;
+
+    #[test]
+    fn test_turns_between_prompts() {
+        let start = Utc::now();
+        let mut at = 0;
+        let mut event = |event_type: EventType, tool: Option<&str>, path: Option<&str>, tokens: i64| {
+            let mut event = SessionEvent::new("s", event_type, AgentType::ClaudeCode);
+            at += 1;
+            event.timestamp = start + Duration::seconds(at);
+            event.tool_name = tool.map(str::to_string);
+            event.file_path = path.map(str::to_string);
+            event.tokens_input = (tokens > 0).then_some(tokens);
+            event.content = (event_type == EventType::PromptReceived).then(|| "fix the parser\nplease".to_string());
+            event
+        };
+
+        let mut events = vec![
+            event(EventType::SessionStart, None, None, 0),
+            event(EventType::PromptReceived, None, None, 0),
+            event(EventType::ToolStart, Some("Read"), Some("src/lib.rs"), 0),
+            event(EventType::FileRead, None, Some("src/lib.rs"), 0),
+            event(EventType::ToolStart, Some("Bash"), None, 0),
+            event(EventType::ResponseGenerated, None, None, 1_200),
+            event(EventType::PromptReceived, None, None, 0),
+            event(EventType::ToolStart, Some("Read"), Some("src/lib.rs"), 0),
+            event(EventType::ToolStart, Some("Read"), Some("src/parser.rs"), 0),
+            event(EventType::ToolStart, Some("Edit"), Some("src/parser.rs"), 0),
+            event(EventType::Compaction, None, None, 0),
+            event(EventType::ResponseGenerated, None, None, 30_000),
+        ];
+        // Storage returns newest first
+        events.reverse();
+
+        let turns = turns(&events);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0].number, 0);
+        assert_eq!(turns[0].events, 1);
+
+        let first = &turns[1];
+        assert_eq!(first.prompt.as_deref(), Some("fix the parser"));
+        assert_eq!(first.new_files, vec!["src/lib.rs"]);
+        assert_eq!(first.tool_runs(), 2);
+        assert_eq!(first.tokens_added(), 1_200);
+        assert_eq!(first.prompt_words, 4);
+        assert!(first.acted);
+
+        let second = &turns[2];
+        assert_eq!(second.new_files, vec!["src/parser.rs"]);
+        assert_eq!(second.reread_files, 1);
+        assert_eq!(second.modified_files, vec!["src/parser.rs"]);
+        assert_eq!(second.tools.get("Read"), Some(&2));
+        assert!(second.compacted);
+        assert_eq!(second.tokens_added(), 30_000);
+    }
+}
@@ -0,0 +1,345 @@
+//! What `agent-monitor uninstall` removes.
+//!
+//! Installing leaves hook scripts in `~/.claude/hooks`, hook and statusline
+//! entries in `~/.claude/settings.json`, maybe a LaunchAgent or systemd user
+//! unit, and the daemon's socket. Those always go: they are what makes
+//! Claude Code call a binary that may be about to disappear. The monitor's
+//! data (sessions, trash, archives, keys) and the config directory are only
+//! removed when asked for. Other hooks and settings are left as they are; only
+//! hook scripts this monitor wrote are deleted, and of the data directory only
+//! the files the monitor keeps there.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::archive;
+use crate::config::Config;
+use crate::hooks::{self, HookSetup};
+use crate::statusline;
+use crate::storage::{self, Storage};
+use crate::HOOK_EVENTS;
+
+/// Label of the LaunchAgent `install.sh` sets up.
+const LAUNCHD_LABEL: &str = "com.user.agent-monitor";
+
+/// Name of a systemd user unit running the daemon.
+const SYSTEMD_UNIT: &str = "agent-monitor.service";
+
+/// What the monitor keeps in its data directory besides the database.
+const DATA_ENTRIES: &[&str] = &[
+    "spool",
+    "trash",
+    "archive",
+    "profiles",
+    "db.key",
+    "ipc_token",
+    "claude_settings_seen.json",
+    "digest_last_sent",
+    "timetrack_last_pushed",
+    "export_last_run",
+    "provider_incidents.json",
+];
+
+/// Marks a hook script written by `install-hooks`.
+const SCRIPT_MARKER: &str = "# Agent Monitor hook for ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResidueKind {
+    Hook,
+    Settings,
+    Service,
+    Socket,
+    Data,
+    Config,
+}
+
+impl ResidueKind {
+    /// Whether it is only removed when asked for.
+    pub fn is_optional(self) -> bool {
+        matches!(self, Self::Data | Self::Config)
+    }
+}
+
+/// One thing the monitor left behind.
+#[derive(Debug, Clone, Serialize)]
+pub struct Residue {
+    pub kind: ResidueKind,
+    pub path: PathBuf,
+    pub detail: String,
+    /// For data, the files and directories under `path` that are removed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<PathBuf>,
+}
+
+impl Residue {
+    fn new(kind: ResidueKind, path: PathBuf, detail: impl Into<String>) -> Self {
+        Self { kind, path, detail: detail.into(), entries: Vec::new() }
+    }
+}
+
+/// Everything of the monitor's under `home` and in `config`'s directories.
+pub fn plan(config: &Config, setup: &HookSetup, home: &Path) -> Result<Vec<Residue>> {
+    let mut residue = Vec::new();
+
+    for event in HOOK_EVENTS {
+        let script = setup.hooks_dir.join(format!("{}.sh", event));
+        let ours = std::fs::read_to_string(&script).is_ok_and(|c| c.contains(SCRIPT_MARKER));
+        if ours {
+            residue.push(Residue::new(ResidueKind::Hook, script, format!("{} hook script", event)));
+        }
+    }
+
+    if setup.settings_path.exists() {
+        let mut settings: Value = serde_json::from_str(&std::fs::read_to_string(&setup.settings_path)?)?;
+        let hooks = hooks::unregister(&mut settings);
+        let statusline = statusline::uninstall(&mut settings).is_some();
+        let mut parts = Vec::new();
+        if hooks > 0 {
+            parts.push(format!("{} hook entries", hooks));
+        }
+        if statusline {
+            parts.push("the statusLine".to_string());
+        }
+        if !parts.is_empty() {
+            residue.push(Residue::new(ResidueKind::Settings, setup.settings_path.clone(), parts.join(" and ")));
+        }
+    }
+
+    let services = [
+        (home.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL)), "LaunchAgent"),
+        (home.join("Library/Logs/agent-monitor.log"), "LaunchAgent log"),
+        (home.join("Library/Logs/agent-monitor-error.log"), "LaunchAgent error log"),
+        (home.join(".config/systemd/user").join(SYSTEMD_UNIT), "systemd user unit"),
+    ];
+    for (path, detail) in services {
+        if path.exists() {
+            residue.push(Residue::new(ResidueKind::Service, path, detail));
+        }
+    }
+
+    if config.socket_path.exists() {
+        residue.push(Residue::new(ResidueKind::Socket, config.socket_path.clone(), "daemon socket"));
+    }
+
+    let entries = data_entries(config);
+    if !entries.is_empty() {
+        let mut detail = "sessions, trash and archives".to_string();
+        if config.database_url.is_some() {
+            detail = "local files; the configured database is not touched".to_string();
+        }
+        let profiles = config.data_dir.join("profiles");
+        if let Ok(entries) = std::fs::read_dir(&profiles) {
+            detail.push_str(&format!(", including {} profiles", entries.count()));
+        }
+        let mut data = Residue::new(ResidueKind::Data, config.data_dir.clone(), detail);
+        data.entries = entries;
+        residue.push(data);
+    }
+
+    let mut config_dirs = vec![config.config_dir.clone()];
+    if let Some(dir) = Config::user_config_path().parent() {
+        config_dirs.push(dir.to_path_buf());
+    }
+    config_dirs.dedup();
+    for dir in config_dirs.into_iter().filter(|d| d.exists()) {
+        residue.push(Residue::new(ResidueKind::Config, dir, "configuration"));
+    }
+
+    Ok(residue)
+}
+
+/// The monitor's files that exist: the local database and what it keeps in
+/// the data directory. Anything else there is left alone.
+fn data_entries(config: &Config) -> Vec<PathBuf> {
+    let mut entries = Vec::new();
+    if config.uses_local_db() {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = config.db_path.clone().into_os_string();
+            path.push(suffix);
+            entries.push(PathBuf::from(path));
+        }
+    }
+    entries.extend(DATA_ENTRIES.iter().map(|name| config.data_dir.join(name)));
+    entries.retain(|path| path.exists());
+    entries
+}
+
+/// How many archived sessions outside the data directory are encrypted with
+/// the key file in it, and so can't be restored once it is removed.
+pub async fn archives_needing_key(config: &Config) -> Result<usize> {
+    if !storage::key_path(&config.data_dir).exists() || (config.uses_local_db() && !config.db_path.exists()) {
+        return Ok(0);
+    }
+    let storage = Storage::connect(config).await?;
+    let archived = storage.list_archived_sessions().await?;
+    Ok(archived
+        .iter()
+        .filter(|a| archive::is_encrypted(&a.location) && !Path::new(&a.location).starts_with(&config.data_dir))
+        .count())
+}
+
+/// Remove one thing. Services are stopped first, best effort.
+pub fn remove(residue: &Residue) -> Result<()> {
+    match residue.kind {
+        ResidueKind::Settings => {
+            let mut settings: Value = serde_json::from_str(&std::fs::read_to_string(&residue.path)?)?;
+            hooks::unregister(&mut settings);
+            statusline::uninstall(&mut settings);
+            std::fs::write(&residue.path, serde_json::to_string_pretty(&settings)? + "\n")?;
+        }
+        ResidueKind::Data => {
+            for entry in &residue.entries {
+                if entry.is_dir() {
+                    std::fs::remove_dir_all(entry)?;
+                } else {
+                    std::fs::remove_file(entry)?;
+                }
+            }
+            // Only gone if nothing else was kept there
+            let _ = std::fs::remove_dir(&residue.path);
+        }
+        ResidueKind::Config => std::fs::remove_dir_all(&residue.path)?,
+        ResidueKind::Service => {
+            stop_service(&residue.path);
+            std::fs::remove_file(&residue.path)?;
+        }
+        ResidueKind::Hook | ResidueKind::Socket => std::fs::remove_file(&residue.path)?,
+    }
+    Ok(())
+}
+
+fn stop_service(path: &Path) {
+    let quiet = |program: &str, args: &[&str]| {
+        let _ = std::process::Command::new(program)
+            .args(args)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    };
+    if path.extension().is_some_and(|e| e == "plist") {
+        quiet("launchctl", &["unload", &path.to_string_lossy()]);
+    } else if path.extension().is_some_and(|e| e == "service") {
+        quiet("systemctl", &["--user", "disable", "--now", SYSTEMD_UNIT]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_and_remove_only_what_is_ours() {
+        let home = tempfile::tempdir().unwrap();
+        let home = home.path();
+        let setup = HookSetup {
+            hooks_dir: home.join(".claude/hooks"),
+            settings_path: home.join(".claude/settings.json"),
+            exe: PathBuf::from("/usr/local/bin/agent-monitor"),
+            profile_arg: String::new(),
+        };
+        let mut config = Config::for_profile(None);
+        config.data_dir = home.join("data");
+        config.config_dir = home.join("config");
+        config.socket_path = home.join("daemon.sock");
+        std::fs::create_dir_all(&config.data_dir).unwrap();
+        std::fs::write(&config.db_path, "").unwrap();
+
+        for event in HOOK_EVENTS {
+            hooks::write_script(&setup.hooks_dir, event, &setup.exe, "").unwrap();
+        }
+        std::fs::write(setup.hooks_dir.join("lint.sh"), "#!/bin/bash\nmake lint\n").unwrap();
+        let script = setup.hooks_dir.join("PreToolUse.sh").to_string_lossy().to_string();
+        let settings = serde_json::json!({
+            "model": "opus",
+            "statusLine": { "type": "command", "command": "\"/usr/local/bin/agent-monitor\" claude-statusline" },
+            "hooks": {
+                "PreToolUse": [{ "matcher": "*", "hooks": [
+                    { "type": "command", "command": script },
+                    { "type": "command", "command": "lint.sh" },
+                ]}],
+                "SessionStart": [{ "hooks": [{ "type": "command", "command": "\"/usr/local/bin/agent-monitor\" hook SessionStart" }] }],
+            },
+        });
+        std::fs::write(&setup.settings_path, settings.to_string()).unwrap();
+        // A log, so removing it doesn't try to stop a service on this machine
+        std::fs::create_dir_all(home.join("Library/Logs")).unwrap();
+        std::fs::write(home.join("Library/Logs/agent-monitor.log"), "started\n").unwrap();
+
+        let residue = plan(&config, &setup, home).unwrap();
+        let kinds: Vec<ResidueKind> = residue.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == ResidueKind::Hook).count(), HOOK_EVENTS.len());
+        assert!(kinds.contains(&ResidueKind::Service) && kinds.contains(&ResidueKind::Data));
+        assert!(!kinds.contains(&ResidueKind::Socket));
+        let settings_entry = residue.iter().find(|r| r.kind == ResidueKind::Settings).unwrap();
+        assert_eq!(settings_entry.detail, "2 hook entries and the statusLine");
+
+        for item in residue.iter().filter(|r| !r.kind.is_optional()) {
+            remove(item).unwrap();
+        }
+        let settings: Value = serde_json::from_str(&std::fs::read_to_string(&setup.settings_path).unwrap()).unwrap();
+        assert_eq!(
+            settings,
+            serde_json::json!({
+                "model": "opus",
+                "hooks": { "PreToolUse": [{ "matcher": "*", "hooks": [{ "type": "command", "command": "lint.sh" }] }] },
+            })
+        );
+        assert!(setup.hooks_dir.join("lint.sh").exists());
+        assert!(config.data_dir.exists());
+        let left = plan(&config, &setup, home).unwrap();
+        assert!(left.iter().all(|r| r.kind.is_optional()));
+    }
+
+    #[tokio::test]
+    async fn test_data_removal_spares_other_files_and_needed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_profile(None);
+        config.data_dir = dir.path().to_path_buf();
+        config.db_path = dir.path().join("sessions.db");
+        config.encryption.enabled = true;
+        std::fs::create_dir_all(dir.path().join("trash")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "mine\n").unwrap();
+
+        let storage = Storage::connect(&config).await.unwrap();
+        storage.initialize().await.unwrap();
+        let session = crate::models::Session::new(crate::models::AgentType::ClaudeCode, "/work/app", "ext-1");
+        for location in [
+            dir.path().join("archive/one.json.gz.enc").display().to_string(),
+            "s3://team-archive/two.json.gz.enc".to_string(),
+            "s3://team-archive/three.json.gz".to_string(),
+        ] {
+            let mut session = session.clone();
+            session.id = location.clone();
+            storage
+                .insert_archived_session(&crate::models::ArchivedSession {
+                    session,
+                    first_prompt: None,
+                    location,
+                    archived_at: chrono::Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+        drop(storage);
+        // Only the bucket bundle that is encrypted needs the key
+        assert_eq!(archives_needing_key(&config).await.unwrap(), 1);
+
+        let setup = HookSetup {
+            hooks_dir: dir.path().join("hooks"),
+            settings_path: dir.path().join("settings.json"),
+            exe: PathBuf::from("/usr/local/bin/agent-monitor"),
+            profile_arg: String::new(),
+        };
+        let residue = plan(&config, &setup, dir.path()).unwrap();
+        let data = residue.iter().find(|r| r.kind == ResidueKind::Data).unwrap();
+        assert!(data.entries.contains(&storage::key_path(&config.data_dir)));
+        assert!(!data.entries.contains(&dir.path().join("notes.txt")));
+
+        remove(data).unwrap();
+        assert!(!config.db_path.exists() && !dir.path().join("trash").exists());
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,226 @@
+//! How much of the time the daemon itself was running.
+//!
+//! Each daemon run is recorded when it starts and touched every minute; a
+//! clean shutdown records when it stopped. A run still open when the next
+//! one starts crashed (or was killed) around its last heartbeat. Sessions
+//! that happened while no daemon was running may be missing or partial, so
+//! the stretches between runs are reported as gaps in monitoring coverage.
+//! Coverage is only measured from the first recorded run on.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use tokio::time::interval;
+use tracing::{debug, warn};
+
+use crate::humanize;
+use crate::models::DaemonRun;
+use crate::storage::Storage;
+
+/// Seconds between heartbeats.
+const HEARTBEAT_SECS: i64 = 60;
+
+/// Shorter stretches between runs are restarts, not gaps; heartbeats leave
+/// the end of a crashed run up to a minute early.
+const MIN_GAP_SECS: i64 = 2 * HEARTBEAT_SECS;
+
+/// A stretch with no daemon running.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Gap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Whether the run before it crashed rather than stopped
+    pub after_crash: bool,
+}
+
+impl Gap {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Monitoring coverage over a window.
+#[derive(Debug, Clone, Serialize)]
+pub struct Coverage {
+    pub days: i64,
+    /// Start of the window, or of the first recorded run if later
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Share of the window the daemon was running, 0 to 1; None if no run
+    /// was ever recorded
+    pub running: Option<f64>,
+    pub runs: usize,
+    pub crashes: usize,
+    /// Oldest first
+    pub gaps: Vec<Gap>,
+    pub gap_seconds: i64,
+}
+
+impl Coverage {
+    /// One line, like `daemon was running 96% of the last 7 days; 3 gaps totaling 6.5h`.
+    pub fn summary(&self) -> String {
+        let Some(running) = self.running else {
+            return "no daemon runs recorded yet".to_string();
+        };
+        let period = if self.days == 1 { "day".to_string() } else { format!("{} days", self.days) };
+        let gaps = match self.gaps.len() {
+            0 => "no gaps".to_string(),
+            1 => format!("1 gap of {}", humanize::duration(self.gap_seconds as f64)),
+            n => format!("{} gaps totaling {}", n, humanize::duration(self.gap_seconds as f64)),
+        };
+        format!("daemon was running {:.0}% of the last {}; {}", running * 100.0, period, gaps)
+    }
+}
+
+/// Coverage of the last `days`.
+pub async fn build(storage: &Storage, days: i64) -> Result<Coverage> {
+    let days = days.max(1);
+    let to = Utc::now();
+    let from = to - Duration::days(days);
+    let runs = storage.get_daemon_runs(from).await?;
+    Ok(coverage(&runs, from, to, days))
+}
+
+/// When `run` stopped running, as far as is known at `now`.
+fn end_of(run: &DaemonRun, now: DateTime<Utc>) -> DateTime<Utc> {
+    match run.stopped_at {
+        Some(stopped) => stopped,
+        // Still beating: running until now
+        None if now - run.last_seen_at <= Duration::seconds(MIN_GAP_SECS) => now,
+        None => run.last_seen_at,
+    }
+}
+
+/// Coverage of `from`..`to` by `runs`, sorted by start.
+pub fn coverage(runs: &[DaemonRun], from: DateTime<Utc>, to: DateTime<Utc>, days: i64) -> Coverage {
+    let Some(first) = runs.first() else {
+        return Coverage { days, from, to, running: None, runs: 0, crashes: 0, gaps: Vec::new(), gap_seconds: 0 };
+    };
+    let from = from.max(first.started_at);
+    let mut gaps = Vec::new();
+    // Covered up to here, and whether the run that got there crashed
+    let mut covered = from;
+    let mut crashed = false;
+    for run in runs {
+        let start = run.started_at.max(from);
+        if start - covered >= Duration::seconds(MIN_GAP_SECS) {
+            gaps.push(Gap { start: covered, end: start, after_crash: crashed });
+        }
+        let end = end_of(run, to).min(to);
+        if end > covered {
+            covered = end;
+            crashed = run.crashed || (run.stopped_at.is_none() && end < to);
+        }
+    }
+    if to - covered >= Duration::seconds(MIN_GAP_SECS) {
+        gaps.push(Gap { start: covered, end: to, after_crash: crashed });
+    }
+
+    let gap_seconds: i64 = gaps.iter().map(|g| g.duration().num_seconds()).sum();
+    let window = (to - from).num_seconds();
+    Coverage {
+        days,
+        from,
+        to,
+        running: Some(if window > 0 { 1.0 - gap_seconds as f64 / window as f64 } else { 1.0 }),
+        runs: runs.len(),
+        crashes: runs.iter().filter(|r| r.crashed || (r.stopped_at.is_none() && end_of(r, to) < to)).count(),
+        gaps,
+        gap_seconds,
+    }
+}
+
+/// Records this daemon run while it lasts.
+#[derive(Clone)]
+pub struct Heartbeat {
+    storage: Storage,
+    run: DaemonRun,
+}
+
+impl Heartbeat {
+    /// Record this run, and the runs before it that never stopped as crashed.
+    pub async fn start(storage: Storage) -> Result<Self> {
+        for mut run in storage.get_daemon_runs(Utc::now()).await? {
+            if run.stopped_at.is_none() {
+                warn!("The daemon run started at {} (pid {}) did not shut down cleanly", run.started_at, run.pid);
+                run.stopped_at = Some(run.last_seen_at);
+                run.crashed = true;
+                storage.upsert_daemon_run(&run).await?;
+            }
+        }
+        let run = DaemonRun::start();
+        storage.upsert_daemon_run(&run).await?;
+        Ok(Self { storage, run })
+    }
+
+    /// Touch the run every minute until the task is stopped.
+    pub async fn run(self) {
+        let mut run = self.run.clone();
+        let mut ticker = interval(std::time::Duration::from_secs(HEARTBEAT_SECS as u64));
+        loop {
+            ticker.tick().await;
+            run.last_seen_at = Utc::now();
+            if let Err(e) = self.storage.upsert_daemon_run(&run).await {
+                debug!("Recording the daemon heartbeat failed: {}", e);
+            }
+        }
+    }
+
+    /// Record a clean shutdown. Stop the heartbeat task first.
+    pub async fn stop(&self) -> Result<()> {
+        let mut run = self.run.clone();
+        let now = Utc::now();
+        run.last_seen_at = now;
+        run.stopped_at = Some(now);
+        self.storage.upsert_daemon_run(&run).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(started_hours_ago: i64, ended_hours_ago: Option<i64>, crashed: bool) -> DaemonRun {
+        let now = Utc::now();
+        let mut run = DaemonRun::start();
+        run.started_at = now - Duration::hours(started_hours_ago);
+        run.last_seen_at = now - Duration::hours(ended_hours_ago.unwrap_or(0));
+        run.stopped_at = ended_hours_ago.filter(|_| !crashed).map(|h| now - Duration::hours(h));
+        if crashed {
+            run.stopped_at = Some(run.last_seen_at);
+            run.crashed = true;
+        }
+        run
+    }
+
+    #[tokio::test]
+    async fn test_coverage_and_heartbeat() {
+        let to = Utc::now();
+        let from = to - Duration::days(7);
+        assert_eq!(coverage(&[], from, to, 7).running, None);
+
+        // Before the first run nothing is known; then a crash, a clean stop
+        // and a run still going
+        let runs = vec![run(200, Some(100), true), run(98, Some(50), false), run(44, None, false)];
+        let covered = coverage(&runs, from, to, 7);
+        assert_eq!(covered.from, from);
+        assert_eq!(covered.crashes, 1);
+        assert_eq!(covered.gaps.len(), 2);
+        assert!(covered.gaps[0].after_crash);
+        assert!(!covered.gaps[1].after_crash);
+        assert_eq!(covered.gap_seconds, 8 * 3600);
+        assert!((covered.running.unwrap() - 160.0 / 168.0).abs() < 1e-3);
+        assert_eq!(covered.summary(), "daemon was running 95% of the last 7 days; 2 gaps totaling 8.0h");
+
+        let storage = Storage::in_memory();
+        let first = Heartbeat::start(storage.clone()).await.unwrap();
+        let second = Heartbeat::start(storage.clone()).await.unwrap();
+        second.stop().await.unwrap();
+        let runs = storage.get_daemon_runs(Utc::now() - Duration::hours(1)).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        let crashed = runs.iter().find(|r| r.id == first.run.id).unwrap();
+        assert!(crashed.crashed);
+        let stopped = runs.iter().find(|r| r.id == second.run.id).unwrap();
+        assert!(!stopped.crashed && stopped.stopped_at.is_some());
+    }
+}
@@ -0,0 +1,238 @@
+//! Whether a watched directory gets native file events or is polled.
+//!
+//! inotify and FSEvents only see changes made through the local kernel, so
+//! a Claude home on NFS, SMB or a FUSE mount (sshfs, a synced drive) never
+//! reports the transcripts written to it from elsewhere, and FSEvents
+//! reports a symlinked directory's changes under the path the link points
+//! to. In `auto` mode such directories are polled instead: the filesystem
+//! is looked up in the mount table after resolving symlinks, and on macOS a
+//! path reached through a symlink is polled too. `native` and `poll` force
+//! one or the other, for everything or per path.
+//!
+//! Watching all of `~/.claude/projects` recursively takes an inotify watch
+//! per directory, which thousands of projects can run out of. With
+//! `selective` (the default) the Claude home and the projects directory are
+//! watched on their own, for `history.jsonl` and new projects, and only the
+//! project directories in use recursively: those with sessions in the last
+//! `active_days` or a running agent at startup, and those prompts arrive
+//! for later. Beyond `max_project_dirs` the least recently active is
+//! dropped.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Filesystems whose changes made elsewhere native events miss. FUSE
+/// mounts show up as `fuse` or `fuse.<name>` (`fuse.sshfs`, ...).
+const UNRELIABLE_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "afpfs", "webdav", "davfs", "9p", "virtiofs", "vboxsf", "prl_fs",
+    "ceph", "glusterfs", "lustre", "gpfs", "sshfs", "osxfuse", "macfuse",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// Poll where native events are unreliable
+    #[default]
+    Auto,
+    /// Always native events
+    Native,
+    /// Always poll
+    Poll,
+}
+
+/// Settings for one directory and what's below it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchPathConfig {
+    pub path: PathBuf,
+    /// Overrides the global mode
+    #[serde(default)]
+    pub mode: Option<WatchMode>,
+    /// Overrides the global poll interval
+    #[serde(default)]
+    pub poll_secs: Option<f64>,
+}
+
+/// How the adapters' file watchers get changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    pub mode: WatchMode,
+
+    /// Seconds between polls of a polled directory
+    pub poll_secs: f64,
+
+    /// Mode and interval of particular directories; the longest matching
+    /// path wins
+    pub paths: Vec<WatchPathConfig>,
+
+    /// Watch only the project directories in use rather than all of them
+    pub selective: bool,
+
+    /// Most project directories watched at once
+    pub max_project_dirs: usize,
+
+    /// Days of sessions whose project directories are watched from the start
+    pub active_days: i64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            mode: WatchMode::Auto,
+            poll_secs: 2.0,
+            paths: Vec::new(),
+            selective: true,
+            max_project_dirs: 200,
+            active_days: 7,
+        }
+    }
+}
+
+/// How one directory is watched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchMethod {
+    Native,
+    /// Polled every this many seconds, for the reason given
+    Poll { secs: f64, reason: String },
+}
+
+impl WatchConfig {
+    /// Shortest poll interval allowed, so a typo can't spin on the disk.
+    const MIN_POLL_SECS: f64 = 0.5;
+
+    /// How `path` should be watched.
+    pub fn method(&self, path: &Path) -> WatchMethod {
+        self.method_with(path, mount_table)
+    }
+
+    fn method_with(&self, path: &Path, mounts: impl FnOnce() -> Vec<Mount>) -> WatchMethod {
+        let configured = self
+            .paths
+            .iter()
+            .filter(|p| path.starts_with(&p.path))
+            .max_by_key(|p| p.path.components().count());
+        let secs = configured.and_then(|p| p.poll_secs).unwrap_or(self.poll_secs).max(Self::MIN_POLL_SECS);
+        match configured.and_then(|p| p.mode).unwrap_or(self.mode) {
+            WatchMode::Native => WatchMethod::Native,
+            WatchMode::Poll => WatchMethod::Poll { secs, reason: "configured".to_string() },
+            WatchMode::Auto => match unreliable(path, &mounts()) {
+                Some(reason) => WatchMethod::Poll { secs, reason },
+                None => WatchMethod::Native,
+            },
+        }
+    }
+}
+
+/// A mounted filesystem.
+#[derive(Debug, Clone, PartialEq)]
+struct Mount {
+    point: PathBuf,
+    fs_type: String,
+}
+
+/// Why native events for `path` can't be trusted, if they can't.
+fn unreliable(path: &Path, mounts: &[Mount]) -> Option<String> {
+    // A directory that doesn't exist yet is judged by where it will be
+    let existing = path.ancestors().find(|a| a.exists())?;
+    let resolved = existing.canonicalize().ok()?;
+    let mount = mounts
+        .iter()
+        .filter(|m| resolved.starts_with(&m.point))
+        .max_by_key(|m| m.point.components().count())?;
+    let fs_type = mount.fs_type.as_str();
+    if fs_type == "fuse" || fs_type.starts_with("fuse.") || UNRELIABLE_FILESYSTEMS.contains(&fs_type) {
+        return Some(format!("{} filesystem at {}", fs_type, mount.point.display()));
+    }
+    if cfg!(target_os = "macos") && resolved != existing {
+        return Some(format!("symlink to {}", resolved.display()));
+    }
+    None
+}
+
+/// The mounted filesystems, or none if they can't be listed.
+fn mount_table() -> Vec<Mount> {
+    if cfg!(target_os = "linux") {
+        std::fs::read_to_string("/proc/self/mounts").map(|t| parse_proc_mounts(&t)).unwrap_or_default()
+    } else {
+        std::process::Command::new("mount")
+            .output()
+            .map(|o| parse_mount_output(&String::from_utf8_lossy(&o.stdout)))
+            .unwrap_or_default()
+    }
+}
+
+/// Lines of `/proc/self/mounts`: `device mount-point type options 0 0`,
+/// with spaces in the mount point written as `\040`.
+fn parse_proc_mounts(text: &str) -> Vec<Mount> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let point = fields.nth(1)?;
+            let fs_type = fields.next()?;
+            let point = point.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+            Some(Mount { point: PathBuf::from(point), fs_type: fs_type.to_string() })
+        })
+        .collect()
+}
+
+/// Lines of BSD `mount` output: `device on /mount/point (type, options)`.
+fn parse_mount_output(text: &str) -> Vec<Mount> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (point, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some(Mount { point: PathBuf::from(point), fs_type: fs_type.to_string() })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_filesystems_are_polled() {
+        let mounts = parse_proc_mounts(
+            "/dev/sda1 / ext4 rw,relatime 0 0\n\
+             nas:/export/home /mnt/home nfs4 rw,vers=4.2 0 0\n\
+             user@host:/srv /mnt/remote\\040box fuse.sshfs rw 0 0\n",
+        );
+        assert_eq!(mounts[2].point, Path::new("/mnt/remote box"));
+        assert_eq!(mounts[1].fs_type, "nfs4");
+        let bsd = parse_mount_output(
+            "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n\
+             //me@nas/home on /Volumes/home (smbfs, nodev, nosuid, mounted by me)\n",
+        );
+        assert_eq!(bsd[1], Mount { point: PathBuf::from("/Volumes/home"), fs_type: "smbfs".to_string() });
+
+        // Judged by the filesystem the directory (or its nearest existing
+        // parent) resolves to
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let missing = dir.path().join(".claude/projects");
+        let local = vec![Mount { point: PathBuf::from("/"), fs_type: "ext4".to_string() }];
+        let mut remote = local.clone();
+        remote.push(Mount { point: root.clone(), fs_type: "nfs".to_string() });
+        let fuse = vec![Mount { point: root.clone(), fs_type: "fuse.sshfs".to_string() }];
+
+        let config = WatchConfig::default();
+        assert_eq!(config.method_with(&missing, || local.clone()), WatchMethod::Native);
+        let polled = config.method_with(&missing, || remote.clone());
+        assert_eq!(polled, WatchMethod::Poll { secs: 2.0, reason: format!("nfs filesystem at {}", root.display()) });
+        assert!(matches!(config.method_with(&missing, || fuse), WatchMethod::Poll { .. }));
+
+        // Per-path settings override the global ones
+        let config = WatchConfig {
+            mode: WatchMode::Poll,
+            poll_secs: 0.1,
+            paths: vec![WatchPathConfig { path: dir.path().join(".claude"), mode: Some(WatchMode::Native), poll_secs: None }],
+            ..WatchConfig::default()
+        };
+        assert_eq!(config.method_with(&missing, || remote.clone()), WatchMethod::Native);
+        assert_eq!(
+            config.method_with(Path::new("/elsewhere"), Vec::new),
+            WatchMethod::Poll { secs: 0.5, reason: "configured".to_string() }
+        );
+    }
+}